@@ -83,20 +83,20 @@ DEPS_graphviz := std
 DEPS_glob := std
 DEPS_serialize := std log
 DEPS_rbml := std log serialize
-DEPS_term := std log
+DEPS_term := std log libc
 DEPS_semver := std
-DEPS_uuid := std serialize
+DEPS_uuid := std serialize time
 DEPS_sync := core alloc rustrt collections
 DEPS_getopts := std
 DEPS_collections := core alloc unicode
 DEPS_fourcc := rustc syntax std
 DEPS_hexfloat := rustc syntax std
 DEPS_num := std
-DEPS_test := std getopts serialize rbml term time regex native:rust_test_helpers
+DEPS_test := std getopts serialize rbml term time regex libc native:rust_test_helpers
 DEPS_time := std serialize
 DEPS_rand := core
 DEPS_url := std
-DEPS_log := std regex
+DEPS_log := std regex libc
 DEPS_regex := std
 DEPS_regex_macros = rustc syntax std regex
 DEPS_fmt_macros = std
@@ -283,8 +283,12 @@ pub fn test_opts(config: &Config) -> test::TestOpts {
         ratchet_noise_percent: config.ratchet_noise_percent.clone(),
         save_metrics: config.save_metrics.clone(),
         test_shard: config.test_shard.clone(),
+        test_timeout: None,
+        bench_baseline: None,
+        bench_compare: None,
         nocapture: false,
         color: test::AutoColor,
+        format: test::PrettyFormat,
     }
 }
 
@@ -342,7 +346,8 @@ pub fn make_test(config: &Config, testfile: &Path, f: || -> test::TestFn)
         desc: test::TestDesc {
             name: make_test_name(config, testfile),
             ignore: header::is_test_ignored(config, testfile),
-            should_fail: false
+            should_fail: false,
+            timeout: None
         },
         testfn: f(),
     }
@@ -345,6 +345,11 @@ def emit_regex_module(f, cats, w_data):
 
     emit_table(f, "PERLW", w_data, regex_class)
 
+    f.write("    pub static UPPER_TO_LOWER: %s = super::conversions::LuLl_table;\n\n"
+            % regex_class)
+    f.write("    pub static LOWER_TO_UPPER: %s = super::conversions::LlLu_table;\n\n"
+            % regex_class)
+
     f.write("}\n\n")
 
 def emit_conversions_module(f, lowerupper, upperlower):
@@ -383,9 +388,9 @@ def emit_conversions_module(f, lowerupper, upperlower):
 
 """)
     emit_table(f, "LuLl_table",
-        sorted(upperlower.iteritems(), key=operator.itemgetter(0)), is_pub=False)
+        sorted(upperlower.iteritems(), key=operator.itemgetter(0)))
     emit_table(f, "LlLu_table",
-        sorted(lowerupper.iteritems(), key=operator.itemgetter(0)), is_pub=False)
+        sorted(lowerupper.iteritems(), key=operator.itemgetter(0)))
     f.write("}\n\n")
 
 def emit_grapheme_module(f, grapheme_table, grapheme_cats):
@@ -1,4 +1,4 @@
-// Copyright 2012-2013 The Rust Project Developers. See the COPYRIGHT
+// Copyright 2012-2014 The Rust Project Developers. See the COPYRIGHT
 // file at the top-level directory of this distribution and at
 // http://rust-lang.org/COPYRIGHT.
 //
@@ -7,913 +7,2257 @@
 // <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
-//
-
-// NB. this is not deprecated for removal, just deprecating the
-// current implementation. If the major pain-points are addressed
-// (overuse of by-value self and .clone), this can be removed.
-#![deprecated = "the current implementation is extremely inefficient, \
-                 prefer a HashMap, TreeMap or TrieMap"]
-#![allow(deprecated)]
 
-//! Starting implementation of a B-tree for Rust.
-//! Structure inspired by Github user davidhalperin's gist.
-
-// A B-tree contains a root node (which contains a vector of elements),
-// a length (the height of the tree), and lower and upper bounds on the
-// number of elements that a given node can contain.
+//! An ordered map and set implemented as B-trees. The only requirement
+//! for the types is that the key implements `Ord`.
+//!
+//! A B-tree stores several sorted elements in every node, so compared to
+//! the binary tree behind `TreeMap` it performs far fewer allocations and
+//! searches and iterates over mostly contiguous memory.
+//!
+//! ## Example
+//!
+//! ```{rust}
+//! use std::collections::BTreeSet;
+//!
+//! let mut set = BTreeSet::new();
+//!
+//! set.insert(2i);
+//! set.insert(1i);
+//! set.insert(3i);
+//!
+//! for i in set.iter() {
+//!    println!("{}", i) // prints 1, then 2, then 3
+//! }
+//! ```
 
 use core::prelude::*;
 
-use alloc::boxed::Box;
+use core::default::Default;
 use core::fmt;
 use core::fmt::Show;
-
-use MutableSeq;
+use core::iter::Peekable;
+use core::iter;
+use core::kinds::marker;
+use core::mem;
+use std::hash::{Writer, Hash};
+
+use {Mutable, Set, MutableSet, MutableMap, Map, MutableSeq};
+use vec;
 use vec::Vec;
 
-#[allow(missing_doc)]
-pub struct BTree<K, V> {
+// Every node other than the root holds between `B - 1` and `2 * B - 1`
+// elements, and every internal node has one more edge than it has elements.
+static B: uint = 6;
+static CAPACITY: uint = 2 * B - 1;
+static MIN_LEN: uint = B - 1;
+
+/// A map based on a B-tree. Elements are stored in place in the nodes of
+/// the tree, which are kept between half full and full, so the map is
+/// both compact and cheap to search.
+///
+/// # Example
+///
+/// ```
+/// use std::collections::BTreeMap;
+///
+/// let mut map = BTreeMap::new();
+///
+/// map.insert(2i, "bar");
+/// map.insert(1i, "foo");
+/// map.insert(3i, "quux");
+///
+/// // In ascending order by keys
+/// for (key, value) in map.iter() {
+///     println!("{}: {}", key, value);
+/// }
+///
+/// // Only the keys in `[2, 4)`, in descending order
+/// for (key, value) in map.range(&2, &4).rev() {
+///     println!("{}: {}", key, value);
+/// }
+///
+/// map.remove(&1);
+/// assert_eq!(map.len(), 2);
+///
+/// if !map.contains_key(&1) {
+///     println!("1 is no more");
+/// }
+///
+/// map.clear();
+/// assert!(map.is_empty());
+/// ```
+#[deriving(Clone)]
+pub struct BTreeMap<K, V> {
     root: Node<K, V>,
-    len: uint,
-    lower_bound: uint,
-    upper_bound: uint
-}
-
-impl<K: Ord, V> BTree<K, V> {
-    /// Returns new `BTree` with root node (leaf) and user-supplied lower bound
-    /// The lower bound applies to every node except the root node.
-    pub fn new(k: K, v: V, lb: uint) -> BTree<K, V> {
-        BTree {
-            root: Node::new_leaf(vec!(LeafElt::new(k, v))),
-            len: 1,
-            lower_bound: lb,
-            upper_bound: 2 * lb
-        }
-    }
-
-    /// Helper function for `clone`: returns new BTree with supplied root node,
-    /// length, and lower bound. For use when the length is known already.
-    fn new_with_node_len(n: Node<K, V>,
-                         length: uint,
-                         lb: uint) -> BTree<K, V> {
-        BTree {
-            root: n,
-            len: length,
-            lower_bound: lb,
-            upper_bound: 2 * lb
-        }
-    }
-}
-
-// We would probably want to remove the dependence on the Clone trait in the future.
-// It is here as a crutch to ensure values can be passed around through the tree's nodes
-// especially during insertions and deletions.
-impl<K: Clone + Ord, V: Clone> BTree<K, V> {
-    /// Returns the value of a given key, which may not exist in the tree.
-    /// Calls the root node's get method.
-    pub fn get(self, k: K) -> Option<V> {
-        return self.root.get(k);
-    }
-
-    /// An insert method that uses the `clone` method for support.
-    pub fn insert(mut self, k: K, v: V) -> BTree<K, V> {
-        let (a, b) = self.root.clone().insert(k, v, self.upper_bound.clone());
-        if b {
-            match a.clone() {
-                LeafNode(leaf) => {
-                    self.root = Node::new_leaf(leaf.clone().elts);
-                }
-                BranchNode(branch) => {
-                    self.root = Node::new_branch(branch.clone().elts,
-                                                 branch.clone().rightmost_child);
-                }
-            }
-        }
-        self
+    length: uint
+}
+
+impl<K: PartialEq + Ord, V: PartialEq> PartialEq for BTreeMap<K, V> {
+    fn eq(&self, other: &BTreeMap<K, V>) -> bool {
+        self.len() == other.len() &&
+            self.iter().zip(other.iter()).all(|(a, b)| a == b)
     }
 }
 
-impl<K: Clone + Ord, V: Clone> Clone for BTree<K, V> {
-    fn clone(&self) -> BTree<K, V> {
-        BTree::new_with_node_len(self.root.clone(), self.len, self.lower_bound)
+impl<K: Eq + Ord, V: Eq> Eq for BTreeMap<K, V> {}
+
+impl<K: Ord, V: PartialOrd> PartialOrd for BTreeMap<K, V> {
+    #[inline]
+    fn partial_cmp(&self, other: &BTreeMap<K, V>) -> Option<Ordering> {
+        iter::order::partial_cmp(self.iter(), other.iter())
     }
 }
 
-impl<K: Ord, V: Eq> PartialEq for BTree<K, V> {
-    fn eq(&self, other: &BTree<K, V>) -> bool {
-        self.root.cmp(&other.root) == Equal
+impl<K: Ord, V: Ord> Ord for BTreeMap<K, V> {
+    #[inline]
+    fn cmp(&self, other: &BTreeMap<K, V>) -> Ordering {
+        iter::order::cmp(self.iter(), other.iter())
     }
 }
 
-impl<K: Ord, V: Eq> Eq for BTree<K, V> {}
+impl<K: Ord + Show, V: Show> Show for BTreeMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        try!(write!(f, "{{"));
+
+        for (i, (k, v)) in self.iter().enumerate() {
+            if i != 0 { try!(write!(f, ", ")); }
+            try!(write!(f, "{}: {}", *k, *v));
+        }
 
-impl<K: Ord, V: Eq> PartialOrd for BTree<K, V> {
-    fn partial_cmp(&self, other: &BTree<K, V>) -> Option<Ordering> {
-        Some(self.cmp(other))
+        write!(f, "}}")
     }
 }
 
-impl<K: Ord, V: Eq> Ord for BTree<K, V> {
-    /// Returns an ordering based on the root nodes of each `BTree`.
-    fn cmp(&self, other: &BTree<K, V>) -> Ordering {
-        self.root.cmp(&other.root)
+impl<K: Ord, V> Collection for BTreeMap<K, V> {
+    fn len(&self) -> uint { self.length }
+}
+
+impl<K: Ord, V> Mutable for BTreeMap<K, V> {
+    fn clear(&mut self) {
+        self.root = Node::empty();
+        self.length = 0
     }
 }
 
-impl<K: fmt::Show + Ord, V: fmt::Show> fmt::Show for BTree<K, V> {
-    /// Returns a string representation of the `BTree`.
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        self.root.fmt(f)
+impl<K: Ord, V> Map<K, V> for BTreeMap<K, V> {
+    #[inline]
+    fn find<'a>(&'a self, key: &K) -> Option<&'a V> {
+        self.find_with(|k| key.cmp(k))
     }
 }
 
+impl<K: Ord, V> MutableMap<K, V> for BTreeMap<K, V> {
+    #[inline]
+    fn find_mut<'a>(&'a mut self, key: &K) -> Option<&'a mut V> {
+        self.find_mut_with(|k| key.cmp(k))
+    }
 
-// Node types
-//
-// A node is either a LeafNode or a BranchNode, which contain either a Leaf or a Branch.
-// Branches contain BranchElts, which contain a left child (another node) and a key-value
-// pair. Branches also contain the rightmost child of the elements in the array.
-// Leaves contain LeafElts, which do not have children.
-enum Node<K, V> {
-    LeafNode(Leaf<K, V>),
-    BranchNode(Branch<K, V>)
+    fn swap(&mut self, key: K, value: V) -> Option<V> {
+        match self.root.insert(key, value) {
+            Fit(old) => {
+                if old.is_none() { self.length += 1 }
+                old
+            }
+            Split(key, value, right) => {
+                // The root overflowed, so the tree grows by one level.
+                let left = mem::replace(&mut self.root, Node::new());
+                self.root.keys.push(key);
+                self.root.vals.push(value);
+                self.root.edges.push(left);
+                self.root.edges.push(right);
+                self.length += 1;
+                None
+            }
+        }
+    }
+
+    fn pop(&mut self, key: &K) -> Option<V> {
+        let ret = self.root.remove(key);
+        if ret.is_some() {
+            self.length -= 1;
+            // The root may have been emptied by a merge of its last two
+            // children, in which case the tree shrinks by one level.
+            if self.root.keys.is_empty() && !self.root.is_leaf() {
+                let child = self.root.edges.pop().unwrap();
+                self.root = child;
+            }
+        }
+        ret
+    }
 }
 
+impl<K: Ord, V> Default for BTreeMap<K,V> {
+    #[inline]
+    fn default() -> BTreeMap<K, V> { BTreeMap::new() }
+}
 
-impl<K: Ord, V> Node<K, V> {
-    /// Creates a new leaf node given a vector of elements.
-    fn new_leaf(vec: Vec<LeafElt<K, V>>) -> Node<K,V> {
-        LeafNode(Leaf::new(vec))
+impl<K: Ord, V> Index<K, V> for BTreeMap<K, V> {
+    #[inline]
+    fn index<'a>(&'a self, i: &K) -> &'a V {
+        self.find(i).expect("no entry found for key")
     }
+}
 
-    /// Creates a new branch node given a vector of an elements and a pointer to a rightmost child.
-    fn new_branch(vec: Vec<BranchElt<K, V>>, right: Box<Node<K, V>>)
-                  -> Node<K, V> {
-        BranchNode(Branch::new(vec, right))
+impl<K: Ord, V> BTreeMap<K, V> {
+    /// Creates an empty `BTreeMap`. This does not allocate.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::BTreeMap;
+    /// let mut map: BTreeMap<&str, int> = BTreeMap::new();
+    /// ```
+    pub fn new() -> BTreeMap<K, V> { BTreeMap{root: Node::empty(), length: 0} }
+
+    /// Gets a lazy iterator over the keys in the map, in ascending order.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::BTreeMap;
+    /// let mut map = BTreeMap::new();
+    /// map.insert("a", 1i);
+    /// map.insert("c", 3i);
+    /// map.insert("b", 2i);
+    ///
+    /// // Print "a", "b", "c" in order.
+    /// for x in map.keys() {
+    ///     println!("{}", x);
+    /// }
+    /// ```
+    pub fn keys<'a>(&'a self) -> Keys<'a, K, V> {
+        self.iter().map(|(k, _v)| k)
     }
 
-    /// Determines whether the given Node contains a Branch or a Leaf.
-    /// Used in testing.
-    fn is_leaf(&self) -> bool {
-        match self {
-            &LeafNode(..) => true,
-            &BranchNode(..) => false
+    /// Gets a lazy iterator over the values in the map, in ascending order
+    /// with respect to the corresponding keys.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::BTreeMap;
+    /// let mut map = BTreeMap::new();
+    /// map.insert("a", 1i);
+    /// map.insert("c", 3i);
+    /// map.insert("b", 2i);
+    ///
+    /// // Print 1, 2, 3 ordered by keys.
+    /// for x in map.values() {
+    ///     println!("{}", x);
+    /// }
+    /// ```
+    pub fn values<'a>(&'a self) -> Values<'a, K, V> {
+        self.iter().map(|(_k, v)| v)
+    }
+
+    /// Gets a lazy iterator over the key-value pairs in the map, in
+    /// ascending order. The iterator is double-ended, so it can also be
+    /// consumed from the back, or from both ends at once.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::BTreeMap;
+    /// let mut map = BTreeMap::new();
+    /// map.insert("a", 1i);
+    /// map.insert("c", 3i);
+    /// map.insert("b", 2i);
+    ///
+    /// // Print contents in ascending order
+    /// for (key, value) in map.iter() {
+    ///     println!("{}: {}", key, value);
+    /// }
+    ///
+    /// let mut iter = map.iter();
+    /// assert_eq!(iter.next(), Some((&"a", &1)));
+    /// assert_eq!(iter.next_back(), Some((&"c", &3)));
+    /// assert_eq!(iter.next(), Some((&"b", &2)));
+    /// assert_eq!(iter.next_back(), None);
+    /// ```
+    pub fn iter<'a>(&'a self) -> Entries<'a, K, V> {
+        Entries {
+            raw: self.raw_entries(|_| 0, |node| node.keys.len(), true),
+            marker: marker::ContravariantLifetime
         }
     }
 
-    /// A binary search function for Nodes.
-    /// Calls either the Branch's or the Leaf's bsearch function.
-    fn bsearch_node(&self, k: K) -> Option<uint> {
-         match self {
-             &LeafNode(ref leaf) => leaf.bsearch_leaf(k),
-             &BranchNode(ref branch) => branch.bsearch_branch(k)
-         }
-     }
-}
+    /// Gets a lazy reverse iterator over the key-value pairs in the map, in
+    /// descending order.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::BTreeMap;
+    /// let mut map = BTreeMap::new();
+    /// map.insert("a", 1i);
+    /// map.insert("c", 3i);
+    /// map.insert("b", 2i);
+    ///
+    /// // Print contents in descending order
+    /// for (key, value) in map.rev_iter() {
+    ///     println!("{}: {}", key, value);
+    /// }
+    /// ```
+    pub fn rev_iter<'a>(&'a self) -> RevEntries<'a, K, V> {
+        self.iter().rev()
+    }
 
-impl<K: Clone + Ord, V: Clone> Node<K, V> {
-    /// Returns the corresponding value to the provided key.
-    /// `get()` is called in different ways on a branch or a leaf.
-    fn get(&self, k: K) -> Option<V> {
-        match *self {
-            LeafNode(ref leaf) => return leaf.get(k),
-            BranchNode(ref branch) => return branch.get(k)
+    /// Gets a lazy forward iterator over the key-value pairs in the
+    /// map, with the values being mutable.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::BTreeMap;
+    /// let mut map = BTreeMap::new();
+    /// map.insert("a", 1i);
+    /// map.insert("c", 3i);
+    /// map.insert("b", 2i);
+    ///
+    /// // Add 10 until we find "b"
+    /// for (key, value) in map.mut_iter() {
+    ///     *value += 10;
+    ///     if key == &"b" { break }
+    /// }
+    ///
+    /// assert_eq!(map.find(&"a"), Some(&11));
+    /// assert_eq!(map.find(&"b"), Some(&12));
+    /// assert_eq!(map.find(&"c"), Some(&3));
+    /// ```
+    pub fn mut_iter<'a>(&'a mut self) -> MutEntries<'a, K, V> {
+        MutEntries {
+            raw: self.raw_entries(|_| 0, |node| node.keys.len(), true),
+            marker: marker::ContravariantLifetime
         }
     }
 
-    /// Matches on the `Node`, then performs and returns the appropriate insert method.
-    fn insert(self, k: K, v: V, ub: uint) -> (Node<K, V>, bool) {
-        match self {
-            LeafNode(leaf) => leaf.insert(k, v, ub),
-            BranchNode(branch) => branch.insert(k, v, ub)
+    /// Gets a lazy reverse iterator over the key-value pairs in the
+    /// map, with the values being mutable.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::BTreeMap;
+    /// let mut map = BTreeMap::new();
+    /// map.insert("a", 1i);
+    /// map.insert("c", 3i);
+    /// map.insert("b", 2i);
+    ///
+    /// // Add 10 until we find "b"
+    /// for (key, value) in map.mut_rev_iter() {
+    ///     *value += 10;
+    ///     if key == &"b" { break }
+    /// }
+    ///
+    /// assert_eq!(map.find(&"a"), Some(&1));
+    /// assert_eq!(map.find(&"b"), Some(&12));
+    /// assert_eq!(map.find(&"c"), Some(&13));
+    /// ```
+    pub fn mut_rev_iter<'a>(&'a mut self) -> RevMutEntries<'a, K, V> {
+        self.mut_iter().rev()
+    }
+
+    /// Gets a lazy iterator that consumes the map.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::BTreeMap;
+    /// let mut map = BTreeMap::new();
+    /// map.insert("a", 1i);
+    /// map.insert("c", 3i);
+    /// map.insert("b", 2i);
+    ///
+    /// // Not possible with a regular `.iter()`
+    /// let vec: Vec<(&str, int)> = map.move_iter().collect();
+    /// assert_eq!(vec, vec![("a", 1), ("b", 2), ("c", 3)]);
+    /// ```
+    pub fn move_iter(self) -> MoveEntries<K, V> {
+        let BTreeMap { root: root, length: length } = self;
+        let mut elts = Vec::with_capacity(length);
+        root.move_into(&mut elts);
+        MoveEntries { iter: elts.move_iter() }
+    }
+
+    /// Returns a lazy iterator to the first key-value pair whose key is not
+    /// less than `k`. If all keys in the map are less than `k` an empty
+    /// iterator is returned.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::BTreeMap;
+    ///
+    /// let mut map = BTreeMap::new();
+    /// map.insert(2i, "a");
+    /// map.insert(4, "b");
+    /// map.insert(6, "c");
+    /// map.insert(8, "d");
+    ///
+    /// assert_eq!(map.lower_bound(&4).next(), Some((&4, &"b")));
+    /// assert_eq!(map.lower_bound(&5).next(), Some((&6, &"c")));
+    /// assert_eq!(map.lower_bound(&10).next(), None);
+    /// ```
+    pub fn lower_bound<'a>(&'a self, k: &K) -> Entries<'a, K, V> {
+        Entries {
+            raw: self.raw_entries(|node| node.bound(k, false), |node| node.keys.len(), false),
+            marker: marker::ContravariantLifetime
         }
     }
-}
 
-impl<K: Clone + Ord, V: Clone> Clone for Node<K, V> {
-    /// Returns a new `Node` based on whether or not it is a branch or a leaf.
-    fn clone(&self) -> Node<K, V> {
-        match *self {
-            LeafNode(ref leaf) => {
-                Node::new_leaf(leaf.elts.clone())
-            }
-            BranchNode(ref branch) => {
-                Node::new_branch(branch.elts.clone(),
-                                 branch.rightmost_child.clone())
-            }
+    /// Returns a lazy iterator to the first key-value pair whose key is
+    /// greater than `k`. If all keys in the map are less than or equal to
+    /// `k` an empty iterator is returned.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::BTreeMap;
+    ///
+    /// let mut map = BTreeMap::new();
+    /// map.insert(2i, "a");
+    /// map.insert(4, "b");
+    /// map.insert(6, "c");
+    /// map.insert(8, "d");
+    ///
+    /// assert_eq!(map.upper_bound(&4).next(), Some((&6, &"c")));
+    /// assert_eq!(map.upper_bound(&5).next(), Some((&6, &"c")));
+    /// assert_eq!(map.upper_bound(&10).next(), None);
+    /// ```
+    pub fn upper_bound<'a>(&'a self, k: &K) -> Entries<'a, K, V> {
+        Entries {
+            raw: self.raw_entries(|node| node.bound(k, true), |node| node.keys.len(), false),
+            marker: marker::ContravariantLifetime
         }
     }
-}
 
-impl<K: Ord, V: Eq> PartialEq for Node<K, V> {
-    fn eq(&self, other: &Node<K, V>) -> bool {
-        match *self{
-            BranchNode(ref branch) => {
-                if other.is_leaf() {
-                    return false;
-                }
-                match *other {
-                    BranchNode(ref branch2) => branch.cmp(branch2) == Equal,
-                    LeafNode(..) => false
-                }
-            }
-            LeafNode(ref leaf) => {
-                match *other {
-                    LeafNode(ref leaf2) => leaf.cmp(leaf2) == Equal,
-                    BranchNode(..) => false
-                }
-            }
+    /// Returns a lazy iterator over the key-value pairs whose keys lie in
+    /// the half-open interval `[min, max)`, in ascending order. If `max` is
+    /// not greater than `min` an empty iterator is returned.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::BTreeMap;
+    ///
+    /// let map: BTreeMap<int, int> = range(0i, 10).map(|i| (i, i * i)).collect();
+    ///
+    /// let squares: Vec<int> = map.range(&3, &6).map(|(_, &v)| v).collect();
+    /// assert_eq!(squares, vec![9, 16, 25]);
+    ///
+    /// let keys: Vec<int> = map.range(&7, &100).rev().map(|(&k, _)| k).collect();
+    /// assert_eq!(keys, vec![9, 8, 7]);
+    /// ```
+    pub fn range<'a>(&'a self, min: &K, max: &K) -> Entries<'a, K, V> {
+        let max = if *max < *min { min } else { max };
+        Entries {
+            raw: self.raw_entries(|node| node.bound(min, false),
+                                  |node| node.bound(max, false), false),
+            marker: marker::ContravariantLifetime
         }
     }
-}
 
-impl<K: Ord, V: Eq> Eq for Node<K, V> {}
+    /// Returns a lazy value iterator to the first key-value pair (with
+    /// the value being mutable) whose key is not less than `k`.
+    ///
+    /// If all keys in the map are less than `k` an empty iterator is
+    /// returned.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::BTreeMap;
+    ///
+    /// let mut map = BTreeMap::new();
+    /// map.insert(2i, "a");
+    /// map.insert(4, "b");
+    /// map.insert(6, "c");
+    /// map.insert(8, "d");
+    ///
+    /// for (key, value) in map.mut_lower_bound(&4) {
+    ///     *value = "changed";
+    /// }
+    ///
+    /// assert_eq!(map.find(&2), Some(&"a"));
+    /// assert_eq!(map.find(&4), Some(&"changed"));
+    /// assert_eq!(map.find(&6), Some(&"changed"));
+    /// assert_eq!(map.find(&8), Some(&"changed"));
+    /// ```
+    pub fn mut_lower_bound<'a>(&'a mut self, k: &K) -> MutEntries<'a, K, V> {
+        MutEntries {
+            raw: self.raw_entries(|node| node.bound(k, false), |node| node.keys.len(), false),
+            marker: marker::ContravariantLifetime
+        }
+    }
 
-impl<K: Ord, V: Eq> PartialOrd for Node<K, V> {
-    fn partial_cmp(&self, other: &Node<K, V>) -> Option<Ordering> {
-        Some(self.cmp(other))
+    /// Returns a lazy iterator to the first key-value pair (with the
+    /// value being mutable) whose key is greater than `k`.
+    ///
+    /// If all keys in the map are less than or equal to `k` an empty
+    /// iterator is returned.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::BTreeMap;
+    ///
+    /// let mut map = BTreeMap::new();
+    /// map.insert(2i, "a");
+    /// map.insert(4, "b");
+    /// map.insert(6, "c");
+    /// map.insert(8, "d");
+    ///
+    /// for (key, value) in map.mut_upper_bound(&4) {
+    ///     *value = "changed";
+    /// }
+    ///
+    /// assert_eq!(map.find(&2), Some(&"a"));
+    /// assert_eq!(map.find(&4), Some(&"b"));
+    /// assert_eq!(map.find(&6), Some(&"changed"));
+    /// assert_eq!(map.find(&8), Some(&"changed"));
+    /// ```
+    pub fn mut_upper_bound<'a>(&'a mut self, k: &K) -> MutEntries<'a, K, V> {
+        MutEntries {
+            raw: self.raw_entries(|node| node.bound(k, true), |node| node.keys.len(), false),
+            marker: marker::ContravariantLifetime
+        }
+    }
+
+    /// Returns a lazy iterator over the key-value pairs (with the values
+    /// being mutable) whose keys lie in the half-open interval `[min, max)`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::BTreeMap;
+    ///
+    /// let mut map: BTreeMap<int, int> = range(0i, 10).map(|i| (i, i)).collect();
+    ///
+    /// for (_, value) in map.mut_range(&3, &6) {
+    ///     *value = 0;
+    /// }
+    ///
+    /// let values: Vec<int> = map.values().map(|&v| v).collect();
+    /// assert_eq!(values, vec![0, 1, 2, 0, 0, 0, 6, 7, 8, 9]);
+    /// ```
+    pub fn mut_range<'a>(&'a mut self, min: &K, max: &K) -> MutEntries<'a, K, V> {
+        let max = if *max < *min { min } else { max };
+        MutEntries {
+            raw: self.raw_entries(|node| node.bound(min, false),
+                                  |node| node.bound(max, false), false),
+            marker: marker::ContravariantLifetime
+        }
     }
 }
 
-impl<K: Ord, V: Eq> Ord for Node<K, V> {
-    /// Implementation of `Ord` for `Node`s.
-    fn cmp(&self, other: &Node<K, V>) -> Ordering {
-        match *self {
-            LeafNode(ref leaf) => {
-                match *other {
-                    LeafNode(ref leaf2) => leaf.cmp(leaf2),
-                    BranchNode(_) => Less
+impl<K, V> BTreeMap<K, V> {
+    /// Returns the value for which `f(key)` returns `Equal`. `f` is invoked
+    /// with current key and guides tree navigation. That means `f` should
+    /// be aware of natural ordering of the tree.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::BTreeMap;
+    ///
+    /// let mut map = BTreeMap::new();
+    /// map.insert("Content-Type".to_string(), "application/xml".to_string());
+    /// map.insert("User-Agent".to_string(), "Curl-Rust/0.1".to_string());
+    ///
+    /// let ua = map.find_with(|k| "User-Agent".cmp(&k.as_slice()));
+    /// assert_eq!(ua.unwrap().as_slice(), "Curl-Rust/0.1");
+    /// ```
+    pub fn find_with<'a>(&'a self, f: |&K| -> Ordering) -> Option<&'a V> {
+        let mut node = &self.root;
+        loop {
+            let mut i = 0;
+            while i < node.keys.len() {
+                match f(&node.keys[i]) {
+                    Greater => i += 1,
+                    Equal => return Some(&node.vals[i]),
+                    Less => break
                 }
             }
-            BranchNode(ref branch) => {
-                match *other {
-                    BranchNode(ref branch2) => branch.cmp(branch2),
-                    LeafNode(_) => Greater
+            if node.is_leaf() { return None }
+            node = &node.edges[i];
+        }
+    }
+
+    /// Returns the value for which `f(key)` returns `Equal`. `f` is invoked
+    /// with current key and guides tree navigation. That means `f` should
+    /// be aware of natural ordering of the tree.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::BTreeMap;
+    ///
+    /// let mut map = BTreeMap::new();
+    /// map.insert("Content-Type", "application/xml");
+    /// map.insert("User-Agent", "Curl-Rust/0.1");
+    ///
+    /// let new_ua = "Safari/156.0";
+    /// match map.find_mut_with(|k| "User-Agent".cmp(k)) {
+    ///    Some(x) => *x = new_ua,
+    ///    None => fail!(),
+    /// }
+    ///
+    /// assert_eq!(map.find(&"User-Agent"), Some(&new_ua));
+    /// ```
+    pub fn find_mut_with<'a>(&'a mut self, f: |&K| -> Ordering) -> Option<&'a mut V> {
+        let mut node = &mut self.root;
+        loop {
+            let temp = node; // hack to appease borrowck
+            let mut i = 0;
+            while i < temp.keys.len() {
+                match f(&temp.keys[i]) {
+                    Greater => i += 1,
+                    Equal => return Some(temp.vals.get_mut(i)),
+                    Less => break
                 }
             }
+            if temp.is_leaf() { return None }
+            node = temp.edges.get_mut(i);
         }
     }
-}
 
-impl<K: fmt::Show + Ord, V: fmt::Show> fmt::Show for Node<K, V> {
-    /// Returns a string representation of a `Node`.
-    /// Will iterate over the Node and show `Key: x, value: y, child: ()`
-    /// for all elements in the `Node`. `child` only exists if the `Node` contains
-    /// a branch.
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match *self {
-            LeafNode(ref leaf) => leaf.fmt(f),
-            BranchNode(ref branch) => branch.fmt(f),
-        }
+    /// Builds the raw iterator shared by all borrowing iterators. `front`
+    /// and `back` pick the edge to descend through at every level, and
+    /// `exact` says whether the iterator is known to cover the whole map.
+    fn raw_entries(&self, front: |&Node<K, V>| -> uint, back: |&Node<K, V>| -> uint,
+                   exact: bool) -> RawEntries<K, V> {
+        let mut raw = RawEntries {
+            front: Vec::new(),
+            back: Vec::new(),
+            remaining_min: if exact { self.length } else { 0 },
+            remaining_max: self.length
+        };
+        descend(&mut raw.front, &self.root, front);
+        descend(&mut raw.back, &self.root, back);
+        raw
     }
 }
 
+// Nodes store their keys, values and (for internal nodes) children in
+// separate vectors, so that a search only touches the keys.
+#[deriving(Clone)]
+struct Node<K, V> {
+    keys: Vec<K>,
+    vals: Vec<V>,
+    // Empty for leaves, one longer than `keys` otherwise.
+    edges: Vec<Node<K, V>>
+}
 
-// A leaf is a vector with elements that contain no children. A leaf also
-// does not contain a rightmost child.
-struct Leaf<K, V> {
-    elts: Vec<LeafElt<K, V>>
+enum SearchResult {
+    Found(uint),
+    GoDown(uint)
 }
 
-// Vector of values with children, plus a rightmost child (greater than all)
-struct Branch<K, V> {
-    elts: Vec<BranchElt<K,V>>,
-    rightmost_child: Box<Node<K, V>>,
+enum InsertResult<K, V> {
+    Fit(Option<V>),
+    Split(K, V, Node<K, V>)
 }
 
+impl<K, V> Node<K, V> {
+    /// Creates an empty node without allocating, for the root of an empty
+    /// tree.
+    fn empty() -> Node<K, V> {
+        Node { keys: Vec::new(), vals: Vec::new(), edges: Vec::new() }
+    }
 
-impl<K: Ord, V> Leaf<K, V> {
-    /// Creates a new `Leaf` from a vector of `LeafElts`.
-    fn new(vec: Vec<LeafElt<K, V>>) -> Leaf<K, V> {
-        Leaf {
-            elts: vec
+    /// Creates an empty node with room for an overfull set of elements,
+    /// so that it never needs to reallocate.
+    fn new() -> Node<K, V> {
+        Node {
+            keys: Vec::with_capacity(CAPACITY + 1),
+            vals: Vec::with_capacity(CAPACITY + 1),
+            edges: Vec::new()
         }
     }
 
-    /// Searches a leaf for a spot for a new element using a binary search.
-    /// Returns `None` if the element is already in the vector.
-    fn bsearch_leaf(&self, k: K) -> Option<uint> {
-        let mut high: uint = self.elts.len();
-        let mut low: uint = 0;
-        let mut midpoint: uint = (high - low) / 2 ;
-        if midpoint == high {
-            midpoint = 0;
+    #[inline]
+    fn is_leaf(&self) -> bool { self.edges.is_empty() }
+
+    /// Splits an overfull node in half, returning the median element and
+    /// the new right sibling.
+    fn split(&mut self) -> (K, V, Node<K, V>) {
+        let mut right = Node::new();
+        split_off(&mut self.keys, &mut right.keys, B + 1);
+        split_off(&mut self.vals, &mut right.vals, B + 1);
+        if !self.is_leaf() {
+            right.edges = Vec::with_capacity(CAPACITY + 2);
+            split_off(&mut self.edges, &mut right.edges, B + 1);
         }
-        loop {
-            let order = self.elts[midpoint].key.cmp(&k);
-            match order {
-                Equal => {
-                    return None;
-                }
-                Greater => {
-                    if midpoint > 0 {
-                        if self.elts[midpoint - 1].key.cmp(&k) == Less {
-                            return Some(midpoint);
-                        }
-                        else {
-                            let tmp = midpoint;
-                            midpoint = midpoint / 2;
-                            high = tmp;
-                            continue;
-                        }
-                    }
-                    else {
-                        return Some(0);
-                    }
-                }
-                Less => {
-                    if midpoint + 1 < self.elts.len() {
-                        if self.elts[midpoint + 1].key.cmp(&k) == Greater {
-                            return Some(midpoint);
-                        }
-                        else {
-                            let tmp = midpoint;
-                            midpoint = (high + low) / 2;
-                            low = tmp;
-                        }
-                    }
-                    else {
-                        return Some(self.elts.len());
-                    }
-                }
+        (self.keys.pop().unwrap(), self.vals.pop().unwrap(), right)
+    }
+
+    /// Removes and returns the greatest element of this subtree.
+    fn pop_last(&mut self) -> (K, V) {
+        if self.is_leaf() {
+            (self.keys.pop().unwrap(), self.vals.pop().unwrap())
+        } else {
+            let i = self.keys.len();
+            let ret = self.edges.get_mut(i).pop_last();
+            self.fix_child(i);
+            ret
+        }
+    }
+
+    /// Restores the minimum occupancy of the `i`th child after a removal,
+    /// either by rotating an element through this node from a sibling or
+    /// by merging the child with a sibling.
+    fn fix_child(&mut self, i: uint) {
+        if self.edges[i].keys.len() >= MIN_LEN { return }
+
+        if i > 0 && self.edges[i - 1].keys.len() > MIN_LEN {
+            self.rotate_right(i - 1);
+        } else if i + 1 < self.edges.len() && self.edges[i + 1].keys.len() > MIN_LEN {
+            self.rotate_left(i);
+        } else if i > 0 {
+            self.merge(i - 1);
+        } else {
+            self.merge(i);
+        }
+    }
+
+    /// Moves the last element of the `i`th child up into this node, and
+    /// the `i`th element down into the front of the following child.
+    fn rotate_right(&mut self, i: uint) {
+        let (key, val, edge) = {
+            let left = self.edges.get_mut(i);
+            (left.keys.pop().unwrap(), left.vals.pop().unwrap(), left.edges.pop())
+        };
+        let key = mem::replace(self.keys.get_mut(i), key);
+        let val = mem::replace(self.vals.get_mut(i), val);
+
+        let right = self.edges.get_mut(i + 1);
+        right.keys.insert(0, key);
+        right.vals.insert(0, val);
+        match edge {
+            Some(edge) => right.edges.insert(0, edge),
+            None => {}
+        }
+    }
+
+    /// Moves the first element of the `i + 1`th child up into this node,
+    /// and the `i`th element down onto the end of the preceding child.
+    fn rotate_left(&mut self, i: uint) {
+        let (key, val, edge) = {
+            let right = self.edges.get_mut(i + 1);
+            let edge = if right.is_leaf() { None } else { right.edges.remove(0) };
+            (right.keys.remove(0).unwrap(), right.vals.remove(0).unwrap(), edge)
+        };
+        let key = mem::replace(self.keys.get_mut(i), key);
+        let val = mem::replace(self.vals.get_mut(i), val);
+
+        let left = self.edges.get_mut(i);
+        left.keys.push(key);
+        left.vals.push(val);
+        match edge {
+            Some(edge) => left.edges.push(edge),
+            None => {}
+        }
+    }
+
+    /// Merges the `i + 1`th child and the `i`th element into the `i`th
+    /// child.
+    fn merge(&mut self, i: uint) {
+        let key = self.keys.remove(i).unwrap();
+        let val = self.vals.remove(i).unwrap();
+        let right = self.edges.remove(i + 1).unwrap();
+
+        let left = self.edges.get_mut(i);
+        left.keys.push(key);
+        left.vals.push(val);
+        left.keys.push_all_move(right.keys);
+        left.vals.push_all_move(right.vals);
+        left.edges.push_all_move(right.edges);
+    }
+
+    /// Moves all elements of this subtree onto `out`, in order.
+    fn move_into(self, out: &mut Vec<(K, V)>) {
+        let Node { keys: keys, vals: vals, edges: edges } = self;
+        let mut edges = edges.move_iter();
+        for elt in keys.move_iter().zip(vals.move_iter()) {
+            match edges.next() {
+                Some(edge) => edge.move_into(out),
+                None => {}
             }
+            out.push(elt);
+        }
+        match edges.next() {
+            Some(edge) => edge.move_into(out),
+            None => {}
         }
     }
 }
 
-
-impl<K: Clone + Ord, V: Clone> Leaf<K, V> {
-    /// Returns the corresponding value to the supplied key.
-    fn get(&self, k: K) -> Option<V> {
-        for s in self.elts.iter() {
-            let order = s.key.cmp(&k);
-            match order {
-                Equal => return Some(s.value.clone()),
-                _ => {}
+impl<K: Ord, V> Node<K, V> {
+    fn search(&self, key: &K) -> SearchResult {
+        // Nodes are small enough that a linear scan beats a binary search.
+        for (i, k) in self.keys.iter().enumerate() {
+            match key.cmp(k) {
+                Greater => {}
+                Equal => return Found(i),
+                Less => return GoDown(i)
             }
         }
-        return None;
+        GoDown(self.keys.len())
     }
 
-    /// Uses `clone()` to facilitate inserting new elements into a tree.
-    fn insert(mut self, k: K, v: V, ub: uint) -> (Node<K, V>, bool) {
-        let to_insert = LeafElt::new(k, v);
-        let index: Option<uint> = self.bsearch_leaf(to_insert.clone().key);
-        //Check index to see whether we actually inserted the element into the vector.
-        match index {
-            //If the index is None, the new element already exists in the vector.
-            None => {
-                return (Node::new_leaf(self.clone().elts), false);
+    /// Returns the index of the edge separating the keys less than `key`
+    /// (or not greater than it, if `inclusive`) from the rest.
+    fn bound(&self, key: &K, inclusive: bool) -> uint {
+        let mut i = 0;
+        while i < self.keys.len() {
+            match self.keys[i].cmp(key) {
+                Less => {}
+                Equal if inclusive => {}
+                _ => break
             }
-            //If there is an index, insert at that index.
-            Some(i) => {
-                if i >= self.elts.len() {
-                    self.elts.push(to_insert.clone());
-                }
-                else {
-                    self.elts.insert(i, to_insert.clone());
+            i += 1;
+        }
+        i
+    }
+
+    fn insert(&mut self, key: K, value: V) -> InsertResult<K, V> {
+        let i = match self.search(&key) {
+            Found(i) => return Fit(Some(mem::replace(self.vals.get_mut(i), value))),
+            GoDown(i) => i
+        };
+
+        if self.is_leaf() {
+            self.keys.insert(i, key);
+            self.vals.insert(i, value);
+        } else {
+            match self.edges.get_mut(i).insert(key, value) {
+                Split(key, value, right) => {
+                    self.keys.insert(i, key);
+                    self.vals.insert(i, value);
+                    self.edges.insert(i + 1, right);
                 }
+                fit => return fit
             }
         }
-        //If we have overfilled the vector (by making its size greater than the
-        //upper bound), we return a new Branch with one element and two children.
-        if self.elts.len() > ub {
-            let midpoint_opt = self.elts.remove(ub / 2);
-            let midpoint = midpoint_opt.unwrap();
-            let (left_leaf, right_leaf) = self.elts.partition(|le|
-                                                              le.key.cmp(&midpoint.key.clone())
-                                                              == Less);
-            let branch_return = Node::new_branch(vec!(BranchElt::new(midpoint.key.clone(),
-                                                                  midpoint.value.clone(),
-                                                             box Node::new_leaf(left_leaf))),
-                                            box Node::new_leaf(right_leaf));
-            return (branch_return, true);
+
+        if self.keys.len() > CAPACITY {
+            let (key, value, right) = self.split();
+            Split(key, value, right)
+        } else {
+            Fit(None)
+        }
+    }
+
+    fn remove(&mut self, key: &K) -> Option<V> {
+        match self.search(key) {
+            Found(i) => {
+                if self.is_leaf() {
+                    self.keys.remove(i);
+                    self.vals.remove(i)
+                } else {
+                    // Replace the element with its in-order predecessor,
+                    // which always lives in a leaf.
+                    let (key, val) = self.edges.get_mut(i).pop_last();
+                    *self.keys.get_mut(i) = key;
+                    let ret = mem::replace(self.vals.get_mut(i), val);
+                    self.fix_child(i);
+                    Some(ret)
+                }
+            }
+            GoDown(i) => {
+                if self.is_leaf() { return None }
+                let ret = self.edges.get_mut(i).remove(key);
+                if ret.is_some() { self.fix_child(i) }
+                ret
+            }
         }
-        (Node::new_leaf(self.elts.clone()), true)
     }
 }
 
-impl<K: Clone + Ord, V: Clone> Clone for Leaf<K, V> {
-    /// Returns a new `Leaf` with the same elts.
-    fn clone(&self) -> Leaf<K, V> {
-        Leaf::new(self.elts.clone())
+/// Moves the elements of `v` from index `at` onwards onto the (empty) `to`.
+fn split_off<T>(v: &mut Vec<T>, to: &mut Vec<T>, at: uint) {
+    while v.len() > at {
+        to.push(v.pop().unwrap());
     }
+    to.reverse();
 }
 
-impl<K: Ord, V: Eq> PartialEq for Leaf<K, V> {
-    fn eq(&self, other: &Leaf<K, V>) -> bool {
-        self.elts == other.elts
+// The borrowing iterators are built on a pair of positions in the tree,
+// each of which lies between two adjacent elements. Every such position
+// corresponds to exactly one edge of a leaf, so a position is represented
+// by the path from the root down to that leaf edge, recording which edge
+// of every node the path goes through. Iteration is over when the two
+// positions meet.
+type Path<K, V> = Vec<(*const Node<K, V>, uint)>;
+
+/// Extends `path` from `node` down to a leaf, using `pick` to choose the
+/// edge of every node.
+fn descend<K, V>(path: &mut Path<K, V>, node: &Node<K, V>, pick: |&Node<K, V>| -> uint) {
+    let mut node = node;
+    loop {
+        let i = pick(node);
+        path.push((node as *const Node<K, V>, i));
+        if node.is_leaf() { return }
+        node = &node.edges[i];
     }
 }
 
-impl<K: Ord, V: Eq> Eq for Leaf<K, V> {}
+struct RawEntries<K, V> {
+    front: Path<K, V>,
+    back: Path<K, V>,
+    remaining_min: uint,
+    remaining_max: uint
+}
 
-impl<K: Ord, V: Eq> PartialOrd for Leaf<K, V> {
-    fn partial_cmp(&self, other: &Leaf<K, V>) -> Option<Ordering> {
-        Some(self.cmp(other))
+impl<K, V> RawEntries<K, V> {
+    #[inline]
+    fn is_empty(&self) -> bool {
+        self.front.last() == self.back.last()
     }
-}
 
-impl<K: Ord, V: Eq> Ord for Leaf<K, V> {
-    /// Returns an ordering based on the first element of each `Leaf`.
-    fn cmp(&self, other: &Leaf<K, V>) -> Ordering {
-        if self.elts.len() > other.elts.len() {
-            return Greater;
+    #[inline]
+    fn count(&mut self) {
+        self.remaining_max -= 1;
+        if self.remaining_min > 0 {
+            self.remaining_min -= 1;
         }
-        if self.elts.len() < other.elts.len() {
-            return Less;
+    }
+
+    unsafe fn next(&mut self) -> Option<(*const K, *const V)> {
+        if self.is_empty() { return None }
+
+        // Climb to the closest node with an element after the front edge;
+        // there is one, since the front has not reached the back yet.
+        let mut level = self.front.len() - 1;
+        loop {
+            let (node, i) = self.front[level];
+            if i < (*node).keys.len() { break }
+            level -= 1;
         }
-        self.elts[0].cmp(&other.elts[0])
+
+        let (node, i) = self.front[level];
+        self.front.truncate(level);
+        self.front.push((node, i + 1));
+        if !(*node).is_leaf() {
+            descend(&mut self.front, &(*node).edges[i + 1], |_| 0);
+        }
+
+        self.count();
+        Some((&(*node).keys[i] as *const K, &(*node).vals[i] as *const V))
     }
-}
 
+    unsafe fn next_back(&mut self) -> Option<(*const K, *const V)> {
+        if self.is_empty() { return None }
 
-impl<K: fmt::Show + Ord, V: fmt::Show> fmt::Show for Leaf<K, V> {
-    /// Returns a string representation of a `Leaf`.
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        for (i, s) in self.elts.iter().enumerate() {
-            if i != 0 { try!(write!(f, " // ")) }
-            try!(write!(f, "{}", *s))
+        let mut level = self.back.len() - 1;
+        loop {
+            let (_, i) = self.back[level];
+            if i > 0 { break }
+            level -= 1;
+        }
+
+        let (node, i) = self.back[level];
+        self.back.truncate(level);
+        self.back.push((node, i - 1));
+        if !(*node).is_leaf() {
+            descend(&mut self.back, &(*node).edges[i - 1], |node| node.keys.len());
         }
-        Ok(())
+
+        self.count();
+        Some((&(*node).keys[i - 1] as *const K, &(*node).vals[i - 1] as *const V))
     }
 }
 
+/// Lazy double-ended iterator over a map
+pub struct Entries<'a, K:'a, V:'a> {
+    raw: RawEntries<K, V>,
+    marker: marker::ContravariantLifetime<'a>
+}
 
-impl<K: Ord, V> Branch<K, V> {
-    /// Creates a new `Branch` from a vector of `BranchElts` and a rightmost child (a node).
-    fn new(vec: Vec<BranchElt<K, V>>, right: Box<Node<K, V>>)
-           -> Branch<K, V> {
-        Branch {
-            elts: vec,
-            rightmost_child: right
-        }
+/// Lazy backward iterator over a map
+pub type RevEntries<'a, K, V> = iter::Rev<Entries<'a, K, V>>;
+
+/// Lazy double-ended iterator over a map that allows for the mutation of
+/// the values.
+pub struct MutEntries<'a, K:'a, V:'a> {
+    raw: RawEntries<K, V>,
+    marker: marker::ContravariantLifetime<'a>
+}
+
+/// Lazy backward iterator over a map that allows for the mutation of the
+/// values.
+pub type RevMutEntries<'a, K, V> = iter::Rev<MutEntries<'a, K, V>>;
+
+/// BTreeMap keys iterator.
+pub type Keys<'a, K, V> =
+    iter::Map<'static, (&'a K, &'a V), &'a K, Entries<'a, K, V>>;
+
+/// BTreeMap values iterator.
+pub type Values<'a, K, V> =
+    iter::Map<'static, (&'a K, &'a V), &'a V, Entries<'a, K, V>>;
+
+impl<'a, K, V> Iterator<(&'a K, &'a V)> for Entries<'a, K, V> {
+    #[inline]
+    fn next(&mut self) -> Option<(&'a K, &'a V)> {
+        unsafe { self.raw.next().map(|(k, v)| (&*k, &*v)) }
     }
 
-    fn bsearch_branch(&self, k: K) -> Option<uint> {
-        let mut midpoint: uint = self.elts.len() / 2;
-        let mut high: uint = self.elts.len();
-        let mut low: uint = 0u;
-        if midpoint == high {
-            midpoint = 0u;
-        }
-        loop {
-            let order = self.elts[midpoint].key.cmp(&k);
-            match order {
-                Equal => {
-                    return None;
-                }
-                Greater => {
-                    if midpoint > 0 {
-                        if self.elts[midpoint - 1].key.cmp(&k) == Less {
-                            return Some(midpoint);
-                        }
-                        else {
-                            let tmp = midpoint;
-                            midpoint = (midpoint - low) / 2;
-                            high = tmp;
-                            continue;
-                        }
-                    }
-                    else {
-                        return Some(0);
-                    }
-                }
-                Less => {
-                    if midpoint + 1 < self.elts.len() {
-                        if self.elts[midpoint + 1].key.cmp(&k) == Greater {
-                            return Some(midpoint);
-                        }
-                        else {
-                            let tmp = midpoint;
-                            midpoint = (high - midpoint) / 2;
-                            low = tmp;
-                        }
-                    }
-                    else {
-                        return Some(self.elts.len());
-                    }
-                }
-            }
-        }
+    #[inline]
+    fn size_hint(&self) -> (uint, Option<uint>) {
+        (self.raw.remaining_min, Some(self.raw.remaining_max))
     }
 }
 
-impl<K: Clone + Ord, V: Clone> Branch<K, V> {
-    /// Returns the corresponding value to the supplied key.
-    /// If the key is not there, find the child that might hold it.
-    fn get(&self, k: K) -> Option<V> {
-        for s in self.elts.iter() {
-            let order = s.key.cmp(&k);
-            match order {
-                Less => return s.left.get(k),
-                Equal => return Some(s.value.clone()),
-                _ => {}
-            }
-        }
-        self.rightmost_child.get(k)
-    }
-
-    /// An insert method that uses `.clone()` for support.
-    fn insert(mut self, k: K, v: V, ub: uint) -> (Node<K, V>, bool) {
-        let mut new_branch = Node::new_branch(self.clone().elts, self.clone().rightmost_child);
-        let mut outcome = false;
-        let index: Option<uint> = new_branch.bsearch_node(k.clone());
-        //First, find which path down the tree will lead to the appropriate leaf
-        //for the key-value pair.
-        match index.clone() {
-            None => {
-                return (Node::new_branch(self.clone().elts,
-                                         self.clone().rightmost_child),
-                        outcome);
-            }
-            Some(i) => {
-                if i == self.elts.len() {
-                    let new_outcome = self.clone().rightmost_child.insert(k.clone(),
-                                                                          v.clone(),
-                                                                          ub.clone());
-                    new_branch = new_outcome.clone().val0();
-                    outcome = new_outcome.val1();
-                }
-                else {
-                    let new_outcome = self.elts[i].left.clone().insert(k.clone(),
-                                                                       v.clone(),
-                                                                       ub.clone());
-                    new_branch = new_outcome.clone().val0();
-                    outcome = new_outcome.val1();
-                }
-                //Check to see whether a branch or a leaf was returned from the
-                //tree traversal.
-                match new_branch.clone() {
-                    //If we have a leaf, we do not need to resize the tree,
-                    //so we can return false.
-                    LeafNode(..) => {
-                        if i == self.elts.len() {
-                            self.rightmost_child = box new_branch.clone();
-                        }
-                        else {
-                            self.elts.get_mut(i).left = box new_branch.clone();
-                        }
-                        return (Node::new_branch(self.clone().elts,
-                                                 self.clone().rightmost_child),
-                                true);
-                    }
-                    //If we have a branch, we might need to refactor the tree.
-                    BranchNode(..) => {}
-                }
-            }
-        }
-        //If we inserted something into the tree, do the following:
-        if outcome {
-            match new_branch.clone() {
-                //If we have a new leaf node, integrate it into the current branch
-                //and return it, saying we have inserted a new element.
-                LeafNode(..) => {
-                    if index.unwrap() == self.elts.len() {
-                        self.rightmost_child = box new_branch;
-                    }
-                    else {
-                        self.elts.get_mut(index.unwrap()).left = box new_branch;
-                    }
-                    return (Node::new_branch(self.clone().elts,
-                                             self.clone().rightmost_child),
-                            true);
-                }
-                //If we have a new branch node, attempt to insert it into the tree
-                //as with the key-value pair, then check to see if the node is overfull.
-                BranchNode(branch) => {
-                    let new_elt = branch.elts[0].clone();
-                    let new_elt_index = self.bsearch_branch(new_elt.clone().key);
-                    match new_elt_index {
-                        None => {
-                            return (Node::new_branch(self.clone().elts,
-                                                     self.clone().rightmost_child),
-                                    false);
-                            }
-                        Some(i) => {
-                            self.elts.insert(i, new_elt);
-                            if i + 1 >= self.elts.len() {
-                                self.rightmost_child = branch.clone().rightmost_child;
-                            }
-                            else {
-                                self.elts.get_mut(i + 1).left =
-                                    branch.clone().rightmost_child;
-                            }
-                        }
-                    }
-                }
-            }
-            //If the current node is overfilled, create a new branch with one element
-            //and two children.
-            if self.elts.len() > ub {
-                let midpoint = self.elts.remove(ub / 2).unwrap();
-                let (new_left, new_right) = self.clone().elts.partition(|le|
-                                                                midpoint.key.cmp(&le.key)
-                                                                        == Greater);
-                new_branch = Node::new_branch(
-                    vec!(BranchElt::new(midpoint.clone().key,
-                                     midpoint.clone().value,
-                                     box Node::new_branch(new_left,
-                                                       midpoint.clone().left))),
-                    box Node::new_branch(new_right, self.clone().rightmost_child));
-                return (new_branch, true);
-            }
-        }
-        (Node::new_branch(self.elts.clone(), self.rightmost_child.clone()), outcome)
+impl<'a, K, V> DoubleEndedIterator<(&'a K, &'a V)> for Entries<'a, K, V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<(&'a K, &'a V)> {
+        unsafe { self.raw.next_back().map(|(k, v)| (&*k, &*v)) }
     }
 }
 
-impl<K: Clone + Ord, V: Clone> Clone for Branch<K, V> {
-    /// Returns a new branch using the clone methods of the `Branch`'s internal variables.
-    fn clone(&self) -> Branch<K, V> {
-        Branch::new(self.elts.clone(), self.rightmost_child.clone())
+impl<'a, K, V> Iterator<(&'a K, &'a mut V)> for MutEntries<'a, K, V> {
+    #[inline]
+    fn next(&mut self) -> Option<(&'a K, &'a mut V)> {
+        unsafe { self.raw.next().map(|(k, v)| (&*k, &mut *(v as *mut V))) }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (uint, Option<uint>) {
+        (self.raw.remaining_min, Some(self.raw.remaining_max))
     }
 }
 
-impl<K: Ord, V: Eq> PartialEq for Branch<K, V> {
-    fn eq(&self, other: &Branch<K, V>) -> bool {
-        self.elts == other.elts
+impl<'a, K, V> DoubleEndedIterator<(&'a K, &'a mut V)> for MutEntries<'a, K, V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<(&'a K, &'a mut V)> {
+        unsafe { self.raw.next_back().map(|(k, v)| (&*k, &mut *(v as *mut V))) }
     }
 }
 
-impl<K: Ord, V: Eq> Eq for Branch<K, V> {}
+/// Lazy double-ended iterator over a map that consumes the map while
+/// iterating
+pub struct MoveEntries<K, V> {
+    iter: vec::MoveItems<(K, V)>
+}
+
+impl<K, V> Iterator<(K, V)> for MoveEntries<K, V> {
+    #[inline]
+    fn next(&mut self) -> Option<(K, V)> { self.iter.next() }
+
+    #[inline]
+    fn size_hint(&self) -> (uint, Option<uint>) { self.iter.size_hint() }
+}
+
+impl<K, V> DoubleEndedIterator<(K, V)> for MoveEntries<K, V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<(K, V)> { self.iter.next_back() }
+}
+
+impl<K, V> ExactSize<(K, V)> for MoveEntries<K, V> {}
+
+/// An implementation of the `Set` trait on top of the `BTreeMap` container.
+/// The only requirement is that the type of the elements contained ascribes
+/// to the `Ord` trait.
+///
+/// ## Example
+///
+/// ```{rust}
+/// use std::collections::BTreeSet;
+///
+/// let mut set = BTreeSet::new();
+///
+/// set.insert(2i);
+/// set.insert(1i);
+/// set.insert(3i);
+///
+/// for i in set.iter() {
+///    println!("{}", i) // prints 1, then 2, then 3
+/// }
+///
+/// set.remove(&3);
+///
+/// if !set.contains(&3) {
+///     println!("set does not contain a 3 anymore");
+/// }
+/// ```
+#[deriving(Clone)]
+pub struct BTreeSet<T> {
+    map: BTreeMap<T, ()>
+}
+
+impl<T: PartialEq + Ord> PartialEq for BTreeSet<T> {
+    #[inline]
+    fn eq(&self, other: &BTreeSet<T>) -> bool { self.map == other.map }
+}
+
+impl<T: Eq + Ord> Eq for BTreeSet<T> {}
 
-impl<K: Ord, V: Eq> PartialOrd for Branch<K, V> {
-    fn partial_cmp(&self, other: &Branch<K, V>) -> Option<Ordering> {
-        Some(self.cmp(other))
+impl<T: Ord> PartialOrd for BTreeSet<T> {
+    #[inline]
+    fn partial_cmp(&self, other: &BTreeSet<T>) -> Option<Ordering> {
+        self.map.partial_cmp(&other.map)
     }
 }
 
-impl<K: Ord, V: Eq> Ord for Branch<K, V> {
-    /// Compares the first elements of two `Branch`es to determine an
-    /// `Ordering`.
-    fn cmp(&self, other: &Branch<K, V>) -> Ordering {
-        if self.elts.len() > other.elts.len() {
-            return Greater;
-        }
-        if self.elts.len() < other.elts.len() {
-            return Less;
-        }
-        self.elts[0].cmp(&other.elts[0])
+impl<T: Ord> Ord for BTreeSet<T> {
+    #[inline]
+    fn cmp(&self, other: &BTreeSet<T>) -> Ordering {
+        iter::order::cmp(self.iter(), other.iter())
     }
 }
 
-impl<K: fmt::Show + Ord, V: fmt::Show> fmt::Show for Branch<K, V> {
-    /// Returns a string representation of a `Branch`.
+impl<T: Ord + Show> Show for BTreeSet<T> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        for (i, s) in self.elts.iter().enumerate() {
-            if i != 0 { try!(write!(f, " // ")) }
-            try!(write!(f, "{}", *s))
+        try!(write!(f, "{{"));
+
+        for (i, x) in self.iter().enumerate() {
+            if i != 0 { try!(write!(f, ", ")); }
+            try!(write!(f, "{}", *x));
         }
-        write!(f, " // rightmost child: ({}) ", *self.rightmost_child)
+
+        write!(f, "}}")
     }
 }
 
-//A LeafElt contains no left child, but a key-value pair.
-struct LeafElt<K, V> {
-    key: K,
-    value: V
+impl<T: Ord> Collection for BTreeSet<T> {
+    #[inline]
+    fn len(&self) -> uint { self.map.len() }
 }
 
-//A BranchElt has a left child in insertion to a key-value pair.
-struct BranchElt<K, V> {
-    left: Box<Node<K, V>>,
-    key: K,
-    value: V
+impl<T: Ord> Mutable for BTreeSet<T> {
+    #[inline]
+    fn clear(&mut self) { self.map.clear() }
 }
 
-impl<K: Ord, V> LeafElt<K, V> {
-    /// Creates a new `LeafElt` from a supplied key-value pair.
-    fn new(k: K, v: V) -> LeafElt<K, V> {
-        LeafElt {
-            key: k,
-            value: v
+impl<T: Ord> Set<T> for BTreeSet<T> {
+    #[inline]
+    fn contains(&self, value: &T) -> bool {
+        self.map.contains_key(value)
+    }
+
+    fn is_disjoint(&self, other: &BTreeSet<T>) -> bool {
+        self.intersection(other).next().is_none()
+    }
+
+    fn is_subset(&self, other: &BTreeSet<T>) -> bool {
+        let mut x = self.iter();
+        let mut y = other.iter();
+        let mut a = x.next();
+        let mut b = y.next();
+        while a.is_some() {
+            if b.is_none() {
+                return false;
+            }
+
+            let a1 = a.unwrap();
+            let b1 = b.unwrap();
+
+            match b1.cmp(a1) {
+                Less => (),
+                Greater => return false,
+                Equal => a = x.next(),
+            }
+
+            b = y.next();
         }
+        true
     }
 }
 
-impl<K: Clone + Ord, V: Clone> Clone for LeafElt<K, V> {
-    /// Returns a new `LeafElt` by cloning the key and value.
-    fn clone(&self) -> LeafElt<K, V> {
-        LeafElt::new(self.key.clone(), self.value.clone())
-    }
+impl<T: Ord> MutableSet<T> for BTreeSet<T> {
+    #[inline]
+    fn insert(&mut self, value: T) -> bool { self.map.insert(value, ()) }
+
+    #[inline]
+    fn remove(&mut self, value: &T) -> bool { self.map.remove(value) }
+}
+
+impl<T: Ord> Default for BTreeSet<T> {
+    #[inline]
+    fn default() -> BTreeSet<T> { BTreeSet::new() }
 }
 
-impl<K: Ord, V: Eq> PartialEq for LeafElt<K, V> {
-    fn eq(&self, other: &LeafElt<K, V>) -> bool {
-        self.key == other.key && self.value == other.value
+impl<T: Ord> BTreeSet<T> {
+    /// Creates an empty `BTreeSet`. This does not allocate.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::BTreeSet;
+    /// let mut set: BTreeSet<int> = BTreeSet::new();
+    /// ```
+    #[inline]
+    pub fn new() -> BTreeSet<T> { BTreeSet{map: BTreeMap::new()} }
+
+    /// Gets a lazy iterator over the values in the set, in ascending order.
+    /// The iterator is double-ended.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::BTreeSet;
+    /// let set: BTreeSet<int> = [1i, 4, 3, 5, 2].iter().map(|&x| x).collect();
+    ///
+    /// // Will print in ascending order.
+    /// for x in set.iter() {
+    ///     println!("{}", x);
+    /// }
+    /// ```
+    #[inline]
+    pub fn iter<'a>(&'a self) -> SetItems<'a, T> {
+        SetItems{iter: self.map.keys()}
+    }
+
+    /// Gets a lazy iterator over the values in the set, in descending order.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::BTreeSet;
+    /// let set: BTreeSet<int> = [1i, 4, 3, 5, 2].iter().map(|&x| x).collect();
+    ///
+    /// // Will print in descending order.
+    /// for x in set.rev_iter() {
+    ///     println!("{}", x);
+    /// }
+    /// ```
+    #[inline]
+    pub fn rev_iter<'a>(&'a self) -> RevSetItems<'a, T> {
+        self.iter().rev()
+    }
+
+    /// Creates a consuming iterator, that is, one that moves each value out of the
+    /// set in ascending order. The set cannot be used after calling this.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::BTreeSet;
+    /// let set: BTreeSet<int> = [1i, 4, 3, 5, 2].iter().map(|&x| x).collect();
+    ///
+    /// // Not possible with a regular `.iter()`
+    /// let v: Vec<int> = set.move_iter().collect();
+    /// assert_eq!(v, vec![1, 2, 3, 4, 5]);
+    /// ```
+    #[inline]
+    pub fn move_iter(self) -> MoveSetItems<T> {
+        self.map.move_iter().map(|(value, _)| value)
+    }
+
+    /// Gets a lazy iterator pointing to the first value not less than `v` (greater or equal).
+    /// If all elements in the set are less than `v` empty iterator is returned.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::BTreeSet;
+    /// let set: BTreeSet<int> = [2, 4, 6, 8].iter().map(|&x| x).collect();
+    ///
+    /// assert_eq!(set.lower_bound(&4).next(), Some(&4));
+    /// assert_eq!(set.lower_bound(&5).next(), Some(&6));
+    /// assert_eq!(set.lower_bound(&10).next(), None);
+    /// ```
+    #[inline]
+    pub fn lower_bound<'a>(&'a self, v: &T) -> SetItems<'a, T> {
+        SetItems{iter: self.map.lower_bound(v).map(|(k, _)| k)}
+    }
+
+    /// Gets a lazy iterator pointing to the first value greater than `v`.
+    /// If all elements in the set are less than or equal to `v` an
+    /// empty iterator is returned.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::BTreeSet;
+    /// let set: BTreeSet<int> = [2, 4, 6, 8].iter().map(|&x| x).collect();
+    ///
+    /// assert_eq!(set.upper_bound(&4).next(), Some(&6));
+    /// assert_eq!(set.upper_bound(&5).next(), Some(&6));
+    /// assert_eq!(set.upper_bound(&10).next(), None);
+    /// ```
+    #[inline]
+    pub fn upper_bound<'a>(&'a self, v: &T) -> SetItems<'a, T> {
+        SetItems{iter: self.map.upper_bound(v).map(|(k, _)| k)}
+    }
+
+    /// Gets a lazy iterator over the values in the half-open interval
+    /// `[min, max)`, in ascending order.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::BTreeSet;
+    /// let set: BTreeSet<int> = [2, 4, 6, 8].iter().map(|&x| x).collect();
+    ///
+    /// let v: Vec<int> = set.range(&3, &8).map(|&x| x).collect();
+    /// assert_eq!(v, vec![4, 6]);
+    /// ```
+    #[inline]
+    pub fn range<'a>(&'a self, min: &T, max: &T) -> SetItems<'a, T> {
+        SetItems{iter: self.map.range(min, max).map(|(k, _)| k)}
+    }
+
+    /// Visits the values representing the difference, in ascending order.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::BTreeSet;
+    ///
+    /// let a: BTreeSet<int> = [1, 2, 3].iter().map(|&x| x).collect();
+    /// let b: BTreeSet<int> = [3, 4, 5].iter().map(|&x| x).collect();
+    ///
+    /// let diff: BTreeSet<int> = a.difference(&b).map(|&x| x).collect();
+    /// assert_eq!(diff, [1, 2].iter().map(|&x| x).collect());
+    /// ```
+    pub fn difference<'a>(&'a self, other: &'a BTreeSet<T>) -> DifferenceItems<'a, T> {
+        DifferenceItems{a: self.iter().peekable(), b: other.iter().peekable()}
+    }
+
+    /// Visits the values representing the symmetric difference, in ascending order.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::BTreeSet;
+    ///
+    /// let a: BTreeSet<int> = [1, 2, 3].iter().map(|&x| x).collect();
+    /// let b: BTreeSet<int> = [3, 4, 5].iter().map(|&x| x).collect();
+    ///
+    /// let diff: BTreeSet<int> = a.symmetric_difference(&b).map(|&x| x).collect();
+    /// assert_eq!(diff, [1, 2, 4, 5].iter().map(|&x| x).collect());
+    /// ```
+    pub fn symmetric_difference<'a>(&'a self, other: &'a BTreeSet<T>)
+        -> SymDifferenceItems<'a, T> {
+        SymDifferenceItems{a: self.iter().peekable(), b: other.iter().peekable()}
+    }
+
+    /// Visits the values representing the intersection, in ascending order.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::BTreeSet;
+    ///
+    /// let a: BTreeSet<int> = [1, 2, 3].iter().map(|&x| x).collect();
+    /// let b: BTreeSet<int> = [2, 3, 4].iter().map(|&x| x).collect();
+    ///
+    /// let diff: BTreeSet<int> = a.intersection(&b).map(|&x| x).collect();
+    /// assert_eq!(diff, [2, 3].iter().map(|&x| x).collect());
+    /// ```
+    pub fn intersection<'a>(&'a self, other: &'a BTreeSet<T>)
+        -> IntersectionItems<'a, T> {
+        IntersectionItems{a: self.iter().peekable(), b: other.iter().peekable()}
+    }
+
+    /// Visits the values representing the union, in ascending order.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::BTreeSet;
+    ///
+    /// let a: BTreeSet<int> = [1, 2, 3].iter().map(|&x| x).collect();
+    /// let b: BTreeSet<int> = [3, 4, 5].iter().map(|&x| x).collect();
+    ///
+    /// let diff: BTreeSet<int> = a.union(&b).map(|&x| x).collect();
+    /// assert_eq!(diff, [1, 2, 3, 4, 5].iter().map(|&x| x).collect());
+    /// ```
+    pub fn union<'a>(&'a self, other: &'a BTreeSet<T>) -> UnionItems<'a, T> {
+        UnionItems{a: self.iter().peekable(), b: other.iter().peekable()}
     }
 }
 
-impl<K: Ord, V: Eq> Eq for LeafElt<K, V> {}
+/// A lazy double-ended iterator over a set.
+pub struct SetItems<'a, T:'a> {
+    iter: Keys<'a, T, ()>
+}
+
+/// A lazy backward iterator over a set.
+pub type RevSetItems<'a, T> = iter::Rev<SetItems<'a, T>>;
+
+/// A lazy forward iterator over a set that consumes the set while iterating.
+pub type MoveSetItems<T> = iter::Map<'static, (T, ()), T, MoveEntries<T, ()>>;
+
+/// A lazy iterator producing elements in the set difference (in-order).
+pub struct DifferenceItems<'a, T:'a> {
+    a: Peekable<&'a T, SetItems<'a, T>>,
+    b: Peekable<&'a T, SetItems<'a, T>>,
+}
+
+/// A lazy iterator producing elements in the set symmetric difference (in-order).
+pub struct SymDifferenceItems<'a, T:'a> {
+    a: Peekable<&'a T, SetItems<'a, T>>,
+    b: Peekable<&'a T, SetItems<'a, T>>,
+}
+
+/// A lazy iterator producing elements in the set intersection (in-order).
+pub struct IntersectionItems<'a, T:'a> {
+    a: Peekable<&'a T, SetItems<'a, T>>,
+    b: Peekable<&'a T, SetItems<'a, T>>,
+}
+
+/// A lazy iterator producing elements in the set union (in-order).
+pub struct UnionItems<'a, T:'a> {
+    a: Peekable<&'a T, SetItems<'a, T>>,
+    b: Peekable<&'a T, SetItems<'a, T>>,
+}
+
+impl<'a, T> Iterator<&'a T> for SetItems<'a, T> {
+    #[inline]
+    fn next(&mut self) -> Option<&'a T> { self.iter.next() }
 
-impl<K: Ord, V: Eq> PartialOrd for LeafElt<K, V> {
-    fn partial_cmp(&self, other: &LeafElt<K, V>) -> Option<Ordering> {
-        Some(self.cmp(other))
+    #[inline]
+    fn size_hint(&self) -> (uint, Option<uint>) { self.iter.size_hint() }
+}
+
+impl<'a, T> DoubleEndedIterator<&'a T> for SetItems<'a, T> {
+    #[inline]
+    fn next_back(&mut self) -> Option<&'a T> { self.iter.next_back() }
+}
+
+/// Compare `x` and `y`, but return `short` if x is None and `long` if y is None
+fn cmp_opt<T: Ord>(x: Option<&T>, y: Option<&T>,
+                        short: Ordering, long: Ordering) -> Ordering {
+    match (x, y) {
+        (None    , _       ) => short,
+        (_       , None    ) => long,
+        (Some(x1), Some(y1)) => x1.cmp(y1),
     }
 }
 
-impl<K: Ord, V: Eq> Ord for LeafElt<K, V> {
-    /// Returns an ordering based on the keys of the `LeafElt`s.
-    fn cmp(&self, other: &LeafElt<K, V>) -> Ordering {
-        self.key.cmp(&other.key)
+impl<'a, T: Ord> Iterator<&'a T> for DifferenceItems<'a, T> {
+    fn next(&mut self) -> Option<&'a T> {
+        loop {
+            match cmp_opt(self.a.peek(), self.b.peek(), Less, Less) {
+                Less    => return self.a.next(),
+                Equal   => { self.a.next(); self.b.next(); }
+                Greater => { self.b.next(); }
+            }
+        }
     }
 }
 
-impl<K: fmt::Show + Ord, V: fmt::Show> fmt::Show for LeafElt<K, V> {
-    /// Returns a string representation of a `LeafElt`.
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "Key: {}, value: {};", self.key, self.value)
+impl<'a, T: Ord> Iterator<&'a T> for SymDifferenceItems<'a, T> {
+    fn next(&mut self) -> Option<&'a T> {
+        loop {
+            match cmp_opt(self.a.peek(), self.b.peek(), Greater, Less) {
+                Less    => return self.a.next(),
+                Equal   => { self.a.next(); self.b.next(); }
+                Greater => return self.b.next(),
+            }
+        }
     }
 }
 
-impl<K: Ord, V> BranchElt<K, V> {
-    /// Creates a new `BranchElt` from a supplied key, value, and left child.
-    fn new(k: K, v: V, n: Box<Node<K, V>>) -> BranchElt<K, V> {
-        BranchElt {
-            left: n,
-            key: k,
-            value: v
+impl<'a, T: Ord> Iterator<&'a T> for IntersectionItems<'a, T> {
+    fn next(&mut self) -> Option<&'a T> {
+        loop {
+            let o_cmp = match (self.a.peek(), self.b.peek()) {
+                (None    , _       ) => None,
+                (_       , None    ) => None,
+                (Some(a1), Some(b1)) => Some(a1.cmp(b1)),
+            };
+            match o_cmp {
+                None          => return None,
+                Some(Less)    => { self.a.next(); }
+                Some(Equal)   => { self.b.next(); return self.a.next() }
+                Some(Greater) => { self.b.next(); }
+            }
         }
     }
 }
 
+impl<'a, T: Ord> Iterator<&'a T> for UnionItems<'a, T> {
+    fn next(&mut self) -> Option<&'a T> {
+        loop {
+            match cmp_opt(self.a.peek(), self.b.peek(), Greater, Less) {
+                Less    => return self.a.next(),
+                Equal   => { self.b.next(); return self.a.next() }
+                Greater => return self.b.next(),
+            }
+        }
+    }
+}
 
-impl<K: Clone + Ord, V: Clone> Clone for BranchElt<K, V> {
-    /// Returns a new `BranchElt` by cloning the key, value, and left child.
-    fn clone(&self) -> BranchElt<K, V> {
-        BranchElt::new(self.key.clone(),
-                       self.value.clone(),
-                       self.left.clone())
+impl<K: Ord, V> FromIterator<(K, V)> for BTreeMap<K, V> {
+    fn from_iter<T: Iterator<(K, V)>>(iter: T) -> BTreeMap<K, V> {
+        let mut map = BTreeMap::new();
+        map.extend(iter);
+        map
     }
 }
 
-impl<K: Ord, V: Eq> PartialEq for BranchElt<K, V>{
-    fn eq(&self, other: &BranchElt<K, V>) -> bool {
-        self.key == other.key && self.value == other.value
+impl<K: Ord, V> Extendable<(K, V)> for BTreeMap<K, V> {
+    #[inline]
+    fn extend<T: Iterator<(K, V)>>(&mut self, mut iter: T) {
+        for (k, v) in iter {
+            self.insert(k, v);
+        }
     }
 }
 
-impl<K: Ord, V: Eq> Eq for BranchElt<K, V>{}
+impl<S: Writer, K: Ord + Hash<S>, V: Hash<S>> Hash<S> for BTreeMap<K, V> {
+    fn hash(&self, state: &mut S) {
+        for elt in self.iter() {
+            elt.hash(state);
+        }
+    }
+}
 
-impl<K: Ord, V: Eq> PartialOrd for BranchElt<K, V> {
-    fn partial_cmp(&self, other: &BranchElt<K, V>) -> Option<Ordering> {
-        Some(self.cmp(other))
+impl<T: Ord> FromIterator<T> for BTreeSet<T> {
+    fn from_iter<Iter: Iterator<T>>(iter: Iter) -> BTreeSet<T> {
+        let mut set = BTreeSet::new();
+        set.extend(iter);
+        set
     }
 }
 
-impl<K: Ord, V: Eq> Ord for BranchElt<K, V> {
-    /// Fulfills `Ord` for `BranchElts`.
-    fn cmp(&self, other: &BranchElt<K, V>) -> Ordering {
-        self.key.cmp(&other.key)
+impl<T: Ord> Extendable<T> for BTreeSet<T> {
+    #[inline]
+    fn extend<Iter: Iterator<T>>(&mut self, mut iter: Iter) {
+        for elem in iter {
+            self.insert(elem);
+        }
     }
 }
 
-impl<K: fmt::Show + Ord, V: fmt::Show> fmt::Show for BranchElt<K, V> {
-    /// Formats as a string containing the key, value, and child (which should recur to a
-    /// leaf). Consider changing in future to be more readable.
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "Key: {}, value: {}, (child: {})",
-               self.key, self.value, *self.left)
+impl<S: Writer, T: Ord + Hash<S>> Hash<S> for BTreeSet<T> {
+    fn hash(&self, state: &mut S) {
+        for elt in self.iter() {
+            elt.hash(state);
+        }
     }
 }
 
 #[cfg(test)]
-mod test_btree {
+mod test_map {
     use std::prelude::*;
+    use std::iter::range_step;
+    use std::rand::Rng;
+    use std::rand;
+
+    use {Map, MutableMap, Mutable, MutableSeq};
+    use super::{BTreeMap, Node, CAPACITY, MIN_LEN};
+
+    #[test]
+    fn find_empty() {
+        let m: BTreeMap<int,int> = BTreeMap::new();
+        assert!(m.find(&5) == None);
+    }
+
+    #[test]
+    fn find_not_found() {
+        let mut m = BTreeMap::new();
+        assert!(m.insert(1i, 2i));
+        assert!(m.insert(5i, 3i));
+        assert!(m.insert(9i, 3i));
+        assert_eq!(m.find(&2), None);
+    }
+
+    #[test]
+    fn find_with_found() {
+        let mut m = BTreeMap::new();
+        assert!(m.insert("test1", 2i));
+        assert!(m.insert("test2", 3i));
+        assert!(m.insert("test3", 4i));
+        assert_eq!(m.find_with(|k| "test2".cmp(k)), Some(&3i));
+        assert_eq!(m.find_with(|k| "test4".cmp(k)), None);
+    }
+
+    #[test]
+    fn test_find_mut() {
+        let mut m = BTreeMap::new();
+        for i in range(0i, 100) {
+            assert!(m.insert(i, i));
+        }
+        for i in range(0i, 100) {
+            *m.find_mut(&i).unwrap() *= 2;
+        }
+        assert!(m.find_mut(&100).is_none());
+        for i in range(0i, 100) {
+            assert_eq!(m.find(&i), Some(&(i * 2)));
+        }
+    }
+
+    #[test]
+    fn test_swap_and_pop() {
+        let mut m = BTreeMap::new();
+        assert_eq!(m.swap(1i, 2i), None);
+        assert_eq!(m.swap(1i, 3i), Some(2));
+        assert_eq!(m.swap(1i, 4i), Some(3));
+        assert_eq!(m.pop(&1), Some(4));
+        assert_eq!(m.pop(&1), None);
+        assert!(m.is_empty());
+    }
+
+    fn check_equal<K: PartialEq + Ord, V: PartialEq>(ctrl: &[(K, V)],
+                                                     map: &BTreeMap<K, V>) {
+        assert_eq!(ctrl.len(), map.len());
+        for x in ctrl.iter() {
+            let &(ref k, ref v) = x;
+            assert!(map.find(k).unwrap() == v)
+        }
+        let mut sorted: Vec<&(K, V)> = ctrl.iter().collect();
+        sorted.sort_by(|&&(ref a, _), &&(ref b, _)| a.cmp(b));
+        for (&&(ref k, ref v), (map_k, map_v)) in sorted.iter().zip(map.iter()) {
+            assert!(k == map_k && v == map_v);
+        }
+    }
+
+    // Returns the height of the subtree, checking that every leaf is at
+    // the same depth and every non-root node is at least half full.
+    fn check_node<K: Ord, V>(node: &Node<K, V>, is_root: bool) -> uint {
+        assert_eq!(node.keys.len(), node.vals.len());
+        assert!(node.keys.len() <= CAPACITY);
+        if !is_root {
+            assert!(node.keys.len() >= MIN_LEN);
+        }
+        for w in node.keys.as_slice().windows(2) {
+            assert!(w[0] < w[1]);
+        }
+        if node.is_leaf() { return 0 }
+
+        assert_eq!(node.edges.len(), node.keys.len() + 1);
+        let height = check_node(&node.edges[0], false);
+        for (i, edge) in node.edges.iter().enumerate() {
+            assert_eq!(check_node(edge, false), height);
+            if i > 0 {
+                assert!(edge.keys[0] > node.keys[i - 1]);
+            }
+            if i < node.keys.len() {
+                assert!(*edge.keys.last().unwrap() < node.keys[i]);
+            }
+        }
+        height + 1
+    }
+
+    fn check_structure<K: Ord, V>(map: &BTreeMap<K, V>) {
+        check_node(&map.root, true);
+    }
+
+    #[test]
+    fn test_rand_int() {
+        let mut map: BTreeMap<int,int> = BTreeMap::new();
+        let mut ctrl = vec![];
+
+        check_equal(ctrl.as_slice(), &map);
+        assert!(map.find(&5).is_none());
+
+        let seed: &[_] = &[42];
+        let mut rng: rand::IsaacRng = rand::SeedableRng::from_seed(seed);
+
+        for _ in range(0u, 3) {
+            for _ in range(0u, 900) {
+                let k = rng.gen();
+                let v = rng.gen();
+                if !ctrl.iter().any(|&(x, _)| x == k) {
+                    assert!(map.insert(k, v));
+                    ctrl.push((k, v));
+                    check_structure(&map);
+                }
+            }
+            check_equal(ctrl.as_slice(), &map);
+
+            for _ in range(0u, 300) {
+                let r = rng.gen_range(0, ctrl.len());
+                let (key, _) = ctrl.remove(r).unwrap();
+                assert!(map.remove(&key));
+                check_structure(&map);
+            }
+            check_equal(ctrl.as_slice(), &map);
+        }
+
+        while !ctrl.is_empty() {
+            let (key, _) = ctrl.pop().unwrap();
+            assert!(map.remove(&key));
+            check_structure(&map);
+        }
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn test_len() {
+        let mut m = BTreeMap::new();
+        assert!(m.insert(3i, 6i));
+        assert_eq!(m.len(), 1);
+        assert!(m.insert(0, 0));
+        assert_eq!(m.len(), 2);
+        assert!(!m.insert(0, 1));
+        assert_eq!(m.len(), 2);
+        assert!(m.remove(&0));
+        assert_eq!(m.len(), 1);
+        assert!(!m.remove(&0));
+        assert_eq!(m.len(), 1);
+        m.clear();
+        assert_eq!(m.len(), 0);
+    }
+
+    #[test]
+    fn test_iterator() {
+        let mut m = BTreeMap::new();
+        for i in range(0u, 1000) {
+            assert!(m.insert(999 - i, i));
+        }
+
+        let mut n = 0;
+        for (&k, &v) in m.iter() {
+            assert_eq!(k, n);
+            assert_eq!(v, 999 - n);
+            n += 1;
+        }
+        assert_eq!(n, 1000);
+        assert_eq!(m.iter().size_hint(), (1000, Some(1000)));
+    }
+
+    #[test]
+    fn test_rev_iter() {
+        let mut m = BTreeMap::new();
+        for i in range(0u, 1000) {
+            assert!(m.insert(i, i * 2));
+        }
+
+        let mut n = 1000;
+        for (&k, &v) in m.rev_iter() {
+            n -= 1;
+            assert_eq!(k, n);
+            assert_eq!(v, n * 2);
+        }
+        assert_eq!(n, 0);
+    }
+
+    #[test]
+    fn test_double_ended_iterator() {
+        let m: BTreeMap<uint, uint> = range(0u, 1000).map(|i| (i, i)).collect();
+        let seed: &[_] = &[42];
+        let mut rng: rand::IsaacRng = rand::SeedableRng::from_seed(seed);
+
+        let mut iter = m.keys();
+        let (mut lo, mut hi) = (0u, 1000u);
+        while lo < hi {
+            if rng.gen() {
+                assert_eq!(iter.next(), Some(&lo));
+                lo += 1;
+            } else {
+                hi -= 1;
+                assert_eq!(iter.next_back(), Some(&hi));
+            }
+        }
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next_back(), None);
+    }
+
+    #[test]
+    fn test_mut_iter() {
+        let mut m = BTreeMap::new();
+        for i in range(0u, 100) {
+            assert!(m.insert(i, 100 * i));
+        }
+
+        for (i, (&k, v)) in m.mut_iter().enumerate() {
+            *v += k * 10 + i;
+        }
+
+        for (&k, &v) in m.iter() {
+            assert_eq!(v, 111 * k);
+        }
+    }
+
+    #[test]
+    fn test_mut_rev_iter() {
+        let mut m = BTreeMap::new();
+        for i in range(0u, 100) {
+            assert!(m.insert(i, 100 * i));
+        }
+
+        for (i, (&k, v)) in m.mut_rev_iter().enumerate() {
+            *v += k * 10 + (99 - i);
+        }
 
-    use super::{BTree, Node, LeafElt};
+        for (&k, &v) in m.iter() {
+            assert_eq!(v, 111 * k);
+        }
+    }
+
+    #[test]
+    fn test_move_iter() {
+        let m: BTreeMap<uint, uint> = range(0u, 1000).rev().map(|i| (i, i * 3)).collect();
+        let v: Vec<(uint, uint)> = m.clone().move_iter().collect();
+        let expected: Vec<(uint, uint)> = range(0u, 1000).map(|i| (i, i * 3)).collect();
+        assert_eq!(v, expected);
+
+        let mut iter = m.move_iter();
+        assert_eq!(iter.size_hint(), (1000, Some(1000)));
+        assert_eq!(iter.next_back(), Some((999, 2997)));
+        assert_eq!(iter.next(), Some((0, 0)));
+    }
+
+    #[test]
+    fn test_interval_iteration() {
+        let mut m = BTreeMap::new();
+        for i in range(1i, 100i) {
+            assert!(m.insert(i * 2, i * 4));
+        }
+
+        for i in range(1i, 198i) {
+            let mut lb_it = m.lower_bound(&i);
+            let (&k, &v) = lb_it.next().unwrap();
+            let lb = i + i % 2;
+            assert_eq!(lb, k);
+            assert_eq!(lb * 2, v);
+            assert_eq!(lb_it.next_back(), Some((&198, &396)));
+
+            let mut ub_it = m.upper_bound(&i);
+            let (&k, &v) = ub_it.next().unwrap();
+            let ub = i + 2 - i % 2;
+            assert_eq!(ub, k);
+            assert_eq!(ub * 2, v);
+        }
+        let mut end_it = m.lower_bound(&199);
+        assert_eq!(end_it.next(), None);
+        assert_eq!(end_it.next_back(), None);
+    }
+
+    #[test]
+    fn test_range() {
+        let m: BTreeMap<int, int> = range(0i, 500).map(|i| (i * 2, i)).collect();
+
+        for lo in range_step(-3i, 1004, 7) {
+            for hi in range_step(-3i, 1004, 11) {
+                let expected: Vec<int> = range(0i, 500).map(|i| i * 2)
+                                                       .filter(|&k| lo <= k && k < hi)
+                                                       .collect();
+                let forward: Vec<int> = m.range(&lo, &hi).map(|(&k, _)| k).collect();
+                assert_eq!(forward, expected);
+
+                let mut backward: Vec<int> = m.range(&lo, &hi).rev().map(|(&k, _)| k).collect();
+                backward.reverse();
+                assert_eq!(backward, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_mut_bounds() {
+        let mut m: BTreeMap<int, int> = range(0i, 100).map(|i| (i, 0)).collect();
+        for (_, v) in m.mut_lower_bound(&90) {
+            *v += 1;
+        }
+        for (_, v) in m.mut_upper_bound(&89) {
+            *v += 1;
+        }
+        for (_, v) in m.mut_range(&10, &20).rev() {
+            *v += 5;
+        }
+        for (&k, &v) in m.iter() {
+            let expected = if k >= 90 { 2 } else if k >= 10 && k < 20 { 5 } else { 0 };
+            assert_eq!(v, expected);
+        }
+    }
+
+    #[test]
+    fn test_eq() {
+        let mut a = BTreeMap::new();
+        let mut b = BTreeMap::new();
+
+        assert!(a == b);
+        assert!(a.insert(0i, 5i));
+        assert!(a != b);
+        assert!(b.insert(0, 4));
+        assert!(a != b);
+        assert!(a.insert(5, 19));
+        assert!(a != b);
+        assert!(!b.insert(0, 5));
+        assert!(a != b);
+        assert!(b.insert(5, 19));
+        assert!(a == b);
+    }
 
-    use MutableSeq;
+    #[test]
+    fn test_lt() {
+        let mut a = BTreeMap::new();
+        let mut b = BTreeMap::new();
+
+        assert!(!(a < b) && !(b < a));
+        assert!(b.insert(0i, 5i));
+        assert!(a < b);
+        assert!(a.insert(0, 7));
+        assert!(!(a < b) && b < a);
+        assert!(b.insert(-2, 0));
+        assert!(b < a);
+        assert!(a.insert(-5, 2));
+        assert!(a < b);
+        assert!(a.insert(6, 2));
+        assert!(a < b && !(b < a));
+    }
 
-    //Tests the functionality of the insert methods (which are unfinished).
     #[test]
-    fn insert_test_one() {
-        let b = BTree::new(1i, "abc".to_string(), 2);
-        let is_insert = b.insert(2i, "xyz".to_string());
-        assert!(is_insert.root.is_leaf());
+    fn test_show() {
+        let mut map: BTreeMap<int, int> = BTreeMap::new();
+        let empty: BTreeMap<int, int> = BTreeMap::new();
+
+        map.insert(1, 2);
+        map.insert(3, 4);
+
+        let map_str = format!("{}", map);
+
+        assert!(map_str == "{1: 2, 3: 4}".to_string());
+        assert_eq!(format!("{}", empty), "{}".to_string());
     }
 
     #[test]
-    fn insert_test_two() {
-        let leaf_elt_1 = LeafElt::new(1i, "aaa".to_string());
-        let leaf_elt_2 = LeafElt::new(2i, "bbb".to_string());
-        let leaf_elt_3 = LeafElt::new(3i, "ccc".to_string());
-        let n = Node::new_leaf(vec!(leaf_elt_1, leaf_elt_2, leaf_elt_3));
-        let b = BTree::new_with_node_len(n, 3, 2);
-        //println!("{}", b.clone().insert(4, "ddd".to_string()).to_string());
-        assert!(b.insert(4, "ddd".to_string()).root.is_leaf());
+    fn test_from_iter() {
+        let xs = [(1i, 1i), (2, 2), (3, 3), (4, 4), (5, 5), (6, 6)];
+
+        let map: BTreeMap<int, int> = xs.iter().map(|&x| x).collect();
+
+        for &(k, v) in xs.iter() {
+            assert_eq!(map.find(&k), Some(&v));
+        }
     }
 
     #[test]
-    fn insert_test_three() {
-        let leaf_elt_1 = LeafElt::new(1i, "aaa".to_string());
-        let leaf_elt_2 = LeafElt::new(2i, "bbb".to_string());
-        let leaf_elt_3 = LeafElt::new(3i, "ccc".to_string());
-        let leaf_elt_4 = LeafElt::new(4i, "ddd".to_string());
-        let n = Node::new_leaf(vec!(leaf_elt_1, leaf_elt_2, leaf_elt_3, leaf_elt_4));
-        let b = BTree::new_with_node_len(n, 3, 2);
-        //println!("{}", b.clone().insert(5, "eee".to_string()).to_string());
-        assert!(!b.insert(5, "eee".to_string()).root.is_leaf());
+    fn test_index() {
+        let mut map: BTreeMap<int, int> = BTreeMap::new();
+
+        map.insert(1, 2);
+        map.insert(2, 1);
+        map.insert(3, 4);
+
+        assert_eq!(map[2], 1);
+    }
+
+    #[test]
+    #[should_fail]
+    fn test_index_nonexistent() {
+        let mut map: BTreeMap<int, int> = BTreeMap::new();
+
+        map.insert(1, 2);
+        map.insert(2, 1);
+        map.insert(3, 4);
+
+        map[4];
+    }
+}
+
+#[cfg(test)]
+mod bench {
+    use std::prelude::*;
+    use std::rand::{weak_rng, Rng};
+    use test::{Bencher, black_box};
+
+    use super::BTreeMap;
+    use MutableMap;
+    use deque::bench::{insert_rand_n, insert_seq_n, find_rand_n, find_seq_n};
+
+    // Find seq
+    #[bench]
+    pub fn insert_rand_100(b: &mut Bencher) {
+        let mut m : BTreeMap<uint,uint> = BTreeMap::new();
+        insert_rand_n(100, &mut m, b);
+    }
+
+    #[bench]
+    pub fn insert_rand_10_000(b: &mut Bencher) {
+        let mut m : BTreeMap<uint,uint> = BTreeMap::new();
+        insert_rand_n(10_000, &mut m, b);
+    }
+
+    // Insert seq
+    #[bench]
+    pub fn insert_seq_100(b: &mut Bencher) {
+        let mut m : BTreeMap<uint,uint> = BTreeMap::new();
+        insert_seq_n(100, &mut m, b);
     }
 
+    #[bench]
+    pub fn insert_seq_10_000(b: &mut Bencher) {
+        let mut m : BTreeMap<uint,uint> = BTreeMap::new();
+        insert_seq_n(10_000, &mut m, b);
+    }
+
+    // Find rand
+    #[bench]
+    pub fn find_rand_100(b: &mut Bencher) {
+        let mut m : BTreeMap<uint,uint> = BTreeMap::new();
+        find_rand_n(100, &mut m, b);
+    }
+
+    #[bench]
+    pub fn find_rand_10_000(b: &mut Bencher) {
+        let mut m : BTreeMap<uint,uint> = BTreeMap::new();
+        find_rand_n(10_000, &mut m, b);
+    }
+
+    // Find seq
+    #[bench]
+    pub fn find_seq_100(b: &mut Bencher) {
+        let mut m : BTreeMap<uint,uint> = BTreeMap::new();
+        find_seq_n(100, &mut m, b);
+    }
+
+    #[bench]
+    pub fn find_seq_10_000(b: &mut Bencher) {
+        let mut m : BTreeMap<uint,uint> = BTreeMap::new();
+        find_seq_n(10_000, &mut m, b);
+    }
+
+    fn bench_iter(b: &mut Bencher, size: uint) {
+        let mut map = BTreeMap::<uint, uint>::new();
+        let mut rng = weak_rng();
+
+        for _ in range(0, size) {
+            map.swap(rng.gen(), rng.gen());
+        }
+
+        b.iter(|| {
+            for entry in map.iter() {
+                black_box(entry);
+            }
+        });
+    }
+
+    #[bench]
+    pub fn iter_20(b: &mut Bencher) {
+        bench_iter(b, 20);
+    }
+
+    #[bench]
+    pub fn iter_1000(b: &mut Bencher) {
+        bench_iter(b, 1000);
+    }
+
+    #[bench]
+    pub fn iter_100000(b: &mut Bencher) {
+        bench_iter(b, 100000);
+    }
+}
+
+#[cfg(test)]
+mod test_set {
+    use std::prelude::*;
+    use std::hash;
+
+    use {Set, MutableSet, Mutable};
+    use super::BTreeSet;
+
     #[test]
-    fn insert_test_four() {
-        let leaf_elt_1 = LeafElt::new(1i, "aaa".to_string());
-        let leaf_elt_2 = LeafElt::new(2i, "bbb".to_string());
-        let leaf_elt_3 = LeafElt::new(3i, "ccc".to_string());
-        let leaf_elt_4 = LeafElt::new(4i, "ddd".to_string());
-        let n = Node::new_leaf(vec!(leaf_elt_1, leaf_elt_2, leaf_elt_3, leaf_elt_4));
-        let mut b = BTree::new_with_node_len(n, 3, 2);
-        b = b.clone().insert(5, "eee".to_string());
-        b = b.clone().insert(6, "fff".to_string());
-        b = b.clone().insert(7, "ggg".to_string());
-        b = b.clone().insert(8, "hhh".to_string());
-        b = b.clone().insert(0, "omg".to_string());
-        //println!("{}", b.clone().to_string());
-        assert!(!b.root.is_leaf());
+    fn test_clear() {
+        let mut s = BTreeSet::new();
+        s.clear();
+        assert!(s.insert(5i));
+        assert!(s.insert(12));
+        assert!(s.insert(19));
+        s.clear();
+        assert!(!s.contains(&5));
+        assert!(!s.contains(&12));
+        assert!(!s.contains(&19));
+        assert!(s.is_empty());
     }
 
     #[test]
-    fn bsearch_test_one() {
-        let b = BTree::new(1i, "abc".to_string(), 2u);
-        assert_eq!(Some(1), b.root.bsearch_node(2));
+    fn test_disjoint() {
+        let mut xs = BTreeSet::new();
+        let mut ys = BTreeSet::new();
+        assert!(xs.is_disjoint(&ys));
+        assert!(ys.is_disjoint(&xs));
+        assert!(xs.insert(5i));
+        assert!(ys.insert(11i));
+        assert!(xs.is_disjoint(&ys));
+        assert!(ys.is_disjoint(&xs));
+        assert!(xs.insert(7));
+        assert!(xs.insert(19));
+        assert!(xs.insert(4));
+        assert!(ys.insert(2));
+        assert!(ys.insert(-11));
+        assert!(xs.is_disjoint(&ys));
+        assert!(ys.is_disjoint(&xs));
+        assert!(ys.insert(7));
+        assert!(!xs.is_disjoint(&ys));
+        assert!(!ys.is_disjoint(&xs));
     }
 
     #[test]
-    fn bsearch_test_two() {
-        let b = BTree::new(1i, "abc".to_string(), 2u);
-        assert_eq!(Some(0), b.root.bsearch_node(0));
+    fn test_subset_and_superset() {
+        let mut a = BTreeSet::new();
+        assert!(a.insert(0i));
+        assert!(a.insert(5));
+        assert!(a.insert(11));
+        assert!(a.insert(7));
+
+        let mut b = BTreeSet::new();
+        assert!(b.insert(0i));
+        assert!(b.insert(7));
+        assert!(b.insert(19));
+        assert!(b.insert(250));
+        assert!(b.insert(11));
+        assert!(b.insert(200));
+
+        assert!(!a.is_subset(&b));
+        assert!(!a.is_superset(&b));
+        assert!(!b.is_subset(&a));
+        assert!(!b.is_superset(&a));
+
+        assert!(b.insert(5));
+
+        assert!(a.is_subset(&b));
+        assert!(!a.is_superset(&b));
+        assert!(!b.is_subset(&a));
+        assert!(b.is_superset(&a));
     }
 
     #[test]
-    fn bsearch_test_three() {
-        let leaf_elt_1 = LeafElt::new(1i, "aaa".to_string());
-        let leaf_elt_2 = LeafElt::new(2i, "bbb".to_string());
-        let leaf_elt_3 = LeafElt::new(4i, "ccc".to_string());
-        let leaf_elt_4 = LeafElt::new(5i, "ddd".to_string());
-        let n = Node::new_leaf(vec!(leaf_elt_1, leaf_elt_2, leaf_elt_3, leaf_elt_4));
-        let b = BTree::new_with_node_len(n, 3, 2);
-        assert_eq!(Some(2), b.root.bsearch_node(3));
+    fn test_iterator() {
+        let mut m = BTreeSet::new();
+
+        assert!(m.insert(3i));
+        assert!(m.insert(0));
+        assert!(m.insert(4));
+        assert!(m.insert(2));
+        assert!(m.insert(1));
+
+        let mut n = 0;
+        for x in m.iter() {
+            assert_eq!(*x, n);
+            n += 1
+        }
+
+        let v: Vec<int> = m.rev_iter().map(|&x| x).collect();
+        assert_eq!(v, vec![4, 3, 2, 1, 0]);
     }
 
     #[test]
-    fn bsearch_test_four() {
-        let leaf_elt_1 = LeafElt::new(1i, "aaa".to_string());
-        let leaf_elt_2 = LeafElt::new(2i, "bbb".to_string());
-        let leaf_elt_3 = LeafElt::new(4i, "ccc".to_string());
-        let leaf_elt_4 = LeafElt::new(5i, "ddd".to_string());
-        let n = Node::new_leaf(vec!(leaf_elt_1, leaf_elt_2, leaf_elt_3, leaf_elt_4));
-        let b = BTree::new_with_node_len(n, 3, 2);
-        assert_eq!(Some(4), b.root.bsearch_node(800));
+    fn test_bounds_and_range() {
+        let set: BTreeSet<int> = range(0i, 100).map(|i| i * 3).collect();
+
+        assert_eq!(set.lower_bound(&30).next(), Some(&30));
+        assert_eq!(set.upper_bound(&30).next(), Some(&33));
+        assert_eq!(set.upper_bound(&297).next(), None);
+
+        let v: Vec<int> = set.range(&10, &20).map(|&x| x).collect();
+        assert_eq!(v, vec![12, 15, 18]);
+        assert!(set.range(&20, &10).next().is_none());
     }
 
-    //Tests the functionality of the get method.
     #[test]
-    fn get_test() {
-        let b = BTree::new(1i, "abc".to_string(), 2);
-        let val = b.get(1);
-        assert_eq!(val, Some("abc".to_string()));
+    fn test_move_iter() {
+        let s: BTreeSet<int> = range(0i, 5).collect();
+
+        let v: Vec<int> = s.move_iter().collect();
+        assert_eq!(v, vec![0, 1, 2, 3, 4]);
     }
 
-    //Tests the BTree's clone() method.
     #[test]
-    fn btree_clone_test() {
-        let b = BTree::new(1i, "abc".to_string(), 2);
-        let b2 = b.clone();
-        assert!(b.root == b2.root)
+    fn test_hash() {
+      let mut x = BTreeSet::new();
+      let mut y = BTreeSet::new();
+
+      x.insert(1i);
+      x.insert(2);
+      x.insert(3);
+
+      y.insert(3i);
+      y.insert(2);
+      y.insert(1);
+
+      assert!(hash::hash(&x) == hash::hash(&y));
+    }
+
+    fn check(a: &[int],
+             b: &[int],
+             expected: &[int],
+             f: |&BTreeSet<int>, &BTreeSet<int>, f: |&int| -> bool| -> bool) {
+        let mut set_a = BTreeSet::new();
+        let mut set_b = BTreeSet::new();
+
+        for x in a.iter() { assert!(set_a.insert(*x)) }
+        for y in b.iter() { assert!(set_b.insert(*y)) }
+
+        let mut i = 0;
+        f(&set_a, &set_b, |x| {
+            assert_eq!(*x, expected[i]);
+            i += 1;
+            true
+        });
+        assert_eq!(i, expected.len());
     }
 
-    //Tests the BTree's cmp() method when one node is "less than" another.
     #[test]
-    fn btree_cmp_test_less() {
-        let b = BTree::new(1i, "abc".to_string(), 2);
-        let b2 = BTree::new(2i, "bcd".to_string(), 2);
-        assert!(&b.cmp(&b2) == &Less)
+    fn test_intersection() {
+        fn check_intersection(a: &[int], b: &[int], expected: &[int]) {
+            check(a, b, expected, |x, y, f| x.intersection(y).all(f))
+        }
+
+        check_intersection([], [], []);
+        check_intersection([1, 2, 3], [], []);
+        check_intersection([], [1, 2, 3], []);
+        check_intersection([2], [1, 2, 3], [2]);
+        check_intersection([1, 2, 3], [2], [2]);
+        check_intersection([11, 1, 3, 77, 103, 5, -5],
+                           [2, 11, 77, -9, -42, 5, 3],
+                           [3, 5, 11, 77]);
     }
 
-    //Tests the BTree's cmp() method when two nodes are equal.
     #[test]
-    fn btree_cmp_test_eq() {
-        let b = BTree::new(1i, "abc".to_string(), 2);
-        let b2 = BTree::new(1i, "bcd".to_string(), 2);
-        assert!(&b.cmp(&b2) == &Equal)
+    fn test_difference() {
+        fn check_difference(a: &[int], b: &[int], expected: &[int]) {
+            check(a, b, expected, |x, y, f| x.difference(y).all(f))
+        }
+
+        check_difference([], [], []);
+        check_difference([1, 12], [], [1, 12]);
+        check_difference([], [1, 2, 3, 9], []);
+        check_difference([1, 3, 5, 9, 11],
+                         [3, 9],
+                         [1, 5, 11]);
+        check_difference([-5, 11, 22, 33, 40, 42],
+                         [-12, -5, 14, 23, 34, 38, 39, 50],
+                         [11, 22, 33, 40, 42]);
     }
 
-    //Tests the BTree's cmp() method when one node is "greater than" another.
     #[test]
-    fn btree_cmp_test_greater() {
-        let b = BTree::new(1i, "abc".to_string(), 2);
-        let b2 = BTree::new(2i, "bcd".to_string(), 2);
-        assert!(&b2.cmp(&b) == &Greater)
+    fn test_symmetric_difference() {
+        fn check_symmetric_difference(a: &[int], b: &[int],
+                                      expected: &[int]) {
+            check(a, b, expected, |x, y, f| x.symmetric_difference(y).all(f))
+        }
+
+        check_symmetric_difference([], [], []);
+        check_symmetric_difference([1, 2, 3], [2], [1, 3]);
+        check_symmetric_difference([2], [1, 2, 3], [1, 3]);
+        check_symmetric_difference([1, 3, 5, 9, 11],
+                                   [-2, 3, 9, 14, 22],
+                                   [-2, 1, 5, 11, 14, 22]);
     }
 
-    //Tests the BTree's to_string() method.
     #[test]
-    fn btree_tostr_test() {
-        let b = BTree::new(1i, "abc".to_string(), 2);
-        assert_eq!(b.to_string(), "Key: 1, value: abc;".to_string())
+    fn test_union() {
+        fn check_union(a: &[int], b: &[int],
+                                      expected: &[int]) {
+            check(a, b, expected, |x, y, f| x.union(y).all(f))
+        }
+
+        check_union([], [], []);
+        check_union([1, 2, 3], [2], [1, 2, 3]);
+        check_union([2], [1, 2, 3], [1, 2, 3]);
+        check_union([1, 3, 5, 9, 11, 16, 19, 24],
+                    [-2, 1, 5, 9, 13, 19],
+                    [-2, 1, 3, 5, 9, 11, 13, 16, 19, 24]);
     }
 
+    #[test]
+    fn test_show() {
+        let mut set: BTreeSet<int> = BTreeSet::new();
+        let empty: BTreeSet<int> = BTreeSet::new();
+
+        set.insert(1);
+        set.insert(2);
+
+        let set_str = format!("{}", set);
+
+        assert!(set_str == "{1, 2}".to_string());
+        assert_eq!(format!("{}", empty), "{}".to_string());
+    }
 }
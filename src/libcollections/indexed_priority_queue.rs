@@ -0,0 +1,548 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A priority queue whose elements can be reprioritized or removed.
+//!
+//! `IndexedPriorityQueue` is a 4-ary heap that hands back a `Handle` for
+//! every element pushed onto it. The handle can later be used to look at
+//! the element, replace it with one of a different priority or remove it,
+//! all in logarithmic time. The queue is a max-queue by default; one made
+//! with `new_min` keeps its least element on top instead.
+//!
+//! # Example
+//!
+//! Dijkstra's algorithm, lowering the priority of a queued node when a
+//! shorter path to it turns up instead of queueing it a second time.
+//!
+//! ```
+//! use std::collections::IndexedPriorityQueue;
+//! use std::uint;
+//!
+//! // Each node is an index into the adjacency list, and each edge a
+//! // `(node, cost)` pair.
+//! fn shortest_path(adj_list: &Vec<Vec<(uint, uint)>>, start: uint, goal: uint) -> uint {
+//!     let mut dist = Vec::from_elem(adj_list.len(), uint::MAX);
+//!     let mut handles = Vec::from_elem(adj_list.len(), None);
+//!     let mut pq = IndexedPriorityQueue::new_min();
+//!
+//!     *dist.get_mut(start) = 0u;
+//!     *handles.get_mut(start) = Some(pq.push((0u, start)));
+//!
+//!     loop {
+//!         let (cost, position) = match pq.pop() {
+//!             None => break,
+//!             Some(s) => s
+//!         };
+//!
+//!         if position == goal { return cost }
+//!
+//!         for &(node, edge_cost) in adj_list[position].iter() {
+//!             let next = cost + edge_cost;
+//!             if next < dist[node] {
+//!                 *dist.get_mut(node) = next;
+//!                 match handles[node] {
+//!                     Some(handle) if pq.contains(handle) => {
+//!                         pq.change_priority(handle, (next, node));
+//!                         continue
+//!                     }
+//!                     _ => {}
+//!                 }
+//!                 *handles.get_mut(node) = Some(pq.push((next, node)));
+//!             }
+//!         }
+//!     }
+//!
+//!     uint::MAX
+//! }
+//!
+//! fn main() {
+//!     let graph = vec![
+//!         vec![(2u, 10u), (1, 1)],
+//!         vec![(3, 2)],
+//!         vec![(1, 1), (3, 3), (4, 1)],
+//!         vec![(0, 7), (4, 2)],
+//!         vec![]];
+//!
+//!     assert_eq!(shortest_path(&graph, 0, 1), 1);
+//!     assert_eq!(shortest_path(&graph, 0, 3), 3);
+//!     assert_eq!(shortest_path(&graph, 3, 0), 7);
+//!     assert_eq!(shortest_path(&graph, 0, 4), 5);
+//!     assert_eq!(shortest_path(&graph, 4, 0), uint::MAX);
+//! }
+//! ```
+
+use core::prelude::*;
+
+use core::cmp;
+use core::default::Default;
+use core::mem::replace;
+
+use {Mutable, MutableSeq};
+use slice;
+use vec::Vec;
+
+// A wider heap is shallower, which makes the sift-ups done by `push` and
+// by lowering priorities in a min-queue cheaper.
+static ARITY: uint = 4;
+
+/// A reference to an element of an `IndexedPriorityQueue`.
+///
+/// A handle stays valid until its element is popped or removed. From then
+/// on, the queue ignores it, even after reusing its storage for a newer
+/// element.
+#[deriving(Clone, PartialEq, Eq, Hash, Show)]
+pub struct Handle {
+    slot: uint,
+    generation: uint,
+}
+
+#[deriving(Clone)]
+struct Slot {
+    generation: uint,
+    pos: Option<uint>,
+}
+
+#[deriving(Clone)]
+struct Entry<T> {
+    item: T,
+    slot: uint,
+}
+
+/// A priority queue with stable handles to its elements.
+#[deriving(Clone)]
+pub struct IndexedPriorityQueue<T> {
+    data: Vec<Entry<T>>,
+    slots: Vec<Slot>,
+    free: Vec<uint>,
+    min: bool,
+}
+
+impl<T: Ord> Collection for IndexedPriorityQueue<T> {
+    fn len(&self) -> uint { self.data.len() }
+}
+
+impl<T: Ord> Mutable for IndexedPriorityQueue<T> {
+    /// Remove all elements, invalidating every handle.
+    fn clear(&mut self) {
+        for i in range(0, self.data.len()) {
+            let slot = self.data[i].slot;
+            self.release(slot);
+        }
+        self.data.truncate(0)
+    }
+}
+
+impl<T: Ord> Default for IndexedPriorityQueue<T> {
+    #[inline]
+    fn default() -> IndexedPriorityQueue<T> { IndexedPriorityQueue::new() }
+}
+
+impl<T: Ord> IndexedPriorityQueue<T> {
+    /// Create an empty queue whose top element is its greatest.
+    pub fn new() -> IndexedPriorityQueue<T> {
+        IndexedPriorityQueue { data: vec![], slots: vec![], free: vec![], min: false }
+    }
+
+    /// Create an empty queue whose top element is its least.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::IndexedPriorityQueue;
+    ///
+    /// let mut pq = IndexedPriorityQueue::new_min();
+    /// pq.push(5i);
+    /// pq.push(2);
+    /// pq.push(8);
+    /// assert_eq!(pq.pop(), Some(2));
+    /// ```
+    pub fn new_min() -> IndexedPriorityQueue<T> {
+        IndexedPriorityQueue { data: vec![], slots: vec![], free: vec![], min: true }
+    }
+
+    /// Return true if the top element of the queue is its least rather
+    /// than its greatest.
+    pub fn is_min(&self) -> bool { self.min }
+
+    /// An iterator visiting all elements in arbitrary order.
+    pub fn iter<'a>(&'a self) -> Items<'a, T> {
+        Items { iter: self.data.iter() }
+    }
+
+    /// Return the top element of the queue, or `None` if it is empty.
+    pub fn top<'a>(&'a self) -> Option<&'a T> {
+        if self.is_empty() { None } else { Some(&self.data[0].item) }
+    }
+
+    /// Return the handle of the top element of the queue, or `None` if it
+    /// is empty.
+    pub fn top_handle(&self) -> Option<Handle> {
+        if self.is_empty() { None } else { Some(self.handle(self.data[0].slot)) }
+    }
+
+    /// Return the element referred to by `handle`, or `None` if it is no
+    /// longer in the queue.
+    pub fn get<'a>(&'a self, handle: Handle) -> Option<&'a T> {
+        match self.position(handle) {
+            None => None,
+            Some(pos) => Some(&self.data[pos].item),
+        }
+    }
+
+    /// Return true if the element referred to by `handle` is still in the
+    /// queue.
+    pub fn contains(&self, handle: Handle) -> bool {
+        self.position(handle).is_some()
+    }
+
+    /// Push an item onto the queue, returning a handle to it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::IndexedPriorityQueue;
+    ///
+    /// let mut pq = IndexedPriorityQueue::new();
+    /// let h = pq.push(3i);
+    /// pq.push(7);
+    /// assert_eq!(pq.get(h), Some(&3));
+    /// assert_eq!(pq.top(), Some(&7));
+    /// ```
+    pub fn push(&mut self, item: T) -> Handle {
+        let slot = match self.free.pop() {
+            Some(slot) => slot,
+            None => {
+                self.slots.push(Slot { generation: 0, pos: None });
+                self.slots.len() - 1
+            }
+        };
+        let pos = self.data.len();
+        self.slots.get_mut(slot).pos = Some(pos);
+        self.data.push(Entry { item: item, slot: slot });
+        self.siftup(pos);
+        self.handle(slot)
+    }
+
+    /// Remove the top element from the queue and return it, or `None` if
+    /// the queue is empty.
+    pub fn pop(&mut self) -> Option<T> {
+        if self.is_empty() { None } else { Some(self.remove_at(0)) }
+    }
+
+    /// Replace the element referred to by `handle` with `item`, moving it
+    /// to its new place in the queue, and return the old element. Return
+    /// `None` and leave the queue untouched if the handle is no longer
+    /// valid. The handle keeps referring to the new element.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::IndexedPriorityQueue;
+    ///
+    /// let mut pq = IndexedPriorityQueue::new_min();
+    /// let a = pq.push(("a", 10i));
+    /// pq.push(("b", 5));
+    ///
+    /// assert_eq!(pq.change_priority(a, ("a", 1)), Some(("a", 10)));
+    /// assert_eq!(pq.top(), Some(&("a", 1)));
+    /// assert_eq!(pq.top_handle(), Some(a));
+    /// ```
+    pub fn change_priority(&mut self, handle: Handle, item: T) -> Option<T> {
+        match self.position(handle) {
+            None => None,
+            Some(pos) => {
+                let old = replace(&mut self.data.get_mut(pos).item, item);
+                let pos = self.siftup(pos);
+                self.siftdown(pos);
+                Some(old)
+            }
+        }
+    }
+
+    /// Remove the element referred to by `handle` from the queue and return
+    /// it, or `None` if it is no longer in the queue.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::IndexedPriorityQueue;
+    ///
+    /// let mut pq = IndexedPriorityQueue::new();
+    /// pq.push(1i);
+    /// let h = pq.push(9);
+    ///
+    /// assert_eq!(pq.remove(h), Some(9));
+    /// assert_eq!(pq.remove(h), None);
+    /// assert_eq!(pq.top(), Some(&1));
+    /// ```
+    pub fn remove(&mut self, handle: Handle) -> Option<T> {
+        match self.position(handle) {
+            None => None,
+            Some(pos) => Some(self.remove_at(pos)),
+        }
+    }
+
+    fn handle(&self, slot: uint) -> Handle {
+        Handle { slot: slot, generation: self.slots[slot].generation }
+    }
+
+    fn position(&self, handle: Handle) -> Option<uint> {
+        if handle.slot < self.slots.len() {
+            let slot = &self.slots[handle.slot];
+            if slot.generation == handle.generation {
+                return slot.pos;
+            }
+        }
+        None
+    }
+
+    // Invalidate all handles to `slot` and make it available for reuse.
+    fn release(&mut self, slot: uint) {
+        {
+            let slot = self.slots.get_mut(slot);
+            slot.generation += 1;
+            slot.pos = None;
+        }
+        self.free.push(slot);
+    }
+
+    fn remove_at(&mut self, pos: uint) -> T {
+        let last = self.data.len() - 1;
+        self.swap(pos, last);
+        let entry = self.data.pop().unwrap();
+        self.release(entry.slot);
+        if pos < last {
+            let pos = self.siftup(pos);
+            self.siftdown(pos);
+        }
+        entry.item
+    }
+
+    // Whether the element at `a` belongs closer to the top than the one at `b`.
+    #[inline]
+    fn above(&self, a: uint, b: uint) -> bool {
+        let (x, y) = (&self.data[a].item, &self.data[b].item);
+        if self.min { *x < *y } else { *x > *y }
+    }
+
+    #[inline]
+    fn swap(&mut self, a: uint, b: uint) {
+        self.data.as_mut_slice().swap(a, b);
+        let slot = self.data[a].slot;
+        self.slots.get_mut(slot).pos = Some(a);
+        let slot = self.data[b].slot;
+        self.slots.get_mut(slot).pos = Some(b);
+    }
+
+    fn siftup(&mut self, mut pos: uint) -> uint {
+        while pos > 0 {
+            let parent = (pos - 1) / ARITY;
+            if !self.above(pos, parent) { break }
+            self.swap(pos, parent);
+            pos = parent;
+        }
+        pos
+    }
+
+    fn siftdown(&mut self, mut pos: uint) {
+        let len = self.data.len();
+        loop {
+            let first = pos * ARITY + 1;
+            if first >= len { break }
+            let mut best = first;
+            for child in range(first + 1, cmp::min(first + ARITY, len)) {
+                if self.above(child, best) { best = child }
+            }
+            if !self.above(best, pos) { break }
+            self.swap(pos, best);
+            pos = best;
+        }
+    }
+}
+
+/// IndexedPriorityQueue iterator
+pub struct Items<'a, T: 'a> {
+    iter: slice::Items<'a, Entry<T>>,
+}
+
+impl<'a, T> Iterator<&'a T> for Items<'a, T> {
+    #[inline]
+    fn next(&mut self) -> Option<&'a T> {
+        match self.iter.next() {
+            None => None,
+            Some(entry) => Some(&entry.item),
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (uint, Option<uint>) { self.iter.size_hint() }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::prelude::*;
+    use std::rand::Rng;
+    use std::rand;
+
+    use super::{IndexedPriorityQueue, ARITY};
+    use vec::Vec;
+    use {Mutable, MutableSeq};
+
+    fn check_heap<T: Ord>(pq: &IndexedPriorityQueue<T>) {
+        for pos in range(1, pq.data.len()) {
+            assert!(!pq.above(pos, (pos - 1) / ARITY));
+        }
+        for (pos, entry) in pq.data.iter().enumerate() {
+            assert_eq!(pq.slots[entry.slot].pos, Some(pos));
+        }
+        assert_eq!(pq.data.len() + pq.free.len(), pq.slots.len());
+    }
+
+    #[test]
+    fn test_push_pop() {
+        let data = vec!(2u, 4, 6, 2, 1, 8, 10, 3, 5, 7, 0, 9, 1);
+        let mut sorted = data.clone();
+        sorted.sort();
+
+        let mut max = IndexedPriorityQueue::new();
+        let mut min = IndexedPriorityQueue::new_min();
+        for &x in data.iter() {
+            max.push(x);
+            min.push(x);
+        }
+        assert_eq!(max.len(), data.len());
+        check_heap(&max);
+        check_heap(&min);
+
+        for &x in sorted.iter() {
+            assert_eq!(min.top(), Some(&x));
+            assert_eq!(min.pop(), Some(x));
+        }
+        while !max.is_empty() {
+            assert_eq!(max.top(), sorted.last());
+            assert_eq!(max.pop(), sorted.pop());
+        }
+        assert!(min.pop().is_none());
+        assert!(max.top().is_none());
+        assert!(max.top_handle().is_none());
+    }
+
+    #[test]
+    fn test_change_priority() {
+        let mut pq = IndexedPriorityQueue::new();
+        let handles: Vec<_> = range(0i, 20).map(|i| pq.push(i * 10)).collect();
+
+        assert_eq!(pq.change_priority(handles[3], 1000), Some(30));
+        check_heap(&pq);
+        assert_eq!(pq.top(), Some(&1000));
+        assert_eq!(pq.top_handle(), Some(handles[3]));
+
+        assert_eq!(pq.change_priority(handles[3], -5), Some(1000));
+        assert_eq!(pq.change_priority(handles[19], 95), Some(190));
+        check_heap(&pq);
+        assert_eq!(pq.get(handles[3]), Some(&-5));
+        assert_eq!(pq.top(), Some(&180));
+
+        let mut popped = vec![];
+        while !pq.is_empty() {
+            popped.push(pq.pop().unwrap());
+            check_heap(&pq);
+        }
+        assert_eq!(popped.len(), 20);
+        assert_eq!(*popped.last().unwrap(), -5);
+        assert!(popped.as_slice().windows(2).all(|w| w[0] >= w[1]));
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut pq = IndexedPriorityQueue::new_min();
+        let handles: Vec<_> = range(0i, 10).map(|i| pq.push(i)).collect();
+
+        assert_eq!(pq.remove(handles[0]), Some(0));
+        assert_eq!(pq.remove(handles[5]), Some(5));
+        assert_eq!(pq.remove(handles[9]), Some(9));
+        assert_eq!(pq.remove(handles[5]), None);
+        check_heap(&pq);
+        assert_eq!(pq.len(), 7);
+
+        let rest: Vec<int> = range(0u, 7).map(|_| pq.pop().unwrap()).collect();
+        assert_eq!(rest, vec![1, 2, 3, 4, 6, 7, 8]);
+    }
+
+    #[test]
+    fn test_stale_handles() {
+        let mut pq = IndexedPriorityQueue::new();
+        let a = pq.push(1i);
+        assert_eq!(pq.pop(), Some(1));
+        assert!(!pq.contains(a));
+
+        // The new element reuses the storage of the popped one.
+        let b = pq.push(2);
+        assert!(a != b);
+        assert!(pq.contains(b));
+        assert_eq!(pq.get(a), None);
+        assert_eq!(pq.change_priority(a, 5), None);
+        assert_eq!(pq.remove(a), None);
+        assert_eq!(pq.get(b), Some(&2));
+
+        pq.clear();
+        assert!(pq.is_empty());
+        assert!(!pq.contains(b));
+        let c = pq.push(3);
+        assert!(!pq.contains(b));
+        assert_eq!(pq.get(c), Some(&3));
+        check_heap(&pq);
+    }
+
+    #[test]
+    fn test_iter() {
+        let mut pq = IndexedPriorityQueue::new();
+        for i in range(0i, 10) {
+            pq.push(i);
+        }
+        assert_eq!(pq.iter().size_hint(), (10, Some(10)));
+        let mut v: Vec<int> = pq.iter().map(|&x| x).collect();
+        v.sort();
+        assert_eq!(v, range(0i, 10).collect::<Vec<int>>());
+    }
+
+    #[test]
+    fn test_rand() {
+        let seed: &[_] = &[42];
+        let mut rng: rand::IsaacRng = rand::SeedableRng::from_seed(seed);
+        let mut pq = IndexedPriorityQueue::new_min();
+        let mut ctrl: Vec<(super::Handle, int)> = vec![];
+
+        for _ in range(0u, 2000) {
+            match rng.gen_range(0u, 4) {
+                0 | 1 => {
+                    let x = rng.gen_range(0i, 1000);
+                    ctrl.push((pq.push(x), x));
+                }
+                2 if !ctrl.is_empty() => {
+                    let i = rng.gen_range(0, ctrl.len());
+                    let x = rng.gen_range(0i, 1000);
+                    let (h, old) = ctrl[i];
+                    assert_eq!(pq.change_priority(h, x), Some(old));
+                    *ctrl.get_mut(i) = (h, x);
+                }
+                3 if !ctrl.is_empty() => {
+                    let i = rng.gen_range(0, ctrl.len());
+                    let (h, x) = ctrl.swap_remove(i).unwrap();
+                    assert_eq!(pq.remove(h), Some(x));
+                }
+                _ => {}
+            }
+            check_heap(&pq);
+            let min = ctrl.iter().map(|&(_, x)| x).min();
+            assert_eq!(pq.top().map(|&x| x), min);
+        }
+    }
+}
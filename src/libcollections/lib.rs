@@ -37,10 +37,12 @@ extern crate alloc;
 use core::prelude::Option;
 
 pub use bitv::{Bitv, BitvSet};
-pub use btree::BTree;
+pub use btree::{BTreeMap, BTreeSet};
 pub use core::prelude::Collection;
 pub use dlist::DList;
 pub use enum_set::EnumSet;
+pub use indexed_priority_queue::IndexedPriorityQueue;
+pub use pairing_heap::PairingHeap;
 pub use priority_queue::PriorityQueue;
 pub use ringbuf::RingBuf;
 pub use smallintmap::SmallIntMap;
@@ -55,6 +57,8 @@ pub mod bitv;
 pub mod btree;
 pub mod dlist;
 pub mod enum_set;
+pub mod indexed_priority_queue;
+pub mod pairing_heap;
 pub mod priority_queue;
 pub mod ringbuf;
 pub mod smallintmap;
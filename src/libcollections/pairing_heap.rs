@@ -0,0 +1,374 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A priority queue implemented with a pairing heap.
+//!
+//! A `PairingHeap` pushes elements and merges whole heaps in constant
+//! time, and pops in amortized logarithmic time. It is a max-heap by
+//! default; one made with `new_min` keeps its least element on top.
+//!
+//! # Example
+//!
+//! ```
+//! use std::collections::PairingHeap;
+//!
+//! let mut events = PairingHeap::new_min();
+//! events.push((3u, "tick"));
+//! events.push((1u, "start"));
+//!
+//! let mut later = PairingHeap::new_min();
+//! later.push((7u, "stop"));
+//! later.push((2u, "tock"));
+//! events.merge(later);
+//!
+//! let order: Vec<&str> = range(0u, 4).map(|_| events.pop().unwrap().val1()).collect();
+//! assert_eq!(order, vec!["start", "tock", "tick", "stop"]);
+//! ```
+
+use core::prelude::*;
+
+use alloc::boxed::Box;
+use core::default::Default;
+use core::mem::swap;
+
+use {Mutable, MutableSeq};
+use vec::Vec;
+
+struct Node<T> {
+    item: T,
+    children: Vec<Box<Node<T>>>,
+}
+
+/// A pairing heap.
+pub struct PairingHeap<T> {
+    root: Option<Box<Node<T>>>,
+    len: uint,
+    min: bool,
+}
+
+impl<T: Ord> Collection for PairingHeap<T> {
+    fn len(&self) -> uint { self.len }
+}
+
+impl<T: Ord> Mutable for PairingHeap<T> {
+    fn clear(&mut self) {
+        let root = self.root.take();
+        destroy(root);
+        self.len = 0;
+    }
+}
+
+impl<T: Ord> Default for PairingHeap<T> {
+    #[inline]
+    fn default() -> PairingHeap<T> { PairingHeap::new() }
+}
+
+impl<T: Ord> PairingHeap<T> {
+    /// Create an empty heap whose top element is its greatest.
+    pub fn new() -> PairingHeap<T> {
+        PairingHeap { root: None, len: 0, min: false }
+    }
+
+    /// Create an empty heap whose top element is its least.
+    pub fn new_min() -> PairingHeap<T> {
+        PairingHeap { root: None, len: 0, min: true }
+    }
+
+    /// Return true if the top element of the heap is its least rather
+    /// than its greatest.
+    pub fn is_min(&self) -> bool { self.min }
+
+    /// An iterator visiting all elements in arbitrary order.
+    pub fn iter<'a>(&'a self) -> Items<'a, T> {
+        let mut stack = Vec::new();
+        match self.root {
+            Some(ref root) => stack.push(&**root),
+            None => {}
+        }
+        Items { stack: stack, remaining: self.len }
+    }
+
+    /// Return the top element of the heap, or `None` if it is empty.
+    pub fn top<'a>(&'a self) -> Option<&'a T> {
+        match self.root {
+            Some(ref root) => Some(&root.item),
+            None => None,
+        }
+    }
+
+    /// Push an item onto the heap.
+    pub fn push(&mut self, item: T) {
+        let node = box Node { item: item, children: Vec::new() };
+        self.root = Some(match self.root.take() {
+            Some(root) => self.meld(root, node),
+            None => node,
+        });
+        self.len += 1;
+    }
+
+    /// Remove the top element from the heap and return it, or `None` if
+    /// the heap is empty.
+    pub fn pop(&mut self) -> Option<T> {
+        match self.root.take() {
+            None => None,
+            Some(root) => {
+                let box root = root;
+                let Node { item: item, children: children } = root;
+                self.root = self.combine(children);
+                self.len -= 1;
+                Some(item)
+            }
+        }
+    }
+
+    /// Move all elements of `other` into this heap in constant time.
+    ///
+    /// # Failure
+    ///
+    /// Fails if one heap is a min-heap and the other is a max-heap.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::PairingHeap;
+    ///
+    /// let mut a = PairingHeap::new();
+    /// a.push(1i);
+    /// a.push(5);
+    ///
+    /// let mut b = PairingHeap::new();
+    /// b.push(3i);
+    /// b.push(8);
+    ///
+    /// a.merge(b);
+    /// assert_eq!(a.len(), 4);
+    /// assert_eq!(a.pop(), Some(8));
+    /// assert_eq!(a.pop(), Some(5));
+    /// ```
+    pub fn merge(&mut self, mut other: PairingHeap<T>) {
+        assert!(self.min == other.min, "cannot merge a min-heap with a max-heap");
+        self.root = match (self.root.take(), other.root.take()) {
+            (Some(a), Some(b)) => Some(self.meld(a, b)),
+            (a, None) => a,
+            (None, b) => b,
+        };
+        self.len += other.len;
+        other.len = 0;
+    }
+
+    // Make the tree whose root belongs further down a child of the other.
+    fn meld(&self, mut a: Box<Node<T>>, mut b: Box<Node<T>>) -> Box<Node<T>> {
+        let below = if self.min { a.item > b.item } else { a.item < b.item };
+        if below {
+            swap(&mut a, &mut b);
+        }
+        a.children.push(b);
+        a
+    }
+
+    // Meld the subtrees left behind by a popped root back into one tree:
+    // first in pairs from left to right, then the pairs from right to
+    // left into a single tree.
+    fn combine(&self, trees: Vec<Box<Node<T>>>) -> Option<Box<Node<T>>> {
+        let mut pairs = Vec::with_capacity(trees.len() / 2 + 1);
+        let mut iter = trees.move_iter();
+        loop {
+            match (iter.next(), iter.next()) {
+                (Some(a), Some(b)) => pairs.push(self.meld(a, b)),
+                (Some(a), None) => pairs.push(a),
+                (None, _) => break,
+            }
+        }
+
+        let mut root = match pairs.pop() {
+            None => return None,
+            Some(tree) => tree,
+        };
+        loop {
+            match pairs.pop() {
+                None => return Some(root),
+                Some(tree) => root = self.meld(tree, root),
+            }
+        }
+    }
+}
+
+// Free a tree without recursing, as it can be as deep as it is large.
+fn destroy<T>(root: Option<Box<Node<T>>>) {
+    let mut stack = Vec::new();
+    match root {
+        Some(root) => stack.push(root),
+        None => {}
+    }
+    loop {
+        match stack.pop() {
+            None => break,
+            Some(node) => {
+                let box node = node;
+                let Node { item: _, children: children } = node;
+                stack.push_all_move(children);
+            }
+        }
+    }
+}
+
+#[unsafe_destructor]
+impl<T> Drop for PairingHeap<T> {
+    fn drop(&mut self) {
+        let root = self.root.take();
+        destroy(root);
+    }
+}
+
+/// PairingHeap iterator
+pub struct Items<'a, T: 'a> {
+    stack: Vec<&'a Node<T>>,
+    remaining: uint,
+}
+
+impl<'a, T> Iterator<&'a T> for Items<'a, T> {
+    #[inline]
+    fn next(&mut self) -> Option<&'a T> {
+        match self.stack.pop() {
+            None => None,
+            Some(node) => {
+                for child in node.children.iter() {
+                    self.stack.push(&**child);
+                }
+                self.remaining -= 1;
+                Some(&node.item)
+            }
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (uint, Option<uint>) { (self.remaining, Some(self.remaining)) }
+}
+
+impl<T: Ord> FromIterator<T> for PairingHeap<T> {
+    fn from_iter<Iter: Iterator<T>>(iter: Iter) -> PairingHeap<T> {
+        let mut heap = PairingHeap::new();
+        heap.extend(iter);
+        heap
+    }
+}
+
+impl<T: Ord> Extendable<T> for PairingHeap<T> {
+    fn extend<Iter: Iterator<T>>(&mut self, mut iter: Iter) {
+        for elem in iter {
+            self.push(elem);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::prelude::*;
+    use std::rand::Rng;
+    use std::rand;
+
+    use pairing_heap::PairingHeap;
+    use vec::Vec;
+    use {Mutable, MutableSeq};
+
+    #[test]
+    fn test_top_and_pop() {
+        let data = vec!(2u, 4, 6, 2, 1, 8, 10, 3, 5, 7, 0, 9, 1);
+        let mut sorted = data.clone();
+        sorted.sort();
+        let mut heap: PairingHeap<uint> = data.iter().map(|&x| x).collect();
+        assert_eq!(heap.len(), data.len());
+        while !heap.is_empty() {
+            assert_eq!(heap.top(), sorted.last());
+            assert_eq!(heap.pop(), sorted.pop());
+        }
+        assert!(heap.top().is_none());
+        assert!(heap.pop().is_none());
+    }
+
+    #[test]
+    fn test_min() {
+        let mut heap = PairingHeap::new_min();
+        assert!(heap.is_min());
+        for &x in [5i, 1, 4, 2, 3].iter() {
+            heap.push(x);
+        }
+        let popped: Vec<int> = range(0u, 5).map(|_| heap.pop().unwrap()).collect();
+        assert_eq!(popped, vec![1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut a: PairingHeap<int> = range(0i, 10).map(|i| i * 2).collect();
+        let b: PairingHeap<int> = range(0i, 10).map(|i| i * 2 + 1).collect();
+        a.merge(b);
+        a.merge(PairingHeap::new());
+        let mut empty = PairingHeap::new();
+        empty.merge(a);
+        assert_eq!(empty.len(), 20);
+        for i in range(0i, 20).rev() {
+            assert_eq!(empty.pop(), Some(i));
+        }
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    #[should_fail]
+    fn test_merge_mismatched_order() {
+        let mut a: PairingHeap<int> = PairingHeap::new();
+        a.merge(PairingHeap::new_min());
+    }
+
+    #[test]
+    fn test_iter() {
+        let mut heap: PairingHeap<int> = range(0i, 10).collect();
+        heap.pop();
+        assert_eq!(heap.iter().size_hint(), (9, Some(9)));
+        let mut v: Vec<int> = heap.iter().map(|&x| x).collect();
+        v.sort();
+        assert_eq!(v, range(0i, 9).collect::<Vec<int>>());
+    }
+
+    #[test]
+    fn test_clear_and_drop_deep() {
+        // Pushing in increasing order builds a chain as deep as the heap is large.
+        let mut heap = PairingHeap::new();
+        for i in range(0u, 100000) {
+            heap.push(box i);
+        }
+        heap.clear();
+        assert!(heap.is_empty());
+        assert!(heap.top().is_none());
+
+        for i in range(0u, 100000) {
+            heap.push(box i);
+        }
+    }
+
+    #[test]
+    fn test_rand() {
+        let seed: &[_] = &[42];
+        let mut rng: rand::IsaacRng = rand::SeedableRng::from_seed(seed);
+        let mut heap = PairingHeap::new_min();
+        let mut ctrl = vec![];
+
+        for _ in range(0u, 3000) {
+            if rng.gen_weighted_bool(3) && !ctrl.is_empty() {
+                ctrl.sort_by(|a: &int, b| b.cmp(a));
+                assert_eq!(heap.pop(), ctrl.pop());
+            } else {
+                let x = rng.gen_range(0i, 500);
+                heap.push(x);
+                ctrl.push(x);
+            }
+            assert_eq!(heap.len(), ctrl.len());
+        }
+    }
+}
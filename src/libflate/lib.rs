@@ -13,6 +13,14 @@
 Simple [DEFLATE][def]-based compression. This is a wrapper around the
 [`miniz`][mz] library, which is a one-file pure-C implementation of zlib.
 
+Whole buffers can be compressed and decompressed in one go with
+`deflate_bytes` and `inflate_bytes`. For data that does not fit in memory,
+`DeflateWriter` and `InflateReader` compress and decompress incrementally
+through the `Writer` and `Reader` traits, and `GzipWriter` and `GzipReader`
+do the same for the [gzip][gz] file format.
+
+[gz]: http://tools.ietf.org/html/rfc1952
+
 [def]: https://en.wikipedia.org/wiki/DEFLATE
 [mz]: https://code.google.com/p/miniz/
 
@@ -26,14 +34,20 @@ Simple [DEFLATE][def]-based compression. This is a wrapper around the
 #![doc(html_logo_url = "http://www.rust-lang.org/logos/rust-logo-128x128-blk-v2.png",
        html_favicon_url = "http://www.rust-lang.org/favicon.ico",
        html_root_url = "http://doc.rust-lang.org/master/")]
-#![feature(phase)]
+#![feature(phase, unsafe_destructor)]
 
 #[cfg(test)] #[phase(plugin, link)] extern crate log;
 
 extern crate libc;
 
 use std::c_vec::CVec;
-use libc::{c_void, size_t, c_int};
+use libc::{c_void, size_t, c_int, c_ulong};
+
+pub use stream::{DeflateWriter, InflateReader, FlushMode, NoFlush, SyncFlush, FullFlush};
+pub use stream::{GzipWriter, GzipReader, GzipHeader};
+pub use stream::{BEST_SPEED, BEST_COMPRESSION, DEFAULT_COMPRESSION, NO_COMPRESSION};
+
+mod stream;
 
 #[link(name = "miniz", kind = "static")]
 extern {
@@ -50,6 +64,34 @@ extern {
                                     pout_len: *mut size_t,
                                     flags: c_int)
                                     -> *mut c_void;
+
+    /// Incremental compression into a caller-provided buffer.
+    fn tdefl_compress(d: *mut c_void,
+                      pin_buf: *const c_void,
+                      pin_buf_size: *mut size_t,
+                      pout_buf: *mut c_void,
+                      pout_buf_size: *mut size_t,
+                      flush: c_int)
+                      -> c_int;
+
+    /// Incremental decompression into a caller-provided buffer.
+    fn tinfl_decompress(r: *mut c_void,
+                        pin_buf_next: *const u8,
+                        pin_buf_size: *mut size_t,
+                        pout_buf_start: *mut u8,
+                        pout_buf_next: *mut u8,
+                        pout_buf_size: *mut size_t,
+                        decomp_flags: u32)
+                        -> c_int;
+
+    fn mz_crc32(crc: c_ulong, ptr: *const u8, buf_len: size_t) -> c_ulong;
+
+    // Defined in rust_miniz.c
+    fn rust_miniz_tdefl_new(flags: c_int) -> *mut c_void;
+    fn rust_miniz_tdefl_free(d: *mut c_void);
+    fn rust_miniz_tinfl_new() -> *mut c_void;
+    fn rust_miniz_tinfl_free(r: *mut c_void);
+    fn rust_miniz_tinfl_unused_bytes(r: *const c_void) -> size_t;
 }
 
 static LZ_NORM : c_int = 0x80;  // LZ with 128 probes, "normal"
@@ -108,10 +150,61 @@ pub fn inflate_bytes_zlib(bytes: &[u8]) -> Option<CVec<u8>> {
 
 #[cfg(test)]
 mod tests {
-    use super::{inflate_bytes, deflate_bytes};
+    use super::{inflate_bytes, deflate_bytes, inflate_bytes_zlib, deflate_bytes_zlib};
+    use super::{DeflateWriter, InflateReader, GzipWriter, GzipReader, GzipHeader};
+    use super::{SyncFlush, BEST_SPEED, BEST_COMPRESSION, DEFAULT_COMPRESSION, NO_COMPRESSION};
+    use std::io::{MemReader, MemWriter, InvalidInput};
     use std::rand;
     use std::rand::Rng;
 
+    fn random_words(len: uint) -> Vec<u8> {
+        let mut r = rand::task_rng();
+        let mut words = vec!();
+        for _ in range(0u, 20) {
+            let range = r.gen_range(1u, 10);
+            words.push(r.gen_iter::<u8>().take(range).collect::<Vec<u8>>());
+        }
+        let mut input = vec![];
+        while input.len() < len {
+            input.push_all(r.choose(words.as_slice()).unwrap().as_slice());
+        }
+        input.truncate(len);
+        input
+    }
+
+    // Writes `input` in randomly sized pieces.
+    fn write_in_pieces<W: Writer>(w: &mut W, input: &[u8]) {
+        let mut r = rand::task_rng();
+        let mut pos = 0;
+        while pos < input.len() {
+            let end = ::std::cmp::min(input.len(), pos + r.gen_range(1u, 5000));
+            w.write(input.slice(pos, end)).unwrap();
+            pos = end;
+        }
+    }
+
+    // Reads everything through a small buffer.
+    fn read_in_pieces<R: Reader>(r: &mut R) -> Vec<u8> {
+        let mut out = vec![];
+        let mut buf = [0u8, ..1000];
+        loop {
+            match r.read(buf) {
+                Ok(n) => out.push_all(buf.slice_to(n)),
+                Err(e) => { assert_eq!(e.kind, ::std::io::EndOfFile); return out }
+            }
+        }
+    }
+
+    // A reader which hands out at most one byte at a time.
+    struct Trickle(MemReader);
+
+    impl Reader for Trickle {
+        fn read(&mut self, buf: &mut [u8]) -> ::std::io::IoResult<uint> {
+            let Trickle(ref mut r) = *self;
+            r.read(buf.mut_slice_to(1))
+        }
+    }
+
     #[test]
     fn test_flate_round_trip() {
         let mut r = rand::task_rng();
@@ -144,4 +237,144 @@ mod tests {
         let inflated = inflate_bytes(deflated.as_slice()).expect("inflation failed");
         assert_eq!(inflated.as_slice(), bytes.as_slice());
     }
+
+    #[test]
+    fn test_stream_round_trip() {
+        let input = random_words(200000);
+        for &level in [NO_COMPRESSION, BEST_SPEED, DEFAULT_COMPRESSION,
+                       BEST_COMPRESSION].iter() {
+            let mut w = DeflateWriter::new(MemWriter::new(), level);
+            write_in_pieces(&mut w, input.as_slice());
+            let cmp = w.finish().unwrap().unwrap();
+            debug!("level {}: {} bytes deflated to {}", level, input.len(), cmp.len());
+
+            let out = inflate_bytes(cmp.as_slice()).expect("inflation failed");
+            assert!(out.as_slice() == input.as_slice());
+
+            let mut r = InflateReader::new(MemReader::new(cmp));
+            assert!(read_in_pieces(&mut r).as_slice() == input.as_slice());
+        }
+    }
+
+    #[test]
+    fn test_stream_zlib() {
+        let input = random_words(50000);
+        let mut w = DeflateWriter::new_zlib(MemWriter::new(), DEFAULT_COMPRESSION);
+        write_in_pieces(&mut w, input.as_slice());
+        let cmp = w.finish().unwrap().unwrap();
+        let out = inflate_bytes_zlib(cmp.as_slice()).expect("inflation failed");
+        assert!(out.as_slice() == input.as_slice());
+
+        let cmp = deflate_bytes_zlib(input.as_slice()).expect("deflation failed");
+        let trickle = Trickle(MemReader::new(Vec::from_slice(cmp.as_slice())));
+        let mut r = InflateReader::new_zlib(trickle);
+        assert!(r.read_to_end().unwrap().as_slice() == input.as_slice());
+
+        let mut bad = Vec::from_slice(cmp.as_slice());
+        let last = bad.len() - 1;
+        *bad.get_mut(last) ^= 1;
+        let mut r = InflateReader::new_zlib(MemReader::new(bad));
+        assert_eq!(r.read_to_end().unwrap_err().kind, InvalidInput);
+    }
+
+    #[test]
+    fn test_inflate_truncated() {
+        let input = random_words(10000);
+        let cmp = deflate_bytes(input.as_slice()).expect("deflation failed");
+        for n in range(0, 8u) {
+            let cut = Vec::from_slice(cmp.slice_to(cmp.len() * n / 8));
+            let mut r = InflateReader::new(MemReader::new(cut));
+            let err = r.read_to_end().unwrap_err();
+            assert_eq!(err.kind, InvalidInput);
+            assert_eq!(err.detail, Some("unexpected end of stream".to_string()));
+        }
+
+        let cmp = deflate_bytes_zlib(input.as_slice()).expect("deflation failed");
+        let cut = Vec::from_slice(cmp.slice_to(cmp.len() - 1));
+        let mut r = InflateReader::new_zlib(MemReader::new(cut));
+        assert_eq!(r.read_to_end().unwrap_err().kind, InvalidInput);
+    }
+
+    #[test]
+    fn test_sync_flush() {
+        let mut w = DeflateWriter::new(MemWriter::new(), DEFAULT_COMPRESSION);
+        w.set_flush_mode(SyncFlush);
+        w.write(b"hello, ").unwrap();
+        w.flush().unwrap();
+        let flushed = Vec::from_slice(w.get_ref().get_ref());
+        w.write(b"world").unwrap();
+        let all = w.finish().unwrap().unwrap();
+        assert!(all.as_slice().starts_with(flushed.as_slice()));
+
+        // Everything written before the flush is available without the rest
+        // of the stream.
+        let mut r = InflateReader::new(MemReader::new(flushed));
+        let mut buf = [0u8, ..7];
+        assert_eq!(r.read_at_least(7, buf).unwrap(), 7);
+        assert_eq!(buf.as_slice(), b"hello, ");
+
+        let mut r = InflateReader::new(MemReader::new(all));
+        assert_eq!(r.read_to_end().unwrap().as_slice(), b"hello, world");
+    }
+
+    #[test]
+    fn test_gzip_round_trip() {
+        let input = random_words(100000);
+        let header = GzipHeader {
+            filename: Some(Vec::from_slice(b"words.bin")),
+            comment: Some(Vec::from_slice(b"random words")),
+            extra: Some(vec![1, 2, 3]),
+            mtime: 1408000000,
+        };
+        let mut w = GzipWriter::with_header(MemWriter::new(), BEST_COMPRESSION, &header);
+        write_in_pieces(&mut w, input.as_slice());
+        let gz = w.finish().unwrap().unwrap();
+
+        let mut r = GzipReader::new(Trickle(MemReader::new(gz))).unwrap();
+        assert_eq!(r.header(), &header);
+        assert!(read_in_pieces(&mut r).as_slice() == input.as_slice());
+    }
+
+    #[test]
+    fn test_gzip_empty() {
+        let gz = GzipWriter::new(MemWriter::new(), DEFAULT_COMPRESSION).finish().unwrap().unwrap();
+        let mut r = GzipReader::new(MemReader::new(gz)).unwrap();
+        assert_eq!(r.read_to_end().unwrap().len(), 0);
+    }
+
+    #[test]
+    fn test_gzip_reference() {
+        // `hello, world\n` as compressed by Python's gzip module.
+        let gz = vec![0x1f, 0x8b, 0x08, 0x08, 0x00, 0x4e, 0x72, 0x53, 0x02, 0xff,
+                      0x68, 0x65, 0x6c, 0x6c, 0x6f, 0x2e, 0x74, 0x78, 0x74, 0x00,
+                      0xcb, 0x48, 0xcd, 0xc9, 0xc9, 0xd7, 0x51, 0x28, 0xcf, 0x2f,
+                      0xca, 0x49, 0xe1, 0x02, 0x00, 0x53, 0x74, 0x24, 0xf4, 0x0d,
+                      0x00, 0x00, 0x00];
+        let mut r = GzipReader::new(MemReader::new(gz.clone())).unwrap();
+        assert_eq!(r.header().filename, Some(Vec::from_slice(b"hello.txt")));
+        assert_eq!(r.header().mtime, 1400000000);
+        assert_eq!(r.read_to_end().unwrap().as_slice(), b"hello, world\n");
+
+        let mut bad = gz.clone();
+        *bad.get_mut(35) ^= 1;
+        let mut r = GzipReader::new(MemReader::new(bad)).unwrap();
+        assert_eq!(r.read_to_end().unwrap_err().kind, InvalidInput);
+
+        let mut r = GzipReader::new(MemReader::new(Vec::from_slice(gz.slice_to(40)))).unwrap();
+        assert_eq!(r.read_to_end().unwrap_err().kind, InvalidInput);
+
+        assert!(GzipReader::new(MemReader::new(vec![0x1f, 0x8c])).is_err());
+    }
+
+    #[test]
+    fn test_gzip_multiple_members() {
+        let mut w = GzipWriter::new(MemWriter::new(), DEFAULT_COMPRESSION);
+        w.write(b"first, ").unwrap();
+        let mut w = GzipWriter::new(w.finish().unwrap(), BEST_SPEED);
+        w.write(b"second").unwrap();
+        let gz = w.finish().unwrap().unwrap();
+
+        let mut r = GzipReader::new(MemReader::new(gz)).unwrap();
+        assert_eq!(r.read_to_end().unwrap().as_slice(), b"first, second");
+    }
 }
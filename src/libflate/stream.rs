@@ -0,0 +1,789 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Streaming compression and decompression on top of miniz's incremental
+//! `tdefl`/`tinfl` interface, along with gzip framing.
+
+use std::cmp;
+use std::io::{IoError, IoResult, EndOfFile, InvalidInput, OtherIoError};
+use std::io;
+use std::slice::bytes;
+use std::default::Default;
+use std::io::MemWriter;
+use libc::{c_void, c_int, c_ulong, size_t};
+
+use {rust_miniz_tdefl_new, rust_miniz_tdefl_free, tdefl_compress};
+use {rust_miniz_tinfl_new, rust_miniz_tinfl_free, rust_miniz_tinfl_unused_bytes};
+use {tinfl_decompress, mz_crc32, TDEFL_WRITE_ZLIB_HEADER, TINFL_FLAG_PARSE_ZLIB_HEADER};
+
+/// Compression level that favours speed over output size.
+pub static BEST_SPEED: uint = 1;
+/// Compression level that favours output size over speed.
+pub static BEST_COMPRESSION: uint = 9;
+/// The compression level used by `deflate_bytes` and friends.
+pub static DEFAULT_COMPRESSION: uint = 6;
+/// Store the input in uncompressed blocks.
+pub static NO_COMPRESSION: uint = 0;
+
+// miniz goes one step past zlib's level 9.
+static MAX_LEVEL: uint = 10;
+static NUM_PROBES: [c_int, ..11] = [0, 1, 6, 32, 16, 32, 128, 256, 512, 768, 1500];
+
+static TDEFL_GREEDY_PARSING_FLAG: c_int = 0x04000;
+static TDEFL_FORCE_ALL_RAW_BLOCKS: c_int = 0x80000;
+
+static TDEFL_STATUS_DONE: c_int = 1;
+
+static TDEFL_NO_FLUSH: c_int = 0;
+static TDEFL_SYNC_FLUSH: c_int = 2;
+static TDEFL_FULL_FLUSH: c_int = 3;
+static TDEFL_FINISH: c_int = 4;
+
+static TINFL_FLAG_HAS_MORE_INPUT: u32 = 0x2;
+
+static TINFL_STATUS_ADLER32_MISMATCH: c_int = -2;
+static TINFL_STATUS_DONE: c_int = 0;
+static TINFL_STATUS_NEEDS_MORE_INPUT: c_int = 1;
+
+// The decompressor writes into a circular buffer which must be at least as
+// large as the deflate window.
+static TINFL_LZ_DICT_SIZE: uint = 32768;
+static OUT_BUF_SIZE: uint = 32 * 1024;
+static IN_BUF_SIZE: uint = 32 * 1024;
+// tinfl may pull up to a full 64-bit bit buffer's worth of bytes past the
+// end of a stream, so that many consumed bytes are kept around to be handed
+// back.
+static LOOKBEHIND: uint = 8;
+
+// Translates a zlib-style compression level into tdefl flags.
+fn level_flags(level: uint) -> c_int {
+    let level = cmp::min(level, MAX_LEVEL);
+    let mut flags = NUM_PROBES[level];
+    if level <= 3 {
+        flags |= TDEFL_GREEDY_PARSING_FLAG;
+    }
+    if level == 0 {
+        flags |= TDEFL_FORCE_ALL_RAW_BLOCKS;
+    }
+    flags
+}
+
+/// Determines what `flush` does on a `DeflateWriter`.
+#[deriving(Clone, PartialEq, Eq, Show)]
+pub enum FlushMode {
+    /// Only flush the underlying writer. Compressed data buffered by the
+    /// compressor stays where it is, which gives the best compression.
+    NoFlush,
+    /// Emit all pending output followed by an empty stored block, so that
+    /// everything written so far can be decompressed by the reader.
+    SyncFlush,
+    /// Like `SyncFlush`, but also reset the compressor's dictionary so that
+    /// decompression can restart from this point.
+    FullFlush,
+}
+
+fn corrupt(detail: &str) -> IoError {
+    IoError {
+        kind: InvalidInput,
+        desc: "corrupt compressed stream",
+        detail: Some(detail.to_string()),
+    }
+}
+
+struct Compressor {
+    raw: *mut c_void,
+}
+
+impl Compressor {
+    fn new(flags: c_int) -> Compressor {
+        let raw = unsafe { rust_miniz_tdefl_new(flags) };
+        assert!(!raw.is_null(), "failed to allocate compressor");
+        Compressor { raw: raw }
+    }
+
+    /// Returns the tdefl status along with the number of bytes of `input`
+    /// consumed and the number of bytes written to `output`.
+    fn compress(&mut self, input: &[u8], output: &mut [u8],
+                flush: c_int) -> (c_int, uint, uint) {
+        let mut in_size = input.len() as size_t;
+        let mut out_size = output.len() as size_t;
+        let status = unsafe {
+            tdefl_compress(self.raw,
+                           input.as_ptr() as *const c_void,
+                           &mut in_size,
+                           output.as_mut_ptr() as *mut c_void,
+                           &mut out_size,
+                           flush)
+        };
+        (status, in_size as uint, out_size as uint)
+    }
+}
+
+impl Drop for Compressor {
+    fn drop(&mut self) {
+        unsafe { rust_miniz_tdefl_free(self.raw) }
+    }
+}
+
+/// A `Writer` which compresses everything written to it before passing it
+/// on to the wrapped writer.
+///
+/// The deflate stream is terminated by `finish`, or when the writer is
+/// dropped. Errors are ignored in the latter case, so `finish` should be
+/// preferred.
+///
+/// # Example
+///
+/// ```rust
+/// use flate::{DeflateWriter, InflateReader, DEFAULT_COMPRESSION};
+/// use std::io::{MemWriter, MemReader};
+///
+/// let mut w = DeflateWriter::new(MemWriter::new(), DEFAULT_COMPRESSION);
+/// w.write(b"hello, hello, hello").unwrap();
+/// let compressed = w.finish().unwrap().unwrap();
+///
+/// let mut r = InflateReader::new(MemReader::new(compressed));
+/// assert_eq!(r.read_to_end().unwrap().as_slice(), b"hello, hello, hello");
+/// ```
+pub struct DeflateWriter<W> {
+    inner: Option<W>,
+    state: Compressor,
+    buf: Vec<u8>,
+    flush_mode: FlushMode,
+}
+
+impl<W: Writer> DeflateWriter<W> {
+    /// Creates a writer producing a raw deflate stream with the given
+    /// compression level, from `NO_COMPRESSION` to `BEST_COMPRESSION`.
+    /// Higher levels are treated as the highest level miniz supports.
+    pub fn new(inner: W, level: uint) -> DeflateWriter<W> {
+        DeflateWriter::with_flags(inner, level_flags(level))
+    }
+
+    /// Creates a writer producing a deflate stream with a zlib header and
+    /// adler32 trailer.
+    pub fn new_zlib(inner: W, level: uint) -> DeflateWriter<W> {
+        DeflateWriter::with_flags(inner, level_flags(level) | TDEFL_WRITE_ZLIB_HEADER)
+    }
+
+    fn with_flags(inner: W, flags: c_int) -> DeflateWriter<W> {
+        DeflateWriter {
+            inner: Some(inner),
+            state: Compressor::new(flags),
+            buf: Vec::from_elem(OUT_BUF_SIZE, 0u8),
+            flush_mode: NoFlush,
+        }
+    }
+
+    /// Sets what a call to `flush` does. The default is `NoFlush`.
+    pub fn set_flush_mode(&mut self, mode: FlushMode) {
+        self.flush_mode = mode;
+    }
+
+    /// Gets a reference to the underlying writer.
+    ///
+    /// This type does not expose the ability to get a mutable reference to
+    /// the underlying writer because writing to it would corrupt the stream.
+    pub fn get_ref<'a>(&'a self) -> &'a W { self.inner.get_ref() }
+
+    /// Terminates the deflate stream and returns the underlying writer.
+    pub fn finish(mut self) -> IoResult<W> {
+        try!(self.compress(&[], TDEFL_FINISH));
+        Ok(self.inner.take().unwrap())
+    }
+
+    fn compress(&mut self, mut input: &[u8], flush: c_int) -> IoResult<()> {
+        loop {
+            let (status, consumed, produced) =
+                self.state.compress(input, self.buf.as_mut_slice(), flush);
+            if status < 0 {
+                return Err(IoError {
+                    kind: OtherIoError,
+                    desc: "deflate failed",
+                    detail: None,
+                });
+            }
+            if produced > 0 {
+                try!(self.inner.get_mut_ref().write(self.buf.slice_to(produced)));
+            }
+            input = input.slice_from(consumed);
+            if status == TDEFL_STATUS_DONE {
+                return Ok(());
+            }
+            // Until the stream is finished, the compressor has caught up
+            // once it has taken all of the input and no longer fills the
+            // output buffer.
+            if flush != TDEFL_FINISH && input.is_empty() && produced < self.buf.len() {
+                return Ok(());
+            }
+        }
+    }
+}
+
+impl<W: Writer> Writer for DeflateWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> IoResult<()> {
+        self.compress(buf, TDEFL_NO_FLUSH)
+    }
+
+    fn flush(&mut self) -> IoResult<()> {
+        match self.flush_mode {
+            NoFlush => {}
+            SyncFlush => try!(self.compress(&[], TDEFL_SYNC_FLUSH)),
+            FullFlush => try!(self.compress(&[], TDEFL_FULL_FLUSH)),
+        }
+        self.inner.get_mut_ref().flush()
+    }
+}
+
+#[unsafe_destructor]
+impl<W: Writer> Drop for DeflateWriter<W> {
+    fn drop(&mut self) {
+        if self.inner.is_some() {
+            // dtors should not fail, so we ignore a failed write
+            let _ = self.compress(&[], TDEFL_FINISH);
+        }
+    }
+}
+
+// Buffers input for the decompressor, remembering the last few consumed
+// bytes so that anything read past the end of a deflate stream can be
+// given back.
+struct Input<R> {
+    inner: R,
+    buf: Vec<u8>,
+    pos: uint,
+    cap: uint,
+    eof: bool,
+}
+
+impl<R: Reader> Input<R> {
+    fn new(inner: R) -> Input<R> {
+        Input {
+            inner: inner,
+            buf: Vec::from_elem(LOOKBEHIND + IN_BUF_SIZE, 0u8),
+            pos: 0,
+            cap: 0,
+            eof: false,
+        }
+    }
+
+    fn available<'a>(&'a self) -> &'a [u8] {
+        self.buf.slice(self.pos, self.cap)
+    }
+
+    fn consume(&mut self, amt: uint) {
+        self.pos = cmp::min(self.pos + amt, self.cap);
+    }
+
+    fn unconsume(&mut self, amt: uint) {
+        assert!(amt <= self.pos);
+        self.pos -= amt;
+    }
+
+    /// Refills the buffer once everything in it has been consumed. Returns
+    /// `false` if there is nothing left to read.
+    fn fill(&mut self) -> IoResult<bool> {
+        if self.pos < self.cap {
+            return Ok(true);
+        }
+        if self.eof {
+            return Ok(false);
+        }
+        let keep = cmp::min(self.cap, LOOKBEHIND);
+        for i in range(0, keep) {
+            let b = *self.buf.get(self.cap - keep + i);
+            *self.buf.get_mut(i) = b;
+        }
+        self.pos = keep;
+        self.cap = keep;
+        loop {
+            match self.inner.read(self.buf.mut_slice_from(keep)) {
+                Ok(0) => {}
+                Ok(n) => { self.cap += n; return Ok(true) }
+                Err(ref e) if e.kind == EndOfFile => { self.eof = true; return Ok(false) }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    fn get_ref<'a>(&'a self) -> &'a R { &self.inner }
+}
+
+impl<R: Reader> Reader for Input<R> {
+    fn read(&mut self, buf: &mut [u8]) -> IoResult<uint> {
+        if !try!(self.fill()) {
+            return Err(io::standard_error(EndOfFile));
+        }
+        let n = cmp::min(buf.len(), self.cap - self.pos);
+        bytes::copy_memory(buf, self.buf.slice(self.pos, self.pos + n));
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+struct Decompressor {
+    raw: *mut c_void,
+}
+
+impl Decompressor {
+    fn new() -> Decompressor {
+        let raw = unsafe { rust_miniz_tinfl_new() };
+        assert!(!raw.is_null(), "failed to allocate decompressor");
+        Decompressor { raw: raw }
+    }
+
+    /// Decompresses into the circular buffer `dict` starting at `pos`.
+    /// Returns the tinfl status along with the number of bytes of `input`
+    /// consumed and the number of bytes written.
+    fn decompress(&mut self, input: &[u8], dict: &mut [u8], pos: uint,
+                  flags: u32) -> (c_int, uint, uint) {
+        let mut in_size = input.len() as size_t;
+        let mut out_size = (dict.len() - pos) as size_t;
+        let status = unsafe {
+            let start = dict.as_mut_ptr();
+            tinfl_decompress(self.raw, input.as_ptr(), &mut in_size,
+                             start, start.offset(pos as int), &mut out_size,
+                             flags)
+        };
+        (status, in_size as uint, out_size as uint)
+    }
+
+    fn unused_bytes(&self) -> uint {
+        unsafe { rust_miniz_tinfl_unused_bytes(self.raw as *const c_void) as uint }
+    }
+}
+
+impl Drop for Decompressor {
+    fn drop(&mut self) {
+        unsafe { rust_miniz_tinfl_free(self.raw) }
+    }
+}
+
+/// A `Reader` which decompresses a deflate stream read from the wrapped
+/// reader.
+///
+/// Reading stops at the end of the deflate stream; anything after it is
+/// left unread.
+pub struct InflateReader<R> {
+    input: Input<R>,
+    state: Decompressor,
+    flags: u32,
+    dict: Vec<u8>,
+    // `dict[out_pos..]` is where the decompressor writes next, and
+    // `dict[pending..out_end]` is decompressed data not yet returned.
+    out_pos: uint,
+    pending: uint,
+    out_end: uint,
+    done: bool,
+}
+
+impl<R: Reader> InflateReader<R> {
+    /// Creates a reader for a raw deflate stream.
+    pub fn new(inner: R) -> InflateReader<R> {
+        InflateReader::with_flags(inner, 0)
+    }
+
+    /// Creates a reader for a deflate stream with a zlib header. The adler32
+    /// trailer is verified once the end of the stream is reached.
+    pub fn new_zlib(inner: R) -> InflateReader<R> {
+        InflateReader::with_flags(inner, TINFL_FLAG_PARSE_ZLIB_HEADER as u32)
+    }
+
+    fn with_flags(inner: R, flags: u32) -> InflateReader<R> {
+        InflateReader {
+            input: Input::new(inner),
+            state: Decompressor::new(),
+            flags: flags,
+            dict: Vec::from_elem(TINFL_LZ_DICT_SIZE, 0u8),
+            out_pos: 0,
+            pending: 0,
+            out_end: 0,
+            done: false,
+        }
+    }
+
+    /// Gets a reference to the underlying reader.
+    pub fn get_ref<'a>(&'a self) -> &'a R { self.input.get_ref() }
+
+    // Prepares to decompress another deflate stream from the same input.
+    fn reset(&mut self) {
+        self.state = Decompressor::new();
+        self.out_pos = 0;
+        self.pending = 0;
+        self.out_end = 0;
+        self.done = false;
+    }
+
+    fn step(&mut self) -> IoResult<()> {
+        try!(self.input.fill());
+        // Without this flag miniz pads a short input with zeros, so it is
+        // always passed and a stream that still needs input at the end of the
+        // reader is reported as truncated below.
+        let flags = self.flags | TINFL_FLAG_HAS_MORE_INPUT;
+        let (status, consumed, produced) =
+            self.state.decompress(self.input.available(), self.dict.as_mut_slice(),
+                                  self.out_pos, flags);
+        self.input.consume(consumed);
+        self.pending = self.out_pos;
+        self.out_end = self.out_pos + produced;
+        self.out_pos = self.out_end & (TINFL_LZ_DICT_SIZE - 1);
+
+        if status == TINFL_STATUS_DONE {
+            self.done = true;
+            self.input.unconsume(self.state.unused_bytes());
+        } else if status == TINFL_STATUS_ADLER32_MISMATCH {
+            return Err(corrupt("adler32 checksum mismatch"));
+        } else if status < 0 {
+            return Err(corrupt("invalid deflate data"));
+        } else if status == TINFL_STATUS_NEEDS_MORE_INPUT && self.input.eof {
+            return Err(corrupt("unexpected end of stream"));
+        }
+        Ok(())
+    }
+}
+
+impl<R: Reader> Reader for InflateReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> IoResult<uint> {
+        loop {
+            if self.pending < self.out_end {
+                let n = cmp::min(buf.len(), self.out_end - self.pending);
+                bytes::copy_memory(buf, self.dict.slice(self.pending, self.pending + n));
+                self.pending += n;
+                return Ok(n);
+            }
+            if self.done {
+                return Err(io::standard_error(EndOfFile));
+            }
+            try!(self.step());
+        }
+    }
+}
+
+static GZIP_ID1: u8 = 0x1f;
+static GZIP_ID2: u8 = 0x8b;
+static GZIP_CM_DEFLATE: u8 = 8;
+static GZIP_OS_UNKNOWN: u8 = 255;
+
+static FHCRC: u8 = 0x02;
+static FEXTRA: u8 = 0x04;
+static FNAME: u8 = 0x08;
+static FCOMMENT: u8 = 0x10;
+static FRESERVED: u8 = 0xe0;
+
+fn crc32(crc: u32, data: &[u8]) -> u32 {
+    unsafe { mz_crc32(crc as c_ulong, data.as_ptr(), data.len() as size_t) as u32 }
+}
+
+// Running out of input part way through a gzip member is an error, not the
+// end of the decompressed data.
+fn truncated<T>(r: IoResult<T>) -> IoResult<T> {
+    match r {
+        Err(ref e) if e.kind == EndOfFile => Err(corrupt("unexpected end of stream")),
+        r => r,
+    }
+}
+
+/// The header of a gzip member, as described by RFC 1952.
+#[deriving(Clone, PartialEq, Eq, Show, Default)]
+pub struct GzipHeader {
+    /// The name of the original file, without any directory components.
+    pub filename: Option<Vec<u8>>,
+    /// A free-form comment.
+    pub comment: Option<Vec<u8>>,
+    /// Application-specific extra field data.
+    pub extra: Option<Vec<u8>>,
+    /// Modification time of the original file in seconds since the epoch,
+    /// or zero if none is recorded.
+    pub mtime: u32,
+}
+
+impl GzipHeader {
+    fn to_bytes(&self, level: uint) -> Vec<u8> {
+        let mut flags = 0;
+        if self.extra.is_some() { flags |= FEXTRA; }
+        if self.filename.is_some() { flags |= FNAME; }
+        if self.comment.is_some() { flags |= FCOMMENT; }
+        let xfl = if level >= BEST_COMPRESSION {
+            2
+        } else if level == BEST_SPEED {
+            4
+        } else {
+            0
+        };
+
+        // Writing to a MemWriter cannot fail.
+        let mut w = MemWriter::new();
+        w.write(&[GZIP_ID1, GZIP_ID2, GZIP_CM_DEFLATE, flags]).unwrap();
+        w.write_le_u32(self.mtime).unwrap();
+        w.write(&[xfl, GZIP_OS_UNKNOWN]).unwrap();
+        match self.extra {
+            Some(ref extra) => {
+                assert!(extra.len() <= 0xffff, "gzip extra field is too long");
+                w.write_le_u16(extra.len() as u16).unwrap();
+                w.write(extra.as_slice()).unwrap();
+            }
+            None => {}
+        }
+        for field in [&self.filename, &self.comment].iter() {
+            match **field {
+                Some(ref s) => {
+                    assert!(!s.as_slice().contains(&0), "gzip header string contains a NUL byte");
+                    w.write(s.as_slice()).unwrap();
+                    w.write_u8(0).unwrap();
+                }
+                None => {}
+            }
+        }
+        w.unwrap()
+    }
+
+    fn read_from<R: Reader>(r: &mut R) -> IoResult<GzipHeader> {
+        let mut raw = try!(truncated(r.read_exact(10)));
+        if raw[0] != GZIP_ID1 || raw[1] != GZIP_ID2 {
+            return Err(corrupt("not in gzip format"));
+        }
+        if raw[2] != GZIP_CM_DEFLATE {
+            return Err(corrupt("unknown gzip compression method"));
+        }
+        let flags = raw[3];
+        if flags & FRESERVED != 0 {
+            return Err(corrupt("reserved gzip flags are set"));
+        }
+        let mut header = GzipHeader {
+            mtime: (raw[4] as u32) | ((raw[5] as u32) << 8) |
+                   ((raw[6] as u32) << 16) | ((raw[7] as u32) << 24),
+            ..Default::default()
+        };
+
+        if flags & FEXTRA != 0 {
+            let lo = try!(truncated(r.read_u8()));
+            let hi = try!(truncated(r.read_u8()));
+            let extra = try!(truncated(r.read_exact((lo as uint) | ((hi as uint) << 8))));
+            raw.push(lo);
+            raw.push(hi);
+            raw.push_all(extra.as_slice());
+            header.extra = Some(extra);
+        }
+        if flags & FNAME != 0 {
+            let name = try!(read_cstr(r));
+            raw.push_all(name.as_slice());
+            raw.push(0);
+            header.filename = Some(name);
+        }
+        if flags & FCOMMENT != 0 {
+            let comment = try!(read_cstr(r));
+            raw.push_all(comment.as_slice());
+            raw.push(0);
+            header.comment = Some(comment);
+        }
+        if flags & FHCRC != 0 {
+            let crc = try!(truncated(r.read_le_u16()));
+            if crc != crc32(0, raw.as_slice()) as u16 {
+                return Err(corrupt("gzip header CRC16 mismatch"));
+            }
+        }
+        Ok(header)
+    }
+}
+
+fn read_cstr<R: Reader>(r: &mut R) -> IoResult<Vec<u8>> {
+    let mut s = Vec::new();
+    loop {
+        match try!(truncated(r.read_u8())) {
+            0 => return Ok(s),
+            b => s.push(b),
+        }
+    }
+}
+
+/// A `Writer` which compresses everything written to it into a single gzip
+/// member.
+///
+/// Like `DeflateWriter`, the member is completed by `finish` or when the
+/// writer is dropped.
+pub struct GzipWriter<W> {
+    inner: Option<DeflateWriter<W>>,
+    header: Option<Vec<u8>>,
+    crc: u32,
+    size: u32,
+}
+
+impl<W: Writer> GzipWriter<W> {
+    /// Creates a gzip writer with an empty header.
+    pub fn new(inner: W, level: uint) -> GzipWriter<W> {
+        GzipWriter::with_header(inner, level, &Default::default())
+    }
+
+    /// Creates a gzip writer which writes `header` ahead of the compressed
+    /// data.
+    ///
+    /// # Failure
+    ///
+    /// Fails if the file name or comment contains a NUL byte, or if the extra
+    /// field is longer than 65535 bytes.
+    pub fn with_header(inner: W, level: uint, header: &GzipHeader) -> GzipWriter<W> {
+        GzipWriter {
+            inner: Some(DeflateWriter::new(inner, level)),
+            header: Some(header.to_bytes(level)),
+            crc: 0,
+            size: 0,
+        }
+    }
+
+    /// Sets what a call to `flush` does. The default is `NoFlush`.
+    pub fn set_flush_mode(&mut self, mode: FlushMode) {
+        self.inner.get_mut_ref().set_flush_mode(mode);
+    }
+
+    /// Gets a reference to the underlying writer.
+    pub fn get_ref<'a>(&'a self) -> &'a W { self.inner.get_ref().get_ref() }
+
+    /// Writes the gzip trailer and returns the underlying writer.
+    pub fn finish(mut self) -> IoResult<W> {
+        self.finish_member()
+    }
+
+    // The header goes straight to the underlying writer, and is held back
+    // until there is something else to write so that creating a
+    // `GzipWriter` cannot fail.
+    fn write_header(&mut self) -> IoResult<()> {
+        match self.header.take() {
+            Some(header) => {
+                let deflate = self.inner.get_mut_ref();
+                deflate.inner.get_mut_ref().write(header.as_slice())
+            }
+            None => Ok(()),
+        }
+    }
+
+    fn finish_member(&mut self) -> IoResult<W> {
+        try!(self.write_header());
+        let mut w = try!(self.inner.take().unwrap().finish());
+        try!(w.write_le_u32(self.crc));
+        try!(w.write_le_u32(self.size));
+        Ok(w)
+    }
+}
+
+impl<W: Writer> Writer for GzipWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> IoResult<()> {
+        try!(self.write_header());
+        try!(self.inner.get_mut_ref().write(buf));
+        self.crc = crc32(self.crc, buf);
+        // ISIZE is the input size modulo 2^32.
+        self.size += buf.len() as u32;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> IoResult<()> {
+        try!(self.write_header());
+        self.inner.get_mut_ref().flush()
+    }
+}
+
+#[unsafe_destructor]
+impl<W: Writer> Drop for GzipWriter<W> {
+    fn drop(&mut self) {
+        if self.inner.is_some() {
+            // dtors should not fail, so we ignore a failed write
+            let _ = self.finish_member();
+        }
+    }
+}
+
+/// A `Reader` which decompresses gzip data from the wrapped reader.
+///
+/// Each member's CRC32 and length are checked against its trailer, and
+/// concatenated members are read one after another as a single stream, as
+/// `gunzip` does.
+///
+/// # Example
+///
+/// ```rust
+/// use flate::{GzipWriter, GzipReader, DEFAULT_COMPRESSION};
+/// use std::io::{MemWriter, MemReader};
+///
+/// let mut w = GzipWriter::new(MemWriter::new(), DEFAULT_COMPRESSION);
+/// w.write(b"hello, world").unwrap();
+/// let gz = w.finish().unwrap().unwrap();
+///
+/// let mut r = GzipReader::new(MemReader::new(gz)).unwrap();
+/// assert_eq!(r.read_to_end().unwrap().as_slice(), b"hello, world");
+/// ```
+pub struct GzipReader<R> {
+    inflate: InflateReader<R>,
+    header: GzipHeader,
+    crc: u32,
+    size: u32,
+    eof: bool,
+}
+
+impl<R: Reader> GzipReader<R> {
+    /// Creates a gzip reader, reading the header of the first member.
+    pub fn new(inner: R) -> IoResult<GzipReader<R>> {
+        let mut inflate = InflateReader::new(inner);
+        let header = try!(GzipHeader::read_from(&mut inflate.input));
+        Ok(GzipReader {
+            inflate: inflate,
+            header: header,
+            crc: 0,
+            size: 0,
+            eof: false,
+        })
+    }
+
+    /// Returns the header of the member currently being read.
+    pub fn header<'a>(&'a self) -> &'a GzipHeader { &self.header }
+
+    /// Gets a reference to the underlying reader.
+    pub fn get_ref<'a>(&'a self) -> &'a R { self.inflate.get_ref() }
+
+    // Checks the trailer of the member just decompressed and moves on to
+    // the next one, if any.
+    fn next_member(&mut self) -> IoResult<()> {
+        let crc = try!(truncated(self.inflate.input.read_le_u32()));
+        let size = try!(truncated(self.inflate.input.read_le_u32()));
+        if crc != self.crc {
+            return Err(corrupt("gzip CRC32 mismatch"));
+        }
+        if size != self.size {
+            return Err(corrupt("gzip length mismatch"));
+        }
+        if !try!(self.inflate.input.fill()) {
+            self.eof = true;
+            return Ok(());
+        }
+        self.header = try!(GzipHeader::read_from(&mut self.inflate.input));
+        self.inflate.reset();
+        self.crc = 0;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+impl<R: Reader> Reader for GzipReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> IoResult<uint> {
+        loop {
+            if self.eof {
+                return Err(io::standard_error(EndOfFile));
+            }
+            match self.inflate.read(buf) {
+                Ok(n) => {
+                    self.crc = crc32(self.crc, buf.slice_to(n));
+                    self.size += n as u32;
+                    return Ok(n);
+                }
+                Err(ref e) if e.kind == EndOfFile => {}
+                Err(e) => return Err(e),
+            }
+            try!(self.next_member());
+        }
+    }
+}
@@ -25,6 +25,12 @@
 //! argument following either a space or an equals sign. Single-character
 //! options don't require the space.
 //!
+//! Option arguments can be converted to other types with the `opt_parse`
+//! family of accessors, which report arguments that fail to convert as
+//! `ArgumentInvalid`. Tools that take subcommands, as in `tool build
+//! --release`, can describe each one with `subcommand` and parse the whole
+//! command line with `getopts_subcommand`.
+//!
 //! # Example
 //!
 //! The following example shows simple command line parsing for an application
@@ -96,6 +102,7 @@
 
 use std::cmp::PartialEq;
 use std::fmt;
+use std::from_str::FromStr;
 use std::result::{Err, Ok};
 use std::result;
 use std::string::String;
@@ -198,6 +205,13 @@ pub enum Fail_ {
     OptionDuplicated(String),
     /// There's an argument being passed to a non-argument option.
     UnexpectedArgument(String),
+    /// The argument to an option could not be converted to the requested
+    /// type. Holds the option name and the argument.
+    ArgumentInvalid(String, String),
+    /// The subcommand given is not one of the possible subcommands.
+    UnrecognizedSubcommand(String),
+    /// No subcommand was given.
+    SubcommandMissing,
 }
 
 /// The type of failure that occurred.
@@ -209,6 +223,9 @@ pub enum FailType {
     OptionMissing_,
     OptionDuplicated_,
     UnexpectedArgument_,
+    ArgumentInvalid_,
+    UnrecognizedSubcommand_,
+    SubcommandMissing_,
 }
 
 /// The result of parsing a command line with a set of options.
@@ -370,6 +387,45 @@ impl Matches {
         }
     }
 
+    /// Returns the argument supplied to a matching option converted with
+    /// `FromStr`, or `None`.
+    ///
+    /// Returns `Ok(None)` if the option was not present or was given without
+    /// an argument, and `Err(ArgumentInvalid(..))` if the argument could not
+    /// be converted.
+    pub fn opt_parse<T: FromStr>(&self, nm: &str) -> result::Result<Option<T>, Fail_> {
+        match self.opt_str(nm) {
+            Some(s) => parse_arg(nm, s).map(|v| Some(v)),
+            None => Ok(None)
+        }
+    }
+
+    /// Returns the argument supplied to a matching option converted with
+    /// `FromStr`, or `def` if there is none.
+    pub fn opt_parse_or<T: FromStr>(&self, nm: &str, def: T) -> result::Result<T, Fail_> {
+        self.opt_parse(nm).map(|v| v.unwrap_or(def))
+    }
+
+    /// Returns a vector of the arguments provided to all matches of the given
+    /// option, each converted with `FromStr`.
+    ///
+    /// Fails with `ArgumentInvalid` on the first argument that could not be
+    /// converted.
+    pub fn opt_parse_all<T: FromStr>(&self, nm: &str) -> result::Result<Vec<T>, Fail_> {
+        let mut acc = Vec::new();
+        for s in self.opt_strs(nm).move_iter() {
+            acc.push(try!(parse_arg(nm, s)));
+        }
+        Ok(acc)
+    }
+
+}
+
+fn parse_arg<T: FromStr>(nm: &str, arg: String) -> result::Result<T, Fail_> {
+    match from_str(arg.as_slice()) {
+        Some(v) => Ok(v),
+        None => Err(ArgumentInvalid(nm.to_string(), arg))
+    }
 }
 
 fn is_arg(arg: &str) -> bool {
@@ -524,6 +580,15 @@ impl fmt::Show for Fail_ {
             UnexpectedArgument(ref nm) => {
                 write!(f, "Option '{}' does not take an argument.", *nm)
             }
+            ArgumentInvalid(ref nm, ref arg) => {
+                write!(f, "Invalid argument '{}' to option '{}'.", *arg, *nm)
+            }
+            UnrecognizedSubcommand(ref nm) => {
+                write!(f, "Unrecognized subcommand: '{}'.", *nm)
+            }
+            SubcommandMissing => {
+                write!(f, "Subcommand missing.")
+            }
         }
     }
 }
@@ -558,7 +623,8 @@ pub fn getopts(args: &[String], optgrps: &[OptGroup]) -> Result {
             let mut i_arg = None;
             if cur.as_bytes()[1] == b'-' {
                 let tail = cur.as_slice().slice(2, curlen);
-                let tail_eq: Vec<&str> = tail.split('=').collect();
+                // only split at the first `=`, the argument may contain more
+                let tail_eq: Vec<&str> = tail.splitn(1, '=').collect();
                 if tail_eq.len() <= 1 {
                     names = vec!(Long(tail.to_string()));
                 } else {
@@ -669,8 +735,6 @@ pub fn getopts(args: &[String], optgrps: &[OptGroup]) -> Result {
 /// Derive a usage message from a set of long options.
 pub fn usage(brief: &str, opts: &[OptGroup]) -> String {
 
-    let desc_sep = format!("\n{}", " ".repeat(24));
-
     let mut rows = opts.iter().map(|optref| {
         let OptGroup{short_name: short_name,
                      long_name: long_name,
@@ -713,42 +777,49 @@ pub fn usage(brief: &str, opts: &[OptGroup]) -> String {
             }
         }
 
-        // FIXME: #5516 should be graphemes not codepoints
-        // here we just need to indent the start of the description
-        let rowlen = row.as_slice().char_len();
-        if rowlen < 24 {
-            for _ in range(0, 24 - rowlen) {
-                row.push_char(' ');
-            }
-        } else {
-            row.push_str(desc_sep.as_slice())
-        }
+        append_desc(&mut row, desc.as_slice());
+        row
+    });
 
-        // Normalize desc to contain words separated by one space character
-        let mut desc_normalized_whitespace = String::new();
-        for word in desc.as_slice().words() {
-            desc_normalized_whitespace.push_str(word);
-            desc_normalized_whitespace.push_char(' ');
-        }
+    format!("{}\n\nOptions:\n{}\n", brief,
+            rows.collect::<Vec<String>>().connect("\n"))
+}
 
-        // FIXME: #5516 should be graphemes not codepoints
-        let mut desc_rows = Vec::new();
-        each_split_within(desc_normalized_whitespace.as_slice(),
-                          54,
-                          |substr| {
-            desc_rows.push(substr.to_string());
-            true
-        });
+// Appends `desc` to a usage row, starting it in the 25th column and wrapping
+// it to fit in 80 columns.
+fn append_desc(row: &mut String, desc: &str) {
+    let desc_sep = format!("\n{}", " ".repeat(24));
+
+    // FIXME: #5516 should be graphemes not codepoints
+    // here we just need to indent the start of the description
+    let rowlen = row.as_slice().char_len();
+    if rowlen < 24 {
+        for _ in range(0, 24 - rowlen) {
+            row.push_char(' ');
+        }
+    } else {
+        row.push_str(desc_sep.as_slice())
+    }
 
-        // FIXME: #5516 should be graphemes not codepoints
-        // wrapped description
-        row.push_str(desc_rows.connect(desc_sep.as_slice()).as_slice());
+    // Normalize desc to contain words separated by one space character
+    let mut desc_normalized_whitespace = String::new();
+    for word in desc.words() {
+        desc_normalized_whitespace.push_str(word);
+        desc_normalized_whitespace.push_char(' ');
+    }
 
-        row
+    // FIXME: #5516 should be graphemes not codepoints
+    let mut desc_rows = Vec::new();
+    each_split_within(desc_normalized_whitespace.as_slice(),
+                      54,
+                      |substr| {
+        desc_rows.push(substr.to_string());
+        true
     });
 
-    format!("{}\n\nOptions:\n{}\n", brief,
-            rows.collect::<Vec<String>>().connect("\n"))
+    // FIXME: #5516 should be graphemes not codepoints
+    // wrapped description
+    row.push_str(desc_rows.connect(desc_sep.as_slice()).as_slice());
 }
 
 fn format_option(opt: &OptGroup) -> String {
@@ -799,6 +870,177 @@ pub fn short_usage(program_name: &str, opts: &[OptGroup]) -> String {
     line
 }
 
+/// A subcommand, such as the `build` in `tool build --release`, along with
+/// the options it accepts.
+#[deriving(Clone, PartialEq, Eq)]
+pub struct Subcommand {
+    /// Name of the subcommand
+    pub name: String,
+    /// Description
+    pub desc: String,
+    /// Options accepted after the subcommand name
+    pub opts: Vec<OptGroup>,
+}
+
+/// The result of checking a command line with subcommands.
+#[deriving(Clone, PartialEq, Eq)]
+pub struct SubcommandMatches {
+    /// Options that matched before the subcommand name
+    pub global: Matches,
+    /// Name of the subcommand that was given
+    pub name: String,
+    /// Options and free string fragments that matched after the subcommand
+    /// name
+    pub matches: Matches,
+}
+
+/// The result of parsing a command line with a set of options and
+/// subcommands.
+pub type SubcommandResult = result::Result<SubcommandMatches, Fail_>;
+
+/// Create a subcommand accepting the given options.
+pub fn subcommand(name: &str, desc: &str, opts: &[OptGroup]) -> Subcommand {
+    Subcommand {
+        name: name.to_string(),
+        desc: desc.to_string(),
+        opts: Vec::from_slice(opts)
+    }
+}
+
+impl Subcommand {
+    /// Derive a usage message for this subcommand, headed by a usage line
+    /// and its description.
+    pub fn usage(&self, program_name: &str) -> String {
+        let mut brief = format!("Usage: {} {} [options]", program_name, self.name);
+        if !self.desc.is_empty() {
+            brief.push_str("\n\n");
+            brief.push_str(self.desc.as_slice());
+        }
+        usage(brief.as_slice(), self.opts.as_slice())
+    }
+
+    /// Derive a short one-line usage summary for this subcommand.
+    pub fn short_usage(&self, program_name: &str) -> String {
+        let name = format!("{} {}", program_name, self.name);
+        short_usage(name.as_slice(), self.opts.as_slice())
+    }
+}
+
+/// Parse command line arguments of the form `[options] subcommand [arguments]`.
+///
+/// The arguments before the subcommand name are parsed with `optgrps`, and
+/// those after it with the options of the matching entry of `cmds`. The
+/// subcommand name is the first free argument, so a `--` before it means that
+/// no subcommand was given.
+///
+/// # Example
+///
+/// ~~~{.rust}
+/// use getopts::{optflag, optopt, subcommand, getopts_subcommand};
+///
+/// let args = vec!["-v".to_string(), "build".to_string(),
+///                 "--jobs=4".to_string(), "src".to_string()];
+/// let opts = [optflag("v", "verbose", "use verbose output")];
+/// let cmds = [subcommand("build", "compile the sources",
+///                        [optopt("j", "jobs", "number of jobs", "N")])];
+///
+/// let m = getopts_subcommand(args.as_slice(), opts, cmds).unwrap();
+/// assert!(m.global.opt_present("verbose"));
+/// assert_eq!(m.name.as_slice(), "build");
+/// assert_eq!(m.matches.opt_parse::<uint>("jobs"), Ok(Some(4)));
+/// assert_eq!(m.matches.free, vec!["src".to_string()]);
+/// ~~~
+pub fn getopts_subcommand(args: &[String], optgrps: &[OptGroup],
+                          cmds: &[Subcommand]) -> SubcommandResult {
+    let opts: Vec<Opt> = optgrps.iter().map(|x| x.long_to_short()).collect();
+    let pos = match find_first_free(args, opts.as_slice()) {
+        Some(pos) => pos,
+        None => {
+            // report problems with the options before a missing subcommand
+            try!(getopts(args, optgrps));
+            return Err(SubcommandMissing);
+        }
+    };
+
+    let global = try!(getopts(args.slice_to(pos), optgrps));
+    let name = args[pos].clone();
+    let cmd = match cmds.iter().find(|cmd| cmd.name == name) {
+        Some(cmd) => cmd,
+        None => return Err(UnrecognizedSubcommand(name))
+    };
+    let matches = try!(getopts(args.slice_from(pos + 1), cmd.opts.as_slice()));
+    Ok(SubcommandMatches {
+        global: global,
+        name: name,
+        matches: matches
+    })
+}
+
+// Returns the index of the first free argument, skipping over options and
+// their arguments the same way `getopts` does. Returns `None` if there is no
+// free argument before the end of the arguments or a `--`.
+fn find_first_free(args: &[String], opts: &[Opt]) -> Option<uint> {
+    let mut i = 0;
+    while i < args.len() {
+        let cur = args[i].as_slice();
+        if !is_arg(cur) {
+            return Some(i);
+        } else if cur == "--" {
+            return None;
+        }
+
+        // whether the option takes the next argument as its own
+        let mut hasarg = None;
+        if cur.as_bytes()[1] == b'-' {
+            if !cur.contains_char('=') {
+                let nm = Long(cur.slice_from(2).to_string());
+                hasarg = find_opt(opts, nm).map(|id| opts[id].hasarg);
+            }
+        } else {
+            // only the last of a series of short options can take the next
+            // argument, the others would take the rest of the series
+            let mut j = 1;
+            while j < cur.len() {
+                let range = cur.char_range_at(j);
+                match find_opt(opts, Short(range.ch)) {
+                    Some(id) if opts[id].hasarg != No => {
+                        if range.next == cur.len() {
+                            hasarg = Some(opts[id].hasarg);
+                        }
+                        break;
+                    }
+                    Some(_) => {}
+                    None => break
+                }
+                j = range.next;
+            }
+        }
+
+        match hasarg {
+            Some(Yes) => i += 1,
+            Some(Maybe) if i + 1 < args.len() && !is_arg(args[i + 1].as_slice()) => i += 1,
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Derive a usage message for a program with subcommands, listing the
+/// subcommands after the options.
+pub fn subcommand_usage(brief: &str, opts: &[OptGroup], cmds: &[Subcommand]) -> String {
+    let rows = cmds.iter().map(|cmd| {
+        let mut row = " ".repeat(4);
+        row.push_str(cmd.name.as_slice());
+        row.push_char(' ');
+        append_desc(&mut row, cmd.desc.as_slice());
+        row
+    });
+
+    format!("{}\nCommands:\n{}\n", usage(brief, opts),
+            rows.collect::<Vec<String>>().connect("\n"))
+}
+
 
 /// Splits a string into substrings with possibly internal whitespace,
 /// each of them at most `lim` bytes long. The substrings have leading and trailing
@@ -935,7 +1177,10 @@ mod tests {
           UnrecognizedOption(_) => assert!(ft == UnrecognizedOption_),
           OptionMissing(_) => assert!(ft == OptionMissing_),
           OptionDuplicated(_) => assert!(ft == OptionDuplicated_),
-          UnexpectedArgument(_) => assert!(ft == UnexpectedArgument_)
+          UnexpectedArgument(_) => assert!(ft == UnexpectedArgument_),
+          ArgumentInvalid(..) => assert!(ft == ArgumentInvalid_),
+          UnrecognizedSubcommand(_) => assert!(ft == UnrecognizedSubcommand_),
+          SubcommandMissing => assert!(ft == SubcommandMissing_)
         }
     }
 
@@ -1577,4 +1822,192 @@ Options:
         debug!("generated: <<{}>>", generated_usage);
         assert_eq!(generated_usage, expected);
     }
+
+    #[test]
+    fn test_long_option_with_equals_in_argument() {
+        let opts = vec!(optmulti("D", "define", "define a value", "NAME=VALUE"));
+        let args = vec!("--define=a=b".to_string(), "--define=".to_string(),
+                        "-Dc=d".to_string());
+        match getopts(args.as_slice(), opts.as_slice()) {
+          Ok(ref m) => {
+            assert_eq!(m.opt_strs("define"),
+                       vec!("a=b".to_string(), "".to_string(), "c=d".to_string()));
+          }
+          _ => fail!()
+        }
+    }
+
+    #[test]
+    fn test_double_dash() {
+        let opts = vec!(optflag("a", "", "Desc"),
+                        optflag("b", "", "Desc"),
+                        optopt("c", "cc", "Desc", "VAL"));
+        let args = vec!("-a".to_string(), "--cc=--".to_string(), "--".to_string(),
+                        "-b".to_string(), "--cc=d".to_string(), "--".to_string());
+        match getopts(args.as_slice(), opts.as_slice()) {
+          Ok(ref m) => {
+            assert!(m.opt_present("a"));
+            assert!(!m.opt_present("b"));
+            assert_eq!(m.opt_str("c").unwrap(), "--".to_string());
+            assert_eq!(m.free, vec!("-b".to_string(), "--cc=d".to_string(),
+                                    "--".to_string()));
+          }
+          _ => fail!()
+        }
+    }
+
+    #[test]
+    fn test_opt_parse() {
+        let opts = vec!(optopt("j", "jobs", "Desc", "N"),
+                        optmulti("p", "port", "Desc", "PORT"),
+                        optflagopt("l", "level", "Desc", "LEVEL"));
+        let args = vec!("--jobs=4".to_string(), "-p".to_string(), "80".to_string(),
+                        "--port=8080".to_string(), "-l".to_string());
+        let m = getopts(args.as_slice(), opts.as_slice()).unwrap();
+
+        assert_eq!(m.opt_parse::<uint>("jobs"), Ok(Some(4)));
+        assert_eq!(m.opt_parse::<f64>("j"), Ok(Some(4.0)));
+        assert_eq!(m.opt_parse_or("jobs", 1u), Ok(4));
+        assert_eq!(m.opt_parse_all::<u16>("port"), Ok(vec!(80, 8080)));
+
+        // present without an argument
+        assert_eq!(m.opt_parse::<uint>("level"), Ok(None));
+        assert_eq!(m.opt_parse_or("level", 3u), Ok(3));
+
+        let args = vec!("--jobs=four".to_string(), "-p80".to_string(), "-p-1".to_string());
+        let m = getopts(args.as_slice(), opts.as_slice()).unwrap();
+        match m.opt_parse::<uint>("jobs") {
+          Err(f) => {
+            assert_eq!(f, ArgumentInvalid("jobs".to_string(), "four".to_string()));
+            assert_eq!(f.to_string(),
+                       "Invalid argument 'four' to option 'jobs'.".to_string());
+            check_fail_type(f, ArgumentInvalid_);
+          }
+          _ => fail!()
+        }
+        assert_eq!(m.opt_parse_or("jobs", 1u),
+                   Err(ArgumentInvalid("jobs".to_string(), "four".to_string())));
+        assert_eq!(m.opt_parse_all::<u16>("p"),
+                   Err(ArgumentInvalid("p".to_string(), "-1".to_string())));
+        assert_eq!(m.opt_parse_all::<i16>("p"), Ok(vec!(80, -1)));
+    }
+
+    fn subcommand_fixture() -> (Vec<OptGroup>, Vec<Subcommand>) {
+        let opts = vec!(optflag("v", "verbose", "Use verbose output"),
+                        optopt("C", "", "Change to DIR first", "DIR"),
+                        optflagopt("", "color", "Colorize output", "WHEN"));
+        let cmds = vec!(subcommand("build", "Compile the current project",
+                                   [optflag("r", "release", "Build in release mode")]),
+                        subcommand("test", "Run the tests",
+                                   [optopt("j", "jobs", "Number of jobs", "N")]));
+        (opts, cmds)
+    }
+
+    #[test]
+    fn test_subcommand() {
+        let (opts, cmds) = subcommand_fixture();
+        let args = vec!("-vC".to_string(), "build".to_string(), "test".to_string(),
+                        "-j".to_string(), "2".to_string(), "--".to_string(),
+                        "-v".to_string());
+        match getopts_subcommand(args.as_slice(), opts.as_slice(), cmds.as_slice()) {
+          Ok(ref m) => {
+            assert!(m.global.opt_present("verbose"));
+            assert_eq!(m.global.opt_str("C").unwrap(), "build".to_string());
+            assert!(m.global.free.is_empty());
+            assert_eq!(m.name, "test".to_string());
+            assert_eq!(m.matches.opt_parse::<uint>("jobs"), Ok(Some(2)));
+            assert_eq!(m.matches.free, vec!("-v".to_string()));
+          }
+          _ => fail!()
+        }
+
+        let args = vec!("--color=never".to_string(), "build".to_string(),
+                        "-r".to_string());
+        match getopts_subcommand(args.as_slice(), opts.as_slice(), cmds.as_slice()) {
+          Ok(ref m) => {
+            assert_eq!(m.global.opt_str("color").unwrap(), "never".to_string());
+            assert_eq!(m.name, "build".to_string());
+            assert!(m.matches.opt_present("release"));
+          }
+          _ => fail!()
+        }
+
+        // the subcommand options are not accepted before its name
+        let args = vec!("-r".to_string(), "build".to_string());
+        match getopts_subcommand(args.as_slice(), opts.as_slice(), cmds.as_slice()) {
+          Err(f) => check_fail_type(f, UnrecognizedOption_),
+          _ => fail!()
+        }
+    }
+
+    #[test]
+    fn test_subcommand_missing() {
+        let (opts, cmds) = subcommand_fixture();
+        let tests = [vec!(), vec!("-v".to_string()),
+                     vec!("-v".to_string(), "--".to_string(), "build".to_string())];
+        for args in tests.iter() {
+            match getopts_subcommand(args.as_slice(), opts.as_slice(), cmds.as_slice()) {
+              Err(f) => check_fail_type(f, SubcommandMissing_),
+              _ => fail!()
+            }
+        }
+
+        let args = vec!("-C".to_string());
+        match getopts_subcommand(args.as_slice(), opts.as_slice(), cmds.as_slice()) {
+          Err(f) => check_fail_type(f, ArgumentMissing_),
+          _ => fail!()
+        }
+
+        let args = vec!("-v".to_string(), "bulid".to_string(), "-r".to_string());
+        match getopts_subcommand(args.as_slice(), opts.as_slice(), cmds.as_slice()) {
+          Err(f) => {
+            assert_eq!(f.to_string(), "Unrecognized subcommand: 'bulid'.".to_string());
+            check_fail_type(f, UnrecognizedSubcommand_);
+          }
+          _ => fail!()
+        }
+    }
+
+    #[test]
+    fn test_subcommand_usage() {
+        let (opts, cmds) = subcommand_fixture();
+
+        let expected =
+"Usage: cargo [options] <command>
+
+Options:
+    -v --verbose        Use verbose output
+    -C DIR              Change to DIR first
+    --color [WHEN]      Colorize output
+
+Commands:
+    build               Compile the current project
+    test                Run the tests
+".to_string();
+
+        let usage = subcommand_usage("Usage: cargo [options] <command>",
+                                     opts.as_slice(), cmds.as_slice());
+
+        debug!("expected: <<{}>>", expected);
+        debug!("generated: <<{}>>", usage);
+        assert!(usage == expected);
+
+        let expected =
+"Usage: cargo build [options]
+
+Compile the current project
+
+Options:
+    -r --release        Build in release mode
+".to_string();
+
+        let usage = cmds[0].usage("cargo");
+
+        debug!("expected: <<{}>>", expected);
+        debug!("generated: <<{}>>", usage);
+        assert!(usage == expected);
+
+        assert_eq!(cmds[1].short_usage("cargo"),
+                   "Usage: cargo test [-j N]".to_string());
+    }
 }
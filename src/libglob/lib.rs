@@ -37,6 +37,7 @@
 
 use std::cell::Cell;
 use std::{cmp, os, path};
+use std::io;
 use std::io::fs;
 use std::path::is_sep;
 use std::string::String;
@@ -49,7 +50,13 @@ pub struct Paths {
     dir_patterns: Vec<Pattern>,
     require_dir: bool,
     options: MatchOptions,
+    walk: WalkOptions,
+    // the directory exclusion patterns are matched relative to, unless the
+    // pattern being walked is absolute
+    exclude_root: Option<Path>,
     todo: Vec<(Path,uint)>,
+    // brace expansions of the pattern still to be walked, last first
+    pending: Vec<String>,
 }
 
 ///
@@ -94,51 +101,127 @@ pub fn glob(pattern: &str) -> Paths {
  * the exception that `require_literal_separator` is always set to `true` regardless of the
  * value passed to this function.
  *
- * Paths are yielded in alphabetical order, as absolute paths.
+ * Paths are yielded in alphabetical order, as absolute paths. As in the shell, a
+ * pattern containing `{a,b}` is treated as the separate patterns it expands to, and
+ * the matches of each are yielded in turn. `**` does not descend into symbolic links;
+ * use `glob_with_walk` to change this.
  */
 pub fn glob_with(pattern: &str, options: MatchOptions) -> Paths {
-    #[cfg(windows)]
-    fn check_windows_verbatim(p: &Path) -> bool { path::windows::is_verbatim(p) }
-    #[cfg(not(windows))]
-    fn check_windows_verbatim(_: &Path) -> bool { false }
-
-    // calculate root this way to handle volume-relative Windows paths correctly
-    let mut root = os::getcwd();
-    let pat_root = Path::new(pattern).root_path();
-    if pat_root.is_some() {
-        if check_windows_verbatim(pat_root.as_ref().unwrap()) {
-            // FIXME: How do we want to handle verbatim paths? I'm inclined to return nothing,
-            // since we can't very well find all UNC shares with a 1-letter server name.
-            return Paths {
-                dir_patterns: Vec::new(),
-                require_dir: false,
-                options: options,
-                todo: Vec::new(),
-            };
-        }
-        root.push(pat_root.as_ref().unwrap());
+    glob_with_walk(pattern, options, WalkOptions::new())
+}
+
+/// Like `glob_with`, but with control over how directories are traversed.
+///
+/// Paths matching any of the `exclude` patterns, and everything below them, are
+/// skipped. Exclusion patterns are matched against paths relative to the current
+/// working directory if the pattern being globbed is relative, and against absolute
+/// paths otherwise, using the same match options as the pattern itself.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// # #![allow(deprecated)]
+/// use glob::{glob_with_walk, MatchOptions, Pattern, WalkOptions};
+///
+/// let walk = WalkOptions {
+///     exclude: vec![Pattern::new("target"), Pattern::new("**/.git")],
+///     follow_links: false,
+/// };
+/// for path in glob_with_walk("**/*.rs", MatchOptions::new(), walk) {
+///     println!("{}", path.display());
+/// }
+/// ```
+pub fn glob_with_walk(pattern: &str, options: MatchOptions, walk: WalkOptions) -> Paths {
+    let mut pending = expand_braces(pattern);
+    pending.reverse();
+    Paths {
+        dir_patterns: Vec::new(),
+        require_dir: false,
+        options: options,
+        walk: walk,
+        exclude_root: None,
+        todo: Vec::new(),
+        pending: pending,
     }
+}
 
-    let root_len = pat_root.map_or(0u, |p| p.as_vec().len());
-    let dir_patterns = pattern.slice_from(cmp::min(root_len, pattern.len()))
-                       .split_terminator(is_sep)
-                       .map(|s| Pattern::new(s))
-                       .collect::<Vec<Pattern>>();
-    let require_dir = pattern.chars().next_back().map(is_sep) == Some(true);
+impl Paths {
+    // Sets up the walk for a single pattern, which must not contain any brace
+    // alternations.
+    fn start(&mut self, pattern: &str) {
+        #[cfg(windows)]
+        fn check_windows_verbatim(p: &Path) -> bool { path::windows::is_verbatim(p) }
+        #[cfg(not(windows))]
+        fn check_windows_verbatim(_: &Path) -> bool { false }
+
+        self.dir_patterns = Vec::new();
+        self.todo = Vec::new();
+
+        // calculate root this way to handle volume-relative Windows paths correctly
+        let cwd = os::getcwd();
+        let mut root = cwd.clone();
+        let pat_root = Path::new(pattern).root_path();
+        if pat_root.is_some() {
+            if check_windows_verbatim(pat_root.as_ref().unwrap()) {
+                // FIXME: How do we want to handle verbatim paths? I'm inclined to return nothing,
+                // since we can't very well find all UNC shares with a 1-letter server name.
+                return;
+            }
+            root.push(pat_root.as_ref().unwrap());
+            self.exclude_root = None;
+        } else {
+            self.exclude_root = Some(cwd);
+        }
 
-    let mut todo = Vec::new();
-    if dir_patterns.len() > 0 {
-        // Shouldn't happen, but we're using -1 as a special index.
-        assert!(dir_patterns.len() < -1 as uint);
+        let root_len = pat_root.map_or(0u, |p| p.as_vec().len());
+        self.dir_patterns = pattern.slice_from(cmp::min(root_len, pattern.len()))
+                                   .split_terminator(is_sep)
+                                   .map(|s| Pattern::new(s))
+                                   .collect::<Vec<Pattern>>();
+        self.require_dir = pattern.chars().next_back().map(is_sep) == Some(true);
 
-        fill_todo(&mut todo, dir_patterns.as_slice(), 0, &root, options);
+        if self.dir_patterns.len() > 0 {
+            // Shouldn't happen, but we're using -1 as a special index.
+            assert!(self.dir_patterns.len() < -1 as uint);
+
+            fill_todo(&mut self.todo, self.dir_patterns.as_slice(), 0, &root, self.options);
+        }
     }
 
-    Paths {
-        dir_patterns: dir_patterns,
-        require_dir: require_dir,
-        options: options,
-        todo: todo,
+    // Whether `path`, or any directory it is in, matches an exclusion pattern.
+    fn is_excluded(&self, path: &Path) -> bool {
+        if self.walk.exclude.is_empty() {
+            return false;
+        }
+        let options = MatchOptions { require_literal_separator: true, .. self.options };
+        let mut path = match self.exclude_root {
+            Some(ref root) => match path.path_relative_from(root) {
+                Some(p) => p,
+                None => return false,
+            },
+            None => path.clone(),
+        };
+        loop {
+            if path.filename().is_none() {
+                return false;
+            }
+            if self.walk.exclude.iter().any(|p| p.matches_path_with(&path, options)) {
+                return true;
+            }
+            path.pop();
+        }
+    }
+
+    // Whether `**` should descend into `path`.
+    fn should_descend(&self, path: &Path) -> bool {
+        if self.walk.follow_links {
+            path.is_dir()
+        } else {
+            match fs::lstat(path) {
+                Ok(stat) => stat.kind == io::TypeDirectory,
+                Err(..) => false,
+            }
+        }
     }
 }
 
@@ -147,16 +230,43 @@ impl Iterator<Path> for Paths {
     fn next(&mut self) -> Option<Path> {
         loop {
             if self.dir_patterns.is_empty() || self.todo.is_empty() {
-                return None;
+                match self.pending.pop() {
+                    Some(pattern) => {
+                        self.start(pattern.as_slice());
+                        continue;
+                    }
+                    None => return None,
+                }
             }
 
             let (path,idx) = self.todo.pop().unwrap();
+            if self.is_excluded(&path) {
+                continue;
+            }
             // idx -1: was already checked by fill_todo, maybe path was '.' or
             // '..' that we can't match here because of normalization.
             if idx == -1 as uint {
                 if self.require_dir && !path.is_dir() { continue; }
                 return Some(path);
             }
+            let is_last = idx == self.dir_patterns.len() - 1;
+
+            if self.dir_patterns[idx].is_recursive() {
+                // `path` is one more directory level matched by `**`
+                let hidden = path.filename_str().map_or(false, |s| s.starts_with("."));
+                if hidden && self.options.require_literal_leading_dot {
+                    continue;
+                }
+                if self.should_descend(&path) {
+                    fill_todo(&mut self.todo, self.dir_patterns.as_slice(),
+                              idx, &path, self.options);
+                }
+                if is_last && (!self.require_dir || path.is_dir()) {
+                    return Some(path);
+                }
+                continue;
+            }
+
             let ref pattern = self.dir_patterns[idx];
 
             if pattern.matches_with(match path.filename_str() {
@@ -168,7 +278,7 @@ impl Iterator<Path> for Paths {
                 }
                 Some(x) => x
             }, self.options) {
-                if idx == self.dir_patterns.len() - 1 {
+                if is_last {
                     // it is not possible for a pattern to match a directory *AND* its children
                     // so we don't need to check the children
 
@@ -208,8 +318,10 @@ enum PatternToken {
     Char(char),
     AnyChar,
     AnySequence,
+    AnyRecursiveSequence,
     AnyWithin(Vec<CharSpecifier> ),
-    AnyExcept(Vec<CharSpecifier> )
+    AnyExcept(Vec<CharSpecifier> ),
+    AnyOf(Vec<Pattern> )
 }
 
 #[deriving(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -245,6 +357,16 @@ impl Pattern {
      *
      * When a `[` does not have a closing `]` before the end of the string then
      * the `[` will be treated literally.
+     *
+     * `{a,b,...}` matches any one of the comma-separated alternatives, each of
+     * which may itself be a pattern, e.g. `*.{rs,md}`. Braces that do not
+     * contain a top-level comma are treated literally.
+     *
+     * A `**` making up a whole path component matches zero or more complete
+     * path components, so `src/**/*.rs` matches both `src/lib.rs` and
+     * `src/a/b/lib.rs`. With `require_literal_leading_dot` set, it does not
+     * match components starting with a `.`. Anywhere else `**` is equivalent
+     * to `*`.
      */
     pub fn new(pattern: &str) -> Pattern {
 
@@ -259,41 +381,57 @@ impl Pattern {
                     i += 1;
                 }
                 '*' => {
-                    // *, **, ***, ****, ... are all equivalent
+                    let start = i;
                     while i < chars.len() && chars[i] == '*' {
                         i += 1;
                     }
-                    tokens.push(AnySequence);
+                    let whole_component = (start == 0 || is_sep(chars[start - 1])) &&
+                                          (i == chars.len() || is_sep(chars[i]));
+                    if i - start == 2 && whole_component {
+                        // the separator after `**` is part of what it matches
+                        if i < chars.len() {
+                            i += 1;
+                        }
+                        tokens.push(AnyRecursiveSequence);
+                    } else {
+                        // otherwise *, **, ***, ****, ... are all equivalent
+                        tokens.push(AnySequence);
+                    }
                 }
                 '[' => {
-
-                    if i <= chars.len() - 4 && chars[i + 1] == '!' {
-                        match chars.slice_from(i + 3).position_elem(&']') {
-                            None => (),
-                            Some(j) => {
-                                let chars = chars.slice(i + 2, i + 3 + j);
-                                let cs = parse_char_specifiers(chars);
-                                tokens.push(AnyExcept(cs));
-                                i += j + 4;
-                                continue;
-                            }
+                    match char_class_end(chars.as_slice(), i) {
+                        Some(end) if chars[i + 1] == '!' => {
+                            let cs = parse_char_specifiers(chars.slice(i + 2, end));
+                            tokens.push(AnyExcept(cs));
+                            i = end + 1;
+                        }
+                        Some(end) => {
+                            let cs = parse_char_specifiers(chars.slice(i + 1, end));
+                            tokens.push(AnyWithin(cs));
+                            i = end + 1;
+                        }
+                        None => {
+                            // if we get here then this is not a valid range pattern
+                            tokens.push(Char('['));
+                            i += 1;
                         }
                     }
-                    else if i <= chars.len() - 3 && chars[i + 1] != '!' {
-                        match chars.slice_from(i + 2).position_elem(&']') {
-                            None => (),
-                            Some(j) => {
-                                let cs = parse_char_specifiers(chars.slice(i + 1, i + 2 + j));
-                                tokens.push(AnyWithin(cs));
-                                i += j + 3;
-                                continue;
-                            }
+                }
+                '{' => {
+                    match brace_alternatives(chars.as_slice(), i) {
+                        Some((alternatives, end)) => {
+                            let alternatives = alternatives.iter().map(|alt| {
+                                Pattern::new(String::from_chars(*alt).as_slice())
+                            }).collect();
+                            tokens.push(AnyOf(alternatives));
+                            i = end + 1;
+                        }
+                        None => {
+                            // braces without a comma between them are literal
+                            tokens.push(Char('{'));
+                            i += 1;
                         }
                     }
-
-                    // if we get here then this is not a valid range pattern
-                    tokens.push(Char('['));
-                    i += 1;
                 }
                 c => {
                     tokens.push(Char(c));
@@ -315,7 +453,7 @@ impl Pattern {
         for c in s.chars() {
             match c {
                 // note that ! does not need escaping because it is only special inside brackets
-                '?' | '*' | '[' | ']' => {
+                '?' | '*' | '[' | ']' | '{' | '}' => {
                     escaped.push_char('[');
                     escaped.push_char(c);
                     escaped.push_char(']');
@@ -375,6 +513,11 @@ impl Pattern {
         })
     }
 
+    // Whether this pattern is a `**` path component on its own.
+    fn is_recursive(&self) -> bool {
+        self.tokens.len() == 1 && self.tokens[0] == AnyRecursiveSequence
+    }
+
     fn matches_from(&self,
                     prev_char: Option<char>,
                     mut file: &str,
@@ -410,6 +553,48 @@ impl Pattern {
                         file = next;
                     }
                 }
+                AnyRecursiveSequence => {
+                    // `**` matches whole path components, so the rest of the
+                    // pattern is only tried at the start of each component.
+                    loop {
+                        match self.matches_from(prev_char.get(), file, i + ti + 1, options) {
+                            SubPatternDoesntMatch => (), // keep trying
+                            m => return m,
+                        }
+
+                        if file.is_empty() {
+                            return EntirePatternDoesntMatch;
+                        }
+                        if options.require_literal_leading_dot && file.starts_with(".") {
+                            return SubPatternDoesntMatch;
+                        }
+
+                        match file.find(is_sep) {
+                            Some(j) => {
+                                prev_char.set(Some(file.char_at(j)));
+                                file = file.slice_from(j + 1);
+                            }
+                            // a trailing `**` matches everything below it
+                            None if i + ti + 1 == self.tokens.len() => return Match,
+                            None => return SubPatternDoesntMatch,
+                        }
+                    }
+                }
+                AnyOf(ref alternatives) => {
+                    let rest = self.tokens.slice_from(i + ti + 1);
+                    let mut result = EntirePatternDoesntMatch;
+                    for alternative in alternatives.iter() {
+                        let mut tokens = alternative.tokens.clone();
+                        tokens.push_all(rest);
+                        let pattern = Pattern { tokens: tokens };
+                        match pattern.matches_from(prev_char.get(), file, 0, options) {
+                            Match => return Match,
+                            SubPatternDoesntMatch => result = SubPatternDoesntMatch,
+                            EntirePatternDoesntMatch => (),
+                        }
+                    }
+                    return result;
+                }
                 _ => {
                     if file.is_empty() {
                         return EntirePatternDoesntMatch;
@@ -436,7 +621,7 @@ impl Pattern {
                         Char(c2) => {
                             chars_eq(c, c2, options.case_sensitive)
                         }
-                        AnySequence => {
+                        AnySequence | AnyRecursiveSequence | AnyOf(..) => {
                             unreachable!()
                         }
                     };
@@ -488,6 +673,20 @@ fn fill_todo(todo: &mut Vec<(Path, uint)>, patterns: &[Pattern], idx: uint, path
 
     let pattern = &patterns[idx];
 
+    if pattern.is_recursive() {
+        // Directories below `path` are queued up to be matched by `**` first,
+        // so that matches at this level come out of the iterator before them.
+        match list_dir_sorted(path) {
+            Some(entries) => todo.extend(entries.move_iter().map(|x|(x, idx))),
+            None => {}
+        }
+        // `**` matching no directories at all
+        if idx + 1 < patterns.len() {
+            fill_todo(todo, patterns, idx + 1, path, options);
+        }
+        return;
+    }
+
     match pattern_as_str(pattern) {
         Some(s) => {
             // This pattern component doesn't have any metacharacters, so we
@@ -524,6 +723,94 @@ fn fill_todo(todo: &mut Vec<(Path, uint)>, patterns: &[Pattern], idx: uint, path
     }
 }
 
+// Returns the index of the `]` closing the character class that starts with
+// the `[` at `chars[i]`, if it is a valid one.
+fn char_class_end(chars: &[char], i: uint) -> Option<uint> {
+    if i + 4 <= chars.len() && chars[i + 1] == '!' {
+        chars.slice_from(i + 3).position_elem(&']').map(|j| i + 3 + j)
+    } else if i + 3 <= chars.len() && chars[i + 1] != '!' {
+        chars.slice_from(i + 2).position_elem(&']').map(|j| i + 2 + j)
+    } else {
+        None
+    }
+}
+
+// If the `{` at `chars[i]` starts a brace alternation, returns its
+// comma-separated alternatives and the index of the closing `}`.
+fn brace_alternatives<'a>(chars: &'a [char], i: uint) -> Option<(Vec<&'a [char]>, uint)> {
+    let mut alternatives = Vec::new();
+    let mut depth = 0u;
+    let mut start = i + 1;
+    let mut j = i + 1;
+    while j < chars.len() {
+        match chars[j] {
+            '[' => {
+                match char_class_end(chars, j) {
+                    Some(end) => {
+                        j = end + 1;
+                        continue;
+                    }
+                    None => {}
+                }
+            }
+            '{' => depth += 1,
+            '}' if depth > 0 => depth -= 1,
+            '}' => {
+                if alternatives.is_empty() {
+                    return None;
+                }
+                alternatives.push(chars.slice(start, j));
+                return Some((alternatives, j));
+            }
+            ',' if depth == 0 => {
+                alternatives.push(chars.slice(start, j));
+                start = j + 1;
+            }
+            _ => {}
+        }
+        j += 1;
+    }
+    None
+}
+
+// Expands the first brace alternation in `pattern`, and recursively those in
+// the results, giving patterns which only contain literal braces.
+fn expand_braces(pattern: &str) -> Vec<String> {
+    let chars = pattern.chars().collect::<Vec<_>>();
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '[' => {
+                match char_class_end(chars.as_slice(), i) {
+                    Some(end) => {
+                        i = end + 1;
+                        continue;
+                    }
+                    None => {}
+                }
+            }
+            '{' => {
+                match brace_alternatives(chars.as_slice(), i) {
+                    Some((alternatives, end)) => {
+                        let prefix = String::from_chars(chars.slice_to(i));
+                        let suffix = String::from_chars(chars.slice_from(end + 1));
+                        let mut expanded = Vec::new();
+                        for alt in alternatives.iter() {
+                            let s = format!("{}{}{}", prefix, String::from_chars(*alt), suffix);
+                            expanded.extend(expand_braces(s.as_slice()).move_iter());
+                        }
+                        return expanded;
+                    }
+                    None => {}
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    vec![pattern.to_string()]
+}
+
 fn parse_char_specifiers(s: &[char]) -> Vec<CharSpecifier> {
     let mut cs = Vec::new();
     let mut i = 0;
@@ -647,10 +934,48 @@ impl MatchOptions {
 
 }
 
+/**
+ * Configuration options for the directory traversal done by `glob_with_walk(..)`
+ */
+#[deriving(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct WalkOptions {
+
+    /**
+     * Paths matching any of these patterns are not yielded, and directories
+     * matching them are not searched.
+     */
+    pub exclude: Vec<Pattern>,
+
+    /**
+     * Whether `**` descends into symbolic links to directories. Following
+     * links can lead to the same files being found more than once, or to
+     * cycles.
+     */
+    pub follow_links: bool
+}
+
+impl WalkOptions {
+
+    /**
+     * Constructs a new `WalkOptions` which excludes nothing and does not
+     * follow symbolic links. This is used when calling functions that do not
+     * take an explicit `WalkOptions` parameter.
+     */
+    pub fn new() -> WalkOptions {
+        WalkOptions {
+            exclude: Vec::new(),
+            follow_links: false
+        }
+    }
+
+}
+
 #[cfg(test)]
 mod test {
+    use std::io;
+    use std::io::{fs, File, TempDir};
     use std::os;
-    use super::{glob, Pattern, MatchOptions};
+    use super::{glob, glob_with_walk, expand_braces, Pattern, MatchOptions, WalkOptions};
 
     #[test]
     fn test_absolute_pattern() {
@@ -888,4 +1213,180 @@ mod test {
         // tests that / and \ are considered equivalent on windows
         assert!(Pattern::new("a/b").matches_path(&Path::new("a/b")));
     }
+
+    #[test]
+    fn test_recursive_wildcard() {
+        let pat = Pattern::new("some/**/needle.txt");
+        assert!(pat.matches("some/needle.txt"));
+        assert!(pat.matches("some/one/needle.txt"));
+        assert!(pat.matches("some/one/two/needle.txt"));
+        assert!(!pat.matches("some/other/notthis.txt"));
+        assert!(!pat.matches("someneedle.txt"));
+        assert!(!pat.matches("some/one/xneedle.txt"));
+
+        let pat = Pattern::new("**");
+        assert!(pat.matches("abcde"));
+        assert!(pat.matches(".asdf"));
+        assert!(pat.matches("/x/.asdf"));
+
+        let pat = Pattern::new("**/test");
+        assert!(pat.matches("one/two/test"));
+        assert!(pat.matches("one/test"));
+        assert!(pat.matches("test"));
+        assert!(!pat.matches("one/notthis"));
+        assert!(!pat.matches("onetest"));
+
+        let pat = Pattern::new("/**/test");
+        assert!(pat.matches("/one/two/test"));
+        assert!(pat.matches("/test"));
+        assert!(!pat.matches("test"));
+
+        let pat = Pattern::new("some/**");
+        assert!(pat.matches("some/a"));
+        assert!(pat.matches("some/a/b/c"));
+        assert!(!pat.matches("other/a"));
+
+        // `**` is only special as a whole path component
+        let pat = Pattern::new("a**b");
+        assert!(pat.matches("axxb"));
+        assert!(pat.matches("ab"));
+    }
+
+    #[test]
+    fn test_recursive_wildcard_require_literal_leading_dot() {
+        let options = MatchOptions {
+            case_sensitive: true,
+            require_literal_separator: false,
+            require_literal_leading_dot: true
+        };
+
+        let pat = Pattern::new("**/*.rs");
+        assert!(pat.matches_with("src/lib.rs", options));
+        assert!(!pat.matches_with(".git/lib.rs", options));
+        assert!(!pat.matches_with("src/.hidden/lib.rs", options));
+        assert!(pat.matches("src/.hidden/lib.rs"));
+
+        assert!(Pattern::new("**/.git").matches_with("src/.git", options));
+        assert!(!Pattern::new("a/**").matches_with("a/.b", options));
+    }
+
+    #[test]
+    fn test_brace_alternatives() {
+        let pat = Pattern::new("*.{rs,md}");
+        assert!(pat.matches("lib.rs"));
+        assert!(pat.matches("README.md"));
+        assert!(!pat.matches("lib.txt"));
+        assert!(!pat.matches("lib.rsmd"));
+
+        let pat = Pattern::new("{a,b{c,d}}x");
+        assert!(pat.matches("ax"));
+        assert!(pat.matches("bcx"));
+        assert!(pat.matches("bdx"));
+        assert!(!pat.matches("bx"));
+
+        let pat = Pattern::new("x{,y}");
+        assert!(pat.matches("x"));
+        assert!(pat.matches("xy"));
+
+        // a single alternative, or an unclosed brace, is literal
+        assert!(Pattern::new("{a}").matches("{a}"));
+        assert!(Pattern::new("{a,b").matches("{a,b"));
+        assert!(Pattern::new("[{]a,b}").matches("{a,b}"));
+
+        let options = MatchOptions {
+            case_sensitive: false,
+            require_literal_separator: false,
+            require_literal_leading_dot: false
+        };
+        assert!(Pattern::new("{A,b}").matches_with("a", options));
+
+        let s = "{a,b}";
+        assert_eq!(Pattern::escape(s), "[{]a,b[}]".to_string());
+        assert!(Pattern::new(Pattern::escape(s).as_slice()).matches(s));
+        assert!(!Pattern::new(Pattern::escape(s).as_slice()).matches("a"));
+    }
+
+    #[test]
+    fn test_expand_braces() {
+        assert_eq!(expand_braces("a{b,c}d{e,f}"),
+                   vec!["abde".to_string(), "abdf".to_string(),
+                        "acde".to_string(), "acdf".to_string()]);
+        assert_eq!(expand_braces("{src/a,b}/*.rs"),
+                   vec!["src/a/*.rs".to_string(), "b/*.rs".to_string()]);
+        assert_eq!(expand_braces("[{]a,b}"), vec!["[{]a,b}".to_string()]);
+        assert_eq!(expand_braces("{a}"), vec!["{a}".to_string()]);
+    }
+
+    fn make_tree(files: &[&str]) -> TempDir {
+        let dir = TempDir::new("glob-tests").unwrap();
+        for file in files.iter() {
+            let path = dir.path().join(*file);
+            fs::mkdir_recursive(&path.dir_path(), io::UserRWX).unwrap();
+            File::create(&path).unwrap();
+        }
+        dir
+    }
+
+    fn walk_names(root: &Path, pattern: &str, options: MatchOptions,
+                  walk: WalkOptions) -> Vec<String> {
+        // FIXME (#9639): This needs to handle non-utf8 paths
+        let root_str = Pattern::escape(root.as_str().unwrap());
+        let pattern = format!("{}/{}", root_str, pattern);
+        let mut names = glob_with_walk(pattern.as_slice(), options, walk).map(|p| {
+            p.path_relative_from(root).unwrap().as_str().unwrap().replace("\\", "/")
+        }).collect::<Vec<String>>();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_glob_recursive() {
+        let dir = make_tree(&["a/b/c.rs", "a/d.rs", "a/e.txt", "f.rs",
+                              "target/g.rs", ".hidden/h.rs"]);
+        let root = dir.path();
+
+        assert_eq!(walk_names(root, "**/*.rs", MatchOptions::new(), WalkOptions::new()),
+                   vec![".hidden/h.rs".to_string(), "a/b/c.rs".to_string(),
+                        "a/d.rs".to_string(), "f.rs".to_string(), "target/g.rs".to_string()]);
+
+        assert_eq!(walk_names(root, "a/**", MatchOptions::new(), WalkOptions::new()),
+                   vec!["a/b".to_string(), "a/b/c.rs".to_string(),
+                        "a/d.rs".to_string(), "a/e.txt".to_string()]);
+
+        assert_eq!(walk_names(root, "**/", MatchOptions::new(), WalkOptions::new()),
+                   vec![".hidden".to_string(), "a".to_string(), "a/b".to_string(),
+                        "target".to_string()]);
+
+        assert_eq!(walk_names(root, "{a/b,target}/*.rs", MatchOptions::new(),
+                              WalkOptions::new()),
+                   vec!["a/b/c.rs".to_string(), "target/g.rs".to_string()]);
+
+        let options = MatchOptions {
+            case_sensitive: true,
+            require_literal_separator: false,
+            require_literal_leading_dot: true
+        };
+        let walk = WalkOptions {
+            exclude: vec![Pattern::new("**/target"), Pattern::new("**/*.txt")],
+            follow_links: false
+        };
+        assert_eq!(walk_names(root, "**/*", options, walk),
+                   vec!["a".to_string(), "a/b".to_string(), "a/b/c.rs".to_string(),
+                        "a/d.rs".to_string(), "f.rs".to_string()]);
+    }
+
+    #[test]
+    #[ignore(cfg(windows))]
+    fn test_glob_recursive_symlinks() {
+        let dir = make_tree(&["a/b.rs"]);
+        let root = dir.path();
+        fs::symlink(&root.join("a"), &root.join("link")).unwrap();
+
+        assert_eq!(walk_names(root, "**/*.rs", MatchOptions::new(), WalkOptions::new()),
+                   vec!["a/b.rs".to_string()]);
+
+        let walk = WalkOptions { exclude: Vec::new(), follow_links: true };
+        assert_eq!(walk_names(root, "**/*.rs", MatchOptions::new(), walk),
+                   vec!["a/b.rs".to_string(), "link/b.rs".to_string()]);
+    }
 }
@@ -30,6 +30,12 @@ algorithm to use, etc). The current intention of this library is to
 emit a human-readable .dot file with very regular structure suitable
 for easy post-processing.
 
+Beyond labels, the `Labeller` trait has optional methods for the most
+common attributes: the graph can be rendered as an undirected `graph`
+rather than a `digraph`, nodes and edges can be given shapes, colors
+and styles, and nodes can be grouped into (possibly nested) clusters,
+which are rendered as `subgraph cluster_*` blocks.
+
 # Examples
 
 The first example uses a very simple graph representation: a list of
@@ -275,6 +281,7 @@ pub fn main() {
        html_favicon_url = "http://www.rust-lang.org/favicon.ico",
        html_root_url = "http://doc.rust-lang.org/master/")]
 
+use std::collections::HashMap;
 use std::io;
 use std::str;
 use self::maybe_owned_vec::MaybeOwnedVector;
@@ -302,6 +309,136 @@ pub enum LabelText<'a> {
     EscStr(str::MaybeOwned<'a>),
 }
 
+/// Whether a graph is rendered as a `digraph`, whose edges are drawn as
+/// arrows from their source to their target, or as an undirected `graph`.
+#[deriving(Clone, PartialEq, Show)]
+pub enum Kind {
+    /// A `digraph`, with edges written `source -> target`.
+    Directed,
+    /// A `graph`, with edges written `source -- target`.
+    Undirected,
+}
+
+impl Kind {
+    /// The keyword starting the DOT description of a graph of this kind.
+    pub fn keyword(&self) -> &'static str {
+        match *self {
+            Directed => "digraph",
+            Undirected => "graph",
+        }
+    }
+
+    /// The operator between the two ends of an edge in a graph of this kind.
+    pub fn edgeop(&self) -> &'static str {
+        match *self {
+            Directed => "->",
+            Undirected => "--",
+        }
+    }
+}
+
+/// The style used to draw a node, an edge or a cluster:
+/// http://www.graphviz.org/content/attrs#kstyle
+///
+/// Not every style applies to everything; for example `Filled` has no
+/// effect on edges, and `Diagonals` only applies to nodes.
+#[deriving(Clone, PartialEq, Show)]
+pub enum Style {
+    /// Continuous lines; the default.
+    Solid,
+    /// Dashed lines.
+    Dashed,
+    /// Dotted lines.
+    Dotted,
+    /// Lines drawn thicker than usual.
+    Bold,
+    /// A box shape with rounded corners.
+    Rounded,
+    /// A box shape with small diagonal lines across its corners.
+    Diagonals,
+    /// A shape filled with its fill color.
+    Filled,
+    /// A rectangular shape filled with vertical stripes of its colors.
+    Striped,
+    /// An elliptical shape filled with wedges of its colors.
+    Wedged,
+    /// Not drawn at all, though still laid out.
+    Invisible,
+}
+
+impl Style {
+    /// The name of the style in a `style` attribute.
+    pub fn as_slice(&self) -> &'static str {
+        match *self {
+            Solid => "solid",
+            Dashed => "dashed",
+            Dotted => "dotted",
+            Bold => "bold",
+            Rounded => "rounded",
+            Diagonals => "diagonals",
+            Filled => "filled",
+            Striped => "striped",
+            Wedged => "wedged",
+            Invisible => "invis",
+        }
+    }
+}
+
+/// The direction in which successive ranks of nodes are laid out:
+/// http://www.graphviz.org/content/attrs#krankdir
+#[deriving(Clone, PartialEq, Show)]
+pub enum RankDir {
+    /// Ranks run from the top of the drawing down; the default.
+    TopToBottom,
+    /// Ranks run from the left of the drawing to the right.
+    LeftToRight,
+    /// Ranks run from the bottom of the drawing up.
+    BottomToTop,
+    /// Ranks run from the right of the drawing to the left.
+    RightToLeft,
+}
+
+impl RankDir {
+    /// The value of the direction in a `rankdir` attribute.
+    pub fn as_slice(&self) -> &'static str {
+        match *self {
+            TopToBottom => "TB",
+            LeftToRight => "LR",
+            BottomToTop => "BT",
+            RightToLeft => "RL",
+        }
+    }
+}
+
+/// Constrains the ranks of the nodes directly inside a cluster:
+/// http://www.graphviz.org/content/attrs#krank
+#[deriving(Clone, PartialEq, Show)]
+pub enum Rank {
+    /// All the nodes are on the same rank.
+    SameRank,
+    /// All the nodes are on the minimum rank.
+    MinRank,
+    /// All the nodes are on the minimum rank, and are the only ones there.
+    SourceRank,
+    /// All the nodes are on the maximum rank.
+    MaxRank,
+    /// All the nodes are on the maximum rank, and are the only ones there.
+    SinkRank,
+}
+
+impl Rank {
+    /// The value of the constraint in a `rank` attribute.
+    pub fn as_slice(&self) -> &'static str {
+        match *self {
+            SameRank => "same",
+            MinRank => "min",
+            SourceRank => "source",
+            MaxRank => "max",
+            SinkRank => "sink",
+        }
+    }
+}
+
 // There is a tension in the design of the labelling API.
 //
 // For example, I considered making a `Labeller<T>` trait that
@@ -389,10 +526,26 @@ impl<'a> Id<'a> {
 /// The graph instance is responsible for providing the DOT compatible
 /// identifiers for the nodes and (optionally) rendered labels for the nodes and
 /// edges, as well as an identifier for the graph itself.
+///
+/// The remaining methods optionally provide attributes for drawing the graph:
+/// whether it is directed, the shapes, colors and styles of its nodes and
+/// edges, and the clusters the nodes are grouped into. The defaults leave
+/// all of these to Graphviz.
 pub trait Labeller<'a,N,E> {
     /// Must return a DOT compatible identifier naming the graph.
     fn graph_id(&'a self) -> Id<'a>;
 
+    /// Whether the graph is rendered as a directed or an undirected graph;
+    /// the default is directed.
+    fn kind(&'a self) -> Kind {
+        Directed
+    }
+
+    /// The direction in which the graph is laid out, if not top to bottom.
+    fn rank_dir(&'a self) -> Option<RankDir> {
+        None
+    }
+
     /// Maps `n` to a unique identifier with respect to `self`. The
     /// implementer is responsible for ensuring that the returned name
     /// is a valid DOT identifier.
@@ -412,6 +565,83 @@ pub trait Labeller<'a,N,E> {
         let _ignored = e;
         LabelStr(str::Slice(""))
     }
+
+    /// Maps `n` to the name of its shape, such as `box` or `ellipse`:
+    /// http://www.graphviz.org/content/node-shapes
+    fn node_shape(&'a self, n: &N) -> Option<LabelText<'a>> {
+        let _ignored = n;
+        None
+    }
+
+    /// Maps `n` to the color it is drawn in, either a color name such as
+    /// `red` or an RGB value such as `#ff0000`.
+    fn node_color(&'a self, n: &N) -> Option<LabelText<'a>> {
+        let _ignored = n;
+        None
+    }
+
+    /// Maps `n` to the style it is drawn in.
+    fn node_style(&'a self, n: &N) -> Option<Style> {
+        let _ignored = n;
+        None
+    }
+
+    /// Maps `e` to the color it is drawn in, as for `node_color`.
+    fn edge_color(&'a self, e: &E) -> Option<LabelText<'a>> {
+        let _ignored = e;
+        None
+    }
+
+    /// Maps `e` to the style it is drawn in.
+    fn edge_style(&'a self, e: &E) -> Option<Style> {
+        let _ignored = e;
+        None
+    }
+
+    /// Maps `n` to the innermost cluster containing it, if any. Each
+    /// cluster is rendered as a `subgraph cluster_<id>`, which Graphviz
+    /// draws as a box around its nodes and nested clusters.
+    ///
+    /// Clusters are identified by their `Id`, which must be unique among
+    /// clusters but may coincide with the id of a node.
+    fn node_cluster(&'a self, n: &N) -> Option<Id<'a>> {
+        let _ignored = n;
+        None
+    }
+
+    /// Maps cluster `c` to the cluster directly containing it, if any.
+    /// A cluster must not contain itself, directly or indirectly.
+    fn cluster_parent(&'a self, c: &Id<'a>) -> Option<Id<'a>> {
+        let _ignored = c;
+        None
+    }
+
+    /// Maps cluster `c` to a label that will be used in the rendered
+    /// output; the default is the empty string.
+    fn cluster_label(&'a self, c: &Id<'a>) -> LabelText<'a> {
+        let _ignored = c;
+        LabelStr(str::Slice(""))
+    }
+
+    /// Maps cluster `c` to the color its box is drawn in, as for
+    /// `node_color`.
+    fn cluster_color(&'a self, c: &Id<'a>) -> Option<LabelText<'a>> {
+        let _ignored = c;
+        None
+    }
+
+    /// Maps cluster `c` to the style its box is drawn in.
+    fn cluster_style(&'a self, c: &Id<'a>) -> Option<Style> {
+        let _ignored = c;
+        None
+    }
+
+    /// Maps cluster `c` to a constraint on the ranks of the nodes directly
+    /// inside it.
+    fn cluster_rank(&'a self, c: &Id<'a>) -> Option<Rank> {
+        let _ignored = c;
+        None
+    }
 }
 
 impl<'a> LabelText<'a> {
@@ -475,7 +705,8 @@ pub type Edges<'a,E> = MaybeOwnedVector<'a,E>;
 
 /// GraphWalk is an abstraction over a directed graph = (nodes,edges)
 /// made up of node handles `N` and edge handles `E`, where each `E`
-/// can be mapped to its source and target nodes.
+/// can be mapped to its source and target nodes. (For an undirected
+/// graph, which end of an edge is the source is up to the implementer.)
 ///
 /// The lifetime parameter `'a` is exposed in this trait (rather than
 /// introduced as a generic parameter on each method declaration) so
@@ -497,48 +728,216 @@ pub trait GraphWalk<'a, N, E> {
     fn target(&'a self, edge: &E) -> N;
 }
 
-/// Renders directed graph `g` into the writer `w` in DOT syntax.
+/// Renders graph `g` into the writer `w` in DOT syntax.
 /// (Main entry point for the library.)
 pub fn render<'a, N:'a, E:'a, G:Labeller<'a,N,E>+GraphWalk<'a,N,E>, W:Writer>(
               g: &'a G,
               w: &mut W) -> io::IoResult<()>
 {
-    fn writeln<W:Writer>(w: &mut W, arg: &[&str]) -> io::IoResult<()> {
-        for &s in arg.iter() { try!(w.write_str(s)); }
-        w.write_char('\n')
+    let kind = g.kind();
+    try!(writeln(w, [kind.keyword(), " ", g.graph_id().as_slice(), " {"]));
+    match g.rank_dir() {
+        Some(dir) => {
+            try!(indent(w, 1));
+            try!(writeln(w, ["rankdir=\"", dir.as_slice(), "\";"]));
+        }
+        None => {}
     }
 
-    fn indent<W:Writer>(w: &mut W) -> io::IoResult<()> {
-        w.write_str("    ")
+    let nodes = g.nodes();
+    let clusters = Clusters::new(g, nodes.as_slice());
+    for &i in clusters.unclustered.iter() {
+        try!(render_node(g, &nodes.as_slice()[i], w, 1));
     }
-
-    try!(writeln(w, ["digraph ", g.graph_id().as_slice(), " {"]));
-    for n in g.nodes().iter() {
-        try!(indent(w));
-        let id = g.node_id(n);
-        let escaped = g.node_label(n).escape();
-        try!(writeln(w, [id.as_slice(),
-                         "[label=\"", escaped.as_slice(), "\"];"]));
+    for &c in clusters.roots.iter() {
+        try!(render_cluster(g, nodes.as_slice(), &clusters, c, w, 1));
     }
 
     for e in g.edges().iter() {
-        let escaped_label = g.edge_label(e).escape();
-        try!(indent(w));
+        let mut attrs = vec!(attr("label", g.edge_label(e).escape().as_slice()));
+        match g.edge_color(e) {
+            Some(color) => attrs.push(attr("color", color.escape().as_slice())),
+            None => {}
+        }
+        match g.edge_style(e) {
+            Some(style) => attrs.push(attr("style", style.as_slice())),
+            None => {}
+        }
+        try!(indent(w, 1));
         let source = g.source(e);
         let target = g.target(e);
         let source_id = g.node_id(&source);
         let target_id = g.node_id(&target);
-        try!(writeln(w, [source_id.as_slice(), " -> ", target_id.as_slice(),
-                         "[label=\"", escaped_label.as_slice(), "\"];"]));
+        try!(writeln(w, [source_id.as_slice(), " ", kind.edgeop(), " ", target_id.as_slice(),
+                         "[", attrs.as_slice().connect(", ").as_slice(), "];"]));
     }
 
     writeln(w, ["}"])
 }
 
+fn writeln<W:Writer>(w: &mut W, arg: &[&str]) -> io::IoResult<()> {
+    for &s in arg.iter() { try!(w.write_str(s)); }
+    w.write_char('\n')
+}
+
+fn indent<W:Writer>(w: &mut W, depth: uint) -> io::IoResult<()> {
+    for _ in range(0, depth) {
+        try!(w.write_str("    "));
+    }
+    Ok(())
+}
+
+// Formats a `name="value"` attribute; `value` must already be escaped.
+fn attr(name: &str, value: &str) -> String {
+    format!("{}=\"{}\"", name, value)
+}
+
+fn render_node<'a, N, E, G:Labeller<'a,N,E>, W:Writer>(g: &'a G, n: &N, w: &mut W,
+                                                        depth: uint) -> io::IoResult<()> {
+    let mut attrs = vec!(attr("label", g.node_label(n).escape().as_slice()));
+    match g.node_shape(n) {
+        Some(shape) => attrs.push(attr("shape", shape.escape().as_slice())),
+        None => {}
+    }
+    match g.node_color(n) {
+        Some(color) => attrs.push(attr("color", color.escape().as_slice())),
+        None => {}
+    }
+    match g.node_style(n) {
+        Some(style) => attrs.push(attr("style", style.as_slice())),
+        None => {}
+    }
+    try!(indent(w, depth));
+    let id = g.node_id(n);
+    writeln(w, [id.as_slice(), "[", attrs.as_slice().connect(", ").as_slice(), "];"])
+}
+
+fn render_cluster<'a, N, E, G:Labeller<'a,N,E>, W:Writer>(g: &'a G,
+                                                           nodes: &[N],
+                                                           clusters: &Clusters<'a>,
+                                                           c: uint,
+                                                           w: &mut W,
+                                                           depth: uint) -> io::IoResult<()> {
+    let cluster = &clusters.clusters[c];
+    try!(indent(w, depth));
+    try!(writeln(w, ["subgraph cluster_", cluster.id.as_slice(), " {"]));
+
+    let mut attrs = vec!(attr("label", g.cluster_label(&cluster.id).escape().as_slice()));
+    match g.cluster_color(&cluster.id) {
+        Some(color) => attrs.push(attr("color", color.escape().as_slice())),
+        None => {}
+    }
+    match g.cluster_style(&cluster.id) {
+        Some(style) => attrs.push(attr("style", style.as_slice())),
+        None => {}
+    }
+    match g.cluster_rank(&cluster.id) {
+        Some(rank) => attrs.push(attr("rank", rank.as_slice())),
+        None => {}
+    }
+    for a in attrs.iter() {
+        try!(indent(w, depth + 1));
+        try!(writeln(w, [a.as_slice(), ";"]));
+    }
+
+    for &i in cluster.nodes.iter() {
+        try!(render_node(g, &nodes[i], w, depth + 1));
+    }
+    for &child in cluster.children.iter() {
+        try!(render_cluster(g, nodes, clusters, child, w, depth + 1));
+    }
+    try!(indent(w, depth));
+    writeln(w, ["}"])
+}
+
+struct Cluster<'a> {
+    id: Id<'a>,
+    parent: Option<uint>,
+    // The indices of the nodes directly inside this cluster.
+    nodes: Vec<uint>,
+    // The indices of the clusters directly inside this cluster.
+    children: Vec<uint>,
+}
+
+// The tree of clusters that the nodes of a graph are grouped into, in the
+// order in which they are first reached from the nodes.
+struct Clusters<'a> {
+    clusters: Vec<Cluster<'a>>,
+    // The indices of the clusters not inside any other cluster.
+    roots: Vec<uint>,
+    // The indices of the nodes not inside any cluster.
+    unclustered: Vec<uint>,
+    index: HashMap<String, uint>,
+}
+
+impl<'a> Clusters<'a> {
+    fn new<N, E, G:Labeller<'a,N,E>>(g: &'a G, nodes: &[N]) -> Clusters<'a> {
+        let mut clusters = Clusters {
+            clusters: Vec::new(),
+            roots: Vec::new(),
+            unclustered: Vec::new(),
+            index: HashMap::new(),
+        };
+        for (i, n) in nodes.iter().enumerate() {
+            match g.node_cluster(n) {
+                Some(id) => {
+                    let c = clusters.insert(g, id);
+                    clusters.clusters.get_mut(c).nodes.push(i);
+                }
+                None => clusters.unclustered.push(i),
+            }
+        }
+        clusters
+    }
+
+    // Returns the index of cluster `id`, first adding it and the clusters
+    // containing it if they haven't been seen yet.
+    fn insert<N, E, G:Labeller<'a,N,E>>(&mut self, g: &'a G, id: Id<'a>) -> uint {
+        let name = id.as_slice().to_string();
+        match self.index.find(&name) {
+            Some(&c) => return c,
+            None => {}
+        }
+
+        let parent = g.cluster_parent(&id);
+        let c = self.clusters.len();
+        self.index.insert(name, c);
+        self.clusters.push(Cluster {
+            id: id,
+            parent: None,
+            nodes: Vec::new(),
+            children: Vec::new(),
+        });
+
+        match parent {
+            Some(parent) => {
+                let p = self.insert(g, parent);
+                let mut ancestor = Some(p);
+                loop {
+                    match ancestor {
+                        Some(a) if a == c => {
+                            fail!("cluster `{}` is inside itself",
+                                  self.clusters[c].id.as_slice())
+                        }
+                        Some(a) => ancestor = self.clusters[a].parent,
+                        None => break,
+                    }
+                }
+                self.clusters.get_mut(c).parent = Some(p);
+                self.clusters.get_mut(p).children.push(c);
+            }
+            None => self.roots.push(c),
+        }
+        c
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::{Id, LabelText, LabelStr, EscStr, Labeller};
     use super::{Nodes, Edges, GraphWalk, render};
+    use super::{Kind, Directed, Undirected, Style, Dashed, Filled, Bold};
+    use super::{RankDir, LeftToRight, Rank, SameRank};
     use std::io::{MemWriter, BufReader, IoResult};
     use std::str;
 
@@ -687,6 +1086,84 @@ mod tests {
         }
     }
 
+    // A wrapper around LabelledGraph which draws even nodes as red boxes,
+    // edges from the first node dashed, and puts each node listed in
+    // `clusters` into the cluster given alongside it.
+    struct DecoratedGraph {
+        graph: LabelledGraph,
+        kind: Kind,
+        rank_dir: Option<RankDir>,
+        clusters: Vec<(uint, &'static str)>,
+        // (cluster, parent) pairs
+        cluster_parents: Vec<(&'static str, &'static str)>,
+    }
+
+    impl DecoratedGraph {
+        fn new(graph: LabelledGraph) -> DecoratedGraph {
+            DecoratedGraph {
+                graph: graph,
+                kind: Directed,
+                rank_dir: None,
+                clusters: vec!(),
+                cluster_parents: vec!(),
+            }
+        }
+    }
+
+    impl<'a> Labeller<'a, Node, &'a Edge> for DecoratedGraph {
+        fn graph_id(&'a self) -> Id<'a> { self.graph.graph_id() }
+        fn kind(&'a self) -> Kind { self.kind }
+        fn rank_dir(&'a self) -> Option<RankDir> { self.rank_dir }
+        fn node_id(&'a self, n: &Node) -> Id<'a> { self.graph.node_id(n) }
+        fn node_label(&'a self, n: &Node) -> LabelText<'a> { self.graph.node_label(n) }
+        fn edge_label(&'a self, e: & &'a Edge) -> LabelText<'a> { self.graph.edge_label(e) }
+        fn node_shape(&'a self, n: &Node) -> Option<LabelText<'a>> {
+            if *n % 2 == 0 { Some(LabelStr(str::Slice("box"))) } else { None }
+        }
+        fn node_color(&'a self, n: &Node) -> Option<LabelText<'a>> {
+            if *n % 2 == 0 { Some(LabelStr(str::Slice("#ff0000"))) } else { None }
+        }
+        fn node_style(&'a self, n: &Node) -> Option<Style> {
+            if *n % 2 == 0 { Some(Filled) } else { None }
+        }
+        fn edge_color(&'a self, e: & &'a Edge) -> Option<LabelText<'a>> {
+            if e.from == 0 { Some(LabelStr(str::Slice("blue"))) } else { None }
+        }
+        fn edge_style(&'a self, e: & &'a Edge) -> Option<Style> {
+            if e.from == 0 { Some(Dashed) } else { None }
+        }
+        fn node_cluster(&'a self, n: &Node) -> Option<Id<'a>> {
+            self.clusters.iter().find(|&&(m, _)| m == *n).map(|&(_, c)| Id::new(c))
+        }
+        fn cluster_parent(&'a self, c: &Id<'a>) -> Option<Id<'a>> {
+            self.cluster_parents.iter()
+                .find(|&&(child, _)| child == c.as_slice())
+                .map(|&(_, parent)| Id::new(parent))
+        }
+        fn cluster_label(&'a self, c: &Id<'a>) -> LabelText<'a> {
+            LabelStr(str::Owned(format!("scope {}", c.as_slice())))
+        }
+        fn cluster_style(&'a self, c: &Id<'a>) -> Option<Style> {
+            if c.as_slice() == "outer" { Some(Bold) } else { None }
+        }
+        fn cluster_rank(&'a self, c: &Id<'a>) -> Option<Rank> {
+            if c.as_slice() == "inner" { Some(SameRank) } else { None }
+        }
+    }
+
+    impl<'a> GraphWalk<'a, Node, &'a Edge> for DecoratedGraph {
+        fn nodes(&'a self) -> Nodes<'a,Node> { self.graph.nodes() }
+        fn edges(&'a self) -> Edges<'a,&'a Edge> { self.graph.edges() }
+        fn source(&'a self, edge: & &'a Edge) -> Node { edge.from }
+        fn target(&'a self, edge: & &'a Edge) -> Node { edge.to }
+    }
+
+    fn test_decorated(g: DecoratedGraph) -> String {
+        let mut writer = MemWriter::new();
+        render(&g, &mut writer).unwrap();
+        str::from_utf8(writer.get_ref()).unwrap().to_string()
+    }
+
     fn test_input(g: LabelledGraph) -> IoResult<String> {
         let mut writer = MemWriter::new();
         render(&g, &mut writer).unwrap();
@@ -805,4 +1282,83 @@ r#"digraph syntax_tree {
 }
 "#);
     }
+    #[test]
+    fn undirected_graph() {
+        let labels : Trivial = UnlabelledNodes(2);
+        let mut g = DecoratedGraph::new(LabelledGraph::new("undirected_graph", labels,
+                                                           vec!(edge(1, 1, "E"))));
+        g.kind = Undirected;
+        assert_eq!(test_decorated(g).as_slice(),
+r#"graph undirected_graph {
+    N0[label="N0", shape="box", color="#ff0000", style="filled"];
+    N1[label="N1"];
+    N1 -- N1[label="E"];
+}
+"#);
+    }
+
+    #[test]
+    fn node_and_edge_attributes() {
+        let labels = AllNodesLabelled(vec!("{x,y}", "{x}", "{y}", "{}"));
+        let mut g = DecoratedGraph::new(LabelledGraph::new(
+            "attributes", labels,
+            vec!(edge(0, 1, ""), edge(0, 2, ""),
+                 edge(1, 3, ""), edge(2, 3, ""))));
+        g.rank_dir = Some(LeftToRight);
+        assert_eq!(test_decorated(g).as_slice(),
+r#"digraph attributes {
+    rankdir="LR";
+    N0[label="{x,y}", shape="box", color="#ff0000", style="filled"];
+    N1[label="{x}"];
+    N2[label="{y}", shape="box", color="#ff0000", style="filled"];
+    N3[label="{}"];
+    N0 -> N1[label="", color="blue", style="dashed"];
+    N0 -> N2[label="", color="blue", style="dashed"];
+    N1 -> N3[label=""];
+    N2 -> N3[label=""];
+}
+"#);
+    }
+
+    #[test]
+    fn nested_clusters() {
+        let labels : Trivial = UnlabelledNodes(5);
+        let mut g = DecoratedGraph::new(LabelledGraph::new(
+            "nested_clusters", labels,
+            vec!(edge(1, 2, "E"), edge(3, 4, "F"))));
+        g.clusters = vec!((3, "inner"), (1, "outer"), (4, "inner"), (2, "other"));
+        g.cluster_parents = vec!(("inner", "outer"));
+        assert_eq!(test_decorated(g).as_slice(),
+r#"digraph nested_clusters {
+    N0[label="N0", shape="box", color="#ff0000", style="filled"];
+    subgraph cluster_outer {
+        label="scope outer";
+        style="bold";
+        N1[label="N1"];
+        subgraph cluster_inner {
+            label="scope inner";
+            rank="same";
+            N3[label="N3"];
+            N4[label="N4", shape="box", color="#ff0000", style="filled"];
+        }
+    }
+    subgraph cluster_other {
+        label="scope other";
+        N2[label="N2", shape="box", color="#ff0000", style="filled"];
+    }
+    N1 -> N2[label="E"];
+    N3 -> N4[label="F"];
+}
+"#);
+    }
+
+    #[test]
+    #[should_fail]
+    fn cyclic_clusters() {
+        let labels : Trivial = UnlabelledNodes(1);
+        let mut g = DecoratedGraph::new(LabelledGraph::new("cyclic_clusters", labels, vec!()));
+        g.clusters = vec!((0, "a"));
+        g.cluster_parents = vec!(("a", "b"), ("b", "a"));
+        test_decorated(g);
+    }
 }
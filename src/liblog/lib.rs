@@ -143,6 +143,35 @@
 //!  hello. In both cases the log message must include a single digit number
 //!  followed by 'scopes'
 //!
+//! ## Changing log levels at runtime
+//!
+//! The directives read from `RUST_LOG` can be replaced while the program is
+//! running, either all at once with `set_logging_spec`, which takes a string
+//! in the same format, or one module path at a time with `set_target_level`.
+//! The new levels apply to all tasks from then on.
+//!
+//! ## Fields, timestamps and sinks
+//!
+//! Each `LogRecord` carries the time it was created at, and any key/value
+//! fields given to the logging macros in a `fields { .. }` block:
+//!
+//! ```
+//! #![feature(phase)]
+//! #[phase(plugin, link)] extern crate log;
+//!
+//! fn main() {
+//!     let addr = "10.0.0.1";
+//!     info!(fields { addr: addr, port: 80u }, "accepted connection");
+//! }
+//! ```
+//!
+//! The default logger appends the fields to the message, as `addr=10.0.0.1
+//! port=80`, but does not print timestamps. Besides the default logger, this
+//! crate provides `JsonLogger`, which writes one JSON object per line to any
+//! `Writer`, and `FileLogger`, which writes timestamped lines of text to a
+//! file that is rotated once it reaches a maximum size. Install one of them
+//! in a task with `set_logger`.
+//!
 //! ## Performance and Side Effects
 //!
 //! Each of these macros will expand to code similar to:
@@ -170,21 +199,32 @@
 #![deny(missing_doc)]
 
 extern crate regex;
+extern crate libc;
 
 use regex::Regex;
+use std::any::Any;
+use std::cmp;
 use std::fmt;
 use std::io::LineBufferedWriter;
 use std::io;
 use std::mem;
 use std::os;
 use std::rt;
+use std::rt::mutex::{StaticNativeMutex, NATIVE_MUTEX_INIT};
 use std::slice;
+use std::sync::atomic;
 use std::sync::{Once, ONCE_INIT};
 
 use directive::LOG_LEVEL_NAMES;
+use sink::TextFields;
+
+pub use sink::{JsonLogger, RotatingFile, FileLogger};
+pub use timestamp::Timestamp;
 
 pub mod macros;
 mod directive;
+mod sink;
+mod timestamp;
 
 /// Maximum logging level of a module that can be specified. Common logging
 /// levels are found in the DEBUG/INFO/WARN/ERROR constants.
@@ -198,11 +238,27 @@ static DEFAULT_LOG_LEVEL: u32 = 1;
 /// logging statement should be run.
 static mut LOG_LEVEL: u32 = MAX_LOG_LEVEL;
 
-static mut DIRECTIVES: *const Vec<directive::LogDirective> =
-    0 as *const Vec<directive::LogDirective>;
+/// A `*const Vec<directive::LogDirective>`, swapped atomically so that
+/// loggers can read it without taking `LOCK`.
+static mut DIRECTIVES: atomic::AtomicUint = atomic::INIT_ATOMIC_UINT;
+
+/// Optional regex filter, as a `*const Regex` which is 0 if there is none.
+static mut FILTER: atomic::AtomicUint = atomic::INIT_ATOMIC_UINT;
+
+/// Directives and filters which have been replaced at runtime while another
+/// task may still have been reading them. They are freed by the next
+/// replacement made while no task is reading, or at exit.
+static mut RETIRED: *mut Vec<Box<Any + Send>> = 0 as *mut _;
+
+/// The number of tasks currently reading `DIRECTIVES` or `FILTER`.
+static mut READERS: atomic::AtomicUint = atomic::INIT_ATOMIC_UINT;
+
+/// Serializes the replacement of `DIRECTIVES` and `FILTER`, and guards
+/// `RETIRED`.
+static mut LOCK: StaticNativeMutex = NATIVE_MUTEX_INIT;
 
-/// Optional regex filter.
-static mut FILTER: *const Regex = 0 as *const _;
+/// Reads `RUST_LOG` on first use of the logging directives.
+static mut INIT: Once = ONCE_INIT;
 
 /// Debug log level
 pub static DEBUG: u32 = 4;
@@ -223,6 +279,22 @@ pub trait Logger {
     fn log(&mut self, record: &LogRecord);
 }
 
+/// Counts the current task in `READERS` for as long as it is alive.
+struct ReadGuard;
+
+impl ReadGuard {
+    fn new() -> ReadGuard {
+        unsafe { READERS.fetch_add(1, atomic::SeqCst); }
+        ReadGuard
+    }
+}
+
+impl Drop for ReadGuard {
+    fn drop(&mut self) {
+        unsafe { READERS.fetch_sub(1, atomic::SeqCst); }
+    }
+}
+
 struct DefaultLogger {
     handle: LineBufferedWriter<io::stdio::StdWriter>,
 }
@@ -251,10 +323,11 @@ impl fmt::Signed for LogLevel {
 impl Logger for DefaultLogger {
     fn log(&mut self, record: &LogRecord) {
         match writeln!(&mut self.handle,
-                       "{}:{}: {}",
+                       "{}:{}: {}{}",
                        record.level,
                        record.module_path,
-                       record.args) {
+                       record.args,
+                       TextFields(record.fields)) {
             Err(e) => fail!("failed to log: {}", e),
             Ok(()) => {}
         }
@@ -280,11 +353,23 @@ impl Drop for DefaultLogger {
 /// invoked through the logging family of macros.
 #[doc(hidden)]
 pub fn log(level: u32, loc: &'static LogLocation, args: &fmt::Arguments) {
+    log_fields(level, loc, args, &[])
+}
+
+/// Like `log`, for log lines with key/value fields.
+#[doc(hidden)]
+pub fn log_fields<'a>(level: u32, loc: &'static LogLocation, args: &fmt::Arguments,
+                      fields: &'a [(&'a str, &'a fmt::Show+'a)]) {
     // Test the literal string from args against the current filter, if there
-    // is one.
-    match unsafe { FILTER.as_ref() } {
-        Some(filter) if filter.is_match(args.to_string().as_slice()) => return,
-        _ => {}
+    // is one. The filter is not freed while the guard is alive, so it stays
+    // valid even if formatting the string replaces it.
+    {
+        let _reading = ReadGuard::new();
+        let filter = unsafe { FILTER.load(atomic::SeqCst) as *const Regex };
+        if !filter.is_null() {
+            let msg = args.to_string();
+            if unsafe { (*filter).is_match(msg.as_slice()) } { return }
+        }
     }
 
     // Completely remove the local logger from TLS in case anyone attempts to
@@ -299,6 +384,8 @@ pub fn log(level: u32, loc: &'static LogLocation, args: &fmt::Arguments) {
         file: loc.file,
         module_path: loc.module_path,
         line: loc.line,
+        fields: fields,
+        timestamp: Timestamp::now(),
     });
     local_logger.replace(Some(logger));
 }
@@ -334,6 +421,12 @@ pub struct LogRecord<'a> {
 
     /// The line number of where the LogRecord originated.
     pub line: uint,
+
+    /// The key/value pairs given with the log line, in order.
+    pub fields: &'a [(&'a str, &'a fmt::Show+'a)],
+
+    /// The time at which the LogRecord was created.
+    pub timestamp: Timestamp,
 }
 
 #[doc(hidden)]
@@ -348,7 +441,6 @@ pub struct LogLocation {
 /// module's log statement should be emitted or not.
 #[doc(hidden)]
 pub fn mod_enabled(level: u32, module: &str) -> bool {
-    static mut INIT: Once = ONCE_INIT;
     unsafe { INIT.doit(init); }
 
     // It's possible for many threads are in this function, only one of them
@@ -358,11 +450,109 @@ pub fn mod_enabled(level: u32, module: &str) -> bool {
     // the log level again.
     if level > unsafe { LOG_LEVEL } { return false }
 
-    // This assertion should never get tripped unless we're in an at_exit
-    // handler after logging has been torn down and a logging attempt was made.
-    assert!(unsafe { !DIRECTIVES.is_null() });
+    let _reading = ReadGuard::new();
+    unsafe {
+        let directives = DIRECTIVES.load(atomic::SeqCst)
+                         as *const Vec<directive::LogDirective>;
+
+        // This assertion should never get tripped unless we're in an at_exit
+        // handler after logging has been torn down and a logging attempt was
+        // made.
+        assert!(!directives.is_null());
+
+        enabled(level, module, (*directives).iter())
+    }
+}
+
+/// Replaces the logging directives and the regex filter with those parsed
+/// from `spec`, which has the same syntax as the `RUST_LOG` environment
+/// variable.
+///
+/// This takes effect immediately in all tasks.
+pub fn set_logging_spec(spec: &str) {
+    let (directives, filter) = directive::parse_logging_spec(spec);
+    unsafe {
+        INIT.doit(init);
+        let _guard = LOCK.lock();
+        set_directives(directives);
+        set_filter(filter);
+    }
+}
+
+/// Sets the log level of the modules whose path starts with `target`, or the
+/// default log level of all modules if `target` is `None`, replacing the
+/// directive given for the same target by `RUST_LOG` or a previous call.
+///
+/// This takes effect immediately in all tasks.
+pub fn set_target_level(target: Option<&str>, level: u32) {
+    unsafe {
+        INIT.doit(init);
+        let _guard = LOCK.lock();
+        let current = DIRECTIVES.load(atomic::SeqCst)
+                      as *const Vec<directive::LogDirective>;
+        assert!(!current.is_null());
+        let mut directives = (*current).clone();
+        directives.retain(|d| d.name.as_ref().map(|s| s.as_slice()) != target);
+        directives.push(directive::LogDirective {
+            name: target.map(|s| s.to_string()),
+            level: cmp::min(level, MAX_LOG_LEVEL),
+        });
+        set_directives(directives);
+    }
+}
+
+/// Installs `directives` and updates the maximum log level to match. Must be
+/// called with `LOCK` held.
+unsafe fn set_directives(mut directives: Vec<directive::LogDirective>) {
+    // Sort the provided directives by length of their name, this allows a
+    // little more efficient lookup at runtime.
+    directives.sort_by(|a, b| {
+        let alen = a.name.as_ref().map(|a| a.len()).unwrap_or(0);
+        let blen = b.name.as_ref().map(|b| b.len()).unwrap_or(0);
+        alen.cmp(&blen)
+    });
 
-    enabled(level, module, unsafe { (*DIRECTIVES).iter() })
+    let max_level = {
+        let max = directives.iter().max_by(|d| d.level);
+        max.map(|d| d.level).unwrap_or(DEFAULT_LOG_LEVEL)
+    };
+
+    LOG_LEVEL = max_level;
+
+    let old = DIRECTIVES.swap(mem::transmute(box directives), atomic::SeqCst);
+    if old != 0 {
+        let old: Box<Vec<directive::LogDirective>> = mem::transmute(old);
+        retire(old as Box<Any + Send>);
+    }
+}
+
+/// Installs `filter`. Must be called with `LOCK` held.
+unsafe fn set_filter(filter: Option<Regex>) {
+    let new = match filter {
+        Some(f) => mem::transmute(box f),
+        None => 0,
+    };
+    let old = FILTER.swap(new, atomic::SeqCst);
+    if old != 0 {
+        let old: Box<Regex> = mem::transmute(old);
+        retire(old as Box<Any + Send>);
+    }
+}
+
+/// Frees a replaced directive list or filter, along with any retired
+/// earlier, unless a task is reading the globals and may still hold one of
+/// them. Must be called with `LOCK` held.
+unsafe fn retire(old: Box<Any + Send>) {
+    if RETIRED.is_null() {
+        RETIRED = mem::transmute(box Vec::<Box<Any + Send>>::new());
+    }
+    (*RETIRED).push(old);
+
+    // The replacement has already been swapped in, so a task which starts
+    // reading after this load can only see the new value.
+    if READERS.load(atomic::SeqCst) == 0 {
+        (*RETIRED).clear();
+    }
 }
 
 fn enabled(level: u32,
@@ -386,46 +576,39 @@ fn enabled(level: u32,
 /// This is not threadsafe at all, so initialization os performed through a
 /// `Once` primitive (and this function is called from that primitive).
 fn init() {
-    let (mut directives, filter) = match os::getenv("RUST_LOG") {
+    let (directives, filter) = match os::getenv("RUST_LOG") {
         Some(spec) => directive::parse_logging_spec(spec.as_slice()),
         None => (Vec::new(), None),
     };
 
-    // Sort the provided directives by length of their name, this allows a
-    // little more efficient lookup at runtime.
-    directives.sort_by(|a, b| {
-        let alen = a.name.as_ref().map(|a| a.len()).unwrap_or(0);
-        let blen = b.name.as_ref().map(|b| b.len()).unwrap_or(0);
-        alen.cmp(&blen)
-    });
-
-    let max_level = {
-        let max = directives.iter().max_by(|d| d.level);
-        max.map(|d| d.level).unwrap_or(DEFAULT_LOG_LEVEL)
-    };
-
     unsafe {
-        LOG_LEVEL = max_level;
+        {
+            let _guard = LOCK.lock();
 
-        assert!(FILTER.is_null());
-        match filter {
-            Some(f) => FILTER = mem::transmute(box f),
-            None => {}
-        }
+            assert!(FILTER.load(atomic::SeqCst) == 0);
+            set_filter(filter);
 
-        assert!(DIRECTIVES.is_null());
-        DIRECTIVES = mem::transmute(box directives);
+            assert!(DIRECTIVES.load(atomic::SeqCst) == 0);
+            set_directives(directives);
+        }
 
         // Schedule the cleanup for the globals for when the runtime exits.
         rt::at_exit(proc() {
-            assert!(!DIRECTIVES.is_null());
+            let _guard = LOCK.lock();
+
+            let directives = DIRECTIVES.swap(0, atomic::SeqCst);
+            assert!(directives != 0);
             let _directives: Box<Vec<directive::LogDirective>> =
-                mem::transmute(DIRECTIVES);
-            DIRECTIVES = 0 as *const Vec<directive::LogDirective>;
+                mem::transmute(directives);
+
+            let filter = FILTER.swap(0, atomic::SeqCst);
+            if filter != 0 {
+                let _filter: Box<Regex> = mem::transmute(filter);
+            }
 
-            if !FILTER.is_null() {
-                let _filter: Box<Regex> = mem::transmute(FILTER);
-                FILTER = 0 as *const _;
+            if !RETIRED.is_null() {
+                let _retired: Box<Vec<Box<Any + Send>>> = mem::transmute(RETIRED);
+                RETIRED = 0 as *mut _;
             }
         });
     }
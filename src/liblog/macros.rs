@@ -50,8 +50,41 @@
 /// WARN:main: this is a warning message
 /// 6:main: this is a custom logging level: 6
 /// ```
+///
+/// Key/value fields can be attached to a log line by listing them in a
+/// `fields { .. }` block before the format string. Each value can be of any
+/// type implementing `Show`, and is only formatted if the line is logged.
+///
+/// ```
+/// #![feature(phase)]
+/// #[phase(plugin, link)] extern crate log;
+///
+/// fn main() {
+///     let user = "alice";
+///     log!(log::WARN, fields { user: user, attempts: 3u }, "login failed");
+/// }
+/// ```
+///
+/// ```{.bash}
+/// $ RUST_LOG=warn ./main
+/// WARN:main: login failed user=alice attempts=3
+/// ```
 #[macro_export]
 macro_rules! log(
+    ($lvl:expr, fields { $($key:ident : $value:expr),* }, $($arg:tt)+) => ({
+        static LOC: ::log::LogLocation = ::log::LogLocation {
+            line: line!(),
+            file: file!(),
+            module_path: module_path!(),
+        };
+        let lvl = $lvl;
+        if log_enabled!(lvl) {
+            format_args!(|args| {
+                ::log::log_fields(lvl, &LOC, args,
+                                  &[$((stringify!($key), &$value as &::std::fmt::Show)),*])
+            }, $($arg)+)
+        }
+    });
     ($lvl:expr, $($arg:tt)+) => ({
         static LOC: ::log::LogLocation = ::log::LogLocation {
             line: line!(),
@@ -0,0 +1,322 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Loggers writing to files and other sinks
+
+use std::fmt;
+use std::io::{fs, File, IoResult};
+use std::io;
+
+use {Logger, LogRecord};
+
+/// Formats the fields of a record as ` key=value` pairs, for appending to the
+/// message in the text log formats.
+pub struct TextFields<'a>(pub &'a [(&'a str, &'a fmt::Show+'a)]);
+
+impl<'a> fmt::Show for TextFields<'a> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let TextFields(fields) = *self;
+        for &(key, value) in fields.iter() {
+            try!(write!(fmt, " {}={}", key, value));
+        }
+        Ok(())
+    }
+}
+
+/// A logger which writes each record as a single line of JSON.
+///
+/// Every line is an object with the keys `timestamp` (an RFC 3339 string in
+/// UTC), `level`, `module`, `file`, `line`, `message` and `fields`, the last
+/// of which is an object mapping the key of each field to its value formatted
+/// with `Show`. For example:
+///
+/// ```text
+/// {"timestamp":"2014-08-20T17:03:41.071892Z","level":"INFO","module":"main",
+///  "file":"main.rs","line":10,"message":"logged in","fields":{"user":"bob"}}
+/// ```
+///
+/// (without the line break).
+pub struct JsonLogger<W> {
+    writer: W,
+}
+
+impl<W: Writer> JsonLogger<W> {
+    /// Creates a logger writing JSON lines to `writer`. Each record is passed
+    /// to the writer with a single call to `write`.
+    pub fn new(writer: W) -> JsonLogger<W> {
+        JsonLogger { writer: writer }
+    }
+
+    /// Gets a reference to the underlying writer.
+    pub fn get_ref<'a>(&'a self) -> &'a W { &self.writer }
+
+    /// Unwraps this `JsonLogger`, returning the underlying writer.
+    pub fn unwrap(self) -> W { self.writer }
+}
+
+impl<W: Writer> Logger for JsonLogger<W> {
+    fn log(&mut self, record: &LogRecord) {
+        let mut line = String::new();
+        line.push_str("{\"timestamp\":");
+        push_json_str(&mut line, record.timestamp.rfc3339().as_slice());
+        line.push_str(",\"level\":");
+        push_json_str(&mut line, record.level.to_string().as_slice());
+        line.push_str(",\"module\":");
+        push_json_str(&mut line, record.module_path);
+        line.push_str(",\"file\":");
+        push_json_str(&mut line, record.file);
+        line.push_str(format!(",\"line\":{}", record.line).as_slice());
+        line.push_str(",\"message\":");
+        push_json_str(&mut line, record.args.to_string().as_slice());
+        line.push_str(",\"fields\":{");
+        for (i, &(key, value)) in record.fields.iter().enumerate() {
+            if i > 0 {
+                line.push_char(',');
+            }
+            push_json_str(&mut line, key);
+            line.push_char(':');
+            push_json_str(&mut line, value.to_string().as_slice());
+        }
+        line.push_str("}}\n");
+
+        match self.writer.write_str(line.as_slice()).and_then(|()| self.writer.flush()) {
+            Err(e) => fail!("failed to log: {}", e),
+            Ok(()) => {}
+        }
+    }
+}
+
+// Appends `s` to `out` as a JSON string literal.
+fn push_json_str(out: &mut String, s: &str) {
+    out.push_char('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 || c == '\x7f' => {
+                out.push_str(format!("\\u{:04x}", c as u32).as_slice())
+            }
+            c => out.push_char(c),
+        }
+    }
+    out.push_char('"');
+}
+
+/// A file which is rotated once it grows past a maximum size.
+///
+/// Writing to a `RotatingFile` appends to the file at its path. When a write
+/// would take the file past the maximum size, the file is first renamed by
+/// appending `.1` to its name, any older files are renamed from `.1` to `.2`
+/// and so on, the oldest one is removed, and a new, empty file is created.
+/// A single write is never split across files, so a file can still exceed the
+/// maximum size if a single write is larger than it.
+pub struct RotatingFile {
+    path: Path,
+    file: File,
+    size: u64,
+    max_size: u64,
+    max_files: uint,
+}
+
+impl RotatingFile {
+    /// Opens the file at `path` for appending, creating it if needed, which
+    /// will be rotated once it reaches `max_size` bytes, keeping at most
+    /// `max_files` old files alongside it.
+    pub fn open(path: &Path, max_size: u64, max_files: uint) -> IoResult<RotatingFile> {
+        let mut file = try!(File::open_mode(path, io::Append, io::Write));
+        let size = try!(file.stat()).size;
+        Ok(RotatingFile {
+            path: path.clone(),
+            file: file,
+            size: size,
+            max_size: max_size,
+            max_files: max_files,
+        })
+    }
+
+    /// Returns the path of the file currently being written to.
+    pub fn path<'a>(&'a self) -> &'a Path { &self.path }
+
+    /// Returns the path of the `n`th most recent old file, where `n` starts
+    /// at 1.
+    pub fn rotated_path(&self, n: uint) -> Path {
+        let mut filename = Vec::from_slice(self.path.filename().unwrap_or(b""));
+        filename.push_all(format!(".{}", n).as_bytes());
+        self.path.with_filename(filename)
+    }
+
+    /// Rotates the file now, whatever its size.
+    pub fn rotate(&mut self) -> IoResult<()> {
+        if self.max_files > 0 {
+            let oldest = self.rotated_path(self.max_files);
+            if oldest.exists() {
+                try!(fs::unlink(&oldest));
+            }
+            for n in range(1, self.max_files).rev() {
+                let from = self.rotated_path(n);
+                if from.exists() {
+                    try!(fs::rename(&from, &self.rotated_path(n + 1)));
+                }
+            }
+            try!(fs::rename(&self.path, &self.rotated_path(1)));
+        }
+        self.file = try!(File::open_mode(&self.path, io::Truncate, io::Write));
+        self.size = 0;
+        Ok(())
+    }
+}
+
+impl Writer for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> IoResult<()> {
+        if self.size > 0 && self.size + buf.len() as u64 > self.max_size {
+            try!(self.rotate());
+        }
+        try!(self.file.write(buf));
+        self.size += buf.len() as u64;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> IoResult<()> {
+        self.file.flush()
+    }
+}
+
+/// A logger which writes timestamped lines of text to a `RotatingFile`.
+///
+/// Lines have the same format as the default logger's, prefixed with the
+/// RFC 3339 timestamp of the record:
+///
+/// ```text
+/// 2014-08-20T17:03:41.071892Z INFO:main: logged in user=bob
+/// ```
+pub struct FileLogger {
+    file: RotatingFile,
+}
+
+impl FileLogger {
+    /// Creates a logger appending to the file at `path`, which is rotated
+    /// once it reaches `max_size` bytes, keeping at most `max_files` old files.
+    pub fn new(path: &Path, max_size: u64, max_files: uint) -> IoResult<FileLogger> {
+        let file = try!(RotatingFile::open(path, max_size, max_files));
+        Ok(FileLogger { file: file })
+    }
+
+    /// Gets a reference to the underlying file.
+    pub fn get_ref<'a>(&'a self) -> &'a RotatingFile { &self.file }
+}
+
+impl Logger for FileLogger {
+    fn log(&mut self, record: &LogRecord) {
+        let line = format!("{} {}:{}: {}{}\n",
+                           record.timestamp.rfc3339(),
+                           record.level,
+                           record.module_path,
+                           record.args,
+                           TextFields(record.fields));
+        match self.file.write_str(line.as_slice()) {
+            Err(e) => fail!("failed to log: {}", e),
+            Ok(()) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fmt::Show;
+    use std::io::{File, MemWriter, TempDir};
+    use std::str;
+
+    use {Logger, LogRecord, LogLevel, Timestamp, INFO};
+    use super::{JsonLogger, RotatingFile, FileLogger, push_json_str};
+
+    fn log_to<'a, L: Logger>(logger: &mut L, fields: &'a [(&'a str, &'a Show+'a)]) {
+        format_args!(|args| {
+            logger.log(&LogRecord {
+                module_path: "app::db",
+                level: LogLevel(INFO),
+                args: args,
+                file: "src/db.rs",
+                line: 42,
+                fields: fields,
+                timestamp: Timestamp { sec: 1408554221, nsec: 71892000 },
+            })
+        }, "connected to {}", "localhost")
+    }
+
+    #[test]
+    fn json_str() {
+        let mut s = String::new();
+        push_json_str(&mut s, "a \"quoted\"\\\n\t\x01 é");
+        assert_eq!(s, "\"a \\\"quoted\\\"\\\\\\n\\t\\u0001 é\"".to_string());
+    }
+
+    #[test]
+    fn json_logger() {
+        let mut logger = JsonLogger::new(MemWriter::new());
+        log_to(&mut logger, &[("user", &"bob" as &Show), ("id", &7u as &Show)]);
+        log_to(&mut logger, &[]);
+        let out = logger.unwrap().unwrap();
+        assert_eq!(str::from_utf8(out.as_slice()).unwrap(),
+                   "{\"timestamp\":\"2014-08-20T17:03:41.071892Z\",\"level\":\"INFO\",\
+                    \"module\":\"app::db\",\"file\":\"src/db.rs\",\"line\":42,\
+                    \"message\":\"connected to localhost\",\
+                    \"fields\":{\"user\":\"bob\",\"id\":\"7\"}}\n\
+                    {\"timestamp\":\"2014-08-20T17:03:41.071892Z\",\"level\":\"INFO\",\
+                    \"module\":\"app::db\",\"file\":\"src/db.rs\",\"line\":42,\
+                    \"message\":\"connected to localhost\",\"fields\":{}}\n");
+    }
+
+    #[test]
+    fn rotating_file() {
+        let dir = TempDir::new("log-tests").unwrap();
+        let path = dir.path().join("app.log");
+        {
+            let mut file = RotatingFile::open(&path, 10, 2).unwrap();
+            assert_eq!(file.rotated_path(2), dir.path().join("app.log.2"));
+            for line in ["aaaa\n", "bbbb\n", "cccc\n", "dddd\n", "eeee\n",
+                         "ffff\n", "gggggggggggg\n", "hhhh\n"].iter() {
+                file.write_str(*line).unwrap();
+            }
+        }
+        let read = |name: &str| {
+            File::open(&dir.path().join(name)).read_to_string().unwrap()
+        };
+        assert_eq!(read("app.log"), "hhhh\n".to_string());
+        assert_eq!(read("app.log.1"), "gggggggggggg\n".to_string());
+        assert_eq!(read("app.log.2"), "eeee\nffff\n".to_string());
+        assert!(!dir.path().join("app.log.3").exists());
+
+        // reopening appends to the existing file
+        {
+            let mut file = RotatingFile::open(&path, 10, 2).unwrap();
+            file.write_str("iiii\n").unwrap();
+            file.write_str("jjjj\n").unwrap();
+        }
+        assert_eq!(read("app.log"), "jjjj\n".to_string());
+        assert_eq!(read("app.log.1"), "hhhh\niiii\n".to_string());
+        assert_eq!(read("app.log.2"), "gggggggggggg\n".to_string());
+    }
+
+    #[test]
+    fn file_logger() {
+        let dir = TempDir::new("log-tests").unwrap();
+        let path = dir.path().join("app.log");
+        {
+            let mut logger = FileLogger::new(&path, 1024, 1).unwrap();
+            log_to(&mut logger, &[("user", &"bob" as &Show)]);
+        }
+        assert_eq!(File::open(&path).read_to_string().unwrap(),
+                   "2014-08-20T17:03:41.071892Z INFO:app::db: connected to localhost \
+                    user=bob\n".to_string());
+    }
+}
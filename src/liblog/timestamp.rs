@@ -0,0 +1,114 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+/// A point in time, in seconds and nanoseconds since 1970-01-01T00:00:00Z.
+///
+/// This is the same representation as `time::Timespec`, which can't be used
+/// here because the `time` crate depends on this one.
+#[deriving(Clone, PartialEq, Eq, PartialOrd, Ord, Show)]
+pub struct Timestamp {
+    /// Seconds since the epoch
+    pub sec: i64,
+    /// Nanoseconds since the last whole second
+    pub nsec: i32,
+}
+
+impl Timestamp {
+    /// Returns the current time.
+    pub fn now() -> Timestamp {
+        let (sec, nsec) = unsafe { os_now() };
+        Timestamp { sec: sec, nsec: nsec }
+    }
+
+    /// Formats the timestamp as an RFC 3339 date and time in UTC with
+    /// microsecond precision, e.g. `2014-08-20T17:03:41.071892Z`.
+    pub fn rfc3339(&self) -> String {
+        let days = if self.sec >= 0 {
+            self.sec / 86400
+        } else {
+            (self.sec - 86399) / 86400
+        };
+        let secs = self.sec - days * 86400;
+        let (year, month, day) = civil_from_days(days);
+        format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:06}Z",
+                year, month, day, secs / 3600, secs / 60 % 60, secs % 60,
+                self.nsec / 1000)
+    }
+}
+
+// Converts a number of days since 1970-01-01 to a (year, month, day) date in
+// the proleptic Gregorian calendar.
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    // shift the epoch to 0000-03-01, the start of a 400 year era, so that leap
+    // days fall at the end of each year
+    let z = days + 719468;
+    let era = (if z >= 0 { z } else { z - 146096 }) / 146097;
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(unix)]
+unsafe fn os_now() -> (i64, i32) {
+    use libc::{c_int, c_void, timeval};
+    use std::ptr;
+
+    extern {
+        fn gettimeofday(tp: *mut timeval, tzp: *mut c_void) -> c_int;
+    }
+
+    let mut tv = timeval { tv_sec: 0, tv_usec: 0 };
+    gettimeofday(&mut tv, ptr::mut_null());
+    (tv.tv_sec as i64, tv.tv_usec as i32 * 1000)
+}
+
+#[cfg(windows)]
+unsafe fn os_now() -> (i64, i32) {
+    use libc;
+
+    static HECTONANOSECONDS_FROM_1601_TO_1970: u64 = 116444736000000000;
+
+    let mut time = libc::FILETIME {
+        dwLowDateTime: 0,
+        dwHighDateTime: 0,
+    };
+    libc::GetSystemTimeAsFileTime(&mut time);
+
+    // A FILETIME counts 100-nanosecond intervals since 1601-01-01T00:00:00Z.
+    let since_1601 = ((time.dwHighDateTime as u64) << 32) | (time.dwLowDateTime as u64);
+    let since_1970 = since_1601 - HECTONANOSECONDS_FROM_1601_TO_1970;
+    ((since_1970 / 10000000) as i64, ((since_1970 % 10000000) * 100) as i32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Timestamp;
+
+    #[test]
+    fn rfc3339() {
+        let t = |sec, nsec| Timestamp { sec: sec, nsec: nsec }.rfc3339();
+        assert_eq!(t(0, 0), "1970-01-01T00:00:00.000000Z".to_string());
+        assert_eq!(t(1408554221, 71892123), "2014-08-20T17:03:41.071892Z".to_string());
+        assert_eq!(t(951782400, 0), "2000-02-29T00:00:00.000000Z".to_string());
+        assert_eq!(t(-1, 500000000), "1969-12-31T23:59:59.500000Z".to_string());
+        assert_eq!(t(-86400 * 365, 0), "1969-01-01T00:00:00.000000Z".to_string());
+    }
+
+    #[test]
+    fn now() {
+        // 2014-01-01T00:00:00Z
+        assert!(Timestamp::now().sec > 1388534400);
+    }
+}
@@ -55,6 +55,32 @@
 //! // Probably an even larger number.
 //! println!("{}", a * b);
 //! ```
+//!
+//! There is also modular arithmetic for number-theoretic uses, such as RSA
+//! style computations:
+//!
+//! ```rust
+//! use num::bigint::{BigUint, RandBigInt};
+//! use std::num::{FromPrimitive, One};
+//! use std::rand;
+//!
+//! let mut rng = rand::task_rng();
+//! let p = rng.gen_prime(128);
+//! let q = rng.gen_prime(128);
+//! let n = p * q;
+//! let one: BigUint = One::one();
+//! let phi = (p - one) * (q - one);
+//!
+//! let e: BigUint = FromPrimitive::from_uint(65537).unwrap();
+//! match e.modinv(&phi) {
+//!     Some(d) => {
+//!         let message = rng.gen_biguint_below(&n);
+//!         let ciphertext = message.modpow(&e, &n);
+//!         assert_eq!(ciphertext.modpow(&d, &n), message);
+//!     }
+//!     None => println!("e isn't coprime to phi, pick other primes"),
+//! }
+//! ```
 
 use Integer;
 use rand::Rng;
@@ -65,6 +91,7 @@ use std::from_str::FromStr;
 use std::num::CheckedDiv;
 use std::num::{ToPrimitive, FromPrimitive};
 use std::num::{Zero, One, ToStrRadix, FromStrRadix};
+use std::rand::task_rng;
 use std::string::String;
 use std::{uint, i64, u64};
 
@@ -78,6 +105,25 @@ pub type DoubleBigDigit = u64;
 pub static ZERO_BIG_DIGIT: BigDigit = 0;
 static ZERO_VEC: [BigDigit, ..1] = [ZERO_BIG_DIGIT];
 
+// Operands with fewer digits than these thresholds (in `BigDigit`s) use the
+// simpler algorithms, which are faster for small numbers.
+static KARATSUBA_THRESHOLD: uint = 32;
+static TOOM3_THRESHOLD: uint = 256;
+static BURNIKEL_ZIEGLER_THRESHOLD: uint = 64;
+static RADIX_DC_THRESHOLD: uint = 64;
+
+// The primes below 256, used for trial division before the Miller-Rabin test.
+static SMALL_PRIMES: [BigDigit, ..54] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71,
+    73, 79, 83, 89, 97, 101, 103, 107, 109, 113, 127, 131, 137, 139, 149, 151,
+    157, 163, 167, 173, 179, 181, 191, 193, 197, 199, 211, 223, 227, 229, 233,
+    239, 241, 251
+];
+
+// The number of Miller-Rabin rounds used by `RandBigInt::gen_prime`, for a
+// probability of at most 2^-64 that the result is composite.
+static PRIME_TEST_ROUNDS: uint = 32;
+
 #[allow(non_snake_case)]
 pub mod BigDigit {
     use super::BigDigit;
@@ -296,64 +342,148 @@ impl Mul<BigUint, BigUint> for BigUint {
         if s_len == 1 { return mul_digit(other, self.data.as_slice()[0]);  }
         if o_len == 1 { return mul_digit(self,  other.data.as_slice()[0]); }
 
-        // Using Karatsuba multiplication
-        // (a1 * base + a0) * (b1 * base + b0)
-        // = a1*b1 * base^2 +
-        //   (a1*b1 + a0*b0 - (a1-b0)*(b1-a0)) * base +
-        //   a0*b0
-        let half_len = cmp::max(s_len, o_len) / 2;
-        let (s_hi, s_lo) = cut_at(self,  half_len);
-        let (o_hi, o_lo) = cut_at(other, half_len);
-
-        let ll = s_lo * o_lo;
-        let hh = s_hi * o_hi;
-        let mm = {
-            let (s1, n1) = sub_sign(s_hi, s_lo);
-            let (s2, n2) = sub_sign(o_hi, o_lo);
-            match (s1, s2) {
-                (Equal, _) | (_, Equal) => hh + ll,
-                (Less, Greater) | (Greater, Less) => hh + ll + (n1 * n2),
-                (Less, Less) | (Greater, Greater) => hh + ll - (n1 * n2)
-            }
-        };
-
-        return ll + mm.shl_unit(half_len) + hh.shl_unit(half_len * 2);
-
+        let min_len = cmp::min(s_len, o_len);
+        if min_len < KARATSUBA_THRESHOLD {
+            mul_schoolbook(self.data.as_slice(), other.data.as_slice())
+        } else if min_len < TOOM3_THRESHOLD {
+            mul_karatsuba(self, other)
+        } else {
+            mul_toom3(self, other)
+        }
+    }
+}
 
-        fn mul_digit(a: &BigUint, n: BigDigit) -> BigUint {
-            if n == 0 { return Zero::zero(); }
-            if n == 1 { return (*a).clone(); }
+fn mul_digit(a: &BigUint, n: BigDigit) -> BigUint {
+    if n == 0 { return Zero::zero(); }
+    if n == 1 { return (*a).clone(); }
 
+    let mut carry = 0;
+    let mut prod: Vec<BigDigit> = a.data.iter().map(|ai| {
+        let (hi, lo) = BigDigit::from_doublebigdigit(
+            (*ai as DoubleBigDigit) * (n as DoubleBigDigit) + (carry as DoubleBigDigit)
+        );
+        carry = hi;
+        lo
+    }).collect();
+    if carry != 0 { prod.push(carry); }
+    return BigUint::new(prod);
+}
+
+fn mul_schoolbook(a: &[BigDigit], b: &[BigDigit]) -> BigUint {
+    let mut prod = Vec::from_elem(a.len() + b.len(), ZERO_BIG_DIGIT);
+    {
+        let prod = prod.as_mut_slice();
+        for (i, &ai) in a.iter().enumerate() {
+            if ai == 0 { continue; }
             let mut carry = 0;
-            let mut prod: Vec<BigDigit> = a.data.iter().map(|ai| {
+            for (j, &bj) in b.iter().enumerate() {
+                // (base - 1)^2 + 2 * (base - 1) fits in a `DoubleBigDigit`
                 let (hi, lo) = BigDigit::from_doublebigdigit(
-                    (*ai as DoubleBigDigit) * (n as DoubleBigDigit) + (carry as DoubleBigDigit)
-                );
+                    (ai as DoubleBigDigit) * (bj as DoubleBigDigit)
+                        + (prod[i + j] as DoubleBigDigit)
+                        + (carry as DoubleBigDigit));
+                prod[i + j] = lo;
                 carry = hi;
-                lo
-            }).collect();
-            if carry != 0 { prod.push(carry); }
-            return BigUint::new(prod);
+            }
+            prod[i + b.len()] = carry;
         }
+    }
+    BigUint::new(prod)
+}
 
-        #[inline]
-        fn cut_at(a: &BigUint, n: uint) -> (BigUint, BigUint) {
-            let mid = cmp::min(a.data.len(), n);
-            return (BigUint::from_slice(a.data.slice(mid, a.data.len())),
-                    BigUint::from_slice(a.data.slice(0, mid)));
+fn mul_karatsuba(a: &BigUint, b: &BigUint) -> BigUint {
+    // Using Karatsuba multiplication
+    // (a1 * base + a0) * (b1 * base + b0)
+    // = a1*b1 * base^2 +
+    //   (a1*b1 + a0*b0 - (a1-b0)*(b1-a0)) * base +
+    //   a0*b0
+    let half_len = cmp::max(a.data.len(), b.data.len()) / 2;
+    let (s_hi, s_lo) = cut_at(a, half_len);
+    let (o_hi, o_lo) = cut_at(b, half_len);
+
+    let ll = s_lo * o_lo;
+    let hh = s_hi * o_hi;
+    let mm = {
+        let (s1, n1) = sub_sign(s_hi, s_lo);
+        let (s2, n2) = sub_sign(o_hi, o_lo);
+        match (s1, s2) {
+            (Equal, _) | (_, Equal) => hh + ll,
+            (Less, Greater) | (Greater, Less) => hh + ll + (n1 * n2),
+            (Less, Less) | (Greater, Greater) => hh + ll - (n1 * n2)
         }
+    };
 
-        #[inline]
-        fn sub_sign(a: BigUint, b: BigUint) -> (Ordering, BigUint) {
-            match a.cmp(&b) {
-                Less    => (Less,    b - a),
-                Greater => (Greater, a - b),
-                _       => (Equal,   Zero::zero())
-            }
+    return ll + mm.shl_unit(half_len) + hh.shl_unit(half_len * 2);
+
+    #[inline]
+    fn cut_at(a: &BigUint, n: uint) -> (BigUint, BigUint) {
+        let mid = cmp::min(a.data.len(), n);
+        return (BigUint::from_slice(a.data.slice(mid, a.data.len())),
+                BigUint::from_slice(a.data.slice(0, mid)));
+    }
+
+    #[inline]
+    fn sub_sign(a: BigUint, b: BigUint) -> (Ordering, BigUint) {
+        match a.cmp(&b) {
+            Less    => (Less,    b - a),
+            Greater => (Greater, a - b),
+            _       => (Equal,   Zero::zero())
         }
     }
 }
 
+fn mul_toom3(a: &BigUint, b: &BigUint) -> BigUint {
+    // Using Toom-Cook 3-way multiplication
+    // With a = a2 * x^2 + a1 * x + a0 and b = b2 * x^2 + b1 * x + b0, where
+    // x = base^k, the product r = a * b is a polynomial of degree 4 in x.
+    // It is found from its values at 0, 1, -1, -2 and infinity, each the
+    // product of the values of a and b there, using Bodrato's sequence of
+    // operations for the interpolation (http://bodrato.it/toom-cook/).
+    let k = (cmp::max(a.data.len(), b.data.len()) + 2) / 3;
+    let (a0, a1, a2) = (part(a, k, 0), part(a, k, 1), part(a, k, 2));
+    let (b0, b1, b2) = (part(b, k, 0), part(b, k, 1), part(b, k, 2));
+
+    let p = a0 + a2;
+    let p1 = p + a1;
+    let pm1 = p - a1;
+    let pm2 = ((pm1 + a2) << 1) - a0;
+    let q = b0 + b2;
+    let q1 = q + b1;
+    let qm1 = q - b1;
+    let qm2 = ((qm1 + b2) << 1) - b0;
+
+    let r0 = a0 * b0;
+    let r1 = p1 * q1;
+    let rm1 = pm1 * qm1;
+    let rm2 = pm2 * qm2;
+    let rinf = a2 * b2;
+
+    // all of these divisions are exact
+    let t3 = div3(&(rm2 - r1));
+    let t1 = (r1 - rm1) >> 1;
+    let t2 = rm1 - r0;
+    let t3 = ((t2 - t3) >> 1) + (rinf << 1);
+    let t2 = t2 + t1 - rinf;
+    let t1 = t1 - t3;
+
+    // every coefficient of the product is positive
+    return r0.data + t1.data.shl_unit(k) + t2.data.shl_unit(2 * k)
+        + t3.data.shl_unit(3 * k) + rinf.data.shl_unit(4 * k);
+
+    // The `i`th part of `n` split into parts of `k` digits.
+    fn part(n: &BigUint, k: uint, i: uint) -> BigInt {
+        let len = n.data.len();
+        let start = cmp::min(i * k, len);
+        let end = cmp::min(start + k, len);
+        BigInt::from_slice(Plus, n.data.slice(start, end))
+    }
+
+    fn div3(n: &BigInt) -> BigInt {
+        let (q, _) = div_rem_digit(&n.data, 3);
+        BigInt::from_biguint(n.sign, q)
+    }
+}
+
 impl Div<BigUint, BigUint> for BigUint {
     #[inline]
     fn div(&self, other: &BigUint) -> BigUint {
@@ -438,77 +568,20 @@ impl Integer for BigUint {
             Greater => {} // Do nothing
         }
 
-        let mut shift = 0;
-        let mut n = *other.data.last().unwrap();
-        while n < (1 << BigDigit::bits - 2) {
-            n <<= 1;
-            shift += 1;
+        if other.data.len() == 1 {
+            let (d, m) = div_rem_digit(self, other.data.as_slice()[0]);
+            return (d, BigUint::new(vec!(m)));
         }
-        assert!(shift < BigDigit::bits);
-        let (d, m) = div_mod_floor_inner(self << shift, other << shift);
-        return (d, m >> shift);
-
 
-        fn div_mod_floor_inner(a: BigUint, b: BigUint) -> (BigUint, BigUint) {
-            let mut m = a;
-            let mut d: BigUint = Zero::zero();
-            let mut n = 1;
-            while m >= b {
-                let (d0, d_unit, b_unit) = div_estimate(&m, &b, n);
-                let mut d0 = d0;
-                let mut prod = b * d0;
-                while prod > m {
-                    // FIXME(#5992): assignment operator overloads
-                    // d0 -= d_unit
-                    d0   = d0 - d_unit;
-                    // FIXME(#5992): assignment operator overloads
-                    // prod -= b_unit;
-                    prod = prod - b_unit
-                }
-                if d0.is_zero() {
-                    n = 2;
-                    continue;
-                }
-                n = 1;
-                // FIXME(#5992): assignment operator overloads
-                // d += d0;
-                d = d + d0;
-                // FIXME(#5992): assignment operator overloads
-                // m -= prod;
-                m = m - prod;
-            }
-            return (d, m);
+        if other.data.len() >= BURNIKEL_ZIEGLER_THRESHOLD
+            && self.data.len() - other.data.len() >= BURNIKEL_ZIEGLER_THRESHOLD {
+            return div_rem_burnikel_ziegler(self, other);
         }
 
-
-        fn div_estimate(a: &BigUint, b: &BigUint, n: uint)
-            -> (BigUint, BigUint, BigUint) {
-            if a.data.len() < n {
-                return (Zero::zero(), Zero::zero(), (*a).clone());
-            }
-
-            let an = a.data.tailn(a.data.len() - n);
-            let bn = *b.data.last().unwrap();
-            let mut d = Vec::with_capacity(an.len());
-            let mut carry = 0;
-            for elt in an.iter().rev() {
-                let ai = BigDigit::to_doublebigdigit(carry, *elt);
-                let di = ai / (bn as DoubleBigDigit);
-                assert!(di < BigDigit::base);
-                carry = (ai % (bn as DoubleBigDigit)) as BigDigit;
-                d.push(di as BigDigit)
-            }
-            d.reverse();
-
-            let shift = (a.data.len() - an.len()) - (b.data.len() - 1);
-            if shift == 0 {
-                return (BigUint::new(d), One::one(), (*b).clone());
-            }
-            let one: BigUint = One::one();
-            return (BigUint::new(d).shl_unit(shift),
-                    one.shl_unit(shift),
-                    b.shl_unit(shift));
-        }
+        // Knuth's algorithm needs the top bit of the divisor to be set.
+        let shift = other.data.last().unwrap().leading_zeros();
+        let (d, m) = div_rem_knuth(&(self << shift), &(other << shift));
+        return (d, m >> shift);
     }
 
     /// Calculates the Greatest Common Divisor (GCD) of the number and `other`.
@@ -555,6 +628,158 @@ impl Integer for BigUint {
     fn is_odd(&self) -> bool { !self.is_even() }
 }
 
+// Divides `a` by the single digit `b`.
+fn div_rem_digit(a: &BigUint, b: BigDigit) -> (BigUint, BigDigit) {
+    let mut q = Vec::from_elem(a.data.len(), ZERO_BIG_DIGIT);
+    let mut rem = 0;
+    {
+        let q = q.as_mut_slice();
+        for i in range(0, a.data.len()).rev() {
+            let n = BigDigit::to_doublebigdigit(rem, a.data.as_slice()[i]);
+            q[i] = (n / (b as DoubleBigDigit)) as BigDigit;
+            rem = (n % (b as DoubleBigDigit)) as BigDigit;
+        }
+    }
+    (BigUint::new(q), rem)
+}
+
+// Divides `a` by `b` using Knuth's algorithm D (The Art of Computer
+// Programming, vol. 2, section 4.3.1). `b` must have at least two digits and
+// the top bit of its last digit must be set.
+fn div_rem_knuth(a: &BigUint, b: &BigUint) -> (BigUint, BigUint) {
+    if *a < *b { return (Zero::zero(), (*a).clone()); }
+
+    let v = b.data.as_slice();
+    let n = v.len();
+    let m = a.data.len() - n;
+    let v_top = v[n - 1] as DoubleBigDigit;
+    let v_next = v[n - 2] as DoubleBigDigit;
+
+    // `u` holds the remainder, which has one more digit than `a` to start with
+    let mut u = a.data.clone();
+    u.push(ZERO_BIG_DIGIT);
+    let mut q = Vec::from_elem(m + 1, ZERO_BIG_DIGIT);
+    {
+        let u = u.as_mut_slice();
+        let q = q.as_mut_slice();
+        for j in range(0, m + 1).rev() {
+            // Estimate the next digit of the quotient from the top digits of
+            // the remainder and the divisor. This is at most one too large
+            // once corrected using the next digit of the divisor.
+            let top = BigDigit::to_doublebigdigit(u[j + n], u[j + n - 1]);
+            let mut q_hat = top / v_top;
+            let mut r_hat = top % v_top;
+            while q_hat >= BigDigit::base
+                || q_hat * v_next > BigDigit::to_doublebigdigit(r_hat as BigDigit,
+                                                                u[j + n - 2]) {
+                q_hat -= 1;
+                r_hat += v_top;
+                if r_hat >= BigDigit::base { break; }
+            }
+
+            // Subtract q_hat * v from the remainder.
+            let mut borrow = 0i64;
+            for i in range(0, n) {
+                let p = q_hat * (v[i] as DoubleBigDigit);
+                let t = (u[i + j] as i64) - borrow - ((p as BigDigit) as i64);
+                u[i + j] = t as BigDigit;
+                borrow = ((p >> BigDigit::bits) as i64) - (t >> BigDigit::bits);
+            }
+            let t = (u[j + n] as i64) - borrow;
+            u[j + n] = t as BigDigit;
+
+            // If that went negative, q_hat was one too large, so add v back.
+            if t < 0 {
+                q_hat -= 1;
+                let mut carry = 0;
+                for i in range(0, n) {
+                    let (hi, lo) = BigDigit::from_doublebigdigit(
+                        (u[i + j] as DoubleBigDigit) + (v[i] as DoubleBigDigit)
+                            + (carry as DoubleBigDigit));
+                    u[i + j] = lo;
+                    carry = hi;
+                }
+                u[j + n] += carry;
+            }
+            q[j] = q_hat as BigDigit;
+        }
+    }
+    u.truncate(n);
+    (BigUint::new(q), BigUint::new(u))
+}
+
+// Divides `a` by `b` using the recursive algorithm of Burnikel and Ziegler
+// ("Fast Recursive Division", 1998), which reduces division to
+// multiplication and is much faster than Knuth's for large divisors.
+fn div_rem_burnikel_ziegler(a: &BigUint, b: &BigUint) -> (BigUint, BigUint) {
+    // Pad the divisor to n = j * 2^k digits, where j is below the threshold,
+    // so that it can be halved k times, and normalize it.
+    let s = b.data.len();
+    let mut k = 0;
+    while s > BURNIKEL_ZIEGLER_THRESHOLD << k { k += 1; }
+    let n = ((s + (1 << k) - 1) >> k) << k;
+    let sigma = (n - s) * BigDigit::bits + b.data.last().unwrap().leading_zeros();
+    let a = a << sigma;
+    let b = b << sigma;
+
+    // Split the dividend into t blocks of n digits, the top one of which has
+    // its top bit clear so that it is less than the divisor, and divide one
+    // block at a time.
+    let n_bits = n * BigDigit::bits;
+    let t = cmp::max(2, (a.bits() + n_bits) / n_bits);
+    let mut z = a.shr_unit((t - 2) * n);
+    let mut q: BigUint = Zero::zero();
+    let mut i = t - 2;
+    loop {
+        let (qi, ri) = div_2n1n(&z, &b, n);
+        q = q.shl_unit(n) + qi;
+        if i == 0 {
+            return (q, ri >> sigma);
+        }
+        i -= 1;
+        z = ri.shl_unit(n) + digits(&a, i * n, n);
+    }
+
+    // Divides `a` by the `n` digit `b`, where `a < b * base^n`.
+    fn div_2n1n(a: &BigUint, b: &BigUint, n: uint) -> (BigUint, BigUint) {
+        if n % 2 == 1 || n < BURNIKEL_ZIEGLER_THRESHOLD {
+            return div_rem_knuth(a, b);
+        }
+        let half = n / 2;
+        let (q1, r) = div_3n2n(&a.shr_unit(half), b, half);
+        let (q2, s) = div_3n2n(&(r.shl_unit(half) + digits(a, 0, half)), b, half);
+        (q1.shl_unit(half) + q2, s)
+    }
+
+    // Divides `a` by the `2 * n` digit `b`, where `a < b * base^n`.
+    fn div_3n2n(a: &BigUint, b: &BigUint, n: uint) -> (BigUint, BigUint) {
+        let b1 = b.shr_unit(n);
+        let b2 = digits(b, 0, n);
+        let a12 = a.shr_unit(n);
+        let (mut q, r1) = if a.shr_unit(2 * n) < b1 {
+            div_2n1n(&a12, &b1, n)
+        } else {
+            // the quotient is at most base^n - 1
+            let one: BigUint = One::one();
+            (one.shl_unit(n) - one, a12 + b1 - b1.shl_unit(n))
+        };
+        let d = q * b2;
+        let mut r = r1.shl_unit(n) + digits(a, 0, n);
+        while r < d {
+            r = r + *b;
+            q = q - One::one();
+        }
+        (q, r - d)
+    }
+
+    // The `len` digits of `n` starting at digit `start`.
+    fn digits(n: &BigUint, start: uint, len: uint) -> BigUint {
+        let start = cmp::min(start, n.data.len());
+        let end = cmp::min(start + len, n.data.len());
+        BigUint::from_slice(n.data.slice(start, end))
+    }
+}
+
 impl ToPrimitive for BigUint {
     #[inline]
     fn to_i64(&self) -> Option<i64> {
@@ -663,20 +888,47 @@ impl ToStrRadix for BigUint {
         return fill_concat(convert_base(self, base).as_slice(), radix, max_len);
 
         fn convert_base(n: &BigUint, base: DoubleBigDigit) -> Vec<BigDigit> {
-            let divider    = base.to_biguint().unwrap();
-            let mut result = Vec::new();
-            let mut m      = n.clone();
-            while m >= divider {
-                let (d, m0) = m.div_mod_floor(&divider);
-                result.push(m0.to_uint().unwrap() as BigDigit);
-                m = d;
-            }
-            if !m.is_zero() {
-                result.push(m.to_uint().unwrap() as BigDigit);
+            // Large numbers are converted by divide and conquer, splitting
+            // them by the largest of the powers base^(2^i) below them.
+            let mut powers = vec!(base.to_biguint().unwrap());
+            if n.data.len() > RADIX_DC_THRESHOLD {
+                loop {
+                    let next = *powers.last().unwrap() * *powers.last().unwrap();
+                    if next > *n { break; }
+                    powers.push(next);
+                }
             }
+            let mut result = Vec::new();
+            push_digits(n, base as BigDigit, powers.as_slice(), false, &mut result);
             return result;
         }
 
+        // Appends the digits of `n` in base `base` to `out`, least
+        // significant first, where `powers[i]` is base^(2^i) and `n` is less
+        // than base^(2^powers.len()). If `pad` is set, exactly
+        // 2^powers.len() digits are appended.
+        fn push_digits(n: &BigUint, base: BigDigit, powers: &[BigUint], pad: bool,
+                       out: &mut Vec<BigDigit>) {
+            let k = powers.len();
+            if k == 0 || n.data.len() <= RADIX_DC_THRESHOLD {
+                let start = out.len();
+                let mut m = n.clone();
+                while !m.is_zero() {
+                    let (d, m0) = div_rem_digit(&m, base);
+                    out.push(m0);
+                    m = d;
+                }
+                if pad {
+                    let len = out.len() - start;
+                    out.grow((1 << k) - len, &ZERO_BIG_DIGIT);
+                }
+                return;
+            }
+            let (hi, lo) = n.div_rem(&powers[k - 1]);
+            push_digits(&lo, base, powers.slice_to(k - 1), true, out);
+            push_digits(&hi, base, powers.slice_to(k - 1), pad, out);
+        }
+
         fn fill_concat(v: &[BigDigit], radix: uint, l: uint) -> String {
             if v.is_empty() {
                 return "0".to_string()
@@ -723,38 +975,155 @@ impl BigUint {
     /// Creates and initializes a `BigUint`.
     pub fn parse_bytes(buf: &[u8], radix: uint) -> Option<BigUint> {
         let (base, unit_len) = get_radix_base(radix);
-        let base_num = match base.to_biguint() {
-            Some(base_num) => base_num,
-            None => { return None; }
-        };
 
-        let mut end             = buf.len();
-        let mut n: BigUint      = Zero::zero();
-        let mut power: BigUint  = One::one();
+        // Parse the digits in base `base`, least significant first, from
+        // `unit_len` characters at a time.
+        let mut digits = Vec::with_capacity(buf.len() / unit_len + 1);
+        let mut end = buf.len();
         loop {
             let start = cmp::max(end, unit_len) - unit_len;
             match uint::parse_bytes(buf.slice(start, end), radix) {
-                Some(d) => {
-                    let d: Option<BigUint> = FromPrimitive::from_uint(d);
-                    match d {
-                        Some(d) => {
-                            // FIXME(#5992): assignment operator overloads
-                            // n += d * power;
-                            n = n + d * power;
-                        }
-                        None => { return None; }
-                    }
-                }
+                Some(d) => digits.push(d as BigDigit),
                 None => { return None; }
             }
             if end <= unit_len {
-                return Some(n);
+                break;
             }
             end -= unit_len;
-            // FIXME(#5992): assignment operator overloads
-            // power *= base_num;
-            power = power * base_num;
         }
+
+        if base == BigDigit::base {
+            return Some(BigUint::new(digits));
+        }
+        let mut powers = Vec::new();
+        return Some(combine_digits(digits.as_slice(), base as BigDigit, &mut powers));
+
+        // Returns the number with the given digits in base `base`, least
+        // significant first. Large numbers are combined by divide and
+        // conquer, with `powers[i]` caching base^(2^i).
+        fn combine_digits(digits: &[BigDigit], base: BigDigit,
+                          powers: &mut Vec<BigUint>) -> BigUint {
+            if digits.len() <= RADIX_DC_THRESHOLD {
+                let mut n: Vec<BigDigit> = Vec::with_capacity(digits.len());
+                for &d in digits.iter().rev() {
+                    let mut carry = d;
+                    for ni in n.mut_iter() {
+                        let (hi, lo) = BigDigit::from_doublebigdigit(
+                            (*ni as DoubleBigDigit) * (base as DoubleBigDigit)
+                                + (carry as DoubleBigDigit));
+                        *ni = lo;
+                        carry = hi;
+                    }
+                    if carry != 0 { n.push(carry); }
+                }
+                return BigUint::new(n);
+            }
+
+            // split off the lowest 2^k digits, where 2^k < len <= 2^(k+1)
+            let mut k = 0u;
+            while (1 << (k + 1)) < digits.len() { k += 1; }
+            while powers.len() <= k {
+                let next = match powers.last() {
+                    Some(p) => *p * *p,
+                    None => BigUint::new(vec!(base))
+                };
+                powers.push(next);
+            }
+            let lo = combine_digits(digits.slice_to(1 << k), base, powers);
+            let hi = combine_digits(digits.slice_from(1 << k), base, powers);
+            hi * (*powers)[k] + lo
+        }
+    }
+
+    /// Returns `self` raised to the power `exponent`, modulo `modulus`.
+    ///
+    /// Fails if `modulus` is zero.
+    pub fn modpow(&self, exponent: &BigUint, modulus: &BigUint) -> BigUint {
+        assert!(!modulus.is_zero(), "The modulus must not be zero");
+        let one: BigUint = One::one();
+        if *modulus == one { return Zero::zero(); }
+
+        // Using fixed windows of 4 bits of the exponent, which never span
+        // two digits, with a table of the powers of `self` from 0 to 15.
+        let base = *self % *modulus;
+        let mut table = Vec::with_capacity(16);
+        table.push(one.clone());
+        for i in range(1u, 16) {
+            let next = table[i - 1] * base % *modulus;
+            table.push(next);
+        }
+
+        let mut result = one;
+        let mut i = (exponent.bits() + 3) / 4;
+        while i > 0 {
+            i -= 1;
+            for _ in range(0u, 4) {
+                result = result * result % *modulus;
+            }
+            let digit = exponent.data.as_slice()[i * 4 / BigDigit::bits];
+            let window = (digit >> (i * 4 % BigDigit::bits)) & 0xf;
+            if window != 0 {
+                result = result * table[window as uint] % *modulus;
+            }
+        }
+        result
+    }
+
+    /// Returns the inverse of `self` modulo `modulus`, that is the `x` less
+    /// than `modulus` such that `self * x` is `1` modulo `modulus`, or `None`
+    /// if `self` and `modulus` are not coprime.
+    ///
+    /// Fails if `modulus` is zero.
+    pub fn modinv(&self, modulus: &BigUint) -> Option<BigUint> {
+        assert!(!modulus.is_zero(), "The modulus must not be zero");
+
+        // Using the extended Euclidean algorithm, keeping track of only the
+        // coefficients of `self`.
+        let m = BigInt::from_biguint(Plus, modulus.clone());
+        let mut r0 = m.clone();
+        let mut r1 = BigInt::from_biguint(Plus, *self % *modulus);
+        let mut t0: BigInt = Zero::zero();
+        let mut t1: BigInt = One::one();
+        while !r1.is_zero() {
+            let (q, r2) = r0.div_rem(&r1);
+            let t2 = t0 - q * t1;
+            r0 = r1;
+            r1 = r2;
+            t0 = t1;
+            t1 = t2;
+        }
+        if r0 != One::one() {
+            return None;
+        }
+        t0.mod_floor(&m).to_biguint()
+    }
+
+    /// Returns the integer square root of `self`, the largest number whose
+    /// square is no greater than `self`.
+    pub fn sqrt(&self) -> BigUint {
+        if self.is_zero() { return Zero::zero(); }
+
+        // Using Newton's method, starting from a power of two no less than
+        // the root, from which the estimates decrease until they reach it.
+        let one: BigUint = One::one();
+        let mut x = one << ((self.bits() + 1) / 2);
+        loop {
+            let y = (x + *self / x) >> 1;
+            if y >= x {
+                return x;
+            }
+            x = y;
+        }
+    }
+
+    /// Tests whether `self` is prime, using trial division by small primes
+    /// followed by `rounds` rounds of the Miller-Rabin test with random bases
+    /// from `task_rng`.
+    ///
+    /// Primes always pass the test, and a composite number passes with
+    /// probability at most 4^-`rounds`.
+    pub fn is_probable_prime(&self, rounds: uint) -> bool {
+        is_probable_prime(self, rounds, &mut task_rng())
     }
 
     #[inline]
@@ -811,6 +1180,45 @@ impl BigUint {
     }
 }
 
+fn is_probable_prime<R: Rng>(n: &BigUint, rounds: uint, rng: &mut R) -> bool {
+    let one: BigUint = One::one();
+    let two = one + one;
+    if *n < two { return false; }
+
+    for &p in SMALL_PRIMES.iter() {
+        let (_, rem) = div_rem_digit(n, p);
+        if rem == 0 {
+            return n.data.len() == 1 && n.data.as_slice()[0] == p;
+        }
+    }
+    let largest = *SMALL_PRIMES.last().unwrap();
+    if n.data.len() == 1 && n.data.as_slice()[0] < largest * largest {
+        return true;
+    }
+
+    // n - 1 = d * 2^s, with d odd
+    let n_minus_one = *n - one;
+    let mut d = n_minus_one.clone();
+    let mut s = 0u;
+    while d.is_even() {
+        d = d >> 1;
+        s += 1;
+    }
+
+    'rounds: for _ in range(0, rounds) {
+        let a = rng.gen_biguint_range(&two, &n_minus_one);
+        let mut x = a.modpow(&d, n);
+        if x == one || x == n_minus_one { continue; }
+        for _ in range(1, s) {
+            x = x * x % *n;
+            if x == n_minus_one { continue 'rounds; }
+            if x == one { return false; }
+        }
+        return false;
+    }
+    true
+}
+
 // `DoubleBigDigit` size dependent
 #[inline]
 fn get_radix_base(radix: uint) -> (DoubleBigDigit, uint) {
@@ -1304,6 +1712,11 @@ pub trait RandBigInt {
     /// bound is inclusive; the upper bound is exclusive. Fails when
     /// the upper bound is not greater than the lower bound.
     fn gen_bigint_range(&mut self, lbound: &BigInt, ubound: &BigInt) -> BigInt;
+
+    /// Generate a random prime `BigUint` of the given bit size, that is with
+    /// its top bit set. The result is composite with probability at most
+    /// 2^-64. Fails when the bit size is less than 2.
+    fn gen_prime(&mut self, bit_size: uint) -> BigUint;
 }
 
 impl<R: Rng> RandBigInt for R {
@@ -1367,6 +1780,21 @@ impl<R: Rng> RandBigInt for R {
         let delta = (*ubound - *lbound).to_biguint().unwrap();
         return *lbound + self.gen_biguint_below(&delta).to_bigint().unwrap();
     }
+
+    fn gen_prime(&mut self, bit_size: uint) -> BigUint {
+        assert!(bit_size >= 2);
+        let one: BigUint = One::one();
+        let top = one << (bit_size - 1);
+        loop {
+            let mut n = self.gen_biguint(bit_size - 1) + top;
+            if bit_size > 2 {
+                n = n | one;
+            }
+            if is_probable_prime(&n, PRIME_TEST_ROUNDS, self) {
+                return n;
+            }
+        }
+    }
 }
 
 impl BigInt {
@@ -1424,10 +1852,13 @@ mod biguint_tests {
     use Integer;
     use super::{BigDigit, BigUint, ToBigUint};
     use super::{Plus, BigInt, RandBigInt, ToBigInt};
+    use super::{mul_schoolbook, mul_karatsuba, mul_toom3};
+    use super::{div_rem_knuth, div_rem_burnikel_ziegler};
 
     use std::cmp::{Less, Equal, Greater};
     use std::from_str::FromStr;
     use std::i64;
+    use std::num;
     use std::num::{Zero, One, FromStrRadix, ToStrRadix};
     use std::num::{ToPrimitive, FromPrimitive};
     use std::num::CheckedDiv;
@@ -2280,6 +2711,230 @@ mod biguint_tests {
         // Switching u and l should fail:
         let _n: BigUint = rng.gen_biguint_range(&u, &l);
     }
+
+    #[test]
+    fn test_mul_algorithms() {
+        let mut rng = task_rng();
+        for &(a_bits, b_bits) in [(3000u, 3000u), (3000, 1100), (9000, 9000),
+                                  (9000, 12000), (20000, 9000)].iter() {
+            let a = rng.gen_biguint(a_bits);
+            let b = rng.gen_biguint(b_bits);
+            let expected = mul_schoolbook(a.data.as_slice(), b.data.as_slice());
+            assert_eq!(mul_karatsuba(&a, &b), expected);
+            assert_eq!(mul_toom3(&a, &b), expected);
+            assert_eq!(a * b, expected);
+        }
+
+        // all digits at their maximum, to exercise the carries
+        let a = BigUint::new(Vec::from_elem(300, -1 as BigDigit));
+        let one: BigUint = One::one();
+        let expected = (one << 19200) - (one << 9601) + one;
+        assert_eq!(mul_toom3(&a, &a), expected);
+        assert_eq!(mul_karatsuba(&a, &a), expected);
+    }
+
+    #[test]
+    fn test_div_rem_algorithms() {
+        let mut rng = task_rng();
+        for &(a_bits, b_bits) in [(5000u, 2100u), (20000, 2100), (20000, 9000),
+                                  (40000, 19999)].iter() {
+            let a = rng.gen_biguint(a_bits);
+            let b = rng.gen_biguint(b_bits);
+            let (q, r) = div_rem_burnikel_ziegler(&a, &b);
+            assert!(r < b);
+            assert_eq!(q * b + r, a);
+
+            let shift = b.data.last().unwrap().leading_zeros();
+            let (q2, r2) = div_rem_knuth(&(a << shift), &(b << shift));
+            assert_eq!(q2, q);
+            assert_eq!(r2 >> shift, r);
+            assert_eq!(a.div_rem(&b), (q, r));
+        }
+
+        // a quotient digit whose first estimate is too large
+        let one: BigUint = One::one();
+        let b = (one << 3000) - (one << 1000);
+        let a = (one << 9000) - one;
+        let (q, r) = a.div_rem(&b);
+        assert!(r < b);
+        assert_eq!(q * b + r, a);
+    }
+
+    #[test]
+    fn test_large_radix() {
+        let mut rng = task_rng();
+        for &bits in [100u, 2000, 5000, 30000].iter() {
+            let n = rng.gen_biguint(bits);
+            for &radix in [3u, 7, 10, 16].iter() {
+                let s = n.to_str_radix(radix);
+                assert_eq!(FromStrRadix::from_str_radix(s.as_slice(), radix), Some(n.clone()));
+            }
+        }
+
+        let ten: BigUint = FromPrimitive::from_uint(10).unwrap();
+        let n = num::pow(ten, 3000);
+        let s = n.to_string();
+        assert_eq!(s.len(), 3001);
+        assert_eq!(s.as_slice().slice_to(1), "1");
+        assert!(s.as_slice().slice_from(1).chars().all(|c| c == '0'));
+        assert_eq!(from_str(s.as_slice()), Some(n.clone()));
+        assert_eq!(from_str((n - One::one()).to_string().as_slice()), Some(n - One::one()));
+        assert_eq!((n - One::one()).to_string(), "9".repeat(3000));
+    }
+
+    #[test]
+    fn test_modpow() {
+        fn check(b: uint, e: uint, m: uint, expected: uint) {
+            let b: BigUint = FromPrimitive::from_uint(b).unwrap();
+            let e: BigUint = FromPrimitive::from_uint(e).unwrap();
+            let m: BigUint = FromPrimitive::from_uint(m).unwrap();
+            let expected: BigUint = FromPrimitive::from_uint(expected).unwrap();
+            assert_eq!(b.modpow(&e, &m), expected);
+        }
+        check(4, 13, 497, 445);
+        check(2, 0, 7, 1);
+        check(0, 0, 7, 1);
+        check(0, 5, 7, 0);
+        check(5, 3, 1, 0);
+        check(123456789, 987654321, 1000000007, 652541198);
+
+        // Fermat's little theorem for the Mersenne prime 2^521 - 1
+        let one: BigUint = One::one();
+        let p = (one << 521) - one;
+        let a = task_rng().gen_biguint_below(&p);
+        if !a.is_zero() {
+            assert_eq!(a.modpow(&(p - one), &p), one);
+        }
+
+        let mut rng = task_rng();
+        for _ in range(0u, 10) {
+            let b = rng.gen_biguint(200);
+            let m = rng.gen_biguint(150) + one;
+            let expected = num::pow(b.clone(), 37) % m;
+            assert_eq!(b.modpow(&FromPrimitive::from_uint(37).unwrap(), &m), expected);
+        }
+    }
+
+    #[test]
+    #[should_fail]
+    fn test_modpow_zero_modulus() {
+        let one: BigUint = One::one();
+        one.modpow(&one, &Zero::zero());
+    }
+
+    #[test]
+    fn test_modinv() {
+        fn check(a: uint, m: uint, expected: Option<uint>) {
+            let a: BigUint = FromPrimitive::from_uint(a).unwrap();
+            let m: BigUint = FromPrimitive::from_uint(m).unwrap();
+            let expected: Option<BigUint> = expected.map(|x| FromPrimitive::from_uint(x).unwrap());
+            assert_eq!(a.modinv(&m), expected);
+        }
+        check(3, 11, Some(4));
+        check(10, 17, Some(12));
+        check(1, 2, Some(1));
+        check(20, 17, Some(6));
+        check(6, 9, None);
+        check(0, 7, None);
+
+        let mut rng = task_rng();
+        let one: BigUint = One::one();
+        let m = (one << 521) - one;
+        for _ in range(0u, 10) {
+            let a = rng.gen_biguint_below(&m);
+            if a.is_zero() { continue; }
+            let inv = a.modinv(&m).unwrap();
+            assert!(inv < m);
+            assert_eq!(a * inv % m, one);
+        }
+    }
+
+    #[test]
+    fn test_sqrt() {
+        fn check(n: uint, expected: uint) {
+            let n: BigUint = FromPrimitive::from_uint(n).unwrap();
+            let expected: BigUint = FromPrimitive::from_uint(expected).unwrap();
+            assert_eq!(n.sqrt(), expected);
+        }
+        check(0, 0);
+        check(1, 1);
+        check(3, 1);
+        check(4, 2);
+        check(15, 3);
+        check(16, 4);
+        check(17, 4);
+        check(1000000, 1000);
+
+        let mut rng = task_rng();
+        let one: BigUint = One::one();
+        for &bits in [31u, 32, 33, 64, 1000, 5001].iter() {
+            let x = rng.gen_biguint(bits) + one;
+            let square = x * x;
+            assert_eq!(square.sqrt(), x);
+            assert_eq!((square - one).sqrt(), x - one);
+            assert_eq!((square + x + x).sqrt(), x);
+        }
+    }
+
+    #[test]
+    fn test_is_probable_prime() {
+        let primes = [2u, 3, 5, 7, 251, 257, 65521, 65537, 1000000007, 4294967291];
+        let composites = [0u, 1, 4, 9, 561, 41041, 63001, 65535, 1000000005, 4294967295];
+        for &p in primes.iter() {
+            let p: BigUint = FromPrimitive::from_uint(p).unwrap();
+            assert!(p.is_probable_prime(20), "{} is prime", p);
+        }
+        for &c in composites.iter() {
+            let c: BigUint = FromPrimitive::from_uint(c).unwrap();
+            assert!(!c.is_probable_prime(20), "{} is composite", c);
+        }
+
+        let one: BigUint = One::one();
+        let m127 = (one << 127) - one;
+        let m521 = (one << 521) - one;
+        assert!(m127.is_probable_prime(20));
+        assert!(m521.is_probable_prime(20));
+        assert!(!(m127 * m521).is_probable_prime(20));
+        assert!(!((one << 521) + one).is_probable_prime(20));
+        // a Carmichael number, (6k + 1)(12k + 1)(18k + 1) for k = 2^60 + 330,
+        // the least k above 2^60 for which all three factors are prime
+        let carmichael: BigUint =
+            from_str("1986114220962193666411479745396577410163873092323808013209").unwrap();
+        assert!(!carmichael.is_probable_prime(20));
+        // 149491 * 747451 * 34233211, a strong pseudoprime to every prime base
+        // up to 31
+        let pseudoprime: BigUint = from_str("3825123056546413051").unwrap();
+        assert!(!pseudoprime.is_probable_prime(20));
+    }
+
+    #[test]
+    fn test_gen_prime() {
+        let mut rng = task_rng();
+        for &bits in [2u, 3, 16, 64, 200].iter() {
+            let p = rng.gen_prime(bits);
+            assert_eq!(p.bits(), bits);
+            assert!(p.is_probable_prime(20));
+        }
+    }
+
+    #[test]
+    fn test_rsa() {
+        let mut rng = task_rng();
+        let one: BigUint = One::one();
+        let e: BigUint = FromPrimitive::from_uint(65537).unwrap();
+        let mut key = None;
+        while key.is_none() {
+            let p = rng.gen_prime(256);
+            let q = rng.gen_prime(256);
+            key = e.modinv(&((p - one) * (q - one))).map(|d| (p * q, d));
+        }
+        let (n, d) = key.unwrap();
+        for _ in range(0u, 5) {
+            let message = rng.gen_biguint_below(&n);
+            let ciphertext = message.modpow(&e, &n);
+            assert_eq!(ciphertext.modpow(&d, &n), message);
+        }
+    }
 }
 
 #[cfg(test)]
@@ -2894,10 +3549,12 @@ mod bigint_tests {
 mod bench {
     extern crate test;
     use self::test::Bencher;
-    use super::BigUint;
+    use super::{BigUint, RandBigInt};
+    use Integer;
     use std::iter;
     use std::mem::replace;
-    use std::num::{FromPrimitive, Zero, One};
+    use std::num::{FromPrimitive, FromStrRadix, ToStrRadix, Zero, One};
+    use std::rand::task_rng;
 
     fn factorial(n: uint) -> BigUint {
         let mut f: BigUint = One::one();
@@ -2953,4 +3610,72 @@ mod bench {
             }
         })
     }
+    fn multiply_bench(b: &mut Bencher, bits: uint) {
+        let mut rng = task_rng();
+        let x = rng.gen_biguint(bits);
+        let y = rng.gen_biguint(bits);
+        b.iter(|| x * y);
+    }
+
+    #[bench]
+    fn multiply_schoolbook(b: &mut Bencher) {
+        multiply_bench(b, 800);
+    }
+
+    #[bench]
+    fn multiply_karatsuba(b: &mut Bencher) {
+        multiply_bench(b, 4000);
+    }
+
+    #[bench]
+    fn multiply_toom3(b: &mut Bencher) {
+        multiply_bench(b, 40000);
+    }
+
+    fn divide_bench(b: &mut Bencher, bits: uint) {
+        let mut rng = task_rng();
+        let x = rng.gen_biguint(bits * 2);
+        let y = rng.gen_biguint(bits);
+        b.iter(|| x.div_rem(&y));
+    }
+
+    #[bench]
+    fn divide_knuth(b: &mut Bencher) {
+        divide_bench(b, 1000);
+    }
+
+    #[bench]
+    fn divide_burnikel_ziegler(b: &mut Bencher) {
+        divide_bench(b, 20000);
+    }
+
+    #[bench]
+    fn to_str_radix_large(b: &mut Bencher) {
+        let n = task_rng().gen_biguint(100000);
+        b.iter(|| n.to_str_radix(10));
+    }
+
+    #[bench]
+    fn from_str_radix_large(b: &mut Bencher) {
+        let s = task_rng().gen_biguint(100000).to_str_radix(10);
+        b.iter(|| {
+            let n: Option<BigUint> = FromStrRadix::from_str_radix(s.as_slice(), 10);
+            n
+        });
+    }
+
+    #[bench]
+    fn modpow_1024(b: &mut Bencher) {
+        let mut rng = task_rng();
+        let base = rng.gen_biguint(1024);
+        let exponent = rng.gen_biguint(1024);
+        let modulus = rng.gen_biguint(1024) | One::one();
+        b.iter(|| base.modpow(&exponent, &modulus));
+    }
+
+    #[bench]
+    fn is_probable_prime_512(b: &mut Bencher) {
+        let p = task_rng().gen_prime(512);
+        b.iter(|| p.is_probable_prime(10));
+    }
 }
@@ -0,0 +1,703 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Regular expressions that search arbitrary bytes.
+//!
+//! The API in this module mirrors the API for `Regex`, except that it
+//! searches `&[u8]` instead of `&str`. The search text doesn't need to be
+//! valid UTF-8, which makes it possible to search binary data, or text in
+//! an unknown or legacy encoding.
+//!
+//! By default, an expression still matches Unicode characters, which must
+//! be encoded as UTF-8 in the search text. (e.g., `.` matches any UTF-8
+//! encoded character other than `\n` and never matches a byte that isn't
+//! part of one.) Unicode can be disabled with the `u` flag, in which case
+//! `.`, character classes, escapes like `\xFF` and case insensitive matches
+//! apply to single bytes instead:
+//!
+//! ```rust
+//! use regex::bytes::Regex;
+//!
+//! let re = Regex::new(r"(?-u)\xFF(.)\xFF").unwrap();
+//! let caps = re.captures(b"\x00\xFF\x80\xFF").unwrap();
+//! assert_eq!(caps.at(1), b"\x80");
+//! ```
+//!
+//! When Unicode is disabled, `\w`, `\d`, `\s` and `\b` only recognize ASCII
+//! characters. A non-ASCII character that is written literally in such an
+//! expression matches its UTF-8 encoding, but it can't be used in a character
+//! class.
+//!
+//! All positions returned are byte indices. Unlike with `Regex`, they may not
+//! be at a UTF-8 character boundary.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::from_str::from_str;
+use std::str;
+
+use compile::Program;
+use dfa;
+use parse;
+use vm;
+use vm::{CaptureLocs, MatchKind, Exists, Location, Submatches};
+
+/// A compiled regular expression for searching bytes.
+///
+/// Apart from the type of the search text, it works exactly like `Regex`.
+/// It can also be compiled to native Rust code with the `regex_bytes!`
+/// macro:
+///
+/// ```rust
+/// #![feature(phase)]
+/// extern crate regex;
+/// #[phase(plugin)] extern crate regex_macros;
+///
+/// fn main() {
+///     let re = regex_bytes!(r"(?-u)[\x80-\xFF]+");
+///     assert_eq!(re.find(b"abc\xE9\xFFdef"), Some((3, 5)));
+/// }
+/// ```
+#[deriving(Clone)]
+#[allow(visible_private_types)]
+pub enum Regex {
+    // The representation of `Regex` is exported to support the
+    // `regex_bytes!` syntax extension. Do not rely on it.
+    #[doc(hidden)]
+    Dynamic(Dynamic),
+    #[doc(hidden)]
+    Native(Native),
+}
+
+#[deriving(Clone)]
+#[doc(hidden)]
+pub struct Dynamic {
+    original: String,
+    names: Vec<Option<String>>,
+    #[doc(hidden)]
+    pub prog: Program
+}
+
+#[doc(hidden)]
+pub struct Native {
+    #[doc(hidden)]
+    pub original: &'static str,
+    #[doc(hidden)]
+    pub names: &'static [Option<&'static str>],
+    #[doc(hidden)]
+    pub prog: fn(MatchKind, &[u8], uint, uint) -> Vec<Option<uint>>
+}
+
+impl Clone for Native {
+    fn clone(&self) -> Native { *self }
+}
+
+impl fmt::Show for Regex {
+    /// Shows the original regular expression.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl Regex {
+    /// Compiles a dynamic regular expression for searching bytes.
+    ///
+    /// If an invalid expression is given, then an error is returned.
+    pub fn new(re: &str) -> Result<Regex, parse::Error> {
+        let ast = try!(parse::parse_bytes(re));
+        let (prog, names) = Program::new_bytes(ast);
+        Ok(Dynamic(Dynamic {
+            original: re.to_string(),
+            names: names,
+            prog: prog,
+        }))
+    }
+
+    /// Returns true if and only if the regex matches the bytes given.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use regex::bytes::Regex;
+    /// let re = Regex::new(r"\bcat\b").unwrap();
+    /// assert!(re.is_match(b"\xFF\xFEcat\x00"));
+    /// ```
+    pub fn is_match(&self, text: &[u8]) -> bool {
+        has_match(&exec(self, Exists, text))
+    }
+
+    /// Returns the start and end byte range of the leftmost-first match in
+    /// `text`. If no match exists, then `None` is returned.
+    pub fn find(&self, text: &[u8]) -> Option<(uint, uint)> {
+        let caps = exec(self, Location, text);
+        if has_match(&caps) {
+            Some((caps[0].unwrap(), caps[1].unwrap()))
+        } else {
+            None
+        }
+    }
+
+    /// Returns an iterator for each successive non-overlapping match in
+    /// `text`, returning the start and end byte indices with respect to
+    /// `text`.
+    pub fn find_iter<'r, 't>(&'r self, text: &'t [u8]) -> FindMatches<'r, 't> {
+        FindMatches {
+            re: self,
+            search: text,
+            last_end: 0,
+            last_match: None,
+        }
+    }
+
+    /// Returns the capture groups corresponding to the leftmost-first
+    /// match in `text`. Capture group `0` always corresponds to the entire
+    /// match. If no match is found, then `None` is returned.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use regex::bytes::Regex;
+    /// let re = Regex::new(r"(?-u)(?P<key>\w+)=(?P<value>[^\x00]*)").unwrap();
+    /// let caps = re.captures(b"\x00lang=caf\xE9\x00").unwrap();
+    /// assert_eq!(caps.name("key"), b"lang");
+    /// assert_eq!(caps.name("value"), b"caf\xE9");
+    /// ```
+    pub fn captures<'t>(&self, text: &'t [u8]) -> Option<Captures<'t>> {
+        let caps = exec(self, Submatches, text);
+        Captures::new(self, text, caps)
+    }
+
+    /// Returns an iterator over all the non-overlapping capture groups matched
+    /// in `text`. This is operationally the same as `find_iter` (except it
+    /// yields information about submatches).
+    pub fn captures_iter<'r, 't>(&'r self, text: &'t [u8])
+                                -> FindCaptures<'r, 't> {
+        FindCaptures {
+            re: self,
+            search: text,
+            last_match: None,
+            last_end: 0,
+        }
+    }
+
+    /// Returns an iterator of slices of `text` delimited by a match of the
+    /// regular expression.
+    ///
+    /// This method will *not* copy the bytes given.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use regex::bytes::Regex;
+    /// let re = Regex::new(r"(?-u)[\x00\xFF]+").unwrap();
+    /// let fields: Vec<&[u8]> = re.split(b"a\x00b\xFF\x00c").collect();
+    /// assert_eq!(fields, vec![b"a", b"b", b"c"]);
+    /// ```
+    pub fn split<'r, 't>(&'r self, text: &'t [u8]) -> RegexSplits<'r, 't> {
+        RegexSplits {
+            finder: self.find_iter(text),
+            last: 0,
+        }
+    }
+
+    /// Returns an iterator of at most `limit` slices of `text` delimited by
+    /// a match of the regular expression. (A `limit` of `0` will return no
+    /// slices.) The remainder of the bytes that are not split will be the
+    /// last element in the iterator.
+    ///
+    /// This method will *not* copy the bytes given.
+    pub fn splitn<'r, 't>(&'r self, text: &'t [u8], limit: uint)
+                         -> RegexSplitsN<'r, 't> {
+        RegexSplitsN {
+            splits: self.split(text),
+            cur: 0,
+            limit: limit,
+        }
+    }
+
+    /// Replaces the leftmost-first match with the replacement provided.
+    /// The replacement can be a byte string (where `$N` and `$name` are
+    /// expanded to match capture groups) or a function that takes the
+    /// matches' `Captures` and returns the replacement bytes.
+    ///
+    /// If no match is found, then a copy of the bytes is returned unchanged.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use regex::bytes::Regex;
+    /// let re = Regex::new(r"(?-u)(?P<hi>[\x80-\xFF])").unwrap();
+    /// let result = re.replace(b"caf\xE9!", b"<$hi>");
+    /// assert_eq!(result.as_slice(), b"caf<\xE9>!");
+    /// ```
+    pub fn replace<R: Replacer>(&self, text: &[u8], rep: R) -> Vec<u8> {
+        self.replacen(text, 1, rep)
+    }
+
+    /// Replaces all non-overlapping matches in `text` with the
+    /// replacement provided. This is the same as calling `replacen` with
+    /// `limit` set to `0`.
+    pub fn replace_all<R: Replacer>(&self, text: &[u8], rep: R) -> Vec<u8> {
+        self.replacen(text, 0, rep)
+    }
+
+    /// Replaces at most `limit` non-overlapping matches in `text` with the
+    /// replacement provided. If `limit` is 0, then all non-overlapping matches
+    /// are replaced.
+    pub fn replacen<R: Replacer>
+                   (&self, text: &[u8], limit: uint, mut rep: R) -> Vec<u8> {
+        let mut new = Vec::with_capacity(text.len());
+        let mut last_match = 0u;
+
+        for (i, cap) in self.captures_iter(text).enumerate() {
+            if limit > 0 && i >= limit {
+                break
+            }
+
+            let (s, e) = cap.pos(0).unwrap(); // captures only reports matches
+            new.push_all(text.slice(last_match, s));
+            rep.reg_replace(&cap, &mut new);
+            last_match = e;
+        }
+        new.push_all(text.slice_from(last_match));
+        new
+    }
+
+    /// Returns the original string of this regex.
+    pub fn as_str<'a>(&'a self) -> &'a str {
+        match *self {
+            Dynamic(Dynamic { ref original, .. }) => original.as_slice(),
+            Native(Native { ref original, .. }) => original.as_slice(),
+        }
+    }
+
+    #[doc(hidden)]
+    #[allow(visible_private_types)]
+    #[experimental]
+    pub fn names_iter<'a>(&'a self) -> NamesIter<'a> {
+        match *self {
+            Native(ref n) => NamesIterNative(n.names.iter()),
+            Dynamic(ref d) => NamesIterDynamic(d.names.iter())
+        }
+    }
+
+    fn names_len(&self) -> uint {
+        match *self {
+            Native(ref n) => n.names.len(),
+            Dynamic(ref d) => d.names.len()
+        }
+    }
+}
+
+enum NamesIter<'a> {
+    NamesIterNative(::std::slice::Items<'a, Option<&'static str>>),
+    NamesIterDynamic(::std::slice::Items<'a, Option<String>>)
+}
+
+impl<'a> Iterator<Option<String>> for NamesIter<'a> {
+    fn next(&mut self) -> Option<Option<String>> {
+        match *self {
+            NamesIterNative(ref mut i) => i.next().map(|x| x.map(|s| s.to_string())),
+            NamesIterDynamic(ref mut i) => i.next().map(|x| x.as_ref().map(|s| s.to_string())),
+        }
+    }
+}
+
+/// NoExpand indicates literal replacement.
+///
+/// It can be used with `replace` and `replace_all` to do a literal
+/// replacement without expanding `$name` to their corresponding capture
+/// groups.
+///
+/// `'t` is the lifetime of the literal bytes.
+pub struct NoExpand<'t>(pub &'t [u8]);
+
+/// Replacer describes types that can be used to replace matches in a byte
+/// string.
+pub trait Replacer {
+    /// Appends the bytes that replace the match corresponding to the `caps`
+    /// capture group to `dst`.
+    fn reg_replace(&mut self, caps: &Captures, dst: &mut Vec<u8>);
+}
+
+impl<'t> Replacer for NoExpand<'t> {
+    fn reg_replace(&mut self, _: &Captures, dst: &mut Vec<u8>) {
+        let NoExpand(s) = *self;
+        dst.push_all(s);
+    }
+}
+
+impl<'t> Replacer for &'t [u8] {
+    fn reg_replace(&mut self, caps: &Captures, dst: &mut Vec<u8>) {
+        caps.expand_into(*self, dst)
+    }
+}
+
+impl<'t> Replacer for |&Captures|: 't -> Vec<u8> {
+    fn reg_replace(&mut self, caps: &Captures, dst: &mut Vec<u8>) {
+        dst.push_all_move((*self)(caps))
+    }
+}
+
+/// Yields all slices delimited by a regular expression match.
+///
+/// `'r` is the lifetime of the compiled expression and `'t` is the lifetime
+/// of the bytes being split.
+pub struct RegexSplits<'r, 't> {
+    finder: FindMatches<'r, 't>,
+    last: uint,
+}
+
+impl<'r, 't> Iterator<&'t [u8]> for RegexSplits<'r, 't> {
+    fn next(&mut self) -> Option<&'t [u8]> {
+        let text = self.finder.search;
+        match self.finder.next() {
+            None => {
+                if self.last >= text.len() {
+                    None
+                } else {
+                    let s = text.slice_from(self.last);
+                    self.last = text.len();
+                    Some(s)
+                }
+            }
+            Some((s, e)) => {
+                let matched = text.slice(self.last, s);
+                self.last = e;
+                Some(matched)
+            }
+        }
+    }
+}
+
+/// Yields at most `N` slices delimited by a regular expression match.
+///
+/// The last slice will be whatever remains after splitting.
+///
+/// `'r` is the lifetime of the compiled expression and `'t` is the lifetime
+/// of the bytes being split.
+pub struct RegexSplitsN<'r, 't> {
+    splits: RegexSplits<'r, 't>,
+    cur: uint,
+    limit: uint,
+}
+
+impl<'r, 't> Iterator<&'t [u8]> for RegexSplitsN<'r, 't> {
+    fn next(&mut self) -> Option<&'t [u8]> {
+        let text = self.splits.finder.search;
+        if self.cur >= self.limit {
+            None
+        } else {
+            self.cur += 1;
+            if self.cur >= self.limit {
+                Some(text.slice_from(self.splits.last))
+            } else {
+                self.splits.next()
+            }
+        }
+    }
+}
+
+/// Captures represents a group of captured byte strings for a single match.
+///
+/// The 0th capture always corresponds to the entire match. Each subsequent
+/// index corresponds to the next capture group in the regex.
+/// If a capture group is named, then the matched bytes are *also* available
+/// via the `name` method.
+///
+/// `'t` is the lifetime of the matched bytes.
+pub struct Captures<'t> {
+    text: &'t [u8],
+    locs: CaptureLocs,
+    named: Option<HashMap<String, uint>>,
+}
+
+impl<'t> Captures<'t> {
+    #[allow(experimental)]
+    fn new(re: &Regex, search: &'t [u8], locs: CaptureLocs)
+          -> Option<Captures<'t>> {
+        if !has_match(&locs) {
+            return None
+        }
+
+        let named =
+            if re.names_len() == 0 {
+                None
+            } else {
+                let mut named = HashMap::new();
+                for (i, name) in re.names_iter().enumerate() {
+                    match name {
+                        None => {},
+                        Some(name) => {
+                            named.insert(name, i);
+                        }
+                    }
+                }
+                Some(named)
+            };
+        Some(Captures {
+            text: search,
+            locs: locs,
+            named: named,
+        })
+    }
+
+    /// Returns the start and end positions of the Nth capture group.
+    /// Returns `None` if `i` is not a valid capture group or if the capture
+    /// group did not match anything.
+    pub fn pos(&self, i: uint) -> Option<(uint, uint)> {
+        let (s, e) = (i * 2, i * 2 + 1);
+        if e >= self.locs.len() || self.locs[s].is_none() {
+            return None
+        }
+        Some((self.locs[s].unwrap(), self.locs[e].unwrap()))
+    }
+
+    /// Returns the matched bytes for the capture group `i`.
+    /// If `i` isn't a valid capture group or didn't match anything, then an
+    /// empty slice is returned.
+    pub fn at(&self, i: uint) -> &'t [u8] {
+        match self.pos(i) {
+            None => self.text.slice_to(0),
+            Some((s, e)) => self.text.slice(s, e),
+        }
+    }
+
+    /// Returns the matched bytes for the capture group named `name`.
+    /// If `name` isn't a valid capture group or didn't match anything, then
+    /// an empty slice is returned.
+    pub fn name(&self, name: &str) -> &'t [u8] {
+        match self.named {
+            None => self.text.slice_to(0),
+            Some(ref h) => {
+                match h.find_equiv(&name) {
+                    None => self.text.slice_to(0),
+                    Some(i) => self.at(*i),
+                }
+            }
+        }
+    }
+
+    /// Creates an iterator of all the capture groups in order of appearance
+    /// in the regular expression.
+    pub fn iter(&'t self) -> SubCaptures<'t> {
+        SubCaptures { idx: 0, caps: self, }
+    }
+
+    /// Creates an iterator of all the capture group positions in order of
+    /// appearance in the regular expression.
+    pub fn iter_pos(&'t self) -> SubCapturesPos<'t> {
+        SubCapturesPos { idx: 0, caps: self, }
+    }
+
+    /// Expands all instances of `$name` in `text` to the corresponding capture
+    /// group `name`.
+    ///
+    /// `name` may be an integer corresponding to the index of the capture
+    /// group or it can be a name (consisting of ASCII letters, digits or
+    /// underscores) corresponding to a named capture group. If `name` isn't
+    /// a valid capture group, then it is replaced with nothing.
+    ///
+    /// To write a literal `$` use `$$`.
+    pub fn expand(&self, text: &[u8]) -> Vec<u8> {
+        let mut dst = Vec::with_capacity(text.len());
+        self.expand_into(text, &mut dst);
+        dst
+    }
+
+    fn expand_into(&self, text: &[u8], dst: &mut Vec<u8>) {
+        let mut i = 0;
+        while i < text.len() {
+            if text[i] != b'$' {
+                dst.push(text[i]);
+                i += 1;
+                continue
+            }
+            if i + 1 < text.len() && text[i + 1] == b'$' {
+                dst.push(b'$');
+                i += 2;
+                continue
+            }
+            let mut end = i + 1;
+            while end < text.len() && is_name_byte(text[end]) {
+                end += 1;
+            }
+            if end == i + 1 {
+                // A lone `$` is copied as is.
+                dst.push(b'$');
+                i += 1;
+                continue
+            }
+            // Names are always ASCII.
+            let name = str::from_utf8(text.slice(i + 1, end)).unwrap();
+            match from_str::<uint>(name) {
+                None => dst.push_all(self.name(name)),
+                Some(n) => dst.push_all(self.at(n)),
+            }
+            i = end;
+        }
+    }
+}
+
+impl<'t> Collection for Captures<'t> {
+    /// Returns the number of captured groups.
+    #[inline]
+    fn len(&self) -> uint {
+        self.locs.len() / 2
+    }
+}
+
+/// An iterator over capture groups for a particular match of a regular
+/// expression.
+///
+/// `'t` is the lifetime of the matched bytes.
+pub struct SubCaptures<'t> {
+    idx: uint,
+    caps: &'t Captures<'t>,
+}
+
+impl<'t> Iterator<&'t [u8]> for SubCaptures<'t> {
+    fn next(&mut self) -> Option<&'t [u8]> {
+        if self.idx < self.caps.len() {
+            self.idx += 1;
+            Some(self.caps.at(self.idx - 1))
+        } else {
+            None
+        }
+    }
+}
+
+/// An iterator over capture group positions for a particular match of a
+/// regular expression.
+///
+/// `'t` is the lifetime of the matched bytes.
+pub struct SubCapturesPos<'t> {
+    idx: uint,
+    caps: &'t Captures<'t>,
+}
+
+impl<'t> Iterator<Option<(uint, uint)>> for SubCapturesPos<'t> {
+    fn next(&mut self) -> Option<Option<(uint, uint)>> {
+        if self.idx < self.caps.len() {
+            self.idx += 1;
+            Some(self.caps.pos(self.idx - 1))
+        } else {
+            None
+        }
+    }
+}
+
+/// An iterator that yields all non-overlapping capture groups matching a
+/// particular regular expression.
+///
+/// `'r` is the lifetime of the compiled expression and `'t` is the lifetime
+/// of the matched bytes.
+pub struct FindCaptures<'r, 't> {
+    re: &'r Regex,
+    search: &'t [u8],
+    last_match: Option<uint>,
+    last_end: uint,
+}
+
+impl<'r, 't> Iterator<Captures<'t>> for FindCaptures<'r, 't> {
+    fn next(&mut self) -> Option<Captures<'t>> {
+        if self.last_end > self.search.len() {
+            return None
+        }
+
+        let caps = exec_slice(self.re, Submatches, self.search,
+                              self.last_end, self.search.len());
+        let (s, e) =
+            if !has_match(&caps) {
+                return None
+            } else {
+                (caps[0].unwrap(), caps[1].unwrap())
+            };
+
+        // Don't accept empty matches immediately following a match.
+        if e == s && Some(self.last_end) == self.last_match {
+            self.last_end += 1;
+            return self.next()
+        }
+        self.last_end = e;
+        self.last_match = Some(self.last_end);
+        Captures::new(self.re, self.search, caps)
+    }
+}
+
+/// An iterator over all non-overlapping matches for a particular byte string.
+///
+/// The iterator yields a tuple of integers corresponding to the start and end
+/// of the match.
+///
+/// `'r` is the lifetime of the compiled expression and `'t` is the lifetime
+/// of the matched bytes.
+pub struct FindMatches<'r, 't> {
+    re: &'r Regex,
+    search: &'t [u8],
+    last_match: Option<uint>,
+    last_end: uint,
+}
+
+impl<'r, 't> Iterator<(uint, uint)> for FindMatches<'r, 't> {
+    fn next(&mut self) -> Option<(uint, uint)> {
+        if self.last_end > self.search.len() {
+            return None
+        }
+
+        let caps = exec_slice(self.re, Location, self.search,
+                              self.last_end, self.search.len());
+        let (s, e) =
+            if !has_match(&caps) {
+                return None
+            } else {
+                (caps[0].unwrap(), caps[1].unwrap())
+            };
+
+        // Don't accept empty matches immediately following a match.
+        if e == s && Some(self.last_end) == self.last_match {
+            self.last_end += 1;
+            return self.next()
+        }
+        self.last_end = e;
+        self.last_match = Some(self.last_end);
+        Some((s, e))
+    }
+}
+
+fn exec(re: &Regex, which: MatchKind, input: &[u8]) -> CaptureLocs {
+    exec_slice(re, which, input, 0, input.len())
+}
+
+fn exec_slice(re: &Regex, which: MatchKind,
+              input: &[u8], s: uint, e: uint) -> CaptureLocs {
+    match *re {
+        Dynamic(Dynamic { ref prog, .. }) => {
+            match dfa::run_bytes(which, prog, input, s, e) {
+                Some(caps) => caps,
+                None => vm::run_bytes(which, prog, input, s, e),
+            }
+        }
+        Native(Native { prog, .. }) => prog(which, input, s, e),
+    }
+}
+
+#[inline]
+fn has_match(caps: &CaptureLocs) -> bool {
+    caps.len() >= 2 && caps[0].is_some() && caps[1].is_some()
+}
+
+#[inline]
+fn is_name_byte(b: u8) -> bool {
+    match b {
+        b'0' .. b'9' | b'a' .. b'z' | b'A' .. b'Z' | b'_' => true,
+        _ => false,
+    }
+}
@@ -12,10 +12,14 @@
 // for use with the regex! macro. See lib.rs for explanation.
 #![allow(visible_private_types)]
 
+use std::char;
 use std::cmp;
+use std::cmp::{Less, Greater};
+use std::slice::{Found, NotFound};
+use unicode::regex::{UPPER_TO_LOWER, LOWER_TO_UPPER};
 use parse;
 use parse::{
-    Flags, FLAG_EMPTY,
+    Flags, FLAG_EMPTY, FLAG_NOCASE, FLAG_DOTNL, FLAG_NEGATED, FLAG_NOUNICODE,
     Nothing, Literal, Dot, Class, Begin, End, WordBoundary, Capture, Cat, Alt,
     Rep,
     ZeroOne, ZeroMore, OneMore,
@@ -28,8 +32,14 @@ pub enum Inst {
     // When a Match instruction is executed, the current thread is successful.
     Match,
 
+    // Like Match, but used in a program compiled from a set of regexes.
+    // It indicates which regex in the set was matched.
+    MatchSet(uint),
+
     // The OneChar instruction matches a literal character.
     // The flags indicate whether to do a case insensitive match.
+    // (In a program that searches bytes, every character is a byte in the
+    // range \x00-\xFF.)
     OneChar(char, Flags),
 
     // The CharClass instruction tries to match one input character against
@@ -55,7 +65,7 @@ pub enum Inst {
     // Matches a word boundary (\w on one side and \W \A or \z on the other),
     // and consumes no character.
     // The flags indicate whether this matches a word boundary or something
-    // that isn't a word boundary.
+    // that isn't a word boundary, and whether \w is Unicode aware.
     EmptyWordBoundary(Flags),
 
     // Saves the current position in the input string to the Nth save slot.
@@ -89,9 +99,24 @@ pub struct Program {
 impl Program {
     /// Compiles a Regex given its AST.
     pub fn new(ast: parse::Ast) -> (Program, Vec<Option<String>>) {
+        Program::compile(ast, false)
+    }
+
+    /// Compiles a Regex that searches bytes given its AST.
+    ///
+    /// The program reads its input one byte at a time. Parts of the
+    /// expression that have Unicode enabled are compiled to match the UTF-8
+    /// encoding of the characters they describe.
+    pub fn new_bytes(ast: parse::Ast) -> (Program, Vec<Option<String>>) {
+        Program::compile(ast, true)
+    }
+
+    fn compile(ast: parse::Ast, bytes: bool)
+              -> (Program, Vec<Option<String>>) {
         let mut c = Compiler {
             insts: Vec::with_capacity(100),
             names: Vec::with_capacity(10),
+            bytes: bytes,
         };
 
         c.insts.push(Save(0));
@@ -105,12 +130,15 @@ impl Program {
         let mut pre = String::with_capacity(5);
         for inst in c.insts.slice_from(1).iter() {
             match *inst {
-                OneChar(c, FLAG_EMPTY) => pre.push_char(c),
+                // The prefix is searched for in the raw input, so only
+                // ASCII bytes have the same encoding in both.
+                OneChar(c, FLAG_EMPTY) if !bytes || c <= '\x7F' =>
+                    pre.push_char(c),
                 _ => break
             }
         }
 
-        let Compiler { insts, names } = c;
+        let Compiler { insts, names, .. } = c;
         let prog = Program {
             insts: insts,
             prefix: pre,
@@ -118,6 +146,42 @@ impl Program {
         (prog, names)
     }
 
+    /// Compiles a set of regexes into a single program given their ASTs.
+    ///
+    /// The program tries every regex as an alternative, and each of them ends
+    /// with a `MatchSet` instruction carrying its index in `asts`. Capture
+    /// groups are compiled, but they have no meaning in a set.
+    ///
+    /// `asts` must not be empty.
+    pub fn new_set(asts: Vec<parse::Ast>) -> Program {
+        let mut c = Compiler {
+            insts: Vec::with_capacity(100),
+            names: Vec::with_capacity(10),
+            bytes: false,
+        };
+
+        c.insts.push(Save(0));
+        let n = asts.len();
+        for (i, ast) in asts.move_iter().enumerate() {
+            if i + 1 < n {
+                let split = c.empty_split();
+                let j1 = c.insts.len();
+                c.compile(ast);
+                c.push(MatchSet(i));
+                let j2 = c.insts.len();
+                c.set_split(split, j1, j2);
+            } else {
+                c.compile(ast);
+                c.push(MatchSet(i));
+            }
+        }
+
+        Program {
+            insts: c.insts,
+            prefix: String::new(),
+        }
+    }
+
     /// Returns the total number of capture groups in the regular expression.
     /// This includes the zeroth capture.
     pub fn num_captures(&self) -> uint {
@@ -136,6 +200,7 @@ impl Program {
 struct Compiler<'r> {
     insts: Vec<Inst>,
     names: Vec<Option<String>>,
+    bytes: bool,
 }
 
 // The compiler implemented here is extremely simple. Most of the complexity
@@ -146,10 +211,9 @@ impl<'r> Compiler<'r> {
     fn compile(&mut self, ast: parse::Ast) {
         match ast {
             Nothing => {},
-            Literal(c, flags) => self.push(OneChar(c, flags)),
-            Dot(nl) => self.push(Any(nl)),
-            Class(ranges, flags) =>
-                self.push(CharClass(ranges, flags)),
+            Literal(c, flags) => self.compile_literal(c, flags),
+            Dot(flags) => self.compile_dot(flags),
+            Class(ranges, flags) => self.compile_class(ranges, flags),
             Begin(flags) => self.push(EmptyBegin(flags)),
             End(flags) => self.push(EmptyEnd(flags)),
             WordBoundary(flags) => self.push(EmptyWordBoundary(flags)),
@@ -223,6 +287,102 @@ impl<'r> Compiler<'r> {
         }
     }
 
+    /// Compiles a literal character. Case insensitivity is resolved here
+    /// when Unicode is disabled (only ASCII letters have a case) or when the
+    /// program searches bytes.
+    fn compile_literal(&mut self, c: char, flags: Flags) {
+        let casei = flags & FLAG_NOCASE > 0;
+        if flags & FLAG_NOUNICODE > 0 {
+            if casei && is_ascii_letter(c) {
+                let ranges = ascii_fold(vec![(c, c)]);
+                self.push(CharClass(ranges, FLAG_EMPTY))
+            } else {
+                self.push(OneChar(c, FLAG_EMPTY))
+            }
+        } else if self.bytes {
+            let ranges = if casei { unicode_fold(vec![(c, c)]) }
+                         else { vec![(c, c)] };
+            self.compile_utf8(ranges)
+        } else {
+            self.push(OneChar(c, flags))
+        }
+    }
+
+    /// Compiles `.`, which never matches `\n` unless the `s` flag is set.
+    fn compile_dot(&mut self, flags: Flags) {
+        if self.bytes && flags & FLAG_NOUNICODE == 0 {
+            let ranges =
+                if flags & FLAG_DOTNL > 0 {
+                    vec![('\x00', char::MAX)]
+                } else {
+                    vec![('\x00', '\x09'), ('\x0B', char::MAX)]
+                };
+            self.compile_utf8(ranges)
+        } else {
+            self.push(Any(flags & FLAG_DOTNL))
+        }
+    }
+
+    /// Compiles a character class.
+    fn compile_class(&mut self, ranges: Vec<(char, char)>, flags: Flags) {
+        let casei = flags & FLAG_NOCASE > 0;
+        if flags & FLAG_NOUNICODE > 0 {
+            let ranges = if casei { ascii_fold(ranges) } else { ranges };
+            self.push(CharClass(ranges, flags & FLAG_NEGATED))
+        } else if self.bytes {
+            let ranges = if casei { unicode_fold(ranges) } else { ranges };
+            let ranges =
+                if flags & FLAG_NEGATED > 0 {
+                    negate_ranges(ranges)
+                } else {
+                    ranges
+                };
+            self.compile_utf8(ranges)
+        } else {
+            self.push(CharClass(ranges, flags))
+        }
+    }
+
+    /// Compiles an alternation of byte sequences that matches exactly the
+    /// UTF-8 encodings of the characters in `ranges`.
+    fn compile_utf8(&mut self, ranges: Vec<(char, char)>) {
+        let mut seqs = vec![];
+        for &(s, e) in ranges.iter() {
+            utf8_sequences(s as u32, e as u32, &mut seqs);
+        }
+        if seqs.is_empty() {
+            // An empty class never matches.
+            self.push(CharClass(vec![], FLAG_EMPTY));
+            return
+        }
+        let n = seqs.len();
+        let mut jumps = vec![];
+        for (i, seq) in seqs.move_iter().enumerate() {
+            let split = if i + 1 < n { Some(self.empty_split()) } else { None };
+            let j1 = self.insts.len();
+            for &(lo, hi) in seq.iter() {
+                let (lo, hi) = (lo as char, hi as char);
+                if lo == hi {
+                    self.push(OneChar(lo, FLAG_EMPTY))
+                } else {
+                    self.push(CharClass(vec![(lo, hi)], FLAG_EMPTY))
+                }
+            }
+            match split {
+                None => {}
+                Some(split) => {
+                    jumps.push(self.empty_jump());
+                    let j2 = self.insts.len();
+                    self.set_split(split, j1, j2);
+                }
+            }
+        }
+        let end = self.insts.len();
+        for &jmp in jumps.iter() {
+            self.set_jump(jmp, end);
+        }
+    }
+
     /// Appends the given instruction to the program.
     #[inline]
     fn push(&mut self, x: Inst) {
@@ -271,3 +431,140 @@ impl<'r> Compiler<'r> {
         }
     }
 }
+
+fn is_ascii_letter(c: char) -> bool {
+    (c >= 'a' && c <= 'z') || (c >= 'A' && c <= 'Z')
+}
+
+// Adds the other case of every ASCII letter in `ranges`.
+fn ascii_fold(mut ranges: Vec<(char, char)>) -> Vec<(char, char)> {
+    let mut folded = vec![];
+    for &(s, e) in ranges.iter() {
+        for &(lo, hi, other) in [('a', 'z', 'A'), ('A', 'Z', 'a')].iter() {
+            let (s, e) = (cmp::max(s, lo), cmp::min(e, hi));
+            if s <= e {
+                let shift = |c: char| {
+                    char::from_u32(c as u32 - lo as u32 + other as u32).unwrap()
+                };
+                folded.push((shift(s), shift(e)));
+            }
+        }
+    }
+    ranges.push_all_move(folded);
+    parse::combine_ranges(ranges)
+}
+
+// Adds the upper and lower case forms of every character in `ranges`.
+//
+// Rather than visiting every code point (a negated class can cover almost
+// all of Unicode), this looks up the slice of each case mapping table whose
+// keys fall in a range. Ranges without any cased characters cost only a
+// binary search.
+fn unicode_fold(mut ranges: Vec<(char, char)>) -> Vec<(char, char)> {
+    let mut folded = vec![];
+    for &(s, e) in ranges.iter() {
+        for table in [UPPER_TO_LOWER, LOWER_TO_UPPER].iter() {
+            let first = match table.binary_search(|&(key, _)| {
+                if key < s { Less } else { Greater }
+            }) {
+                Found(i) | NotFound(i) => i,
+            };
+            for &(key, other) in table.slice_from(first).iter() {
+                if key > e {
+                    break
+                }
+                folded.push((other, other));
+            }
+        }
+    }
+    ranges.push_all_move(folded);
+    parse::combine_ranges(ranges)
+}
+
+// Returns every Unicode scalar value that is not in `ranges`, which must be
+// sorted and non-overlapping.
+fn negate_ranges(ranges: Vec<(char, char)>) -> Vec<(char, char)> {
+    let mut negated = vec![];
+    let mut next = 0u32;
+    for &(s, e) in ranges.iter() {
+        if (s as u32) > next {
+            negated.push((next, s as u32 - 1));
+        }
+        next = e as u32 + 1;
+    }
+    if next <= char::MAX as u32 {
+        negated.push((next, char::MAX as u32));
+    }
+    // Surrogates are not characters, so the bounds of the ranges are moved
+    // around them.
+    negated.move_iter().filter_map(|(s, e)| {
+        let s = if s >= 0xD800 && s <= 0xDFFF { 0xE000 } else { s };
+        let e = if e >= 0xD800 && e <= 0xDFFF { 0xD7FF } else { e };
+        if s > e {
+            None
+        } else {
+            Some((char::from_u32(s).unwrap(), char::from_u32(e).unwrap()))
+        }
+    }).collect()
+}
+
+// Appends the byte ranges that match the UTF-8 encodings of the scalar values
+// from `start` to `end` (inclusive) to `seqs`. Each sequence is a list of
+// byte ranges, one for each byte of the encoding.
+//
+// The range is split until both of its ends encode to the same number of
+// bytes, and until each byte of the encodings of the ends can vary
+// independently of the others. Then the ranges of the individual bytes are
+// simply the bytes of the encodings of the two ends.
+fn utf8_sequences(start: u32, end: u32, seqs: &mut Vec<Vec<(u8, u8)>>) {
+    static MAX_BYTES: [u32, ..3] = [0x7F, 0x7FF, 0xFFFF];
+
+    let mut todo = vec![(start, end)];
+    'ranges: loop {
+        let (s, mut e) = match todo.pop() {
+            None => break,
+            Some(r) => r,
+        };
+        'split: loop {
+            if s < 0xE000 && e > 0xD7FF {
+                todo.push((0xE000, e));
+                e = 0xD7FF;
+                if s > e {
+                    continue 'ranges
+                }
+            }
+            for &max in MAX_BYTES.iter() {
+                if s <= max && max < e {
+                    todo.push((max + 1, e));
+                    e = max;
+                    continue 'split
+                }
+            }
+            if e <= 0x7F {
+                seqs.push(vec![(s as u8, e as u8)]);
+                continue 'ranges
+            }
+            for i in range(1u, 4) {
+                let m = (1u32 << (6 * i)) - 1;
+                if s & !m != e & !m {
+                    if s & m != 0 {
+                        todo.push(((s | m) + 1, e));
+                        e = s | m;
+                        continue 'split
+                    }
+                    if e & m != m {
+                        todo.push((e & !m, e));
+                        e = (e & !m) - 1;
+                        continue 'split
+                    }
+                }
+            }
+            let (mut bs, mut be) = (String::new(), String::new());
+            bs.push_char(char::from_u32(s).unwrap());
+            be.push_char(char::from_u32(e).unwrap());
+            seqs.push(bs.as_bytes().iter().zip(be.as_bytes().iter())
+                        .map(|(&a, &b)| (a, b)).collect());
+            continue 'ranges
+        }
+    }
+}
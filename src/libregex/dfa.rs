@@ -0,0 +1,650 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// A lazily constructed DFA that answers "does this match" and "where does it
+// match" without tracking capture groups.
+//
+// Each DFA state is the ordered list of instructions the Pike VM would have
+// in its thread queue at some position in the input, before following empty
+// transitions. Following empty transitions requires knowing both the
+// character before and after the current position (for `^`, `$` and `\b`),
+// so a state also remembers what kind of character was last consumed, and
+// each transition is computed from the pair (state, next character). The
+// result of every transition is cached, so text that keeps revisiting the
+// same states is scanned with one table lookup per character.
+//
+// Since states are *ordered* sets, the forward DFA implements exactly the same
+// leftmost-first semantics as the VM: when a `Match` instruction is reached,
+// all lower priority threads are dropped and no new threads are started. The
+// last position at which a match is seen is therefore the end of the match
+// the VM would report.
+//
+// The start of the match is found by running a second DFA backwards from the
+// end of the match over the reversed program. Its last match is the leftmost
+// position at which the regex matches up to that end, which is precisely the
+// leftmost-first start (no match can start any earlier, otherwise the forward
+// DFA would have preferred it).
+//
+// When searching bytes, every byte is treated as a character in the range
+// \x00-\xFF. A Unicode word boundary can't be decided from a single byte, so
+// the DFA gives up on byte programs that contain one.
+//
+// The cache only lives for a single search. If a regex produces too many
+// states too quickly (i.e., the cache keeps getting flushed without making
+// much progress through the input), the DFA gives up and the caller falls back
+// to the VM.
+//
+// See http://swtch.com/~rsc/regex/regex3.html for the inspiration.
+
+use std::collections::HashMap;
+use std::uint;
+use compile::{
+    Program, Inst,
+    Match, MatchSet, OneChar, CharClass, Any,
+    EmptyBegin, EmptyEnd, EmptyWordBoundary,
+    Save, Jump, Split,
+};
+use parse::{
+    Flags, FLAG_NOCASE, FLAG_MULTI, FLAG_DOTNL, FLAG_NEGATED, FLAG_NOUNICODE,
+};
+use vm;
+use vm::{CaptureLocs, MatchKind, Exists, Location, Submatches};
+
+/// The maximum number of states kept in the cache before it is flushed.
+static STATE_LIMIT: uint = 1000;
+
+/// If fewer than this many characters per state have been scanned when the
+/// cache fills up, the DFA gives up on the search.
+static MIN_CHARS_PER_STATE: uint = 10;
+
+/// The number of cached transitions per state: one for each ASCII character
+/// and one for the end (or beginning, when running backwards) of the input.
+/// Transitions on other characters are cached in a hash map.
+static NUM_TRANS: uint = 129;
+
+/// Marks a transition that hasn't been computed yet.
+static UNKNOWN: uint = uint::MAX;
+
+// Describes the character on one side of a position in the input.
+static LOOK_NONE: u8 = 1 << 0; // beginning or end of the input
+static LOOK_NL: u8   = 1 << 1; // '\n'
+static LOOK_WORD: u8 = 1 << 2; // \w
+static LOOK_ASCII_WORD: u8 = 1 << 3; // \w without Unicode
+
+type StatePtr = uint;
+
+#[deriving(Clone, PartialEq, Eq, Hash)]
+struct State {
+    /// Instructions to resume at, in priority order.
+    insts: Vec<uint>,
+    /// The kind of the last character consumed.
+    look: u8,
+    /// When false, a new thread is started at the beginning of the program
+    /// at every position (i.e., the implicit `.*?`). Once a match has been
+    /// found, no new threads are started.
+    anchored: bool,
+}
+
+/// Runs a lazy DFA on the compiled expression given on the search text
+/// `input`, between the byte indices `start` and `end`.
+///
+/// This behaves like `vm::run`, except that it can't report the locations of
+/// submatches. `None` is returned if the caller asked for submatches or if
+/// the DFA gave up, in which case the VM should be used instead.
+pub fn run(which: MatchKind, prog: &Program, input: &str,
+           start: uint, end: uint) -> Option<CaptureLocs> {
+    run_input(which, prog, input.as_bytes(), true, start, end)
+}
+
+/// Like `run`, but for a program compiled with `Program::new_bytes` that
+/// searches arbitrary bytes.
+pub fn run_bytes(which: MatchKind, prog: &Program, input: &[u8],
+                 start: uint, end: uint) -> Option<CaptureLocs> {
+    let unicode_boundary = prog.insts.iter().any(|inst| match *inst {
+        EmptyWordBoundary(flags) => flags & FLAG_NOUNICODE == 0,
+        _ => false,
+    });
+    if unicode_boundary {
+        return None
+    }
+    run_input(which, prog, input, false, start, end)
+}
+
+fn run_input(which: MatchKind, prog: &Program, input: &[u8], utf8: bool,
+             start: uint, end: uint) -> Option<CaptureLocs> {
+    match which {
+        Exists => {
+            let mut dfa = Dfa::new(prog, false, utf8);
+            match dfa.forward(input, start, end, true) {
+                None => None,
+                Some(Some(_)) => Some(vec![Some(0), Some(0)]),
+                Some(None) => Some(vec![None, None]),
+            }
+        }
+        Location => {
+            let mut dfa = Dfa::new(prog, false, utf8);
+            let e = match dfa.forward(input, start, end, false) {
+                None => return None,
+                Some(None) => return Some(vec![None, None]),
+                Some(Some(e)) => e,
+            };
+            let mut rdfa = Dfa::new(prog, true, utf8);
+            match rdfa.backward(input, start, e) {
+                Some(Some(s)) => Some(vec![Some(s), Some(e)]),
+                // The reverse DFA must find a start since the forward DFA
+                // found an end. If it gave up, let the VM have a go.
+                Some(None) | None => None,
+            }
+        }
+        Submatches => None,
+    }
+}
+
+/// Runs a lazy DFA on a program compiled from a set of `npats` regexes and
+/// returns which of the regexes match somewhere in `input`.
+///
+/// `None` is returned if the DFA gave up, in which case `vm::run_set` should be
+/// used instead.
+pub fn run_set(prog: &Program, npats: uint, input: &str,
+               start: uint, end: uint) -> Option<Vec<bool>> {
+    let input = input.as_bytes();
+    Dfa::new(prog, false, true).forward_set(input, start, end, npats)
+}
+
+struct Dfa<'r> {
+    prog: &'r Program,
+    /// Whether this DFA runs over the reversed program.
+    reverse: bool,
+    /// Whether the input is UTF-8 text or arbitrary bytes.
+    utf8: bool,
+    /// For the reverse DFA, the instructions that can reach each instruction
+    /// without consuming a character.
+    preds: Vec<Vec<uint>>,
+    states: Vec<State>,
+    cache: HashMap<State, StatePtr>,
+    /// `NUM_TRANS` entries for every state. A transition is encoded as the
+    /// next state shifted left by one, with the low bit set if the state
+    /// being left contained a match.
+    trans: Vec<uint>,
+    /// Transitions on non-ASCII characters.
+    unicode: HashMap<(StatePtr, char), uint>,
+    /// For a program compiled from a set of regexes, the regexes matched by
+    /// each transition that has its match bit set.
+    set_matches: HashMap<(StatePtr, Option<char>), Vec<uint>>,
+    /// The number of characters scanned since the cache was last flushed.
+    scanned: uint,
+    // Scratch space used when computing a transition.
+    visited: Vec<bool>,
+    closure: Vec<uint>,
+}
+
+impl<'r> Dfa<'r> {
+    fn new(prog: &'r Program, reverse: bool, utf8: bool) -> Dfa<'r> {
+        let ninsts = prog.insts.len();
+        let mut preds = Vec::new();
+        if reverse {
+            preds = Vec::from_elem(ninsts, Vec::new());
+            for (pc, inst) in prog.insts.iter().enumerate() {
+                match *inst {
+                    Jump(to) => preds.get_mut(to).push(pc),
+                    Split(x, y) => {
+                        preds.get_mut(x).push(pc);
+                        if x != y {
+                            preds.get_mut(y).push(pc);
+                        }
+                    }
+                    EmptyBegin(_) | EmptyEnd(_) | EmptyWordBoundary(_)
+                    | Save(_) => preds.get_mut(pc + 1).push(pc),
+                    Match | MatchSet(_)
+                    | OneChar(_, _) | CharClass(_, _) | Any(_) => {}
+                }
+            }
+        }
+        Dfa {
+            prog: prog,
+            reverse: reverse,
+            utf8: utf8,
+            preds: preds,
+            states: Vec::new(),
+            cache: HashMap::new(),
+            trans: Vec::new(),
+            unicode: HashMap::new(),
+            set_matches: HashMap::new(),
+            scanned: 0,
+            visited: Vec::from_elem(ninsts, false),
+            closure: Vec::with_capacity(ninsts),
+        }
+    }
+
+    /// Scans forward from `start` and returns the end of the leftmost-first
+    /// match, or only the end of *some* match if `early` is true.
+    fn forward(&mut self, input: &[u8], start: uint, end: uint, early: bool)
+              -> Option<Option<uint>> {
+        // See the comment in the VM on dropping the initial .*?
+        let prog = self.prog;
+        let prefix_anchor =
+            match prog.insts[1] {
+                EmptyBegin(flags) if flags & FLAG_MULTI == 0 => true,
+                _ => false,
+            };
+        let needle = prog.prefix.as_slice().as_bytes();
+
+        let look = self.look_before(input, start);
+        let mut si = self.add_state(State {
+            insts: vec![],
+            look: look,
+            anchored: false,
+        });
+        let mut last = None;
+        let mut i = start;
+        loop {
+            if self.states[si].insts.is_empty() {
+                // Once a match has been found and every thread has died,
+                // nothing else can match.
+                if self.states[si].anchored {
+                    break
+                }
+                // An anchored regex can only start at the beginning.
+                if prefix_anchor && i > 0 {
+                    break
+                }
+                // With no threads left, skip ahead to the next occurrence
+                // of the literal prefix (if there is one).
+                if needle.len() > 0 && i < end {
+                    match vm::find_prefix(needle, input.slice_from(i)) {
+                        None => break,
+                        Some(0) => {}
+                        Some(n) => {
+                            i += n;
+                            let look = self.look_before(input, i);
+                            si = self.add_state(State {
+                                insts: vec![],
+                                look: look,
+                                anchored: false,
+                            });
+                        }
+                    }
+                }
+            }
+
+            let (c, next) = self.char_at(input, i);
+            si = match self.check_cache(si) {
+                None => return None,
+                Some(si) => si,
+            };
+            let t = self.transition(si, c);
+            if t & 1 == 1 {
+                last = Some(i);
+                if early {
+                    break
+                }
+            }
+            if i >= end {
+                break
+            }
+            si = t >> 1;
+            i = next;
+        }
+        Some(last)
+    }
+
+    /// Scans forward from `start` like `forward`, but keeps going until every
+    /// regex in a set has matched or the end of the input is reached.
+    fn forward_set(&mut self, input: &[u8], start: uint, end: uint,
+                   npats: uint) -> Option<Vec<bool>> {
+        let mut matched = Vec::from_elem(npats, false);
+        let mut nmatched = 0;
+
+        let look = self.look_before(input, start);
+        let mut si = self.add_state(State {
+            insts: vec![],
+            look: look,
+            anchored: false,
+        });
+        let mut i = start;
+        loop {
+            let (c, next) = self.char_at(input, i);
+            si = match self.check_cache(si) {
+                None => return None,
+                Some(si) => si,
+            };
+            let t = self.transition(si, c);
+            if t & 1 == 1 {
+                match self.set_matches.find(&(si, c)) {
+                    None => {}
+                    Some(pats) => {
+                        for &k in pats.iter() {
+                            if !matched[k] {
+                                *matched.get_mut(k) = true;
+                                nmatched += 1;
+                            }
+                        }
+                    }
+                }
+                if nmatched == npats {
+                    break
+                }
+            }
+            if i >= end {
+                break
+            }
+            si = t >> 1;
+            i = next;
+        }
+        Some(matched)
+    }
+
+    /// Scans backward from `end` (which must be the end of a match) and
+    /// returns the leftmost position not before `start` at which that match
+    /// can begin.
+    fn backward(&mut self, input: &[u8], start: uint, end: uint)
+               -> Option<Option<uint>> {
+        let look = self.look_after(input, end);
+        let last_pc = self.prog.insts.len() - 1;
+        let mut si = self.add_state(State {
+            insts: vec![last_pc],
+            look: look,
+            anchored: true,
+        });
+        let mut last = None;
+        let mut i = end;
+        loop {
+            if self.states[si].insts.is_empty() {
+                break
+            }
+
+            let (c, next) = self.char_before(input, i);
+            si = match self.check_cache(si) {
+                None => return None,
+                Some(si) => si,
+            };
+            let t = self.transition(si, c);
+            if t & 1 == 1 {
+                last = Some(i);
+            }
+            if i <= start {
+                break
+            }
+            si = t >> 1;
+            i = next;
+        }
+        Some(last)
+    }
+
+    /// Returns the character at byte index `i` and the index of the
+    /// character following it.
+    #[inline]
+    fn char_at(&self, input: &[u8], i: uint) -> (Option<char>, uint) {
+        if i >= input.len() {
+            (None, i + 1)
+        } else if self.utf8 {
+            let cr = vm::as_str(input).char_range_at(i);
+            (Some(cr.ch), cr.next)
+        } else {
+            (Some(input[i] as char), i + 1)
+        }
+    }
+
+    /// Returns the character preceding byte index `i` and the index at which
+    /// it starts.
+    #[inline]
+    fn char_before(&self, input: &[u8], i: uint) -> (Option<char>, uint) {
+        if i == 0 {
+            (None, 0)
+        } else if self.utf8 {
+            let cr = vm::as_str(input).char_range_at_reverse(i);
+            (Some(cr.ch), cr.next)
+        } else {
+            (Some(input[i - 1] as char), i - 1)
+        }
+    }
+
+    /// Describes the character preceding the byte index `i`.
+    fn look_before(&self, input: &[u8], i: uint) -> u8 {
+        look(self.char_before(input, i).val0())
+    }
+
+    /// Describes the character at the byte index `i`.
+    fn look_after(&self, input: &[u8], i: uint) -> u8 {
+        look(self.char_at(input, i).val0())
+    }
+
+    /// Flushes the cache if it's full and returns the (possibly new) pointer
+    /// to the state given. Returns `None` if the cache is being flushed too
+    /// often to be worth it.
+    fn check_cache(&mut self, si: StatePtr) -> Option<StatePtr> {
+        self.scanned += 1;
+        if self.states.len() < STATE_LIMIT {
+            return Some(si)
+        }
+        if self.scanned < STATE_LIMIT * MIN_CHARS_PER_STATE {
+            return None
+        }
+        let state = self.states[si].clone();
+        self.states.clear();
+        self.cache.clear();
+        self.trans.clear();
+        self.unicode.clear();
+        self.set_matches.clear();
+        self.scanned = 0;
+        Some(self.add_state(state))
+    }
+
+    /// Returns the transition out of state `si` on the character `c`,
+    /// computing it if it isn't already cached.
+    #[inline]
+    fn transition(&mut self, si: StatePtr, c: Option<char>) -> uint {
+        let slot = match c {
+            None => Some(NUM_TRANS - 1),
+            Some(c) if (c as uint) < NUM_TRANS - 1 => Some(c as uint),
+            Some(_) => None,
+        };
+        match slot {
+            Some(slot) => {
+                let idx = si * NUM_TRANS + slot;
+                let t = self.trans[idx];
+                if t != UNKNOWN {
+                    return t
+                }
+                let t = self.compute(si, c);
+                *self.trans.get_mut(idx) = t;
+                t
+            }
+            None => {
+                let key = (si, c.unwrap());
+                match self.unicode.find(&key) {
+                    Some(&t) => return t,
+                    None => {}
+                }
+                let t = self.compute(si, c);
+                self.unicode.insert(key, t);
+                t
+            }
+        }
+    }
+
+    fn compute(&mut self, si: StatePtr, c: Option<char>) -> uint {
+        let state = self.states[si].clone();
+        let clook = look(c);
+        let (prev, cur) =
+            if self.reverse {
+                (clook, state.look)
+            } else {
+                (state.look, clook)
+            };
+
+        for &pc in state.insts.iter() {
+            self.add(pc, prev, cur);
+        }
+        if !state.anchored {
+            self.add(0, prev, cur);
+        }
+
+        let prog = self.prog;
+        let mut matched = false;
+        let mut insts = vec![];
+        let mut pats = vec![];
+        for &pc in self.closure.iter() {
+            if self.reverse {
+                if pc == 0 {
+                    matched = true;
+                }
+                match c {
+                    Some(c) if pc > 0 && matches_char(&prog.insts[pc - 1], c) =>
+                        insts.push(pc - 1),
+                    _ => {}
+                }
+            } else {
+                match prog.insts[pc] {
+                    Match => {
+                        // Leftmost-first: lower priority threads are dropped.
+                        matched = true;
+                        break
+                    }
+                    MatchSet(k) => {
+                        matched = true;
+                        pats.push(k);
+                    }
+                    ref inst => match c {
+                        Some(c) if matches_char(inst, c) => insts.push(pc + 1),
+                        _ => {}
+                    }
+                }
+            }
+        }
+        for &pc in self.closure.iter() {
+            *self.visited.get_mut(pc) = false;
+        }
+        self.closure.clear();
+
+        // Every regex in a set keeps being tried at every position, so a
+        // match in a set doesn't stop new threads from being started.
+        let anchored = state.anchored || (matched && pats.is_empty());
+        if !pats.is_empty() {
+            self.set_matches.insert((si, c), pats);
+        }
+        let next = self.add_state(State {
+            insts: insts,
+            look: clook,
+            anchored: anchored,
+        });
+        (next << 1) | (matched as uint)
+    }
+
+    /// Adds `pc` and everything reachable from it without consuming a
+    /// character to the closure being computed. `prev` and `cur` describe
+    /// the characters on either side of the current position.
+    fn add(&mut self, pc: uint, prev: u8, cur: u8) {
+        if self.visited[pc] {
+            return
+        }
+        *self.visited.get_mut(pc) = true;
+        self.closure.push(pc);
+
+        let prog = self.prog;
+        if self.reverse {
+            for i in range(0, self.preds[pc].len()) {
+                let p = self.preds[pc][i];
+                if empty_ok(&prog.insts[p], prev, cur) {
+                    self.add(p, prev, cur)
+                }
+            }
+            return
+        }
+        match prog.insts[pc] {
+            EmptyBegin(_) | EmptyEnd(_) | EmptyWordBoundary(_) => {
+                if empty_ok(&prog.insts[pc], prev, cur) {
+                    self.add(pc + 1, prev, cur)
+                }
+            }
+            Save(_) => self.add(pc + 1, prev, cur),
+            Jump(to) => self.add(to, prev, cur),
+            Split(x, y) => {
+                self.add(x, prev, cur);
+                self.add(y, prev, cur);
+            }
+            Match | MatchSet(_)
+            | OneChar(_, _) | CharClass(_, _) | Any(_) => {}
+        }
+    }
+
+    fn add_state(&mut self, state: State) -> StatePtr {
+        match self.cache.find(&state) {
+            Some(&si) => return si,
+            None => {}
+        }
+        let si = self.states.len();
+        self.states.push(state.clone());
+        self.cache.insert(state, si);
+        self.trans.grow(NUM_TRANS, &UNKNOWN);
+        si
+    }
+}
+
+/// Returns true if the empty-width assertion `inst` holds at a position
+/// between characters described by `prev` and `cur`. Other instructions
+/// always hold.
+fn empty_ok(inst: &Inst, prev: u8, cur: u8) -> bool {
+    let multi = |flags: Flags| flags & FLAG_MULTI > 0;
+    match *inst {
+        EmptyBegin(flags) => {
+            prev & LOOK_NONE > 0 || (multi(flags) && prev & LOOK_NL > 0)
+        }
+        EmptyEnd(flags) => {
+            cur & LOOK_NONE > 0 || (multi(flags) && cur & LOOK_NL > 0)
+        }
+        EmptyWordBoundary(flags) => {
+            let word =
+                if flags & FLAG_NOUNICODE > 0 {
+                    LOOK_ASCII_WORD
+                } else {
+                    LOOK_WORD
+                };
+            let boundary = (prev & word > 0) != (cur & word > 0);
+            boundary == !(flags & FLAG_NEGATED > 0)
+        }
+        _ => true,
+    }
+}
+
+/// Returns true if `inst` consumes the character `c`.
+fn matches_char(inst: &Inst, c: char) -> bool {
+    match *inst {
+        OneChar(regc, flags) => {
+            regc == c
+                || (flags & FLAG_NOCASE > 0
+                    && regc.to_uppercase() == c.to_uppercase())
+        }
+        CharClass(ref ranges, flags) => {
+            let negate = flags & FLAG_NEGATED > 0;
+            let casei = flags & FLAG_NOCASE > 0;
+            let found = ranges.as_slice();
+            let found = found.binary_search(|&rc| vm::class_cmp(casei, c, rc))
+                .found().is_some();
+            found ^ negate
+        }
+        Any(flags) => flags & FLAG_DOTNL > 0 || c != '\n',
+        _ => false,
+    }
+}
+
+#[inline]
+fn look(c: Option<char>) -> u8 {
+    let mut look = match c {
+        None => LOOK_NONE,
+        Some('\n') => LOOK_NL,
+        c if vm::is_word(c) => LOOK_WORD,
+        Some(_) => 0,
+    };
+    if vm::is_ascii_word(c) {
+        look |= LOOK_ASCII_WORD;
+    }
+    look
+}
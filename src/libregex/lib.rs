@@ -136,6 +136,34 @@
 //! provides more flexibility than is seen here. (See the documentation for
 //! `Regex::replace` for more details.)
 //!
+//! # Example: matching many expressions at once
+//!
+//! When text needs to be checked against a lot of expressions, compiling them
+//! into a `RegexSet` finds out which of them match in a single scan of the
+//! text:
+//!
+//! ```rust
+//! use regex::RegexSet;
+//! let set = RegexSet::new(&[r"^GET ", r"^POST ", r" 404 "]).unwrap();
+//! let matches = set.matches("GET /favicon.ico 404 0");
+//! assert!(matches.matched(0) && !matches.matched(1) && matches.matched(2));
+//! ```
+//!
+//! # Example: searching bytes
+//!
+//! Text that isn't valid UTF-8 can be searched with the byte-oriented `Regex`
+//! in the `bytes` module, which has the same API except that it works with
+//! `&[u8]`. With Unicode support disabled, it can match arbitrary bytes:
+//!
+//! ```rust
+//! use regex::bytes::Regex;
+//! let re = Regex::new(r"(?-u)\x7FELF(?P<class>[\x01\x02])").unwrap();
+//! let caps = re.captures(b"\x7FELF\x02\x01\x01\x00").unwrap();
+//! assert_eq!(caps.name("class"), b"\x02");
+//! ```
+//!
+//! The `regex_bytes!` macro is the counterpart of `regex!` for byte regexes.
+//!
 //! # Pay for what you use
 //!
 //! With respect to searching text with a regular expression, there are three
@@ -266,15 +294,22 @@
 //! the same time: `(?xy)` sets both the `x` and `y` flags and `(?x-y)` sets
 //! the `x` flag and clears the `y` flag.
 //!
-//! All flags are by default disabled. They are:
+//! All flags except `u` are by default disabled. They are:
 //!
 //! <pre class="rust">
 //! i     case insensitive
 //! m     multi-line mode: ^ and $ match begin/end of line
 //! s     allow . to match \n
 //! U     swap the meaning of x* and x*?
+//! u     Unicode support (enabled by default)
 //! </pre>
 //!
+//! Disabling Unicode support with `(?-u)` makes `\w`, `\d`, `\s`, `\b` and
+//! case insensitive matching only consider ASCII characters. (Unicode classes
+//! like `\pL` are unaffected.) In a regex that searches bytes (see the
+//! `bytes` module), it also makes `.`, character classes and escapes like
+//! `\xFF` match single bytes rather than UTF-8 encoded characters.
+//!
 //! Here's an example that matches case insensitively for only part of the
 //! expression:
 //!
@@ -389,10 +424,15 @@ pub use re::{Regex, Captures, SubCaptures, SubCapturesPos};
 pub use re::{FindCaptures, FindMatches};
 pub use re::{Replacer, NoExpand, RegexSplits, RegexSplitsN};
 pub use re::{quote, is_match};
+pub use set::{RegexSet, SetMatches, SetMatchesIter};
+
+pub mod bytes;
 
 mod compile;
+mod dfa;
 mod parse;
 mod re;
+mod set;
 mod vm;
 
 #[cfg(test)]
@@ -423,7 +463,7 @@ pub mod native {
     };
     pub use parse::{
         FLAG_EMPTY, FLAG_NOCASE, FLAG_MULTI, FLAG_DOTNL,
-        FLAG_SWAP_GREED, FLAG_NEGATED,
+        FLAG_SWAP_GREED, FLAG_NEGATED, FLAG_NOUNICODE,
     };
     pub use re::{Dynamic, Native};
     pub use vm::{
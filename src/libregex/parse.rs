@@ -168,6 +168,7 @@ pub static FLAG_MULTI:      u8 = 1 << 1; // m
 pub static FLAG_DOTNL:      u8 = 1 << 2; // s
 pub static FLAG_SWAP_GREED: u8 = 1 << 3; // U
 pub static FLAG_NEGATED:    u8 = 1 << 4; // char class or not word boundary
+pub static FLAG_NOUNICODE:  u8 = 1 << 5; // -u (Unicode is on by default)
 
 struct Parser<'a> {
     // The input, parsed only as a sequence of UTF8 code points.
@@ -184,6 +185,9 @@ struct Parser<'a> {
     caps: uint,
     // A set of all capture group names used only to detect duplicates.
     names: Vec<String>,
+    // Whether the expression searches arbitrary bytes rather than UTF-8
+    // text. When it does, Unicode can be disabled to match raw bytes.
+    bytes: bool,
 }
 
 pub fn parse(s: &str) -> Result<Ast, Error> {
@@ -194,6 +198,27 @@ pub fn parse(s: &str) -> Result<Ast, Error> {
         flags: FLAG_EMPTY,
         caps: 0,
         names: vec!(),
+        bytes: false,
+    }.parse()
+}
+
+/// Parses an expression that searches arbitrary bytes.
+///
+/// This differs from `parse` only when Unicode is disabled with `(?-u)`.
+/// In that case, literals, classes and escapes like `\xFF` produce ASTs
+/// that match single bytes, which are represented by the characters
+/// `\x00` to `\xFF` and flagged with `FLAG_NOUNICODE`. A non-ASCII
+/// character written literally is split into the bytes of its UTF-8
+/// encoding.
+pub fn parse_bytes(s: &str) -> Result<Ast, Error> {
+    Parser {
+        chars: s.chars().collect(),
+        chari: 0,
+        stack: vec!(),
+        flags: FLAG_EMPTY,
+        caps: 0,
+        names: vec!(),
+        bytes: true,
     }.parse()
 }
 
@@ -354,6 +379,15 @@ impl<'a> Parser<'a> {
             '$' => {
                 self.push(End(flags))
             }
+            c if self.bytes_mode() && c > '\x7F' => {
+                // Match the UTF-8 encoding of `c` one byte at a time.
+                let mut buf = String::new();
+                buf.push_char(c);
+                let lits = buf.as_bytes().iter()
+                              .map(|&b| Literal(b as char, flags))
+                              .collect();
+                self.push(Cat(lits))
+            }
             _ => {
                 self.push(Literal(c, flags))
             }
@@ -361,6 +395,12 @@ impl<'a> Parser<'a> {
         Ok(())
     }
 
+    // Returns true if the expression searches bytes and Unicode is
+    // currently disabled.
+    fn bytes_mode(&self) -> bool {
+        self.bytes && self.flags & FLAG_NOUNICODE > 0
+    }
+
     // Parses all forms of character classes.
     // Assumes that '[' is the current character.
     fn parse_class(&mut self) -> Result<(), Error> {
@@ -371,6 +411,7 @@ impl<'a> Parser<'a> {
             } else {
                 FLAG_EMPTY
             };
+        let unicode = self.flags & FLAG_NOUNICODE;
         let mut ranges: Vec<(char, char)> = vec!();
         let mut alts: Vec<Ast> = vec!();
 
@@ -411,12 +452,19 @@ impl<'a> Parser<'a> {
                         ast => fail!("Unexpected AST item '{}'", ast),
                     }
                 }
+                c if self.bytes_mode() && c > '\x7F' => {
+                    return self.err(format!(
+                        "Non-ASCII character '{}' in a class without Unicode \
+                         support. Use escapes like \\xFF to match bytes.",
+                        c).as_slice())
+                }
                 _ => {},
             }
             match c {
                 ']' => {
                     if ranges.len() > 0 {
-                        let flags = negated | (self.flags & FLAG_NOCASE);
+                        let flags =
+                            negated | unicode | (self.flags & FLAG_NOCASE);
                         let mut ast = Class(combine_ranges(ranges), flags);
                         for alt in alts.move_iter() {
                             ast = Alt(box alt, box ast)
@@ -435,14 +483,27 @@ impl<'a> Parser<'a> {
                     if self.peek_is(1, '-') && !self.peek_is(2, ']') {
                         try!(self.expect('-'))
                         try!(self.noteof("not a ']'"))
-                        let c2 = self.cur();
+                        let mut c2 = self.cur();
+                        if c2 == '\\' {
+                            match try!(self.parse_escape()) {
+                                Literal(c3, _) => c2 = c3,
+                                _ => return self.err(
+                                    "Expected a character at the end of a \
+                                     character class range."),
+                            }
+                        } else if self.bytes_mode() && c2 > '\x7F' {
+                            return self.err(format!(
+                                "Non-ASCII character '{}' in a class without \
+                                 Unicode support. Use escapes like \\xFF to \
+                                 match bytes.", c2).as_slice())
+                        }
                         if c2 < c {
                             return self.err(format!("Invalid character class \
                                                      range '{}-{}'",
                                                     c,
                                                     c2).as_slice())
                         }
-                        ranges.push((c, self.cur()))
+                        ranges.push((c, c2))
                     } else {
                         ranges.push((c, c))
                     }
@@ -484,7 +545,8 @@ impl<'a> Parser<'a> {
             None => None,
             Some(ranges) => {
                 self.chari = closer;
-                let flags = negated | (self.flags & FLAG_NOCASE);
+                let flags = negated
+                            | (self.flags & (FLAG_NOCASE | FLAG_NOUNICODE));
                 Some(Class(combine_ranges(ranges), flags))
             }
         }
@@ -590,26 +652,32 @@ impl<'a> Parser<'a> {
         try!(self.noteof("an escape sequence following a '\\'"))
 
         let c = self.cur();
+        let unicode = self.flags & FLAG_NOUNICODE;
         if is_punct(c) {
-            return Ok(Literal(c, FLAG_EMPTY))
+            return Ok(Literal(c, unicode))
         }
         match c {
-            'a' => Ok(Literal('\x07', FLAG_EMPTY)),
-            'f' => Ok(Literal('\x0C', FLAG_EMPTY)),
-            't' => Ok(Literal('\t', FLAG_EMPTY)),
-            'n' => Ok(Literal('\n', FLAG_EMPTY)),
-            'r' => Ok(Literal('\r', FLAG_EMPTY)),
-            'v' => Ok(Literal('\x0B', FLAG_EMPTY)),
+            'a' => Ok(Literal('\x07', unicode)),
+            'f' => Ok(Literal('\x0C', unicode)),
+            't' => Ok(Literal('\t', unicode)),
+            'n' => Ok(Literal('\n', unicode)),
+            'r' => Ok(Literal('\r', unicode)),
+            'v' => Ok(Literal('\x0B', unicode)),
             'A' => Ok(Begin(FLAG_EMPTY)),
             'z' => Ok(End(FLAG_EMPTY)),
-            'b' => Ok(WordBoundary(FLAG_EMPTY)),
-            'B' => Ok(WordBoundary(FLAG_NEGATED)),
+            'b' => Ok(WordBoundary(unicode)),
+            'B' => Ok(WordBoundary(FLAG_NEGATED | unicode)),
             '0'|'1'|'2'|'3'|'4'|'5'|'6'|'7' => Ok(try!(self.parse_octal())),
             'x' => Ok(try!(self.parse_hex())),
             'p' | 'P' => Ok(try!(self.parse_unicode_name())),
             'd' | 'D' | 's' | 'S' | 'w' | 'W' => {
-                let ranges = perl_unicode_class(c);
-                let mut flags = self.flags & FLAG_NOCASE;
+                let ranges =
+                    if unicode > 0 {
+                        perl_ascii_class(c)
+                    } else {
+                        perl_unicode_class(c)
+                    };
+                let mut flags = (self.flags & FLAG_NOCASE) | unicode;
                 if c.is_uppercase() { flags |= FLAG_NEGATED }
                 Ok(Class(ranges, flags))
             }
@@ -676,7 +744,7 @@ impl<'a> Parser<'a> {
         }
         let s = self.slice(start, end);
         match num::from_str_radix::<u32>(s.as_slice(), 8) {
-            Some(n) => Ok(Literal(try!(self.char_from_u32(n)), FLAG_EMPTY)),
+            Some(n) => self.escaped_literal(n),
             None => {
                 self.err(format!("Could not parse '{}' as octal number.",
                                  s).as_slice())
@@ -720,7 +788,7 @@ impl<'a> Parser<'a> {
     // Parses `s` as a hexadecimal number.
     fn parse_hex_digits(&self, s: &str) -> Result<Ast, Error> {
         match num::from_str_radix::<u32>(s, 16) {
-            Some(n) => Ok(Literal(try!(self.char_from_u32(n)), FLAG_EMPTY)),
+            Some(n) => self.escaped_literal(n),
             None => {
                 self.err(format!("Could not parse '{}' as hex number.",
                                  s).as_slice())
@@ -728,6 +796,18 @@ impl<'a> Parser<'a> {
         }
     }
 
+    // Returns a literal for a character given by its code, which is a byte
+    // when Unicode is disabled in an expression that searches bytes.
+    fn escaped_literal(&self, n: u32) -> Result<Ast, Error> {
+        if self.bytes_mode() && n > 0xFF {
+            return self.err(format!(
+                "Escape '\\x{{{:X}}}' is not a byte. (Unicode support is \
+                 disabled.)", n).as_slice())
+        }
+        let c = try!(self.char_from_u32(n));
+        Ok(Literal(c, self.flags & FLAG_NOUNICODE))
+    }
+
     // Parses a named capture.
     // Assumes that '(?P<' has been consumed and that the current character
     // is '<'.
@@ -772,11 +852,12 @@ impl<'a> Parser<'a> {
         let mut saw_flag = false;
         loop {
             try!(self.noteof("expected non-empty set of flags or closing ')'"))
-            match self.cur() {
-                'i' => { flags = flags | FLAG_NOCASE;     saw_flag = true},
-                'm' => { flags = flags | FLAG_MULTI;      saw_flag = true},
-                's' => { flags = flags | FLAG_DOTNL;      saw_flag = true},
-                'U' => { flags = flags | FLAG_SWAP_GREED; saw_flag = true},
+            let flag = match self.cur() {
+                'i' => FLAG_NOCASE,
+                'm' => FLAG_MULTI,
+                's' => FLAG_DOTNL,
+                'U' => FLAG_SWAP_GREED,
+                'u' => FLAG_NOUNICODE,
                 '-' => {
                     if sign < 0 {
                         return self.err(format!(
@@ -785,16 +866,13 @@ impl<'a> Parser<'a> {
                     }
                     sign = -1;
                     saw_flag = false;
-                    flags = flags ^ flags;
+                    continue
                 }
                 ':' | ')' => {
-                    if sign < 0 {
-                        if !saw_flag {
-                            return self.err(format!(
-                                "A valid flag does not follow negation in '{}'",
-                                self.slice(start, self.chari + 1)).as_slice())
-                        }
-                        flags = flags ^ flags;
+                    if sign < 0 && !saw_flag {
+                        return self.err(format!(
+                            "A valid flag does not follow negation in '{}'",
+                            self.slice(start, self.chari + 1)).as_slice())
                     }
                     if self.cur() == ':' {
                         // Save the old flags with the opening paren.
@@ -805,7 +883,14 @@ impl<'a> Parser<'a> {
                 }
                 _ => return self.err(format!(
                     "Unrecognized flag '{}'.", self.cur()).as_slice()),
+            };
+            // Unicode support is on by default, so its flag is inverted.
+            if (sign > 0) == (flag != FLAG_NOUNICODE) {
+                flags = flags | flag;
+            } else {
+                flags = flags & !flag;
             }
+            saw_flag = true;
         }
     }
 
@@ -951,7 +1036,7 @@ impl<'a> Parser<'a> {
 // Given an unordered collection of character ranges, combine_ranges returns
 // an ordered sequence of character ranges where no two ranges overlap. They
 // are ordered from least to greatest (using start position).
-fn combine_ranges(unordered: Vec<(char, char)>) -> Vec<(char, char)> {
+pub fn combine_ranges(unordered: Vec<(char, char)>) -> Vec<(char, char)> {
     // Returns true iff the two character classes overlap or share a boundary.
     // e.g., ('a', 'g') and ('h', 'm') would return true.
     fn should_merge((a, b): (char, char), (x, y): (char, char)) -> bool {
@@ -993,6 +1078,18 @@ fn perl_unicode_class(which: char) -> Vec<(char, char)> {
     }
 }
 
+// Constructs an ASCII-only Perl character class from \d, \s or \w (or any
+// of their negated forms), for use when Unicode support is disabled.
+fn perl_ascii_class(which: char) -> Vec<(char, char)> {
+    let name = match which.to_lowercase() {
+        'd' => "digit",
+        's' => "space",
+        'w' => "word",
+        _ => unreachable!(),
+    };
+    combine_ranges(find_class(ASCII_CLASSES, name).unwrap())
+}
+
 // Returns a concatenation of two expressions. This also guarantees that a
 // `Cat` expression will never be a direct child of another `Cat` expression.
 fn concat_flatten(x: Ast, y: Ast) -> Ast {
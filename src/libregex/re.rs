@@ -14,6 +14,7 @@ use std::from_str::from_str;
 use std::str::{MaybeOwned, Owned, Slice};
 
 use compile::Program;
+use dfa;
 use parse;
 use vm;
 use vm::{CaptureLocs, MatchKind, Exists, Location, Submatches};
@@ -915,7 +916,13 @@ fn exec(re: &Regex, which: MatchKind, input: &str) -> CaptureLocs {
 fn exec_slice(re: &Regex, which: MatchKind,
               input: &str, s: uint, e: uint) -> CaptureLocs {
     match *re {
-        Dynamic(Dynamic { ref prog, .. }) => vm::run(which, prog, input, s, e),
+        Dynamic(Dynamic { ref prog, .. }) => {
+            // The DFA is much faster, but it can't find submatches.
+            match dfa::run(which, prog, input, s, e) {
+                Some(caps) => caps,
+                None => vm::run(which, prog, input, s, e),
+            }
+        }
         Native(Native { prog, .. }) => prog(which, input, s, e),
     }
 }
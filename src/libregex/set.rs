@@ -0,0 +1,172 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::fmt;
+use std::slice;
+
+use compile::Program;
+use dfa;
+use parse;
+use vm;
+
+/// A set of regular expressions that are matched against text at the same
+/// time.
+///
+/// All of the expressions in a set are compiled into a single program, so
+/// finding out which of them match some text requires only a single scan of
+/// that text, no matter how many expressions are in the set. This is much
+/// faster than running each expression in turn.
+///
+/// A set can only report *which* expressions matched. To find the location
+/// of a match or its submatches, compile the matching expressions with
+/// `Regex::new` and use them as usual.
+///
+/// Each expression is matched independently (with an implicit `.*?` at the
+/// beginning and end, just like `Regex`), so the matches of different
+/// expressions may overlap.
+///
+/// # Example
+///
+/// ```rust
+/// # use regex::RegexSet;
+/// let set = RegexSet::new(&[r"\w+@\w+", r"\d+", r"^ERROR"]).unwrap();
+/// let matches = set.matches("ERROR: 3 mails bounced");
+/// let matched: Vec<uint> = matches.iter().collect();
+/// assert_eq!(matched, vec![1, 2]);
+/// ```
+#[deriving(Clone)]
+pub struct RegexSet {
+    originals: Vec<String>,
+    // `None` for an empty set.
+    prog: Option<Program>,
+}
+
+impl fmt::Show for RegexSet {
+    /// Shows the original regular expressions.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.originals)
+    }
+}
+
+impl RegexSet {
+    /// Compiles a set of regular expressions. Each expression uses the same
+    /// syntax (including flags) as `Regex::new`.
+    ///
+    /// If any of the expressions is invalid, then the error for the first
+    /// invalid one is returned.
+    pub fn new(res: &[&str]) -> Result<RegexSet, parse::Error> {
+        let mut asts = Vec::with_capacity(res.len());
+        for re in res.iter() {
+            asts.push(try!(parse::parse(*re)));
+        }
+        let prog =
+            if asts.is_empty() {
+                None
+            } else {
+                Some(Program::new_set(asts))
+            };
+        Ok(RegexSet {
+            originals: res.iter().map(|re| re.to_string()).collect(),
+            prog: prog,
+        })
+    }
+
+    /// Returns true if and only if any of the expressions in the set match
+    /// the text given.
+    pub fn is_match(&self, text: &str) -> bool {
+        self.exec(text).iter().any(|&m| m)
+    }
+
+    /// Returns which expressions in the set match the text given.
+    ///
+    /// The text is only scanned once. Scanning stops early when all of the
+    /// expressions have matched.
+    pub fn matches(&self, text: &str) -> SetMatches {
+        SetMatches { matched: self.exec(text) }
+    }
+
+    /// Returns the original expressions of this set, in the order they were
+    /// given to `new`.
+    pub fn patterns<'a>(&'a self) -> &'a [String] {
+        self.originals.as_slice()
+    }
+
+    fn exec(&self, text: &str) -> Vec<bool> {
+        let npats = self.originals.len();
+        let prog = match self.prog {
+            None => return vec![],
+            Some(ref prog) => prog,
+        };
+        match dfa::run_set(prog, npats, text, 0, text.len()) {
+            Some(matched) => matched,
+            None => vm::run_set(prog, npats, text, 0, text.len()),
+        }
+    }
+}
+
+impl Collection for RegexSet {
+    /// Returns the number of expressions in the set.
+    #[inline]
+    fn len(&self) -> uint {
+        self.originals.len()
+    }
+}
+
+/// The expressions of a `RegexSet` that matched some text.
+#[deriving(Clone, PartialEq, Show)]
+pub struct SetMatches {
+    matched: Vec<bool>,
+}
+
+impl SetMatches {
+    /// Returns true if and only if any expression in the set matched.
+    pub fn matched_any(&self) -> bool {
+        self.matched.iter().any(|&m| m)
+    }
+
+    /// Returns true if and only if the expression at index `i` in the set
+    /// matched. Returns false if `i` is not a valid index.
+    pub fn matched(&self, i: uint) -> bool {
+        i < self.matched.len() && self.matched[i]
+    }
+
+    /// Creates an iterator over the indices of the expressions that matched,
+    /// in increasing order.
+    pub fn iter<'a>(&'a self) -> SetMatchesIter<'a> {
+        SetMatchesIter { it: self.matched.iter().enumerate() }
+    }
+}
+
+impl Collection for SetMatches {
+    /// Returns the number of expressions in the set (whether they matched
+    /// or not).
+    #[inline]
+    fn len(&self) -> uint {
+        self.matched.len()
+    }
+}
+
+/// An iterator over the indices of the expressions in a set that matched.
+///
+/// `'a` is the lifetime of the `SetMatches` value.
+pub struct SetMatchesIter<'a> {
+    it: ::std::iter::Enumerate<slice::Items<'a, bool>>,
+}
+
+impl<'a> Iterator<uint> for SetMatchesIter<'a> {
+    fn next(&mut self) -> Option<uint> {
+        for (i, &m) in self.it.by_ref() {
+            if m {
+                return Some(i)
+            }
+        }
+        None
+    }
+}
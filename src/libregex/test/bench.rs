@@ -148,6 +148,30 @@ macro_rules! throughput(
     );
 )
 
+// `find` runs on the DFA for dynamic regexes while `captures` always runs
+// on the VM, so comparing the two shows the difference between the engines.
+macro_rules! throughput_find(
+    ($name:ident, $regex:expr, $size:expr) => (
+        #[bench]
+        fn $name(b: &mut Bencher) {
+            let text = gen_text($size);
+            b.bytes = $size;
+            b.iter(|| if $regex.find(text.as_slice()).is_some() { fail!("match") });
+        }
+    );
+)
+
+macro_rules! throughput_captures(
+    ($name:ident, $regex:expr, $size:expr) => (
+        #[bench]
+        fn $name(b: &mut Bencher) {
+            let text = gen_text($size);
+            b.bytes = $size;
+            b.iter(|| if $regex.captures(text.as_slice()).is_some() { fail!("match") });
+        }
+    );
+)
+
 fn easy0() -> Regex { regex!("ABCDEFGHIJKLMNOPQRSTUVWXYZ$") }
 fn easy1() -> Regex { regex!("A[AB]B[BC]C[CD]D[DE]E[EF]F[FG]G[GH]H[HI]I[IJ]J$") }
 fn medium() -> Regex { regex!("[XYZ]ABCDEFGHIJKLMNOPQRSTUVWXYZ$") }
@@ -180,3 +204,27 @@ throughput!(medium_32K,medium(), 32<<10)
 throughput!(hard_32, hard(), 32)
 throughput!(hard_1K, hard(), 1<<10)
 throughput!(hard_32K,hard(), 32<<10)
+
+throughput!(easy1_1MB, easy1(), 1<<20)
+throughput!(medium_1MB, medium(), 1<<20)
+throughput!(hard_1MB, hard(), 1<<20)
+
+throughput_find!(find_easy1_32K, easy1(), 32<<10)
+throughput_find!(find_medium_32K, medium(), 32<<10)
+throughput_find!(find_hard_32K, hard(), 32<<10)
+throughput_find!(find_hard_1MB, hard(), 1<<20)
+
+throughput_captures!(captures_easy1_32K, easy1(), 32<<10)
+throughput_captures!(captures_medium_32K, medium(), 32<<10)
+throughput_captures!(captures_hard_32K, hard(), 32<<10)
+
+#[bench]
+fn find_iter_log_lines(b: &mut Bencher) {
+    // A grep-style scan for timestamps in a log.
+    let re = regex!(r"\b\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}\b");
+    let line = "2014-08-21T12:34:56 INFO request served in 12ms\n\
+                GET /index.html HTTP/1.1 200 1043 \"Mozilla/5.0\"\n";
+    let text = line.repeat(500);
+    b.bytes = text.len() as u64;
+    b.iter(|| re.find_iter(text.as_slice()).count());
+}
@@ -0,0 +1,169 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use regex::bytes::{Regex, Captures, NoExpand};
+
+#[test]
+fn bytes_split() {
+    let re = regex_bytes!(r"(?-u)[\x00\xFF]+");
+    let subs: Vec<&[u8]> = re.split(b"ab\x00\xFFcd\xFFef").collect();
+    assert_eq!(subs, vec![b"ab", b"cd", b"ef"]);
+}
+
+#[test]
+fn bytes_splitn() {
+    let re = regex_bytes!(r"(?-u)\xFF");
+    let subs: Vec<&[u8]> = re.splitn(b"a\xFFb\xFFc", 2).collect();
+    assert_eq!(subs, vec![b"a", b"b\xFFc"]);
+}
+
+#[test]
+fn bytes_find_iter_invalid_utf8() {
+    let re = regex_bytes!(r"\d+");
+    let ms = re.find_iter(b"\xFF12\xC3 3\x80").collect::<Vec<(uint, uint)>>();
+    assert_eq!(ms, vec![(1, 3), (5, 6)]);
+}
+
+#[test]
+fn bytes_empty_matches() {
+    let re = regex_bytes!("");
+    let ms = re.find_iter(b"\xFF\xFF").collect::<Vec<(uint, uint)>>();
+    assert_eq!(ms, vec![(0, 0), (1, 1), (2, 2)]);
+}
+
+#[test]
+fn bytes_captures_named() {
+    let re = regex_bytes!(r"(?-u)(?P<key>\w+)=(?P<value>[^\x00]*)");
+    let caps = re.captures(b"\x00name=caf\xE9\x00").unwrap();
+    assert_eq!(caps.name("key"), b"name");
+    assert_eq!(caps.name("value"), b"caf\xE9");
+    assert_eq!(caps.at(0), b"name=caf\xE9");
+    assert_eq!(caps.pos(2), Some((6, 10)));
+    assert_eq!(caps.name("nope"), b"");
+}
+
+#[test]
+fn bytes_replace_closure() {
+    let re = regex_bytes!(r"(?-u)[\x80-\xFF]");
+    let result = re.replace_all(b"a\xE9b\xFF", |caps: &Captures| {
+        format!("\\x{:02X}", caps.at(0)[0]).into_bytes()
+    });
+    assert_eq!(result.as_slice(), b"a\\xE9b\\xFF");
+}
+
+macro_rules! replace(
+    ($name:ident, $which:ident, $re:expr,
+     $search:expr, $replace:expr, $result:expr) => (
+        #[test]
+        fn $name() {
+            let re = regex_bytes!($re);
+            assert_eq!(re.$which($search, $replace).as_slice(), $result);
+        }
+    );
+)
+
+replace!(rep_bytes_first, replace, r"(?-u)\xFF", b"a\xFF\xFF", b"-", b"a-\xFF")
+replace!(rep_bytes_all, replace_all, r"(?-u)\xFF", b"a\xFF\xFF", b"-", b"a--")
+// `\S` doesn't match bytes that aren't valid UTF-8.
+replace!(rep_bytes_groups, replace, r"(\S+)\s+(\S+)", b"\xFF1 w2", b"$2 $1",
+         b"\xFFw2 1")
+replace!(rep_bytes_named, replace_all, r"(?-u)(?P<a>.)(?P<b>.)",
+         b"\x01\x02\x03\x04", b"$b$a", b"\x02\x01\x04\x03")
+replace!(rep_bytes_double_dollar, replace, r"(\S+)", b"w1", b"$$1", b"$1")
+replace!(rep_bytes_lone_dollar, replace, r"(\S+)", b"w1", b"$ $1", b"$ w1")
+replace!(rep_bytes_no_expand, replace, r"(\S+)", b"w1",
+         NoExpand(b"$1"), b"$1")
+
+macro_rules! noparse(
+    ($name:ident, $re:expr) => (
+        #[test]
+        fn $name() {
+            let re = $re;
+            match Regex::new(re) {
+                Err(_) => {},
+                Ok(_) => fail!("Regex '{}' should cause a parse error.", re),
+            }
+        }
+    );
+)
+
+noparse!(fail_bytes_big_escape, r"(?-u)\x{100}")
+noparse!(fail_bytes_big_octal, r"(?-u)\777")
+noparse!(fail_bytes_unicode_in_class, r"(?-u)[é]")
+noparse!(fail_bytes_unicode_class_range, r"(?-u)[a-é]")
+
+macro_rules! mat(
+    ($name:ident, $re:expr, $text:expr, $($loc:tt)+) => (
+        #[test]
+        fn $name() {
+            let text: &[u8] = $text;
+            let expected: Vec<Option<(uint, uint)>> = vec!($($loc)+);
+            let r = regex_bytes!($re);
+            let got = match r.captures(text) {
+                Some(c) => c.iter_pos().collect::<Vec<Option<(uint, uint)>>>(),
+                None => vec!(None),
+            };
+            let (sexpect, mut sgot) = (expected.as_slice(), got.as_slice());
+            if sgot.len() > sexpect.len() {
+                sgot = sgot.slice(0, sexpect.len())
+            }
+            if sexpect != sgot {
+                fail!("For RE '{}' against '{}', expected '{}' but got '{}'",
+                      $re, text, sexpect, sgot);
+            }
+            let found = r.find(text);
+            if found != got[0] {
+                fail!("For RE '{}' against '{}', find gave '{}' but \
+                       captures gave '{}'", $re, text, found, got[0]);
+            }
+            if r.is_match(text) != found.is_some() {
+                fail!("For RE '{}' against '{}', is_match disagrees with \
+                       find", $re, text);
+            }
+        }
+    );
+)
+
+// Raw bytes can only be matched with Unicode disabled.
+mat!(bytes_raw_escape, r"(?-u)\xFF", b"a\xFF", Some((1, 2)))
+mat!(bytes_unicode_escape, r"\xFF", b"a\xFF", None)
+mat!(bytes_unicode_escape_utf8, r"\xFF", b"a\xC3\xBF", Some((1, 3)))
+mat!(bytes_raw_octal, r"(?-u)\377", b"\xFF", Some((0, 1)))
+mat!(bytes_raw_range, r"(?-u)[\x80-\xFF]+", b"a\x80\xC3\xFFb", Some((1, 4)))
+mat!(bytes_raw_negated, r"(?-u)[^a]+", b"a\xFF\x00a", Some((1, 3)))
+mat!(bytes_raw_dot, r"(?-u).+", b"\xFF\x00\n\xFE", Some((0, 2)))
+mat!(bytes_raw_dotnl, r"(?s-u).+", b"\xFF\n\xFE", Some((0, 3)))
+mat!(bytes_raw_literal_utf8, r"(?-u)é+", b"\xC3\xA9\xC3\xA9\xC3", Some((0, 4)))
+mat!(bytes_raw_mixed, r"(?-u)\xFF(?u:.)\xFF", b"\xFF\xC3\xA9\xFF", Some((0, 4)))
+
+// With Unicode enabled, the search text is decoded as UTF-8.
+mat!(bytes_dot, r".", b"\xC3\xA9", Some((0, 2)))
+mat!(bytes_dot_invalid, r".", b"\xFF\xC3", None)
+mat!(bytes_dot_four, r"^.$", b"\xF0\x9F\x92\xA9", Some((0, 4)))
+mat!(bytes_dot_no_surrogate, r".", b"\xED\xA0\x80", None)
+mat!(bytes_dot_nl, r".+", b"a\nb", Some((0, 1)))
+mat!(bytes_negated, r"[^a]", b"a\xFF\xE2\x98\x83", Some((2, 5)))
+mat!(bytes_class_range, r"[α-ω]+", b"\xCE\xB1\xCF\x89\xCE\x91", Some((0, 4)))
+mat!(bytes_unicode_class, r"\pN+", b"\xFF\xE2\x85\xA01", Some((1, 5)))
+mat!(bytes_perl_w, r"\w+", b"d\xCE\xB4d\xFF", Some((0, 4)))
+mat!(bytes_perl_w_ascii, r"(?-u)\w+", b"d\xCE\xB4d\xFF", Some((0, 1)))
+mat!(bytes_case, r"(?i)δ", b"\xCE\x94", Some((0, 2)))
+mat!(bytes_case_class, r"(?i)[δ-ζ]", b"\xCE\x95", Some((0, 2)))
+mat!(bytes_case_class_cyrillic, r"(?i)[а-я]+", b"\xD0\xAF\xD1\x8F", Some((0, 4)))
+mat!(bytes_case_negated, r"(?i)[^a]", b"A\xD0\xAF", Some((1, 3)))
+mat!(bytes_case_negated_all, r"(?i)[^\x00-\x{10FFFF}]", b"aA", None)
+mat!(bytes_case_ascii, r"(?i-u)a\xE9", b"A\xE9", Some((0, 2)))
+mat!(bytes_case_ascii_not, r"(?i-u)\xE9", b"\xC9", None)
+
+// Word boundaries decode the bytes around them when Unicode is enabled.
+mat!(bytes_boundary, r"\bd\b", b"\xCE\xB4d", None)
+mat!(bytes_boundary_invalid, r"\bd\b", b"\xFFd\xFF", Some((1, 2)))
+mat!(bytes_boundary_ascii, r"(?-u)\bd\b", b"\xCE\xB4d", Some((2, 3)))
+mat!(bytes_boundary_not, r"\Bd", b"\xCE\xB4d", Some((2, 3)))
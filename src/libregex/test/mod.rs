@@ -23,6 +23,10 @@ mod native_tests;
 #[cfg(not(stage1))]
 mod native_static;
 
+#[cfg(not(stage1))]
+#[path = "bytes.rs"]
+mod native_bytes;
+
 // Due to macro scoping rules, this definition only applies for the modules
 // defined below. Effectively, it allows us to use the same tests for both
 // native and dynamic regexes.
@@ -35,8 +39,20 @@ macro_rules! regex(
     );
 )
 
+macro_rules! regex_bytes(
+    ($re:expr) => (
+        match ::regex::bytes::Regex::new($re) {
+            Ok(re) => re,
+            Err(err) => fail!("{}", err),
+        }
+    );
+)
+
 #[path = "bench.rs"]
 mod dynamic_bench;
 #[path = "tests.rs"]
 mod dynamic_tests;
+#[path = "bytes.rs"]
+mod dynamic_bytes;
 
+mod set;
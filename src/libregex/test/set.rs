@@ -0,0 +1,79 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use regex::{Regex, RegexSet};
+
+macro_rules! set(
+    ($name:ident, $res:expr, $text:expr, $($matched:expr),*) => (
+        #[test]
+        fn $name() {
+            let res: &[&str] = $res;
+            let text = $text;
+            let expected: Vec<uint> = vec!($($matched),*);
+            let set = match RegexSet::new(res) {
+                Ok(set) => set,
+                Err(err) => fail!("{}", err),
+            };
+            let got = set.matches(text).iter().collect::<Vec<uint>>();
+            if expected != got {
+                fail!("For set {} against '{}', expected '{}' but got '{}'",
+                      res, text, expected, got);
+            }
+            // Each expression on its own must agree with the set.
+            for (i, re) in res.iter().enumerate() {
+                let matched = Regex::new(*re).unwrap().is_match(text);
+                assert_eq!(matched, got.contains(&i));
+            }
+            assert_eq!(set.is_match(text), !got.is_empty());
+        }
+    );
+)
+
+set!(set_empty, &[], "abc", )
+set!(set_one, &["a"], "abc", 0)
+set!(set_none, &["x", "y"], "abc", )
+set!(set_all, &["a", "b", "c"], "abc", 0, 1, 2)
+set!(set_some, &["a", "x", "c"], "abc", 0, 2)
+set!(set_overlap, &["ab", "bc", "abc"], "abc", 0, 1, 2)
+set!(set_same, &["a", "a"], "a", 0, 1)
+set!(set_empty_regex, &["", "z"], "abc", 0)
+set!(set_empty_text, &["^$", "a*", "a+"], "", 0, 1)
+set!(set_anchors, &["^b", "c$", "^a", "b$"], "abc", 1, 2)
+set!(set_multi, &[r"(?m)^b$", r"^b$"], "a\nb\nc", 0)
+set!(set_flags, &["(?i)ABC", "ABC", "(?s)a.b", "a.b"], "abc\na\nb", 0, 2)
+set!(set_boundary, &[r"\bbar\b", r"\Bbar", r"bar\B"], "foo bar", 0)
+set!(set_unicode, &[r"\p{Greek}+", r"\pN", r"(?i)δ"], "ΛΘΓΔ", 0, 2)
+set!(set_captures, &[r"(\d+)-(\d+)", r"(?P<word>\w+)!"], "12-34", 0)
+set!(set_late_match, &[r"a[bc]*z", r"a"], "abcbcbcbcbcbcbz", 0, 1)
+
+#[test]
+fn set_syntax_error() {
+    match RegexSet::new(&["a", "(", "b"]) {
+        Ok(_) => fail!("expected a parse error"),
+        Err(err) => {
+            let expected = Regex::new("(").unwrap_err();
+            assert_eq!(err.pos, expected.pos);
+            assert_eq!(err.msg, expected.msg);
+        }
+    }
+}
+
+#[test]
+fn set_len_and_patterns() {
+    let set = RegexSet::new(&["a", "b+"]).unwrap();
+    assert_eq!(set.len(), 2);
+    assert_eq!(set.patterns(), &["a".to_string(), "b+".to_string()]);
+    let matches = set.matches("bb");
+    assert_eq!(matches.len(), 2);
+    assert!(!matches.matched(0));
+    assert!(matches.matched(1));
+    assert!(!matches.matched(2));
+    assert!(matches.matched_any());
+}
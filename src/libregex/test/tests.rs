@@ -43,6 +43,35 @@ fn empty_regex_nonempty_match() {
     assert_eq!(ms, vec![(0, 0), (1, 1), (2, 2), (3, 3)]);
 }
 
+#[test]
+fn find_long_match() {
+    let re = regex!(r"\ba+b\b");
+    let text = format!("x {}b y", "a".repeat(10000));
+    assert_eq!(re.find(text.as_slice()), Some((2, 10003)));
+}
+
+#[test]
+fn find_iter_multi_line() {
+    let re = regex!(r"(?m)^\w+$");
+    let ms = re.find_iter("ab\ncd e\nfg").collect::<Vec<(uint, uint)>>();
+    assert_eq!(ms, vec![(0, 2), (8, 10)]);
+}
+
+#[test]
+fn find_many_states() {
+    // Needs a DFA state for every combination of the last 13 characters,
+    // which overflows the DFA's cache.
+    let re = regex!(r"[ab]*a[ab]{12}c");
+    let mut text = String::new();
+    for i in range(0u, 20000) {
+        text.push_char(if (i * 7919) % 13 < 6 { 'a' } else { 'b' });
+    }
+    text.push_str("abbbbbbbbbbbbc");
+    let caps = re.captures(text.as_slice()).unwrap();
+    assert_eq!(re.find(text.as_slice()), caps.pos(0));
+    assert!(re.is_match(text.as_slice()));
+}
+
 macro_rules! replace(
     ($name:ident, $which:ident, $re:expr,
      $search:expr, $replace:expr, $result:expr) => (
@@ -136,6 +165,17 @@ macro_rules! mat(
                 fail!("For RE '{}' against '{}', expected '{}' but got '{}'",
                       $re, text, sexpect, sgot);
             }
+            // `find` and `is_match` don't need submatches, so they may be
+            // run by a different engine than `captures`. They must agree.
+            let found = r.find(text);
+            if found != got[0] {
+                fail!("For RE '{}' against '{}', find gave '{}' but \
+                       captures gave '{}'", $re, text, found, got[0]);
+            }
+            if r.is_match(text) != found.is_some() {
+                fail!("For RE '{}' against '{}', is_match disagrees with \
+                       find", $re, text);
+            }
         }
     );
 )
@@ -198,6 +238,19 @@ mat!(uni_case_lower, r"\p{Ll}+", "ΛΘΓΔα", Some((8, 10)))
 mat!(uni_perl_w, r"\w+", "dδd", Some((0, 4)))
 mat!(uni_perl_w_not, r"\w+", "⥡", None)
 mat!(uni_perl_w_neg, r"\W+", "⥡", Some((0, 3)))
+
+// Without Unicode support, Perl classes, word boundaries and case
+// insensitivity only know about ASCII.
+mat!(ascii_perl_w, r"(?-u)\w+", "dδd", Some((0, 1)))
+mat!(ascii_perl_d, r"(?-u)\d+", "1٣2", Some((0, 1)))
+mat!(ascii_perl_s_neg, r"(?-u)\S+", "a\u3000b", Some((0, 5)))
+mat!(ascii_boundary, r"(?-u)\bd\b", "δdδ", Some((2, 3)))
+mat!(ascii_boundary_unicode, r"\bd\b", "δdδ", None)
+mat!(ascii_case, r"(?i-u)δ", "Δ", None)
+mat!(ascii_case_class, r"(?i-u)[a-c]+", "AbC", Some((0, 3)))
+mat!(ascii_unicode_class, r"(?-u)\pL+", "δd", Some((0, 3)))
+mat!(match_flag_unicode_toggle, r"(?-u)\w(?u)\w", "aδ", Some((0, 3)))
+mat!(match_flag_unicode_group, r"(?-u:\w)\w", "δa", None)
 mat!(uni_perl_d, r"\d+", "1२३9", Some((0, 8)))
 mat!(uni_perl_d_not, r"\d+", "Ⅱ", None)
 mat!(uni_perl_d_neg, r"\D+", "Ⅱ", Some((0, 3)))
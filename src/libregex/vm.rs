@@ -8,40 +8,33 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-// FIXME: Currently, the VM simulates an NFA. It would be nice to have another
-// VM that simulates a DFA.
+// The VM simulates an NFA. It is the only engine that can report the
+// locations of submatches, but it is slow since it doesn't reuse any of the
+// states it has previously computed. When submatches aren't needed, searches
+// on dynamic regexes are run by the lazy DFA in `dfa.rs` instead, which
+// falls back to this VM if it can't make progress.
 //
-// According to Russ Cox[1], a DFA performs better than an NFA, principally
-// because it reuses states previously computed by the machine *and* doesn't
-// keep track of capture groups. The drawback of a DFA (aside from its
-// complexity) is that it can't accurately return the locations of submatches.
-// The NFA *can* do that. (This is my understanding anyway.)
-//
-// Cox suggests that a DFA ought to be used to answer "does this match" and
-// "where does it match" questions. (In the latter, the starting position of
-// the match is computed by executing the regex backwards.) Cox also suggests
-// that a DFA should be run when asking "where are the submatches", which can
-// 1) quickly answer "no" is there's no match and 2) discover the substring
-// that matches, which means running the NFA on smaller input.
-//
-// Currently, the NFA simulation implemented below does some dirty tricks to
+// The NFA simulation implemented below also does some dirty tricks to
 // avoid tracking capture groups when they aren't needed (which only works
 // for 'is_match', not 'find'). This is a half-measure, but does provide some
-// perf improvement.
+// perf improvement for native regexes, which can't use the DFA.
 //
-// AFAIK, the DFA/NFA approach is implemented in RE2/C++ but *not* in RE2/Go.
+// See Russ Cox's articles[1] for the NFA/DFA approach, which is implemented
+// in RE2/C++.
 //
 // [1] - http://swtch.com/~rsc/regex/regex3.html
 
 use std::cmp;
 use std::mem;
 use std::slice::MutableSlice;
+use std::str;
 use compile::{
     Program,
-    Match, OneChar, CharClass, Any, EmptyBegin, EmptyEnd, EmptyWordBoundary,
+    Match, MatchSet, OneChar, CharClass, Any,
+    EmptyBegin, EmptyEnd, EmptyWordBoundary,
     Save, Jump, Split,
 };
-use parse::{FLAG_NOCASE, FLAG_MULTI, FLAG_DOTNL, FLAG_NEGATED};
+use parse::{FLAG_NOCASE, FLAG_MULTI, FLAG_DOTNL, FLAG_NEGATED, FLAG_NOUNICODE};
 use unicode::regex::PERLW;
 
 pub type CaptureLocs = Vec<Option<uint>>;
@@ -71,7 +64,7 @@ pub fn run<'r, 't>(which: MatchKind, prog: &'r Program, input: &'t str,
     Nfa {
         which: which,
         prog: prog,
-        input: input,
+        input: input.as_bytes(),
         start: start,
         end: end,
         ic: 0,
@@ -79,10 +72,44 @@ pub fn run<'r, 't>(which: MatchKind, prog: &'r Program, input: &'t str,
     }.run()
 }
 
+/// Like `run`, but for a program compiled with `Program::new_bytes` that
+/// searches arbitrary bytes. The input is read one byte at a time.
+pub fn run_bytes<'r, 't>(which: MatchKind, prog: &'r Program, input: &'t [u8],
+                         start: uint, end: uint) -> CaptureLocs {
+    Nfa {
+        which: which,
+        prog: prog,
+        input: input,
+        start: start,
+        end: end,
+        ic: 0,
+        chars: CharReader::new_bytes(input),
+    }.run()
+}
+
+/// Runs an NFA simulation of a program compiled from a set of `npats` regexes
+/// on the search text `input` and returns which of the regexes match.
+///
+/// Unlike `run`, this doesn't stop at the first match. Every regex in the set
+/// is tried at every position until all of them have matched or the end of
+/// the input is reached.
+pub fn run_set<'r, 't>(prog: &'r Program, npats: uint, input: &'t str,
+                       start: uint, end: uint) -> Vec<bool> {
+    Nfa {
+        which: Exists,
+        prog: prog,
+        input: input.as_bytes(),
+        start: start,
+        end: end,
+        ic: 0,
+        chars: CharReader::new(input),
+    }.run_set(npats)
+}
+
 struct Nfa<'r, 't> {
     which: MatchKind,
     prog: &'r Program,
-    input: &'t str,
+    input: &'t [u8],
     start: uint,
     end: uint,
     ic: uint,
@@ -145,7 +172,7 @@ impl<'r, 't> Nfa<'r, 't> {
                 // out early.
                 if self.prog.prefix.len() > 0 && clist.size == 0 {
                     let needle = self.prog.prefix.as_slice().as_bytes();
-                    let haystack = self.input.as_bytes().slice_from(self.ic);
+                    let haystack = self.input.slice_from(self.ic);
                     match find_prefix(needle, haystack) {
                         None => break,
                         Some(i) => {
@@ -189,6 +216,44 @@ impl<'r, 't> Nfa<'r, 't> {
         }
     }
 
+    fn run_set(&mut self, npats: uint) -> Vec<bool> {
+        let ninsts = self.prog.insts.len();
+        let mut clist = &mut Threads::new(Exists, ninsts, 0);
+        let mut nlist = &mut Threads::new(Exists, ninsts, 0);
+
+        let mut groups: Vec<Option<uint>> = vec![];
+        let mut matched = Vec::from_elem(npats, false);
+        let mut nmatched = 0;
+
+        self.ic = self.start;
+        let mut next_ic = self.chars.set(self.start);
+        while self.ic <= self.end && nmatched < npats {
+            self.add(clist, 0, groups.as_mut_slice());
+
+            self.ic = next_ic;
+            next_ic = self.chars.advance();
+
+            for i in range(0, clist.size) {
+                let pc = clist.pc(i);
+                match self.prog.insts[pc] {
+                    MatchSet(k) => {
+                        if !matched[k] {
+                            *matched.get_mut(k) = true;
+                            nmatched += 1;
+                        }
+                    }
+                    _ => {
+                        self.step(groups.as_mut_slice(), nlist,
+                                  clist.groups(i), pc);
+                    }
+                }
+            }
+            mem::swap(&mut clist, &mut nlist);
+            nlist.empty();
+        }
+        matched
+    }
+
     fn step(&self, groups: &mut [Option<uint>], nlist: &mut Threads,
             caps: &mut [Option<uint>], pc: uint)
            -> StepState {
@@ -235,7 +300,10 @@ impl<'r, 't> Nfa<'r, 't> {
                     self.add(nlist, pc+1, caps)
                 }
             }
-            EmptyBegin(_) | EmptyEnd(_) | EmptyWordBoundary(_)
+            // A set of regexes is run by `run_set`, which records matches
+            // itself.
+            MatchSet(_)
+            | EmptyBegin(_) | EmptyEnd(_) | EmptyWordBoundary(_)
             | Save(_) | Jump(_) | Split(_, _) => {},
         }
         StepContinue
@@ -278,7 +346,13 @@ impl<'r, 't> Nfa<'r, 't> {
             }
             EmptyWordBoundary(flags) => {
                 nlist.add(pc, groups, true);
-                if self.chars.is_word_boundary() == !(flags & FLAG_NEGATED > 0) {
+                let boundary =
+                    if flags & FLAG_NOUNICODE > 0 {
+                        self.chars.is_ascii_word_boundary()
+                    } else {
+                        self.chars.is_word_boundary()
+                    };
+                if boundary == !(flags & FLAG_NEGATED > 0) {
                     self.add(nlist, pc + 1, groups)
                 }
             }
@@ -309,7 +383,7 @@ impl<'r, 't> Nfa<'r, 't> {
                 self.add(nlist, x, groups);
                 self.add(nlist, y, groups);
             }
-            Match | OneChar(_, _) | CharClass(_, _) | Any(_) => {
+            Match | MatchSet(_) | OneChar(_, _) | CharClass(_, _) | Any(_) => {
                 nlist.add(pc, groups, false);
             }
         }
@@ -340,14 +414,20 @@ impl<'r, 't> Nfa<'r, 't> {
 /// CharReader is responsible for maintaining a "previous" and a "current"
 /// character. This one-character lookahead is necessary for assertions that
 /// look one character before or after the current position.
+///
+/// When reading arbitrary bytes rather than UTF-8 text, every byte is read as
+/// a character in the range `\x00-\xFF`.
 pub struct CharReader<'t> {
     /// The previous character read. It is None only when processing the first
     /// character of the input.
     pub prev: Option<char>,
     /// The current character.
     pub cur: Option<char>,
-    input: &'t str,
+    input: &'t [u8],
+    utf8: bool,
     next: uint,
+    /// The byte index of the current character.
+    pos: uint,
 }
 
 impl<'t> CharReader<'t> {
@@ -355,11 +435,26 @@ impl<'t> CharReader<'t> {
     /// Note that a CharReader has no knowledge of the range in which to search
     /// the input.
     pub fn new(input: &'t str) -> CharReader<'t> {
+        CharReader {
+            prev: None,
+            cur: None,
+            input: input.as_bytes(),
+            utf8: true,
+            next: 0,
+            pos: 0,
+       }
+    }
+
+    /// Returns a new CharReader that advances through the input given one
+    /// byte at a time.
+    pub fn new_bytes(input: &'t [u8]) -> CharReader<'t> {
         CharReader {
             prev: None,
             cur: None,
             input: input,
+            utf8: false,
             next: 0,
+            pos: 0,
        }
     }
 
@@ -370,19 +465,19 @@ impl<'t> CharReader<'t> {
         self.prev = None;
         self.cur = None;
         self.next = 0;
+        self.pos = cmp::min(ic, self.input.len());
 
         if self.input.len() == 0 {
             return 1
         }
         if ic > 0 {
             let i = cmp::min(ic, self.input.len());
-            let prev = self.input.char_range_at_reverse(i);
-            self.prev = Some(prev.ch);
+            self.prev = Some(self.char_before(i));
         }
         if ic < self.input.len() {
-            let cur = self.input.char_range_at(ic);
-            self.cur = Some(cur.ch);
-            self.next = cur.next;
+            let (c, next) = self.char_at(ic);
+            self.cur = Some(c);
+            self.next = next;
             self.next
         } else {
             self.input.len() + 1
@@ -394,10 +489,11 @@ impl<'t> CharReader<'t> {
     #[inline]
     pub fn advance(&mut self) -> uint {
         self.prev = self.cur;
+        self.pos = cmp::min(self.next, self.input.len());
         if self.next < self.input.len() {
-            let cur = self.input.char_range_at(self.next);
-            self.cur = Some(cur.ch);
-            self.next = cur.next;
+            let (c, next) = self.char_at(self.next);
+            self.cur = Some(c);
+            self.next = next;
         } else {
             self.cur = None;
             self.next = self.input.len() + 1;
@@ -405,6 +501,29 @@ impl<'t> CharReader<'t> {
         self.next
     }
 
+    /// Returns the character at byte index `i` (which must be in bounds) and
+    /// the index of the character following it.
+    #[inline]
+    fn char_at(&self, i: uint) -> (char, uint) {
+        if self.utf8 {
+            let cr = as_str(self.input).char_range_at(i);
+            (cr.ch, cr.next)
+        } else {
+            (self.input[i] as char, i + 1)
+        }
+    }
+
+    /// Returns the character preceding byte index `i` (which must not be
+    /// zero).
+    #[inline]
+    fn char_before(&self, i: uint) -> char {
+        if self.utf8 {
+            as_str(self.input).char_range_at_reverse(i).ch
+        } else {
+            self.input[i - 1] as char
+        }
+    }
+
     /// Returns true if and only if this is the beginning of the input
     /// (ignoring the range of the input to search).
     #[inline]
@@ -417,16 +536,64 @@ impl<'t> CharReader<'t> {
 
     /// Returns true if and only if the current position is a word boundary.
     /// (Ignoring the range of the input to search.)
+    ///
+    /// When reading bytes, the characters on either side of the position are
+    /// decoded as UTF-8. Bytes that aren't valid UTF-8 are not word
+    /// characters.
     pub fn is_word_boundary(&self) -> bool {
-        if self.is_begin() {
-            return is_word(self.cur)
-        }
-        if self.is_end() {
-            return is_word(self.prev)
+        let (prev, cur) =
+            if self.utf8 {
+                (self.prev, self.cur)
+            } else {
+                (decode_last_utf8(self.input, self.pos),
+                 decode_utf8(self.input, self.pos))
+            };
+        is_word(prev) != is_word(cur)
+    }
+
+    /// Returns true if and only if the current position is a boundary between
+    /// an ASCII word character and anything else.
+    pub fn is_ascii_word_boundary(&self) -> bool {
+        is_ascii_word(self.prev) != is_ascii_word(self.cur)
+    }
+}
+
+/// Returns the UTF-8 text `input` as a string without checking it.
+#[inline]
+pub fn as_str<'t>(input: &'t [u8]) -> &'t str {
+    // This is only used on input that came from a `&str`.
+    unsafe { str::raw::from_utf8(input) }
+}
+
+/// Decodes the character whose UTF-8 encoding starts at byte index `i` of
+/// `input`. Returns `None` if there is no valid encoding there.
+pub fn decode_utf8(input: &[u8], i: uint) -> Option<char> {
+    if i >= input.len() {
+        return None
+    }
+    let width = str::utf8_char_width(input[i]);
+    if width == 0 || i + width > input.len() {
+        return None
+    }
+    str::from_utf8(input.slice(i, i + width)).map(|s| s.char_at(0))
+}
+
+/// Decodes the character whose UTF-8 encoding ends just before byte index `i`
+/// of `input`. Returns `None` if there is no valid encoding there.
+pub fn decode_last_utf8(input: &[u8], i: uint) -> Option<char> {
+    let stop = if i >= 4 { i - 4 } else { 0 };
+    let mut start = i;
+    while start > stop {
+        start -= 1;
+        // Skip over continuation bytes.
+        if input[start] & 0xC0 != 0x80 {
+            return match decode_utf8(input, start) {
+                Some(c) if start + c.len_utf8_bytes() == i => Some(c),
+                _ => None,
+            }
         }
-        (is_word(self.cur) && !is_word(self.prev))
-        || (is_word(self.prev) && !is_word(self.cur))
     }
+    None
 }
 
 struct Thread {
@@ -525,6 +692,16 @@ pub fn is_word(c: Option<char>) -> bool {
     }
 }
 
+/// Returns true if the character is an ASCII word character, i.e., it's in
+/// the class `[0-9A-Za-z_]`.
+#[inline]
+pub fn is_ascii_word(c: Option<char>) -> bool {
+    match c {
+        Some('_') | Some('0' .. '9') | Some('a' .. 'z') | Some('A' .. 'Z') => true,
+        _ => false,
+    }
+}
+
 /// Given a character and a single character class range, return an ordering
 /// indicating whether the character is less than the start of the range,
 /// in the range (inclusive) or greater than the end of the range.
@@ -533,7 +710,7 @@ pub fn is_word(c: Option<char>) -> bool {
 ///
 /// This function is meant to be used with a binary search.
 #[inline]
-fn class_cmp(casei: bool, mut textc: char,
+pub fn class_cmp(casei: bool, mut textc: char,
              (mut start, mut end): (char, char)) -> Ordering {
     if casei {
         // FIXME: This is pretty ridiculous. All of this case conversion
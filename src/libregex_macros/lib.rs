@@ -8,8 +8,8 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-//! This crate provides the `regex!` macro. Its use is documented in the
-//! `regex` crate.
+//! This crate provides the `regex!` and `regex_bytes!` macros. Their use is
+//! documented in the `regex` crate.
 
 #![crate_name = "regex_macros"]
 #![crate_type = "dylib"]
@@ -43,14 +43,15 @@ use regex::native::{
     OneChar, CharClass, Any, Save, Jump, Split,
     Match, EmptyBegin, EmptyEnd, EmptyWordBoundary,
     Program, Dynamic, Native,
-    FLAG_NOCASE, FLAG_MULTI, FLAG_DOTNL, FLAG_NEGATED,
+    FLAG_NOCASE, FLAG_MULTI, FLAG_DOTNL, FLAG_NEGATED, FLAG_NOUNICODE,
 };
 
-/// For the `regex!` syntax extension. Do not use.
+/// For the `regex!` and `regex_bytes!` syntax extensions. Do not use.
 #[plugin_registrar]
 #[doc(hidden)]
 pub fn plugin_registrar(reg: &mut Registry) {
     reg.register_macro("regex", native);
+    reg.register_macro("regex_bytes", native_bytes);
 }
 
 /// Generates specialized code for the Pike VM for a particular regular
@@ -75,39 +76,71 @@ pub fn plugin_registrar(reg: &mut Registry) {
 /// It is strongly recommended to read the dynamic implementation in vm.rs
 /// first before trying to understand the code generator. The implementation
 /// strategy is identical and vm.rs has comments and will be easier to follow.
-#[allow(experimental)]
 fn native(cx: &mut ExtCtxt, sp: codemap::Span, tts: &[ast::TokenTree])
           -> Box<MacResult+'static> {
+    expand(cx, sp, tts, false)
+}
+
+/// Like `native`, but generates a regex that searches bytes
+/// (`regex::bytes::Regex`).
+fn native_bytes(cx: &mut ExtCtxt, sp: codemap::Span, tts: &[ast::TokenTree])
+                -> Box<MacResult+'static> {
+    expand(cx, sp, tts, true)
+}
+
+fn expand(cx: &mut ExtCtxt, sp: codemap::Span, tts: &[ast::TokenTree],
+          bytes: bool) -> Box<MacResult+'static> {
     let regex = match parse(cx, tts) {
         Some(r) => r,
         // error is logged in 'parse' with cx.span_err
         None => return DummyResult::any(sp),
     };
-    let re = match Regex::new(regex.as_slice()) {
-        Ok(re) => re,
+    let (prog, names) = match compile(regex.as_slice(), bytes) {
+        Ok(compiled) => compiled,
         Err(err) => {
             cx.span_err(sp, err.to_string().as_slice());
             return DummyResult::any(sp)
         }
     };
-    let prog = match re {
-        Dynamic(Dynamic { ref prog, .. }) => prog.clone(),
-        Native(_) => unreachable!(),
-    };
 
     let mut gen = NfaGen {
         cx: &*cx, sp: sp, prog: prog,
-        names: re.names_iter().collect(), original: re.as_str().to_string(),
+        names: names, original: regex, bytes: bytes,
     };
     MacExpr::new(gen.code())
 }
 
+/// Compiles `regex` dynamically and returns its program and the names of its
+/// capture groups.
+#[allow(experimental)]
+fn compile(regex: &str, bytes: bool)
+          -> Result<(Program, Vec<Option<String>>), regex::Error> {
+    if bytes {
+        let re = try!(regex::bytes::Regex::new(regex));
+        let names = re.names_iter().collect();
+        match re {
+            regex::bytes::Dynamic(regex::bytes::Dynamic { prog, .. }) =>
+                Ok((prog, names)),
+            regex::bytes::Native(_) => unreachable!(),
+        }
+    } else {
+        let re = try!(Regex::new(regex));
+        let names = re.names_iter().collect();
+        match re {
+            Dynamic(Dynamic { prog, .. }) => Ok((prog, names)),
+            Native(_) => unreachable!(),
+        }
+    }
+}
+
 struct NfaGen<'a> {
     cx: &'a ExtCtxt<'a>,
     sp: codemap::Span,
     prog: Program,
     names: Vec<Option<String>>,
     original: String,
+    // Whether the generated regex searches bytes rather than a string.
+    bytes: bool,
 }
 
 impl<'a> NfaGen<'a> {
@@ -141,6 +174,33 @@ impl<'a> NfaGen<'a> {
         let add_insts = self.add_insts();
         let regex = self.original.as_slice();
 
+        let (input_ty, new_reader) =
+            if self.bytes {
+                (quote_ty!(self.cx, &'t [u8]),
+                 quote_expr!(self.cx, CharReader::new_bytes(input)))
+            } else {
+                (quote_ty!(self.cx, &'t str),
+                 quote_expr!(self.cx, CharReader::new(input)))
+            };
+        let new_regex =
+            if self.bytes {
+                quote_expr!(self.cx,
+                    ::regex::bytes::Native(::regex::bytes::Native {
+                        original: $regex,
+                        names: CAP_NAMES,
+                        prog: exec,
+                    })
+                )
+            } else {
+                quote_expr!(self.cx,
+                    ::regex::native::Native(::regex::native::Native {
+                        original: $regex,
+                        names: CAP_NAMES,
+                        prog: exec,
+                    })
+                )
+            };
+
         quote_expr!(self.cx, {
 // When `regex!` is bound to a name that is not used, we have to make sure
 // that dead_code warnings don't bubble up to the user from the generated
@@ -151,7 +211,7 @@ impl<'a> NfaGen<'a> {
 static CAP_NAMES: &'static [Option<&'static str>] = &$cap_names;
 
 #[allow(dead_code)]
-fn exec<'t>(which: ::regex::native::MatchKind, input: &'t str,
+fn exec<'t>(which: ::regex::native::MatchKind, input: $input_ty,
             start: uint, end: uint) -> Vec<Option<uint>> {
     #![allow(unused_imports)]
     #![allow(unused_mut)]
@@ -166,14 +226,14 @@ fn exec<'t>(which: ::regex::native::MatchKind, input: &'t str,
         which: which,
         input: input,
         ic: 0,
-        chars: CharReader::new(input),
+        chars: $new_reader,
     }.run(start, end);
 
     type Captures = [Option<uint>, ..$num_cap_locs];
 
     struct Nfa<'t> {
         which: MatchKind,
-        input: &'t str,
+        input: $input_ty,
         ic: uint,
         chars: CharReader<'t>,
     }
@@ -322,11 +382,7 @@ fn exec<'t>(which: ::regex::native::MatchKind, input: &'t str,
     }
 }
 
-::regex::native::Native(::regex::native::Native {
-    original: $regex,
-    names: CAP_NAMES,
-    prog: exec,
-})
+$new_regex
         })
     }
 
@@ -367,11 +423,18 @@ fn exec<'t>(which: ::regex::native::MatchKind, input: &'t str,
                     })
                 }
                 EmptyWordBoundary(flags) => {
+                    let boundary =
+                        if flags & FLAG_NOUNICODE > 0 {
+                            quote_expr!(self.cx,
+                                        self.chars.is_ascii_word_boundary())
+                        } else {
+                            quote_expr!(self.cx, self.chars.is_word_boundary())
+                        };
                     let cond =
                         if flags & FLAG_NEGATED > 0 {
-                            quote_expr!(self.cx, !self.chars.is_word_boundary())
+                            quote_expr!(self.cx, !$boundary)
                         } else {
-                            quote_expr!(self.cx, self.chars.is_word_boundary())
+                            boundary
                         };
                     quote_expr!(self.cx, {
                         nlist.add_empty($pc);
@@ -550,9 +613,15 @@ fn exec<'t>(which: ::regex::native::MatchKind, input: &'t str,
         if self.prog.prefix.len() == 0 {
             self.empty_block()
         } else {
+            let input =
+                if self.bytes {
+                    quote_expr!(self.cx, self.input)
+                } else {
+                    quote_expr!(self.cx, self.input.as_bytes())
+                };
             quote_expr!(self.cx,
                 if clist.size == 0 {
-                    let haystack = self.input.as_bytes().slice_from(self.ic);
+                    let haystack = $input.slice_from(self.ic);
                     match find_prefix(prefix_bytes, haystack) {
                         None => break,
                         Some(i) => {
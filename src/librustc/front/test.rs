@@ -42,7 +42,8 @@ struct Test {
     path: Vec<ast::Ident> ,
     bench: bool,
     ignore: bool,
-    should_fail: bool
+    should_fail: bool,
+    timeout: Option<u64>,
 }
 
 struct TestCtxt<'a> {
@@ -125,7 +126,8 @@ impl<'a> fold::Folder for TestHarnessGenerator<'a> {
                         path: self.cx.path.clone(),
                         bench: is_bench_fn(&self.cx, i),
                         ignore: is_ignored(&self.cx, i),
-                        should_fail: should_fail(i)
+                        should_fail: should_fail(i),
+                        timeout: timeout(&self.cx, i),
                     };
                     self.cx.testfns.push(test);
                     self.tests.push(i.ident);
@@ -341,6 +343,26 @@ fn should_fail(i: Gc<ast::Item>) -> bool {
     attr::contains_name(i.attrs.as_slice(), "should_fail")
 }
 
+// #[timeout(5000)] gives the number of milliseconds a test may run for
+fn timeout(cx: &TestCtxt, i: Gc<ast::Item>) -> Option<u64> {
+    let attr = match i.attrs.iter().find(|attr| attr.check_name("timeout")) {
+        Some(attr) => attr,
+        None => return None,
+    };
+    let timeout = match attr.meta_item_list() {
+        Some(items) if items.len() == 1 => match items[0].node {
+            ast::MetaWord(ref ms) => from_str::<u64>(ms.get()),
+            _ => None,
+        },
+        _ => None,
+    };
+    if timeout.is_none() {
+        cx.sess.span_err(attr.span, "the timeout of a test must be a number of \
+                                     milliseconds, e.g. `#[timeout(5000)]`");
+    }
+    timeout
+}
+
 /*
 
 We're going to be building a module that looks more or less like:
@@ -532,13 +554,22 @@ fn mk_test_desc_and_fn_rec(cx: &TestCtxt, test: &Test) -> Gc<ast::Expr> {
     let ignore_expr = ecx.expr_bool(span, test.ignore);
     let fail_expr = ecx.expr_bool(span, test.should_fail);
 
+    let timeout_expr = match test.timeout {
+        Some(ms) => {
+            let lit = ast::LitInt(ms, ast::UnsignedIntLit(ast::TyU64));
+            ecx.expr_some(span, ecx.expr_lit(span, lit))
+        }
+        None => ecx.expr_none(span),
+    };
+
     // self::test::TestDesc { ... }
     let desc_expr = ecx.expr_struct(
         span,
         test_path("TestDesc"),
         vec![field("name", name_expr),
              field("ignore", ignore_expr),
-             field("should_fail", fail_expr)]);
+             field("should_fail", fail_expr),
+             field("timeout", timeout_expr)]);
 
 
     let mut visible_path = match cx.toplevel_reexport {
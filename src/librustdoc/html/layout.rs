@@ -110,6 +110,16 @@ r##"<!DOCTYPE html>
                 <code>trait</code>, <code>typedef</code> (or
                 <code>tdef</code>).
             </p>
+            <p>
+                Search functions by type signature (e.g.
+                <code>vec&lt;u8&gt; -&gt; string</code>). The types left of
+                the arrow must all be arguments, the type right of it the
+                return type.
+            </p>
+            <p>
+                Searches also look for all of their words in the
+                documentation of items (e.g. <code>parse integer</code>).
+            </p>
         </div>
     </div>
 
@@ -33,7 +33,7 @@
 //! These tasks are not parallelized (they haven't been a bottleneck yet), and
 //! both occur before the crate is rendered.
 
-use std::collections::{HashMap, HashSet};
+use std::collections::{HashMap, HashSet, TreeMap};
 use std::fmt;
 use std::io::{fs, File, BufferedWriter, MemWriter, BufferedReader};
 use std::io;
@@ -217,14 +217,46 @@ struct Source<'a>(&'a str);
 struct Item<'a> { cx: &'a Context, item: &'a clean::Item, }
 struct Sidebar<'a> { cx: &'a Context, item: &'a clean::Item, }
 
-/// Struct representing one entry in the JS search index. These are all emitted
-/// by hand to a large JS file at the end of cache-creation.
+/// Struct representing one entry in the search index. These are all emitted
+/// by hand to a large JS file at the end of cache-creation, or as JSON by
+/// `search_index`.
 struct IndexItem {
     ty: ItemType,
     name: String,
     path: String,
     desc: String,
     parent: Option<ast::DefId>,
+    search_type: Option<IndexItemFunctionType>,
+    words: Vec<String>,
+}
+
+/// The argument and return types of a function in the search index, as
+/// rendered by `index_type`. Functions returning `()` have no output.
+struct IndexItemFunctionType {
+    inputs: Vec<String>,
+    output: Option<String>,
+}
+
+impl IndexItem {
+    fn new(item: &clean::Item, path: String, parent: Option<ast::DefId>,
+           typarams: &HashMap<ast::DefId, String>) -> IndexItem {
+        IndexItem {
+            ty: shortty(item),
+            name: item.name.clone().unwrap(),
+            path: path,
+            desc: shorter(item.doc_value()).to_string(),
+            parent: parent,
+            search_type: search_type(item, typarams),
+            words: doc_words(item.doc_value()),
+        }
+    }
+}
+
+impl IndexItemFunctionType {
+    fn to_json(&self) -> json::Json {
+        let inputs = self.inputs.iter().map(|t| t.to_json()).collect();
+        json::List(vec![json::List(inputs), self.output.to_json()])
+    }
 }
 
 // TLS keys used to carry information around during rendering.
@@ -292,49 +324,7 @@ pub fn run(mut krate: clean::Crate, external_html: &ExternalHtml, dst: Path) ->
     }
 
     // Crawl the crate to build various caches used for the output
-    let analysis = ::analysiskey.get();
-    let public_items = analysis.as_ref().map(|a| a.public_items.clone());
-    let public_items = public_items.unwrap_or(NodeSet::new());
-    let paths: HashMap<ast::DefId, (Vec<String>, ItemType)> =
-      analysis.as_ref().map(|a| {
-        let paths = a.external_paths.borrow_mut().take().unwrap();
-        paths.move_iter().map(|(k, (v, t))| {
-            (k, (v, match t {
-                clean::TypeStruct => item_type::Struct,
-                clean::TypeEnum => item_type::Enum,
-                clean::TypeFunction => item_type::Function,
-                clean::TypeTrait => item_type::Trait,
-                clean::TypeModule => item_type::Module,
-                clean::TypeStatic => item_type::Static,
-                clean::TypeVariant => item_type::Variant,
-            }))
-        }).collect()
-    }).unwrap_or(HashMap::new());
-    let mut cache = Cache {
-        impls: HashMap::new(),
-        external_paths: paths.iter().map(|(&k, v)| (k, v.ref0().clone()))
-                             .collect(),
-        paths: paths,
-        implementors: HashMap::new(),
-        stack: Vec::new(),
-        parent_stack: Vec::new(),
-        search_index: Vec::new(),
-        extern_locations: HashMap::new(),
-        primitive_locations: HashMap::new(),
-        privmod: false,
-        public_items: public_items,
-        orphan_methods: Vec::new(),
-        traits: analysis.as_ref().map(|a| {
-            a.external_traits.borrow_mut().take().unwrap()
-        }).unwrap_or(HashMap::new()),
-        typarams: analysis.as_ref().map(|a| {
-            a.external_typarams.borrow_mut().take().unwrap()
-        }).unwrap_or(HashMap::new()),
-        inlined: analysis.as_ref().map(|a| {
-            a.inlined.borrow_mut().take().unwrap()
-        }).unwrap_or(HashSet::new()),
-    };
-    cache.stack.push(krate.name.clone());
+    let mut cache = Cache::new(&krate);
     krate = cache.fold_crate(krate);
 
     // Cache where all our extern crates are located
@@ -373,36 +363,126 @@ pub fn run(mut krate: clean::Crate, external_html: &ExternalHtml, dst: Path) ->
     cx.krate(krate, summary)
 }
 
+impl Cache {
+    /// Creates the cache for `krate`, seeded with what the analysis of the
+    /// crate knows about external items. It's filled in by folding the crate.
+    fn new(krate: &clean::Crate) -> Cache {
+        let analysis = ::analysiskey.get();
+        let public_items = analysis.as_ref().map(|a| a.public_items.clone());
+        let public_items = public_items.unwrap_or(NodeSet::new());
+        let paths: HashMap<ast::DefId, (Vec<String>, ItemType)> =
+          analysis.as_ref().map(|a| {
+            let paths = a.external_paths.borrow_mut().take().unwrap();
+            paths.move_iter().map(|(k, (v, t))| {
+                (k, (v, match t {
+                    clean::TypeStruct => item_type::Struct,
+                    clean::TypeEnum => item_type::Enum,
+                    clean::TypeFunction => item_type::Function,
+                    clean::TypeTrait => item_type::Trait,
+                    clean::TypeModule => item_type::Module,
+                    clean::TypeStatic => item_type::Static,
+                    clean::TypeVariant => item_type::Variant,
+                }))
+            }).collect()
+        }).unwrap_or(HashMap::new());
+        let mut cache = Cache {
+            impls: HashMap::new(),
+            external_paths: paths.iter().map(|(&k, v)| (k, v.ref0().clone()))
+                                 .collect(),
+            paths: paths,
+            implementors: HashMap::new(),
+            stack: Vec::new(),
+            parent_stack: Vec::new(),
+            search_index: Vec::new(),
+            extern_locations: HashMap::new(),
+            primitive_locations: HashMap::new(),
+            privmod: false,
+            public_items: public_items,
+            orphan_methods: Vec::new(),
+            traits: analysis.as_ref().map(|a| {
+                a.external_traits.borrow_mut().take().unwrap()
+            }).unwrap_or(HashMap::new()),
+            typarams: analysis.as_ref().map(|a| {
+                a.external_typarams.borrow_mut().take().unwrap()
+            }).unwrap_or(HashMap::new()),
+            inlined: analysis.as_ref().map(|a| {
+                a.inlined.borrow_mut().take().unwrap()
+            }).unwrap_or(HashSet::new()),
+        };
+        cache.stack.push(krate.name.clone());
+        cache
+    }
+}
+
+/// Builds the search index of `krate` for `rustdoc -w json`. It has the same
+/// entries as the index of the HTML search, but every entry is an object and
+/// names its parent directly.
+pub fn search_index(krate: clean::Crate) -> json::Json {
+    let mut cache = Cache::new(&krate);
+    cache.fold_crate(krate);
+    attach_orphan_methods(&mut cache);
+
+    json::List(cache.search_index.iter().map(|item| {
+        let mut obj = TreeMap::new();
+        obj.insert("type".to_string(), item.ty.to_static_str().to_string().to_json());
+        obj.insert("name".to_string(), item.name.to_json());
+        obj.insert("path".to_string(), item.path.to_json());
+        obj.insert("desc".to_string(), item.desc.to_json());
+        let parent = item.parent.and_then(|did| cache.paths.find(&did));
+        obj.insert("parent".to_string(), match parent {
+            Some(&(ref fqp, short)) => {
+                let mut parent = TreeMap::new();
+                parent.insert("type".to_string(),
+                              short.to_static_str().to_string().to_json());
+                parent.insert("name".to_string(), fqp.last().unwrap().to_json());
+                json::Object(parent)
+            }
+            None => json::Null,
+        });
+        obj.insert("signature".to_string(), match item.search_type {
+            Some(ref t) => {
+                let mut sig = TreeMap::new();
+                sig.insert("inputs".to_string(), t.inputs.to_json());
+                sig.insert("output".to_string(), t.output.to_json());
+                json::Object(sig)
+            }
+            None => json::Null,
+        });
+        obj.insert("words".to_string(), item.words.to_json());
+        json::Object(obj)
+    }).collect())
+}
+
+/// Attach all orphan methods to the type's definition if the type has since
+/// been learned.
+fn attach_orphan_methods(cache: &mut Cache) {
+    let Cache { ref mut search_index,
+                ref orphan_methods,
+                ref typarams,
+                ref mut paths, .. } = *cache;
+
+    for &(pid, ref item) in orphan_methods.iter() {
+        let did = ast_util::local_def(pid);
+        match paths.find(&did) {
+            Some(&(ref fqp, _)) => {
+                let path = fqp.slice_to(fqp.len() - 1).connect("::");
+                search_index.push(IndexItem::new(item, path, Some(did),
+                                                 typarams));
+            },
+            None => {}
+        }
+    };
+}
+
 fn build_index(krate: &clean::Crate, cache: &mut Cache) -> io::IoResult<String> {
     // Build the search index from the collected metadata
     let mut nodeid_to_pathid = HashMap::new();
     let mut pathid_to_nodeid = Vec::new();
+    attach_orphan_methods(cache);
     {
-        let Cache { ref mut search_index,
-                    ref orphan_methods,
-                    ref mut paths, .. } = *cache;
-
-        // Attach all orphan methods to the type's definition if the type
-        // has since been learned.
-        for &(pid, ref item) in orphan_methods.iter() {
-            let did = ast_util::local_def(pid);
-            match paths.find(&did) {
-                Some(&(ref fqp, _)) => {
-                    search_index.push(IndexItem {
-                        ty: shortty(item),
-                        name: item.name.clone().unwrap(),
-                        path: fqp.slice_to(fqp.len() - 1).connect("::"),
-                        desc: shorter(item.doc_value()).to_string(),
-                        parent: Some(did),
-                    });
-                },
-                None => {}
-            }
-        };
-
         // Reduce `NodeId` in paths into smaller sequential numbers,
         // and prune the paths that do not appear in the index.
-        for item in search_index.iter() {
+        for item in cache.search_index.iter() {
             match item.parent {
                 Some(nodeid) => {
                     if !nodeid_to_pathid.contains_key(&nodeid) {
@@ -417,6 +497,11 @@ fn build_index(krate: &clean::Crate, cache: &mut Cache) -> io::IoResult<String>
         assert_eq!(nodeid_to_pathid.len(), pathid_to_nodeid.len());
     }
 
+    // Every distinct documentation word is written once to the `words` table
+    // of the crate, and items refer to their words by index into it.
+    let mut word_ids = HashMap::new();
+    let mut words = Vec::new();
+
     // Collect the index into a string
     let mut w = MemWriter::new();
     try!(write!(&mut w, r#"searchIndex['{}'] = {{"items":["#, krate.name));
@@ -443,9 +528,29 @@ fn build_index(krate: &clean::Crate, cache: &mut Cache) -> io::IoResult<String>
                 let pathid = *nodeid_to_pathid.find(&nodeid).unwrap();
                 try!(write!(&mut w, ",{}", pathid));
             }
-            None => {}
+            None => try!(write!(&mut w, ",null")),
+        }
+        match item.search_type {
+            Some(ref t) => try!(write!(&mut w, ",{}", t.to_json().to_string())),
+            None => try!(write!(&mut w, ",null")),
+        }
+        try!(write!(&mut w, ",["));
+        for (j, word) in item.words.iter().enumerate() {
+            let id = match word_ids.find(word) {
+                Some(&id) => id,
+                None => {
+                    let id = words.len();
+                    word_ids.insert(word.clone(), id);
+                    words.push(word.clone());
+                    id
+                }
+            };
+            if j > 0 {
+                try!(write!(&mut w, ","));
+            }
+            try!(write!(&mut w, "{}", id));
         }
-        try!(write!(&mut w, "]"));
+        try!(write!(&mut w, "]]"));
     }
 
     try!(write!(&mut w, r#"],"paths":["#));
@@ -459,7 +564,7 @@ fn build_index(krate: &clean::Crate, cache: &mut Cache) -> io::IoResult<String>
                     short, *fqp.last().unwrap()));
     }
 
-    try!(write!(&mut w, "]}};"));
+    try!(write!(&mut w, r#"],"words":{}}};"#, words.to_json().to_string()));
 
     Ok(String::from_utf8(w.unwrap()).unwrap())
 }
@@ -857,13 +962,10 @@ impl DocFolder for Cache {
 
                 match parent {
                     (parent, Some(path)) if is_method || (!self.privmod && !hidden_field) => {
-                        self.search_index.push(IndexItem {
-                            ty: shortty(&item),
-                            name: s.to_string(),
-                            path: path.connect("::").to_string(),
-                            desc: shorter(item.doc_value()).to_string(),
-                            parent: parent,
-                        });
+                        let index_item = IndexItem::new(&item,
+                                                        path.connect("::").to_string(),
+                                                        parent, &self.typarams);
+                        self.search_index.push(index_item);
                     }
                     (Some(parent), None) if is_method || (!self.privmod && !hidden_field)=> {
                         if ast_util::is_local(parent) {
@@ -1421,6 +1523,94 @@ fn shorter<'a>(s: Option<&'a str>) -> &'a str {
     }
 }
 
+fn lowercase(s: &str) -> String {
+    s.chars().map(|c| c.to_lowercase()).collect()
+}
+
+/// The distinct words of the documentation of an item, lowercased, for the
+/// full-text search.
+fn doc_words(doc: Option<&str>) -> Vec<String> {
+    let mut seen = HashSet::new();
+    let mut words = Vec::new();
+    let doc = doc.unwrap_or("");
+    for word in doc.split(|c: char| !(c.is_alphanumeric() || c == '_')) {
+        if word.len() < 2 {
+            continue
+        }
+        let word = lowercase(word);
+        if seen.insert(word.clone()) {
+            words.push(word);
+        }
+    }
+    words
+}
+
+fn search_type(item: &clean::Item,
+               typarams: &HashMap<ast::DefId, String>) -> Option<IndexItemFunctionType> {
+    let decl = match item.inner {
+        clean::FunctionItem(ref f) | clean::ForeignFunctionItem(ref f) => &f.decl,
+        clean::MethodItem(ref m) => &m.decl,
+        clean::TyMethodItem(ref m) => &m.decl,
+        _ => return None
+    };
+    let inputs = decl.inputs.values.iter().filter_map(|arg| {
+        index_type(&arg.type_, typarams)
+    }).collect();
+    let output = match decl.output {
+        clean::Primitive(clean::Unit) => None,
+        ref ty => index_type(ty, typarams),
+    };
+    Some(IndexItemFunctionType { inputs: inputs, output: output })
+}
+
+/// Renders a type the way the search index and search queries spell it: the
+/// last segment of its path with its type parameters, lowercased, without
+/// any whitespace, and with references left out. For example
+/// `&'a collections::vec::Vec<Box<T>>` becomes `vec<box<t>>`.
+fn index_type(ty: &clean::Type,
+              typarams: &HashMap<ast::DefId, String>) -> Option<String> {
+    let args = |tys: &[clean::Type]| -> String {
+        let tys: Vec<String> = tys.iter().map(|t| {
+            index_type(t, typarams).unwrap_or("_".to_string())
+        }).collect();
+        tys.connect(",")
+    };
+    match *ty {
+        clean::ResolvedPath { ref path, .. } => {
+            path.segments.last().map(|seg| {
+                let mut name = lowercase(seg.name.as_slice());
+                if seg.types.len() > 0 {
+                    name.push_str(format!("<{}>", args(seg.types.as_slice()))
+                                      .as_slice());
+                }
+                name
+            })
+        }
+        clean::Generic(did) => typarams.find(&did).map(|s| lowercase(s.as_slice())),
+        clean::Self(..) => Some("self".to_string()),
+        clean::Primitive(clean::Unit) => Some("()".to_string()),
+        clean::Primitive(clean::Slice) => Some("[]".to_string()),
+        clean::Primitive(clean::PrimitiveTuple) => Some("()".to_string()),
+        clean::Primitive(p) => Some(p.to_string().to_string()),
+        clean::Closure(..) | clean::Proc(..) |
+        clean::BareFunction(..) => Some("fn".to_string()),
+        clean::Tuple(ref tys) => Some(format!("({})", args(tys.as_slice()))),
+        clean::Vector(ref t) | clean::FixedVector(ref t, _) => {
+            index_type(&**t, typarams).map(|t| format!("[{}]", t))
+        }
+        clean::Bottom => Some("!".to_string()),
+        clean::Unique(ref t) => {
+            index_type(&**t, typarams).map(|t| format!("box<{}>", t))
+        }
+        clean::RawPointer(_, ref t) => {
+            index_type(&**t, typarams).map(|t| format!("*{}", t))
+        }
+        clean::Managed(ref t) |
+        clean::BorrowedRef { type_: ref t, .. } => index_type(&**t, typarams),
+        clean::TyParamBinder(..) => None,
+    }
+}
+
 fn document(w: &mut fmt::Formatter, item: &clean::Item) -> fmt::Result {
     match item.doc_value() {
         Some(s) => {
@@ -190,7 +190,9 @@
 
             // quoted values mean literal search
             var nSearchWords = searchWords.length;
-            if ((val.charAt(0) === "\"" || val.charAt(0) === "'") &&
+            if (val.indexOf("->") > -1) {
+                results = execTypeQuery(val, max, typeFilter);
+            } else if ((val.charAt(0) === "\"" || val.charAt(0) === "'") &&
                 val.charAt(val.length - 1) === val.charAt(0))
             {
                 val = val.substr(1, val.length - 2);
@@ -240,6 +242,38 @@
                         }
                     }
                 }
+
+                // then look for all of the words of the query in the
+                // documentation of the items
+                var textWords = valLower.split(/\s+/).filter(function(word) {
+                    return word !== "";
+                });
+                // skip items whose name already matched
+                var seen = {};
+                results.forEach(function(result) {
+                    seen[result.id] = true;
+                });
+                for (var j = 0; j < nSearchWords && results.length < max; ++j) {
+                    var words = searchIndex[j].words;
+                    if (seen[j] || words.length === 0 ||
+                        (typeFilter >= 0 && typeFilter !== searchIndex[j].ty)) {
+                        continue;
+                    }
+                    var found = textWords.every(function(word) {
+                        return words.some(function(docWord) {
+                            return docWord.indexOf(word) === 0;
+                        });
+                    });
+                    if (found) {
+                        results.push({
+                            id: j,
+                            index: -1,
+                            // documentation matches go after name matches
+                            lev: MAX_LEV_DISTANCE + 1,
+                            text: true,
+                        });
+                    }
+                }
             }
 
             var nresults = results.length;
@@ -324,7 +358,10 @@
                     path = result.item.path.toLowerCase(),
                     parent = result.item.parent;
 
-                var valid = validateResult(name, path, split, parent);
+                // matches on the documentation or the signature don't need
+                // to match the name
+                var valid = result.text || result.signature ||
+                            validateResult(name, path, split, parent);
                 if (!valid) {
                     result.id = -1;
                 }
@@ -332,6 +369,113 @@
             return results;
         }
 
+        /**
+         * Normalizes a type in a query the same way as `index_type` in
+         * `html/render.rs` renders the types in the search index: lowercase,
+         * without whitespace and without references.
+         * @param  {[string]} type [The type as written in the query]
+         * @return {[string]}      [The normalized type]
+         */
+        function normalizeType(type) {
+            return type.toLowerCase()
+                       .replace(/&\s*('\w+\s+)?(mut\s+)?/g, "")
+                       .replace(/\s+/g, "");
+        }
+
+        /**
+         * Splits a list of types on the commas that aren't nested within
+         * type parameters, tuples or vectors.
+         * @param  {[string]} list [The types, separated by commas]
+         * @return {[[string]]}    [The types in the list]
+         */
+        function splitTypes(list) {
+            var types = [], depth = 0, start = 0;
+            for (var i = 0; i < list.length; ++i) {
+                var c = list.charAt(i);
+                if (c === "<" || c === "(" || c === "[") {
+                    depth += 1;
+                } else if (c === ">" || c === ")" || c === "]") {
+                    depth -= 1;
+                } else if (c === "," && depth === 0) {
+                    types.push(list.substring(start, i));
+                    start = i + 1;
+                }
+            }
+            types.push(list.substring(start));
+            return types.filter(function(type) { return type !== ""; });
+        }
+
+        /**
+         * Checks whether a type of the query matches a type in the index. A
+         * type without parameters matches the type with any parameters, so
+         * "vec" matches "vec<u8>", but "vec<u8>" doesn't match "vec<char>".
+         */
+        function typeMatches(query, type) {
+            return query === type ||
+                (query.indexOf("<") < 0 && type.split("<")[0] === query);
+        }
+
+        /**
+         * Checks whether the return type of the query matches the return
+         * type of a function. The query may also name one of the parameters
+         * of the returned type, so that "string" finds functions returning
+         * "option<string>" or "result<string,e>".
+         */
+        function outputMatches(query, type) {
+            if (typeMatches(query, type)) {
+                return true;
+            }
+            var start = type.indexOf("<");
+            if (start < 0) {
+                return false;
+            }
+            var params = splitTypes(type.substring(start + 1, type.length - 1));
+            return params.some(function(param) {
+                return typeMatches(query, param);
+            });
+        }
+
+        /**
+         * Searches functions by their signature, for queries like
+         * "Vec<u8> -> String". Every type left of the arrow has to match one
+         * of the arguments of a function, and the type right of it (if any)
+         * its return type. Functions with fewer other arguments go first.
+         * @param  {[string]} val        [The query]
+         * @param  {[type]} max          [The maximum results returned]
+         * @param  {[type]} typeFilter   [The item type to restrict to]
+         * @return {[type]}              [The results, as in execQuery]
+         */
+        function execTypeQuery(val, max, typeFilter) {
+            var arrow = val.indexOf("->"),
+                inputs = splitTypes(normalizeType(val.substring(0, arrow))),
+                output = normalizeType(val.substring(arrow + 2)),
+                results = [];
+
+            for (var i = 0; i < searchIndex.length && results.length < max; ++i) {
+                var item = searchIndex[i], type = item.type;
+                if (!type || (typeFilter >= 0 && typeFilter !== item.ty)) {
+                    continue;
+                }
+                if (output !== "" && (!type[1] || !outputMatches(output, type[1]))) {
+                    continue;
+                }
+                var matched = inputs.every(function(input) {
+                    return type[0].some(function(arg) {
+                        return typeMatches(input, arg);
+                    });
+                });
+                if (matched) {
+                    results.push({
+                        id: i,
+                        index: -1,
+                        lev: Math.max(0, type[0].length - inputs.length),
+                        signature: true,
+                    });
+                }
+            }
+            return results;
+        }
+
         /**
          * Validate performs the following boolean logic. For example:
          * "File::open" will give IF A PARENT EXISTS => ("file" && "open")
@@ -588,11 +732,18 @@
                 //              (String) name,
                 //              (String) full path or empty string for previous path,
                 //              (String) description,
-                //              (optional Number) the parent path index to `paths`]
+                //              (Number or null) the parent path index to `paths`,
+                //              ([[String], String or null] or null) the types
+                //                  of the arguments and the return type of
+                //                  functions,
+                //              ([Number]) the words of the documentation,
+                //                  as indices to `words`]
                 var items = rawSearchIndex[crate].items;
                 // an array of [(Number) item type,
                 //              (String) name]
                 var paths = rawSearchIndex[crate].paths;
+                // an array of the distinct words of the documentation
+                var docWords = rawSearchIndex[crate].words;
 
                 // convert `paths` into an object form
                 var len = paths.length;
@@ -613,7 +764,10 @@
                     var rawRow = items[i];
                     var row = {crate: crate, ty: rawRow[0], name: rawRow[1],
                                path: rawRow[2] || lastPath, desc: rawRow[3],
-                               parent: paths[rawRow[4]]};
+                               parent: paths[rawRow[4]], type: rawRow[5],
+                               words: rawRow[6].map(function(id) {
+                                   return docWords[id];
+                               })};
                     searchIndex.push(row);
                     if (typeof row.name === "string") {
                         var word = row.name.toLowerCase();
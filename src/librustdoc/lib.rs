@@ -490,7 +490,8 @@ fn json_output(krate: clean::Crate, res: Vec<plugins::PluginJson> ,
     // {
     //   "schema": version,
     //   "crate": { parsed crate ... },
-    //   "plugins": { output of plugins ... }
+    //   "plugins": { output of plugins ... },
+    //   "search_index": [ search index entries ... ]
     // }
     let mut json = std::collections::TreeMap::new();
     json.insert("schema".to_string(), json::String(SCHEMA_VERSION.to_string()));
@@ -521,6 +522,7 @@ fn json_output(krate: clean::Crate, res: Vec<plugins::PluginJson> ,
 
     json.insert("crate".to_string(), crate_json);
     json.insert("plugins".to_string(), json::Object(plugins_json));
+    json.insert("search_index".to_string(), html::render::search_index(krate));
 
     let mut file = try!(File::create(&dst));
     json::Object(json).to_writer(&mut file)
@@ -284,6 +284,7 @@ impl Collector {
                 name: testing::DynTestName(name),
                 ignore: should_ignore,
                 should_fail: false, // compiler failures are test failures
+                timeout: None,
             },
             testfn: testing::DynTestFn(proc() {
                 runtest(test.as_slice(),
@@ -27,6 +27,23 @@
 //!
 //! An example version number with all five components is
 //! `0.8.1-rc.3.0+20130922.linux`.
+//!
+//! A `VersionReq` is a set of comparators, such as `>=1.2.0, <2.0.0`, which
+//! a version must satisfy. It can be used to pick the best version out of
+//! the ones available:
+//!
+//! ```rust
+//! # #![allow(deprecated)]
+//! use semver::VersionReq;
+//!
+//! let req = VersionReq::parse("^1.2").unwrap();
+//! let available = ["1.1.0", "1.2.5", "1.9.0", "2.0.0"];
+//! let versions: Vec<semver::Version> =
+//!     available.iter().map(|v| semver::parse(*v).unwrap()).collect();
+//!
+//! let best = req.max_satisfying(versions.as_slice()).unwrap();
+//! assert_eq!(best.to_string(), "1.9.0".to_string());
+//! ```
 
 #![crate_name = "semver"]
 #![deprecated = "This is now a cargo package located at: \
@@ -44,6 +61,7 @@ use std::char;
 use std::cmp;
 use std::fmt::Show;
 use std::fmt;
+use std::from_str::FromStr;
 use std::hash;
 
 /// An identifier in the pre-release or build metadata. If the identifier can
@@ -282,6 +300,283 @@ pub fn parse(s: &str) -> Option<Version> {
     }
 }
 
+/// The operator of a single comparator in a `VersionReq`
+#[deriving(Clone, PartialEq, Eq)]
+enum Op {
+    OpExact,        // =1.2.3
+    OpGreater,      // >1.2.3
+    OpGreaterEq,    // >=1.2.3
+    OpLess,         // <1.2.3
+    OpLessEq,       // <=1.2.3
+    OpTilde,        // ~1.2.3
+    OpCaret,        // ^1.2.3, or just 1.2.3
+}
+
+/// A single comparator such as `>=1.2`. The minor and patch numbers are
+/// `None` when they were left out or given as a wildcard.
+#[deriving(Clone, PartialEq, Eq)]
+struct Comparator {
+    op: Op,
+    major: uint,
+    minor: Option<uint>,
+    patch: Option<uint>,
+    pre: Vec<Identifier>,
+}
+
+impl Comparator {
+    /// The lowest version this comparator could refer to, with any missing
+    /// parts taken as zero.
+    fn lower_bound(&self) -> Version {
+        Version {
+            major: self.major,
+            minor: self.minor.unwrap_or(0),
+            patch: self.patch.unwrap_or(0),
+            pre: self.pre.clone(),
+            build: vec!(),
+        }
+    }
+
+    /// Compares `v` against this comparator's version, looking only at the
+    /// parts which were given. `1.2.7` is therefore `Equal` to `1.2`.
+    fn cmp_partial(&self, v: &Version) -> Ordering {
+        match (self.minor, self.patch) {
+            (Some(_), Some(_)) => v.cmp(&self.lower_bound()),
+            (Some(minor), None) => (v.major, v.minor).cmp(&(self.major, minor)),
+            (None, _) => v.major.cmp(&self.major),
+        }
+    }
+
+    fn matches(&self, v: &Version) -> bool {
+        match self.op {
+            OpExact => self.cmp_partial(v) == Equal,
+            OpGreater => self.cmp_partial(v) == Greater,
+            OpGreaterEq => self.cmp_partial(v) != Less,
+            OpLess => self.cmp_partial(v) == Less,
+            OpLessEq => self.cmp_partial(v) != Greater,
+            OpTilde => {
+                // ~1.2.3 allows patch-level changes, ~1 minor-level ones
+                *v >= self.lower_bound() &&
+                    v.major == self.major &&
+                    self.minor.map_or(true, |minor| v.minor == minor)
+            }
+            OpCaret => {
+                // ^1.2.3 allows changes which leave the left-most non-zero
+                // part alone, so ^0.2.3 only allows patch-level changes
+                if *v < self.lower_bound() || v.major != self.major {
+                    return false;
+                }
+                match (self.minor, self.patch) {
+                    _ if self.major > 0 => true,
+                    (Some(minor), _) if minor > 0 => v.minor == minor,
+                    (Some(minor), Some(patch)) => {
+                        v.minor == minor && v.patch == patch
+                    }
+                    (Some(minor), None) => v.minor == minor,
+                    (None, _) => true,
+                }
+            }
+        }
+    }
+
+    /// Whether this comparator explicitly mentions a pre-release of the
+    /// same major, minor and patch version as `v`.
+    fn allows_pre_release_of(&self, v: &Version) -> bool {
+        !self.pre.is_empty() &&
+            self.major == v.major &&
+            self.minor == Some(v.minor) &&
+            self.patch == Some(v.patch)
+    }
+}
+
+impl fmt::Show for Comparator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let op = match self.op {
+            OpExact => "=",
+            OpGreater => ">",
+            OpGreaterEq => ">=",
+            OpLess => "<",
+            OpLessEq => "<=",
+            OpTilde => "~",
+            OpCaret => "^",
+        };
+        try!(write!(f, "{}{}", op, self.major));
+        match self.minor {
+            Some(minor) => try!(write!(f, ".{}", minor)),
+            None => return write!(f, ".*"),
+        }
+        match self.patch {
+            Some(patch) => try!(write!(f, ".{}", patch)),
+            None => return write!(f, ".*"),
+        }
+        if !self.pre.is_empty() {
+            try!(write!(f, "-"));
+            for (i, x) in self.pre.iter().enumerate() {
+                if i != 0 { try!(write!(f, ".")) };
+                try!(x.fmt(f));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A version requirement: a comma-separated set of comparators which a
+/// version must all satisfy, such as `>=1.2.0, <2.0.0`.
+///
+/// Each comparator is an operator followed by a version, which may leave
+/// out its minor and patch numbers or give them as a wildcard (`*`, `x` or
+/// `X`):
+///
+///  * `=1.2.3` and `=1.2` match exactly the given parts, so `=1.2` and
+///    `1.2.*` match any `1.2.x` version
+///  * `>`, `>=`, `<` and `<=` compare against the given parts, so `>1.2`
+///    means `>=1.3.0` and `<=1.2` means `<1.3.0`
+///  * `~1.2.3` allows patch-level changes (`>=1.2.3, <1.3.0`), and `~1`
+///    minor-level ones
+///  * `^1.2.3` allows any change which does not modify the left-most
+///    non-zero part (`>=1.2.3, <2.0.0`; `^0.2.3` is `>=0.2.3, <0.3.0`)
+///  * a version without an operator is the same as using `^`
+///  * `*` on its own matches any version
+///
+/// Following the semver spec, versions with pre-release information only
+/// match if some comparator mentions a pre-release of the same major, minor
+/// and patch version. `>=1.0.0-beta` therefore matches `1.0.0-rc.1` and
+/// `1.1.0`, but not `1.1.0-alpha`.
+#[deriving(Clone, PartialEq, Eq)]
+pub struct VersionReq {
+    comparators: Vec<Comparator>,
+}
+
+impl VersionReq {
+    /// Parse a string into a version requirement.
+    pub fn parse(s: &str) -> Option<VersionReq> {
+        if !s.is_ascii() {
+            return None;
+        }
+        let mut comparators = vec!();
+        for part in s.split(',') {
+            let part = part.trim();
+            if is_wildcard(part) {
+                continue;
+            }
+            match parse_comparator(part) {
+                Some(c) => comparators.push(c),
+                None => return None
+            }
+        }
+        Some(VersionReq { comparators: comparators })
+    }
+
+    /// Returns whether `v` satisfies this requirement.
+    pub fn matches(&self, v: &Version) -> bool {
+        self.comparators.iter().all(|c| c.matches(v)) &&
+            (v.pre.is_empty() ||
+             self.comparators.iter().any(|c| c.allows_pre_release_of(v)))
+    }
+
+    /// Returns the highest of `versions` which satisfies this requirement.
+    pub fn max_satisfying<'a>(&self, versions: &'a [Version])
+                              -> Option<&'a Version> {
+        let mut best: Option<&'a Version> = None;
+        for v in versions.iter().filter(|v| self.matches(*v)) {
+            best = match best {
+                Some(b) if *b >= *v => Some(b),
+                _ => Some(v),
+            };
+        }
+        best
+    }
+}
+
+impl fmt::Show for VersionReq {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.comparators.is_empty() {
+            return write!(f, "*");
+        }
+        for (i, c) in self.comparators.iter().enumerate() {
+            if i != 0 { try!(write!(f, ", ")) };
+            try!(c.fmt(f));
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for VersionReq {
+    fn from_str(s: &str) -> Option<VersionReq> {
+        VersionReq::parse(s)
+    }
+}
+
+fn is_wildcard(s: &str) -> bool {
+    s == "*" || s == "x" || s == "X"
+}
+
+fn parse_comparator(s: &str) -> Option<Comparator> {
+    let ops = [(">=", OpGreaterEq), ("<=", OpLessEq), (">", OpGreater),
+               ("<", OpLess), ("=", OpExact), ("~", OpTilde), ("^", OpCaret)];
+    let (op, s) = match ops.iter().find(|&&(prefix, _)| s.starts_with(prefix)) {
+        Some(&(prefix, op)) => (op, s.slice_from(prefix.len()).trim_left()),
+        None => (OpCaret, s)
+    };
+
+    // Build metadata never affects matching, so it is dropped
+    let s = match s.find('+') {
+        Some(i) => s.slice_to(i),
+        None => s
+    };
+    let (nums, pre) = match s.find('-') {
+        Some(i) => (s.slice_to(i), Some(s.slice_from(i + 1))),
+        None => (s, None)
+    };
+
+    // Each part is a number or a wildcard, and only wildcards may follow a
+    // wildcard
+    let mut parts = vec!();
+    for part in nums.split('.') {
+        if is_wildcard(part) {
+            parts.push(None);
+        } else if !part.chars().all(char::is_digit) ||
+                  parts.iter().any(|p| p.is_none()) {
+            return None;
+        } else {
+            match from_str::<uint>(part) {
+                Some(n) => parts.push(Some(n)),
+                None => return None
+            }
+        }
+    }
+    if parts.len() > 3 {
+        return None;
+    }
+    let major = match parts.as_slice().get(0) {
+        Some(&Some(major)) => major,
+        _ => return None
+    };
+    let minor = parts.as_slice().get(1).and_then(|p| *p);
+    let patch = parts.as_slice().get(2).and_then(|p| *p);
+
+    let mut idents = vec!();
+    match pre {
+        None => {}
+        Some(_) if patch.is_none() => return None,
+        Some(pre) => {
+            for id in pre.split('.') {
+                match take_ident(&mut id.chars()) {
+                    Some((ident, None)) => idents.push(ident),
+                    _ => return None
+                }
+            }
+        }
+    }
+
+    Some(Comparator {
+        op: op,
+        major: major,
+        minor: minor,
+        patch: patch,
+        pre: idents,
+    })
+}
+
 #[test]
 fn test_parse() {
     assert_eq!(parse(""), None);
@@ -460,3 +755,98 @@ fn test_spec_order() {
         i += 1;
     }
 }
+
+#[test]
+fn test_parse_req() {
+    fn req(s: &str) -> String {
+        VersionReq::parse(s).unwrap().to_string()
+    }
+
+    assert_eq!(req("1.2.3").as_slice(), "^1.2.3");
+    assert_eq!(req(">= 1.2.0, <2.0.0").as_slice(), ">=1.2.0, <2.0.0");
+    assert_eq!(req("~1.2.3").as_slice(), "~1.2.3");
+    assert_eq!(req("1.*").as_slice(), "^1.*");
+    assert_eq!(req("=1.x").as_slice(), "=1.*");
+    assert_eq!(req("=1.0.0-beta.2+build.5").as_slice(), "=1.0.0-beta.2");
+    assert_eq!(req("*").as_slice(), "*");
+
+    assert!(VersionReq::parse("").is_none());
+    assert!(VersionReq::parse(">=").is_none());
+    assert!(VersionReq::parse("1.2.3.4").is_none());
+    assert!(VersionReq::parse("1.*.3").is_none());
+    assert!(VersionReq::parse("1.2-beta").is_none());
+    assert!(VersionReq::parse("=1.2.3-").is_none());
+    assert!(VersionReq::parse(">=1.0.0,").is_none());
+    assert!(VersionReq::parse("!1.0.0").is_none());
+    assert!(VersionReq::parse("a.b.c").is_none());
+}
+
+#[cfg(test)]
+fn assert_matches(req: &str, yes: &[&str], no: &[&str]) {
+    let r = VersionReq::parse(req).unwrap();
+    for v in yes.iter() {
+        assert!(r.matches(&parse(*v).unwrap()), "{} should match {}", req, v);
+    }
+    for v in no.iter() {
+        assert!(!r.matches(&parse(*v).unwrap()), "{} should not match {}", req, v);
+    }
+}
+
+#[test]
+fn test_req_ops() {
+    assert_matches("=1.2.3", &["1.2.3", "1.2.3+build"], &["1.2.4", "1.2.3-beta"]);
+    assert_matches("=1.2", &["1.2.0", "1.2.9"], &["1.1.9", "1.3.0"]);
+    assert_matches(">1.2.3", &["1.2.4", "2.0.0"], &["1.2.3", "1.0.0"]);
+    assert_matches(">1.2", &["1.3.0"], &["1.2.9"]);
+    assert_matches(">=1.2", &["1.2.0", "3.0.0"], &["1.1.9"]);
+    assert_matches("<1.2.3", &["1.2.2", "0.1.0"], &["1.2.3"]);
+    assert_matches("<=1.2", &["1.2.9"], &["1.3.0"]);
+    assert_matches(">=1.2.0, <2.0.0", &["1.2.0", "1.9.9"], &["1.1.0", "2.0.0"]);
+}
+
+#[test]
+fn test_req_tilde_and_caret() {
+    assert_matches("~1.2.3", &["1.2.3", "1.2.9"], &["1.2.2", "1.3.0"]);
+    assert_matches("~1.2", &["1.2.0", "1.2.9"], &["1.1.0", "1.3.0"]);
+    assert_matches("~1", &["1.0.0", "1.9.0"], &["0.9.0", "2.0.0"]);
+
+    assert_matches("^1.2.3", &["1.2.3", "1.9.0"], &["1.2.2", "2.0.0"]);
+    assert_matches("1.2", &["1.2.0", "1.9.0"], &["1.1.9", "2.0.0"]);
+    assert_matches("^0.2.3", &["0.2.3", "0.2.9"], &["0.2.2", "0.3.0"]);
+    assert_matches("^0.0.3", &["0.0.3"], &["0.0.4", "0.1.0"]);
+    assert_matches("^0.0", &["0.0.0", "0.0.9"], &["0.1.0"]);
+    assert_matches("^0", &["0.0.0", "0.9.0"], &["1.0.0"]);
+}
+
+#[test]
+fn test_req_wildcards() {
+    assert_matches("*", &["0.0.0", "1.2.3"], &["1.0.0-beta"]);
+    assert_matches("1.*", &["1.0.0", "1.9.9"], &["0.9.0", "2.0.0"]);
+    assert_matches("1.2.x", &["1.2.0", "1.2.9"], &["1.3.0"]);
+}
+
+#[test]
+fn test_req_pre_release() {
+    assert_matches("=1.0.0-beta", &["1.0.0-beta"], &["1.0.0-alpha", "1.0.0"]);
+    assert_matches(">=1.0.0-beta", &["1.0.0-beta", "1.0.0-rc.1", "1.0.0", "1.1.0"],
+                   &["1.0.0-alpha", "1.1.0-alpha"]);
+    assert_matches("<2.0.0", &["1.9.9"], &["2.0.0-alpha"]);
+    assert_matches("^1.2.3-beta.2", &["1.2.3-beta.11", "1.5.0"],
+                   &["1.2.3-beta.1", "1.5.0-beta.3"]);
+}
+
+#[test]
+fn test_max_satisfying() {
+    let versions: Vec<Version> = ["0.9.0", "1.2.0", "1.4.2", "1.5.0-beta", "2.0.0"]
+        .iter().map(|v| parse(*v).unwrap()).collect();
+    let best = |req: &str| {
+        VersionReq::parse(req).unwrap()
+            .max_satisfying(versions.as_slice())
+            .map(|v| v.to_string())
+    };
+
+    assert_eq!(best("^1.2"), Some("1.4.2".to_string()));
+    assert_eq!(best(">=1.5.0-alpha"), Some("2.0.0".to_string()));
+    assert_eq!(best(">=1.5.0-alpha, <2.0.0"), Some("1.5.0-beta".to_string()));
+    assert_eq!(best("<0.5"), None);
+}
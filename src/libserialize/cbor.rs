@@ -0,0 +1,1349 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![forbid(non_camel_case_types)]
+#![allow(missing_doc)]
+
+/*!
+CBOR serialization
+
+CBOR, the Concise Binary Object Representation (RFC 7049), is a binary format with a data
+model that extends JSON with byte strings, tags and more simple values.
+
+Integers and floats are always written with the shortest encoding that represents them
+exactly, and all strings, arrays and maps are written with a definite length. Indefinite-length
+items are accepted when decoding. Rust types are mapped the same way `json` maps them:
+
+* integers, floats, booleans and strings are encoded as the corresponding CBOR type
+* `()` and `None` are encoded as `null`, `Some(x)` as `x`
+* structs are encoded as maps from field names to values
+* tuples, tuple structs and sequences are encoded as arrays
+* enum variants without arguments are encoded as their name, other variants as a map
+  `{"variant": name, "fields": [args...]}`
+
+When decoding into Rust types, tags are ignored and the tagged value is decoded instead.
+
+```rust
+extern crate serialize;
+use serialize::cbor;
+
+#[deriving(Decodable, Encodable, PartialEq, Show)]
+pub struct Point {
+    x: i32,
+    y: i32,
+}
+
+fn main() {
+    let point = Point { x: 1, y: -2 };
+    let bytes = cbor::encode(&point);
+    assert_eq!(bytes, vec![0xa2, 0x61, 0x78, 0x01, 0x61, 0x79, 0x21]);
+
+    let decoded: Point = cbor::decode(bytes.as_slice()).unwrap();
+    assert_eq!(decoded, point);
+}
+```
+*/
+
+use std::{cmp, f64, i64, io, num, str};
+use std::io::{BufReader, MemWriter};
+use std::mem::transmute;
+
+use Encodable;
+
+/// Represents a CBOR data item
+#[deriving(Clone, PartialEq, Show)]
+pub enum Value {
+    U64(u64),
+    /// A negative integer. Negative integers below `i64::MIN` cannot be
+    /// represented and are rejected by the decoder.
+    I64(i64),
+    Bytes(Vec<u8>),
+    String(String),
+    Array(Vec<Value>),
+    Map(Vec<(Value, Value)>),
+    Tag(u64, Box<Value>),
+    Boolean(bool),
+    Null,
+    Undefined,
+    /// A simple value other than false, true, null and undefined.
+    Simple(u8),
+    /// A floating-point number of any precision.
+    F64(f64),
+}
+
+#[deriving(Clone, PartialEq, Show)]
+pub enum DecoderError {
+    IoError(io::IoErrorKind, &'static str),
+    /// The initial byte of a data item is not well-formed.
+    InvalidHeader(u8),
+    /// A "break" stop code was found outside of an indefinite-length item.
+    UnexpectedBreak,
+    /// A chunk of an indefinite-length string is not a definite-length
+    /// string of the same type.
+    InvalidChunk(u8),
+    /// A negative integer does not fit in an `i64`.
+    IntegerOverflow,
+    /// Arrays, maps and tags are nested too deeply to decode.
+    NestingTooDeep,
+    NotUtf8,
+    TrailingBytes,
+    ExpectedError(String, String),
+    MissingFieldError(String),
+    UnknownVariantError(String),
+    ApplicationError(String)
+}
+
+pub type EncodeResult = io::IoResult<()>;
+pub type DecodeResult<T> = Result<T, DecoderError>;
+
+static MAJOR_UNSIGNED: u8 = 0;
+static MAJOR_NEGATIVE: u8 = 1;
+static MAJOR_BYTES: u8 = 2;
+static MAJOR_TEXT: u8 = 3;
+static MAJOR_ARRAY: u8 = 4;
+static MAJOR_MAP: u8 = 5;
+static MAJOR_TAG: u8 = 6;
+static MAJOR_SIMPLE: u8 = 7;
+
+static BREAK: u8 = 0xff;
+
+/// Shortcut function to encode a `T` into a CBOR byte vector
+pub fn encode<'a, T: Encodable<Encoder<'a>, io::IoError>>(object: &T) -> Vec<u8> {
+    Encoder::buffer_encode(object)
+}
+
+/// Shortcut function to decode a CBOR byte slice into an object
+pub fn decode<T: ::Decodable<Decoder, DecoderError>>(bytes: &[u8]) -> DecodeResult<T> {
+    let value = try!(from_bytes(bytes));
+    let mut decoder = Decoder::new(value);
+    ::Decodable::decode(&mut decoder)
+}
+
+fn io_error_to_error(io: io::IoError) -> DecoderError {
+    IoError(io.kind, io.desc)
+}
+
+macro_rules! try_io(
+    ($e:expr) => (try!($e.map_err(io_error_to_error)))
+)
+
+// Writes the initial byte of a data item of the given major type, followed by
+// its argument in the shortest form.
+fn write_head(wr: &mut io::Writer, major: u8, v: u64) -> EncodeResult {
+    let major = major << 5;
+    if v < 24 {
+        wr.write_u8(major | v as u8)
+    } else if v <= 0xff {
+        try!(wr.write_u8(major | 24));
+        wr.write_u8(v as u8)
+    } else if v <= 0xffff {
+        try!(wr.write_u8(major | 25));
+        wr.write_be_u16(v as u16)
+    } else if v <= 0xffff_ffff {
+        try!(wr.write_u8(major | 26));
+        wr.write_be_u32(v as u32)
+    } else {
+        try!(wr.write_u8(major | 27));
+        wr.write_be_u64(v)
+    }
+}
+
+fn write_uint(wr: &mut io::Writer, v: u64) -> EncodeResult {
+    write_head(wr, MAJOR_UNSIGNED, v)
+}
+
+fn write_int(wr: &mut io::Writer, v: i64) -> EncodeResult {
+    if v >= 0 {
+        write_head(wr, MAJOR_UNSIGNED, v as u64)
+    } else {
+        // -1 - v, which cannot overflow
+        write_head(wr, MAJOR_NEGATIVE, !v as u64)
+    }
+}
+
+// Returns the half-precision encoding of `v`, if it can be represented
+// exactly. NaNs are left to the caller.
+fn f64_to_f16(v: f64) -> Option<u16> {
+    let bits: u64 = unsafe { transmute(v) };
+    let sign = ((bits >> 48) & 0x8000) as u16;
+    let exp = ((bits >> 52) & 0x7ff) as int;
+    let mant = bits & 0xf_ffff_ffff_ffff;
+
+    if exp == 0x7ff {
+        return if mant == 0 { Some(sign | 0x7c00) } else { None };
+    }
+    if exp == 0 && mant == 0 {
+        return Some(sign);
+    }
+    let exp = exp - 1023;
+    if exp > 15 {
+        None
+    } else if exp >= -14 {
+        // Normal: the 42 low bits of the mantissa are lost.
+        if mant & ((1 << 42) - 1) != 0 { return None; }
+        Some(sign | ((exp + 15) as u16 << 10) | (mant >> 42) as u16)
+    } else if exp >= -24 {
+        // Subnormal: the value is a multiple of 2^-24.
+        let sig = mant | (1 << 52);
+        let shift = (28 - exp) as uint;
+        if sig & ((1 << shift) - 1) != 0 { return None; }
+        Some(sign | (sig >> shift) as u16)
+    } else {
+        None
+    }
+}
+
+fn f16_to_f64(h: u16) -> f64 {
+    let exp = ((h >> 10) & 0x1f) as i32;
+    let mant = (h & 0x3ff) as f64;
+    let v = if exp == 0 {
+        mant * 2.0f64.powi(-24)
+    } else if exp == 31 {
+        if mant == 0.0 { f64::INFINITY } else { f64::NAN }
+    } else {
+        (mant + 1024.0) * 2.0f64.powi(exp - 25)
+    };
+    if h & 0x8000 != 0 { -v } else { v }
+}
+
+fn write_float(wr: &mut io::Writer, v: f64) -> EncodeResult {
+    if v.is_nan() {
+        try!(wr.write_u8(0xf9));
+        return wr.write_be_u16(0x7e00);
+    }
+    match f64_to_f16(v) {
+        Some(h) => {
+            try!(wr.write_u8(0xf9));
+            wr.write_be_u16(h)
+        }
+        None if (v as f32) as f64 == v => {
+            try!(wr.write_u8(0xfa));
+            wr.write_be_f32(v as f32)
+        }
+        None => {
+            try!(wr.write_u8(0xfb));
+            wr.write_be_f64(v)
+        }
+    }
+}
+
+fn write_str(wr: &mut io::Writer, v: &str) -> EncodeResult {
+    try!(write_head(wr, MAJOR_TEXT, v.len() as u64));
+    wr.write_str(v)
+}
+
+pub struct Encoder<'a> {
+    writer: &'a mut io::Writer+'a,
+}
+
+impl<'a> Encoder<'a> {
+    /// Creates a new CBOR encoder whose output will be written to the
+    /// writer specified.
+    pub fn new(writer: &'a mut io::Writer) -> Encoder<'a> {
+        Encoder { writer: writer }
+    }
+
+    /// Encode the specified struct into a CBOR [u8]
+    pub fn buffer_encode<T: Encodable<Encoder<'a>, io::IoError>>(object: &T) -> Vec<u8> {
+        let mut m = MemWriter::new();
+        // FIXME(14302) remove the transmute and unsafe block.
+        unsafe {
+            let mut encoder = Encoder::new(&mut m as &mut io::Writer);
+            // MemWriter never Errs
+            let _ = object.encode(transmute(&mut encoder));
+        }
+        m.unwrap()
+    }
+}
+
+impl<'a> ::Encoder<io::IoError> for Encoder<'a> {
+    fn emit_nil(&mut self) -> EncodeResult { self.writer.write_u8(0xf6) }
+
+    fn emit_uint(&mut self, v: uint) -> EncodeResult { write_uint(self.writer, v as u64) }
+    fn emit_u64(&mut self, v: u64) -> EncodeResult { write_uint(self.writer, v) }
+    fn emit_u32(&mut self, v: u32) -> EncodeResult { write_uint(self.writer, v as u64) }
+    fn emit_u16(&mut self, v: u16) -> EncodeResult { write_uint(self.writer, v as u64) }
+    fn emit_u8(&mut self, v: u8) -> EncodeResult  { write_uint(self.writer, v as u64) }
+
+    fn emit_int(&mut self, v: int) -> EncodeResult { write_int(self.writer, v as i64) }
+    fn emit_i64(&mut self, v: i64) -> EncodeResult { write_int(self.writer, v) }
+    fn emit_i32(&mut self, v: i32) -> EncodeResult { write_int(self.writer, v as i64) }
+    fn emit_i16(&mut self, v: i16) -> EncodeResult { write_int(self.writer, v as i64) }
+    fn emit_i8(&mut self, v: i8) -> EncodeResult  { write_int(self.writer, v as i64) }
+
+    fn emit_bool(&mut self, v: bool) -> EncodeResult {
+        self.writer.write_u8(if v { 0xf5 } else { 0xf4 })
+    }
+
+    fn emit_f64(&mut self, v: f64) -> EncodeResult { write_float(self.writer, v) }
+    fn emit_f32(&mut self, v: f32) -> EncodeResult { write_float(self.writer, v as f64) }
+
+    fn emit_char(&mut self, v: char) -> EncodeResult {
+        let mut buf = [0u8, .. 4];
+        let n = v.encode_utf8(buf).unwrap_or(0);
+        try!(write_head(self.writer, MAJOR_TEXT, n as u64));
+        self.writer.write(buf.slice_to(n))
+    }
+    fn emit_str(&mut self, v: &str) -> EncodeResult { write_str(self.writer, v) }
+
+    fn emit_enum(&mut self, _name: &str, f: |&mut Encoder<'a>| -> EncodeResult) -> EncodeResult {
+        f(self)
+    }
+
+    fn emit_enum_variant(&mut self,
+                         name: &str,
+                         _id: uint,
+                         cnt: uint,
+                         f: |&mut Encoder<'a>| -> EncodeResult) -> EncodeResult {
+        // enums are encoded as text strings or maps, as in json
+        // Bunny => "Bunny"
+        // Kangaroo(34,"William") => {"variant": "Kangaroo", "fields": [34,"William"]}
+        if cnt == 0 {
+            write_str(self.writer, name)
+        } else {
+            try!(write_head(self.writer, MAJOR_MAP, 2));
+            try!(write_str(self.writer, "variant"));
+            try!(write_str(self.writer, name));
+            try!(write_str(self.writer, "fields"));
+            try!(write_head(self.writer, MAJOR_ARRAY, cnt as u64));
+            f(self)
+        }
+    }
+
+    fn emit_enum_variant_arg(&mut self,
+                             _idx: uint,
+                             f: |&mut Encoder<'a>| -> EncodeResult) -> EncodeResult {
+        f(self)
+    }
+
+    fn emit_enum_struct_variant(&mut self,
+                                name: &str,
+                                id: uint,
+                                cnt: uint,
+                                f: |&mut Encoder<'a>| -> EncodeResult) -> EncodeResult {
+        self.emit_enum_variant(name, id, cnt, f)
+    }
+
+    fn emit_enum_struct_variant_field(&mut self,
+                                      _: &str,
+                                      idx: uint,
+                                      f: |&mut Encoder<'a>| -> EncodeResult) -> EncodeResult {
+        self.emit_enum_variant_arg(idx, f)
+    }
+
+    fn emit_struct(&mut self,
+                   _: &str,
+                   len: uint,
+                   f: |&mut Encoder<'a>| -> EncodeResult) -> EncodeResult {
+        try!(write_head(self.writer, MAJOR_MAP, len as u64));
+        f(self)
+    }
+
+    fn emit_struct_field(&mut self,
+                         name: &str,
+                         _idx: uint,
+                         f: |&mut Encoder<'a>| -> EncodeResult) -> EncodeResult {
+        try!(write_str(self.writer, name));
+        f(self)
+    }
+
+    fn emit_tuple(&mut self, len: uint, f: |&mut Encoder<'a>| -> EncodeResult) -> EncodeResult {
+        self.emit_seq(len, f)
+    }
+    fn emit_tuple_arg(&mut self,
+                      idx: uint,
+                      f: |&mut Encoder<'a>| -> EncodeResult) -> EncodeResult {
+        self.emit_seq_elt(idx, f)
+    }
+
+    fn emit_tuple_struct(&mut self,
+                         _name: &str,
+                         len: uint,
+                         f: |&mut Encoder<'a>| -> EncodeResult) -> EncodeResult {
+        self.emit_seq(len, f)
+    }
+    fn emit_tuple_struct_arg(&mut self,
+                             idx: uint,
+                             f: |&mut Encoder<'a>| -> EncodeResult) -> EncodeResult {
+        self.emit_seq_elt(idx, f)
+    }
+
+    fn emit_option(&mut self, f: |&mut Encoder<'a>| -> EncodeResult) -> EncodeResult {
+        f(self)
+    }
+    fn emit_option_none(&mut self) -> EncodeResult { self.emit_nil() }
+    fn emit_option_some(&mut self, f: |&mut Encoder<'a>| -> EncodeResult) -> EncodeResult {
+        f(self)
+    }
+
+    fn emit_seq(&mut self, len: uint, f: |&mut Encoder<'a>| -> EncodeResult) -> EncodeResult {
+        try!(write_head(self.writer, MAJOR_ARRAY, len as u64));
+        f(self)
+    }
+
+    fn emit_seq_elt(&mut self, _idx: uint, f: |&mut Encoder<'a>| -> EncodeResult) -> EncodeResult {
+        f(self)
+    }
+
+    fn emit_map(&mut self, len: uint, f: |&mut Encoder<'a>| -> EncodeResult) -> EncodeResult {
+        try!(write_head(self.writer, MAJOR_MAP, len as u64));
+        f(self)
+    }
+
+    fn emit_map_elt_key(&mut self,
+                        _idx: uint,
+                        f: |&mut Encoder<'a>| -> EncodeResult) -> EncodeResult {
+        f(self)
+    }
+
+    fn emit_map_elt_val(&mut self,
+                        _idx: uint,
+                        f: |&mut Encoder<'a>| -> EncodeResult) -> EncodeResult {
+        f(self)
+    }
+}
+
+
+impl Value {
+    /// Encodes a CBOR data item into a Writer
+    pub fn to_writer(&self, wr: &mut io::Writer) -> EncodeResult {
+        match *self {
+            U64(n) => write_uint(wr, n),
+            I64(n) => write_int(wr, n),
+            Bytes(ref b) => {
+                try!(write_head(wr, MAJOR_BYTES, b.len() as u64));
+                wr.write(b.as_slice())
+            }
+            String(ref s) => write_str(wr, s.as_slice()),
+            Array(ref values) => {
+                try!(write_head(wr, MAJOR_ARRAY, values.len() as u64));
+                for v in values.iter() {
+                    try!(v.to_writer(wr));
+                }
+                Ok(())
+            }
+            Map(ref pairs) => {
+                try!(write_head(wr, MAJOR_MAP, pairs.len() as u64));
+                for &(ref k, ref v) in pairs.iter() {
+                    try!(k.to_writer(wr));
+                    try!(v.to_writer(wr));
+                }
+                Ok(())
+            }
+            Tag(tag, ref value) => {
+                try!(write_head(wr, MAJOR_TAG, tag));
+                value.to_writer(wr)
+            }
+            Boolean(b) => wr.write_u8(if b { 0xf5 } else { 0xf4 }),
+            Null => wr.write_u8(0xf6),
+            Undefined => wr.write_u8(0xf7),
+            // 20 to 23 are false, true, null and undefined, and 24 to 31
+            // have no well-formed encoding.
+            Simple(n) if n >= 20 && n < 32 => Err(io::IoError {
+                kind: io::InvalidInput,
+                desc: "simple value has no encoding of its own",
+                detail: Some(format!("simple({})", n)),
+            }),
+            Simple(n) => write_head(wr, MAJOR_SIMPLE, n as u64),
+            F64(n) => write_float(wr, n),
+        }
+    }
+
+    /// Encodes a CBOR data item into a byte vector
+    ///
+    /// # Failure
+    ///
+    /// Fails if the item contains a `Simple` value between 20 and 31, which
+    /// CBOR cannot represent.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut m = MemWriter::new();
+        // MemWriter never Errs, so this can only be an unencodable value
+        match self.to_writer(&mut m as &mut io::Writer) {
+            Ok(()) => m.unwrap(),
+            Err(e) => fail!("cannot encode CBOR value: {}", e),
+        }
+    }
+
+    /// If the Value is a Map, returns the value associated with the text
+    /// string key provided. Returns None otherwise.
+    pub fn find<'a>(&'a self, key: &str) -> Option<&'a Value> {
+        match *self {
+            Map(ref pairs) => {
+                pairs.iter().find(|&&(ref k, _)| k.as_string() == Some(key)).map(|&(_, ref v)| v)
+            }
+            _ => None
+        }
+    }
+
+    /// If the Value is a String, returns the associated str.
+    /// Returns None otherwise.
+    pub fn as_string<'a>(&'a self) -> Option<&'a str> {
+        match *self {
+            String(ref s) => Some(s.as_slice()),
+            _ => None
+        }
+    }
+
+    /// If the Value is an Array, returns the associated vector.
+    /// Returns None otherwise.
+    pub fn as_array<'a>(&'a self) -> Option<&'a Vec<Value>> {
+        match *self {
+            Array(ref values) => Some(values),
+            _ => None
+        }
+    }
+
+    /// If the Value is a Bytes, returns the associated bytes.
+    /// Returns None otherwise.
+    pub fn as_bytes<'a>(&'a self) -> Option<&'a [u8]> {
+        match *self {
+            Bytes(ref b) => Some(b.as_slice()),
+            _ => None
+        }
+    }
+
+    /// If the Value is a number, returns it as an i64 if it fits.
+    /// Returns None otherwise.
+    pub fn as_i64(&self) -> Option<i64> {
+        match *self {
+            I64(n) => Some(n),
+            U64(n) => num::cast(n),
+            _ => None
+        }
+    }
+
+    /// If the Value is a number, returns it as an u64 if it fits.
+    /// Returns None otherwise.
+    pub fn as_u64(&self) -> Option<u64> {
+        match *self {
+            I64(n) => num::cast(n),
+            U64(n) => Some(n),
+            _ => None
+        }
+    }
+
+    /// If the Value is a number, returns it as an f64.
+    /// Returns None otherwise.
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self {
+            I64(n) => Some(n as f64),
+            U64(n) => Some(n as f64),
+            F64(n) => Some(n),
+            _ => None
+        }
+    }
+
+    /// Returns true if the Value is Null.
+    pub fn is_null(&self) -> bool {
+        *self == Null
+    }
+}
+
+// Reads the argument of a data item whose initial byte is `initial`.
+fn read_arg(rdr: &mut io::Reader, initial: u8) -> DecodeResult<u64> {
+    match initial & 0x1f {
+        n @ 0 .. 23 => Ok(n as u64),
+        24 => Ok(try_io!(rdr.read_u8()) as u64),
+        25 => Ok(try_io!(rdr.read_be_u16()) as u64),
+        26 => Ok(try_io!(rdr.read_be_u32()) as u64),
+        27 => Ok(try_io!(rdr.read_be_u64())),
+        _ => Err(InvalidHeader(initial)),
+    }
+}
+
+// Input is read in pieces of at most this many bytes, so that a length
+// taken from the input cannot make the decoder allocate much more memory
+// than the input actually holds.
+static READ_CHUNK: u64 = 64 * 1024;
+
+// Appends `len` bytes from the reader to `bytes`.
+fn read_bytes(rdr: &mut io::Reader, len: u64, bytes: &mut Vec<u8>) -> DecodeResult<()> {
+    let mut remaining = len;
+    while remaining > 0 {
+        let n = cmp::min(remaining, READ_CHUNK) as uint;
+        try_io!(rdr.push_at_least(n, n, bytes));
+        remaining -= n as u64;
+    }
+    Ok(())
+}
+
+// Reads the contents of a byte or text string of the given major type. An
+// indefinite-length string is read as the concatenation of its chunks.
+fn read_string_bytes(rdr: &mut io::Reader, initial: u8) -> DecodeResult<Vec<u8>> {
+    if initial & 0x1f != 31 {
+        let len = try!(read_arg(rdr, initial));
+        let mut bytes = Vec::new();
+        try!(read_bytes(rdr, len, &mut bytes));
+        return Ok(bytes);
+    }
+    let mut bytes = Vec::new();
+    loop {
+        let chunk = try_io!(rdr.read_u8());
+        if chunk == BREAK {
+            return Ok(bytes);
+        }
+        if chunk >> 5 != initial >> 5 || chunk & 0x1f == 31 {
+            return Err(InvalidChunk(chunk));
+        }
+        let len = try!(read_arg(rdr, chunk));
+        try!(read_bytes(rdr, len, &mut bytes));
+    }
+}
+
+// The deepest nesting of arrays, maps and tags that is decoded, so that
+// hostile input cannot overflow the stack.
+static MAX_DEPTH: uint = 512;
+
+// Reads a data item nested inside `depth` arrays, maps and tags, or returns
+// None if a "break" is found and `in_indefinite` says that one is allowed.
+fn read_value(rdr: &mut io::Reader, in_indefinite: bool,
+              depth: uint) -> DecodeResult<Option<Value>> {
+    let initial = try_io!(rdr.read_u8());
+    if initial == BREAK {
+        return if in_indefinite { Ok(None) } else { Err(UnexpectedBreak) };
+    }
+    let major = initial >> 5;
+    let indefinite = initial & 0x1f == 31;
+    if major >= 4 && major <= 6 && depth >= MAX_DEPTH {
+        return Err(NestingTooDeep);
+    }
+
+    let value = match major {
+        0 => U64(try!(read_arg(rdr, initial))),
+        1 => {
+            let n = try!(read_arg(rdr, initial));
+            if n > i64::MAX as u64 {
+                return Err(IntegerOverflow);
+            }
+            I64(!(n as i64))
+        }
+        2 => Bytes(try!(read_string_bytes(rdr, initial))),
+        3 => {
+            match String::from_utf8(try!(read_string_bytes(rdr, initial))) {
+                Ok(s) => String(s),
+                Err(_) => return Err(NotUtf8),
+            }
+        }
+        4 => {
+            // The length comes from the input, so don't trust it for
+            // preallocation.
+            let mut values = Vec::new();
+            if indefinite {
+                loop {
+                    match try!(read_value(rdr, true, depth + 1)) {
+                        Some(v) => values.push(v),
+                        None => break,
+                    }
+                }
+            } else {
+                for _ in range(0, try!(read_arg(rdr, initial))) {
+                    values.push(try!(read_item(rdr, depth + 1)));
+                }
+            }
+            Array(values)
+        }
+        5 => {
+            let mut pairs = Vec::new();
+            if indefinite {
+                loop {
+                    let key = match try!(read_value(rdr, true, depth + 1)) {
+                        Some(k) => k,
+                        None => break,
+                    };
+                    pairs.push((key, try!(read_item(rdr, depth + 1))));
+                }
+            } else {
+                for _ in range(0, try!(read_arg(rdr, initial))) {
+                    let key = try!(read_item(rdr, depth + 1));
+                    pairs.push((key, try!(read_item(rdr, depth + 1))));
+                }
+            }
+            Map(pairs)
+        }
+        6 => {
+            let tag = try!(read_arg(rdr, initial));
+            Tag(tag, box try!(read_item(rdr, depth + 1)))
+        }
+        _ => {
+            match initial & 0x1f {
+                20 => Boolean(false),
+                21 => Boolean(true),
+                22 => Null,
+                23 => Undefined,
+                n @ 0 .. 19 => Simple(n),
+                24 => {
+                    let n = try_io!(rdr.read_u8());
+                    // Two-byte encodings of the simple values below 32 are
+                    // not well-formed.
+                    if n < 32 {
+                        return Err(InvalidHeader(initial));
+                    }
+                    Simple(n)
+                }
+                25 => F64(f16_to_f64(try_io!(rdr.read_be_u16()))),
+                26 => F64(try_io!(rdr.read_be_f32()) as f64),
+                27 => F64(try_io!(rdr.read_be_f64())),
+                _ => return Err(InvalidHeader(initial)),
+            }
+        }
+    };
+    Ok(Some(value))
+}
+
+// Like `read_value`, for an item which cannot be a "break".
+fn read_item(rdr: &mut io::Reader, depth: uint) -> DecodeResult<Value> {
+    read_value(rdr, false, depth).map(|v| v.unwrap())
+}
+
+/// Decodes a single CBOR data item from an `&mut io::Reader`, leaving the
+/// reader positioned right after it.
+pub fn from_reader(rdr: &mut io::Reader) -> DecodeResult<Value> {
+    read_item(rdr, 0)
+}
+
+/// Decodes a CBOR data item from a byte slice, which must contain exactly
+/// one item.
+pub fn from_bytes(bytes: &[u8]) -> DecodeResult<Value> {
+    let mut rdr = BufReader::new(bytes);
+    let value = try!(from_reader(&mut rdr));
+    if !rdr.eof() {
+        return Err(TrailingBytes);
+    }
+    Ok(value)
+}
+
+pub struct Decoder {
+    stack: Vec<Value>,
+}
+
+impl Decoder {
+    /// Creates a new decoder instance for decoding the specified value.
+    pub fn new(value: Value) -> Decoder {
+        Decoder { stack: vec![value] }
+    }
+}
+
+impl Decoder {
+    // Tags carry meaning that the serialization traits cannot express, so the
+    // decoder looks through them to the tagged value.
+    fn pop(&mut self) -> Value {
+        let mut value = self.stack.pop().unwrap();
+        loop {
+            value = match value {
+                Tag(_, inner) => *inner,
+                value => return value,
+            }
+        }
+    }
+}
+
+// Removes the member with the given string key from a map and returns its value.
+fn take_field(pairs: &mut Vec<(Value, Value)>, name: &str) -> Option<Value> {
+    let idx = pairs.iter().position(|&(ref k, _)| k.as_string() == Some(name));
+    match idx {
+        Some(idx) => pairs.remove(idx).map(|(_, v)| v),
+        None => None,
+    }
+}
+
+macro_rules! expect(
+    ($e:expr, Null) => ({
+        match $e {
+            Null => Ok(()),
+            other => Err(ExpectedError("Null".to_string(),
+                                       format!("{}", other)))
+        }
+    });
+    ($e:expr, $t:ident) => ({
+        match $e {
+            $t(v) => Ok(v),
+            other => {
+                Err(ExpectedError(stringify!($t).to_string(),
+                                  format!("{}", other)))
+            }
+        }
+    })
+)
+
+macro_rules! read_primitive {
+    ($name:ident, $ty:ty) => {
+        fn $name(&mut self) -> DecodeResult<$ty> {
+            let value = self.pop();
+            let n = match value {
+                I64(n) => num::cast(n),
+                U64(n) => num::cast(n),
+                F64(n) => num::cast(n),
+                _ => None,
+            };
+            match n {
+                Some(n) => Ok(n),
+                None => Err(ExpectedError("Number".to_string(), format!("{}", value))),
+            }
+        }
+    }
+}
+
+impl ::Decoder<DecoderError> for Decoder {
+    fn read_nil(&mut self) -> DecodeResult<()> {
+        debug!("read_nil");
+        expect!(self.pop(), Null)
+    }
+
+    read_primitive!(read_uint, uint)
+    read_primitive!(read_u8, u8)
+    read_primitive!(read_u16, u16)
+    read_primitive!(read_u32, u32)
+    read_primitive!(read_u64, u64)
+    read_primitive!(read_int, int)
+    read_primitive!(read_i8, i8)
+    read_primitive!(read_i16, i16)
+    read_primitive!(read_i32, i32)
+    read_primitive!(read_i64, i64)
+
+    fn read_f32(&mut self) -> DecodeResult<f32> { self.read_f64().map(|x| x as f32) }
+
+    fn read_f64(&mut self) -> DecodeResult<f64> {
+        debug!("read_f64");
+        match self.pop() {
+            I64(f) => Ok(f as f64),
+            U64(f) => Ok(f as f64),
+            F64(f) => Ok(f),
+            value => Err(ExpectedError("Number".to_string(), format!("{}", value)))
+        }
+    }
+
+    fn read_bool(&mut self) -> DecodeResult<bool> {
+        debug!("read_bool");
+        expect!(self.pop(), Boolean)
+    }
+
+    fn read_char(&mut self) -> DecodeResult<char> {
+        let s = try!(self.read_str());
+        {
+            let mut it = s.as_slice().chars();
+            match (it.next(), it.next()) {
+                // exactly one character
+                (Some(c), None) => return Ok(c),
+                _ => ()
+            }
+        }
+        Err(ExpectedError("single character string".to_string(), format!("{}", s)))
+    }
+
+    fn read_str(&mut self) -> DecodeResult<String> {
+        debug!("read_str");
+        expect!(self.pop(), String)
+    }
+
+    fn read_enum<T>(&mut self,
+                    name: &str,
+                    f: |&mut Decoder| -> DecodeResult<T>) -> DecodeResult<T> {
+        debug!("read_enum({})", name);
+        f(self)
+    }
+
+    fn read_enum_variant<T>(&mut self,
+                            names: &[&str],
+                            f: |&mut Decoder, uint| -> DecodeResult<T>)
+                            -> DecodeResult<T> {
+        debug!("read_enum_variant(names={})", names);
+        let name = match self.pop() {
+            String(s) => s,
+            Map(mut pairs) => {
+                let n = match take_field(&mut pairs, "variant") {
+                    Some(String(s)) => s,
+                    Some(val) => {
+                        return Err(ExpectedError("String".to_string(), format!("{}", val)))
+                    }
+                    None => {
+                        return Err(MissingFieldError("variant".to_string()))
+                    }
+                };
+                match take_field(&mut pairs, "fields") {
+                    Some(Array(l)) => {
+                        for field in l.move_iter().rev() {
+                            self.stack.push(field);
+                        }
+                    },
+                    Some(val) => {
+                        return Err(ExpectedError("Array".to_string(), format!("{}", val)))
+                    }
+                    None => {
+                        return Err(MissingFieldError("fields".to_string()))
+                    }
+                }
+                n
+            }
+            value => {
+                return Err(ExpectedError("String or Map".to_string(), format!("{}", value)))
+            }
+        };
+        let idx = match names.iter()
+                             .position(|n| str::eq_slice(*n, name.as_slice())) {
+            Some(idx) => idx,
+            None => return Err(UnknownVariantError(name))
+        };
+        f(self, idx)
+    }
+
+    fn read_enum_variant_arg<T>(&mut self, idx: uint, f: |&mut Decoder| -> DecodeResult<T>)
+                                -> DecodeResult<T> {
+        debug!("read_enum_variant_arg(idx={})", idx);
+        f(self)
+    }
+
+    fn read_enum_struct_variant<T>(&mut self,
+                                   names: &[&str],
+                                   f: |&mut Decoder, uint| -> DecodeResult<T>)
+                                   -> DecodeResult<T> {
+        debug!("read_enum_struct_variant(names={})", names);
+        self.read_enum_variant(names, f)
+    }
+
+
+    fn read_enum_struct_variant_field<T>(&mut self,
+                                         name: &str,
+                                         idx: uint,
+                                         f: |&mut Decoder| -> DecodeResult<T>)
+                                         -> DecodeResult<T> {
+        debug!("read_enum_struct_variant_field(name={}, idx={})", name, idx);
+        self.read_enum_variant_arg(idx, f)
+    }
+
+    fn read_struct<T>(&mut self,
+                      name: &str,
+                      len: uint,
+                      f: |&mut Decoder| -> DecodeResult<T>)
+                      -> DecodeResult<T> {
+        debug!("read_struct(name={}, len={})", name, len);
+        let value = try!(f(self));
+        self.pop();
+        Ok(value)
+    }
+
+    fn read_struct_field<T>(&mut self,
+                            name: &str,
+                            idx: uint,
+                            f: |&mut Decoder| -> DecodeResult<T>)
+                            -> DecodeResult<T> {
+        debug!("read_struct_field(name={}, idx={})", name, idx);
+        let mut pairs = try!(expect!(self.pop(), Map));
+
+        let value = match take_field(&mut pairs, name) {
+            None => {
+                // Add a Null and try to parse it as an Option<_>
+                // to get None as a default value.
+                self.stack.push(Null);
+                match f(self) {
+                    Ok(x) => x,
+                    Err(_) => return Err(MissingFieldError(name.to_string())),
+                }
+            },
+            Some(value) => {
+                self.stack.push(value);
+                try!(f(self))
+            }
+        };
+        self.stack.push(Map(pairs));
+        Ok(value)
+    }
+
+    fn read_tuple<T>(&mut self, f: |&mut Decoder, uint| -> DecodeResult<T>) -> DecodeResult<T> {
+        debug!("read_tuple()");
+        self.read_seq(f)
+    }
+
+    fn read_tuple_arg<T>(&mut self,
+                         idx: uint,
+                         f: |&mut Decoder| -> DecodeResult<T>) -> DecodeResult<T> {
+        debug!("read_tuple_arg(idx={})", idx);
+        self.read_seq_elt(idx, f)
+    }
+
+    fn read_tuple_struct<T>(&mut self,
+                            name: &str,
+                            f: |&mut Decoder, uint| -> DecodeResult<T>)
+                            -> DecodeResult<T> {
+        debug!("read_tuple_struct(name={})", name);
+        self.read_tuple(f)
+    }
+
+    fn read_tuple_struct_arg<T>(&mut self,
+                                idx: uint,
+                                f: |&mut Decoder| -> DecodeResult<T>)
+                                -> DecodeResult<T> {
+        debug!("read_tuple_struct_arg(idx={})", idx);
+        self.read_tuple_arg(idx, f)
+    }
+
+    fn read_option<T>(&mut self, f: |&mut Decoder, bool| -> DecodeResult<T>) -> DecodeResult<T> {
+        debug!("read_option()");
+        match self.pop() {
+            Null | Undefined => f(self, false),
+            value => { self.stack.push(value); f(self, true) }
+        }
+    }
+
+    fn read_seq<T>(&mut self, f: |&mut Decoder, uint| -> DecodeResult<T>) -> DecodeResult<T> {
+        debug!("read_seq()");
+        let values = try!(expect!(self.pop(), Array));
+        let len = values.len();
+        for v in values.move_iter().rev() {
+            self.stack.push(v);
+        }
+        f(self, len)
+    }
+
+    fn read_seq_elt<T>(&mut self,
+                       idx: uint,
+                       f: |&mut Decoder| -> DecodeResult<T>) -> DecodeResult<T> {
+        debug!("read_seq_elt(idx={})", idx);
+        f(self)
+    }
+
+    fn read_map<T>(&mut self, f: |&mut Decoder, uint| -> DecodeResult<T>) -> DecodeResult<T> {
+        debug!("read_map()");
+        let pairs = try!(expect!(self.pop(), Map));
+        let len = pairs.len();
+        for (key, value) in pairs.move_iter().rev() {
+            self.stack.push(value);
+            self.stack.push(key);
+        }
+        f(self, len)
+    }
+
+    fn read_map_elt_key<T>(&mut self, idx: uint, f: |&mut Decoder| -> DecodeResult<T>)
+                           -> DecodeResult<T> {
+        debug!("read_map_elt_key(idx={})", idx);
+        f(self)
+    }
+
+    fn read_map_elt_val<T>(&mut self, idx: uint, f: |&mut Decoder| -> DecodeResult<T>)
+                           -> DecodeResult<T> {
+        debug!("read_map_elt_val(idx={})", idx);
+        f(self)
+    }
+
+    fn error(&mut self, err: &str) -> DecoderError {
+        ApplicationError(err.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {Encodable, Decodable};
+    use hex::{FromHex, ToHex};
+    use super::{Value, U64, I64, Bytes, String, Array, Map, Tag, Boolean, Null, Undefined,
+                Simple, F64, from_bytes, encode, decode, DecodeResult, InvalidHeader,
+                UnexpectedBreak, InvalidChunk, IntegerOverflow, NestingTooDeep, TrailingBytes,
+                ExpectedError, MissingFieldError, IoError};
+    use std::collections::HashMap;
+    use std::io::MemWriter;
+    use std::{f64, i64, io};
+
+    // Checks that `hex` decodes to `value` and that `value` encodes back to
+    // the same bytes.
+    fn check_vector(hex: &str, value: Value) {
+        check_decode(hex, value.clone());
+        assert_eq!(value.to_bytes().as_slice().to_hex(), hex.to_string());
+    }
+
+    fn check_decode(hex: &str, value: Value) {
+        let bytes = hex.from_hex().unwrap();
+        assert_eq!(from_bytes(bytes.as_slice()), Ok(value));
+    }
+
+    fn check_nan(hex: &str) {
+        let bytes = hex.from_hex().unwrap();
+        match from_bytes(bytes.as_slice()) {
+            Ok(F64(n)) => assert!(n.is_nan()),
+            other => fail!("`{}` decoded to {}, expected NaN", hex, other),
+        }
+    }
+
+    fn text(s: &str) -> Value {
+        String(s.to_string())
+    }
+
+    // The examples of RFC 7049, appendix A.
+
+    #[test]
+    fn test_integer_vectors() {
+        check_vector("00", U64(0));
+        check_vector("01", U64(1));
+        check_vector("0a", U64(10));
+        check_vector("17", U64(23));
+        check_vector("1818", U64(24));
+        check_vector("1819", U64(25));
+        check_vector("1864", U64(100));
+        check_vector("1903e8", U64(1000));
+        check_vector("1a000f4240", U64(1000000));
+        check_vector("1b000000e8d4a51000", U64(1000000000000));
+        check_vector("1bffffffffffffffff", U64(18446744073709551615));
+        check_vector("c249010000000000000000",
+                     Tag(2, box Bytes(vec![1, 0, 0, 0, 0, 0, 0, 0, 0])));
+        check_vector("c349010000000000000000",
+                     Tag(3, box Bytes(vec![1, 0, 0, 0, 0, 0, 0, 0, 0])));
+        check_vector("20", I64(-1));
+        check_vector("29", I64(-10));
+        check_vector("3863", I64(-100));
+        check_vector("3903e7", I64(-1000));
+        check_vector("3b7fffffffffffffff", I64(i64::MIN));
+
+        // -18446744073709551616 does not fit in an i64.
+        assert_eq!(from_bytes("3bffffffffffffffff".from_hex().unwrap().as_slice()),
+                   Err(IntegerOverflow));
+    }
+
+    #[test]
+    fn test_float_vectors() {
+        check_vector("f90000", F64(0.0));
+        check_vector("f98000", F64(-0.0));
+        check_vector("f93c00", F64(1.0));
+        check_vector("fb3ff199999999999a", F64(1.1));
+        check_vector("f93e00", F64(1.5));
+        check_vector("f97bff", F64(65504.0));
+        check_vector("fa47c35000", F64(100000.0));
+        check_vector("fa7f7fffff", F64(3.4028234663852886e+38));
+        check_vector("fb7e37e43c8800759c", F64(1.0e+300));
+        check_vector("f90001", F64(5.960464477539063e-8));
+        check_vector("f90400", F64(0.00006103515625));
+        check_vector("f9c400", F64(-4.0));
+        check_vector("fbc010666666666666", F64(-4.1));
+        check_vector("f97c00", F64(f64::INFINITY));
+        check_vector("f9fc00", F64(f64::NEG_INFINITY));
+        check_nan("f97e00");
+        assert_eq!(F64(f64::NAN).to_bytes().as_slice().to_hex(), "f97e00".to_string());
+
+        // Longer encodings of the same values are accepted.
+        check_decode("fa7f800000", F64(f64::INFINITY));
+        check_decode("faff800000", F64(f64::NEG_INFINITY));
+        check_decode("fb7ff0000000000000", F64(f64::INFINITY));
+        check_decode("fbfff0000000000000", F64(f64::NEG_INFINITY));
+        check_nan("fa7fc00000");
+        check_nan("fb7ff8000000000000");
+    }
+
+    #[test]
+    fn test_simple_vectors() {
+        check_vector("f4", Boolean(false));
+        check_vector("f5", Boolean(true));
+        check_vector("f6", Null);
+        check_vector("f7", Undefined);
+        check_vector("f0", Simple(16));
+        check_vector("f820", Simple(32));
+        check_vector("f8ff", Simple(255));
+
+        for n in range(20u8, 32) {
+            let mut m = MemWriter::new();
+            assert!(Simple(n).to_writer(&mut m as &mut io::Writer).is_err());
+            assert!(m.unwrap().is_empty());
+        }
+    }
+
+    #[test]
+    fn test_tag_vectors() {
+        check_vector("c074323031332d30332d32315432303a30343a30305a",
+                     Tag(0, box text("2013-03-21T20:04:00Z")));
+        check_vector("c11a514b67b0", Tag(1, box U64(1363896240)));
+        check_vector("c1fb41d452d9ec200000", Tag(1, box F64(1363896240.5)));
+        check_vector("d74401020304", Tag(23, box Bytes(vec![1, 2, 3, 4])));
+        check_vector("d818456449455446", Tag(24, box Bytes(vec![0x64, 0x49, 0x45, 0x54, 0x46])));
+        check_vector("d82076687474703a2f2f7777772e6578616d706c652e636f6d",
+                     Tag(32, box text("http://www.example.com")));
+    }
+
+    #[test]
+    fn test_string_vectors() {
+        check_vector("40", Bytes(vec![]));
+        check_vector("4401020304", Bytes(vec![1, 2, 3, 4]));
+        check_vector("60", text(""));
+        check_vector("6161", text("a"));
+        check_vector("6449455446", text("IETF"));
+        check_vector("62225c", text("\"\\"));
+        check_vector("62c3bc", text("ü"));
+        check_vector("63e6b0b4", text("水"));
+        check_vector("64f0908591", text("\U00010151"));
+    }
+
+    #[test]
+    fn test_array_map_vectors() {
+        check_vector("80", Array(vec![]));
+        check_vector("83010203", Array(vec![U64(1), U64(2), U64(3)]));
+        check_vector("8301820203820405",
+                     Array(vec![U64(1),
+                                Array(vec![U64(2), U64(3)]),
+                                Array(vec![U64(4), U64(5)])]));
+        let one_to_25 = Array(Vec::from_fn(25, |i| U64(i as u64 + 1)));
+        check_vector("98190102030405060708090a0b0c0d0e0f101112131415161718181819",
+                     one_to_25.clone());
+        check_vector("a0", Map(vec![]));
+        check_vector("a201020304", Map(vec![(U64(1), U64(2)), (U64(3), U64(4))]));
+        check_vector("a26161016162820203",
+                     Map(vec![(text("a"), U64(1)),
+                              (text("b"), Array(vec![U64(2), U64(3)]))]));
+        check_vector("826161a161626163",
+                     Array(vec![text("a"), Map(vec![(text("b"), text("c"))])]));
+        check_vector("a56161614161626142616361436164614461656145",
+                     Map(vec![(text("a"), text("A")), (text("b"), text("B")),
+                              (text("c"), text("C")), (text("d"), text("D")),
+                              (text("e"), text("E"))]));
+
+        check_decode("9f018202039f0405ffff",
+                     Array(vec![U64(1),
+                                Array(vec![U64(2), U64(3)]),
+                                Array(vec![U64(4), U64(5)])]));
+        check_decode("9f0102030405060708090a0b0c0d0e0f101112131415161718181819ff",
+                     one_to_25);
+    }
+
+    #[test]
+    fn test_indefinite_length_vectors() {
+        check_decode("5f42010243030405ff", Bytes(vec![1, 2, 3, 4, 5]));
+        check_decode("7f657374726561646d696e67ff", text("streaming"));
+        check_decode("9fff", Array(vec![]));
+        let nested = Array(vec![U64(1),
+                                Array(vec![U64(2), U64(3)]),
+                                Array(vec![U64(4), U64(5)])]);
+        check_decode("9f01820203820405ff", nested.clone());
+        check_decode("83018202039f0405ff", nested.clone());
+        check_decode("83019f0203ff820405", nested);
+        check_decode("bf61610161629f0203ffff",
+                     Map(vec![(text("a"), U64(1)),
+                              (text("b"), Array(vec![U64(2), U64(3)]))]));
+        check_decode("826161bf61626163ff",
+                     Array(vec![text("a"), Map(vec![(text("b"), text("c"))])]));
+        check_decode("bf6346756ef563416d7421ff",
+                     Map(vec![(text("Fun"), Boolean(true)), (text("Amt"), I64(-2))]));
+    }
+
+    #[test]
+    fn test_malformed_input() {
+        assert_eq!(from_bytes([0x1c]), Err(InvalidHeader(0x1c)));
+        assert_eq!(from_bytes([0xf8, 0x10]), Err(InvalidHeader(0xf8)));
+        assert_eq!(from_bytes([0xf8, 0x18]), Err(InvalidHeader(0xf8)));
+        assert_eq!(from_bytes([0xf8, 0x1f]), Err(InvalidHeader(0xf8)));
+        assert_eq!(from_bytes([0xff]), Err(UnexpectedBreak));
+        assert_eq!(from_bytes([0x82, 0x01, 0xff]), Err(UnexpectedBreak));
+        assert_eq!(from_bytes([0x5f, 0x61, 0x61, 0xff]), Err(InvalidChunk(0x61)));
+        assert_eq!(from_bytes([0x01, 0x02]), Err(TrailingBytes));
+    }
+
+    #[test]
+    fn test_nesting_depth() {
+        let mut ok = Vec::from_elem(100, 0x81u8);
+        ok.push(0xf6);
+        assert!(from_bytes(ok.as_slice()).is_ok());
+
+        for &head in [0x81u8, 0x9f, 0xa1, 0xbf, 0xc1].iter() {
+            let mut nested = Vec::from_elem(100000, head);
+            nested.push(0xf6);
+            assert_eq!(from_bytes(nested.as_slice()), Err(NestingTooDeep));
+        }
+    }
+
+    #[test]
+    fn test_malformed_lengths() {
+        // Lengths far beyond the end of the input must not be trusted for
+        // allocation.
+        let inputs: [&[u8], ..5] = [
+            &[0x5b, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff],
+            &[0x7b, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x61],
+            &[0x5f, 0x5b, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff],
+            &[0x9b, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01],
+            &[0xbb, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff],
+        ];
+        for input in inputs.iter() {
+            match from_bytes(*input) {
+                Err(IoError(io::EndOfFile, _)) => {}
+                other => fail!("unexpected result {}", other),
+            }
+        }
+    }
+
+    #[deriving(PartialEq, Encodable, Decodable, Show)]
+    enum Animal {
+        Dog,
+        Frog(String, int)
+    }
+
+    #[deriving(PartialEq, Encodable, Decodable, Show)]
+    struct Inner {
+        a: (),
+        b: uint,
+        c: Vec<String>,
+    }
+
+    #[deriving(PartialEq, Encodable, Decodable, Show)]
+    struct Outer {
+        inner: Vec<Inner>,
+        pet: Option<Animal>,
+        ratio: f64,
+        initial: char,
+    }
+
+    #[test]
+    fn test_encode_struct() {
+        let inner = Inner { a: (), b: 2, c: vec!["x".to_string()] };
+        // {"a": null, "b": 2, "c": ["x"]}
+        assert_eq!(encode(&inner).as_slice().to_hex(), "a36161f66162026163816178".to_string());
+    }
+
+    #[test]
+    fn test_encode_enum() {
+        assert_eq!(encode(&Dog).as_slice().to_hex(), "63446f67".to_string());
+        let frog = encode(&Frog("Henry".to_string(), -1));
+        assert_eq!(from_bytes(frog.as_slice()),
+                   Ok(Map(vec![(text("variant"), text("Frog")),
+                               (text("fields"), Array(vec![text("Henry"), I64(-1)]))])));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let outer = Outer {
+            inner: vec![Inner { a: (), b: 300, c: vec!["abc".to_string(), "".to_string()] }],
+            pet: Some(Frog("Henry".to_string(), 349)),
+            ratio: -0.25,
+            initial: 'é',
+        };
+        let decoded: Outer = decode(encode(&outer).as_slice()).unwrap();
+        assert_eq!(decoded, outer);
+
+        let none = Outer { pet: None, ..outer };
+        let decoded: Outer = decode(encode(&none).as_slice()).unwrap();
+        assert_eq!(decoded, none);
+
+        let mut map = HashMap::new();
+        map.insert(-1i, vec![1.5f32]);
+        map.insert(70000i, vec![]);
+        let decoded: HashMap<int, Vec<f32>> = decode(encode(&map).as_slice()).unwrap();
+        assert_eq!(decoded, map);
+    }
+
+    #[test]
+    fn test_decode_through_tags() {
+        // 1(1363896240)
+        let n: u64 = decode("c11a514b67b0".from_hex().unwrap().as_slice()).unwrap();
+        assert_eq!(n, 1363896240);
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        // {"a": null, "b": true, "c": []}
+        let bytes = "a36161f66162f5616380".from_hex().unwrap();
+        let res: DecodeResult<Inner> = decode(bytes.as_slice());
+        assert_eq!(res, Err(ExpectedError("Number".to_string(), "Boolean(true)".to_string())));
+
+        // {"a": null, "b": 1}
+        let bytes = "a26161f6616201".from_hex().unwrap();
+        let res: DecodeResult<Inner> = decode(bytes.as_slice());
+        assert_eq!(res, Err(MissingFieldError("c".to_string())));
+    }
+}
@@ -104,6 +104,44 @@ fn main() {
 }
 ```
 
+## Decoding from a stream
+
+`json::decode` parses the whole input into a `Json` value before decoding it. A
+`json::StreamingDecoder` decodes straight from the events of the streaming `Parser`
+instead, so large inputs can be decoded without holding them in memory as a whole.
+
+Lists and objects decoded through `read_seq` and `read_map`, such as a `Vec` or a
+`HashMap`, are the exception: `Decodable` asks for their length before their first
+element, so the parser events of all their elements are buffered first, taking
+memory in proportion to their size. Use `StreamingDecoder::each_element` to go
+through a long list without buffering it.
+
+```rust
+extern crate serialize;
+use serialize::json;
+
+#[deriving(Decodable)]
+pub struct Entry {
+    level: u8,
+    msg: String,
+}
+
+fn main() {
+    let src = r#"[{"level": 1, "msg": "started"}, {"level": 3, "msg": "failed"}]"#;
+
+    // Decode a single value with `json::decode_stream`...
+    let all: Vec<Entry> = json::decode_stream(src.chars()).unwrap();
+    assert_eq!(all.len(), 2);
+
+    // ...or go through a long list one element at a time.
+    let mut decoder = json::StreamingDecoder::new(src.chars());
+    decoder.each_element(|entry: Entry| {
+        println!("{}: {}", entry.level, entry.msg);
+        true
+    }).unwrap();
+}
+```
+
 ## Using the `ToJson` trait
 
 The examples above use the `ToJson` trait to generate the JSON string, which is required
@@ -2227,6 +2265,621 @@ impl ::Decoder<DecoderError> for Decoder {
     }
 }
 
+/// Shortcut function to decode a `T` straight from a stream of characters,
+/// without building an intermediate `Json` value.
+pub fn decode_stream<T: Iterator<char>, D: ::Decodable<StreamingDecoder<T>, DecoderError>>(
+    src: T) -> DecodeResult<D> {
+    let mut decoder = StreamingDecoder::new(src);
+    let value = try!(::Decodable::decode(&mut decoder));
+    try!(decoder.finish());
+    Ok(value)
+}
+
+// An event of the streaming parser, together with the key of the object
+// member it starts, if any.
+type Token = (Option<String>, JsonEvent);
+
+/// A structure to decode JSON to values in rust directly from a `Parser`,
+/// without building a `Json` value first.
+///
+/// Everything is decoded as the events come in, except for:
+///
+/// * struct fields that appear in the stream before the field being
+///   decoded, which are buffered until they are asked for, and
+/// * lists and objects decoded through `read_seq` and `read_map`, which
+///   includes every `Vec` and map. Their length has to be passed on before
+///   any element is decoded, so the events of the whole collection are
+///   buffered first, and decoding a collection takes memory in proportion
+///   to its size.
+///
+/// Use `each_element` to process a long list one element at a time without
+/// buffering it.
+pub struct StreamingDecoder<T> {
+    parser: Parser<T>,
+    // Events that were read ahead or are being replayed, the next one last.
+    peeked: Vec<Token>,
+    // One map per struct being decoded, holding the events of the members
+    // that were skipped over while looking for another field.
+    fields: Vec<TreeMap<String, Vec<Token>>>,
+}
+
+impl<T: Iterator<char>> StreamingDecoder<T> {
+    /// Creates a new decoder reading from the specified stream of characters.
+    pub fn new(src: T) -> StreamingDecoder<T> {
+        StreamingDecoder { parser: Parser::new(src), peeked: Vec::new(), fields: Vec::new() }
+    }
+
+    /// Decodes the next value as a list, passing each of its elements to `f`
+    /// as soon as it has been decoded. The list is never buffered, so it can
+    /// be arbitrarily long. Decoding stops early if `f` returns `false`.
+    pub fn each_element<D: ::Decodable<StreamingDecoder<T>, DecoderError>>(&mut self,
+                                                                           f: |D| -> bool)
+                                                                           -> DecodeResult<()> {
+        match try!(self.next_token()) {
+            (_, ListStart) => {}
+            tok => return self.expected("List", tok),
+        }
+        loop {
+            match try!(self.next_token()) {
+                (_, ListEnd) => return Ok(()),
+                tok => {
+                    self.peeked.push(tok);
+                    let value = try!(::Decodable::decode(self));
+                    if !f(value) { return Ok(()); }
+                }
+            }
+        }
+    }
+
+    /// Checks that the stream does not contain anything after the value
+    /// that has been decoded.
+    pub fn finish(&mut self) -> DecodeResult<()> {
+        if !self.peeked.is_empty() {
+            return self.syntax_error(TrailingCharacters);
+        }
+        match self.parser.next() {
+            None => Ok(()),
+            Some(Error(e)) => Err(ParseError(e)),
+            Some(_) => self.syntax_error(TrailingCharacters),
+        }
+    }
+
+    fn syntax_error<U>(&self, reason: ErrorCode) -> DecodeResult<U> {
+        Err(ParseError(SyntaxError(reason, self.parser.line, self.parser.col)))
+    }
+
+    fn next_token(&mut self) -> DecodeResult<Token> {
+        match self.peeked.pop() {
+            Some(tok) => return Ok(tok),
+            None => {}
+        }
+        match self.parser.next() {
+            Some(Error(e)) => Err(ParseError(e)),
+            Some(evt) => {
+                let key = match evt {
+                    ListEnd | ObjectEnd => None,
+                    _ => match self.parser.stack().top() {
+                        Some(Key(k)) => Some(k.to_string()),
+                        _ => None,
+                    }
+                };
+                Ok((key, evt))
+            }
+            None => self.syntax_error(EOFWhileParsingValue),
+        }
+    }
+
+    // Pushes a sequence of events back, so that they are read again in order.
+    fn replay(&mut self, tokens: Vec<Token>) {
+        for tok in tokens.move_iter().rev() {
+            self.peeked.push(tok);
+        }
+    }
+
+    // Returns the key of the next member of the current object, leaving its
+    // value to be read, or None once the end of the object has been read.
+    fn read_key(&mut self) -> DecodeResult<Option<String>> {
+        let (key, evt) = try!(self.next_token());
+        if evt == ObjectEnd {
+            return Ok(None);
+        }
+        let k = match key {
+            Some(ref k) => k.clone(),
+            None => return self.syntax_error(KeyMustBeAString),
+        };
+        self.peeked.push((key, evt));
+        Ok(Some(k))
+    }
+
+    // Reads a whole value, keeping its events so that it can be replayed.
+    fn record_value(&mut self) -> DecodeResult<Vec<Token>> {
+        let mut tokens = Vec::new();
+        let mut depth = 0u;
+        loop {
+            let tok = try!(self.next_token());
+            match tok {
+                (_, ListStart) | (_, ObjectStart) => depth += 1,
+                (_, ListEnd) | (_, ObjectEnd) => {
+                    if depth == 0 { return self.syntax_error(InvalidSyntax); }
+                    depth -= 1;
+                }
+                _ => {}
+            }
+            tokens.push(tok);
+            if depth == 0 { return Ok(tokens); }
+        }
+    }
+
+    // Reads a whole value and throws it away.
+    fn skip_value(&mut self) -> DecodeResult<()> {
+        let mut depth = 0u;
+        loop {
+            match try!(self.next_token()) {
+                (_, ListStart) | (_, ObjectStart) => depth += 1,
+                (_, ListEnd) | (_, ObjectEnd) => {
+                    if depth == 0 { return self.syntax_error(InvalidSyntax); }
+                    depth -= 1;
+                }
+                _ => {}
+            }
+            if depth == 0 { return Ok(()); }
+        }
+    }
+
+    // Reads the rest of the list or object whose start has just been read,
+    // including its end, and returns the events of its elements together with
+    // their number.
+    fn buffer_elements(&mut self) -> DecodeResult<(Vec<Token>, uint)> {
+        let mut tokens = Vec::new();
+        let mut len = 0u;
+        loop {
+            match try!(self.next_token()) {
+                (_, ListEnd) | (_, ObjectEnd) => return Ok((tokens, len)),
+                tok => {
+                    self.peeked.push(tok);
+                    let value = try!(self.record_value());
+                    tokens.push_all_move(value);
+                    len += 1;
+                }
+            }
+        }
+    }
+
+    // Builds a Json value out of the next value in the stream.
+    fn read_json(&mut self) -> DecodeResult<Json> {
+        let (_, evt) = try!(self.next_token());
+        match evt {
+            NullValue => Ok(Null),
+            I64Value(n) => Ok(I64(n)),
+            U64Value(n) => Ok(U64(n)),
+            F64Value(n) => Ok(F64(n)),
+            BooleanValue(b) => Ok(Boolean(b)),
+            StringValue(s) => Ok(String(s)),
+            ListStart => {
+                let mut values = Vec::new();
+                loop {
+                    match try!(self.next_token()) {
+                        (_, ListEnd) => return Ok(List(values)),
+                        tok => {
+                            self.peeked.push(tok);
+                            values.push(try!(self.read_json()));
+                        }
+                    }
+                }
+            }
+            ObjectStart => {
+                let mut values = TreeMap::new();
+                loop {
+                    match try!(self.read_key()) {
+                        Some(key) => {
+                            let value = try!(self.read_json());
+                            values.insert(key, value);
+                        }
+                        None => return Ok(Object(values)),
+                    }
+                }
+            }
+            _ => self.syntax_error(InvalidSyntax),
+        }
+    }
+
+    // Reports that the value starting with `tok` is not of the expected type,
+    // describing it the same way `Decoder` does.
+    fn expected<U>(&mut self, expected: &str, tok: Token) -> DecodeResult<U> {
+        self.peeked.push(tok);
+        let found = try!(self.read_json());
+        Err(ExpectedError(expected.to_string(), format!("{}", found)))
+    }
+}
+
+fn variant_index(names: &[&str], name: String) -> DecodeResult<uint> {
+    match names.iter().position(|n| str::eq_slice(*n, name.as_slice())) {
+        Some(idx) => Ok(idx),
+        None => Err(UnknownVariantError(name))
+    }
+}
+
+macro_rules! read_streaming_primitive {
+    ($name:ident, $ty:ty) => {
+        fn $name(&mut self) -> DecodeResult<$ty> {
+            match try!(self.next_token()) {
+                (_, I64Value(f)) => {
+                    match num::cast(f) {
+                        Some(f) => Ok(f),
+                        None => Err(ExpectedError("Number".to_string(), format!("{}", f))),
+                    }
+                }
+                (_, U64Value(f)) => {
+                    match num::cast(f) {
+                        Some(f) => Ok(f),
+                        None => Err(ExpectedError("Number".to_string(), format!("{}", f))),
+                    }
+                }
+                (_, F64Value(f)) => {
+                    match num::cast(f) {
+                        Some(f) => Ok(f),
+                        None => Err(ExpectedError("Number".to_string(), format!("{}", f))),
+                    }
+                }
+                (_, StringValue(s)) => {
+                    // Numeric map keys are strings, see `read_primitive`.
+                    match std::from_str::from_str(s.as_slice()) {
+                        Some(f) => Ok(f),
+                        None => Err(ExpectedError("Number".to_string(), s)),
+                    }
+                }
+                tok => self.expected("Number", tok)
+            }
+        }
+    }
+}
+
+impl<T: Iterator<char>> ::Decoder<DecoderError> for StreamingDecoder<T> {
+    fn read_nil(&mut self) -> DecodeResult<()> {
+        debug!("read_nil");
+        match try!(self.next_token()) {
+            (_, NullValue) => Ok(()),
+            tok => self.expected("Null", tok),
+        }
+    }
+
+    read_streaming_primitive!(read_uint, uint)
+    read_streaming_primitive!(read_u8, u8)
+    read_streaming_primitive!(read_u16, u16)
+    read_streaming_primitive!(read_u32, u32)
+    read_streaming_primitive!(read_u64, u64)
+    read_streaming_primitive!(read_int, int)
+    read_streaming_primitive!(read_i8, i8)
+    read_streaming_primitive!(read_i16, i16)
+    read_streaming_primitive!(read_i32, i32)
+    read_streaming_primitive!(read_i64, i64)
+
+    fn read_f32(&mut self) -> DecodeResult<f32> { self.read_f64().map(|x| x as f32) }
+
+    fn read_f64(&mut self) -> DecodeResult<f64> {
+        debug!("read_f64");
+        match try!(self.next_token()) {
+            (_, I64Value(f)) => Ok(f as f64),
+            (_, U64Value(f)) => Ok(f as f64),
+            (_, F64Value(f)) => Ok(f),
+            (_, StringValue(s)) => {
+                match std::from_str::from_str(s.as_slice()) {
+                    Some(f) => Ok(f),
+                    None => Err(ExpectedError("Number".to_string(), s)),
+                }
+            }
+            (_, NullValue) => Ok(f64::NAN),
+            tok => self.expected("Number", tok)
+        }
+    }
+
+    fn read_bool(&mut self) -> DecodeResult<bool> {
+        debug!("read_bool");
+        match try!(self.next_token()) {
+            (_, BooleanValue(b)) => Ok(b),
+            tok => self.expected("Boolean", tok),
+        }
+    }
+
+    fn read_char(&mut self) -> DecodeResult<char> {
+        let s = try!(self.read_str());
+        {
+            let mut it = s.as_slice().chars();
+            match (it.next(), it.next()) {
+                // exactly one character
+                (Some(c), None) => return Ok(c),
+                _ => ()
+            }
+        }
+        Err(ExpectedError("single character string".to_string(), format!("{}", s)))
+    }
+
+    fn read_str(&mut self) -> DecodeResult<String> {
+        debug!("read_str");
+        match try!(self.next_token()) {
+            (_, StringValue(s)) => Ok(s),
+            tok => self.expected("String", tok),
+        }
+    }
+
+    fn read_enum<R>(&mut self,
+                    name: &str,
+                    f: |&mut StreamingDecoder<T>| -> DecodeResult<R>) -> DecodeResult<R> {
+        debug!("read_enum({})", name);
+        f(self)
+    }
+
+    fn read_enum_variant<R>(&mut self,
+                            names: &[&str],
+                            f: |&mut StreamingDecoder<T>, uint| -> DecodeResult<R>)
+                            -> DecodeResult<R> {
+        debug!("read_enum_variant(names={})", names);
+        match try!(self.next_token()) {
+            (_, StringValue(s)) => {
+                let idx = try!(variant_index(names, s));
+                return f(self, idx);
+            }
+            (_, ObjectStart) => {}
+            tok => return self.expected("String or Object", tok),
+        }
+
+        // Look for the "variant" and "fields" members. If "fields" comes
+        // first it is kept aside until the variant is known.
+        let mut name = None;
+        let mut fields = None;
+        loop {
+            let key = match try!(self.read_key()) {
+                Some(key) => key,
+                None => {
+                    let missing = if name.is_none() { "variant" } else { "fields" };
+                    return Err(MissingFieldError(missing.to_string()));
+                }
+            };
+            if key.as_slice() == "variant" {
+                match try!(self.next_token()) {
+                    (_, StringValue(s)) => name = Some(s),
+                    tok => return self.expected("String", tok),
+                }
+                match fields.take() {
+                    Some(tokens) => {
+                        self.replay(tokens);
+                        break;
+                    }
+                    None => {}
+                }
+            } else if key.as_slice() == "fields" {
+                if name.is_some() { break; }
+                fields = Some(try!(self.record_value()));
+            } else {
+                try!(self.skip_value());
+            }
+        }
+
+        match try!(self.next_token()) {
+            (_, ListStart) => {}
+            tok => return self.expected("List", tok),
+        }
+        let idx = try!(variant_index(names, name.unwrap()));
+        let value = try!(f(self, idx));
+
+        // Skip whatever is left of the fields and of the object.
+        loop {
+            match try!(self.next_token()) {
+                (_, ListEnd) => break,
+                tok => {
+                    self.peeked.push(tok);
+                    try!(self.skip_value());
+                }
+            }
+        }
+        while try!(self.read_key()).is_some() {
+            try!(self.skip_value());
+        }
+        Ok(value)
+    }
+
+    fn read_enum_variant_arg<R>(&mut self,
+                                idx: uint,
+                                f: |&mut StreamingDecoder<T>| -> DecodeResult<R>)
+                                -> DecodeResult<R> {
+        debug!("read_enum_variant_arg(idx={})", idx);
+        f(self)
+    }
+
+    fn read_enum_struct_variant<R>(&mut self,
+                                   names: &[&str],
+                                   f: |&mut StreamingDecoder<T>, uint| -> DecodeResult<R>)
+                                   -> DecodeResult<R> {
+        debug!("read_enum_struct_variant(names={})", names);
+        self.read_enum_variant(names, f)
+    }
+
+    fn read_enum_struct_variant_field<R>(&mut self,
+                                         name: &str,
+                                         idx: uint,
+                                         f: |&mut StreamingDecoder<T>| -> DecodeResult<R>)
+                                         -> DecodeResult<R> {
+        debug!("read_enum_struct_variant_field(name={}, idx={})", name, idx);
+        self.read_enum_variant_arg(idx, f)
+    }
+
+    fn read_struct<R>(&mut self,
+                      name: &str,
+                      len: uint,
+                      f: |&mut StreamingDecoder<T>| -> DecodeResult<R>)
+                      -> DecodeResult<R> {
+        debug!("read_struct(name={}, len={})", name, len);
+        match try!(self.next_token()) {
+            (_, ObjectStart) => {}
+            tok => return self.expected("Object", tok),
+        }
+        self.fields.push(TreeMap::new());
+        let value = f(self);
+        self.fields.pop();
+        let value = try!(value);
+
+        // Skip the members that do not correspond to any field.
+        while try!(self.read_key()).is_some() {
+            try!(self.skip_value());
+        }
+        Ok(value)
+    }
+
+    fn read_struct_field<R>(&mut self,
+                            name: &str,
+                            idx: uint,
+                            f: |&mut StreamingDecoder<T>| -> DecodeResult<R>)
+                            -> DecodeResult<R> {
+        debug!("read_struct_field(name={}, idx={})", name, idx);
+        let buffered = match self.fields.mut_last() {
+            Some(fields) => fields.pop(&name.to_string()),
+            None => None,
+        };
+        match buffered {
+            Some(tokens) => {
+                self.replay(tokens);
+                return f(self);
+            }
+            None => {}
+        }
+
+        loop {
+            match try!(self.read_key()) {
+                Some(key) => {
+                    if key.as_slice() == name {
+                        return f(self);
+                    }
+                    let tokens = try!(self.record_value());
+                    match self.fields.mut_last() {
+                        Some(fields) => { fields.insert(key, tokens); }
+                        None => {}
+                    }
+                }
+                None => {
+                    // Leave the end of the object for the next field, and
+                    // try to decode a Null as an Option<_> to get None as a
+                    // default value.
+                    self.peeked.push((None, ObjectEnd));
+                    self.peeked.push((None, NullValue));
+                    return match f(self) {
+                        Ok(x) => Ok(x),
+                        Err(_) => Err(MissingFieldError(name.to_string())),
+                    };
+                }
+            }
+        }
+    }
+
+    fn read_tuple<R>(&mut self,
+                     f: |&mut StreamingDecoder<T>, uint| -> DecodeResult<R>)
+                     -> DecodeResult<R> {
+        debug!("read_tuple()");
+        self.read_seq(f)
+    }
+
+    fn read_tuple_arg<R>(&mut self,
+                         idx: uint,
+                         f: |&mut StreamingDecoder<T>| -> DecodeResult<R>)
+                         -> DecodeResult<R> {
+        debug!("read_tuple_arg(idx={})", idx);
+        self.read_seq_elt(idx, f)
+    }
+
+    fn read_tuple_struct<R>(&mut self,
+                            name: &str,
+                            f: |&mut StreamingDecoder<T>, uint| -> DecodeResult<R>)
+                            -> DecodeResult<R> {
+        debug!("read_tuple_struct(name={})", name);
+        self.read_tuple(f)
+    }
+
+    fn read_tuple_struct_arg<R>(&mut self,
+                                idx: uint,
+                                f: |&mut StreamingDecoder<T>| -> DecodeResult<R>)
+                                -> DecodeResult<R> {
+        debug!("read_tuple_struct_arg(idx={})", idx);
+        self.read_tuple_arg(idx, f)
+    }
+
+    fn read_option<R>(&mut self,
+                      f: |&mut StreamingDecoder<T>, bool| -> DecodeResult<R>)
+                      -> DecodeResult<R> {
+        debug!("read_option()");
+        match try!(self.next_token()) {
+            (_, NullValue) => f(self, false),
+            tok => {
+                self.peeked.push(tok);
+                f(self, true)
+            }
+        }
+    }
+
+    fn read_seq<R>(&mut self,
+                   f: |&mut StreamingDecoder<T>, uint| -> DecodeResult<R>)
+                   -> DecodeResult<R> {
+        debug!("read_seq()");
+        match try!(self.next_token()) {
+            (_, ListStart) => {}
+            tok => return self.expected("List", tok),
+        }
+        // The length has to be known before the first element is decoded,
+        // so the whole list is buffered; see the docs of `StreamingDecoder`.
+        let (tokens, len) = try!(self.buffer_elements());
+        self.replay(tokens);
+        f(self, len)
+    }
+
+    fn read_seq_elt<R>(&mut self,
+                       idx: uint,
+                       f: |&mut StreamingDecoder<T>| -> DecodeResult<R>)
+                       -> DecodeResult<R> {
+        debug!("read_seq_elt(idx={})", idx);
+        f(self)
+    }
+
+    fn read_map<R>(&mut self,
+                   f: |&mut StreamingDecoder<T>, uint| -> DecodeResult<R>)
+                   -> DecodeResult<R> {
+        debug!("read_map()");
+        match try!(self.next_token()) {
+            (_, ObjectStart) => {}
+            tok => return self.expected("Object", tok),
+        }
+        // As with `read_seq`, the whole object is buffered for its length.
+        let (tokens, len) = try!(self.buffer_elements());
+        self.replay(tokens);
+        f(self, len)
+    }
+
+    fn read_map_elt_key<R>(&mut self,
+                           idx: uint,
+                           f: |&mut StreamingDecoder<T>| -> DecodeResult<R>)
+                           -> DecodeResult<R> {
+        debug!("read_map_elt_key(idx={})", idx);
+        // The key is carried by the first event of the member's value.
+        let (key, evt) = try!(self.next_token());
+        let key = match key {
+            Some(key) => key,
+            None => return self.syntax_error(KeyMustBeAString),
+        };
+        self.peeked.push((None, evt));
+        self.peeked.push((None, StringValue(key)));
+        f(self)
+    }
+
+    fn read_map_elt_val<R>(&mut self,
+                           idx: uint,
+                           f: |&mut StreamingDecoder<T>| -> DecodeResult<R>)
+                           -> DecodeResult<R> {
+        debug!("read_map_elt_val(idx={})", idx);
+        f(self)
+    }
+
+    fn error(&mut self, err: &str) -> DecoderError {
+        ApplicationError(err.to_string())
+    }
+}
+
 /// A trait for converting values to JSON
 pub trait ToJson {
     /// Converts the value of `self` to an instance of JSON
@@ -2372,13 +3025,13 @@ mod tests {
     use super::{Encoder, Decoder, Error, Boolean, I64, U64, F64, List, String, Null,
                 PrettyEncoder, Object, Json, from_str, ParseError, ExpectedError,
                 MissingFieldError, UnknownVariantError, DecodeResult, DecoderError,
-                JsonEvent, Parser, StackElement,
+                JsonEvent, Parser, StackElement, StreamingDecoder, decode_stream,
                 ObjectStart, ObjectEnd, ListStart, ListEnd, BooleanValue, U64Value,
                 F64Value, StringValue, NullValue, SyntaxError, Key, Index, Stack,
                 InvalidSyntax, InvalidNumber, EOFWhileParsingObject, EOFWhileParsingList,
                 EOFWhileParsingValue, EOFWhileParsingString, KeyMustBeAString, ExpectedColon,
                 TrailingCharacters};
-    use std::{i64, u64, f32, f64, io};
+    use std::{i64, u64, f32, f64, io, str};
     use std::collections::TreeMap;
 
     #[deriving(Decodable, Eq, PartialEq, Show)]
@@ -3043,6 +3696,130 @@ mod tests {
                                 UnknownVariantError("C".to_string()));
     }
 
+    fn check_stream_err<T: Decodable<StreamingDecoder<str::Chars<'static>>, DecoderError>>(
+        to_parse: &'static str, expected: DecoderError) {
+        let res: DecodeResult<T> = decode_stream(to_parse.chars());
+        match res {
+            Ok(_) => fail!("`{}` parsed & decoded ok, expecting error `{}`",
+                              to_parse, expected),
+            Err(e) => assert_eq!(e, expected),
+        }
+    }
+    #[test]
+    fn test_stream_decode_struct() {
+        let s = "{
+            \"inner\": [
+                { \"a\": null, \"b\": 2, \"c\": [\"abc\", \"xyz\"] }
+            ]
+        }";
+
+        let v: Outer = decode_stream(s.chars()).unwrap();
+        assert_eq!(
+            v,
+            Outer {
+                inner: vec![
+                    Inner { a: (), b: 2, c: vec!["abc".to_string(), "xyz".to_string()] }
+                ]
+            }
+        );
+    }
+
+    #[test]
+    fn test_stream_decode_struct_out_of_order() {
+        let s = "{\"c\": [\"abc\"], \"extra\": {\"x\": [1, 2]}, \"b\": 2, \"a\": null}";
+        let v: Inner = decode_stream(s.chars()).unwrap();
+        assert_eq!(v, Inner { a: (), b: 2, c: vec!["abc".to_string()] });
+    }
+
+    #[test]
+    fn test_stream_decode_option() {
+        let obj: OptionData = decode_stream("{}".chars()).unwrap();
+        assert_eq!(obj, OptionData { opt: None });
+        let obj: OptionData = decode_stream("{ \"opt\": 10 }".chars()).unwrap();
+        assert_eq!(obj, OptionData { opt: Some(10u) });
+        let value: Option<String> = decode_stream("null".chars()).unwrap();
+        assert_eq!(value, None);
+    }
+
+    #[test]
+    fn test_stream_decode_struct_with_nan() {
+        let obj: FloatStruct = decode_stream("{\"f\":null,\"a\":[null,123]}".chars()).unwrap();
+        assert!(obj.f.is_nan());
+        assert!(obj.a.get(0).is_nan());
+        assert_eq!(obj.a.get(1), &123f64);
+    }
+
+    #[test]
+    fn test_stream_decode_enum() {
+        let value: Animal = decode_stream("\"Dog\"".chars()).unwrap();
+        assert_eq!(value, Dog);
+
+        let s = "{\"variant\":\"Frog\",\"fields\":[\"Henry\",349]}";
+        let value: Animal = decode_stream(s.chars()).unwrap();
+        assert_eq!(value, Frog("Henry".to_string(), 349));
+
+        let s = "{\"fields\":[\"Henry\",349],\"variant\":\"Frog\"}";
+        let value: Animal = decode_stream(s.chars()).unwrap();
+        assert_eq!(value, Frog("Henry".to_string(), 349));
+    }
+
+    #[test]
+    fn test_stream_decode_map() {
+        let s = "{\"a\": \"Dog\", \"b\": {\"variant\":\"Frog\",\
+                  \"fields\":[\"Henry\", 349]}}";
+        let mut map: TreeMap<String, Animal> = decode_stream(s.chars()).unwrap();
+
+        assert_eq!(map.pop(&"a".to_string()), Some(Dog));
+        assert_eq!(map.pop(&"b".to_string()), Some(Frog("Henry".to_string(), 349)));
+
+        let map: TreeMap<uint, bool> = decode_stream("{\"1\": true}".chars()).unwrap();
+        assert_eq!(map.find(&1u), Some(&true));
+    }
+
+    #[test]
+    fn test_stream_decode_errors() {
+        check_stream_err::<DecodeStruct>("[]",
+                                         ExpectedError("Object".to_string(), "[]".to_string()));
+        check_stream_err::<DecodeStruct>("{\"x\": true, \"y\": true, \"z\": \"\", \"w\": []}",
+                                         ExpectedError("Number".to_string(), "true".to_string()));
+        check_stream_err::<DecodeStruct>("{\"x\": 1, \"y\": true, \"z\": {}, \"w\": []}",
+                                         ExpectedError("String".to_string(), "{}".to_string()));
+        check_stream_err::<DecodeStruct>("{\"x\": 1, \"y\": true, \"z\": \"\"}",
+                                         MissingFieldError("w".to_string()));
+        check_stream_err::<DecodeEnum>("{}", MissingFieldError("variant".to_string()));
+        check_stream_err::<DecodeEnum>("{\"variant\": \"A\"}",
+                                       MissingFieldError("fields".to_string()));
+        check_stream_err::<DecodeEnum>("{\"variant\": \"C\", \"fields\": []}",
+                                       UnknownVariantError("C".to_string()));
+    }
+
+    #[test]
+    fn test_stream_decode_syntax_errors() {
+        check_stream_err::<Outer>("{\n  \"inner\":\n [",
+                                  ParseError(SyntaxError(EOFWhileParsingValue, 3, 4)));
+        check_stream_err::<Vec<uint>>("[1, 2] 3",
+                                      ParseError(SyntaxError(TrailingCharacters, 1, 8)));
+        check_stream_err::<Vec<uint>>("[1, 2",
+                                      ParseError(SyntaxError(EOFWhileParsingList, 1, 6)));
+    }
+
+    #[test]
+    fn test_stream_each_element() {
+        let mut decoder = StreamingDecoder::new("[[1], [2, 3], [], [4]]".chars());
+        let mut sums = Vec::new();
+        decoder.each_element(|v: Vec<uint>| {
+            sums.push(v.iter().fold(0, |a, &b| a + b));
+            true
+        }).unwrap();
+        decoder.finish().unwrap();
+        assert_eq!(sums, vec![1u, 5, 0, 4]);
+
+        let mut decoder = StreamingDecoder::new("[1, 2, 3]".chars());
+        let mut seen = Vec::new();
+        decoder.each_element(|v: uint| { seen.push(v); v < 2 }).unwrap();
+        assert_eq!(seen, vec![1u, 2]);
+    }
+
     #[test]
     fn test_find(){
         let json_value = from_str("{\"dog\" : \"cat\"}").unwrap();
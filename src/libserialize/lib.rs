@@ -39,5 +39,7 @@ mod serialize;
 mod collection_impls;
 
 pub mod base64;
+pub mod cbor;
 pub mod hex;
 pub mod json;
+pub mod msgpack;
@@ -0,0 +1,1236 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![forbid(non_camel_case_types)]
+#![allow(missing_doc)]
+
+/*!
+MessagePack serialization
+
+MessagePack (http://msgpack.org) is a compact binary format with the same data model as JSON,
+plus binary strings and application-defined extension types.
+
+Values are encoded with the smallest representation that can hold them. Rust types are mapped
+the same way `json` maps them:
+
+* integers, floats, booleans and strings are encoded as the corresponding MessagePack type
+* `()` and `None` are encoded as `nil`, `Some(x)` as `x`
+* structs are encoded as maps from field names to values
+* tuples, tuple structs and sequences are encoded as arrays
+* enum variants without arguments are encoded as their name, other variants as a map
+  `{"variant": name, "fields": [args...]}`
+
+```rust
+extern crate serialize;
+use serialize::msgpack;
+
+#[deriving(Decodable, Encodable, PartialEq, Show)]
+pub struct Point {
+    x: i32,
+    y: i32,
+}
+
+fn main() {
+    let point = Point { x: 1, y: -2 };
+    let bytes = msgpack::encode(&point);
+    assert_eq!(bytes, vec![0x82, 0xa1, 0x78, 0x01, 0xa1, 0x79, 0xfe]);
+
+    let decoded: Point = msgpack::decode(bytes.as_slice()).unwrap();
+    assert_eq!(decoded, point);
+}
+```
+*/
+
+use std::{cmp, io, num, str, u32};
+use std::io::{BufReader, MemWriter};
+use std::mem::transmute;
+
+use Encodable;
+
+/// Represents a MessagePack value
+#[deriving(Clone, PartialEq, Show)]
+pub enum Value {
+    Nil,
+    Boolean(bool),
+    U64(u64),
+    I64(i64),
+    F32(f32),
+    F64(f64),
+    String(String),
+    Binary(Vec<u8>),
+    Array(Vec<Value>),
+    Map(Vec<(Value, Value)>),
+    /// An application-defined extension type and its data.
+    Ext(i8, Vec<u8>),
+}
+
+#[deriving(Clone, PartialEq, Show)]
+pub enum DecoderError {
+    IoError(io::IoErrorKind, &'static str),
+    /// The reserved marker byte `0xc1` was found.
+    InvalidMarker(u8),
+    /// Arrays and maps are nested too deeply to decode.
+    NestingTooDeep,
+    NotUtf8,
+    TrailingBytes,
+    ExpectedError(String, String),
+    MissingFieldError(String),
+    UnknownVariantError(String),
+    ApplicationError(String)
+}
+
+pub type EncodeResult = io::IoResult<()>;
+pub type DecodeResult<T> = Result<T, DecoderError>;
+
+/// Shortcut function to encode a `T` into a MessagePack byte vector
+///
+/// # Failure
+///
+/// Fails if a string, byte string, array or map in `object` is longer than
+/// MessagePack can represent.
+pub fn encode<'a, T: Encodable<Encoder<'a>, io::IoError>>(object: &T) -> Vec<u8> {
+    Encoder::buffer_encode(object)
+}
+
+/// Shortcut function to decode a MessagePack byte slice into an object
+pub fn decode<T: ::Decodable<Decoder, DecoderError>>(bytes: &[u8]) -> DecodeResult<T> {
+    let value = try!(from_bytes(bytes));
+    let mut decoder = Decoder::new(value);
+    ::Decodable::decode(&mut decoder)
+}
+
+fn io_error_to_error(io: io::IoError) -> DecoderError {
+    IoError(io.kind, io.desc)
+}
+
+macro_rules! try_io(
+    ($e:expr) => (try!($e.map_err(io_error_to_error)))
+)
+
+fn write_uint(wr: &mut io::Writer, v: u64) -> EncodeResult {
+    if v < 0x80 {
+        wr.write_u8(v as u8)
+    } else if v <= 0xff {
+        try!(wr.write_u8(0xcc));
+        wr.write_u8(v as u8)
+    } else if v <= 0xffff {
+        try!(wr.write_u8(0xcd));
+        wr.write_be_u16(v as u16)
+    } else if v <= 0xffff_ffff {
+        try!(wr.write_u8(0xce));
+        wr.write_be_u32(v as u32)
+    } else {
+        try!(wr.write_u8(0xcf));
+        wr.write_be_u64(v)
+    }
+}
+
+fn write_int(wr: &mut io::Writer, v: i64) -> EncodeResult {
+    if v >= 0 {
+        write_uint(wr, v as u64)
+    } else if v >= -32 {
+        wr.write_i8(v as i8)
+    } else if v >= -0x80 {
+        try!(wr.write_u8(0xd0));
+        wr.write_i8(v as i8)
+    } else if v >= -0x8000 {
+        try!(wr.write_u8(0xd1));
+        wr.write_be_i16(v as i16)
+    } else if v >= -0x8000_0000 {
+        try!(wr.write_u8(0xd2));
+        wr.write_be_i32(v as i32)
+    } else {
+        try!(wr.write_u8(0xd3));
+        wr.write_be_i64(v)
+    }
+}
+
+// Writes a length using the fixed form when it fits in `fix_bits` bits and
+// `markers` (8, 16 and 32 bits wide) otherwise. The 8-bit form is skipped
+// when `markers[0]` is zero.
+fn write_len(wr: &mut io::Writer, len: uint, fix: u8, fix_bits: uint,
+             markers: [u8, ..3]) -> EncodeResult {
+    if len < (1 << fix_bits) && fix != 0 {
+        wr.write_u8(fix | len as u8)
+    } else if len <= 0xff && markers[0] != 0 {
+        try!(wr.write_u8(markers[0]));
+        wr.write_u8(len as u8)
+    } else if len <= 0xffff {
+        try!(wr.write_u8(markers[1]));
+        wr.write_be_u16(len as u16)
+    } else if len as u64 <= u32::MAX as u64 {
+        try!(wr.write_u8(markers[2]));
+        wr.write_be_u32(len as u32)
+    } else {
+        Err(io::IoError {
+            kind: io::InvalidInput,
+            desc: "length does not fit in 32 bits",
+            detail: None,
+        })
+    }
+}
+
+fn write_str(wr: &mut io::Writer, v: &str) -> EncodeResult {
+    try!(write_len(wr, v.len(), 0xa0, 5, [0xd9, 0xda, 0xdb]));
+    wr.write_str(v)
+}
+
+fn write_bin(wr: &mut io::Writer, v: &[u8]) -> EncodeResult {
+    try!(write_len(wr, v.len(), 0, 0, [0xc4, 0xc5, 0xc6]));
+    wr.write(v)
+}
+
+fn write_array_len(wr: &mut io::Writer, len: uint) -> EncodeResult {
+    write_len(wr, len, 0x90, 4, [0, 0xdc, 0xdd])
+}
+
+fn write_map_len(wr: &mut io::Writer, len: uint) -> EncodeResult {
+    write_len(wr, len, 0x80, 4, [0, 0xde, 0xdf])
+}
+
+fn write_ext(wr: &mut io::Writer, ty: i8, data: &[u8]) -> EncodeResult {
+    match data.len() {
+        1 => try!(wr.write_u8(0xd4)),
+        2 => try!(wr.write_u8(0xd5)),
+        4 => try!(wr.write_u8(0xd6)),
+        8 => try!(wr.write_u8(0xd7)),
+        16 => try!(wr.write_u8(0xd8)),
+        len => try!(write_len(wr, len, 0, 0, [0xc7, 0xc8, 0xc9])),
+    }
+    try!(wr.write_i8(ty));
+    wr.write(data)
+}
+
+/// A structure for implementing serialization to MessagePack.
+pub struct Encoder<'a> {
+    writer: &'a mut io::Writer+'a,
+}
+
+impl<'a> Encoder<'a> {
+    /// Creates a new MessagePack encoder whose output will be written to the
+    /// writer specified.
+    pub fn new(writer: &'a mut io::Writer) -> Encoder<'a> {
+        Encoder { writer: writer }
+    }
+
+    /// Encode the specified struct into a MessagePack [u8]
+    ///
+    /// # Failure
+    ///
+    /// Fails if a string, byte string, array or map in `object` is longer
+    /// than MessagePack can represent.
+    pub fn buffer_encode<T: Encodable<Encoder<'a>, io::IoError>>(object: &T) -> Vec<u8> {
+        let mut m = MemWriter::new();
+        // FIXME(14302) remove the transmute and unsafe block.
+        let result = unsafe {
+            let mut encoder = Encoder::new(&mut m as &mut io::Writer);
+            object.encode(transmute(&mut encoder))
+        };
+        // MemWriter never Errs, so this can only be an overlong value
+        match result {
+            Ok(()) => m.unwrap(),
+            Err(e) => fail!("cannot encode MessagePack value: {}", e),
+        }
+    }
+}
+
+impl<'a> ::Encoder<io::IoError> for Encoder<'a> {
+    fn emit_nil(&mut self) -> EncodeResult { self.writer.write_u8(0xc0) }
+
+    fn emit_uint(&mut self, v: uint) -> EncodeResult { write_uint(self.writer, v as u64) }
+    fn emit_u64(&mut self, v: u64) -> EncodeResult { write_uint(self.writer, v) }
+    fn emit_u32(&mut self, v: u32) -> EncodeResult { write_uint(self.writer, v as u64) }
+    fn emit_u16(&mut self, v: u16) -> EncodeResult { write_uint(self.writer, v as u64) }
+    fn emit_u8(&mut self, v: u8) -> EncodeResult  { write_uint(self.writer, v as u64) }
+
+    fn emit_int(&mut self, v: int) -> EncodeResult { write_int(self.writer, v as i64) }
+    fn emit_i64(&mut self, v: i64) -> EncodeResult { write_int(self.writer, v) }
+    fn emit_i32(&mut self, v: i32) -> EncodeResult { write_int(self.writer, v as i64) }
+    fn emit_i16(&mut self, v: i16) -> EncodeResult { write_int(self.writer, v as i64) }
+    fn emit_i8(&mut self, v: i8) -> EncodeResult  { write_int(self.writer, v as i64) }
+
+    fn emit_bool(&mut self, v: bool) -> EncodeResult {
+        self.writer.write_u8(if v { 0xc3 } else { 0xc2 })
+    }
+
+    fn emit_f64(&mut self, v: f64) -> EncodeResult {
+        try!(self.writer.write_u8(0xcb));
+        self.writer.write_be_f64(v)
+    }
+    fn emit_f32(&mut self, v: f32) -> EncodeResult {
+        try!(self.writer.write_u8(0xca));
+        self.writer.write_be_f32(v)
+    }
+
+    fn emit_char(&mut self, v: char) -> EncodeResult {
+        let mut buf = [0u8, .. 4];
+        let n = v.encode_utf8(buf).unwrap_or(0);
+        try!(write_len(self.writer, n, 0xa0, 5, [0xd9, 0xda, 0xdb]));
+        self.writer.write(buf.slice_to(n))
+    }
+    fn emit_str(&mut self, v: &str) -> EncodeResult { write_str(self.writer, v) }
+
+    fn emit_enum(&mut self, _name: &str, f: |&mut Encoder<'a>| -> EncodeResult) -> EncodeResult {
+        f(self)
+    }
+
+    fn emit_enum_variant(&mut self,
+                         name: &str,
+                         _id: uint,
+                         cnt: uint,
+                         f: |&mut Encoder<'a>| -> EncodeResult) -> EncodeResult {
+        // enums are encoded as strings or maps, as in json
+        // Bunny => "Bunny"
+        // Kangaroo(34,"William") => {"variant": "Kangaroo", "fields": [34,"William"]}
+        if cnt == 0 {
+            write_str(self.writer, name)
+        } else {
+            try!(write_map_len(self.writer, 2));
+            try!(write_str(self.writer, "variant"));
+            try!(write_str(self.writer, name));
+            try!(write_str(self.writer, "fields"));
+            try!(write_array_len(self.writer, cnt));
+            f(self)
+        }
+    }
+
+    fn emit_enum_variant_arg(&mut self,
+                             _idx: uint,
+                             f: |&mut Encoder<'a>| -> EncodeResult) -> EncodeResult {
+        f(self)
+    }
+
+    fn emit_enum_struct_variant(&mut self,
+                                name: &str,
+                                id: uint,
+                                cnt: uint,
+                                f: |&mut Encoder<'a>| -> EncodeResult) -> EncodeResult {
+        self.emit_enum_variant(name, id, cnt, f)
+    }
+
+    fn emit_enum_struct_variant_field(&mut self,
+                                      _: &str,
+                                      idx: uint,
+                                      f: |&mut Encoder<'a>| -> EncodeResult) -> EncodeResult {
+        self.emit_enum_variant_arg(idx, f)
+    }
+
+    fn emit_struct(&mut self,
+                   _: &str,
+                   len: uint,
+                   f: |&mut Encoder<'a>| -> EncodeResult) -> EncodeResult {
+        try!(write_map_len(self.writer, len));
+        f(self)
+    }
+
+    fn emit_struct_field(&mut self,
+                         name: &str,
+                         _idx: uint,
+                         f: |&mut Encoder<'a>| -> EncodeResult) -> EncodeResult {
+        try!(write_str(self.writer, name));
+        f(self)
+    }
+
+    fn emit_tuple(&mut self, len: uint, f: |&mut Encoder<'a>| -> EncodeResult) -> EncodeResult {
+        self.emit_seq(len, f)
+    }
+    fn emit_tuple_arg(&mut self,
+                      idx: uint,
+                      f: |&mut Encoder<'a>| -> EncodeResult) -> EncodeResult {
+        self.emit_seq_elt(idx, f)
+    }
+
+    fn emit_tuple_struct(&mut self,
+                         _name: &str,
+                         len: uint,
+                         f: |&mut Encoder<'a>| -> EncodeResult) -> EncodeResult {
+        self.emit_seq(len, f)
+    }
+    fn emit_tuple_struct_arg(&mut self,
+                             idx: uint,
+                             f: |&mut Encoder<'a>| -> EncodeResult) -> EncodeResult {
+        self.emit_seq_elt(idx, f)
+    }
+
+    fn emit_option(&mut self, f: |&mut Encoder<'a>| -> EncodeResult) -> EncodeResult {
+        f(self)
+    }
+    fn emit_option_none(&mut self) -> EncodeResult { self.emit_nil() }
+    fn emit_option_some(&mut self, f: |&mut Encoder<'a>| -> EncodeResult) -> EncodeResult {
+        f(self)
+    }
+
+    fn emit_seq(&mut self, len: uint, f: |&mut Encoder<'a>| -> EncodeResult) -> EncodeResult {
+        try!(write_array_len(self.writer, len));
+        f(self)
+    }
+
+    fn emit_seq_elt(&mut self, _idx: uint, f: |&mut Encoder<'a>| -> EncodeResult) -> EncodeResult {
+        f(self)
+    }
+
+    fn emit_map(&mut self, len: uint, f: |&mut Encoder<'a>| -> EncodeResult) -> EncodeResult {
+        try!(write_map_len(self.writer, len));
+        f(self)
+    }
+
+    fn emit_map_elt_key(&mut self,
+                        _idx: uint,
+                        f: |&mut Encoder<'a>| -> EncodeResult) -> EncodeResult {
+        f(self)
+    }
+
+    fn emit_map_elt_val(&mut self,
+                        _idx: uint,
+                        f: |&mut Encoder<'a>| -> EncodeResult) -> EncodeResult {
+        f(self)
+    }
+}
+
+impl Value {
+    /// Encodes a MessagePack value into a Writer
+    pub fn to_writer(&self, wr: &mut io::Writer) -> EncodeResult {
+        match *self {
+            Nil => wr.write_u8(0xc0),
+            Boolean(b) => wr.write_u8(if b { 0xc3 } else { 0xc2 }),
+            U64(n) => write_uint(wr, n),
+            I64(n) => write_int(wr, n),
+            F32(n) => {
+                try!(wr.write_u8(0xca));
+                wr.write_be_f32(n)
+            }
+            F64(n) => {
+                try!(wr.write_u8(0xcb));
+                wr.write_be_f64(n)
+            }
+            String(ref s) => write_str(wr, s.as_slice()),
+            Binary(ref b) => write_bin(wr, b.as_slice()),
+            Array(ref values) => {
+                try!(write_array_len(wr, values.len()));
+                for v in values.iter() {
+                    try!(v.to_writer(wr));
+                }
+                Ok(())
+            }
+            Map(ref pairs) => {
+                try!(write_map_len(wr, pairs.len()));
+                for &(ref k, ref v) in pairs.iter() {
+                    try!(k.to_writer(wr));
+                    try!(v.to_writer(wr));
+                }
+                Ok(())
+            }
+            Ext(ty, ref data) => write_ext(wr, ty, data.as_slice()),
+        }
+    }
+
+    /// Encodes a MessagePack value into a byte vector
+    ///
+    /// # Failure
+    ///
+    /// Fails if a string, byte string, array or map in the value is longer
+    /// than MessagePack can represent.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut m = MemWriter::new();
+        // MemWriter never Errs, so this can only be an overlong value
+        match self.to_writer(&mut m as &mut io::Writer) {
+            Ok(()) => m.unwrap(),
+            Err(e) => fail!("cannot encode MessagePack value: {}", e),
+        }
+    }
+
+    /// If the Value is a Map, returns the value associated with the string
+    /// key provided. Returns None otherwise.
+    pub fn find<'a>(&'a self, key: &str) -> Option<&'a Value> {
+        match *self {
+            Map(ref pairs) => {
+                pairs.iter().find(|&&(ref k, _)| k.as_string() == Some(key)).map(|&(_, ref v)| v)
+            }
+            _ => None
+        }
+    }
+
+    /// If the Value is a String, returns the associated str.
+    /// Returns None otherwise.
+    pub fn as_string<'a>(&'a self) -> Option<&'a str> {
+        match *self {
+            String(ref s) => Some(s.as_slice()),
+            _ => None
+        }
+    }
+
+    /// If the Value is an Array, returns the associated vector.
+    /// Returns None otherwise.
+    pub fn as_array<'a>(&'a self) -> Option<&'a Vec<Value>> {
+        match *self {
+            Array(ref values) => Some(values),
+            _ => None
+        }
+    }
+
+    /// If the Value is a Binary, returns the associated bytes.
+    /// Returns None otherwise.
+    pub fn as_binary<'a>(&'a self) -> Option<&'a [u8]> {
+        match *self {
+            Binary(ref b) => Some(b.as_slice()),
+            _ => None
+        }
+    }
+
+    /// If the Value is a number, returns it as an i64 if it fits.
+    /// Returns None otherwise.
+    pub fn as_i64(&self) -> Option<i64> {
+        match *self {
+            I64(n) => Some(n),
+            U64(n) => num::cast(n),
+            _ => None
+        }
+    }
+
+    /// If the Value is a number, returns it as an u64 if it fits.
+    /// Returns None otherwise.
+    pub fn as_u64(&self) -> Option<u64> {
+        match *self {
+            I64(n) => num::cast(n),
+            U64(n) => Some(n),
+            _ => None
+        }
+    }
+
+    /// If the Value is a number, returns it as an f64.
+    /// Returns None otherwise.
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self {
+            I64(n) => Some(n as f64),
+            U64(n) => Some(n as f64),
+            F32(n) => Some(n as f64),
+            F64(n) => Some(n),
+            _ => None
+        }
+    }
+
+    /// Returns true if the Value is Nil.
+    pub fn is_nil(&self) -> bool {
+        *self == Nil
+    }
+}
+
+fn read_len(rdr: &mut io::Reader, nbytes: uint) -> DecodeResult<uint> {
+    Ok(try_io!(rdr.read_be_uint_n(nbytes)) as uint)
+}
+
+// Input is read in pieces of at most this many bytes, so that a length
+// taken from the input cannot make the decoder allocate much more memory
+// than the input actually holds.
+static READ_CHUNK: uint = 64 * 1024;
+
+fn read_bytes(rdr: &mut io::Reader, len: uint) -> DecodeResult<Vec<u8>> {
+    let mut bytes = Vec::new();
+    let mut remaining = len;
+    while remaining > 0 {
+        let n = cmp::min(remaining, READ_CHUNK);
+        try_io!(rdr.push_at_least(n, n, &mut bytes));
+        remaining -= n;
+    }
+    Ok(bytes)
+}
+
+fn read_string(rdr: &mut io::Reader, len: uint) -> DecodeResult<Value> {
+    let bytes = try!(read_bytes(rdr, len));
+    match String::from_utf8(bytes) {
+        Ok(s) => Ok(String(s)),
+        Err(_) => Err(NotUtf8),
+    }
+}
+
+// The deepest nesting of arrays and maps that is decoded, so that hostile
+// input cannot overflow the stack.
+static MAX_DEPTH: uint = 512;
+
+fn read_array(rdr: &mut io::Reader, len: uint, depth: uint) -> DecodeResult<Value> {
+    if depth >= MAX_DEPTH {
+        return Err(NestingTooDeep);
+    }
+    // The length comes from the input, so don't trust it for preallocation.
+    let mut values = Vec::new();
+    for _ in range(0, len) {
+        values.push(try!(read_value(rdr, depth + 1)));
+    }
+    Ok(Array(values))
+}
+
+fn read_map(rdr: &mut io::Reader, len: uint, depth: uint) -> DecodeResult<Value> {
+    if depth >= MAX_DEPTH {
+        return Err(NestingTooDeep);
+    }
+    let mut pairs = Vec::new();
+    for _ in range(0, len) {
+        let key = try!(read_value(rdr, depth + 1));
+        let value = try!(read_value(rdr, depth + 1));
+        pairs.push((key, value));
+    }
+    Ok(Map(pairs))
+}
+
+fn read_ext(rdr: &mut io::Reader, len: uint) -> DecodeResult<Value> {
+    let ty = try_io!(rdr.read_i8());
+    let data = try!(read_bytes(rdr, len));
+    Ok(Ext(ty, data))
+}
+
+// Reads a value nested inside `depth` arrays and maps.
+fn read_value(rdr: &mut io::Reader, depth: uint) -> DecodeResult<Value> {
+    let marker = try_io!(rdr.read_u8());
+    match marker {
+        0x00 .. 0x7f => Ok(U64(marker as u64)),
+        0x80 .. 0x8f => read_map(rdr, (marker & 0x0f) as uint, depth),
+        0x90 .. 0x9f => read_array(rdr, (marker & 0x0f) as uint, depth),
+        0xa0 .. 0xbf => read_string(rdr, (marker & 0x1f) as uint),
+        0xc0 => Ok(Nil),
+        0xc2 => Ok(Boolean(false)),
+        0xc3 => Ok(Boolean(true)),
+        0xc4 .. 0xc6 => {
+            let len = try!(read_len(rdr, 1 << (marker - 0xc4) as uint));
+            Ok(Binary(try!(read_bytes(rdr, len))))
+        }
+        0xc7 .. 0xc9 => {
+            let len = try!(read_len(rdr, 1 << (marker - 0xc7) as uint));
+            read_ext(rdr, len)
+        }
+        0xca => Ok(F32(try_io!(rdr.read_be_f32()))),
+        0xcb => Ok(F64(try_io!(rdr.read_be_f64()))),
+        0xcc .. 0xcf => Ok(U64(try_io!(rdr.read_be_uint_n(1 << (marker - 0xcc) as uint)))),
+        0xd0 .. 0xd3 => Ok(I64(try_io!(rdr.read_be_int_n(1 << (marker - 0xd0) as uint)))),
+        0xd4 .. 0xd8 => read_ext(rdr, 1 << (marker - 0xd4) as uint),
+        0xd9 .. 0xdb => {
+            let len = try!(read_len(rdr, 1 << (marker - 0xd9) as uint));
+            read_string(rdr, len)
+        }
+        0xdc | 0xdd => {
+            let len = try!(read_len(rdr, 2 << (marker - 0xdc) as uint));
+            read_array(rdr, len, depth)
+        }
+        0xde | 0xdf => {
+            let len = try!(read_len(rdr, 2 << (marker - 0xde) as uint));
+            read_map(rdr, len, depth)
+        }
+        0xe0 .. 0xff => Ok(I64(marker as i8 as i64)),
+        _ => Err(InvalidMarker(marker)),
+    }
+}
+
+/// Decodes a single MessagePack value from an `&mut io::Reader`, leaving the
+/// reader positioned right after it.
+pub fn from_reader(rdr: &mut io::Reader) -> DecodeResult<Value> {
+    read_value(rdr, 0)
+}
+
+/// Decodes a MessagePack value from a byte slice, which must contain exactly
+/// one value.
+pub fn from_bytes(bytes: &[u8]) -> DecodeResult<Value> {
+    let mut rdr = BufReader::new(bytes);
+    let value = try!(from_reader(&mut rdr));
+    if !rdr.eof() {
+        return Err(TrailingBytes);
+    }
+    Ok(value)
+}
+
+/// A structure to decode MessagePack to values in rust.
+pub struct Decoder {
+    stack: Vec<Value>,
+}
+
+impl Decoder {
+    /// Creates a new decoder instance for decoding the specified value.
+    pub fn new(value: Value) -> Decoder {
+        Decoder { stack: vec![value] }
+    }
+}
+
+impl Decoder {
+    fn pop(&mut self) -> Value {
+        self.stack.pop().unwrap()
+    }
+}
+
+// Removes the member with the given string key from a map and returns its value.
+fn take_field(pairs: &mut Vec<(Value, Value)>, name: &str) -> Option<Value> {
+    let idx = pairs.iter().position(|&(ref k, _)| k.as_string() == Some(name));
+    match idx {
+        Some(idx) => pairs.remove(idx).map(|(_, v)| v),
+        None => None,
+    }
+}
+
+macro_rules! expect(
+    ($e:expr, Nil) => ({
+        match $e {
+            Nil => Ok(()),
+            other => Err(ExpectedError("Nil".to_string(),
+                                       format!("{}", other)))
+        }
+    });
+    ($e:expr, $t:ident) => ({
+        match $e {
+            $t(v) => Ok(v),
+            other => {
+                Err(ExpectedError(stringify!($t).to_string(),
+                                  format!("{}", other)))
+            }
+        }
+    })
+)
+
+macro_rules! read_primitive {
+    ($name:ident, $ty:ty) => {
+        fn $name(&mut self) -> DecodeResult<$ty> {
+            let value = self.pop();
+            let n = match value {
+                I64(n) => num::cast(n),
+                U64(n) => num::cast(n),
+                F32(n) => num::cast(n),
+                F64(n) => num::cast(n),
+                _ => None,
+            };
+            match n {
+                Some(n) => Ok(n),
+                None => Err(ExpectedError("Number".to_string(), format!("{}", value))),
+            }
+        }
+    }
+}
+
+impl ::Decoder<DecoderError> for Decoder {
+    fn read_nil(&mut self) -> DecodeResult<()> {
+        debug!("read_nil");
+        expect!(self.pop(), Nil)
+    }
+
+    read_primitive!(read_uint, uint)
+    read_primitive!(read_u8, u8)
+    read_primitive!(read_u16, u16)
+    read_primitive!(read_u32, u32)
+    read_primitive!(read_u64, u64)
+    read_primitive!(read_int, int)
+    read_primitive!(read_i8, i8)
+    read_primitive!(read_i16, i16)
+    read_primitive!(read_i32, i32)
+    read_primitive!(read_i64, i64)
+
+    fn read_f32(&mut self) -> DecodeResult<f32> { self.read_f64().map(|x| x as f32) }
+
+    fn read_f64(&mut self) -> DecodeResult<f64> {
+        debug!("read_f64");
+        match self.pop() {
+            I64(f) => Ok(f as f64),
+            U64(f) => Ok(f as f64),
+            F32(f) => Ok(f as f64),
+            F64(f) => Ok(f),
+            value => Err(ExpectedError("Number".to_string(), format!("{}", value)))
+        }
+    }
+
+    fn read_bool(&mut self) -> DecodeResult<bool> {
+        debug!("read_bool");
+        expect!(self.pop(), Boolean)
+    }
+
+    fn read_char(&mut self) -> DecodeResult<char> {
+        let s = try!(self.read_str());
+        {
+            let mut it = s.as_slice().chars();
+            match (it.next(), it.next()) {
+                // exactly one character
+                (Some(c), None) => return Ok(c),
+                _ => ()
+            }
+        }
+        Err(ExpectedError("single character string".to_string(), format!("{}", s)))
+    }
+
+    fn read_str(&mut self) -> DecodeResult<String> {
+        debug!("read_str");
+        expect!(self.pop(), String)
+    }
+
+    fn read_enum<T>(&mut self,
+                    name: &str,
+                    f: |&mut Decoder| -> DecodeResult<T>) -> DecodeResult<T> {
+        debug!("read_enum({})", name);
+        f(self)
+    }
+
+    fn read_enum_variant<T>(&mut self,
+                            names: &[&str],
+                            f: |&mut Decoder, uint| -> DecodeResult<T>)
+                            -> DecodeResult<T> {
+        debug!("read_enum_variant(names={})", names);
+        let name = match self.pop() {
+            String(s) => s,
+            Map(mut pairs) => {
+                let n = match take_field(&mut pairs, "variant") {
+                    Some(String(s)) => s,
+                    Some(val) => {
+                        return Err(ExpectedError("String".to_string(), format!("{}", val)))
+                    }
+                    None => {
+                        return Err(MissingFieldError("variant".to_string()))
+                    }
+                };
+                match take_field(&mut pairs, "fields") {
+                    Some(Array(l)) => {
+                        for field in l.move_iter().rev() {
+                            self.stack.push(field);
+                        }
+                    },
+                    Some(val) => {
+                        return Err(ExpectedError("Array".to_string(), format!("{}", val)))
+                    }
+                    None => {
+                        return Err(MissingFieldError("fields".to_string()))
+                    }
+                }
+                n
+            }
+            value => {
+                return Err(ExpectedError("String or Map".to_string(), format!("{}", value)))
+            }
+        };
+        let idx = match names.iter()
+                             .position(|n| str::eq_slice(*n, name.as_slice())) {
+            Some(idx) => idx,
+            None => return Err(UnknownVariantError(name))
+        };
+        f(self, idx)
+    }
+
+    fn read_enum_variant_arg<T>(&mut self, idx: uint, f: |&mut Decoder| -> DecodeResult<T>)
+                                -> DecodeResult<T> {
+        debug!("read_enum_variant_arg(idx={})", idx);
+        f(self)
+    }
+
+    fn read_enum_struct_variant<T>(&mut self,
+                                   names: &[&str],
+                                   f: |&mut Decoder, uint| -> DecodeResult<T>)
+                                   -> DecodeResult<T> {
+        debug!("read_enum_struct_variant(names={})", names);
+        self.read_enum_variant(names, f)
+    }
+
+
+    fn read_enum_struct_variant_field<T>(&mut self,
+                                         name: &str,
+                                         idx: uint,
+                                         f: |&mut Decoder| -> DecodeResult<T>)
+                                         -> DecodeResult<T> {
+        debug!("read_enum_struct_variant_field(name={}, idx={})", name, idx);
+        self.read_enum_variant_arg(idx, f)
+    }
+
+    fn read_struct<T>(&mut self,
+                      name: &str,
+                      len: uint,
+                      f: |&mut Decoder| -> DecodeResult<T>)
+                      -> DecodeResult<T> {
+        debug!("read_struct(name={}, len={})", name, len);
+        let value = try!(f(self));
+        self.pop();
+        Ok(value)
+    }
+
+    fn read_struct_field<T>(&mut self,
+                            name: &str,
+                            idx: uint,
+                            f: |&mut Decoder| -> DecodeResult<T>)
+                            -> DecodeResult<T> {
+        debug!("read_struct_field(name={}, idx={})", name, idx);
+        let mut pairs = try!(expect!(self.pop(), Map));
+
+        let value = match take_field(&mut pairs, name) {
+            None => {
+                // Add a Nil and try to parse it as an Option<_>
+                // to get None as a default value.
+                self.stack.push(Nil);
+                match f(self) {
+                    Ok(x) => x,
+                    Err(_) => return Err(MissingFieldError(name.to_string())),
+                }
+            },
+            Some(value) => {
+                self.stack.push(value);
+                try!(f(self))
+            }
+        };
+        self.stack.push(Map(pairs));
+        Ok(value)
+    }
+
+    fn read_tuple<T>(&mut self, f: |&mut Decoder, uint| -> DecodeResult<T>) -> DecodeResult<T> {
+        debug!("read_tuple()");
+        self.read_seq(f)
+    }
+
+    fn read_tuple_arg<T>(&mut self,
+                         idx: uint,
+                         f: |&mut Decoder| -> DecodeResult<T>) -> DecodeResult<T> {
+        debug!("read_tuple_arg(idx={})", idx);
+        self.read_seq_elt(idx, f)
+    }
+
+    fn read_tuple_struct<T>(&mut self,
+                            name: &str,
+                            f: |&mut Decoder, uint| -> DecodeResult<T>)
+                            -> DecodeResult<T> {
+        debug!("read_tuple_struct(name={})", name);
+        self.read_tuple(f)
+    }
+
+    fn read_tuple_struct_arg<T>(&mut self,
+                                idx: uint,
+                                f: |&mut Decoder| -> DecodeResult<T>)
+                                -> DecodeResult<T> {
+        debug!("read_tuple_struct_arg(idx={})", idx);
+        self.read_tuple_arg(idx, f)
+    }
+
+    fn read_option<T>(&mut self, f: |&mut Decoder, bool| -> DecodeResult<T>) -> DecodeResult<T> {
+        debug!("read_option()");
+        match self.pop() {
+            Nil => f(self, false),
+            value => { self.stack.push(value); f(self, true) }
+        }
+    }
+
+    fn read_seq<T>(&mut self, f: |&mut Decoder, uint| -> DecodeResult<T>) -> DecodeResult<T> {
+        debug!("read_seq()");
+        let values = try!(expect!(self.pop(), Array));
+        let len = values.len();
+        for v in values.move_iter().rev() {
+            self.stack.push(v);
+        }
+        f(self, len)
+    }
+
+    fn read_seq_elt<T>(&mut self,
+                       idx: uint,
+                       f: |&mut Decoder| -> DecodeResult<T>) -> DecodeResult<T> {
+        debug!("read_seq_elt(idx={})", idx);
+        f(self)
+    }
+
+    fn read_map<T>(&mut self, f: |&mut Decoder, uint| -> DecodeResult<T>) -> DecodeResult<T> {
+        debug!("read_map()");
+        let pairs = try!(expect!(self.pop(), Map));
+        let len = pairs.len();
+        for (key, value) in pairs.move_iter().rev() {
+            self.stack.push(value);
+            self.stack.push(key);
+        }
+        f(self, len)
+    }
+
+    fn read_map_elt_key<T>(&mut self, idx: uint, f: |&mut Decoder| -> DecodeResult<T>)
+                           -> DecodeResult<T> {
+        debug!("read_map_elt_key(idx={})", idx);
+        f(self)
+    }
+
+    fn read_map_elt_val<T>(&mut self, idx: uint, f: |&mut Decoder| -> DecodeResult<T>)
+                           -> DecodeResult<T> {
+        debug!("read_map_elt_val(idx={})", idx);
+        f(self)
+    }
+
+    fn error(&mut self, err: &str) -> DecoderError {
+        ApplicationError(err.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {Encodable, Decodable};
+    use hex::{FromHex, ToHex};
+    use super::{Value, Nil, Boolean, U64, I64, F32, F64, String, Binary, Array, Map, Ext,
+                from_bytes, encode, decode, DecodeResult, InvalidMarker, NestingTooDeep,
+                TrailingBytes, ExpectedError, MissingFieldError, IoError};
+    use std::collections::{HashMap, TreeMap};
+    use std::{f32, f64, i64, io};
+
+    fn check_vector(hex: &str, value: Value) {
+        let bytes = hex.from_hex().unwrap();
+        assert_eq!(from_bytes(bytes.as_slice()), Ok(value.clone()));
+        assert_eq!(value.to_bytes().as_slice().to_hex(), hex.to_string());
+    }
+
+    fn str_value(s: &str) -> Value {
+        String(s.to_string())
+    }
+
+    #[test]
+    fn test_nil_bool_vectors() {
+        check_vector("c0", Nil);
+        check_vector("c2", Boolean(false));
+        check_vector("c3", Boolean(true));
+    }
+
+    #[test]
+    fn test_int_vectors() {
+        check_vector("00", U64(0));
+        check_vector("7f", U64(127));
+        check_vector("cc80", U64(128));
+        check_vector("ccff", U64(255));
+        check_vector("cd0100", U64(256));
+        check_vector("cdffff", U64(65535));
+        check_vector("ce00010000", U64(65536));
+        check_vector("ceffffffff", U64(4294967295));
+        check_vector("cf0000000100000000", U64(4294967296));
+        check_vector("cfffffffffffffffff", U64(18446744073709551615));
+        check_vector("ff", I64(-1));
+        check_vector("e0", I64(-32));
+        check_vector("d0df", I64(-33));
+        check_vector("d080", I64(-128));
+        check_vector("d1ff7f", I64(-129));
+        check_vector("d18000", I64(-32768));
+        check_vector("d2ffff7fff", I64(-32769));
+        check_vector("d280000000", I64(-2147483648));
+        check_vector("d3ffffffff7fffffff", I64(-2147483649));
+        check_vector("d38000000000000000", I64(i64::MIN));
+
+        // Non-minimal encodings are accepted when decoding.
+        assert_eq!(from_bytes("cc01".from_hex().unwrap().as_slice()), Ok(U64(1)));
+        assert_eq!(from_bytes("d001".from_hex().unwrap().as_slice()), Ok(I64(1)));
+        assert_eq!(from_bytes("cf0000000000000001".from_hex().unwrap().as_slice()), Ok(U64(1)));
+    }
+
+    #[test]
+    fn test_float_vectors() {
+        check_vector("ca3fc00000", F32(1.5));
+        check_vector("ca7f800000", F32(f32::INFINITY));
+        check_vector("cb3ff199999999999a", F64(1.1));
+        check_vector("cbc010666666666666", F64(-4.1));
+        check_vector("cb7ff0000000000000", F64(f64::INFINITY));
+    }
+
+    #[test]
+    fn test_str_bin_vectors() {
+        check_vector("a0", str_value(""));
+        check_vector("a161", str_value("a"));
+        check_vector("a3e282ac", str_value("€"));
+        let s31 = "a".repeat(31);
+        check_vector(format!("bf{}", "61".repeat(31)).as_slice(), str_value(s31.as_slice()));
+        let s32 = "a".repeat(32);
+        check_vector(format!("d920{}", "61".repeat(32)).as_slice(), str_value(s32.as_slice()));
+        let s256 = "a".repeat(256);
+        check_vector(format!("da0100{}", "61".repeat(256)).as_slice(),
+                     str_value(s256.as_slice()));
+
+        check_vector("c400", Binary(vec![]));
+        check_vector("c40100", Binary(vec![0]));
+        check_vector("c403010203", Binary(vec![1, 2, 3]));
+        check_vector(format!("c50100{}", "ff".repeat(256)).as_slice(),
+                     Binary(Vec::from_elem(256, 0xffu8)));
+    }
+
+    #[test]
+    fn test_array_map_vectors() {
+        check_vector("90", Array(vec![]));
+        check_vector("93010203", Array(vec![U64(1), U64(2), U64(3)]));
+        check_vector("9291c0a0", Array(vec![Array(vec![Nil]), str_value("")]));
+        check_vector(format!("dc0010{}", "00".repeat(16)).as_slice(),
+                     Array(Vec::from_elem(16, U64(0))));
+
+        check_vector("80", Map(vec![]));
+        check_vector("81a16101", Map(vec![(str_value("a"), U64(1))]));
+        check_vector("820102c3c2", Map(vec![(U64(1), U64(2)), (Boolean(true), Boolean(false))]));
+        let pairs = Vec::from_fn(16, |i| (U64(i as u64), Nil));
+        let hex = range(0u, 16).map(|i| format!("{:02x}c0", i)).collect::<Vec<String>>().concat();
+        check_vector(format!("de0010{}", hex).as_slice(), Map(pairs));
+    }
+
+    #[test]
+    fn test_ext_vectors() {
+        check_vector("d40102", Ext(1, vec![2]));
+        check_vector("d5020304", Ext(2, vec![3, 4]));
+        check_vector("d6ff01020304", Ext(-1, vec![1, 2, 3, 4]));
+        check_vector("d7010000000000000000", Ext(1, Vec::from_elem(8, 0u8)));
+        check_vector("d80100000000000000000000000000000000", Ext(1, Vec::from_elem(16, 0u8)));
+        check_vector("c70001", Ext(1, vec![]));
+        check_vector("c7030a010203", Ext(10, vec![1, 2, 3]));
+    }
+
+    #[test]
+    fn test_invalid_input() {
+        assert_eq!(from_bytes([0xc1]), Err(InvalidMarker(0xc1)));
+        assert_eq!(from_bytes([0xc0, 0xc0]), Err(TrailingBytes));
+        match from_bytes([0x92, 0x01]) {
+            Err(IoError(io::EndOfFile, _)) => {}
+            other => fail!("unexpected result {}", other),
+        }
+    }
+
+    #[test]
+    fn test_malformed_lengths() {
+        // Lengths far beyond the end of the input must not be trusted for
+        // allocation.
+        let inputs: [&[u8], ..5] = [
+            &[0xc6, 0xff, 0xff, 0xff, 0xff],
+            &[0xdb, 0xff, 0xff, 0xff, 0xff, 0x61],
+            &[0xc9, 0xff, 0xff, 0xff, 0xff, 0x01],
+            &[0xdd, 0xff, 0xff, 0xff, 0xff],
+            &[0xdf, 0xff, 0xff, 0xff, 0xff, 0xc0],
+        ];
+        for input in inputs.iter() {
+            match from_bytes(*input) {
+                Err(IoError(io::EndOfFile, _)) => {}
+                other => fail!("unexpected result {}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_nesting_depth() {
+        let mut ok = Vec::from_elem(100, 0x91u8);
+        ok.push(0xc0);
+        assert!(from_bytes(ok.as_slice()).is_ok());
+
+        let mut arrays = Vec::from_elem(100000, 0x91u8);
+        arrays.push(0xc0);
+        assert_eq!(from_bytes(arrays.as_slice()), Err(NestingTooDeep));
+
+        let mut maps = Vec::new();
+        for _ in range(0u, 100000) {
+            maps.push_all([0x81u8, 0xc0]);
+        }
+        maps.push(0xc0);
+        assert_eq!(from_bytes(maps.as_slice()), Err(NestingTooDeep));
+    }
+
+    #[test]
+    #[cfg(target_word_size = "64")]
+    fn test_write_len_overflow() {
+        let mut w = io::MemWriter::new();
+        {
+            let wr = &mut w as &mut io::Writer;
+            assert!(super::write_len(wr, 1 << 32, 0, 0, [0xc4, 0xc5, 0xc6]).is_err());
+            assert!(super::write_len(wr, (1 << 32) - 1, 0, 0, [0xc4, 0xc5, 0xc6]).is_ok());
+        }
+        assert_eq!(w.unwrap(), vec![0xc6, 0xff, 0xff, 0xff, 0xff]);
+    }
+
+    #[deriving(PartialEq, Encodable, Decodable, Show)]
+    enum Animal {
+        Dog,
+        Frog(String, int)
+    }
+
+    #[deriving(PartialEq, Encodable, Decodable, Show)]
+    struct Inner {
+        a: (),
+        b: uint,
+        c: Vec<String>,
+    }
+
+    #[deriving(PartialEq, Encodable, Decodable, Show)]
+    struct Outer {
+        inner: Vec<Inner>,
+        pet: Option<Animal>,
+        ratio: f64,
+        initial: char,
+    }
+
+    #[test]
+    fn test_encode_struct() {
+        let inner = Inner { a: (), b: 2, c: vec!["x".to_string()] };
+        // {"a": nil, "b": 2, "c": ["x"]}
+        assert_eq!(encode(&inner).as_slice().to_hex(), "83a161c0a16202a16391a178".to_string());
+    }
+
+    #[test]
+    fn test_encode_enum() {
+        assert_eq!(encode(&Dog).as_slice().to_hex(), "a3446f67".to_string());
+        let frog = encode(&Frog("Henry".to_string(), -1));
+        assert_eq!(from_bytes(frog.as_slice()),
+                   Ok(Map(vec![(str_value("variant"), str_value("Frog")),
+                               (str_value("fields"),
+                                Array(vec![str_value("Henry"), I64(-1)]))])));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let outer = Outer {
+            inner: vec![Inner { a: (), b: 300, c: vec!["abc".to_string(), "".to_string()] }],
+            pet: Some(Frog("Henry".to_string(), 349)),
+            ratio: -0.25,
+            initial: 'é',
+        };
+        let bytes = encode(&outer);
+        let decoded: Outer = decode(bytes.as_slice()).unwrap();
+        assert_eq!(decoded, outer);
+
+        let none = Outer { pet: None, ..outer };
+        let decoded: Outer = decode(encode(&none).as_slice()).unwrap();
+        assert_eq!(decoded, none);
+
+        let mut map = HashMap::new();
+        map.insert(1u, "one".to_string());
+        map.insert(300u, "three hundred".to_string());
+        let decoded: HashMap<uint, String> = decode(encode(&map).as_slice()).unwrap();
+        assert_eq!(decoded, map);
+
+        let tuple = (1u8, -2i64, true, "x".to_string());
+        let decoded: (u8, i64, bool, String) = decode(encode(&tuple).as_slice()).unwrap();
+        assert_eq!(decoded, tuple);
+    }
+
+    #[deriving(PartialEq, Decodable, Show)]
+    struct OptionData {
+        opt: Option<uint>,
+    }
+
+    #[test]
+    fn test_decode_struct_out_of_order() {
+        // {"c": [], "b": 1, "a": nil}
+        let bytes = "83a16390a16201a161c0".from_hex().unwrap();
+        let inner: Inner = decode(bytes.as_slice()).unwrap();
+        assert_eq!(inner, Inner { a: (), b: 1, c: vec![] });
+    }
+
+    #[test]
+    fn test_decode_option() {
+        let obj: OptionData = decode([0x80]).unwrap();
+        assert_eq!(obj, OptionData { opt: None });
+        let obj: OptionData = decode([0x81, 0xa3, 0x6f, 0x70, 0x74, 0x0a]).unwrap();
+        assert_eq!(obj, OptionData { opt: Some(10) });
+
+        let mut m = TreeMap::new();
+        m.insert("x".to_string(), Some(1u));
+        m.insert("y".to_string(), None);
+        let decoded: TreeMap<String, Option<uint>> = decode(encode(&m).as_slice()).unwrap();
+        assert_eq!(decoded, m);
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        // {"a": nil, "b": true, "c": []}
+        let bytes = "83a161c0a162c3a16390".from_hex().unwrap();
+        let res: DecodeResult<Inner> = decode(bytes.as_slice());
+        assert_eq!(res, Err(ExpectedError("Number".to_string(), "Boolean(true)".to_string())));
+
+        // {"a": nil, "b": 1}
+        let bytes = "82a161c0a16201".from_hex().unwrap();
+        let res: DecodeResult<Inner> = decode(bytes.as_slice());
+        assert_eq!(res, Err(MissingFieldError("c".to_string())));
+
+        let res: DecodeResult<u8> = decode(encode(&300u).as_slice());
+        assert_eq!(res, Err(ExpectedError("Number".to_string(), "U64(300)".to_string())));
+    }
+}
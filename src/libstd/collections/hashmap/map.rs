@@ -21,7 +21,7 @@ use iter::{Iterator, FromIterator, Extendable};
 use iter;
 use mem::replace;
 use num;
-use ops::{Deref, DerefMut};
+use ops::Deref;
 use option::{Some, None, Option};
 use result::{Ok, Err};
 use ops::Index;
@@ -30,6 +30,7 @@ use super::table;
 use super::table::{
     Bucket,
     Empty,
+    EmptyBucket,
     Full,
     FullBucket,
     FullBucketImm,
@@ -328,6 +329,58 @@ fn search_hashed<K: Eq, V, M: Deref<RawTable<K, V>>>(table: M, hash: &SafeHash,
     search_hashed_generic(table, hash, |k_| *k == *k_)
 }
 
+/// Search for a key, yielding an entry for it whether or not it's present.
+/// This is the single probe shared by the whole entry API.
+fn search_entry_hashed<'a, K: Eq, V>(table: &'a mut RawTable<K, V>, hash: SafeHash, k: K)
+                                     -> Entry<'a, K, V> {
+    // Worst case, we'll find one empty bucket among `size + 1` buckets.
+    let size = table.size();
+    let mut probe = Bucket::new(table, &hash);
+    let ib = probe.index();
+
+    loop {
+        let bucket = match probe.peek() {
+            Empty(bucket) => {
+                // Found a hole!
+                return Vacant(VacantEntry {
+                    hash: hash,
+                    key: k,
+                    elem: NoElem(bucket),
+                });
+            },
+            Full(bucket) => bucket
+        };
+
+        if bucket.hash() == hash {
+            let is_eq = {
+                let (bucket_k, _) = bucket.read();
+                k == *bucket_k
+            };
+
+            if is_eq {
+                return Occupied(OccupiedEntry {
+                    elem: bucket,
+                });
+            }
+        }
+
+        let robin_ib = bucket.index() as int - bucket.distance() as int;
+
+        if (ib as int) < robin_ib {
+            // Found a luckier bucket than me. The new entry will have to
+            // steal its spot if it's ever filled in.
+            return Vacant(VacantEntry {
+                hash: hash,
+                key: k,
+                elem: NeqElem(bucket, robin_ib as uint),
+            });
+        }
+
+        probe = bucket.next();
+        assert!(probe.index() != ib + size + 1);
+    }
+}
+
 fn pop_internal<K, V>(starting_bucket: FullBucketMut<K, V>) -> V {
     let (empty, _k, retval) = starting_bucket.take();
     let mut gap = match empty.gap_peek() {
@@ -422,25 +475,6 @@ impl<K, V, M> SearchResult<K, V, M> {
     }
 }
 
-/// A newtyped mutable reference to the hashmap that allows e.g. Deref to be
-/// implemented without making changes to the visible interface of HashMap.
-/// Used internally because it's accepted by the search functions above.
-struct MapMutRef<'a, K: 'a, V: 'a, H: 'a> {
-    map_ref: &'a mut HashMap<K, V, H>
-}
-
-impl<'a, K, V, H> Deref<RawTable<K, V>> for MapMutRef<'a, K, V, H> {
-    fn deref(&self) -> &RawTable<K, V> {
-        &self.map_ref.table
-    }
-}
-
-impl<'a, K, V, H> DerefMut<RawTable<K, V>> for MapMutRef<'a, K, V, H> {
-    fn deref_mut(&mut self) -> &mut RawTable<K, V> {
-        &mut self.map_ref.table
-    }
-}
-
 impl<K: Eq + Hash<S>, V, S, H: Hasher<S>> HashMap<K, V, H> {
     fn make_hash<X: Hash<S>>(&self, x: &X) -> SafeHash {
         table::make_hash(&self.hasher, x)
@@ -843,13 +877,42 @@ impl<K: Eq + Hash<S>, V, S, H: Hasher<S>> HashMap<K, V, H> {
         }
     }
 
-    /// Inserts an element which has already been hashed, returning a reference
-    /// to that element inside the hashtable. This is more efficient that using
-    /// `insert`, since the key will not be rehashed.
-    fn insert_hashed(&mut self, hash: SafeHash, k: K, v: V) -> &mut V {
+    /// Gets the given key's corresponding entry in the map for in-place
+    /// manipulation. The key is hashed and probed for only once, however
+    /// the entry is used afterwards.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use std::collections::hashmap::{Occupied, Vacant};
+    ///
+    /// let mut counts = HashMap::new();
+    ///
+    /// for word in "the quick fox jumps over the lazy dog".words() {
+    ///     match counts.entry(word) {
+    ///         Occupied(mut entry) => { *entry.get_mut() += 1; }
+    ///         Vacant(entry) => { entry.set(1u); }
+    ///     }
+    /// }
+    ///
+    /// assert_eq!(counts["the"], 2);
+    /// assert_eq!(counts["fox"], 1);
+    ///
+    /// // Remove an entry through the same probe that found it.
+    /// match counts.entry("the") {
+    ///     Occupied(entry) => assert_eq!(entry.take(), 2),
+    ///     Vacant(_) => fail!()
+    /// }
+    /// assert!(!counts.contains_key(&"the"));
+    /// ```
+    pub fn entry<'a>(&'a mut self, key: K) -> Entry<'a, K, V> {
+        // Make room up front, since a vacant entry may be filled in later.
         let potential_new_size = self.table.size() + 1;
         self.make_some_room(potential_new_size);
-        self.insert_hashed_nocheck(hash, k, v)
+
+        let hash = self.make_hash(&key);
+        search_entry_hashed(&mut self.table, hash, key)
     }
 
     /// Return the value corresponding to the key in the map, or insert
@@ -868,7 +931,10 @@ impl<K: Eq + Hash<S>, V, S, H: Hasher<S>> HashMap<K, V, H> {
     /// assert_eq!(*map.find_or_insert("a", -2), 1);
     /// ```
     pub fn find_or_insert(&mut self, k: K, v: V) -> &mut V {
-        self.find_with_or_insert_with(k, v, |_k, _v, _a| (), |_k, a| a)
+        match self.entry(k) {
+            Occupied(entry) => entry.into_mut(),
+            Vacant(entry) => entry.set(v)
+        }
     }
 
     /// Return the value corresponding to the key in the map, or create,
@@ -888,7 +954,13 @@ impl<K: Eq + Hash<S>, V, S, H: Hasher<S>> HashMap<K, V, H> {
     /// ```
     pub fn find_or_insert_with<'a>(&'a mut self, k: K, f: |&K| -> V)
                                -> &'a mut V {
-        self.find_with_or_insert_with(k, (), |_k, _v, _a| (), |k, _a| f(k))
+        match self.entry(k) {
+            Occupied(entry) => entry.into_mut(),
+            Vacant(entry) => {
+                let v = f(&entry.key);
+                entry.set(v)
+            }
+        }
     }
 
     /// Insert a key-value pair into the map if the key is not already present.
@@ -914,11 +986,14 @@ impl<K: Eq + Hash<S>, V, S, H: Hasher<S>> HashMap<K, V, H> {
                                  v: V,
                                  f: |&K, &mut V|)
                                  -> &'a mut V {
-        let potential_new_size = self.table.size() + 1;
-        self.make_some_room(potential_new_size);
-
-        let hash = self.make_hash(&k);
-        self.insert_or_replace_with(hash, k, v, |kref, vref, _v| f(kref, vref))
+        match self.entry(k) {
+            Occupied(entry) => {
+                let (k_ref, v_ref) = entry.elem.into_mut_refs();
+                f(&*k_ref, &mut *v_ref);
+                v_ref
+            }
+            Vacant(entry) => entry.set(v)
+        }
     }
 
     /// Modify and return the value corresponding to the key in the map, or
@@ -972,18 +1047,77 @@ impl<K: Eq + Hash<S>, V, S, H: Hasher<S>> HashMap<K, V, H> {
                                            not_found: |&K, A| -> V)
                                           -> &'a mut V
     {
-        let hash = self.make_hash(&k);
-        let this = MapMutRef { map_ref: self };
-
-        match search_hashed(this, &hash, &k) {
-            FoundExisting(bucket) => {
-                let (_, v_ref) = bucket.into_mut_refs();
-                found(&k, v_ref, a);
+        match self.entry(k) {
+            Occupied(entry) => {
+                let (k_ref, v_ref) = entry.elem.into_mut_refs();
+                found(&*k_ref, &mut *v_ref, a);
                 v_ref
             }
-            TableRef(this) => {
-                let v = not_found(&k, a);
-                this.map_ref.insert_hashed(hash, k, v)
+            Vacant(entry) => {
+                let v = not_found(&entry.key, a);
+                entry.set(v)
+            }
+        }
+    }
+
+    /// Retains only the key-value pairs for which `f` returns `true`.
+    /// The others are removed in place, in a single pass over the table,
+    /// without resizing or rehashing anything.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    ///
+    /// let mut map: HashMap<int, int> = range(0i, 8).map(|x| (x, x * 10)).collect();
+    /// map.retain(|&k, v| {
+    ///     *v += 1;
+    ///     k % 2 == 0
+    /// });
+    ///
+    /// assert_eq!(map.len(), 4);
+    /// assert_eq!(map[4], 41);
+    /// assert!(!map.contains_key(&3));
+    /// ```
+    pub fn retain(&mut self, f: |&K, &mut V| -> bool) {
+        if self.table.size() == 0 {
+            return
+        }
+
+        // Removing an entry shifts the rest of its cluster back by one
+        // bucket. Starting at the head of a cluster ensures that never moves
+        // an entry we have already visited in front of us again.
+        let mut start = 0;
+        loop {
+            match Bucket::at_index(&self.table, start).peek() {
+                Empty(_) => break,
+                Full(full) => if full.distance() == 0 { break }
+            }
+            start += 1;
+        }
+
+        let cap = self.table.capacity();
+        let mut i = 0;
+        while i < cap {
+            let mut bucket = match Bucket::at_index(&mut self.table, start + i).peek() {
+                Empty(_) => {
+                    i += 1;
+                    continue
+                }
+                Full(bucket) => bucket
+            };
+
+            let keep = {
+                let (k, v) = bucket.read_mut();
+                f(&*k, v)
+            };
+
+            if keep {
+                i += 1;
+            } else {
+                // The next entry of the cluster, if any, has shifted into
+                // this bucket, so look at it again.
+                pop_internal(bucket);
             }
         }
     }
@@ -1242,6 +1376,33 @@ impl<K: Eq + Hash<S>, V, S, H: Hasher<S>> HashMap<K, V, H> {
             inner: self.table.move_iter().map(|(_, k, v)| (k, v))
         }
     }
+
+    /// Clears the map, returning all key-value pairs as an iterator. Keeps
+    /// the allocated memory for reuse. Any pairs left unconsumed when the
+    /// iterator is dropped are dropped along with it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    ///
+    /// let mut map = HashMap::new();
+    /// map.insert("a", 1i);
+    /// map.insert("b", 2);
+    ///
+    /// let mut pairs: Vec<(&str, int)> = map.drain().collect();
+    /// pairs.sort();
+    /// assert_eq!(pairs, vec![("a", 1), ("b", 2)]);
+    /// assert!(map.is_empty());
+    /// ```
+    pub fn drain(&mut self) -> Drain<K, V> {
+        // Like `clear`, keep the current capacity from now on.
+        self.resize_policy.reserve(self.table.size());
+
+        Drain {
+            inner: self.table.drain().map(|(_, k, v)| (k, v))
+        }
+    }
 }
 
 impl<K: Eq + Hash<S>, V: Clone, S, H: Hasher<S>> HashMap<K, V, H> {
@@ -1374,6 +1535,100 @@ impl<K, V> Iterator<(K, V)> for MoveEntries<K, V> {
     }
 }
 
+/// HashMap drain iterator
+pub struct Drain<'a, K: 'a, V: 'a> {
+    inner: iter::Map<'static, (SafeHash, K, V), (K, V), table::Drain<'a, K, V>>
+}
+
+impl<'a, K, V> Iterator<(K, V)> for Drain<'a, K, V> {
+    #[inline]
+    fn next(&mut self) -> Option<(K, V)> {
+        self.inner.next()
+    }
+    #[inline]
+    fn size_hint(&self) -> (uint, Option<uint>) {
+        self.inner.size_hint()
+    }
+}
+
+/// A view into a single occupied location in a HashMap
+pub struct OccupiedEntry<'a, K: 'a, V: 'a> {
+    elem: FullBucket<K, V, &'a mut RawTable<K, V>>,
+}
+
+/// A view into a single empty location in a HashMap
+pub struct VacantEntry<'a, K: 'a, V: 'a> {
+    hash: SafeHash,
+    key: K,
+    elem: VacantEntryState<K, V, &'a mut RawTable<K, V>>,
+}
+
+/// A view into a single location in a map, which may be vacant or occupied
+pub enum Entry<'a, K: 'a, V: 'a> {
+    /// An occupied Entry
+    Occupied(OccupiedEntry<'a, K, V>),
+    /// A vacant Entry
+    Vacant(VacantEntry<'a, K, V>),
+}
+
+/// Possible states of a VacantEntry
+enum VacantEntryState<K, V, M> {
+    /// The index is occupied, but the key to insert has precedence,
+    /// and will kick the current one out on insertion
+    NeqElem(FullBucket<K, V, M>, uint),
+    /// The index is genuinely vacant
+    NoElem(EmptyBucket<K, V, M>),
+}
+
+impl<'a, K, V> OccupiedEntry<'a, K, V> {
+    /// Gets a reference to the value in the entry
+    pub fn get(&self) -> &V {
+        let (_, v) = self.elem.read();
+        v
+    }
+
+    /// Gets a mutable reference to the value in the entry
+    pub fn get_mut(&mut self) -> &mut V {
+        let (_, v) = self.elem.read_mut();
+        v
+    }
+
+    /// Converts the OccupiedEntry into a mutable reference to the value in the entry
+    /// with a lifetime bound to the map itself
+    pub fn into_mut(self) -> &'a mut V {
+        let (_, v) = self.elem.into_mut_refs();
+        v
+    }
+
+    /// Sets the value of the entry, and returns the entry's old value
+    pub fn set(&mut self, value: V) -> V {
+        replace(self.get_mut(), value)
+    }
+
+    /// Takes the value out of the entry, and returns it
+    pub fn take(self) -> V {
+        pop_internal(self.elem)
+    }
+}
+
+impl<'a, K, V> VacantEntry<'a, K, V> {
+    /// Sets the value of the entry with the VacantEntry's key,
+    /// and returns a mutable reference to it
+    pub fn set(self, value: V) -> &'a mut V {
+        let VacantEntry { hash: hash, key: key, elem: elem } = self;
+        match elem {
+            NeqElem(bucket, ib) => {
+                robin_hood(bucket, ib, hash, key, value)
+            }
+            NoElem(bucket) => {
+                let full = bucket.put(hash, key, value);
+                let (_, v) = full.into_mut_refs();
+                v
+            }
+        }
+    }
+}
+
 /// HashMap keys iterator
 pub type Keys<'a, K, V> =
     iter::Map<'static, (&'a K, &'a V), &'a K, Entries<'a, K, V>>;
@@ -1403,7 +1658,7 @@ impl<K: Eq + Hash<S>, V, S, H: Hasher<S> + Default> Extendable<(K, V)> for HashM
 mod test_map {
     use prelude::*;
 
-    use super::HashMap;
+    use super::{HashMap, Occupied, Vacant};
     use cmp::Equiv;
     use hash;
     use iter::{Iterator,range_inclusive,range_step_inclusive};
@@ -1714,6 +1969,152 @@ mod test_map {
         }
     }
 
+    #[test]
+    fn test_entry() {
+        let xs = [(1i, 10i), (2, 20), (3, 30), (4, 40), (5, 50), (6, 60)];
+        let mut map: HashMap<int, int> = xs.iter().map(|&x| x).collect();
+
+        // Existing key (insert)
+        match map.entry(1) {
+            Vacant(_) => unreachable!(),
+            Occupied(mut view) => {
+                assert_eq!(view.get(), &10);
+                assert_eq!(view.set(100), 10);
+            }
+        }
+        assert_eq!(map.find(&1).unwrap(), &100);
+        assert_eq!(map.len(), 6);
+
+        // Existing key (update)
+        match map.entry(2) {
+            Vacant(_) => unreachable!(),
+            Occupied(mut view) => {
+                let v = view.get_mut();
+                let new_v = (*v) * 10;
+                *v = new_v;
+            }
+        }
+        assert_eq!(map.find(&2).unwrap(), &200);
+        assert_eq!(map.len(), 6);
+
+        // Existing key (take)
+        match map.entry(3) {
+            Vacant(_) => unreachable!(),
+            Occupied(view) => {
+                assert_eq!(view.take(), 30);
+            }
+        }
+        assert_eq!(map.find(&3), None);
+        assert_eq!(map.len(), 5);
+
+        // Inexistent key (insert)
+        match map.entry(10) {
+            Occupied(_) => unreachable!(),
+            Vacant(view) => {
+                assert_eq!(*view.set(1000), 1000);
+            }
+        }
+        assert_eq!(map.find(&10).unwrap(), &1000);
+        assert_eq!(map.len(), 6);
+    }
+
+    #[test]
+    fn test_entry_take_and_set_many() {
+        let mut m = HashMap::new();
+
+        for i in range(0i, 1000) {
+            match m.entry(i) {
+                Occupied(_) => fail!("Key not yet present"),
+                Vacant(view) => { view.set(i * 2); }
+            }
+        }
+        assert_eq!(m.len(), 1000);
+
+        for i in range_step_inclusive(0i, 999, 3) {
+            match m.entry(i) {
+                Occupied(view) => assert_eq!(view.take(), i * 2),
+                Vacant(_) => fail!("Key should be present")
+            }
+        }
+
+        for i in range(0i, 1000) {
+            if i % 3 == 0 {
+                assert!(m.find(&i).is_none());
+            } else {
+                assert_eq!(m.find(&i), Some(&(i * 2)));
+            }
+        }
+    }
+
+    #[test]
+    fn test_retain() {
+        let mut m: HashMap<int, int> = range(0i, 1000).map(|x| (x, x * 10)).collect();
+
+        let mut visited = 0u;
+        m.retain(|&k, v| {
+            visited += 1;
+            *v += 1;
+            k % 4 != 0
+        });
+        assert_eq!(visited, 1000);
+        assert_eq!(m.len(), 750);
+
+        for i in range(0i, 1000) {
+            if i % 4 == 0 {
+                assert!(m.find(&i).is_none());
+            } else {
+                assert_eq!(m.find(&i), Some(&(i * 10 + 1)));
+            }
+        }
+
+        m.retain(|_, _| false);
+        assert!(m.is_empty());
+        m.retain(|_, _| fail!("Map should be empty"));
+    }
+
+    #[test]
+    fn test_drain() {
+        let mut m: HashMap<int, int> = range(0i, 100).map(|x| (x, x)).collect();
+        let cap = m.table.capacity();
+
+        {
+            let mut drain = m.drain();
+            assert_eq!(drain.size_hint(), (100, Some(100)));
+            let mut pairs: Vec<(int, int)> = drain.by_ref().take(60).collect();
+            assert_eq!(drain.size_hint(), (40, Some(40)));
+            pairs.extend(drain);
+            pairs.sort();
+            let expected: Vec<(int, int)> = range(0i, 100).map(|x| (x, x)).collect();
+            assert_eq!(pairs, expected);
+        }
+
+        assert!(m.is_empty());
+        assert_eq!(m.table.capacity(), cap);
+        assert!(m.insert(5, 5));
+        assert_eq!(m.find(&5), Some(&5));
+    }
+
+    #[test]
+    fn test_drain_drops() {
+        drop_vector.replace(Some(RefCell::new(Vec::from_elem(200, 0i))));
+
+        let mut hm = HashMap::new();
+        for i in range(0u, 100) {
+            hm.insert(Dropable::new(i), Dropable::new(i + 100));
+        }
+
+        {
+            let mut drain = hm.drain();
+            for _ in drain.by_ref().take(50) {}
+        }
+
+        assert!(hm.is_empty());
+        let v = drop_vector.get().unwrap();
+        for i in range(0u, 200) {
+            assert_eq!(v.borrow().as_slice()[i], 0);
+        }
+    }
+
     #[test]
     fn test_conflict_remove() {
         let mut m = HashMap::with_capacity(4);
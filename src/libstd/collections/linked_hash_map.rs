@@ -0,0 +1,856 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A hash map that remembers the order of its entries.
+//!
+//! A `LinkedHashMap` threads a doubly-linked list through the entries of
+//! a `HashMap`, so lookups, insertions and removals stay O(1) while
+//! iteration follows a well-defined order. The map is either kept in
+//! *insertion order*, where the front is the entry inserted longest ago,
+//! or in *access order*, where `get`, `get_mut` and re-inserting a key
+//! move the entry to the back, so that the front is always the
+//! least-recently-used entry.
+//!
+//! # Example
+//!
+//! ```rust
+//! use std::collections::LinkedHashMap;
+//!
+//! let mut map = LinkedHashMap::new();
+//! map.insert(2i, "b");
+//! map.insert(1, "a");
+//! map.insert(3, "c");
+//!
+//! let keys: Vec<int> = map.keys().map(|&k| k).collect();
+//! assert_eq!(keys, vec![2, 1, 3]);
+//!
+//! assert_eq!(map.pop_front(), Some((2, "b")));
+//! assert_eq!(map.pop_back(), Some((3, "c")));
+//! assert_eq!(map.len(), 1);
+//! ```
+
+use cmp::{PartialEq, Eq};
+use collections::{HashMap, Collection, Mutable, Map, MutableMap};
+use default::Default;
+use fmt;
+use hash::Hash;
+use iter;
+use iter::{Iterator, DoubleEndedIterator, FromIterator, Extendable};
+use kinds::marker;
+use mem;
+use ops::{Drop, Index};
+use option::{Some, None, Option};
+use boxed::Box;
+use ptr;
+use result::{Ok, Err};
+
+struct KeyRef<K> { k: *const K }
+
+struct LinkedEntry<K, V> {
+    next: *mut LinkedEntry<K, V>,
+    prev: *mut LinkedEntry<K, V>,
+    key: K,
+    value: V,
+}
+
+/// The order in which a `LinkedHashMap` keeps its entries.
+#[deriving(Clone, PartialEq, Eq, Show)]
+pub enum IterationOrder {
+    /// Entries stay in the order in which their keys were first inserted.
+    /// Updating the value of an existing key does not move it.
+    InsertionOrder,
+    /// Every `get`, `get_mut` or update of an entry moves it to the back,
+    /// so entries go from least- to most-recently used.
+    AccessOrder,
+}
+
+/// A hash map with a predictable iteration order.
+pub struct LinkedHashMap<K, V> {
+    map: HashMap<KeyRef<K>, Box<LinkedEntry<K, V>>>,
+    order: IterationOrder,
+    // Sentinel of the circular list: `head.next` is the front and
+    // `head.prev` is the back of the map.
+    head: *mut LinkedEntry<K, V>,
+}
+
+impl<S, K: Hash<S>> Hash<S> for KeyRef<K> {
+    fn hash(&self, state: &mut S) {
+        unsafe { (*self.k).hash(state) }
+    }
+}
+
+impl<K: PartialEq> PartialEq for KeyRef<K> {
+    fn eq(&self, other: &KeyRef<K>) -> bool {
+        unsafe{ (*self.k).eq(&*other.k) }
+    }
+}
+
+impl<K: Eq> Eq for KeyRef<K> {}
+
+impl<K, V> LinkedEntry<K, V> {
+    fn new(k: K, v: V) -> LinkedEntry<K, V> {
+        LinkedEntry {
+            key: k,
+            value: v,
+            next: ptr::mut_null(),
+            prev: ptr::mut_null(),
+        }
+    }
+}
+
+impl<K: Hash + Eq, V> LinkedHashMap<K, V> {
+    /// Create an empty `LinkedHashMap` kept in insertion order.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::LinkedHashMap;
+    /// let mut map: LinkedHashMap<&str, int> = LinkedHashMap::new();
+    /// ```
+    pub fn new() -> LinkedHashMap<K, V> {
+        LinkedHashMap::with_order(InsertionOrder)
+    }
+
+    /// Create an empty `LinkedHashMap` kept in the given order.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::LinkedHashMap;
+    /// use std::collections::linked_hash_map::AccessOrder;
+    ///
+    /// let mut map = LinkedHashMap::with_order(AccessOrder);
+    /// map.insert(1i, "a");
+    /// map.insert(2, "b");
+    ///
+    /// // Looking up `1` makes it the most-recently used entry.
+    /// map.get(&1);
+    /// assert_eq!(map.front(), Some((&2, &"b")));
+    /// ```
+    pub fn with_order(order: IterationOrder) -> LinkedHashMap<K, V> {
+        let map = LinkedHashMap {
+            map: HashMap::new(),
+            order: order,
+            head: unsafe{ mem::transmute(box mem::uninitialized::<LinkedEntry<K, V>>()) },
+        };
+        unsafe {
+            (*map.head).next = map.head;
+            (*map.head).prev = map.head;
+        }
+        return map;
+    }
+
+    /// Return the order in which the map keeps its entries.
+    pub fn order(&self) -> IterationOrder {
+        self.order
+    }
+
+    /// Return a reference to the value corresponding to the key. In
+    /// access order the entry also becomes the most-recently used one;
+    /// use `find` to look at a value without reordering the map.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::LinkedHashMap;
+    /// use std::collections::linked_hash_map::AccessOrder;
+    ///
+    /// let mut map = LinkedHashMap::with_order(AccessOrder);
+    /// map.insert(1i, "a");
+    /// map.insert(2, "b");
+    ///
+    /// assert_eq!(map.get(&1), Some(&"a"));
+    /// assert_eq!(map.back(), Some((&1, &"a")));
+    /// assert_eq!(map.get(&3), None);
+    /// ```
+    pub fn get<'a>(&'a mut self, k: &K) -> Option<&'a V> {
+        let node_ptr = match self.map.find_mut(&KeyRef{k: k}) {
+            None => return None,
+            Some(node) => {
+                let node_ptr: *mut LinkedEntry<K, V> = &mut **node;
+                node_ptr
+            }
+        };
+        self.touch(node_ptr);
+        Some(unsafe { &(*node_ptr).value })
+    }
+
+    /// Return a mutable reference to the value corresponding to the key.
+    /// In access order the entry also becomes the most-recently used one.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::LinkedHashMap;
+    ///
+    /// let mut map = LinkedHashMap::new();
+    /// map.insert(1i, 10i);
+    ///
+    /// match map.get_mut(&1) {
+    ///     Some(v) => *v += 1,
+    ///     None => (),
+    /// }
+    /// assert_eq!(map.find(&1), Some(&11));
+    /// ```
+    pub fn get_mut<'a>(&'a mut self, k: &K) -> Option<&'a mut V> {
+        let node_ptr = match self.map.find_mut(&KeyRef{k: k}) {
+            None => return None,
+            Some(node) => {
+                let node_ptr: *mut LinkedEntry<K, V> = &mut **node;
+                node_ptr
+            }
+        };
+        self.touch(node_ptr);
+        Some(unsafe { &mut (*node_ptr).value })
+    }
+
+    /// Return the entry at the front of the map: the oldest entry in
+    /// insertion order, or the least-recently used one in access order.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::LinkedHashMap;
+    ///
+    /// let mut map = LinkedHashMap::new();
+    /// assert_eq!(map.front(), None);
+    ///
+    /// map.insert(1i, "a");
+    /// map.insert(2, "b");
+    /// assert_eq!(map.front(), Some((&1, &"a")));
+    /// ```
+    pub fn front<'a>(&'a self) -> Option<(&'a K, &'a V)> {
+        if self.len() == 0 { return None }
+        unsafe {
+            let node = (*self.head).next;
+            Some((&(*node).key, &(*node).value))
+        }
+    }
+
+    /// Return the entry at the back of the map: the newest entry in
+    /// insertion order, or the most-recently used one in access order.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::LinkedHashMap;
+    ///
+    /// let mut map = LinkedHashMap::new();
+    /// assert_eq!(map.back(), None);
+    ///
+    /// map.insert(1i, "a");
+    /// map.insert(2, "b");
+    /// assert_eq!(map.back(), Some((&2, &"b")));
+    /// ```
+    pub fn back<'a>(&'a self) -> Option<(&'a K, &'a V)> {
+        if self.len() == 0 { return None }
+        unsafe {
+            let node = (*self.head).prev;
+            Some((&(*node).key, &(*node).value))
+        }
+    }
+
+    /// Remove and return the entry at the front of the map.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::LinkedHashMap;
+    ///
+    /// let mut map = LinkedHashMap::new();
+    /// map.insert(1i, "a");
+    /// map.insert(2, "b");
+    ///
+    /// assert_eq!(map.pop_front(), Some((1, "a")));
+    /// assert_eq!(map.pop_front(), Some((2, "b")));
+    /// assert_eq!(map.pop_front(), None);
+    /// ```
+    pub fn pop_front(&mut self) -> Option<(K, V)> {
+        if self.len() == 0 { return None }
+        let front = unsafe { (*self.head).next };
+        Some(self.remove_node(front))
+    }
+
+    /// Remove and return the entry at the back of the map.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::LinkedHashMap;
+    ///
+    /// let mut map = LinkedHashMap::new();
+    /// map.insert(1i, "a");
+    /// map.insert(2, "b");
+    ///
+    /// assert_eq!(map.pop_back(), Some((2, "b")));
+    /// assert_eq!(map.pop_back(), Some((1, "a")));
+    /// assert_eq!(map.pop_back(), None);
+    /// ```
+    pub fn pop_back(&mut self) -> Option<(K, V)> {
+        if self.len() == 0 { return None }
+        let back = unsafe { (*self.head).prev };
+        Some(self.remove_node(back))
+    }
+
+    /// An iterator visiting all key-value pairs from front to back.
+    /// Iterator element type is `(&'a K, &'a V)`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::LinkedHashMap;
+    ///
+    /// let mut map = LinkedHashMap::new();
+    /// map.insert("a", 1i);
+    /// map.insert("b", 2);
+    ///
+    /// for (key, val) in map.iter() {
+    ///     println!("key: {} val: {}", key, val);
+    /// }
+    /// ```
+    pub fn iter<'a>(&'a self) -> Entries<'a, K, V> {
+        Entries {
+            head: unsafe { (*self.head).next as *const LinkedEntry<K, V> },
+            tail: unsafe { (*self.head).prev as *const LinkedEntry<K, V> },
+            remaining: self.len(),
+            marker: marker::ContravariantLifetime,
+        }
+    }
+
+    /// An iterator visiting all key-value pairs from front to back, with
+    /// mutable references to the values. Iterating does not reorder the
+    /// map. Iterator element type is `(&'a K, &'a mut V)`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::LinkedHashMap;
+    ///
+    /// let mut map = LinkedHashMap::new();
+    /// map.insert("a", 1i);
+    /// map.insert("b", 2);
+    ///
+    /// for (_, val) in map.mut_iter() {
+    ///     *val *= 2;
+    /// }
+    /// assert_eq!(map.find(&"b"), Some(&4));
+    /// ```
+    pub fn mut_iter<'a>(&'a mut self) -> MutEntries<'a, K, V> {
+        MutEntries {
+            head: unsafe { (*self.head).next },
+            tail: unsafe { (*self.head).prev },
+            remaining: self.len(),
+            marker: marker::ContravariantLifetime,
+        }
+    }
+
+    /// Creates a consuming iterator that moves each key-value pair out of
+    /// the map from front to back.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::LinkedHashMap;
+    ///
+    /// let mut map = LinkedHashMap::new();
+    /// map.insert("a", 1i);
+    /// map.insert("b", 2);
+    ///
+    /// let vec: Vec<(&str, int)> = map.move_iter().collect();
+    /// assert_eq!(vec, vec![("a", 1), ("b", 2)]);
+    /// ```
+    pub fn move_iter(self) -> MoveEntries<K, V> {
+        MoveEntries { map: self }
+    }
+
+    /// An iterator visiting all keys from front to back.
+    /// Iterator element type is `&'a K`.
+    pub fn keys<'a>(&'a self) -> Keys<'a, K, V> {
+        self.iter().map(|(k, _v)| k)
+    }
+
+    /// An iterator visiting all values from front to back.
+    /// Iterator element type is `&'a V`.
+    pub fn values<'a>(&'a self) -> Values<'a, K, V> {
+        self.iter().map(|(_k, v)| v)
+    }
+
+    #[inline]
+    fn touch(&mut self, node: *mut LinkedEntry<K, V>) {
+        if self.order == AccessOrder {
+            self.detach(node);
+            self.attach(node);
+        }
+    }
+
+    fn remove_node(&mut self, node: *mut LinkedEntry<K, V>) -> (K, V) {
+        self.detach(node);
+        let node = self.map.pop(&KeyRef{k: unsafe { &(*node).key }}).unwrap();
+        let box internal_node = node;
+        let LinkedEntry { next: _, prev: _, key: k, value: v } = internal_node;
+        (k, v)
+    }
+
+    #[inline]
+    fn detach(&mut self, node: *mut LinkedEntry<K, V>) {
+        unsafe {
+            (*(*node).prev).next = (*node).next;
+            (*(*node).next).prev = (*node).prev;
+        }
+    }
+
+    #[inline]
+    fn attach(&mut self, node: *mut LinkedEntry<K, V>) {
+        unsafe {
+            (*node).next = self.head;
+            (*node).prev = (*self.head).prev;
+            (*self.head).prev = node;
+            (*(*node).prev).next = node;
+        }
+    }
+}
+
+impl<K: Hash + Eq, V> Collection for LinkedHashMap<K, V> {
+    /// Return the number of entries in the map.
+    fn len(&self) -> uint {
+        self.map.len()
+    }
+}
+
+impl<K: Hash + Eq, V> Mutable for LinkedHashMap<K, V> {
+    /// Remove all entries from the map.
+    fn clear(&mut self) {
+        self.map.clear();
+        unsafe {
+            (*self.head).next = self.head;
+            (*self.head).prev = self.head;
+        }
+    }
+}
+
+impl<K: Hash + Eq, V> Map<K, V> for LinkedHashMap<K, V> {
+    /// Return a reference to the value corresponding to the key, without
+    /// changing the order of the map.
+    fn find<'a>(&'a self, k: &K) -> Option<&'a V> {
+        match self.map.find(&KeyRef{k: k}) {
+            None => None,
+            Some(node) => Some(&node.value),
+        }
+    }
+}
+
+impl<K: Hash + Eq, V> MutableMap<K, V> for LinkedHashMap<K, V> {
+    /// Return a mutable reference to the value corresponding to the key,
+    /// without changing the order of the map.
+    fn find_mut<'a>(&'a mut self, k: &K) -> Option<&'a mut V> {
+        match self.map.find_mut(&KeyRef{k: k}) {
+            None => None,
+            Some(node) => Some(&mut node.value),
+        }
+    }
+
+    /// Insert a key-value pair, returning the previous value of the key if
+    /// there was one. New keys go to the back of the map; an existing key
+    /// keeps its position in insertion order and moves to the back in
+    /// access order.
+    fn swap(&mut self, k: K, v: V) -> Option<V> {
+        let (node_ptr, node_opt, old_val) = match self.map.find_mut(&KeyRef{k: &k}) {
+            Some(node) => {
+                let old_val = mem::replace(&mut node.value, v);
+                let node_ptr: *mut LinkedEntry<K, V> = &mut **node;
+                (node_ptr, None, Some(old_val))
+            }
+            None => {
+                let mut node = box LinkedEntry::new(k, v);
+                let node_ptr: *mut LinkedEntry<K, V> = &mut *node;
+                (node_ptr, Some(node), None)
+            }
+        };
+        match node_opt {
+            None => self.touch(node_ptr),
+            Some(node) => {
+                let keyref = unsafe { &(*node_ptr).key };
+                self.map.swap(KeyRef{k: keyref}, node);
+                self.attach(node_ptr);
+            }
+        }
+        old_val
+    }
+
+    /// Remove a key from the map, returning its value if it was present.
+    fn pop(&mut self, k: &K) -> Option<V> {
+        match self.map.pop(&KeyRef{k: k}) {
+            None => None,
+            Some(mut node) => {
+                let node_ptr: *mut LinkedEntry<K, V> = &mut *node;
+                self.detach(node_ptr);
+                let box internal_node = node;
+                Some(internal_node.value)
+            }
+        }
+    }
+}
+
+impl<K: Hash + Eq, V> Default for LinkedHashMap<K, V> {
+    fn default() -> LinkedHashMap<K, V> {
+        LinkedHashMap::new()
+    }
+}
+
+impl<K: Hash + Eq, V> Index<K, V> for LinkedHashMap<K, V> {
+    #[inline]
+    fn index<'a>(&'a self, index: &K) -> &'a V {
+        match self.find(index) {
+            Some(v) => v,
+            None => fail!("no entry found for key")
+        }
+    }
+}
+
+impl<K: Hash + Eq, V: PartialEq> PartialEq for LinkedHashMap<K, V> {
+    /// Two maps are equal when they hold the same entries in the same order.
+    fn eq(&self, other: &LinkedHashMap<K, V>) -> bool {
+        self.len() == other.len() &&
+            self.iter().zip(other.iter()).all(|((ka, va), (kb, vb))| *ka == *kb && *va == *vb)
+    }
+}
+
+impl<K: Hash + Eq, V: Eq> Eq for LinkedHashMap<K, V> {}
+
+impl<A: fmt::Show + Hash + Eq, B: fmt::Show> fmt::Show for LinkedHashMap<A, B> {
+    /// Return a string that lists the key-value pairs from front to back.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        try!(write!(f, "{{"));
+
+        for (i, (k, v)) in self.iter().enumerate() {
+            if i != 0 { try!(write!(f, ", ")); }
+            try!(write!(f, "{}: {}", *k, *v));
+        }
+
+        write!(f, "}}")
+    }
+}
+
+impl<K: Hash + Eq, V> FromIterator<(K, V)> for LinkedHashMap<K, V> {
+    fn from_iter<T: Iterator<(K, V)>>(iter: T) -> LinkedHashMap<K, V> {
+        let mut map = LinkedHashMap::new();
+        map.extend(iter);
+        map
+    }
+}
+
+impl<K: Hash + Eq, V> Extendable<(K, V)> for LinkedHashMap<K, V> {
+    fn extend<T: Iterator<(K, V)>>(&mut self, mut iter: T) {
+        for (k, v) in iter {
+            self.insert(k, v);
+        }
+    }
+}
+
+#[unsafe_destructor]
+impl<K, V> Drop for LinkedHashMap<K, V> {
+    fn drop(&mut self) {
+        unsafe {
+            let node: Box<LinkedEntry<K, V>> = mem::transmute(self.head);
+            // Prevent compiler from trying to drop the un-initialized field in the sigil node.
+            let box internal_node = node;
+            let LinkedEntry { next: _, prev: _, key: k, value: v } = internal_node;
+            mem::forget(k);
+            mem::forget(v);
+        }
+    }
+}
+
+/// LinkedHashMap iterator
+pub struct Entries<'a, K: 'a, V: 'a> {
+    head: *const LinkedEntry<K, V>,
+    tail: *const LinkedEntry<K, V>,
+    remaining: uint,
+    marker: marker::ContravariantLifetime<'a>,
+}
+
+/// LinkedHashMap mutable values iterator
+pub struct MutEntries<'a, K: 'a, V: 'a> {
+    head: *mut LinkedEntry<K, V>,
+    tail: *mut LinkedEntry<K, V>,
+    remaining: uint,
+    marker: marker::ContravariantLifetime<'a>,
+}
+
+/// LinkedHashMap move iterator
+pub struct MoveEntries<K, V> {
+    map: LinkedHashMap<K, V>
+}
+
+/// LinkedHashMap keys iterator
+pub type Keys<'a, K, V> =
+    iter::Map<'static, (&'a K, &'a V), &'a K, Entries<'a, K, V>>;
+
+/// LinkedHashMap values iterator
+pub type Values<'a, K, V> =
+    iter::Map<'static, (&'a K, &'a V), &'a V, Entries<'a, K, V>>;
+
+impl<'a, K, V> Iterator<(&'a K, &'a V)> for Entries<'a, K, V> {
+    #[inline]
+    fn next(&mut self) -> Option<(&'a K, &'a V)> {
+        if self.remaining == 0 { return None }
+        self.remaining -= 1;
+        unsafe {
+            let node = &*self.head;
+            self.head = node.next as *const LinkedEntry<K, V>;
+            Some((&node.key, &node.value))
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (uint, Option<uint>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, K, V> DoubleEndedIterator<(&'a K, &'a V)> for Entries<'a, K, V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<(&'a K, &'a V)> {
+        if self.remaining == 0 { return None }
+        self.remaining -= 1;
+        unsafe {
+            let node = &*self.tail;
+            self.tail = node.prev as *const LinkedEntry<K, V>;
+            Some((&node.key, &node.value))
+        }
+    }
+}
+
+impl<'a, K, V> Iterator<(&'a K, &'a mut V)> for MutEntries<'a, K, V> {
+    #[inline]
+    fn next(&mut self) -> Option<(&'a K, &'a mut V)> {
+        if self.remaining == 0 { return None }
+        self.remaining -= 1;
+        unsafe {
+            let node = &mut *self.head;
+            self.head = node.next;
+            Some((&node.key, &mut node.value))
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (uint, Option<uint>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, K, V> DoubleEndedIterator<(&'a K, &'a mut V)> for MutEntries<'a, K, V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<(&'a K, &'a mut V)> {
+        if self.remaining == 0 { return None }
+        self.remaining -= 1;
+        unsafe {
+            let node = &mut *self.tail;
+            self.tail = node.prev;
+            Some((&node.key, &mut node.value))
+        }
+    }
+}
+
+impl<K: Hash + Eq, V> Iterator<(K, V)> for MoveEntries<K, V> {
+    #[inline]
+    fn next(&mut self) -> Option<(K, V)> {
+        self.map.pop_front()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (uint, Option<uint>) {
+        let len = self.map.len();
+        (len, Some(len))
+    }
+}
+
+impl<K: Hash + Eq, V> DoubleEndedIterator<(K, V)> for MoveEntries<K, V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<(K, V)> {
+        self.map.pop_back()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use prelude::*;
+    use super::{LinkedHashMap, AccessOrder};
+
+    fn keys(map: &LinkedHashMap<int, int>) -> Vec<int> {
+        map.keys().map(|&k| k).collect()
+    }
+
+    #[test]
+    fn test_insert_and_find() {
+        let mut map = LinkedHashMap::new();
+        assert!(map.insert(1i, 10i));
+        assert!(map.insert(2, 20));
+        assert!(!map.insert(1, 11));
+        assert_eq!(map.find(&1), Some(&11));
+        assert_eq!(map.find(&2), Some(&20));
+        assert_eq!(map.find(&3), None);
+        assert_eq!(map.len(), 2);
+    }
+
+    #[test]
+    fn test_insertion_order() {
+        let mut map = LinkedHashMap::new();
+        map.insert(3i, 30i);
+        map.insert(1, 10);
+        map.insert(2, 20);
+        assert_eq!(keys(&map), vec![3, 1, 2]);
+
+        // Updates and lookups keep the original position.
+        map.insert(3, 33);
+        map.get(&1);
+        assert_eq!(keys(&map), vec![3, 1, 2]);
+        assert_eq!(map.find(&3), Some(&33));
+
+        // A removed key goes to the back when it comes back.
+        map.pop(&3);
+        map.insert(3, 34);
+        assert_eq!(keys(&map), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_access_order() {
+        let mut map = LinkedHashMap::with_order(AccessOrder);
+        map.insert(1i, 10i);
+        map.insert(2, 20);
+        map.insert(3, 30);
+
+        assert_eq!(map.get(&1), Some(&10));
+        assert_eq!(keys(&map), vec![2, 3, 1]);
+
+        *map.get_mut(&2).unwrap() = 22;
+        assert_eq!(keys(&map), vec![3, 1, 2]);
+
+        map.insert(3, 33);
+        assert_eq!(keys(&map), vec![1, 2, 3]);
+
+        // `find` and `find_mut` never reorder.
+        assert_eq!(map.find(&1), Some(&10));
+        *map.find_mut(&2).unwrap() = 23;
+        assert_eq!(keys(&map), vec![1, 2, 3]);
+        assert_eq!(map.get(&4), None);
+    }
+
+    #[test]
+    fn test_front_back() {
+        let mut map = LinkedHashMap::new();
+        assert_eq!(map.front(), None);
+        assert_eq!(map.back(), None);
+        assert_eq!(map.pop_front(), None);
+        assert_eq!(map.pop_back(), None);
+
+        for i in range(0i, 5) {
+            map.insert(i, i * 10);
+        }
+        assert_eq!(map.front(), Some((&0, &0)));
+        assert_eq!(map.back(), Some((&4, &40)));
+        assert_eq!(map.pop_front(), Some((0, 0)));
+        assert_eq!(map.pop_back(), Some((4, 40)));
+        assert_eq!(keys(&map), vec![1, 2, 3]);
+        assert_eq!(map.find(&0), None);
+        assert_eq!(map.find(&4), None);
+        assert_eq!(map.len(), 3);
+    }
+
+    #[test]
+    fn test_pop() {
+        let mut map = LinkedHashMap::new();
+        map.insert(1i, 10i);
+        map.insert(2, 20);
+        map.insert(3, 30);
+        assert_eq!(map.pop(&2), Some(20));
+        assert_eq!(map.pop(&2), None);
+        assert_eq!(keys(&map), vec![1, 3]);
+        let back: Vec<int> = map.iter().rev().map(|(&k, _)| k).collect();
+        assert_eq!(back, vec![3, 1]);
+    }
+
+    #[test]
+    fn test_iter() {
+        let map: LinkedHashMap<int, int> = range(0i, 10).map(|i| (9 - i, i)).collect();
+        let mut it = map.iter();
+        assert_eq!(it.size_hint(), (10, Some(10)));
+        assert_eq!(it.next(), Some((&9, &0)));
+        assert_eq!(it.next_back(), Some((&0, &9)));
+        assert_eq!(it.size_hint(), (8, Some(8)));
+        let rest: Vec<int> = it.map(|(&k, _)| k).collect();
+        assert_eq!(rest, vec![8, 7, 6, 5, 4, 3, 2, 1]);
+
+        let values: Vec<int> = map.values().map(|&v| v).collect();
+        assert_eq!(values, range(0i, 10).collect::<Vec<int>>());
+    }
+
+    #[test]
+    fn test_mut_iter() {
+        let mut map = LinkedHashMap::with_order(AccessOrder);
+        map.insert(1i, 1i);
+        map.insert(2, 2);
+        map.insert(3, 3);
+        for (_, v) in map.mut_iter() {
+            *v *= 10;
+        }
+        {
+            let (_, v) = map.mut_iter().next_back().unwrap();
+            *v += 1;
+        }
+        assert_eq!(keys(&map), vec![1, 2, 3]);
+        let values: Vec<int> = map.values().map(|&v| v).collect();
+        assert_eq!(values, vec![10, 20, 31]);
+    }
+
+    #[test]
+    fn test_move_iter() {
+        let mut map = LinkedHashMap::new();
+        map.insert(1i, "a".to_string());
+        map.insert(2, "b".to_string());
+        map.insert(3, "c".to_string());
+        let mut it = map.move_iter();
+        assert_eq!(it.next(), Some((1, "a".to_string())));
+        assert_eq!(it.next_back(), Some((3, "c".to_string())));
+        // Dropping the iterator drops the remaining entries.
+    }
+
+    #[test]
+    fn test_clear() {
+        let mut map = LinkedHashMap::new();
+        map.insert(1i, 10i);
+        map.insert(2, 20);
+        map.clear();
+        assert!(map.is_empty());
+        assert_eq!(map.front(), None);
+        assert_eq!(map.iter().next(), None);
+        map.insert(3, 30);
+        assert_eq!(keys(&map), vec![3]);
+    }
+
+    #[test]
+    fn test_eq_and_show() {
+        let mut a = LinkedHashMap::new();
+        a.insert(1i, 10i);
+        a.insert(2, 20);
+        let mut b = LinkedHashMap::new();
+        b.insert(2i, 20i);
+        b.insert(1, 10);
+        assert!(a != b);
+        b.pop_front();
+        b.insert(2, 20);
+        assert!(a == b);
+
+        assert_eq!(a.to_string(), "{1: 10, 2: 20}".to_string());
+        assert_eq!(a[2], 20);
+        let empty: LinkedHashMap<int, int> = LinkedHashMap::new();
+        assert_eq!(empty.to_string(), "{}".to_string());
+    }
+}
@@ -14,6 +14,11 @@
 //! (where "used" means a look-up or putting the pair into the cache)
 //! pair is automatically removed.
 //!
+//! Every entry has a weight, 1 unless it is put with `put_weighted`, and
+//! the capacity bounds the total weight of the entries rather than their
+//! number. An `EvictionHandler` can be installed to be told about every
+//! pair the cache drops to stay within its capacity.
+//!
 //! # Example
 //!
 //! ```rust
@@ -37,61 +42,40 @@
 //! assert!(cache.get(&2).is_none());
 //! ```
 
-use cmp::{PartialEq, Eq};
-use collections::{HashMap, Collection, Mutable, MutableMap};
+use collections::{Collection, Mutable, Map, MutableMap};
+use collections::linked_hash_map::{LinkedHashMap, AccessOrder};
 use fmt;
 use hash::Hash;
-use iter::{range, Iterator};
-use mem;
-use ops::Drop;
+use iter::Iterator;
+use num::CheckedAdd;
 use option::{Some, None, Option};
 use boxed::Box;
-use ptr;
 use result::{Ok, Err};
 
-struct KeyRef<K> { k: *const K }
+/// A listener told about the pairs an `LruCache` evicts.
+pub trait EvictionHandler<K, V> {
+    /// Called with every key-value pair the cache removes to stay within
+    /// its capacity, least-recently used first.
+    fn evicted(&mut self, key: K, value: V);
+}
 
-struct LruEntry<K, V> {
-    next: *mut LruEntry<K, V>,
-    prev: *mut LruEntry<K, V>,
-    key: K,
+struct CacheEntry<V> {
     value: V,
+    weight: uint,
 }
 
 /// An LRU Cache.
 pub struct LruCache<K, V> {
-    map: HashMap<KeyRef<K>, Box<LruEntry<K, V>>>,
+    map: LinkedHashMap<K, CacheEntry<V>>,
     max_size: uint,
-    head: *mut LruEntry<K, V>,
-}
-
-impl<S, K: Hash<S>> Hash<S> for KeyRef<K> {
-    fn hash(&self, state: &mut S) {
-        unsafe { (*self.k).hash(state) }
-    }
-}
-
-impl<K: PartialEq> PartialEq for KeyRef<K> {
-    fn eq(&self, other: &KeyRef<K>) -> bool {
-        unsafe{ (*self.k).eq(&*other.k) }
-    }
-}
-
-impl<K: Eq> Eq for KeyRef<K> {}
-
-impl<K, V> LruEntry<K, V> {
-    fn new(k: K, v: V) -> LruEntry<K, V> {
-        LruEntry {
-            key: k,
-            value: v,
-            next: ptr::mut_null(),
-            prev: ptr::mut_null(),
-        }
-    }
+    weight: uint,
+    handler: Option<Box<EvictionHandler<K, V> + 'static>>,
 }
 
 impl<K: Hash + Eq, V> LruCache<K, V> {
-    /// Create an LRU Cache that holds at most `capacity` items.
+    /// Create an LRU Cache that holds key-value pairs of at most `capacity`
+    /// total weight. As each pair put with `put` weighs 1, this is also the
+    /// number of such pairs it holds.
     ///
     /// # Example
     ///
@@ -100,16 +84,40 @@ impl<K: Hash + Eq, V> LruCache<K, V> {
     /// let mut cache: LruCache<int, &str> = LruCache::new(10);
     /// ```
     pub fn new(capacity: uint) -> LruCache<K, V> {
-        let cache = LruCache {
-            map: HashMap::new(),
+        LruCache {
+            map: LinkedHashMap::with_order(AccessOrder),
             max_size: capacity,
-            head: unsafe{ mem::transmute(box mem::uninitialized::<LruEntry<K, V>>()) },
-        };
-        unsafe {
-            (*cache.head).next = cache.head;
-            (*cache.head).prev = cache.head;
+            weight: 0,
+            handler: None,
         }
-        return cache;
+    }
+
+    /// Install a handler to be called with every pair evicted from the
+    /// cache from now on, replacing the previous one. Pairs removed by
+    /// `pop`, `clear` or by putting a new value for an existing key are
+    /// not evictions.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::LruCache;
+    /// use std::collections::lru_cache::EvictionHandler;
+    ///
+    /// struct Printer;
+    ///
+    /// impl EvictionHandler<int, &'static str> for Printer {
+    ///     fn evicted(&mut self, key: int, value: &'static str) {
+    ///         println!("evicted {}: {}", key, value);
+    ///     }
+    /// }
+    ///
+    /// let mut cache: LruCache<int, &'static str> = LruCache::new(1);
+    /// cache.set_eviction_handler(box Printer);
+    /// cache.put(1i, "a");
+    /// cache.put(2, "b"); // prints "evicted 1: a"
+    /// ```
+    pub fn set_eviction_handler(&mut self, handler: Box<EvictionHandler<K, V> + 'static>) {
+        self.handler = Some(handler);
     }
 
     /// Put a key-value pair into cache.
@@ -126,33 +134,45 @@ impl<K: Hash + Eq, V> LruCache<K, V> {
     /// assert_eq!(cache.get(&2), Some(&"b"));
     /// ```
     pub fn put(&mut self, k: K, v: V) {
-        let (node_ptr, node_opt) = match self.map.find_mut(&KeyRef{k: &k}) {
-            Some(node) => {
-                node.value = v;
-                let node_ptr: *mut LruEntry<K, V> = &mut **node;
-                (node_ptr, None)
-            }
-            None => {
-                let mut node = box LruEntry::new(k, v);
-                let node_ptr: *mut LruEntry<K, V> = &mut *node;
-                (node_ptr, Some(node))
-            }
+        self.put_weighted(k, v, 1);
+    }
+
+    /// Put a key-value pair that takes up `weight` of the capacity into
+    /// cache. Least-recently-used pairs are evicted until the total weight
+    /// fits again; a pair heavier than the whole capacity is evicted
+    /// itself once everything else is gone.
+    ///
+    /// # Failure
+    ///
+    /// Fails if the total weight of the pairs in the cache would overflow
+    /// a `uint`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::LruCache;
+    /// let mut cache = LruCache::new(10);
+    ///
+    /// cache.put_weighted(1i, "small", 3);
+    /// cache.put_weighted(2, "medium", 5);
+    /// assert_eq!(cache.weight(), 8);
+    ///
+    /// cache.put_weighted(3, "large", 6);
+    /// assert_eq!(cache.get(&1), None);
+    /// assert_eq!(cache.get(&2), None);
+    /// assert_eq!(cache.weight(), 6);
+    /// ```
+    pub fn put_weighted(&mut self, k: K, v: V, weight: uint) {
+        // Work out the new total before touching the map, so that the cache
+        // is left as it was if this fails.
+        let replaced = self.map.find(&k).map_or(0, |old| old.weight);
+        let total = match (self.weight - replaced).checked_add(&weight) {
+            Some(total) => total,
+            None => fail!("LruCache total weight overflowed putting a pair of weight {}", weight),
         };
-        match node_opt {
-            None => {
-                // Existing node, just update LRU position
-                self.detach(node_ptr);
-                self.attach(node_ptr);
-            }
-            Some(node) => {
-                let keyref = unsafe { &(*node_ptr).key };
-                self.map.swap(KeyRef{k: keyref}, node);
-                self.attach(node_ptr);
-                if self.len() > self.capacity() {
-                    self.remove_lru();
-                }
-            }
-        }
+        self.map.swap(k, CacheEntry { value: v, weight: weight });
+        self.weight = total;
+        self.evict();
     }
 
     /// Return a value corresponding to the key in the cache.
@@ -172,21 +192,33 @@ impl<K: Hash + Eq, V> LruCache<K, V> {
     /// assert_eq!(cache.get(&2), Some(&"c"));
     /// ```
     pub fn get<'a>(&'a mut self, k: &K) -> Option<&'a V> {
-        let (value, node_ptr_opt) = match self.map.find_mut(&KeyRef{k: k}) {
-            None => (None, None),
-            Some(node) => {
-                let node_ptr: *mut LruEntry<K, V> = &mut **node;
-                (Some(unsafe { &(*node_ptr).value }), Some(node_ptr))
-            }
-        };
-        match node_ptr_opt {
-            None => (),
-            Some(node_ptr) => {
-                self.detach(node_ptr);
-                self.attach(node_ptr);
-            }
+        match self.map.get(k) {
+            None => None,
+            Some(entry) => Some(&entry.value),
+        }
+    }
+
+    /// Return a value corresponding to the key in the cache without
+    /// marking it as used.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::LruCache;
+    /// let mut cache = LruCache::new(2);
+    ///
+    /// cache.put(1i, "a");
+    /// cache.put(2, "b");
+    /// assert_eq!(cache.peek(&1), Some(&"a"));
+    ///
+    /// cache.put(3, "c");
+    /// assert_eq!(cache.peek(&1), None);
+    /// ```
+    pub fn peek<'a>(&'a self, k: &K) -> Option<&'a V> {
+        match self.map.find(k) {
+            None => None,
+            Some(entry) => Some(&entry.value),
         }
-        return value;
     }
 
     /// Remove and return a value corresponding to the key from the cache.
@@ -205,13 +237,17 @@ impl<K: Hash + Eq, V> LruCache<K, V> {
     /// assert_eq!(cache.len(), 0);
     /// ```
     pub fn pop(&mut self, k: &K) -> Option<V> {
-        match self.map.pop(&KeyRef{k: k}) {
+        match self.map.pop(k) {
             None => None,
-            Some(lru_entry) => Some(lru_entry.value)
+            Some(entry) => {
+                self.weight -= entry.weight;
+                Some(entry.value)
+            }
         }
     }
 
-    /// Return the maximum number of key-value pairs the cache can hold.
+    /// Return the maximum total weight of the key-value pairs the cache
+    /// can hold, which is their number if none was put with a weight.
     ///
     /// # Example
     ///
@@ -224,8 +260,26 @@ impl<K: Hash + Eq, V> LruCache<K, V> {
         self.max_size
     }
 
-    /// Change the number of key-value pairs the cache can hold. Remove
-    /// least-recently-used key-value pairs if necessary.
+    /// Return the total weight of the key-value pairs in the cache.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::LruCache;
+    /// let mut cache = LruCache::new(10);
+    ///
+    /// cache.put(1i, "a");
+    /// cache.put_weighted(2, "b", 4);
+    /// assert_eq!(cache.weight(), 5);
+    /// assert_eq!(cache.len(), 2);
+    /// ```
+    pub fn weight(&self) -> uint {
+        self.weight
+    }
+
+    /// Change the maximum total weight of the key-value pairs the cache can
+    /// hold. Remove least-recently-used key-value pairs until their total
+    /// weight fits, if necessary.
     ///
     /// # Example
     ///
@@ -256,36 +310,22 @@ impl<K: Hash + Eq, V> LruCache<K, V> {
     /// assert_eq!(cache.get(&3), Some(&"c"));
     /// ```
     pub fn change_capacity(&mut self, capacity: uint) {
-        for _ in range(capacity, self.len()) {
-            self.remove_lru();
-        }
         self.max_size = capacity;
+        self.evict();
     }
 
-    #[inline]
-    fn remove_lru(&mut self) {
-        if self.len() > 0 {
-            let lru = unsafe { (*self.head).prev };
-            self.detach(lru);
-            self.map.pop(&KeyRef{k: unsafe { &(*lru).key }});
-        }
-    }
-
-    #[inline]
-    fn detach(&mut self, node: *mut LruEntry<K, V>) {
-        unsafe {
-            (*(*node).prev).next = (*node).next;
-            (*(*node).next).prev = (*node).prev;
-        }
-    }
-
-    #[inline]
-    fn attach(&mut self, node: *mut LruEntry<K, V>) {
-        unsafe {
-            (*node).next = (*self.head).next;
-            (*node).prev = self.head;
-            (*self.head).next = node;
-            (*(*node).next).prev = node;
+    fn evict(&mut self) {
+        while self.weight > self.max_size {
+            match self.map.pop_front() {
+                None => break,
+                Some((k, entry)) => {
+                    self.weight -= entry.weight;
+                    match self.handler {
+                        Some(ref mut handler) => handler.evicted(k, entry.value),
+                        None => (),
+                    }
+                }
+            }
         }
     }
 }
@@ -295,17 +335,9 @@ impl<A: fmt::Show + Hash + Eq, B: fmt::Show> fmt::Show for LruCache<A, B> {
     /// used to least-recently used.
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         try!(write!(f, "{{"));
-        let mut cur = self.head;
-        for i in range(0, self.len()) {
+        for (i, (k, entry)) in self.map.iter().rev().enumerate() {
             if i > 0 { try!(write!(f, ", ")) }
-            unsafe {
-                cur = (*cur).next;
-                try!(write!(f, "{}", (*cur).key));
-            }
-            try!(write!(f, ": "));
-            unsafe {
-                try!(write!(f, "{}", (*cur).value));
-            }
+            try!(write!(f, "{}: {}", *k, entry.value));
         }
         write!(f, r"}}")
     }
@@ -322,27 +354,27 @@ impl<K: Hash + Eq, V> Mutable for LruCache<K, V> {
     /// Clear the cache of all key-value pairs.
     fn clear(&mut self) {
         self.map.clear();
-    }
-}
-
-#[unsafe_destructor]
-impl<K, V> Drop for LruCache<K, V> {
-    fn drop(&mut self) {
-        unsafe {
-            let node: Box<LruEntry<K, V>> = mem::transmute(self.head);
-            // Prevent compiler from trying to drop the un-initialized field in the sigil node.
-            let box internal_node = node;
-            let LruEntry { next: _, prev: _, key: k, value: v } = internal_node;
-            mem::forget(k);
-            mem::forget(v);
-        }
+        self.weight = 0;
     }
 }
 
 #[cfg(test)]
 mod tests {
     use prelude::*;
-    use super::LruCache;
+    use cell::RefCell;
+    use rc::Rc;
+    use uint;
+    use super::{LruCache, EvictionHandler};
+
+    struct Recorder {
+        evicted: Rc<RefCell<Vec<(int, int)>>>,
+    }
+
+    impl EvictionHandler<int, int> for Recorder {
+        fn evicted(&mut self, key: int, value: int) {
+            self.evicted.borrow_mut().push((key, value));
+        }
+    }
 
     fn assert_opt_eq<V: PartialEq>(opt: Option<&V>, v: V) {
         assert!(opt.is_some());
@@ -431,4 +463,79 @@ mod tests {
         assert!(cache.get(&2).is_none());
         assert_eq!(cache.to_string(), "{}".to_string());
     }
+
+    #[test]
+    fn test_peek() {
+        let mut cache: LruCache<int, int> = LruCache::new(2);
+        cache.put(1, 10);
+        cache.put(2, 20);
+        assert_opt_eq(cache.peek(&1), 10);
+        assert!(cache.peek(&3).is_none());
+        cache.put(3, 30);
+        assert!(cache.peek(&1).is_none());
+        assert_eq!(cache.to_string(), "{3: 30, 2: 20}".to_string());
+    }
+
+    #[test]
+    fn test_weighted() {
+        let mut cache: LruCache<int, int> = LruCache::new(10);
+        cache.put_weighted(1, 10, 4);
+        cache.put_weighted(2, 20, 4);
+        cache.put(3, 30);
+        assert_eq!(cache.weight(), 9);
+        cache.get(&1);
+        cache.put_weighted(4, 40, 3);
+        assert!(cache.peek(&2).is_none());
+        assert_eq!(cache.weight(), 8);
+        assert_eq!(cache.len(), 3);
+
+        // Replacing a value replaces its weight.
+        cache.put_weighted(1, 11, 1);
+        assert_eq!(cache.weight(), 5);
+        assert_eq!(cache.pop(&4), Some(40));
+        assert_eq!(cache.weight(), 2);
+
+        // An entry that can never fit does not stay.
+        cache.put_weighted(5, 50, 11);
+        assert!(cache.is_empty());
+        assert_eq!(cache.weight(), 0);
+    }
+
+    #[test]
+    #[should_fail]
+    fn test_weight_overflow() {
+        let mut cache: LruCache<int, int> = LruCache::new(uint::MAX);
+        cache.put_weighted(1, 10, uint::MAX);
+        cache.put(2, 20);
+    }
+
+    #[test]
+    fn test_weight_replace_at_max() {
+        let mut cache: LruCache<int, int> = LruCache::new(uint::MAX);
+        cache.put_weighted(1, 10, uint::MAX);
+        cache.put_weighted(1, 20, uint::MAX);
+        assert_eq!(cache.weight(), uint::MAX);
+        assert_eq!(cache.get(&1), Some(&20));
+    }
+
+    #[test]
+    fn test_eviction_handler() {
+        let evicted = Rc::new(RefCell::new(Vec::new()));
+        let mut cache: LruCache<int, int> = LruCache::new(2);
+        cache.set_eviction_handler(box Recorder { evicted: evicted.clone() });
+        cache.put(1, 10);
+        cache.put(2, 20);
+        cache.put(1, 11);
+        cache.put(3, 30);
+        assert_eq!(*evicted.borrow(), vec![(2, 20)]);
+
+        cache.pop(&1);
+        cache.put_weighted(4, 40, 2);
+        cache.change_capacity(1);
+        assert_eq!(*evicted.borrow(), vec![(2, 20), (3, 30), (4, 40)]);
+
+        cache.put(5, 50);
+        cache.clear();
+        assert_eq!(evicted.borrow().len(), 3);
+    }
 }
@@ -16,14 +16,17 @@
 
 pub use core_collections::{Collection, Mutable, Map, MutableMap};
 pub use core_collections::{Set, MutableSet, Deque, MutableSeq};
-pub use core_collections::{Bitv, BitvSet, BTree, DList, EnumSet};
-pub use core_collections::{PriorityQueue, RingBuf, SmallIntMap};
+pub use core_collections::{Bitv, BitvSet, BTreeMap, BTreeSet, DList, EnumSet};
+pub use core_collections::{IndexedPriorityQueue, PairingHeap, PriorityQueue};
+pub use core_collections::{RingBuf, SmallIntMap};
 pub use core_collections::{TreeMap, TreeSet, TrieMap, TrieSet};
-pub use core_collections::{bitv, btree, dlist, enum_set};
-pub use core_collections::{priority_queue, ringbuf, smallintmap, treemap, trie};
+pub use core_collections::{bitv, btree, dlist, enum_set, indexed_priority_queue};
+pub use core_collections::{pairing_heap, priority_queue, ringbuf, smallintmap, treemap, trie};
 
 pub use self::hashmap::{HashMap, HashSet};
+pub use self::linked_hash_map::LinkedHashMap;
 pub use self::lru_cache::LruCache;
 
 pub mod hashmap;
+pub mod linked_hash_map;
 pub mod lru_cache;
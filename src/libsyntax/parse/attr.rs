@@ -201,12 +201,23 @@ impl<'a> ParserAttr for Parser<'a> {
         }
     }
 
-    /// matches meta_seq = ( COMMASEP(meta_item) )
+    /// matches meta_seq = ( COMMASEP(meta_item | LIT_INTEGER) )
+    ///
+    /// An integer literal in the list is kept as a word, as in
+    /// `#[timeout(5000)]`.
     fn parse_meta_seq(&mut self) -> Vec<Gc<ast::MetaItem>> {
         self.parse_seq(&token::LPAREN,
                        &token::RPAREN,
                        seq_sep_trailing_disallowed(token::COMMA),
-                       |p| p.parse_meta_item()).node
+                       |p| match p.token {
+                           token::LIT_INTEGER(n) => {
+                               let lo = p.span.lo;
+                               p.bump();
+                               let hi = p.last_span.hi;
+                               box(GC) spanned(lo, hi, ast::MetaWord(token::get_name(n)))
+                           }
+                           _ => p.parse_meta_item()
+                       }).node
     }
 
     fn parse_optional_meta(&mut self) -> Vec<Gc<ast::MetaItem>> {
@@ -34,6 +34,12 @@
 //! }
 //! ```
 //!
+//! Besides the 8 or 16 basic colors, terminals can be asked for colors from
+//! the 256-color palette, or for arbitrary 24-bit colors with `fg_rgb` and
+//! `bg_rgb`, which fall back to the nearest color the terminal supports. The
+//! cursor can be moved with `cursor`, parts of the screen erased with `clear`,
+//! and the dimensions of the terminal are available from `size`.
+//!
 //! [ansi]: https://en.wikipedia.org/wiki/ANSI_escape_code
 //! [win]: http://msdn.microsoft.com/en-us/library/windows/desktop/ms682010%28v=vs.85%29.aspx
 //! [ti]: https://en.wikipedia.org/wiki/Terminfo
@@ -54,6 +60,7 @@
 #![deny(missing_doc)]
 
 #[phase(plugin, link)] extern crate log;
+extern crate libc;
 
 pub use terminfo::TerminfoTerminal;
 #[cfg(windows)]
@@ -151,6 +158,8 @@ pub fn stderr() -> Option<Box<Terminal<WriterWrapper> + Send> + Send> {
 
 /// Terminal color definitions
 pub mod color {
+    use std::cmp;
+
     /// Number for a terminal color
     pub type Color = u16;
 
@@ -171,6 +180,66 @@ pub mod color {
     pub static BRIGHT_MAGENTA: Color = 13u16;
     pub static BRIGHT_CYAN:    Color = 14u16;
     pub static BRIGHT_WHITE:   Color = 15u16;
+
+    // The intensities of the 6 levels of each component in the color cube.
+    static CUBE_LEVELS: [u8, ..6] = [0, 95, 135, 175, 215, 255];
+
+    // The colors of the 16 basic colors in xterm's default palette.
+    static BASIC_RGB: [(u8, u8, u8), ..16] = [
+        (0, 0, 0), (205, 0, 0), (0, 205, 0), (205, 205, 0),
+        (0, 0, 238), (205, 0, 205), (0, 205, 205), (229, 229, 229),
+        (127, 127, 127), (255, 0, 0), (0, 255, 0), (255, 255, 0),
+        (92, 92, 255), (255, 0, 255), (0, 255, 255), (255, 255, 255),
+    ];
+
+    /// Returns the color at the given coordinates of the 6x6x6 color cube
+    /// of 256-color terminals. Each component is in the range 0-5.
+    pub fn cube(r: u8, g: u8, b: u8) -> Color {
+        assert!(r < 6 && g < 6 && b < 6);
+        16 + 36 * r as Color + 6 * g as Color + b as Color
+    }
+
+    /// Returns the color at the given step of the grayscale ramp of 256-color
+    /// terminals, from 0 (nearly black) to 23 (nearly white).
+    pub fn gray(level: u8) -> Color {
+        assert!(level < 24);
+        232 + level as Color
+    }
+
+    /// Returns the approximate red, green and blue components of a color from
+    /// the 256-color palette, using the default palette of xterm.
+    pub fn to_rgb(color: Color) -> (u8, u8, u8) {
+        match color {
+            0..15 => BASIC_RGB[color as uint],
+            16..231 => {
+                let c = color as uint - 16;
+                (CUBE_LEVELS[c / 36], CUBE_LEVELS[c / 6 % 6], CUBE_LEVELS[c % 6])
+            }
+            232..255 => {
+                let level = 8 + 10 * (color - 232) as u8;
+                (level, level, level)
+            }
+            _ => fail!("color {} is not in the 256-color palette", color)
+        }
+    }
+
+    /// Returns the color among the first `num_colors` colors of the 256-color
+    /// palette which is closest to the given red, green and blue components.
+    pub fn from_rgb(r: u8, g: u8, b: u8, num_colors: u16) -> Color {
+        fn square(x: u8, y: u8) -> int { (x as int - y as int) * (x as int - y as int) }
+
+        let mut best = BLACK;
+        let mut best_distance = -1;
+        for color in range(0, cmp::min(num_colors, 256)) {
+            let (r2, g2, b2) = to_rgb(color);
+            let distance = square(r, r2) + square(g, g2) + square(b, b2);
+            if best_distance < 0 || distance < best_distance {
+                best = color;
+                best_distance = distance;
+            }
+        }
+        best
+    }
 }
 
 /// Terminal attributes
@@ -204,6 +273,51 @@ pub mod attr {
     }
 }
 
+/// Terminal cursor movements
+pub mod cursor {
+    /// Movements of the cursor, for use with term.cursor().
+    ///
+    /// Rows and columns are counted from 0, starting at the top left corner
+    /// of the screen.
+    pub enum Cursor {
+        /// Move up the given number of rows
+        Up(uint),
+        /// Move down the given number of rows
+        Down(uint),
+        /// Move left the given number of columns
+        Left(uint),
+        /// Move right the given number of columns
+        Right(uint),
+        /// Move to the given row and column
+        Position(uint, uint),
+        /// Move to the start of the current line
+        LineStart,
+        /// Hide the cursor
+        Hide,
+        /// Show the cursor after it was hidden
+        Show,
+        /// Save the position of the cursor
+        Save,
+        /// Move the cursor to the position saved by `Save`
+        Restore,
+    }
+}
+
+/// Terminal erasing operations
+pub mod clear {
+    /// Parts of the screen which can be erased with term.clear().
+    pub enum Clear {
+        /// The whole screen. Also moves the cursor to the top left corner
+        Screen,
+        /// Everything from the cursor to the end of the screen
+        ToEndOfScreen,
+        /// Everything from the cursor to the end of the line
+        ToEndOfLine,
+        /// Everything from the start of the line to the cursor
+        ToStartOfLine,
+    }
+}
+
 /// A terminal with similar capabilities to an ANSI Terminal
 /// (foreground/background colors etc).
 pub trait Terminal<T: Writer>: Writer {
@@ -229,6 +343,28 @@ pub trait Terminal<T: Writer>: Writer {
     /// if there was an I/O error.
     fn bg(&mut self, color: color::Color) -> IoResult<bool>;
 
+    /// Sets the foreground color to the given 24-bit color.
+    ///
+    /// If the terminal doesn't support 24-bit colors, the closest color it
+    /// supports will be used instead.
+    ///
+    /// Returns `Ok(true)` if the color was set, `Ok(false)` otherwise, and `Err(e)`
+    /// if there was an I/O error.
+    fn fg_rgb(&mut self, r: u8, g: u8, b: u8) -> IoResult<bool> {
+        self.fg(color::from_rgb(r, g, b, 16))
+    }
+
+    /// Sets the background color to the given 24-bit color.
+    ///
+    /// If the terminal doesn't support 24-bit colors, the closest color it
+    /// supports will be used instead.
+    ///
+    /// Returns `Ok(true)` if the color was set, `Ok(false)` otherwise, and `Err(e)`
+    /// if there was an I/O error.
+    fn bg_rgb(&mut self, r: u8, g: u8, b: u8) -> IoResult<bool> {
+        self.bg(color::from_rgb(r, g, b, 16))
+    }
+
     /// Sets the given terminal attribute, if supported.  Returns `Ok(true)`
     /// if the attribute was supported, `Ok(false)` otherwise, and `Err(e)` if
     /// there was an I/O error.
@@ -241,6 +377,26 @@ pub trait Terminal<T: Writer>: Writer {
     /// Returns `Ok()`.
     fn reset(&mut self) -> IoResult<()>;
 
+    /// Moves, hides or shows the cursor, if supported. Returns `Ok(true)` if
+    /// the movement was supported, `Ok(false)` otherwise, and `Err(e)` if
+    /// there was an I/O error.
+    fn cursor(&mut self, _cursor: cursor::Cursor) -> IoResult<bool> {
+        Ok(false)
+    }
+
+    /// Erases part of the screen, if supported. Returns `Ok(true)` if the
+    /// operation was supported, `Ok(false)` otherwise, and `Err(e)` if there
+    /// was an I/O error.
+    fn clear(&mut self, _clear: clear::Clear) -> IoResult<bool> {
+        Ok(false)
+    }
+
+    /// Returns the size of the terminal as a number of columns and rows, or
+    /// `None` if it can't be determined.
+    fn size(&self) -> Option<(uint, uint)> {
+        None
+    }
+
     /// Returns the contained stream, destroying the `Terminal`
     fn unwrap(self) -> T;
 
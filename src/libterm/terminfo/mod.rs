@@ -15,11 +15,13 @@ use std::io::IoResult;
 use std::os;
 
 use attr;
+use clear;
 use color;
+use cursor;
 use Terminal;
 use self::searcher::open;
 use self::parser::compiled::{parse, msys_terminfo};
-use self::parm::{expand, Number, Variables};
+use self::parm::{expand, Number, Param, Variables};
 
 
 /// A parsed terminfo database entry.
@@ -63,10 +65,20 @@ fn cap_for_attr(attr: attr::Attr) -> &'static str {
     }
 }
 
+fn cap_for_clear(clear: clear::Clear) -> &'static str {
+    match clear {
+        clear::Screen        => "clear",
+        clear::ToEndOfScreen => "ed",
+        clear::ToEndOfLine   => "el",
+        clear::ToStartOfLine => "el1"
+    }
+}
+
 /// A Terminal that knows how many colors it supports, with a reference to its
 /// parsed Terminfo database record.
 pub struct TerminfoTerminal<T> {
     num_colors: u16,
+    truecolor: bool,
     out: T,
     ti: Box<TermInfo>
 }
@@ -87,7 +99,7 @@ impl<T: Writer> Terminal<T> for TerminfoTerminal<T> {
                     "mintty.exe" == s.as_slice()
                 }) {
                 // msys terminal
-                return Some(TerminfoTerminal {out: out, ti: msys_terminfo(), num_colors: 8});
+                return Some(TerminfoTerminal::new_with_terminfo(out, msys_terminfo()));
             }
             debug!("error finding terminfo entry: {}", entry.err().unwrap());
             return None;
@@ -100,28 +112,13 @@ impl<T: Writer> Terminal<T> for TerminfoTerminal<T> {
             return None;
         }
 
-        let inf = ti.unwrap();
-        let nc = if inf.strings.find_equiv(&("setaf")).is_some()
-                 && inf.strings.find_equiv(&("setab")).is_some() {
-                     inf.numbers.find_equiv(&("colors")).map_or(0, |&n| n)
-                 } else { 0 };
-
-        return Some(TerminfoTerminal {out: out, ti: inf, num_colors: nc});
+        return Some(TerminfoTerminal::new_with_terminfo(out, ti.unwrap()));
     }
 
     fn fg(&mut self, color: color::Color) -> IoResult<bool> {
         let color = self.dim_if_necessary(color);
         if self.num_colors > color {
-            let s = expand(self.ti
-                               .strings
-                               .find_equiv(&("setaf"))
-                               .unwrap()
-                               .as_slice(),
-                           [Number(color as int)], &mut Variables::new());
-            if s.is_ok() {
-                try!(self.out.write(s.unwrap().as_slice()));
-                return Ok(true)
-            }
+            return self.apply_cap("setaf", [Number(color as int)]);
         }
         Ok(false)
     }
@@ -129,38 +126,36 @@ impl<T: Writer> Terminal<T> for TerminfoTerminal<T> {
     fn bg(&mut self, color: color::Color) -> IoResult<bool> {
         let color = self.dim_if_necessary(color);
         if self.num_colors > color {
-            let s = expand(self.ti
-                               .strings
-                               .find_equiv(&("setab"))
-                               .unwrap()
-                               .as_slice(),
-                           [Number(color as int)], &mut Variables::new());
-            if s.is_ok() {
-                try!(self.out.write(s.unwrap().as_slice()));
-                return Ok(true)
-            }
+            return self.apply_cap("setab", [Number(color as int)]);
         }
         Ok(false)
     }
 
+    fn fg_rgb(&mut self, r: u8, g: u8, b: u8) -> IoResult<bool> {
+        if self.truecolor {
+            try!(write!(self.out, "\x1b[38;2;{};{};{}m", r, g, b));
+            Ok(true)
+        } else {
+            let color = color::from_rgb(r, g, b, self.num_colors);
+            self.fg(color)
+        }
+    }
+
+    fn bg_rgb(&mut self, r: u8, g: u8, b: u8) -> IoResult<bool> {
+        if self.truecolor {
+            try!(write!(self.out, "\x1b[48;2;{};{};{}m", r, g, b));
+            Ok(true)
+        } else {
+            let color = color::from_rgb(r, g, b, self.num_colors);
+            self.bg(color)
+        }
+    }
+
     fn attr(&mut self, attr: attr::Attr) -> IoResult<bool> {
         match attr {
             attr::ForegroundColor(c) => self.fg(c),
             attr::BackgroundColor(c) => self.bg(c),
-            _ => {
-                let cap = cap_for_attr(attr);
-                let parm = self.ti.strings.find_equiv(&cap);
-                if parm.is_some() {
-                    let s = expand(parm.unwrap().as_slice(),
-                                   [],
-                                   &mut Variables::new());
-                    if s.is_ok() {
-                        try!(self.out.write(s.unwrap().as_slice()));
-                        return Ok(true)
-                    }
-                }
-                Ok(false)
-            }
+            _ => self.apply_cap(cap_for_attr(attr), [])
         }
     }
 
@@ -195,6 +190,34 @@ impl<T: Writer> Terminal<T> for TerminfoTerminal<T> {
         Ok(())
     }
 
+    fn cursor(&mut self, cursor: cursor::Cursor) -> IoResult<bool> {
+        match cursor {
+            cursor::Up(n)     => self.apply_cap_n("cuu", "cuu1", n),
+            cursor::Down(n)   => self.apply_cap_n("cud", "cud1", n),
+            cursor::Left(n)   => self.apply_cap_n("cub", "cub1", n),
+            cursor::Right(n)  => self.apply_cap_n("cuf", "cuf1", n),
+            cursor::Position(row, col) => {
+                self.apply_cap("cup", [Number(row as int), Number(col as int)])
+            }
+            cursor::LineStart => self.apply_cap("cr", []),
+            cursor::Hide      => self.apply_cap("civis", []),
+            cursor::Show      => self.apply_cap("cnorm", []),
+            cursor::Save      => self.apply_cap("sc", []),
+            cursor::Restore   => self.apply_cap("rc", [])
+        }
+    }
+
+    fn clear(&mut self, clear: clear::Clear) -> IoResult<bool> {
+        self.apply_cap(cap_for_clear(clear), [])
+    }
+
+    fn size(&self) -> Option<(uint, uint)> {
+        match tty::size() {
+            Some(size) => Some(size),
+            None => self.default_size()
+        }
+    }
+
     fn unwrap(self) -> T { self.out }
 
     fn get_ref<'a>(&'a self) -> &'a T { &self.out }
@@ -203,11 +226,145 @@ impl<T: Writer> Terminal<T> for TerminfoTerminal<T> {
 }
 
 impl<T: Writer> TerminfoTerminal<T> {
+    /// Creates a terminal writing to `out` with the capabilities described
+    /// by the given terminfo entry, rather than the one for `TERM`.
+    ///
+    /// 24-bit colors are used if the `COLORTERM` environment variable is
+    /// `truecolor` or `24bit`, and the entry supports setting colors.
+    pub fn new_with_terminfo(out: T, terminfo: Box<TermInfo>) -> TerminfoTerminal<T> {
+        let nc = if terminfo.strings.find_equiv(&("setaf")).is_some()
+                 && terminfo.strings.find_equiv(&("setab")).is_some() {
+                     terminfo.numbers.find_equiv(&("colors")).map_or(0, |&n| n)
+                 } else { 0 };
+
+        let truecolor = nc > 0 && os::getenv("COLORTERM").map_or(false, |s| {
+            "truecolor" == s.as_slice() || "24bit" == s.as_slice()
+        });
+
+        TerminfoTerminal {out: out, ti: terminfo, num_colors: nc, truecolor: truecolor}
+    }
+
+    /// Returns the number of colors the terminal supports, not counting
+    /// 24-bit colors.
+    pub fn num_colors(&self) -> u16 { self.num_colors }
+
+    /// Returns whether 24-bit colors are written directly to the terminal,
+    /// rather than approximated by the closest supported color.
+    pub fn supports_truecolor(&self) -> bool { self.truecolor }
+
+    /// Sets whether 24-bit colors are written directly to the terminal,
+    /// overriding the detection from `COLORTERM`.
+    pub fn set_truecolor(&mut self, truecolor: bool) { self.truecolor = truecolor }
+
+    /// Returns the terminfo entry describing the terminal.
+    pub fn terminfo<'a>(&'a self) -> &'a TermInfo { &*self.ti }
+
+    // The size of the terminal given by the `COLUMNS` and `LINES` environment
+    // variables, or failing that by the terminfo entry.
+    fn default_size(&self) -> Option<(uint, uint)> {
+        self.size_from(os::getenv("COLUMNS"), os::getenv("LINES"))
+    }
+
+    // The size given by the values of `COLUMNS` and `LINES`, or failing that
+    // by the terminfo entry.
+    fn size_from(&self, columns: Option<String>,
+                 lines: Option<String>) -> Option<(uint, uint)> {
+        let parse = |s: Option<String>| s.and_then(|s| from_str::<uint>(s.as_slice()));
+        match (parse(columns), parse(lines)) {
+            (Some(cols), Some(lines)) => return Some((cols, lines)),
+            _ => {}
+        }
+        match (self.ti.numbers.find_equiv(&("cols")), self.ti.numbers.find_equiv(&("lines"))) {
+            (Some(&cols), Some(&lines)) => Some((cols as uint, lines as uint)),
+            _ => None
+        }
+    }
+
     fn dim_if_necessary(&self, color: color::Color) -> color::Color {
         if color >= self.num_colors && color >= 8 && color < 16 {
             color-8
+        } else if color >= self.num_colors && color >= 16 && color < 256 {
+            let (r, g, b) = color::to_rgb(color);
+            color::from_rgb(r, g, b, self.num_colors)
         } else { color }
     }
+
+    // Writes the expansion of the string capability `cmd` with the given
+    // parameters, returning `Ok(false)` if the terminal doesn't have it.
+    fn apply_cap(&mut self, cmd: &str, params: &[Param]) -> IoResult<bool> {
+        let s = match self.ti.strings.find_equiv(&cmd) {
+            Some(cap) => expand(cap.as_slice(), params, &mut Variables::new()),
+            None => return Ok(false)
+        };
+        match s {
+            Ok(s) => {
+                try!(self.out.write(s.as_slice()));
+                Ok(true)
+            }
+            Err(e) => {
+                debug!("error expanding terminfo capability `{}`: {}", cmd, e);
+                Ok(false)
+            }
+        }
+    }
+
+    // Applies the capability `cmd` taking a count of `n`, or if the terminal
+    // doesn't have it, the capability `cmd1` `n` times.
+    fn apply_cap_n(&mut self, cmd: &str, cmd1: &str, n: uint) -> IoResult<bool> {
+        if n == 0 {
+            return Ok(true);
+        }
+        if self.ti.strings.find_equiv(&cmd).is_some() {
+            return self.apply_cap(cmd, [Number(n as int)]);
+        }
+        for _ in range(0, n) {
+            if !try!(self.apply_cap(cmd1, [])) {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(unix)]
+mod tty {
+    use libc;
+    use libc::{c_int, c_ulong, c_ushort};
+
+    #[repr(C)]
+    struct winsize {
+        ws_row: c_ushort,
+        ws_col: c_ushort,
+        ws_xpixel: c_ushort,
+        ws_ypixel: c_ushort,
+    }
+
+    #[cfg(target_os = "linux")]
+    #[cfg(target_os = "android")]
+    static TIOCGWINSZ: c_ulong = 0x5413;
+    #[cfg(not(target_os = "linux"), not(target_os = "android"))]
+    static TIOCGWINSZ: c_ulong = 0x40087468;
+
+    extern {
+        fn ioctl(fd: c_int, request: c_ulong, ws: *mut winsize) -> c_int;
+    }
+
+    // Returns the size of the terminal connected to stdout, stderr or stdin,
+    // whichever is found first.
+    pub fn size() -> Option<(uint, uint)> {
+        for fd in [libc::STDOUT_FILENO, libc::STDERR_FILENO, libc::STDIN_FILENO].iter() {
+            let mut ws = winsize { ws_row: 0, ws_col: 0, ws_xpixel: 0, ws_ypixel: 0 };
+            if unsafe { ioctl(*fd, TIOCGWINSZ, &mut ws) } == 0 && ws.ws_col > 0 {
+                return Some((ws.ws_col as uint, ws.ws_row as uint));
+            }
+        }
+        None
+    }
+}
+
+#[cfg(not(unix))]
+mod tty {
+    pub fn size() -> Option<(uint, uint)> { None }
 }
 
 
@@ -221,3 +378,113 @@ impl<T: Writer> Writer for TerminfoTerminal<T> {
     }
 }
 
+
+#[cfg(test)]
+mod test {
+    use std::io::{BufReader, MemWriter};
+    use std::str;
+
+    use clear;
+    use color;
+    use cursor;
+    use Terminal;
+    use super::TerminfoTerminal;
+    use super::parser::compiled::parse;
+
+    fn terminal(compiled: &[u8]) -> TerminfoTerminal<MemWriter> {
+        let ti = parse(&mut BufReader::new(compiled), false).unwrap();
+        let mut t = TerminfoTerminal::new_with_terminfo(MemWriter::new(), ti);
+        t.set_truecolor(false);
+        t
+    }
+
+    fn term_8color() -> TerminfoTerminal<MemWriter> {
+        terminal(include_bin!("test/rust-test-8color"))
+    }
+
+    fn term_256color() -> TerminfoTerminal<MemWriter> {
+        terminal(include_bin!("test/rust-test-256color"))
+    }
+
+    fn output(t: TerminfoTerminal<MemWriter>) -> String {
+        str::from_utf8(t.unwrap().unwrap().as_slice()).unwrap().to_string()
+    }
+
+    #[test]
+    fn test_num_colors() {
+        assert_eq!(term_8color().num_colors(), 8);
+        assert_eq!(term_256color().num_colors(), 256);
+    }
+
+    #[test]
+    fn test_fg_256color() {
+        let mut t = term_256color();
+        assert!(t.fg(color::RED).unwrap());
+        assert!(t.fg(color::BRIGHT_RED).unwrap());
+        assert!(t.bg(color::cube(5, 0, 5)).unwrap());
+        assert!(t.fg(color::gray(23)).unwrap());
+        assert_eq!(output(t), "\x1b[31m\x1b[91m\x1b[48;5;201m\x1b[38;5;255m".to_string());
+    }
+
+    #[test]
+    fn test_fg_downgraded() {
+        let mut t = term_8color();
+        assert!(t.fg(color::BRIGHT_RED).unwrap());
+        assert!(t.fg(color::cube(5, 0, 0)).unwrap());
+        assert!(t.bg(color::gray(0)).unwrap());
+        assert_eq!(output(t), "\x1b[31m\x1b[31m\x1b[40m".to_string());
+    }
+
+    #[test]
+    fn test_rgb() {
+        let mut t = term_256color();
+        assert!(t.fg_rgb(255, 0, 255).unwrap());
+        t.set_truecolor(true);
+        assert!(t.supports_truecolor());
+        assert!(t.fg_rgb(1, 2, 3).unwrap());
+        assert!(t.bg_rgb(255, 128, 0).unwrap());
+        assert_eq!(output(t),
+                   "\x1b[95m\x1b[38;2;1;2;3m\x1b[48;2;255;128;0m".to_string());
+    }
+
+    #[test]
+    fn test_cursor() {
+        let mut t = term_256color();
+        assert!(t.cursor(cursor::Position(0, 0)).unwrap());
+        assert!(t.cursor(cursor::Position(9, 19)).unwrap());
+        assert!(t.cursor(cursor::Up(3)).unwrap());
+        assert!(t.cursor(cursor::Right(12)).unwrap());
+        assert!(t.cursor(cursor::Save).unwrap());
+        assert!(t.cursor(cursor::Hide).unwrap());
+        assert_eq!(output(t), "\x1b[1;1H\x1b[10;20H\x1b[3A\x1b[12C\x1b7\x1b[?25l".to_string());
+    }
+
+    #[test]
+    fn test_cursor_repeated() {
+        let mut t = term_8color();
+        assert!(t.cursor(cursor::Up(3)).unwrap());
+        assert!(t.cursor(cursor::Left(2)).unwrap());
+        assert!(t.cursor(cursor::Down(0)).unwrap());
+        assert_eq!(output(t), "\x1b[A\x1b[A\x1b[A\x08\x08".to_string());
+    }
+
+    #[test]
+    fn test_clear() {
+        let mut t = term_8color();
+        assert!(t.clear(clear::ToEndOfLine).unwrap());
+        assert!(t.clear(clear::Screen).unwrap());
+        // no el1 capability
+        assert!(!t.clear(clear::ToStartOfLine).unwrap());
+        assert_eq!(output(t), "\x1b[K\x1b[H\x1b[2J".to_string());
+    }
+
+    #[test]
+    fn test_size_from() {
+        let s = |s: &str| Some(s.to_string());
+        assert_eq!(term_8color().size_from(None, None), Some((80, 24)));
+        assert_eq!(term_256color().size_from(None, None), Some((132, 43)));
+        assert_eq!(term_8color().size_from(s("100"), s("30")), Some((100, 30)));
+        assert_eq!(term_8color().size_from(s("100"), None), Some((80, 24)));
+        assert_eq!(term_8color().size_from(s("wide"), s("30")), Some((80, 24)));
+    }
+}
@@ -318,18 +318,22 @@ pub fn msys_terminfo() -> Box<TermInfo> {
     strings.insert("bold".to_string(), Vec::from_slice(b"\x1B[1m"));
     strings.insert("setaf".to_string(), Vec::from_slice(b"\x1B[3%p1%dm"));
     strings.insert("setab".to_string(), Vec::from_slice(b"\x1B[4%p1%dm"));
+    let mut numbers = HashMap::new();
+    numbers.insert("colors".to_string(), 8u16);
     box TermInfo {
         names: vec!("cygwin".to_string()), // msys is a fork of an older cygwin version
         bools: HashMap::new(),
-        numbers: HashMap::new(),
+        numbers: numbers,
         strings: strings
     }
 }
 
 #[cfg(test)]
 mod test {
+    use std::io::BufReader;
 
     use super::{boolnames, boolfnames, numnames, numfnames, stringnames, stringfnames};
+    use super::parse;
 
     #[test]
     fn test_veclens() {
@@ -339,9 +343,25 @@ mod test {
     }
 
     #[test]
-    #[ignore(reason = "no ncurses on buildbots, needs a bundled terminfo file to test against")]
     fn test_parse() {
-        // FIXME #6870: Distribute a compiled file in src/tests and test there
-        // parse(io::fs_reader(&p("/usr/share/terminfo/r/rxvt-256color")).unwrap(), false);
+        let compiled = include_bin!("../test/rust-test-8color");
+        let ti = parse(&mut BufReader::new(compiled), false).unwrap();
+        assert_eq!(ti.names, vec!("rust-test-8color".to_string(),
+                                  "test fixture for libterm with 8 colors".to_string()));
+        assert_eq!(ti.numbers.find_equiv(&("colors")), Some(&8));
+        assert_eq!(ti.numbers.find_equiv(&("cols")), Some(&80));
+        assert_eq!(ti.numbers.find_equiv(&("lines")), Some(&24));
+        assert_eq!(ti.strings.find_equiv(&("setaf")), Some(&Vec::from_slice(b"\x1b[3%p1%dm")));
+        assert_eq!(ti.strings.find_equiv(&("el1")), None);
+
+        let ti = parse(&mut BufReader::new(compiled), true).unwrap();
+        assert_eq!(ti.numbers.find_equiv(&("max_colors")), Some(&8));
+        assert_eq!(ti.strings.find_equiv(&("cursor_address")),
+                   Some(&Vec::from_slice(b"\x1b[%i%p1%d;%p2%dH")));
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!(parse(&mut BufReader::new(b"\x1a\x02"), false).is_err());
     }
 }
@@ -12,7 +12,7 @@
 
 // FIXME (#13400): this is only a tiny fraction of the Windows console api
 
-extern crate libc;
+use libc;
 
 use std::io::IoResult;
 
@@ -36,6 +36,7 @@
 #![feature(asm, macro_rules, phase)]
 
 extern crate getopts;
+extern crate libc;
 extern crate regex;
 extern crate serialize;
 extern crate term;
@@ -46,7 +47,7 @@ use stats::Stats;
 use time::precise_time_ns;
 use getopts::{OptGroup, optflag, optopt};
 use regex::Regex;
-use serialize::{json, Decodable};
+use serialize::{json, Decodable, Encodable};
 use serialize::json::{Json, ToJson};
 use term::Terminal;
 use term::color::{Color, RED, YELLOW, GREEN, CYAN};
@@ -57,11 +58,12 @@ use std::fmt;
 use std::fmt::Show;
 use std::from_str::FromStr;
 use std::io::stdio::StdWriter;
-use std::io::{File, ChanReader, ChanWriter};
+use std::io::{File, ChanReader, ChanWriter, LineBufferedWriter};
 use std::io;
 use std::os;
 use std::string::String;
 use std::task::TaskBuilder;
+use std::time::Duration;
 
 // to be used by rustc to compile tests in libtest
 pub mod test {
@@ -186,6 +188,7 @@ pub struct TestDesc {
     pub name: TestName,
     pub ignore: bool,
     pub should_fail: bool,
+    pub timeout: Option<u64>,
 }
 
 #[deriving(Show)]
@@ -228,6 +231,43 @@ pub enum MetricChange {
 
 pub type MetricDiff = TreeMap<String,MetricChange>;
 
+/// The results of a single benchmark in a `Baseline`.
+#[deriving(Clone, Encodable, Decodable, PartialEq, Show)]
+pub struct BaselineBench {
+    median: f64,
+    median_abs_dev: f64,
+    mb_s: uint,
+    samples: Vec<f64>,
+}
+
+/// The results of the benchmarks of a run, saved under a name with
+/// `--bench-baseline` so that later runs can be compared against them with
+/// `--bench-compare`.
+#[deriving(Clone, PartialEq)]
+pub struct Baseline(TreeMap<String,BaselineBench>);
+
+/// Analysis of the change of a single benchmark between two runs. The first
+/// value is the change of the median time per iteration in percent, the
+/// second the p-value of a Mann-Whitney U test between the samples of both
+/// runs.
+#[deriving(PartialEq, Show)]
+pub enum BenchChange {
+    BenchAdded,
+    BenchRemoved,
+    BenchUnchanged(f64, f64),
+    BenchImproved(f64, f64),
+    BenchRegressed(f64, f64),
+}
+
+pub type BaselineDiff = TreeMap<String,BenchChange>;
+
+// Changes of benchmarks with a p-value below this are significant.
+static BENCH_SIGNIFICANCE: f64 = 0.05;
+
+// Tests that run for longer than this many milliseconds are reported as
+// still running.
+static TEST_WARN_TIMEOUT_MS: u64 = 60 * 1000;
+
 // The default console test runner. It accepts the command line
 // arguments and a vector of test_descs.
 pub fn test_main(args: &[String], tests: Vec<TestDescAndFn> ) {
@@ -237,9 +277,16 @@ pub fn test_main(args: &[String], tests: Vec<TestDescAndFn> ) {
             Some(Err(msg)) => fail!("{}", msg),
             None => return
         };
-    match run_tests_console(&opts, tests) {
-        Ok(true) => {}
-        Ok(false) => fail!("Some tests failed"),
+    match run_console(&opts, tests) {
+        Ok((true, _)) => {}
+        Ok((false, 0)) => fail!("Some tests failed"),
+        Ok((false, _)) => {
+            // The tasks of the tests that timed out may never finish, and the
+            // runtime waits for all tasks before exiting, so leave right away
+            // once everything printed so far is written out.
+            io::stdio::flush();
+            unsafe { libc::exit(101) }
+        }
         Err(e) => fail!("io error when running tests: {}", e),
     }
 }
@@ -268,6 +315,15 @@ pub enum ColorConfig {
     NeverColor,
 }
 
+/// How the progress and results of a test run are written to stdout.
+#[deriving(Clone, PartialEq, Show)]
+pub enum OutputFormat {
+    /// Human readable lines, in color if the terminal supports it.
+    PrettyFormat,
+    /// One JSON object per line for each event of the run.
+    JsonFormat,
+}
+
 pub struct TestOpts {
     pub filter: Option<Regex>,
     pub run_ignored: bool,
@@ -277,9 +333,13 @@ pub struct TestOpts {
     pub ratchet_noise_percent: Option<f64>,
     pub save_metrics: Option<Path>,
     pub test_shard: Option<(uint,uint)>,
+    pub test_timeout: Option<u64>,
+    pub bench_baseline: Option<String>,
+    pub bench_compare: Option<String>,
     pub logfile: Option<Path>,
     pub nocapture: bool,
     pub color: ColorConfig,
+    pub format: OutputFormat,
 }
 
 impl TestOpts {
@@ -294,9 +354,13 @@ impl TestOpts {
             ratchet_noise_percent: None,
             save_metrics: None,
             test_shard: None,
+            test_timeout: None,
+            bench_baseline: None,
+            bench_compare: None,
             logfile: None,
             nocapture: false,
             color: AutoColor,
+            format: PrettyFormat,
         }
     }
 }
@@ -318,21 +382,34 @@ fn optgroups() -> Vec<getopts::OptGroup> {
       getopts::optopt("", "ratchet-noise-percent",
                      "Tests within N% of the recorded metrics will be \
                       considered as passing", "PERCENTAGE"),
+      getopts::optopt("", "bench-baseline", "Run benchmarks and save their \
+                                             results as the baseline NAME",
+                     "NAME"),
+      getopts::optopt("", "bench-compare", "Run benchmarks and compare their \
+                                            results to the baseline NAME, \
+                                            failing on significant \
+                                            regressions", "NAME"),
       getopts::optopt("", "logfile", "Write logs to the specified file instead \
                           of stdout", "PATH"),
       getopts::optopt("", "test-shard", "run shard A, of B shards, worth of the testsuite",
                      "A.B"),
+      getopts::optopt("", "test-timeout", "fail tests that run for longer than \
+                                           MS milliseconds (default: no \
+                                           limit)", "MS"),
       getopts::optflag("", "nocapture", "don't capture stdout/stderr of each \
                                          task, allow printing directly"),
       getopts::optopt("", "color", "Configure coloring of output:
             auto   = colorize if stdout is a tty and tests are run on serially (default);
             always = always colorize output;
-            never  = never colorize output;", "auto|always|never"))
+            never  = never colorize output;", "auto|always|never"),
+      getopts::optopt("", "format", "Configure formatting of output:
+            pretty = human readable output (default);
+            json   = one JSON object per line for each event", "pretty|json"))
 }
 
 fn usage(binary: &str) {
     let message = format!("Usage: {} [OPTIONS] [FILTER]", binary);
-    println!(r"{usage}
+    println!(r#"{usage}
 
 The FILTER regex is tested against the name of all tests to run, and
 only those tests that match are run.
@@ -344,6 +421,23 @@ All tests have their standard output and standard error captured by default.
 This can be overridden with the --nocapture flag or the RUST_TEST_NOCAPTURE=1
 environment variable. Logging is not captured by default.
 
+Tests that run for longer than 60 seconds are reported as still running. Tests
+that run for longer than the limit given with the --test-timeout flag or the
+RUST_TEST_TIMEOUT environment variable, or for a single test with the #[timeout]
+attribute, are failed. There is no limit by default.
+
+The results of benchmarks can be saved with --bench-baseline NAME, and a later
+run can be compared to them with --bench-compare NAME. Baselines are stored in
+NAME.bench.json, in the directory given by the RUST_BENCH_DIR environment
+variable or else in the current one. A benchmark only counts as improved or
+regressed if a Mann-Whitney U test finds the samples of the two runs to differ
+significantly (p < 0.05).
+
+With --format json, every event is written to stdout as a JSON object on a
+line of its own. Output of tests that isn't captured (see --nocapture) is
+written as "output" events line by line as the tests print it, and the human
+readable failure listing and ratchet report are omitted.
+
 Test Attributes:
 
     #[test]        - Indicates a function is a test to be run. This function
@@ -356,7 +450,9 @@ Test Attributes:
                      test, then the test runner will ignore these tests during
                      normal test runs. Running with --ignored will run these
                      tests. This may also be written as #[ignore(cfg(...))] to
-                     ignore the test on certain configurations.",
+                     ignore the test on certain configurations.
+    #[timeout(MS)] - The test runner will fail this test if it runs for longer
+                     than MS milliseconds."#,
              usage = getopts::usage(message.as_slice(),
                                     optgroups().as_slice()));
 }
@@ -387,7 +483,11 @@ pub fn parse_opts(args: &[String]) -> Option<OptRes> {
     let logfile = matches.opt_str("logfile");
     let logfile = logfile.map(|s| Path::new(s));
 
-    let run_benchmarks = matches.opt_present("bench");
+    let bench_baseline = matches.opt_str("bench-baseline");
+    let bench_compare = matches.opt_str("bench-compare");
+
+    let run_benchmarks = matches.opt_present("bench") ||
+        bench_baseline.is_some() || bench_compare.is_some();
     let run_tests = ! run_benchmarks ||
         matches.opt_present("test");
 
@@ -404,6 +504,18 @@ pub fn parse_opts(args: &[String]) -> Option<OptRes> {
     let test_shard = matches.opt_str("test-shard");
     let test_shard = opt_shard(test_shard);
 
+    let test_timeout = match matches.opt_str("test-timeout")
+                                    .or_else(|| os::getenv("RUST_TEST_TIMEOUT")) {
+        None => None,
+        Some(s) => match from_str::<u64>(s.as_slice()) {
+            Some(0) => None,
+            Some(ms) => Some(ms),
+            None => return Some(Err(format!("argument for --test-timeout must \
+                                             be a number of milliseconds \
+                                             (was {})", s))),
+        },
+    };
+
     let mut nocapture = matches.opt_present("nocapture");
     if !nocapture {
         nocapture = os::getenv("RUST_TEST_NOCAPTURE").is_some();
@@ -419,6 +531,15 @@ pub fn parse_opts(args: &[String]) -> Option<OptRes> {
                                             v))),
     };
 
+    let format = match matches.opt_str("format").as_ref().map(|s| s.as_slice()) {
+        Some("pretty") | None => PrettyFormat,
+        Some("json") => JsonFormat,
+
+        Some(v) => return Some(Err(format!("argument for --format must be \
+                                            pretty or json (was {})",
+                                            v))),
+    };
+
     let test_opts = TestOpts {
         filter: filter,
         run_ignored: run_ignored,
@@ -428,9 +549,13 @@ pub fn parse_opts(args: &[String]) -> Option<OptRes> {
         ratchet_noise_percent: ratchet_noise_percent,
         save_metrics: save_metrics,
         test_shard: test_shard,
+        test_timeout: test_timeout,
+        bench_baseline: bench_baseline,
+        bench_compare: bench_compare,
         logfile: logfile,
         nocapture: nocapture,
         color: color,
+        format: format,
     };
 
     Some(Ok(test_opts))
@@ -461,6 +586,7 @@ pub fn opt_shard(maybestr: Option<String>) -> Option<(uint,uint)> {
 pub struct BenchSamples {
     ns_iter_summ: stats::Summary<f64>,
     mb_s: uint,
+    samples: Vec<f64>,
 }
 
 #[deriving(Clone, PartialEq)]
@@ -481,14 +607,21 @@ struct ConsoleTestState<T> {
     log_out: Option<File>,
     out: OutputLocation<T>,
     use_color: bool,
+    format: OutputFormat,
+    test_shard: Option<(uint, uint)>,
     total: uint,
     passed: uint,
     failed: uint,
     ignored: uint,
     measured: uint,
+    timed_out: uint,
     metrics: MetricMap,
+    baseline: Baseline,
+    compare_to: Option<(String, Baseline)>,
     failures: Vec<(TestDesc, Vec<u8> )> ,
     max_name_len: uint, // number of columns to fill when aligning names
+    // the `test foo ... ` written for a test whose result is still to come
+    open_line: Option<String>,
 }
 
 impl<T: Writer> ConsoleTestState<T> {
@@ -498,6 +631,13 @@ impl<T: Writer> ConsoleTestState<T> {
             Some(ref path) => Some(try!(File::create(path))),
             None => None
         };
+        let compare_to = match opts.bench_compare {
+            Some(ref name) => {
+                let old = try!(Baseline::load(&baseline_path(name.as_slice())));
+                Some((name.clone(), old))
+            }
+            None => None
+        };
         let out = match term::stdout() {
             None => Raw(io::stdio::stdout_raw()),
             Some(t) => Pretty(t)
@@ -507,14 +647,20 @@ impl<T: Writer> ConsoleTestState<T> {
             out: out,
             log_out: log_out,
             use_color: use_color(opts),
+            format: opts.format,
+            test_shard: opts.test_shard,
             total: 0u,
             passed: 0u,
             failed: 0u,
             ignored: 0u,
             measured: 0u,
+            timed_out: 0u,
             metrics: MetricMap::new(),
+            baseline: Baseline::new(),
+            compare_to: compare_to,
             failures: Vec::new(),
             max_name_len: 0u,
+            open_line: None,
         })
     }
 
@@ -581,17 +727,87 @@ impl<T: Writer> ConsoleTestState<T> {
 
     pub fn write_run_start(&mut self, len: uint) -> io::IoResult<()> {
         self.total = len;
-        let noun = if len != 1 { "tests" } else { "test" };
-        self.write_plain(format!("\nrunning {} {}\n", len, noun).as_slice())
+        match self.format {
+            JsonFormat => {
+                let mut event = json_event("suite", "started");
+                event.insert("test_count".to_string(), len.to_json());
+                match self.test_shard {
+                    Some((a, b)) => {
+                        let mut shard = TreeMap::new();
+                        shard.insert("index".to_string(), a.to_json());
+                        shard.insert("count".to_string(), b.to_json());
+                        event.insert("shard".to_string(), json::Object(shard));
+                    }
+                    None => {}
+                }
+                self.write_json(event)
+            }
+            PrettyFormat => {
+                let noun = if len != 1 { "tests" } else { "test" };
+                self.write_plain(format!("\nrunning {} {}\n", len,
+                                         noun).as_slice())
+            }
+        }
     }
 
     pub fn write_test_start(&mut self, test: &TestDesc,
                             align: NamePadding) -> io::IoResult<()> {
-        let name = test.padded_name(self.max_name_len, align);
-        self.write_plain(format!("test {} ... ", name).as_slice())
+        match self.format {
+            JsonFormat => {
+                let mut event = json_event("test", "started");
+                event.insert("name".to_string(), test.name.to_string().to_json());
+                self.write_json(event)
+            }
+            PrettyFormat => {
+                let name = test.padded_name(self.max_name_len, align);
+                let line = format!("test {} ... ", name);
+                try!(self.write_plain(line.as_slice()));
+                self.open_line = Some(line);
+                Ok(())
+            }
+        }
     }
 
-    pub fn write_result(&mut self, result: &TestResult) -> io::IoResult<()> {
+    pub fn write_timeout(&mut self, test: &TestDesc) -> io::IoResult<()> {
+        match self.format {
+            JsonFormat => {
+                let mut event = json_event("test", "timeout");
+                event.insert("name".to_string(), test.name.to_string().to_json());
+                self.write_json(event)
+            }
+            PrettyFormat => {
+                // Don't break into the line of a test that is waiting for its
+                // result; finish it, and start it again after the warning.
+                let open_line = self.open_line.clone();
+                if open_line.is_some() {
+                    try!(self.write_plain("\n"));
+                }
+                try!(self.write_plain(format!("test {} has been running for over \
+                                               {} seconds\n", test.name,
+                                              TEST_WARN_TIMEOUT_MS / 1000).as_slice()));
+                match open_line {
+                    Some(line) => self.write_plain(line.as_slice()),
+                    None => Ok(()),
+                }
+            }
+        }
+    }
+
+    pub fn write_json(&mut self, event: json::Object) -> io::IoResult<()> {
+        let line = format!("{}\n", json::Object(event));
+        self.write_plain(line.as_slice())
+    }
+
+    pub fn write_result(&mut self, test: &TestDesc, result: &TestResult,
+                        stdout: &[u8]) -> io::IoResult<()> {
+        self.open_line = None;
+        match self.format {
+            JsonFormat => {
+                let event = json_result(test, result, stdout);
+                return self.write_json(event)
+            }
+            PrettyFormat => {}
+        }
         try!(match *result {
             TrOk => self.write_ok(),
             TrFailed => self.write_failed(),
@@ -705,6 +921,75 @@ impl<T: Writer> ConsoleTestState<T> {
         Ok(())
     }
 
+    pub fn write_baseline_diff(&mut self, name: &str,
+                               diff: &BaselineDiff) -> io::IoResult<bool> {
+        let mut unchanged = 0u;
+        let mut improved = 0u;
+        let mut regressed = 0u;
+        let mut added = 0u;
+        let mut removed = 0u;
+
+        match self.format {
+            JsonFormat => {}
+            PrettyFormat => {
+                try!(self.write_plain(format!("\ncomparing with baseline: {}\n",
+                                              name).as_slice()));
+            }
+        }
+        for (k, v) in diff.iter() {
+            let (event, change) = match *v {
+                BenchAdded => { added += 1; ("added", None) }
+                BenchRemoved => { removed += 1; ("removed", None) }
+                BenchUnchanged(pct, p) => { unchanged += 1; ("unchanged", Some((pct, p))) }
+                BenchImproved(pct, p) => { improved += 1; ("improved", Some((pct, p))) }
+                BenchRegressed(pct, p) => { regressed += 1; ("regressed", Some((pct, p))) }
+            };
+            match self.format {
+                JsonFormat => {
+                    let mut json = json_event("baseline", event);
+                    json.insert("name".to_string(), k.to_json());
+                    match change {
+                        Some((pct, p)) => {
+                            json.insert("change_pct".to_string(), pct.to_json());
+                            json.insert("p_value".to_string(), p.to_json());
+                        }
+                        None => {}
+                    }
+                    try!(self.write_json(json));
+                    continue;
+                }
+                PrettyFormat => {}
+            }
+            try!(self.write_plain(format!("{}: ", *k).as_slice()));
+            match *v {
+                BenchAdded => try!(self.write_added()),
+                BenchRemoved => try!(self.write_removed()),
+                BenchUnchanged(..) => try!(self.write_plain("unchanged")),
+                BenchImproved(..) => try!(self.write_improved()),
+                BenchRegressed(..) => try!(self.write_regressed()),
+            }
+            match change {
+                Some((pct, p)) => {
+                    let s = format!(" {:+.2f}% (confidence {:.2f}%)\n",
+                                    pct, (1.0 - p) * 100.0);
+                    try!(self.write_plain(s.as_slice()));
+                }
+                None => try!(self.write_plain("\n")),
+            }
+        }
+        match self.format {
+            JsonFormat => {}
+            PrettyFormat => {
+                try!(self.write_plain(format!("result of comparison: {} added, \
+                                               {} removed, {} improved, \
+                                               {} regressed, {} unchanged\n",
+                                              added, removed, improved,
+                                              regressed, unchanged).as_slice()));
+            }
+        }
+        Ok(regressed == 0)
+    }
+
     pub fn write_run_finish(&mut self,
                             ratchet_metrics: &Option<Path>,
                             ratchet_pct: Option<f64>) -> io::IoResult<bool> {
@@ -713,28 +998,55 @@ impl<T: Writer> ConsoleTestState<T> {
         let ratchet_success = match *ratchet_metrics {
             None => true,
             Some(ref pth) => {
-                try!(self.write_plain(format!("\nusing metrics ratchet: {}\n",
-                                              pth.display()).as_slice()));
-                match ratchet_pct {
-                    None => (),
-                    Some(pct) =>
-                        try!(self.write_plain(format!("with noise-tolerance \
-                                                         forced to: {}%\n",
-                                                        pct).as_slice()))
-                }
                 let (diff, ok) = self.metrics.ratchet(pth, ratchet_pct);
-                try!(self.write_metric_diff(&diff));
+                match self.format {
+                    JsonFormat => {}
+                    PrettyFormat => {
+                        try!(self.write_plain(format!("\nusing metrics ratchet: {}\n",
+                                                      pth.display()).as_slice()));
+                        match ratchet_pct {
+                            None => (),
+                            Some(pct) =>
+                                try!(self.write_plain(format!("with noise-tolerance \
+                                                                 forced to: {}%\n",
+                                                                pct).as_slice()))
+                        }
+                        try!(self.write_metric_diff(&diff));
+                    }
+                }
                 ok
             }
         };
 
+        let compare_success = match self.compare_to.take() {
+            None => true,
+            Some((name, old)) => {
+                let diff = self.baseline.compare_to_old(&old);
+                try!(self.write_baseline_diff(name.as_slice(), &diff))
+            }
+        };
+
         let test_success = self.failed == 0u;
+        let success = ratchet_success && compare_success && test_success;
+
+        match self.format {
+            JsonFormat => {
+                let mut event = json_event("suite",
+                                           if success { "ok" } else { "failed" });
+                event.insert("passed".to_string(), self.passed.to_json());
+                event.insert("failed".to_string(), self.failed.to_json());
+                event.insert("ignored".to_string(), self.ignored.to_json());
+                event.insert("measured".to_string(), self.measured.to_json());
+                try!(self.write_json(event));
+                return Ok(success);
+            }
+            PrettyFormat => {}
+        }
+
         if !test_success {
             try!(self.write_failures());
         }
 
-        let success = ratchet_success && test_success;
-
         try!(self.write_plain("\ntest result: "));
         if success {
             // There's no parallelism at this point so it's safe to use color
@@ -749,6 +1061,69 @@ impl<T: Writer> ConsoleTestState<T> {
     }
 }
 
+fn json_event(kind: &str, event: &str) -> json::Object {
+    let mut map = TreeMap::new();
+    map.insert("type".to_string(), kind.to_string().to_json());
+    map.insert("event".to_string(), event.to_string().to_json());
+    map
+}
+
+// Writes the output of a test that isn't captured as JSON events, for
+// `--format json` with `--nocapture`.
+struct JsonOutputWriter<W> {
+    name: String,
+    stream: &'static str,
+    out: W,
+}
+
+impl<W: Writer> Writer for JsonOutputWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::IoResult<()> {
+        let mut event = json_event("test", "output");
+        event.insert("name".to_string(), self.name.to_json());
+        event.insert(self.stream.to_string(),
+                     String::from_utf8_lossy(buf).into_string().to_json());
+        let line = format!("{}\n", json::Object(event));
+        self.out.write(line.as_bytes())
+    }
+
+    fn flush(&mut self) -> io::IoResult<()> {
+        self.out.flush()
+    }
+}
+
+fn json_result(test: &TestDesc, result: &TestResult,
+               stdout: &[u8]) -> json::Object {
+    let mut event = match *result {
+        TrOk => json_event("test", "ok"),
+        TrFailed => json_event("test", "failed"),
+        TrIgnored => json_event("test", "ignored"),
+        TrMetrics(ref mm) => {
+            let mut event = json_event("metric", "ok");
+            let MetricMap(ref mm) = *mm;
+            event.insert("metrics".to_string(), mm.to_json());
+            event
+        }
+        TrBench(ref bs) => {
+            let mut event = json_event("bench", "ok");
+            let summ = &bs.ns_iter_summ;
+            event.insert("ns_per_iter".to_string(),
+                         (summ.median as u64).to_json());
+            event.insert("deviation".to_string(),
+                         ((summ.max - summ.min) as u64).to_json());
+            if bs.mb_s != 0 {
+                event.insert("mb_per_sec".to_string(), bs.mb_s.to_json());
+            }
+            event
+        }
+    };
+    event.insert("name".to_string(), test.name.to_string().to_json());
+    if stdout.len() > 0 {
+        let output = String::from_utf8_lossy(stdout).into_string();
+        event.insert("stdout".to_string(), output.to_json());
+    }
+    event
+}
+
 pub fn fmt_metrics(mm: &MetricMap) -> String {
     let MetricMap(ref mm) = *mm;
     let v : Vec<String> = mm.iter()
@@ -773,14 +1148,26 @@ pub fn fmt_bench_samples(bs: &BenchSamples) -> String {
 
 // A simple console test runner
 pub fn run_tests_console(opts: &TestOpts, tests: Vec<TestDescAndFn> ) -> io::IoResult<bool> {
+    run_console(opts, tests).map(|(success, _)| success)
+}
+
+// Runs the tests on the console, returning whether they all passed and how
+// many of them timed out.
+fn run_console(opts: &TestOpts, tests: Vec<TestDescAndFn>) -> io::IoResult<(bool, uint)> {
 
     fn callback<T: Writer>(event: &TestEvent, st: &mut ConsoleTestState<T>) -> io::IoResult<()> {
         match (*event).clone() {
             TeFiltered(ref filtered_tests) => st.write_run_start(filtered_tests.len()),
             TeWait(ref test, padding) => st.write_test_start(test, padding),
+            TeTimeout(ref test) => st.write_timeout(test),
+            TeTimedOut(test, limit_ms) => {
+                st.timed_out += 1;
+                let msg = format!("test timed out after {} ms", limit_ms);
+                callback(&TeResult(test, TrFailed, msg.into_bytes()), st)
+            }
             TeResult(test, result, stdout) => {
                 try!(st.write_log(&test, &result));
-                try!(st.write_result(&result));
+                try!(st.write_result(&test, &result, stdout.as_slice()));
                 match result {
                     TrOk => st.passed += 1,
                     TrIgnored => st.ignored += 1,
@@ -798,6 +1185,7 @@ pub fn run_tests_console(opts: &TestOpts, tests: Vec<TestDescAndFn> ) -> io::IoR
                         st.measured += 1
                     }
                     TrBench(bs) => {
+                        st.baseline.insert_bench(test.name.as_slice(), &bs);
                         st.metrics.insert_metric(test.name.as_slice(),
                                                  bs.ns_iter_summ.median,
                                                  bs.ns_iter_summ.max - bs.ns_iter_summ.min);
@@ -832,11 +1220,32 @@ pub fn run_tests_console(opts: &TestOpts, tests: Vec<TestDescAndFn> ) -> io::IoR
         None => (),
         Some(ref pth) => {
             try!(st.metrics.save(pth));
-            try!(st.write_plain(format!("\nmetrics saved to: {}",
-                                          pth.display()).as_slice()));
+            match opts.format {
+                JsonFormat => {}
+                PrettyFormat => {
+                    try!(st.write_plain(format!("\nmetrics saved to: {}",
+                                                  pth.display()).as_slice()));
+                }
+            }
         }
     }
-    return st.write_run_finish(&opts.ratchet_metrics, opts.ratchet_noise_percent);
+    match opts.bench_baseline {
+        None => (),
+        Some(ref name) => {
+            let pth = baseline_path(name.as_slice());
+            try!(st.baseline.save(&pth));
+            match opts.format {
+                JsonFormat => {}
+                PrettyFormat => {
+                    try!(st.write_plain(format!("\nbaseline saved to: {}",
+                                                  pth.display()).as_slice()));
+                }
+            }
+        }
+    }
+    let success = try!(st.write_run_finish(&opts.ratchet_metrics,
+                                           opts.ratchet_noise_percent));
+    Ok((success, st.timed_out))
 }
 
 #[test]
@@ -846,27 +1255,35 @@ fn should_sort_failures_before_printing_them() {
     let test_a = TestDesc {
         name: StaticTestName("a"),
         ignore: false,
-        should_fail: false
+        should_fail: false,
+        timeout: None
     };
 
     let test_b = TestDesc {
         name: StaticTestName("b"),
         ignore: false,
-        should_fail: false
+        should_fail: false,
+        timeout: None
     };
 
     let mut st = ConsoleTestState {
         log_out: None,
         out: Raw(MemWriter::new()),
         use_color: false,
+        format: PrettyFormat,
+        test_shard: None,
         total: 0u,
         passed: 0u,
         failed: 0u,
         ignored: 0u,
         measured: 0u,
+        timed_out: 0u,
         max_name_len: 10u,
         metrics: MetricMap::new(),
-        failures: vec!((test_b, Vec::new()), (test_a, Vec::new()))
+        baseline: Baseline::new(),
+        compare_to: None,
+        failures: vec!((test_b, Vec::new()), (test_a, Vec::new())),
+        open_line: None,
     };
 
     st.write_failures().unwrap();
@@ -880,6 +1297,138 @@ fn should_sort_failures_before_printing_them() {
     assert!(apos < bpos);
 }
 
+#[test]
+fn should_write_one_json_object_per_event() {
+    use std::io::MemWriter;
+
+    let test = TestDesc {
+        name: StaticTestName("a"),
+        ignore: false,
+        should_fail: false,
+        timeout: None
+    };
+
+    let mut st = ConsoleTestState {
+        log_out: None,
+        out: Raw(MemWriter::new()),
+        use_color: false,
+        format: JsonFormat,
+        test_shard: Some((1, 2)),
+        total: 0u,
+        passed: 0u,
+        failed: 0u,
+        ignored: 0u,
+        measured: 0u,
+        timed_out: 0u,
+        max_name_len: 10u,
+        metrics: MetricMap::new(),
+        baseline: Baseline::new(),
+        compare_to: None,
+        failures: Vec::new(),
+        open_line: None,
+    };
+
+    st.write_run_start(1).unwrap();
+    st.write_test_start(&test, PadNone).unwrap();
+    st.write_result(&test, &TrFailed, b"oh\nno").unwrap();
+    st.failed += 1;
+    st.write_run_finish(&None, None).unwrap();
+    let s = match st.out {
+        Raw(ref m) => String::from_utf8_lossy(m.get_ref()).into_string(),
+        Pretty(_) => unreachable!()
+    };
+
+    let events: Vec<Json> = s.as_slice().lines().map(|line| {
+        json::from_str(line).unwrap()
+    }).collect();
+    assert_eq!(events.len(), 4);
+    let field = |i: uint, key: &str| -> Json {
+        events[i].find(&key.to_string()).unwrap().clone()
+    };
+    assert_eq!(field(0, "event"), json::String("started".to_string()));
+    assert_eq!(field(0, "test_count"), json::U64(1));
+    assert!(field(0, "shard").find(&"index".to_string()).is_some());
+    assert_eq!(field(1, "type"), json::String("test".to_string()));
+    assert_eq!(field(2, "event"), json::String("failed".to_string()));
+    assert_eq!(field(2, "stdout"), json::String("oh\nno".to_string()));
+    assert_eq!(field(3, "type"), json::String("suite".to_string()));
+    assert_eq!(field(3, "failed"), json::U64(1));
+}
+
+#[test]
+fn should_write_slow_test_warning_on_its_own_line() {
+    use std::io::MemWriter;
+
+    let test = TestDesc {
+        name: StaticTestName("a"),
+        ignore: false,
+        should_fail: false,
+        timeout: None
+    };
+
+    let mut st = ConsoleTestState {
+        log_out: None,
+        out: Raw(MemWriter::new()),
+        use_color: false,
+        format: PrettyFormat,
+        test_shard: None,
+        total: 0u,
+        passed: 0u,
+        failed: 0u,
+        ignored: 0u,
+        measured: 0u,
+        timed_out: 0u,
+        max_name_len: 10u,
+        metrics: MetricMap::new(),
+        baseline: Baseline::new(),
+        compare_to: None,
+        failures: Vec::new(),
+        open_line: None,
+    };
+
+    st.write_test_start(&test, PadNone).unwrap();
+    st.write_timeout(&test).unwrap();
+    st.write_result(&test, &TrOk, b"").unwrap();
+    st.write_timeout(&test).unwrap();
+    let s = match st.out {
+        Raw(ref m) => String::from_utf8_lossy(m.get_ref()).into_string(),
+        Pretty(_) => unreachable!()
+    };
+
+    let lines: Vec<&str> = s.as_slice().lines().collect();
+    assert_eq!(lines, vec!["test a ... ",
+                           "test a has been running for over 60 seconds",
+                           "test a ... ok",
+                           "test a has been running for over 60 seconds"]);
+}
+
+#[test]
+fn should_write_uncaptured_output_as_json_events() {
+    use std::io::MemWriter;
+
+    let mut w = LineBufferedWriter::new(JsonOutputWriter {
+        name: "a".to_string(),
+        stream: "stderr",
+        out: MemWriter::new(),
+    });
+    w.write(b"one\ntw").unwrap();
+    w.write(b"o\n").unwrap();
+    let s = String::from_utf8(w.unwrap().out.unwrap()).unwrap();
+
+    let events: Vec<Json> = s.as_slice().lines().map(|line| {
+        json::from_str(line).unwrap()
+    }).collect();
+    assert_eq!(events.len(), 2);
+    for (event, text) in events.iter().zip(["one\n", "two\n"].iter()) {
+        assert_eq!(event.find(&"event".to_string()).unwrap(),
+                   &json::String("output".to_string()));
+        assert_eq!(event.find(&"name".to_string()).unwrap(),
+                   &json::String("a".to_string()));
+        assert_eq!(event.find(&"stderr".to_string()).unwrap(),
+                   &json::String(text.to_string()));
+    }
+}
+
 fn use_color(opts: &TestOpts) -> bool {
     match opts.color {
         AutoColor => get_concurrency() == 1 && io::stdout().get_ref().isatty(),
@@ -893,10 +1442,58 @@ enum TestEvent {
     TeFiltered(Vec<TestDesc> ),
     TeWait(TestDesc, NamePadding),
     TeResult(TestDesc, TestResult, Vec<u8> ),
+    TeTimeout(TestDesc),
+    TeTimedOut(TestDesc, u64),
 }
 
 pub type MonitorMsg = (TestDesc, TestResult, Vec<u8> );
 
+// A test whose result hasn't come in yet.
+struct RunningTest {
+    desc: TestDesc,
+    started_ns: u64,
+    limit_ms: Option<u64>,
+    warned: bool,
+}
+
+impl RunningTest {
+    fn new(opts: &TestOpts, desc: &TestDesc) -> RunningTest {
+        RunningTest {
+            desc: desc.clone(),
+            started_ns: precise_time_ns(),
+            limit_ms: desc.timeout.or(opts.test_timeout),
+            warned: false,
+        }
+    }
+
+    fn elapsed_ms(&self, now_ns: u64) -> u64 {
+        (now_ns - self.started_ns) / 1000000
+    }
+
+    fn is_over_limit(&self, now_ns: u64) -> bool {
+        match self.limit_ms {
+            Some(ms) => self.elapsed_ms(now_ns) >= ms,
+            None => false,
+        }
+    }
+
+    fn needs_warning(&self, now_ns: u64) -> bool {
+        !self.warned && self.elapsed_ms(now_ns) >= TEST_WARN_TIMEOUT_MS
+    }
+
+    // The number of milliseconds until this test has to be looked at again,
+    // or None if it may run forever without anyone being told.
+    fn next_check_ms(&self, now_ns: u64) -> Option<u64> {
+        let warn_ms = if self.warned { None } else { Some(TEST_WARN_TIMEOUT_MS) };
+        let at = match (warn_ms, self.limit_ms) {
+            (Some(a), Some(b)) => Some(cmp::min(a, b)),
+            (a, b) => a.or(b),
+        };
+        let elapsed = self.elapsed_ms(now_ns);
+        at.map(|at| if at > elapsed { at - elapsed } else { 0 })
+    }
+}
+
 fn run_tests(opts: &TestOpts,
              tests: Vec<TestDescAndFn> ,
              callback: |e: TestEvent| -> io::IoResult<()>) -> io::IoResult<()> {
@@ -921,12 +1518,13 @@ fn run_tests(opts: &TestOpts,
 
     let mut remaining = filtered_tests;
     remaining.reverse();
-    let mut pending = 0;
+    let mut running: Vec<RunningTest> = Vec::new();
+    let mut timer = try!(io::Timer::new());
 
     let (tx, rx) = channel::<MonitorMsg>();
 
-    while pending > 0 || !remaining.is_empty() {
-        while pending < concurrency && !remaining.is_empty() {
+    while !running.is_empty() || !remaining.is_empty() {
+        while running.len() < concurrency && !remaining.is_empty() {
             let test = remaining.pop().unwrap();
             if concurrency == 1 {
                 // We are doing one test at a time so we can print the name
@@ -934,16 +1532,66 @@ fn run_tests(opts: &TestOpts,
                 // that hang forever.
                 try!(callback(TeWait(test.desc.clone(), test.testfn.padding())));
             }
+            running.push(RunningTest::new(opts, &test.desc));
             run_test(opts, !opts.run_tests, test, tx.clone());
-            pending += 1;
         }
 
-        let (desc, result, stdout) = rx.recv();
-        if concurrency != 1 {
-            try!(callback(TeWait(desc.clone(), PadNone)));
+        // Wait for the next result, waking up whenever a test has to be
+        // reported as slow or failed for running out of time.
+        let mut msg = None;
+        while msg.is_none() && !running.is_empty() {
+            let now = precise_time_ns();
+            let mut i = 0;
+            while i < running.len() {
+                if running[i].is_over_limit(now) {
+                    // The task of the test can't be killed, so it's left
+                    // running and its result is dropped if it ever comes in.
+                    let test = running.remove(i).unwrap();
+                    if concurrency != 1 {
+                        try!(callback(TeWait(test.desc.clone(), PadNone)));
+                    }
+                    try!(callback(TeTimedOut(test.desc, test.limit_ms.unwrap())));
+                    continue;
+                }
+                if running[i].needs_warning(now) {
+                    running.get_mut(i).warned = true;
+                    try!(callback(TeTimeout(running[i].desc.clone())));
+                }
+                i += 1;
+            }
+            if running.is_empty() {
+                break;
+            }
+
+            let wait_ms = running.iter().filter_map(|t| t.next_check_ms(now)).min();
+            match wait_ms {
+                None => msg = Some(rx.recv()),
+                Some(ms) => {
+                    let timeout = timer.oneshot(Duration::milliseconds(ms as i64));
+                    select! {
+                        m = rx.recv() => { msg = Some(m) },
+                        () = timeout.recv() => {}
+                    }
+                }
+            }
+        }
+
+        match msg {
+            Some((desc, result, stdout)) => {
+                match running.iter().position(|t| t.desc == desc) {
+                    Some(i) => {
+                        running.remove(i);
+                        if concurrency != 1 {
+                            try!(callback(TeWait(desc.clone(), PadNone)));
+                        }
+                        try!(callback(TeResult(desc, result, stdout)));
+                    }
+                    // A test that has already timed out
+                    None => {}
+                }
+            }
+            None => {}
         }
-        try!(callback(TeResult(desc, result, stdout)));
-        pending -= 1;
     }
 
     // All benchmarks run at the end, in serial.
@@ -1035,18 +1683,35 @@ pub fn run_test(opts: &TestOpts,
     fn run_test_inner(desc: TestDesc,
                       monitor_ch: Sender<MonitorMsg>,
                       nocapture: bool,
+                      format: OutputFormat,
                       testfn: proc():Send) {
         spawn(proc() {
             let (tx, rx) = channel();
             let mut reader = ChanReader::new(rx);
             let stdout = ChanWriter::new(tx.clone());
             let stderr = ChanWriter::new(tx);
-            let mut task = TaskBuilder::new().named(match desc.name {
+            let name = match desc.name {
                 DynTestName(ref name) => name.clone().to_string(),
                 StaticTestName(name) => name.to_string(),
-            });
+            };
+            let mut task = TaskBuilder::new().named(name.clone());
             if nocapture {
                 drop((stdout, stderr));
+                match format {
+                    // Keep stdout a stream of JSON objects, one per line.
+                    JsonFormat => {
+                        let json_writer = |stream| {
+                            box LineBufferedWriter::new(JsonOutputWriter {
+                                name: name.clone(),
+                                stream: stream,
+                                out: io::stdio::stdout_raw(),
+                            }) as Box<Writer + Send>
+                        };
+                        task = task.stdout(json_writer("stdout"));
+                        task = task.stderr(json_writer("stderr"));
+                    }
+                    PrettyFormat => {}
+                }
             } else {
                 task = task.stdout(box stdout as Box<Writer + Send>);
                 task = task.stderr(box stderr as Box<Writer + Send>);
@@ -1083,9 +1748,10 @@ pub fn run_test(opts: &TestOpts,
             monitor_ch.send((desc, TrMetrics(mm), Vec::new()));
             return;
         }
-        DynTestFn(f) => run_test_inner(desc, monitor_ch, opts.nocapture, f),
+        DynTestFn(f) => run_test_inner(desc, monitor_ch, opts.nocapture,
+                                       opts.format, f),
         StaticTestFn(f) => run_test_inner(desc, monitor_ch, opts.nocapture,
-                                          proc() f())
+                                          opts.format, proc() f())
     }
 }
 
@@ -1254,6 +1920,106 @@ impl MetricMap {
     }
 }
 
+/// The file that the baseline `name` is saved to and loaded from:
+/// `NAME.bench.json` in the directory named by the `RUST_BENCH_DIR`
+/// environment variable, or else in the current directory.
+pub fn baseline_path(name: &str) -> Path {
+    let dir = match os::getenv("RUST_BENCH_DIR") {
+        Some(dir) => Path::new(dir),
+        None => Path::new("."),
+    };
+    dir.join(format!("{}.bench.json", name))
+}
+
+impl Baseline {
+
+    pub fn new() -> Baseline {
+        Baseline(TreeMap::new())
+    }
+
+    /// Load a Baseline from a file.
+    pub fn load(p: &Path) -> io::IoResult<Baseline> {
+        let invalid = |err: String| {
+            io::IoError {
+                kind: io::InvalidInput,
+                desc: "invalid benchmark baseline",
+                detail: Some(format!("{}: {}", p.display(), err)),
+            }
+        };
+        let mut f = try!(File::open(p));
+        let value = match json::from_reader(&mut f as &mut io::Reader) {
+            Ok(value) => value,
+            Err(e) => return Err(invalid(e.to_string())),
+        };
+        let mut decoder = json::Decoder::new(value);
+        match Decodable::decode(&mut decoder) {
+            Ok(map) => Ok(Baseline(map)),
+            Err(e) => Err(invalid(e.to_string())),
+        }
+    }
+
+    /// Write a Baseline to a file.
+    pub fn save(&self, p: &Path) -> io::IoResult<()> {
+        let mut file = try!(File::create(p));
+        let Baseline(ref map) = *self;
+        let mut encoder = json::PrettyEncoder::new(&mut file as &mut io::Writer);
+        map.encode(&mut encoder)
+    }
+
+    /// Insert the results of the benchmark `name`.
+    pub fn insert_bench(&mut self, name: &str, bs: &BenchSamples) {
+        let bench = BaselineBench {
+            median: bs.ns_iter_summ.median,
+            median_abs_dev: bs.ns_iter_summ.median_abs_dev,
+            mb_s: bs.mb_s,
+            samples: bs.samples.clone(),
+        };
+        let Baseline(ref mut map) = *self;
+        map.insert(name.to_string(), bench);
+    }
+
+    /// Compare against another Baseline. A benchmark has only improved or
+    /// regressed if the samples of the two runs differ significantly, no
+    /// matter how much its median changed.
+    pub fn compare_to_old(&self, old: &Baseline) -> BaselineDiff {
+        let mut diff : BaselineDiff = TreeMap::new();
+        let Baseline(ref selfmap) = *self;
+        let Baseline(ref old) = *old;
+        for (k, vold) in old.iter() {
+            let r = match selfmap.find(k) {
+                None => BenchRemoved,
+                Some(v) => {
+                    let pct = if vold.median == 0.0 {
+                        0.0
+                    } else {
+                        100.0 * (v.median - vold.median) / vold.median
+                    };
+                    if v.samples.is_empty() || vold.samples.is_empty() {
+                        BenchUnchanged(pct, 1.0)
+                    } else {
+                        let test = stats::mann_whitney_u(v.samples.as_slice(),
+                                                         vold.samples.as_slice());
+                        if test.p_value >= BENCH_SIGNIFICANCE {
+                            BenchUnchanged(pct, test.p_value)
+                        } else if test.z > 0.0 {
+                            BenchRegressed(pct, test.p_value)
+                        } else {
+                            BenchImproved(pct, test.p_value)
+                        }
+                    }
+                }
+            };
+            diff.insert((*k).clone(), r);
+        }
+        for (k, _) in selfmap.iter() {
+            if !diff.contains_key(k) {
+                diff.insert((*k).clone(), BenchAdded);
+            }
+        }
+        diff
+    }
+}
+
 
 // Benchmarking
 
@@ -1303,6 +2069,13 @@ impl Bencher {
 
     // This is a more statistics-driven benchmark algorithm
     pub fn auto_bench(&mut self, f: |&mut Bencher|) -> stats::Summary<f64> {
+        self.auto_bench_samples(f).val0()
+    }
+
+    // Like auto_bench, but also returns the (winsorized) samples that the
+    // summary was computed from.
+    fn auto_bench_samples(&mut self,
+                          f: |&mut Bencher|) -> (stats::Summary<f64>, Vec<f64>) {
 
         // Initial bench run to get ballpark figure.
         let mut n = 1_u64;
@@ -1351,13 +2124,13 @@ impl Bencher {
             if loop_run > 100_000_000 &&
                 summ.median_abs_dev_pct < 1.0 &&
                 summ.median - summ5.median < summ5.median_abs_dev {
-                return summ5;
+                return (summ5, Vec::from_slice(samples));
             }
 
             total_run += loop_run;
             // Longest we ever run for is 3s.
             if total_run > 3_000_000_000 {
-                return summ5;
+                return (summ5, Vec::from_slice(samples));
             }
 
             n *= 2;
@@ -1377,7 +2150,7 @@ pub mod bench {
             bytes: 0
         };
 
-        let ns_iter_summ = bs.auto_bench(f);
+        let (ns_iter_summ, samples) = bs.auto_bench_samples(f);
 
         let ns_iter = cmp::max(ns_iter_summ.median as u64, 1);
         let iter_s = 1_000_000_000 / ns_iter;
@@ -1385,7 +2158,8 @@ pub mod bench {
 
         BenchSamples {
             ns_iter_summ: ns_iter_summ,
-            mb_s: mb_s as uint
+            mb_s: mb_s as uint,
+            samples: samples,
         }
     }
 }
@@ -1406,7 +2180,8 @@ mod tests {
             desc: TestDesc {
                 name: StaticTestName("whatever"),
                 ignore: true,
-                should_fail: false
+                should_fail: false,
+                timeout: None
             },
             testfn: DynTestFn(proc() f()),
         };
@@ -1423,7 +2198,8 @@ mod tests {
             desc: TestDesc {
                 name: StaticTestName("whatever"),
                 ignore: true,
-                should_fail: false
+                should_fail: false,
+                timeout: None
             },
             testfn: DynTestFn(proc() f()),
         };
@@ -1440,7 +2216,8 @@ mod tests {
             desc: TestDesc {
                 name: StaticTestName("whatever"),
                 ignore: false,
-                should_fail: true
+                should_fail: true,
+                timeout: None
             },
             testfn: DynTestFn(proc() f()),
         };
@@ -1457,7 +2234,8 @@ mod tests {
             desc: TestDesc {
                 name: StaticTestName("whatever"),
                 ignore: false,
-                should_fail: true
+                should_fail: true,
+                timeout: None
             },
             testfn: DynTestFn(proc() f()),
         };
@@ -1489,6 +2267,55 @@ mod tests {
         assert!((opts.run_ignored));
     }
 
+    #[test]
+    fn parse_test_timeout_flag() {
+        let parse = |args: &[&str]| {
+            let mut v = vec!("progname".to_string());
+            v.extend(args.iter().map(|s| s.to_string()));
+            match parse_opts(v.as_slice()) {
+                Some(Ok(o)) => o.test_timeout,
+                _ => fail!("Malformed arg in parse_test_timeout_flag")
+            }
+        };
+        assert_eq!(parse(&["--test-timeout", "250"]), Some(250));
+        assert_eq!(parse(&["--test-timeout", "0"]), None);
+        let args = vec!("progname".to_string(),
+                        "--test-timeout".to_string(),
+                        "soon".to_string());
+        assert!(parse_opts(args.as_slice()).unwrap().is_err());
+    }
+
+    #[test]
+    fn tests_that_run_too_long_time_out() {
+        use super::{run_tests, TeTimedOut, TeResult};
+        use std::io::timer;
+        use std::time::Duration;
+
+        let desc = TestDescAndFn {
+            desc: TestDesc {
+                name: StaticTestName("slow"),
+                ignore: false,
+                should_fail: false,
+                timeout: Some(10)
+            },
+            testfn: DynTestFn(proc() timer::sleep(Duration::milliseconds(500))),
+        };
+        let mut opts = TestOpts::new();
+        opts.run_tests = true;
+        let mut timed_out = false;
+        let mut finished = false;
+        run_tests(&opts, vec!(desc), |e| {
+            match e {
+                TeTimedOut(_, ms) => { assert_eq!(ms, 10); timed_out = true }
+                TeResult(..) => finished = true,
+                _ => {}
+            }
+            Ok(())
+        }).unwrap();
+        assert!(timed_out);
+        assert!(!finished);
+    }
+
     #[test]
     pub fn filter_for_ignored_option() {
         // When we run ignored tests the test filter should filter out all the
@@ -1504,6 +2331,7 @@ mod tests {
                     name: StaticTestName("1"),
                     ignore: true,
                     should_fail: false,
+                    timeout: None,
                 },
                 testfn: DynTestFn(proc() {}),
             },
@@ -1511,7 +2339,8 @@ mod tests {
                 desc: TestDesc {
                     name: StaticTestName("2"),
                     ignore: false,
-                    should_fail: false
+                    should_fail: false,
+                    timeout: None
                 },
                 testfn: DynTestFn(proc() {}),
             });
@@ -1547,7 +2376,8 @@ mod tests {
                     desc: TestDesc {
                         name: DynTestName((*name).clone()),
                         ignore: false,
-                        should_fail: false
+                        should_fail: false,
+                        timeout: None
                     },
                     testfn: DynTestFn(testfn),
                 };
@@ -1588,7 +2418,8 @@ mod tests {
                 desc: TestDesc {
                     name: DynTestName(name.to_string()),
                     ignore: false,
-                    should_fail: false
+                    should_fail: false,
+                    timeout: None
                 },
                 testfn: DynTestFn(test_fn)
             }
@@ -1657,6 +2488,57 @@ mod tests {
         assert_eq!(diff2.len(), 7);
     }
 
+    #[test]
+    pub fn baseline_comparison() {
+        use super::{Baseline, BenchSamples, BenchAdded, BenchRemoved,
+                    BenchUnchanged, BenchImproved, BenchRegressed};
+        use stats;
+
+        let bench = |ns: f64| {
+            let samples = Vec::from_fn(50, |i| ns + (i % 10) as f64);
+            BenchSamples {
+                ns_iter_summ: stats::Summary::new(samples.as_slice()),
+                mb_s: 0,
+                samples: samples,
+            }
+        };
+
+        let mut old = Baseline::new();
+        old.insert_bench("steady", &bench(1000.0));
+        old.insert_bench("faster", &bench(1000.0));
+        old.insert_bench("slower", &bench(1000.0));
+        old.insert_bench("gone", &bench(1000.0));
+
+        let mut new = Baseline::new();
+        new.insert_bench("steady", &bench(1000.0));
+        new.insert_bench("faster", &bench(900.0));
+        new.insert_bench("slower", &bench(1100.0));
+        new.insert_bench("new", &bench(1000.0));
+
+        let dpth = TempDir::new("test-baseline").ok().expect("missing test for baseline");
+        let pth = dpth.path().join("old.bench.json");
+        old.save(&pth).unwrap();
+        let loaded = Baseline::load(&pth).unwrap();
+        assert!(loaded == old);
+
+        let diff = new.compare_to_old(&loaded);
+        assert_eq!(diff.len(), 5);
+        match *diff.find(&"steady".to_string()).unwrap() {
+            BenchUnchanged(pct, p) => assert!(pct == 0.0 && p > 0.99),
+            ref change => fail!("steady changed: {}", change),
+        }
+        match *diff.find(&"faster".to_string()).unwrap() {
+            BenchImproved(pct, p) => assert!(pct < -9.0 && p < 0.0001),
+            ref change => fail!("faster didn't improve: {}", change),
+        }
+        match *diff.find(&"slower".to_string()).unwrap() {
+            BenchRegressed(pct, p) => assert!(pct > 9.0 && p < 0.0001),
+            ref change => fail!("slower didn't regress: {}", change),
+        }
+        assert_eq!(*diff.find(&"gone".to_string()).unwrap(), BenchRemoved);
+        assert_eq!(*diff.find(&"new".to_string()).unwrap(), BenchAdded);
+    }
+
     #[test]
     pub fn ratchet_test() {
 
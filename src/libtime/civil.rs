@@ -0,0 +1,563 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Calendar arithmetic on broken-down times, and ISO 8601 parsing.
+//!
+//! Everything here works on the wall-clock fields of a `Tm` in the
+//! proleptic Gregorian calendar, with `tm_gmtoff` held fixed. No daylight
+//! saving rules are applied, so to step across a DST change in some zone,
+//! convert the result with `TimeZone::to_timespec` and `at_zone`.
+
+use std::cmp;
+use std::mem;
+use std::time::Duration;
+
+use super::{Tm, Timespec, NSEC_PER_SEC};
+
+static SECS_PER_DAY: i64 = 86400;
+
+/// Returns whether `year` (such as 2012) is a leap year.
+pub fn is_leap_year(year: i32) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+/// Returns the number of days in a month of `year`, with `month` counted
+/// from 0 for January, like `tm_mon`.
+///
+/// # Failure
+///
+/// Fails if `month` is not in the range `0..11`.
+pub fn days_in_month(year: i32, month: i32) -> i32 {
+    match month {
+        1 if is_leap_year(year) => 29,
+        1 => 28,
+        3 | 5 | 8 | 10 => 30,
+        0 | 2 | 4 | 6 | 7 | 9 | 11 => 31,
+        _ => fail!("invalid month {}", month),
+    }
+}
+
+/// Returns the number of ISO 8601 weeks in `year`, either 52 or 53.
+pub fn iso_weeks_in_year(year: i32) -> i32 {
+    let jan1 = weekday(days_from_civil(year as i64, 1, 1));
+    // Years starting on a Thursday, or leap years starting on a Wednesday,
+    // have a Thursday in a 53rd week
+    if jan1 == 4 || (jan1 == 3 && is_leap_year(year)) { 53 } else { 52 }
+}
+
+/// An iterator over the days between two times, as returned by
+/// `date_range`.
+pub struct DateRange {
+    next: Tm,
+    end: Timespec,
+}
+
+/// Returns an iterator over `start` and the same time on each following
+/// day, up to but not including `end`.
+pub fn date_range(start: &Tm, end: &Tm) -> DateRange {
+    DateRange { next: start.normalized(), end: instant(end) }
+}
+
+impl Iterator<Tm> for DateRange {
+    fn next(&mut self) -> Option<Tm> {
+        if instant(&self.next) >= self.end {
+            return None;
+        }
+        let next = self.next.add_days(1);
+        Some(mem::replace(&mut self.next, next))
+    }
+}
+
+impl Tm {
+    /// Returns this time with every field brought into its normal range,
+    /// carrying into the larger fields, and with `tm_wday` and `tm_yday`
+    /// computed from the date. For example, April 31st becomes May 1st.
+    pub fn normalized(&self) -> Tm {
+        let nsec = self.tm_nsec as i64;
+        let carry = div_floor(nsec, NSEC_PER_SEC as i64);
+        from_local_seconds(local_seconds(self) + carry,
+                           (nsec - carry * NSEC_PER_SEC as i64) as i32,
+                           self.tm_gmtoff, self.tm_isdst)
+    }
+
+    /// Returns the same time of day `days` days later (or earlier, if
+    /// negative).
+    pub fn add_days(&self, days: i64) -> Tm {
+        let mut tm = self.normalized();
+        let secs = local_seconds(&tm) + days * SECS_PER_DAY;
+        tm = from_local_seconds(secs, tm.tm_nsec, tm.tm_gmtoff, tm.tm_isdst);
+        tm
+    }
+
+    /// Returns the same day and time `months` months later (or earlier, if
+    /// negative). If that month is too short, the last day of the month is
+    /// used instead, so a month after January 31st is February 28th or
+    /// 29th.
+    pub fn add_months(&self, months: i32) -> Tm {
+        let mut tm = self.normalized();
+        let mon = tm.tm_mon as i64 + months as i64;
+        let years = div_floor(mon, 12);
+        tm.tm_year += years as i32;
+        tm.tm_mon = (mon - years * 12) as i32;
+        tm.tm_mday = cmp::min(tm.tm_mday, days_in_month(tm.tm_year + 1900, tm.tm_mon));
+        tm.normalized()
+    }
+
+    /// Returns the same day and time `years` years later (or earlier, if
+    /// negative). February 29th becomes February 28th outside leap years.
+    pub fn add_years(&self, years: i32) -> Tm {
+        self.add_months(years * 12)
+    }
+
+    /// Returns the ISO 8601 year and week number (1 to 53) of this date.
+    /// Weeks start on Monday, and the first week of a year is the one
+    /// containing its first Thursday, so the ISO year can differ from
+    /// `tm_year` around the new year.
+    pub fn iso_week(&self) -> (i32, i32) {
+        let tm = self.normalized();
+        let year = tm.tm_year + 1900;
+        let iso_wday = (tm.tm_wday + 6) % 7 + 1;
+        let week = (tm.tm_yday + 1 - iso_wday + 10) / 7;
+        if week < 1 {
+            (year - 1, iso_weeks_in_year(year - 1))
+        } else if week > iso_weeks_in_year(year) {
+            (year + 1, 1)
+        } else {
+            (year, week)
+        }
+    }
+}
+
+/// Adds a duration to the wall-clock time, keeping `tm_gmtoff`.
+impl Add<Duration, Tm> for Tm {
+    fn add(&self, other: &Duration) -> Tm {
+        let tm = self.normalized();
+        let ts = Timespec::new(local_seconds(&tm), tm.tm_nsec) + *other;
+        from_local_seconds(ts.sec, ts.nsec, tm.tm_gmtoff, tm.tm_isdst)
+    }
+}
+
+/// Returns the time elapsed between two times, taking their UTC offsets
+/// into account.
+impl Sub<Tm, Duration> for Tm {
+    fn sub(&self, other: &Tm) -> Duration {
+        instant(self) - instant(other)
+    }
+}
+
+/// Parses an ISO 8601 date and time, such as `2014-08-04T10:15:30.25-07:00`.
+///
+/// This accepts everything RFC 3339 does, along with the other common
+/// ISO 8601 forms:
+///
+/// * calendar (`2014-08-04`), ordinal (`2014-216`) and week (`2014-W32-1`)
+///   dates, in extended or basic (`20140804`) format
+/// * an optional time after `T`, `t` or a space, as `hh`, `hh:mm` or
+///   `hh:mm:ss`, or in basic format (`101530`), where the seconds may have
+///   a fraction after `.` or `,`
+/// * an optional UTC offset after the time: `Z`, `+hh`, `+hh:mm` or `+hhmm`
+///
+/// A time without an offset, or a date on its own, is returned with a
+/// `tm_gmtoff` of zero. `tm_wday` and `tm_yday` are filled in.
+pub fn parse_iso8601(s: &str) -> Result<Tm, String> {
+    let mut p = Parser { s: s.as_bytes(), pos: 0 };
+    match p.datetime() {
+        Ok(tm) if p.pos == p.s.len() => Ok(tm),
+        Ok(_) => Err(format!("invalid ISO 8601 time `{}`: trailing characters", s)),
+        Err(e) => Err(format!("invalid ISO 8601 time `{}`: {}", s, e)),
+    }
+}
+
+struct Parser<'a> {
+    s: &'a [u8],
+    pos: uint,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<u8> {
+        if self.pos < self.s.len() { Some(self.s[self.pos]) } else { None }
+    }
+
+    fn peek_digit(&self) -> bool {
+        self.peek().map_or(false, |c| (c as char).is_digit())
+    }
+
+    fn eat(&mut self, c: u8) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Reads exactly `n` digits.
+    fn digits(&mut self, n: uint, what: &str) -> Result<i32, String> {
+        let mut v = 0;
+        for _ in range(0, n) {
+            if !self.peek_digit() {
+                return Err(format!("expected {}", what));
+            }
+            v = v * 10 + (self.s[self.pos] - b'0') as i32;
+            self.pos += 1;
+        }
+        Ok(v)
+    }
+
+    /// Counts the digits starting at the current position.
+    fn digits_ahead(&self) -> uint {
+        self.s.slice_from(self.pos).iter()
+            .take_while(|&&c| (c as char).is_digit()).count()
+    }
+
+    fn datetime(&mut self) -> Result<Tm, String> {
+        let year = try!(self.digits(4, "a four digit year"));
+        let extended = self.eat(b'-');
+        let days = if self.eat(b'W') {
+            try!(self.week_date(year, extended))
+        } else if self.digits_ahead() == 3 {
+            let yday = try!(self.digits(3, "a day of the year"));
+            let len = if is_leap_year(year) { 366 } else { 365 };
+            if yday < 1 || yday > len {
+                return Err("day of the year out of range".to_string());
+            }
+            days_from_civil(year as i64, 1, 1) + yday as i64 - 1
+        } else {
+            let month = try!(self.digits(2, "a month"));
+            if extended && !self.eat(b'-') {
+                return Err("expected `-` after the month".to_string());
+            }
+            let day = try!(self.digits(2, "a day"));
+            if month < 1 || month > 12 {
+                return Err("month out of range".to_string());
+            }
+            if day < 1 || day > days_in_month(year, month - 1) {
+                return Err("day out of range".to_string());
+            }
+            days_from_civil(year as i64, month as i64, day as i64)
+        };
+
+        let (mut hour, mut min, mut sec, mut nsec, mut gmtoff) = (0, 0, 0, 0, 0);
+        if self.eat(b'T') || self.eat(b't') || self.eat(b' ') {
+            let (h, m, s, ns) = try!(self.time());
+            hour = h;
+            min = m;
+            sec = s;
+            nsec = ns;
+            gmtoff = try!(self.offset());
+        }
+
+        // A leap second is kept as the 60th second rather than rolling over
+        // into the next minute
+        let secs = days * SECS_PER_DAY + hour as i64 * 3600 + min as i64 * 60 +
+                   cmp::min(sec, 59) as i64;
+        let mut tm = from_local_seconds(secs, nsec, gmtoff, 0);
+        tm.tm_sec = sec;
+        Ok(tm)
+    }
+
+    fn week_date(&mut self, year: i32, extended: bool) -> Result<i64, String> {
+        let week = try!(self.digits(2, "a week number"));
+        if extended && !self.eat(b'-') {
+            return Err("expected `-` after the week".to_string());
+        }
+        let wday = try!(self.digits(1, "a day of the week"));
+        if week < 1 || week > iso_weeks_in_year(year) {
+            return Err("week out of range".to_string());
+        }
+        if wday < 1 || wday > 7 {
+            return Err("day of the week out of range".to_string());
+        }
+        // Week 1 is the one containing January 4th
+        let jan4 = days_from_civil(year as i64, 1, 4);
+        let monday = jan4 - ((weekday(jan4) + 6) % 7) as i64;
+        Ok(monday + (week as i64 - 1) * 7 + wday as i64 - 1)
+    }
+
+    /// Reads a time of day as hours, minutes, seconds and nanoseconds.
+    fn time(&mut self) -> Result<(i32, i32, i32, i32), String> {
+        let hour = try!(self.digits(2, "an hour"));
+        let extended = self.eat(b':');
+        let (mut min, mut sec, mut nsec) = (0, 0, 0);
+        if extended || self.digits_ahead() >= 2 {
+            min = try!(self.digits(2, "minutes"));
+            if (extended && self.eat(b':')) ||
+               (!extended && self.digits_ahead() >= 2) {
+                sec = try!(self.digits(2, "seconds"));
+                if self.eat(b'.') || self.eat(b',') {
+                    nsec = try!(self.fraction());
+                }
+            }
+        }
+        if hour > 23 || min > 59 || sec > 60 {
+            return Err("time out of range".to_string());
+        }
+        Ok((hour, min, sec, nsec))
+    }
+
+    /// Reads a decimal fraction of a second as nanoseconds, ignoring any
+    /// digits past the ninth.
+    fn fraction(&mut self) -> Result<i32, String> {
+        let n = self.digits_ahead();
+        if n == 0 {
+            return Err("expected a fraction of a second".to_string());
+        }
+        let mut nsec = 0;
+        for i in range(0u, 9) {
+            nsec *= 10;
+            if i < n {
+                nsec += (self.s[self.pos + i] - b'0') as i32;
+            }
+        }
+        self.pos += n;
+        Ok(nsec)
+    }
+
+    /// Reads an optional UTC offset, in seconds east of UTC.
+    fn offset(&mut self) -> Result<i32, String> {
+        if self.eat(b'Z') || self.eat(b'z') {
+            return Ok(0);
+        }
+        let sign = if self.eat(b'+') {
+            1
+        } else if self.eat(b'-') {
+            -1
+        } else {
+            return Ok(0);
+        };
+        let hours = try!(self.digits(2, "offset hours"));
+        let mins = if self.eat(b':') || self.digits_ahead() >= 2 {
+            try!(self.digits(2, "offset minutes"))
+        } else {
+            0
+        };
+        if hours > 23 || mins > 59 {
+            return Err("offset out of range".to_string());
+        }
+        Ok(sign * (hours * 3600 + mins * 60))
+    }
+}
+
+/// Divides, rounding towards negative infinity.
+pub fn div_floor(a: i64, b: i64) -> i64 {
+    let q = a / b;
+    if (a % b != 0) && ((a < 0) != (b < 0)) { q - 1 } else { q }
+}
+
+/// Returns the day of the week, 0 for Sunday, of a day counted from
+/// 1970-01-01.
+pub fn weekday(days: i64) -> i32 {
+    // 1970-01-01 was a Thursday
+    (days + 4 - div_floor(days + 4, 7) * 7) as i32
+}
+
+/// Returns the number of days from 1970-01-01 to the given date, with
+/// `month` counted from 1.
+pub fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    // Count from 0000-03-01, so that leap days come at the end of the year
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = div_floor(y, 400);
+    let yoe = y - era * 400;
+    let doy = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+/// The inverse of `days_from_civil`, returning the year, month (from 1)
+/// and day.
+pub fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719468;
+    let era = div_floor(z, 146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+/// Returns the wall-clock time in `tm` as seconds since 1970-01-01T00:00,
+/// ignoring `tm_gmtoff`. The fields may be out of range.
+pub fn local_seconds(tm: &Tm) -> i64 {
+    let mon = tm.tm_mon as i64;
+    let years = div_floor(mon, 12);
+    let days = days_from_civil(tm.tm_year as i64 + 1900 + years,
+                               mon - years * 12 + 1, 1) + tm.tm_mday as i64 - 1;
+    days * SECS_PER_DAY + tm.tm_hour as i64 * 3600 + tm.tm_min as i64 * 60 +
+        tm.tm_sec as i64
+}
+
+/// Builds a normalized `Tm` from a wall-clock time in seconds since
+/// 1970-01-01T00:00.
+pub fn from_local_seconds(secs: i64, nsec: i32, gmtoff: i32, isdst: i32) -> Tm {
+    let days = div_floor(secs, SECS_PER_DAY);
+    let rem = secs - days * SECS_PER_DAY;
+    let (year, month, day) = civil_from_days(days);
+    Tm {
+        tm_sec: (rem % 60) as i32,
+        tm_min: (rem / 60 % 60) as i32,
+        tm_hour: (rem / 3600) as i32,
+        tm_mday: day as i32,
+        tm_mon: (month - 1) as i32,
+        tm_year: (year - 1900) as i32,
+        tm_wday: weekday(days),
+        tm_yday: (days - days_from_civil(year, 1, 1)) as i32,
+        tm_isdst: isdst,
+        tm_gmtoff: gmtoff,
+        tm_nsec: nsec,
+    }
+}
+
+/// Returns the instant a `Tm` refers to, using its `tm_gmtoff`.
+fn instant(tm: &Tm) -> Timespec {
+    let tm = tm.normalized();
+    Timespec::new(local_seconds(&tm) - tm.tm_gmtoff as i64, tm.tm_nsec)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{date_range, days_in_month, is_leap_year, iso_weeks_in_year,
+                parse_iso8601};
+    use std::time::Duration;
+    use {Tm, empty_tm};
+
+    fn date(year: i32, month: i32, day: i32) -> Tm {
+        let mut tm = empty_tm();
+        tm.tm_year = year - 1900;
+        tm.tm_mon = month - 1;
+        tm.tm_mday = day;
+        tm.normalized()
+    }
+
+    fn ymd(tm: &Tm) -> (i32, i32, i32) {
+        (tm.tm_year + 1900, tm.tm_mon + 1, tm.tm_mday)
+    }
+
+    #[test]
+    fn test_calendar() {
+        assert!(is_leap_year(2000));
+        assert!(is_leap_year(2012));
+        assert!(!is_leap_year(1900));
+        assert!(!is_leap_year(2014));
+        assert_eq!(days_in_month(2012, 1), 29);
+        assert_eq!(days_in_month(2014, 1), 28);
+        assert_eq!(days_in_month(2014, 3), 30);
+        assert_eq!(days_in_month(2014, 11), 31);
+        assert_eq!(iso_weeks_in_year(2009), 53);
+        assert_eq!(iso_weeks_in_year(2014), 52);
+        assert_eq!(iso_weeks_in_year(2020), 53);
+    }
+
+    #[test]
+    #[should_fail]
+    fn test_days_in_invalid_month() {
+        days_in_month(2014, 12);
+    }
+
+    #[test]
+    fn test_normalized() {
+        let tm = date(2014, 4, 31);
+        assert_eq!(ymd(&tm), (2014, 5, 1));
+        assert_eq!(tm.tm_wday, 4);
+        assert_eq!(tm.tm_yday, 120);
+
+        let tm = date(1969, 12, 31);
+        assert_eq!(tm.tm_wday, 3);
+        assert_eq!(date(1600, 3, 1).tm_wday, 3);
+
+        let mut tm = date(2014, 1, 1);
+        tm.tm_hour = -1;
+        tm.tm_nsec = 1_500_000_000;
+        let tm = tm.normalized();
+        assert_eq!(ymd(&tm), (2013, 12, 31));
+        assert_eq!((tm.tm_hour, tm.tm_sec, tm.tm_nsec), (23, 1, 500_000_000));
+    }
+
+    #[test]
+    fn test_add() {
+        assert_eq!(ymd(&date(2014, 1, 31).add_days(30)), (2014, 3, 2));
+        assert_eq!(ymd(&date(2014, 1, 1).add_days(-1)), (2013, 12, 31));
+        assert_eq!(ymd(&date(2014, 1, 31).add_months(1)), (2014, 2, 28));
+        assert_eq!(ymd(&date(2012, 1, 31).add_months(1)), (2012, 2, 29));
+        assert_eq!(ymd(&date(2014, 3, 31).add_months(-13)), (2013, 2, 28));
+        assert_eq!(ymd(&date(2014, 11, 15).add_months(2)), (2015, 1, 15));
+        assert_eq!(ymd(&date(2012, 2, 29).add_years(1)), (2013, 2, 28));
+        assert_eq!(ymd(&date(2012, 2, 29).add_years(4)), (2016, 2, 29));
+
+        let mut tm = date(2014, 12, 31);
+        tm.tm_hour = 23;
+        tm.tm_gmtoff = 3600;
+        let later = tm + Duration::minutes(90);
+        assert_eq!(ymd(&later), (2015, 1, 1));
+        assert_eq!((later.tm_hour, later.tm_min, later.tm_gmtoff), (0, 30, 3600));
+        assert_eq!(later.tm_wday, 4);
+        assert_eq!(later - tm, Duration::minutes(90));
+
+        let mut utc = later.clone();
+        utc.tm_gmtoff = 0;
+        assert_eq!(utc - later, Duration::hours(1));
+    }
+
+    #[test]
+    fn test_iso_week() {
+        assert_eq!(date(2005, 1, 1).iso_week(), (2004, 53));
+        assert_eq!(date(2008, 12, 29).iso_week(), (2009, 1));
+        assert_eq!(date(2010, 1, 3).iso_week(), (2009, 53));
+        assert_eq!(date(2020, 12, 31).iso_week(), (2020, 53));
+        assert_eq!(date(2021, 1, 3).iso_week(), (2020, 53));
+        assert_eq!(date(2014, 8, 4).iso_week(), (2014, 32));
+    }
+
+    #[test]
+    fn test_date_range() {
+        let days: Vec<(i32, i32, i32)> = date_range(&date(2012, 2, 27), &date(2012, 3, 2))
+            .map(|tm| ymd(&tm)).collect();
+        assert_eq!(days, vec!((2012, 2, 27), (2012, 2, 28), (2012, 2, 29), (2012, 3, 1)));
+        assert_eq!(date_range(&date(2012, 3, 2), &date(2012, 3, 2)).count(), 0);
+    }
+
+    #[test]
+    fn test_parse_iso8601() {
+        let tm = parse_iso8601("2014-08-04T10:15:30.25-07:00").unwrap();
+        assert_eq!(ymd(&tm), (2014, 8, 4));
+        assert_eq!((tm.tm_hour, tm.tm_min, tm.tm_sec, tm.tm_nsec), (10, 15, 30, 250_000_000));
+        assert_eq!(tm.tm_gmtoff, -7 * 3600);
+        assert_eq!((tm.tm_wday, tm.tm_yday), (1, 215));
+        let utc = parse_iso8601("2014-08-04T17:15:30.25Z").unwrap();
+        assert_eq!(tm - utc, Duration::seconds(0));
+
+        let same = ["20140804T171530,25Z", "2014-216T17:15:30.250Z",
+                    "2014-W32-1 17:15:30.25+00", "2014W321T171530.25+0000",
+                    "2014-08-04t17:15:30.250000000999z"];
+        for s in same.iter() {
+            let other = parse_iso8601(*s).unwrap();
+            assert_eq!((ymd(&other), other.tm_hour, other.tm_nsec, other.tm_gmtoff),
+                       ((2014, 8, 4), 17, 250_000_000, 0));
+        }
+
+        let tm = parse_iso8601("2009-W53-7").unwrap();
+        assert_eq!((ymd(&tm), tm.tm_hour), ((2010, 1, 3), 0));
+        let tm = parse_iso8601("2016-12-31T23:59:60Z").unwrap();
+        assert_eq!((ymd(&tm), tm.tm_min, tm.tm_sec), ((2016, 12, 31), 59, 60));
+        let tm = parse_iso8601("2014-08-04T10:15+05:30").unwrap();
+        assert_eq!((tm.tm_min, tm.tm_sec, tm.tm_gmtoff), (15, 0, 19800));
+
+        let bad = ["", "2014", "14-08-04", "2014-13-01", "2014-02-29", "2014-08-4",
+                   "2014-0804", "2014-366", "2014-W53-1", "2014-W01-8",
+                   "2014-08-04T24:00", "2014-08-04T10:60", "2014-08-04T10:15:30.",
+                   "2014-08-04T10:15+25:00", "2014-08-04T10:15:30Zjunk",
+                   "2014-08-04T"];
+        for s in bad.iter() {
+            assert!(parse_iso8601(*s).is_err(), "{} should not parse", s);
+        }
+    }
+}
@@ -33,6 +33,13 @@ use std::num;
 use std::string::String;
 use std::time::Duration;
 
+pub use civil::{DateRange, date_range, days_in_month, is_leap_year,
+                iso_weeks_in_year, parse_iso8601};
+pub use zone::{TimeZone, ZONEINFO_DIR, at_zone};
+
+mod civil;
+mod zone;
+
 static NSEC_PER_SEC: i32 = 1_000_000_000_i32;
 
 mod rustrt {
@@ -324,6 +331,9 @@ pub fn now() -> Tm {
 
 impl Tm {
     /// Convert time to the seconds from January 1, 1970
+    ///
+    /// A time with a non-zero `tm_gmtoff` is taken to be in the local
+    /// timezone; use `TimeZone::to_timespec` for times in other zones.
     pub fn to_timespec(&self) -> Timespec {
         unsafe {
             let sec = match self.tm_gmtoff {
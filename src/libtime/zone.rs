@@ -0,0 +1,686 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Time zones read from the zoneinfo database, independently of the
+//! process-local time zone.
+//!
+//! Zones are loaded from TZif files as described in RFC 8536 and produced
+//! by the `zic` compiler. Instants after the last transition in a file are
+//! handled by the POSIX `TZ` rule stored in its footer.
+
+use std::io::{BufReader, File, IoResult, Seek};
+use std::os;
+use std::str;
+
+use super::{Timespec, Tm};
+use civil::{civil_from_days, days_from_civil, days_in_month, div_floor,
+            from_local_seconds, is_leap_year, local_seconds, weekday};
+
+static SECS_PER_DAY: i64 = 86400;
+
+/// The default location of the zoneinfo database, used unless `TZDIR` is
+/// set in the environment.
+pub static ZONEINFO_DIR: &'static str = "/usr/share/zoneinfo";
+
+/// A set of rules for converting between UTC and the wall-clock time in
+/// one place, such as `America/New_York`.
+#[deriving(Clone)]
+pub struct TimeZone {
+    name: String,
+    // Instants at which the local time type changes, with the index of the
+    // type which comes into effect, sorted by time
+    transitions: Vec<(i64, uint)>,
+    types: Vec<LocalTimeType>,
+    rule: Option<PosixTz>,
+}
+
+#[deriving(Clone, PartialEq)]
+struct LocalTimeType {
+    utoff: i32,
+    isdst: bool,
+    abbrev: String,
+}
+
+// A POSIX TZ string such as `EST5EDT,M3.2.0,M11.1.0`
+#[deriving(Clone)]
+struct PosixTz {
+    std: LocalTimeType,
+    dst: Option<DstRule>,
+}
+
+#[deriving(Clone)]
+struct DstRule {
+    dst: LocalTimeType,
+    // When DST starts, in local standard time
+    start: RuleDay,
+    start_time: i32,
+    // When DST ends, in local daylight time
+    end: RuleDay,
+    end_time: i32,
+}
+
+#[deriving(Clone)]
+enum RuleDay {
+    // Jn: day 1 to 365, never counting February 29th
+    JulianNoLeap(i32),
+    // n: day 0 to 365, counting February 29th in leap years
+    JulianLeap(i32),
+    // Mm.w.d: day d (0 = Sunday) of week w (5 = last) of month m
+    MonthWeekDay(i32, i32, i32),
+}
+
+impl TimeZone {
+    /// Loads the zone with the given name, such as `Europe/London`, from
+    /// the directory named by the `TZDIR` environment variable, or from
+    /// `ZONEINFO_DIR` if it is not set.
+    pub fn named(name: &str) -> Result<TimeZone, String> {
+        if name.is_empty() || name.starts_with("/") ||
+           name.split('/').any(|part| part == "..") {
+            return Err(format!("invalid time zone name `{}`", name));
+        }
+        let dir = os::getenv("TZDIR").unwrap_or(ZONEINFO_DIR.to_string());
+        let path = Path::new(dir).join(name);
+        match File::open(&path).read_to_end() {
+            Ok(data) => TimeZone::from_tzif(name, data.as_slice()),
+            Err(e) => Err(format!("failed to read time zone `{}` from {}: {}",
+                                  name, path.display(), e)),
+        }
+    }
+
+    /// Loads a zone from a TZif file. The zone is named after the path.
+    pub fn from_file(path: &Path) -> Result<TimeZone, String> {
+        let name = path.display().to_string();
+        match File::open(path).read_to_end() {
+            Ok(data) => TimeZone::from_tzif(name.as_slice(), data.as_slice()),
+            Err(e) => Err(format!("failed to read time zone from {}: {}",
+                                  name, e)),
+        }
+    }
+
+    /// Parses the contents of a TZif file, giving the zone the name `name`.
+    pub fn from_tzif(name: &str, data: &[u8]) -> Result<TimeZone, String> {
+        let mut zone = try!(parse_tzif(data));
+        zone.name = name.to_string();
+        Ok(zone)
+    }
+
+    /// Returns the UTC time zone.
+    pub fn utc() -> TimeZone {
+        TimeZone {
+            name: "UTC".to_string(),
+            transitions: vec!(),
+            types: vec!(LocalTimeType {
+                utoff: 0,
+                isdst: false,
+                abbrev: "UTC".to_string(),
+            }),
+            rule: None,
+        }
+    }
+
+    /// Returns the name this zone was loaded with.
+    pub fn name<'a>(&'a self) -> &'a str {
+        self.name.as_slice()
+    }
+
+    /// Returns the abbreviation in use at the given instant, such as `EST`
+    /// or `EDT`.
+    pub fn abbreviation_at<'a>(&'a self, clock: Timespec) -> &'a str {
+        self.type_at(clock.sec).abbrev.as_slice()
+    }
+
+    /// Converts a broken-down time in this zone to a `Timespec`.
+    ///
+    /// Only the date and time fields of `tm` are used, and they may be out
+    /// of range, as with `mktime`. A time which occurs twice, when clocks
+    /// go back, is resolved using `tm_isdst` if it is not negative, and is
+    /// otherwise taken to be the earlier of the two instants. A time which
+    /// is skipped, when clocks go forward, is interpreted using the offset
+    /// from before the change, so 02:30 becomes 03:30 when clocks go
+    /// forward at 02:00.
+    pub fn to_timespec(&self, tm: &Tm) -> Timespec {
+        let tm = tm.normalized();
+        let local = local_seconds(&tm);
+
+        // Zones don't change offset more than once a day, so the offsets
+        // in effect a day either side are the only ones which can apply.
+        let before = self.type_at(local - SECS_PER_DAY);
+        let after = self.type_at(local + SECS_PER_DAY);
+        let fits = |ltt: &LocalTimeType| {
+            self.type_at(local - ltt.utoff as i64).utoff == ltt.utoff
+        };
+
+        let ltt = match (fits(before), fits(after)) {
+            (true, true) if before.utoff != after.utoff => {
+                if tm.tm_isdst >= 0 && (tm.tm_isdst > 0) == after.isdst {
+                    after
+                } else if tm.tm_isdst >= 0 && (tm.tm_isdst > 0) == before.isdst {
+                    before
+                } else if before.utoff > after.utoff {
+                    before
+                } else {
+                    after
+                }
+            }
+            (false, true) => after,
+            _ => before,
+        };
+        Timespec::new(local - ltt.utoff as i64, tm.tm_nsec)
+    }
+
+    /// Returns the local time type in effect at `sec` seconds after the
+    /// epoch.
+    fn type_at<'a>(&'a self, sec: i64) -> &'a LocalTimeType {
+        match self.transitions.iter().rposition(|&(at, _)| at <= sec) {
+            // Before the first transition, or with no transitions and no
+            // rule, the first local time type is used
+            None if self.transitions.is_empty() && self.rule.is_some() => {}
+            None => return &self.types[0],
+            Some(i) if i + 1 < self.transitions.len() || self.rule.is_none() => {
+                let (_, idx) = self.transitions[i];
+                return &self.types[idx];
+            }
+            // After the last transition the footer rule takes over
+            Some(_) => {}
+        }
+        self.rule.get_ref().type_at(sec)
+    }
+}
+
+/// Returns the specified time in the given time zone.
+pub fn at_zone(clock: Timespec, zone: &TimeZone) -> Tm {
+    let ltt = zone.type_at(clock.sec);
+    from_local_seconds(clock.sec + ltt.utoff as i64, clock.nsec, ltt.utoff,
+                       if ltt.isdst { 1 } else { 0 })
+}
+
+impl PosixTz {
+    fn type_at<'a>(&'a self, sec: i64) -> &'a LocalTimeType {
+        let rule = match self.dst {
+            None => return &self.std,
+            Some(ref rule) => rule,
+        };
+
+        let year = year_of(sec + self.std.utoff as i64);
+        let start = rule.start.day_in(year) * SECS_PER_DAY +
+                    rule.start_time as i64 - self.std.utoff as i64;
+        let end = rule.end.day_in(year) * SECS_PER_DAY +
+                  rule.end_time as i64 - rule.dst.utoff as i64;
+
+        // In the southern hemisphere DST spans the turn of the year
+        let in_dst = if start <= end {
+            start <= sec && sec < end
+        } else {
+            !(end <= sec && sec < start)
+        };
+        if in_dst { &rule.dst } else { &self.std }
+    }
+}
+
+impl RuleDay {
+    /// Returns the day this rule refers to in `year`, in days since the
+    /// epoch.
+    fn day_in(&self, year: i64) -> i64 {
+        let jan1 = days_from_civil(year, 1, 1);
+        match *self {
+            JulianNoLeap(n) => {
+                let leap_day = if is_leap_year(year as i32) && n >= 60 { 1 } else { 0 };
+                jan1 + n as i64 - 1 + leap_day
+            }
+            JulianLeap(n) => jan1 + n as i64,
+            MonthWeekDay(month, week, wday) => {
+                let first = days_from_civil(year, month as i64, 1);
+                let mut day = first + ((wday - weekday(first) + 7) % 7) as i64 +
+                              (week as i64 - 1) * 7;
+                let len = days_in_month(year as i32, month - 1) as i64;
+                while day >= first + len {
+                    day -= 7;
+                }
+                day
+            }
+        }
+    }
+}
+
+/// Returns the year containing the instant `sec` seconds after the epoch.
+fn year_of(sec: i64) -> i64 {
+    let (year, _, _) = civil_from_days(div_floor(sec, SECS_PER_DAY));
+    year
+}
+
+fn truncated<T>(r: IoResult<T>) -> Result<T, String> {
+    r.map_err(|_| "unexpected end of TZif data".to_string())
+}
+
+struct Header {
+    version: u8,
+    isutcnt: uint,
+    isstdcnt: uint,
+    leapcnt: uint,
+    timecnt: uint,
+    typecnt: uint,
+    charcnt: uint,
+}
+
+impl Header {
+    /// The size of the data block following this header, for times of the
+    /// given size in bytes, or `None` if it overflows a `uint`.
+    fn data_len(&self, time_size: uint) -> Option<uint> {
+        let sizes = [(self.timecnt, time_size + 1), (self.typecnt, 6),
+                     (self.charcnt, 1), (self.leapcnt, time_size + 4),
+                     (self.isstdcnt, 1), (self.isutcnt, 1)];
+        sizes.iter().fold(Some(0u), |len, &(count, size)| {
+            len.and_then(|len| {
+                count.checked_mul(&size).and_then(|n| len.checked_add(&n))
+            })
+        })
+    }
+}
+
+/// Reads a header out of the `total` bytes of TZif data in `r`, checking
+/// that its counts fit in the rest of the data before anything is allocated
+/// for them.
+fn read_header(r: &mut BufReader, total: uint,
+               time_size: uint) -> Result<Header, String> {
+    let magic = try!(truncated(r.read_exact(4)));
+    if magic.as_slice() != b"TZif" {
+        return Err("not a TZif file".to_string());
+    }
+    let version = try!(truncated(r.read_u8()));
+    try!(truncated(r.read_exact(15)));
+
+    let mut counts = [0u, ..6];
+    for count in counts.mut_iter() {
+        *count = try!(truncated(r.read_be_u32())) as uint;
+    }
+    let header = Header {
+        version: version,
+        isutcnt: counts[0],
+        isstdcnt: counts[1],
+        leapcnt: counts[2],
+        timecnt: counts[3],
+        typecnt: counts[4],
+        charcnt: counts[5],
+    };
+    if header.typecnt == 0 {
+        return Err("TZif data has no local time types".to_string());
+    }
+    let left = total - try!(truncated(r.tell())) as uint;
+    match header.data_len(time_size) {
+        Some(len) if len <= left => Ok(header),
+        _ => Err("TZif counts exceed the size of the data".to_string()),
+    }
+}
+
+fn parse_tzif(data: &[u8]) -> Result<TimeZone, String> {
+    let mut r = BufReader::new(data);
+    let mut header = try!(read_header(&mut r, data.len(), 4));
+
+    // Version 2 and later files repeat the data with 64-bit times after
+    // the version 1 data, which is skipped
+    let time_size = if header.version == 0 {
+        4
+    } else {
+        try!(truncated(r.read_exact(header.data_len(4).unwrap())));
+        header = try!(read_header(&mut r, data.len(), 8));
+        8
+    };
+
+    let mut times = vec!();
+    for _ in range(0, header.timecnt) {
+        times.push(if time_size == 4 {
+            try!(truncated(r.read_be_i32())) as i64
+        } else {
+            try!(truncated(r.read_be_i64()))
+        });
+    }
+    let mut transitions = vec!();
+    for &at in times.iter() {
+        let idx = try!(truncated(r.read_u8())) as uint;
+        if idx >= header.typecnt {
+            return Err("TZif transition has an invalid type".to_string());
+        }
+        transitions.push((at, idx));
+    }
+
+    let mut raw_types = vec!();
+    for _ in range(0, header.typecnt) {
+        let utoff = try!(truncated(r.read_be_i32()));
+        let isdst = try!(truncated(r.read_u8())) != 0;
+        let abbrind = try!(truncated(r.read_u8())) as uint;
+        raw_types.push((utoff, isdst, abbrind));
+    }
+    let chars = try!(truncated(r.read_exact(header.charcnt)));
+    let mut types = vec!();
+    for &(utoff, isdst, abbrind) in raw_types.iter() {
+        if abbrind >= chars.len() {
+            return Err("TZif abbreviation index out of range".to_string());
+        }
+        let abbrev = chars.slice_from(abbrind);
+        let len = abbrev.iter().position(|&b| b == 0).unwrap_or(abbrev.len());
+        types.push(LocalTimeType {
+            utoff: utoff,
+            isdst: isdst,
+            abbrev: String::from_utf8_lossy(abbrev.slice_to(len)).into_string(),
+        });
+    }
+
+    // Leap seconds and the standard/wall and UT/local indicators are not
+    // needed to convert times
+    try!(truncated(r.read_exact(header.leapcnt * (time_size + 4) +
+                                header.isstdcnt + header.isutcnt)));
+
+    let rule = if header.version == 0 {
+        None
+    } else {
+        let footer = try!(truncated(r.read_to_end()));
+        let footer = match str::from_utf8(footer.as_slice()) {
+            Some(s) => s.trim_chars('\n'),
+            None => return Err("TZif footer is not valid UTF-8".to_string()),
+        };
+        if footer.is_empty() {
+            None
+        } else {
+            Some(try!(parse_posix_tz(footer)))
+        }
+    };
+
+    Ok(TimeZone {
+        name: String::new(),
+        transitions: transitions,
+        types: types,
+        rule: rule,
+    })
+}
+
+struct PosixTzParser<'a> {
+    s: &'a [u8],
+    pos: uint,
+}
+
+impl<'a> PosixTzParser<'a> {
+    fn peek(&self) -> Option<u8> {
+        if self.pos < self.s.len() { Some(self.s[self.pos]) } else { None }
+    }
+
+    fn eat(&mut self, c: u8) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn err<T>(&self) -> Result<T, String> {
+        Err(format!("invalid TZ rule `{}`",
+                    String::from_utf8_lossy(self.s).into_string()))
+    }
+
+    // Either alphabetic, or anything but `>` inside angle brackets
+    fn name(&mut self) -> Result<String, String> {
+        let start;
+        let end;
+        if self.eat(b'<') {
+            start = self.pos;
+            while self.peek().map_or(false, |c| c != b'>') {
+                self.pos += 1;
+            }
+            end = self.pos;
+            if !self.eat(b'>') {
+                return self.err();
+            }
+        } else {
+            start = self.pos;
+            while self.peek().map_or(false, |c| (c as char).is_alphabetic()) {
+                self.pos += 1;
+            }
+            end = self.pos;
+        }
+        if end - start < 3 {
+            return self.err();
+        }
+        Ok(String::from_utf8_lossy(self.s.slice(start, end)).into_string())
+    }
+
+    fn number(&mut self) -> Result<i32, String> {
+        let start = self.pos;
+        let mut n = 0i32;
+        while self.peek().map_or(false, |c| (c as char).is_digit()) {
+            n = n * 10 + (self.s[self.pos] - b'0') as i32;
+            self.pos += 1;
+            if self.pos - start > 3 {
+                return self.err();
+            }
+        }
+        if self.pos == start { self.err() } else { Ok(n) }
+    }
+
+    // [+-]hh[:mm[:ss]], in seconds
+    fn time(&mut self) -> Result<i32, String> {
+        let sign = if self.eat(b'-') { -1 } else { self.eat(b'+'); 1 };
+        let mut secs = try!(self.number()) * 3600;
+        if self.eat(b':') {
+            secs += try!(self.number()) * 60;
+            if self.eat(b':') {
+                secs += try!(self.number());
+            }
+        }
+        Ok(sign * secs)
+    }
+
+    fn rule_day(&mut self) -> Result<(RuleDay, i32), String> {
+        let day = if self.eat(b'J') {
+            JulianNoLeap(try!(self.number()))
+        } else if self.eat(b'M') {
+            let month = try!(self.number());
+            if !self.eat(b'.') { return self.err() }
+            let week = try!(self.number());
+            if !self.eat(b'.') { return self.err() }
+            let wday = try!(self.number());
+            if month < 1 || month > 12 || week < 1 || week > 5 || wday > 6 {
+                return self.err();
+            }
+            MonthWeekDay(month, week, wday)
+        } else {
+            JulianLeap(try!(self.number()))
+        };
+        let time = if self.eat(b'/') { try!(self.time()) } else { 2 * 3600 };
+        Ok((day, time))
+    }
+}
+
+/// Parses a POSIX TZ string such as `EST5EDT,M3.2.0,M11.1.0`. Note that
+/// POSIX offsets are positive west of Greenwich.
+fn parse_posix_tz(s: &str) -> Result<PosixTz, String> {
+    let mut p = PosixTzParser { s: s.as_bytes(), pos: 0 };
+    let std = LocalTimeType {
+        abbrev: try!(p.name()),
+        utoff: -try!(p.time()),
+        isdst: false,
+    };
+    if p.peek().is_none() {
+        return Ok(PosixTz { std: std, dst: None });
+    }
+
+    let dst_abbrev = try!(p.name());
+    let dst_utoff = match p.peek() {
+        Some(b',') | None => std.utoff + 3600,
+        Some(_) => -try!(p.time()),
+    };
+    // Without explicit rules, fall back to the US rules like glibc does
+    let ((start, start_time), (end, end_time)) = if p.eat(b',') {
+        let start = try!(p.rule_day());
+        if !p.eat(b',') {
+            return p.err();
+        }
+        (start, try!(p.rule_day()))
+    } else {
+        ((MonthWeekDay(3, 2, 0), 2 * 3600), (MonthWeekDay(11, 1, 0), 2 * 3600))
+    };
+    if p.peek().is_some() {
+        return p.err();
+    }
+
+    Ok(PosixTz {
+        std: std,
+        dst: Some(DstRule {
+            dst: LocalTimeType {
+                utoff: dst_utoff,
+                isdst: true,
+                abbrev: dst_abbrev,
+            },
+            start: start,
+            start_time: start_time,
+            end: end,
+            end_time: end_time,
+        }),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TimeZone, at_zone, parse_posix_tz};
+    use {Timespec, empty_tm};
+
+    fn zone(name: &str) -> TimeZone {
+        let data: &[u8] = match name {
+            "America/New_York" => include_bin!("tzdata/America/New_York"),
+            "Europe/London" => include_bin!("tzdata/Europe/London"),
+            "Australia/Sydney" => include_bin!("tzdata/Australia/Sydney"),
+            "Asia/Kolkata" => include_bin!("tzdata/Asia/Kolkata"),
+            _ => fail!("no fixture for {}", name),
+        };
+        TimeZone::from_tzif(name, data).unwrap()
+    }
+
+    fn local(zone: &TimeZone, sec: i64) -> (i32, i32, i32, i32, i32, i32) {
+        let tm = at_zone(Timespec::new(sec, 0), zone);
+        (tm.tm_year + 1900, tm.tm_mon + 1, tm.tm_mday,
+         tm.tm_hour, tm.tm_min, tm.tm_gmtoff)
+    }
+
+    fn timespec(zone: &TimeZone, y: i32, mo: i32, d: i32, h: i32, mi: i32,
+                isdst: i32) -> i64 {
+        let mut tm = empty_tm();
+        tm.tm_year = y - 1900;
+        tm.tm_mon = mo - 1;
+        tm.tm_mday = d;
+        tm.tm_hour = h;
+        tm.tm_min = mi;
+        tm.tm_isdst = isdst;
+        zone.to_timespec(&tm).sec
+    }
+
+    #[test]
+    fn test_at_zone() {
+        let ny = zone("America/New_York");
+        assert_eq!(local(&ny, 1234567890), (2009, 2, 13, 18, 31, -18000));
+        assert_eq!(local(&ny, 1247000000), (2009, 7, 7, 16, 53, -14400));
+        assert_eq!(local(&ny, -2208988800), (1899, 12, 31, 19, 0, -18000));
+        assert_eq!(ny.abbreviation_at(Timespec::new(1247000000, 0)), "EDT");
+
+        let tm = at_zone(Timespec::new(1247000000, 500), &ny);
+        assert_eq!(tm.tm_isdst, 1);
+        assert_eq!(tm.tm_nsec, 500);
+        assert_eq!(tm.tm_yday, 187);
+
+        let london = zone("Europe/London");
+        assert_eq!(local(&london, 1403000000), (2014, 6, 17, 11, 13, 3600));
+        assert_eq!(london.abbreviation_at(Timespec::new(1403000000, 0)), "BST");
+
+        let kolkata = zone("Asia/Kolkata");
+        assert_eq!(local(&kolkata, 1234567890), (2009, 2, 14, 5, 1, 19800));
+        assert_eq!(kolkata.abbreviation_at(Timespec::new(1234567890, 0)), "IST");
+
+        let utc = TimeZone::utc();
+        assert_eq!(local(&utc, 1234567890), (2009, 2, 13, 23, 31, 0));
+    }
+
+    #[test]
+    fn test_at_zone_after_last_transition() {
+        // These are past the transitions in the fixtures, so they come from
+        // the TZ rule in the footer
+        let ny = zone("America/New_York");
+        assert_eq!(local(&ny, 2539296000), (2050, 6, 19, 20, 0, -14400));
+        assert_eq!(local(&ny, 2525472000), (2050, 1, 10, 19, 0, -18000));
+
+        let sydney = zone("Australia/Sydney");
+        assert_eq!(local(&sydney, 2540000000), (2050, 6, 28, 13, 33, 36000));
+        assert_eq!(local(&sydney, 2530000000), (2050, 3, 4, 20, 46, 39600));
+        assert_eq!(sydney.abbreviation_at(Timespec::new(2530000000, 0)), "AEDT");
+    }
+
+    #[test]
+    fn test_to_timespec() {
+        let ny = zone("America/New_York");
+        assert_eq!(timespec(&ny, 2009, 2, 13, 18, 31, -1) + 30, 1234567890);
+        assert_eq!(timespec(&ny, 2050, 7, 1, 12, 0, -1), 2540304000);
+
+        // Skipped by clocks going forward
+        assert_eq!(timespec(&ny, 2014, 3, 9, 2, 30, -1), 1394350200);
+        assert_eq!(timespec(&ny, 2050, 3, 13, 2, 30, -1), 2530769400);
+        assert_eq!(timespec(&ny, 2050, 3, 13, 3, 30, -1), 2530769400);
+
+        // Repeated by clocks going back
+        assert_eq!(timespec(&ny, 2014, 11, 2, 1, 30, -1), 1414906200);
+        assert_eq!(timespec(&ny, 2014, 11, 2, 1, 30, 1), 1414906200);
+        assert_eq!(timespec(&ny, 2014, 11, 2, 1, 30, 0), 1414909800);
+
+        let sydney = zone("Australia/Sydney");
+        assert_eq!(timespec(&sydney, 2050, 4, 3, 2, 30, -1), 2532526200);
+        assert_eq!(timespec(&sydney, 2050, 4, 3, 2, 30, 0), 2532529800);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let sydney = zone("Australia/Sydney");
+        let mut sec = 1000000000;
+        while sec < 2600000000 {
+            let tm = at_zone(Timespec::new(sec, 0), &sydney);
+            assert_eq!(sydney.to_timespec(&tm).sec, sec);
+            sec += 86400 * 7 + 3607;
+        }
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(TimeZone::from_tzif("x", b"").is_err());
+        assert!(TimeZone::from_tzif("x", b"TZjf2").is_err());
+        let ny = include_bin!("tzdata/America/New_York");
+        assert!(TimeZone::from_tzif("x", ny.slice_to(500)).is_err());
+
+        // A header claiming 2^32 - 1 transitions in a file of a few kilobytes
+        let mut huge = ny.to_vec();
+        for b in huge.mut_slice(32, 36).mut_iter() {
+            *b = 0xff;
+        }
+        assert!(TimeZone::from_tzif("x", huge.as_slice()).is_err());
+        assert!(TimeZone::named("../etc/passwd").is_err());
+        assert!(TimeZone::named("/etc/passwd").is_err());
+    }
+
+    #[test]
+    fn test_parse_posix_tz() {
+        assert!(parse_posix_tz("UTC0").is_ok());
+        assert!(parse_posix_tz("<+0630>-6:30").is_ok());
+        assert!(parse_posix_tz("EST5EDT,M3.2.0,M11.1.0").is_ok());
+        assert!(parse_posix_tz("IST-1GMT0,M10.5.0,M3.5.0/1").is_ok());
+        assert!(parse_posix_tz("<-03>3<-02>,M3.5.0/-2,M10.5.0/-1").is_ok());
+        assert!(parse_posix_tz("EST5EDT,J60/2,300").is_ok());
+
+        assert!(parse_posix_tz("").is_err());
+        assert!(parse_posix_tz("E5").is_err());
+        assert!(parse_posix_tz("EST").is_err());
+        assert!(parse_posix_tz("EST5EDT,M13.2.0,M11.1.0").is_err());
+        assert!(parse_posix_tz("EST5EDT,M3.2.0").is_err());
+        assert!(parse_posix_tz("EST5 EDT").is_err());
+    }
+}
@@ -3916,6 +3916,10 @@ pub mod regex {
         '\U0002fa1d'), ('\U000e0100', '\U000e01ef')
     ];
 
+    pub static UPPER_TO_LOWER: &'static [(char, char)] = super::conversions::LuLl_table;
+
+    pub static LOWER_TO_UPPER: &'static [(char, char)] = super::conversions::LlLu_table;
+
 }
 
 pub mod normalization {
@@ -6383,7 +6387,7 @@ pub mod conversions {
         }
     }
 
-    static LuLl_table: &'static [(char, char)] = &[
+    pub static LuLl_table: &'static [(char, char)] = &[
         ('\x41', '\x61'), ('\x42', '\x62'), ('\x43', '\x63'), ('\x44', '\x64'), ('\x45', '\x65'),
         ('\x46', '\x66'), ('\x47', '\x67'), ('\x48', '\x68'), ('\x49', '\x69'), ('\x4a', '\x6a'),
         ('\x4b', '\x6b'), ('\x4c', '\x6c'), ('\x4d', '\x6d'), ('\x4e', '\x6e'), ('\x4f', '\x6f'),
@@ -6644,7 +6648,7 @@ pub mod conversions {
         ('\U000118bd', '\U000118dd'), ('\U000118be', '\U000118de'), ('\U000118bf', '\U000118df')
     ];
 
-    static LlLu_table: &'static [(char, char)] = &[
+    pub static LlLu_table: &'static [(char, char)] = &[
         ('\x61', '\x41'), ('\x62', '\x42'), ('\x63', '\x43'), ('\x64', '\x44'), ('\x65', '\x45'),
         ('\x66', '\x46'), ('\x67', '\x47'), ('\x68', '\x48'), ('\x69', '\x49'), ('\x6a', '\x4a'),
         ('\x6b', '\x4b'), ('\x6c', '\x4c'), ('\x6d', '\x4d'), ('\x6e', '\x4e'), ('\x6f', '\x4f'),
@@ -22,11 +22,11 @@
        html_playground_url = "http://play.rust-lang.org/")]
 #![feature(default_type_params)]
 
+use std::char;
 use std::collections::HashMap;
 use std::fmt;
 use std::from_str::FromStr;
 use std::hash;
-use std::uint;
 use std::path::BytesContainer;
 
 /// A Uniform Resource Locator (URL).  A URL is a form of URI (Uniform Resource
@@ -134,6 +134,112 @@ impl Url {
                             fragment);
         Ok(url)
     }
+
+    /// Resolves a URI reference against this URL, as described in section 5
+    /// of RFC 3986.
+    ///
+    /// # Arguments
+    /// * reference - an absolute URL, or a reference relative to this one
+    ///   such as `../a?b=c#d`.
+    ///
+    /// # Return value
+    ///
+    /// `Err(e)` if the reference could not be parsed. Otherwise, `Ok(u)` where
+    /// `u` is the target URL, with any `.` and `..` segments removed from its
+    /// path.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # #![allow(deprecated)]
+    /// use url::Url;
+    ///
+    /// let base = Url::parse("http://example.com/a/b/c").unwrap();
+    /// let url = base.join("../d?e=1#f").unwrap();
+    /// assert_eq!(url.to_string().as_slice(), "http://example.com/a/d?e=1#f");
+    /// ```
+    pub fn join(&self, reference: &str) -> DecodeResult<Url> {
+        if get_scheme(reference).is_ok() {
+            let mut url = try!(Url::parse(reference));
+            url.path.path = remove_dot_segments(url.path.path.as_slice());
+            return Ok(url);
+        }
+
+        let has_authority = reference.starts_with("//");
+        let (userinfo, host, port, rest) = try!(get_authority(reference));
+        let (path, rest) = try!(get_path(rest, has_authority));
+        let has_query = rest.starts_with("?");
+        let (query, fragment) = try!(get_query_fragment(rest));
+
+        let mut url = self.clone();
+        url.path.fragment = fragment;
+        if has_authority {
+            url.user = userinfo;
+            url.host = host.to_string();
+            url.port = port;
+            url.path.path = remove_dot_segments(path.as_slice());
+            url.path.query = query;
+        } else if path.is_empty() {
+            if has_query {
+                url.path.query = query;
+            }
+        } else {
+            let path = if path.as_slice().starts_with("/") {
+                path
+            } else {
+                // merge with everything up to the last '/' of the base path
+                let base = self.path.path.as_slice();
+                match base.rfind('/') {
+                    Some(i) => format!("{}{}", base.slice_to(i + 1), path),
+                    None if !self.host.is_empty() => format!("/{}", path),
+                    None => path,
+                }
+            };
+            url.path.path = remove_dot_segments(path.as_slice());
+            url.path.query = query;
+        }
+        Ok(url)
+    }
+
+    /// Returns a normalized copy of this URL, suitable for comparing URLs
+    /// which differ only in ways that do not change the resource they refer
+    /// to (see section 6 of RFC 3986).
+    ///
+    /// The scheme and host are lowercased, internationalized host names are
+    /// converted to their ASCII form with `domain_to_ascii`, `.` and `..`
+    /// segments are removed from the path, an empty path after a host
+    /// becomes `/`, and the port is dropped if it is the scheme's default.
+    ///
+    /// # Return value
+    ///
+    /// `Err(e)` if the host could not be converted to ASCII. Otherwise,
+    /// `Ok(u)` where `u` is the normalized URL.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # #![allow(deprecated)]
+    /// use url::Url;
+    ///
+    /// let url = Url::parse("HTTP://Example.COM:80/a/./b/../c").unwrap();
+    /// assert_eq!(url.normalize().unwrap().to_string().as_slice(),
+    ///            "http://example.com/a/c");
+    /// ```
+    pub fn normalize(&self) -> DecodeResult<Url> {
+        let mut url = self.clone();
+        url.scheme = url.scheme.as_slice().chars().map(|c| c.to_lowercase()).collect();
+        if !url.host.is_empty() {
+            url.host = try!(domain_to_ascii(url.host.as_slice()));
+            if url.path.path.is_empty() {
+                url.path.path = "/".to_string();
+            }
+        }
+        url.path.path = remove_dot_segments(url.path.path.as_slice());
+        if url.port.is_some() && url.port == default_port(url.scheme.as_slice()) {
+            url.port = None;
+        }
+        Ok(url)
+    }
 }
 
 #[deprecated="use `Url::parse`"]
@@ -260,7 +366,9 @@ pub fn decode_component<T: BytesContainer>(container: T) -> DecodeResult<String>
 }
 
 fn decode_inner<T: BytesContainer>(c: T, full_url: bool) -> DecodeResult<String> {
-    let mut out = String::new();
+    // Bytes that are not escaped are copied through unchanged, so that the
+    // non-ASCII characters of an IRI survive decoding.
+    let mut out = Vec::new();
     let mut iter = c.container_as_bytes().iter().map(|&b| b);
 
     loop {
@@ -273,8 +381,10 @@ fn decode_inner<T: BytesContainer>(c: T, full_url: bool) -> DecodeResult<String>
                                                 without two trailing bytes")),
                     };
 
+                    let byte = try!(decode_hex_byte(bytes));
+
                     // Only decode some characters if full_url:
-                    match uint::parse_bytes(bytes, 16u).unwrap() as u8 as char {
+                    match byte as char {
                         // gen-delims:
                         ':' | '/' | '?' | '#' | '[' | ']' | '@' |
 
@@ -282,21 +392,34 @@ fn decode_inner<T: BytesContainer>(c: T, full_url: bool) -> DecodeResult<String>
                         '!' | '$' | '&' | '"' | '(' | ')' | '*' |
                         '+' | ',' | ';' | '='
                             if full_url => {
-                            out.push_char('%');
-                            out.push_char(bytes[0u] as char);
-                            out.push_char(bytes[1u] as char);
+                            out.push('%' as u8);
+                            out.push(bytes[0u]);
+                            out.push(bytes[1u]);
                         }
 
-                        ch => out.push_char(ch)
+                        _ => out.push(byte)
                     }
                 }
-                ch => out.push_char(ch)
+                _ => out.push(b)
+            },
+            None => return match String::from_utf8(out) {
+                Ok(s) => Ok(s),
+                Err(_) => Err("Malformed input: not valid UTF-8".to_string()),
             },
-            None => return Ok(out),
         }
     }
 }
 
+// Decodes the two hex digits that follow a '%'.
+fn decode_hex_byte(bytes: [u8, ..2]) -> DecodeResult<u8> {
+    match ((bytes[0] as char).to_digit(16), (bytes[1] as char).to_digit(16)) {
+        (Some(high), Some(low)) => Ok((high * 16 + low) as u8),
+        _ => Err(format!("Malformed input: found '%' followed by '{}{}' \
+                          rather than two hex digits",
+                         bytes[0] as char, bytes[1] as char)),
+    }
+}
+
 /// Encode a hashmap to the 'application/x-www-form-urlencoded' media type.
 pub fn encode_form_urlencoded(m: &HashMap<String, Vec<String>>) -> String {
     fn encode_plus<T: Str>(s: &T) -> String {
@@ -374,7 +497,7 @@ pub fn decode_form_urlencoded(s: &[u8])
                                                 '%' without two trailing bytes"))
                             };
 
-                            uint::parse_bytes(bytes, 16u).unwrap() as u8 as char
+                            try!(decode_hex_byte(bytes)) as char
                         }
                         '+' => ' ',
                         ch => ch
@@ -395,6 +518,260 @@ pub fn decode_form_urlencoded(s: &[u8])
     }
 }
 
+static PUNYCODE_BASE: u32 = 36;
+static PUNYCODE_TMIN: u32 = 1;
+static PUNYCODE_TMAX: u32 = 26;
+static PUNYCODE_SKEW: u32 = 38;
+static PUNYCODE_DAMP: u32 = 700;
+static PUNYCODE_INITIAL_BIAS: u32 = 72;
+static PUNYCODE_INITIAL_N: u32 = 0x80;
+
+fn punycode_adapt(delta: u32, num_points: u32, first_time: bool) -> u32 {
+    let mut delta = if first_time { delta / PUNYCODE_DAMP } else { delta / 2 };
+    delta += delta / num_points;
+    let mut k = 0;
+    while delta > ((PUNYCODE_BASE - PUNYCODE_TMIN) * PUNYCODE_TMAX) / 2 {
+        delta /= PUNYCODE_BASE - PUNYCODE_TMIN;
+        k += PUNYCODE_BASE;
+    }
+    k + (PUNYCODE_BASE - PUNYCODE_TMIN + 1) * delta / (delta + PUNYCODE_SKEW)
+}
+
+fn punycode_threshold(k: u32, bias: u32) -> u32 {
+    if k <= bias {
+        PUNYCODE_TMIN
+    } else if k >= bias + PUNYCODE_TMAX {
+        PUNYCODE_TMAX
+    } else {
+        k - bias
+    }
+}
+
+fn punycode_encode_digit(d: u32) -> char {
+    if d < 26 {
+        (d as u8 + 'a' as u8) as char
+    } else {
+        (d as u8 - 26 + '0' as u8) as char
+    }
+}
+
+fn punycode_decode_digit(c: char) -> Option<u32> {
+    match c {
+        'a' .. 'z' => Some(c as u32 - 'a' as u32),
+        'A' .. 'Z' => Some(c as u32 - 'A' as u32),
+        '0' .. '9' => Some(c as u32 - '0' as u32 + 26),
+        _ => None,
+    }
+}
+
+/// Encodes a string with the Punycode algorithm of RFC 3492, as used for
+/// internationalized domain name labels (without the `xn--` prefix).
+///
+/// Returns `None` if the input is too long to be encoded.
+///
+/// # Example
+///
+/// ```rust
+/// # #![allow(deprecated)]
+/// use url::punycode_encode;
+///
+/// assert_eq!(punycode_encode("bücher"), Some("bcher-kva".to_string()));
+/// ```
+pub fn punycode_encode(input: &str) -> Option<String> {
+    let input: Vec<u32> = input.chars().map(|c| c as u32).collect();
+    let mut output: String = input.iter()
+                                  .filter(|&&c| c < PUNYCODE_INITIAL_N)
+                                  .map(|&c| c as u8 as char)
+                                  .collect();
+    let basic = output.len() as u32;
+    if basic > 0 {
+        output.push_char('-');
+    }
+
+    // Arithmetic is done in 64 bits so that overflow of the 32-bit
+    // quantities the algorithm is defined over can be detected.
+    let max = 0xffff_ffff_u64;
+    let mut n = PUNYCODE_INITIAL_N;
+    let mut delta = 0u64;
+    let mut bias = PUNYCODE_INITIAL_BIAS;
+    let mut handled = basic;
+    while (handled as uint) < input.len() {
+        let m = *input.iter().filter(|&&c| c >= n).min().unwrap();
+        delta += (m - n) as u64 * (handled + 1) as u64;
+        if delta > max {
+            return None;
+        }
+        n = m;
+        for &c in input.iter() {
+            if c < n {
+                delta += 1;
+                if delta > max {
+                    return None;
+                }
+            }
+            if c == n {
+                let mut q = delta as u32;
+                let mut k = PUNYCODE_BASE;
+                loop {
+                    let t = punycode_threshold(k, bias);
+                    if q < t {
+                        break;
+                    }
+                    output.push_char(punycode_encode_digit(t + (q - t) % (PUNYCODE_BASE - t)));
+                    q = (q - t) / (PUNYCODE_BASE - t);
+                    k += PUNYCODE_BASE;
+                }
+                output.push_char(punycode_encode_digit(q));
+                bias = punycode_adapt(delta as u32, handled + 1, handled == basic);
+                delta = 0;
+                handled += 1;
+            }
+        }
+        delta += 1;
+        n += 1;
+    }
+    Some(output)
+}
+
+/// Decodes a string encoded with the Punycode algorithm of RFC 3492.
+///
+/// Returns `None` if the input is not valid Punycode.
+///
+/// # Example
+///
+/// ```rust
+/// # #![allow(deprecated)]
+/// use url::punycode_decode;
+///
+/// assert_eq!(punycode_decode("bcher-kva"), Some("bücher".to_string()));
+/// ```
+pub fn punycode_decode(input: &str) -> Option<String> {
+    let (basic, extended) = match input.rfind('-') {
+        Some(i) => (input.slice_to(i), input.slice_from(i + 1)),
+        None => ("", input),
+    };
+    if basic.chars().any(|c| c as u32 >= PUNYCODE_INITIAL_N) {
+        return None;
+    }
+    let mut output: Vec<char> = basic.chars().collect();
+
+    let max = 0xffff_ffff_u64;
+    let mut n = PUNYCODE_INITIAL_N as u64;
+    let mut i = 0u64;
+    let mut bias = PUNYCODE_INITIAL_BIAS;
+    let mut digits = extended.chars().peekable();
+    while digits.peek().is_some() {
+        let old_i = i;
+        let mut w = 1u64;
+        let mut k = PUNYCODE_BASE;
+        loop {
+            let digit = match digits.next().and_then(|c| punycode_decode_digit(c)) {
+                Some(d) => d,
+                None => return None,
+            };
+            i += digit as u64 * w;
+            if i > max {
+                return None;
+            }
+            let t = punycode_threshold(k, bias);
+            if digit < t {
+                break;
+            }
+            w *= (PUNYCODE_BASE - t) as u64;
+            if w > max {
+                return None;
+            }
+            k += PUNYCODE_BASE;
+        }
+        let len = output.len() as u64 + 1;
+        bias = punycode_adapt((i - old_i) as u32, len as u32, old_i == 0);
+        n += i / len;
+        i %= len;
+        let c = match char::from_u32(n as u32) {
+            Some(c) if n <= max => c,
+            _ => return None,
+        };
+        output.insert(i as uint, c);
+        i += 1;
+    }
+    Some(output.move_iter().collect())
+}
+
+// Full stops recognized as label separators by IDNA (RFC 3490, section 3.1).
+fn is_label_separator(c: char) -> bool {
+    c == '.' || c == '\u3002' || c == '\uff0e' || c == '\uff61'
+}
+
+/// Converts a domain name to the ASCII form used on the wire, as described
+/// by IDNA (RFC 3490).
+///
+/// Labels are lowercased, and those containing non-ASCII characters are
+/// Punycode-encoded with an `xn--` prefix. The full nameprep mapping is not
+/// applied, so input should already be in Unicode normalization form KC.
+///
+/// # Example
+///
+/// ```rust
+/// # #![allow(deprecated)]
+/// use url::domain_to_ascii;
+///
+/// assert_eq!(domain_to_ascii("Bücher.example"),
+///            Ok("xn--bcher-kva.example".to_string()));
+/// ```
+pub fn domain_to_ascii(domain: &str) -> DecodeResult<String> {
+    let mut out = String::new();
+    for (i, label) in domain.split(is_label_separator).enumerate() {
+        if i > 0 {
+            out.push_char('.');
+        }
+        let label: String = label.chars().map(|c| c.to_lowercase()).collect();
+        if label.as_slice().chars().all(|c| (c as u32) < PUNYCODE_INITIAL_N) {
+            out.push_str(label.as_slice());
+            continue;
+        }
+        match punycode_encode(label.as_slice()) {
+            Some(ref encoded) if encoded.len() + 4 <= 63 => {
+                out.push_str("xn--");
+                out.push_str(encoded.as_slice());
+            }
+            _ => return Err(format!("url: Invalid domain label '{}'.", label)),
+        }
+    }
+    Ok(out)
+}
+
+/// Converts a domain name in ASCII form back to Unicode, decoding any labels
+/// with an `xn--` prefix.
+///
+/// # Example
+///
+/// ```rust
+/// # #![allow(deprecated)]
+/// use url::domain_to_unicode;
+///
+/// assert_eq!(domain_to_unicode("xn--bcher-kva.example"),
+///            Ok("bücher.example".to_string()));
+/// ```
+pub fn domain_to_unicode(domain: &str) -> DecodeResult<String> {
+    let mut out = String::new();
+    for (i, label) in domain.split('.').enumerate() {
+        if i > 0 {
+            out.push_char('.');
+        }
+        let prefix: String = label.chars().take(4).map(|c| c.to_lowercase()).collect();
+        let is_ace = prefix.as_slice() == "xn--";
+        if !is_ace {
+            out.push_str(label);
+            continue;
+        }
+        match punycode_decode(label.slice_from(4)) {
+            Some(decoded) => out.push_str(decoded.as_slice()),
+            None => return Err(format!("url: Invalid Punycode label '{}'.", label)),
+        }
+    }
+    Ok(out)
+}
+
 fn split_char_first(s: &str, c: char) -> (&str, &str) {
     let mut iter = s.splitn(1, c);
 
@@ -468,7 +845,7 @@ pub fn query_to_str(query: &Query) -> String {
 /// println!("Scheme in use: {}.", scheme); // Scheme in use: https.
 /// ```
 pub fn get_scheme(rawurl: &str) -> DecodeResult<(&str, &str)> {
-    for (i,c) in rawurl.chars().enumerate() {
+    for (i,c) in rawurl.char_indices() {
         let result = match c {
             'A' .. 'Z'
             | 'a' .. 'z' => continue,
@@ -530,7 +907,7 @@ fn get_authority(rawurl: &str) ->
     let mut begin = 2;
     let mut end = len;
 
-    for (i,c) in rawurl.chars().enumerate()
+    for (i,c) in rawurl.char_indices()
                                // ignore the leading '//' handled by early return
                                .skip(2) {
         // deal with input class first
@@ -550,6 +927,8 @@ fn get_authority(rawurl: &str) ->
             ':' | '@' | '?' | '#' | '/' => {
                 // separators, don't change anything
             }
+            // internationalized domain names
+            c if c as u32 >= 0x80 => input = Unreserved,
             _ => return Err("Illegal character in authority".to_string()),
         }
 
@@ -666,7 +1045,7 @@ fn get_authority(rawurl: &str) ->
 fn get_path(rawurl: &str, is_authority: bool) -> DecodeResult<(String, &str)> {
     let len = rawurl.len();
     let mut end = len;
-    for (i,c) in rawurl.chars().enumerate() {
+    for (i,c) in rawurl.char_indices() {
         match c {
           'A' .. 'Z'
           | 'a' .. 'z'
@@ -675,6 +1054,8 @@ fn get_path(rawurl: &str, is_authority: bool) -> DecodeResult<(String, &str)> {
           | '@' | ':' | '%' | '/' | '+'
           | '!' | '*' | ',' | ';' | '='
           | '_' | '-' | '~' => continue,
+          // internationalized resource identifiers
+          c if c as u32 >= 0x80 => continue,
           '?' | '#' => {
             end = i;
             break;
@@ -709,6 +1090,54 @@ fn get_query_fragment(rawurl: &str) -> DecodeResult<(Query, Option<String>)> {
     }
 }
 
+// Removes `.` and `..` segments from a path, as described in section 5.2.4
+// of RFC 3986.
+fn remove_dot_segments(path: &str) -> String {
+    fn pop_segment(output: &mut String) {
+        let len = output.as_slice().rfind('/').unwrap_or(0);
+        output.truncate(len);
+    }
+
+    let mut input = path;
+    let mut output = String::new();
+    while !input.is_empty() {
+        if input.starts_with("../") {
+            input = input.slice_from(3);
+        } else if input.starts_with("./") || input.starts_with("/./") {
+            input = input.slice_from(2);
+        } else if input == "/." {
+            input = "/";
+        } else if input.starts_with("/../") {
+            input = input.slice_from(3);
+            pop_segment(&mut output);
+        } else if input == "/.." {
+            input = "/";
+            pop_segment(&mut output);
+        } else if input == "." || input == ".." {
+            input = "";
+        } else {
+            // move the first segment, and its leading '/' if any, to the output
+            let start = if input.starts_with("/") { 1 } else { 0 };
+            let end = match input.slice_from(start).find('/') {
+                Some(i) => start + i,
+                None => input.len(),
+            };
+            output.push_str(input.slice_to(end));
+            input = input.slice_from(end);
+        }
+    }
+    output
+}
+
+fn default_port(scheme: &str) -> Option<u16> {
+    match scheme {
+        "http" | "ws" => Some(80),
+        "https" | "wss" => Some(443),
+        "ftp" => Some(21),
+        _ => None,
+    }
+}
+
 impl FromStr for Url {
     fn from_str(s: &str) -> Option<Url> {
         Url::parse(s).ok()
@@ -831,6 +1260,16 @@ fn test_get_authority() {
     assert_eq!(h, "2001:0db8:85a3:0042:0000:8a2e:0370:7334");
     assert_eq!(p, Some(8000));
 
+    // multibyte hosts
+    let (_, h, p, r) = get_authority("//Bücher.example:81/ü").unwrap();
+    assert_eq!(h, "Bücher.example");
+    assert_eq!(p, Some(81));
+    assert_eq!(r, "/ü");
+    let (_, h, p, r) = get_authority("//ü/").unwrap();
+    assert_eq!(h, "ü");
+    assert!(p.is_none());
+    assert_eq!(r, "/");
+
     // invalid authorities;
     assert!(get_authority("//user:pass@rust-lang:something").is_err());
     assert!(get_authority("//user@rust-lang:something:/path").is_err());
@@ -848,6 +1287,16 @@ fn test_get_authority() {
     assert_eq!(h, "");
 }
 
+#[test]
+fn test_remove_dot_segments() {
+    assert_eq!(remove_dot_segments("/a/b/c/./../../g"), "/a/g".to_string());
+    assert_eq!(remove_dot_segments("mid/content=5/../6"), "mid/6".to_string());
+    assert_eq!(remove_dot_segments("/a/b/.."), "/a/".to_string());
+    assert_eq!(remove_dot_segments("../../a"), "a".to_string());
+    assert_eq!(remove_dot_segments("/../.."), "/".to_string());
+    assert_eq!(remove_dot_segments(""), "".to_string());
+}
+
 #[test]
 fn test_get_path() {
     let (p, r) = get_path("/something+%20orother", true).unwrap();
@@ -859,6 +1308,9 @@ fn test_get_path() {
     let (p, r) = get_path("/gen/:addr=?q=v", false).unwrap();
     assert_eq!(p, "/gen/:addr=".to_string());
     assert_eq!(r, "?q=v");
+    let (p, r) = get_path("/bücher/ü?q#f", true).unwrap();
+    assert_eq!(p, "/bücher/ü".to_string());
+    assert_eq!(r, "?q#f");
 
     //failure cases
     assert!(get_path("something?q", true).is_err());
@@ -868,6 +1320,7 @@ fn test_get_path() {
 mod tests {
     use {encode_form_urlencoded, decode_form_urlencoded, decode, encode,
         encode_component, decode_component, UserInfo, get_scheme, Url, Path};
+    use {punycode_encode, punycode_decode, domain_to_ascii, domain_to_unicode};
 
     use std::collections::HashMap;
     use std::path::BytesContainer;
@@ -886,6 +1339,19 @@ mod tests {
         assert_eq!(u.path.fragment, Some("something".to_string()));
     }
 
+    #[test]
+    fn test_url_parse_multibyte() {
+        let u = from_str::<Url>("http://Bücher.example/bücher/ü?q=ü#ü").unwrap();
+        assert_eq!(u.host, "Bücher.example".to_string());
+        assert_eq!(u.path.path, "/bücher/ü".to_string());
+        assert_eq!(u.path.query, vec!(("q".to_string(), "ü".to_string())));
+        assert_eq!(u.path.fragment, Some("ü".to_string()));
+
+        let u = from_str::<Url>("http://ü/").unwrap();
+        assert_eq!(u.host, "ü".to_string());
+        assert_eq!(u.path.path, "/".to_string());
+    }
+
     #[test]
     fn test_path_parse() {
         let path = "/doc/~u?s=v#something";
@@ -1143,6 +1609,9 @@ mod tests {
 
         assert!(decode("sadsadsda%").is_err());
         assert!(decode("waeasd%4").is_err());
+        assert!(decode("%zz").is_err());
+        t("b%C3%BCcher", "bücher");
+        t("http://example.com/b%C3%BCcher%2F", "http://example.com/bücher%2F");
         t("", "");
         t("abc/def 123", "abc/def 123");
         t("abc%2Fdef%20123", "abc%2Fdef 123");
@@ -1179,9 +1648,17 @@ mod tests {
 
         assert!(decode_component("asacsa%").is_err());
         assert!(decode_component("acsas%4").is_err());
+        assert!(decode_component("%zz").is_err());
+        assert!(decode_component("%4g").is_err());
+        assert!(decode_component("%C3").is_err());
+        assert!(decode_component("%FF%FE").is_err());
         t("", "");
         t("abc/def 123", "abc/def 123");
         t("abc%2Fdef%20123", "abc/def 123");
+        t("bücher%20ü", "bücher ü");
+        t("b%C3%BCcher", "bücher");
+        t("b%c3%bccher", "bücher");
+        t("%E6%97%A5%E6%9C%AC", "日本");
         t("%20", " ");
         t("%21", "!");
         t("%22", "\"");
@@ -1236,5 +1713,133 @@ mod tests {
         assert_eq!(form.get(&"a".to_string()), &vec!("1".to_string()));
         assert_eq!(form.get(&"foo bar".to_string()),
                    &vec!("abc".to_string(), "12 = 34".to_string()));
+
+        assert!(decode_form_urlencoded("a=%zz".as_bytes()).is_err());
+    }
+
+    fn check_join(base: &Url, cases: &[(&str, &str)]) {
+        for &(reference, expected) in cases.iter() {
+            let joined = base.join(reference).unwrap();
+            assert!(joined == Url::parse(expected).unwrap(),
+                    "{} joined with {}: expected {}, got {}",
+                    base, reference, expected, joined);
+        }
+    }
+
+    #[test]
+    fn test_join_normal_examples() {
+        // RFC 3986, section 5.4.1
+        let base = Url::parse("http://a/b/c/d;p?q").unwrap();
+        check_join(&base, &[
+            ("g:h", "g:h"),
+            ("g", "http://a/b/c/g"),
+            ("./g", "http://a/b/c/g"),
+            ("g/", "http://a/b/c/g/"),
+            ("/g", "http://a/g"),
+            ("//g", "http://g"),
+            ("?y", "http://a/b/c/d;p?y"),
+            ("g?y", "http://a/b/c/g?y"),
+            ("#s", "http://a/b/c/d;p?q#s"),
+            ("g#s", "http://a/b/c/g#s"),
+            ("g?y#s", "http://a/b/c/g?y#s"),
+            (";x", "http://a/b/c/;x"),
+            ("g;x", "http://a/b/c/g;x"),
+            ("g;x?y#s", "http://a/b/c/g;x?y#s"),
+            ("", "http://a/b/c/d;p?q"),
+            (".", "http://a/b/c/"),
+            ("./", "http://a/b/c/"),
+            ("..", "http://a/b/"),
+            ("../", "http://a/b/"),
+            ("../g", "http://a/b/g"),
+            ("../..", "http://a/"),
+            ("../../", "http://a/"),
+            ("../../g", "http://a/g"),
+        ]);
+    }
+
+    #[test]
+    fn test_join_abnormal_examples() {
+        // RFC 3986, section 5.4.2
+        let base = Url::parse("http://a/b/c/d;p?q").unwrap();
+        check_join(&base, &[
+            ("../../../g", "http://a/g"),
+            ("../../../../g", "http://a/g"),
+            ("/./g", "http://a/g"),
+            ("/../g", "http://a/g"),
+            ("g.", "http://a/b/c/g."),
+            (".g", "http://a/b/c/.g"),
+            ("g..", "http://a/b/c/g.."),
+            ("..g", "http://a/b/c/..g"),
+            ("./../g", "http://a/b/g"),
+            ("./g/.", "http://a/b/c/g/"),
+            ("g/./h", "http://a/b/c/g/h"),
+            ("g/../h", "http://a/b/c/h"),
+            ("g;x=1/./y", "http://a/b/c/g;x=1/y"),
+            ("g;x=1/../y", "http://a/b/c/y"),
+            ("g?y/./x", "http://a/b/c/g?y/./x"),
+            ("g?y/../x", "http://a/b/c/g?y/../x"),
+            ("g#s/./x", "http://a/b/c/g#s/./x"),
+            ("g#s/../x", "http://a/b/c/g#s/../x"),
+            ("http:g", "http:g"),
+        ]);
+    }
+
+    #[test]
+    fn test_join_empty_base_path() {
+        let base = Url::parse("http://a").unwrap();
+        check_join(&base, &[("g", "http://a/g"), ("?y", "http://a?y")]);
+        assert!(base.join("g h").is_err());
+    }
+
+    #[test]
+    fn test_normalize() {
+        fn t(input: &str, expected: &str) {
+            let url = Url::parse(input).unwrap().normalize().unwrap();
+            assert_eq!(url.to_string().as_slice(), expected);
+        }
+
+        t("HTTP://Example.COM", "http://example.com/");
+        t("http://example.com:80/a/./b/../c", "http://example.com/a/c");
+        t("https://example.com:443/", "https://example.com/");
+        t("https://example.com:80/", "https://example.com:80/");
+        t("http://User@Example.com/Path", "http://User@example.com/Path");
+        t("http://Bücher.example/", "http://xn--bcher-kva.example/");
+        t("mailto:Joe@Example.com", "mailto:Joe@Example.com");
+    }
+
+    #[test]
+    fn test_punycode() {
+        let cases = [
+            ("bücher", "bcher-kva"),
+            ("münchen", "mnchen-3ya"),
+            ("ü", "tda"),
+            ("example", "example-"),
+            // RFC 3492, section 7.1
+            ("他们为什么不说中文", "ihqwcrb4cv8a8dqg056pqjye"),
+            ("ليهمابتكلموشعربي؟", "egbpdaj6bu4bxfgehfvwxn"),
+        ];
+        for &(decoded, encoded) in cases.iter() {
+            assert_eq!(punycode_encode(decoded), Some(encoded.to_string()));
+            assert_eq!(punycode_decode(encoded), Some(decoded.to_string()));
+        }
+
+        assert_eq!(punycode_decode("bcher-kv!"), None);
+        assert_eq!(punycode_decode("ü-tda"), None);
+        assert_eq!(punycode_decode("99999999999"), None);
+    }
+
+    #[test]
+    fn test_domain_to_ascii_and_unicode() {
+        assert_eq!(domain_to_ascii("www.Bücher.example"),
+                   Ok("www.xn--bcher-kva.example".to_string()));
+        assert_eq!(domain_to_ascii("bücher\u3002example"),
+                   Ok("xn--bcher-kva.example".to_string()));
+        assert_eq!(domain_to_ascii("rust-lang.org"), Ok("rust-lang.org".to_string()));
+        assert!(domain_to_ascii(String::from_char(60, 'ü').as_slice()).is_err());
+
+        assert_eq!(domain_to_unicode("www.XN--bcher-kva.example"),
+                   Ok("www.bücher.example".to_string()));
+        assert_eq!(domain_to_unicode("rust-lang.org"), Ok("rust-lang.org".to_string()));
+        assert!(domain_to_unicode("xn--bcher-kv!.example").is_err());
     }
 }
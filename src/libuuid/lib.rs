@@ -39,6 +39,21 @@ fn main() {
 }
 ```
 
+To create a name-based (V5) UUID, which is always the same for a given
+namespace and name:
+
+```rust
+# #![allow(deprecated)]
+# extern crate uuid;
+use uuid::{Uuid, NAMESPACE_DNS};
+
+fn main() {
+    let uuid1 = Uuid::new_v5(&NAMESPACE_DNS, b"python.org");
+    assert_eq!(uuid1.to_hyphenated_str().as_slice(),
+               "886313e1-3b8a-5372-9b90-0c9aee199e5d");
+}
+```
+
 # Strings
 
 Examples of string representations:
@@ -74,8 +89,10 @@ Examples of string representations:
 #[cfg(test)]
 extern crate test;
 extern crate serialize;
+extern crate time;
 
 use std::char::Char;
+use std::cmp;
 use std::default::Default;
 use std::fmt;
 use std::from_str::FromStr;
@@ -84,10 +101,14 @@ use std::mem::{transmute,transmute_copy};
 use std::num::FromStrRadix;
 use std::rand;
 use std::rand::Rng;
+use std::rt::mutex::{StaticNativeMutex, NATIVE_MUTEX_INIT};
 use std::slice;
 
 use serialize::{Encoder, Encodable, Decoder, Decodable};
 
+mod md5;
+mod sha1;
+
 /// A 128-bit (16 byte) buffer containing the ID
 pub type UuidBytes = [u8, ..16];
 
@@ -175,6 +196,30 @@ impl fmt::Show for ParseError {
 // Length of each hyphenated group in hex digits
 static UuidGroupLens: [uint, ..5] = [8u, 4u, 4u, 4u, 12u];
 
+// Number of 100ns intervals between the start of the Gregorian calendar
+// (1582-10-15) and the Unix epoch, as used by version 1 timestamps
+static GregorianEpochOffset: u64 = 0x01B21DD213814000;
+
+/// The namespace for fully-qualified domain names (RFC4122, Appendix C)
+pub static NAMESPACE_DNS: Uuid = Uuid { bytes: [
+    0x6b, 0xa7, 0xb8, 0x10, 0x9d, 0xad, 0x11, 0xd1,
+    0x80, 0xb4, 0x00, 0xc0, 0x4f, 0xd4, 0x30, 0xc8] };
+
+/// The namespace for URLs (RFC4122, Appendix C)
+pub static NAMESPACE_URL: Uuid = Uuid { bytes: [
+    0x6b, 0xa7, 0xb8, 0x11, 0x9d, 0xad, 0x11, 0xd1,
+    0x80, 0xb4, 0x00, 0xc0, 0x4f, 0xd4, 0x30, 0xc8] };
+
+/// The namespace for ISO object identifiers (RFC4122, Appendix C)
+pub static NAMESPACE_OID: Uuid = Uuid { bytes: [
+    0x6b, 0xa7, 0xb8, 0x12, 0x9d, 0xad, 0x11, 0xd1,
+    0x80, 0xb4, 0x00, 0xc0, 0x4f, 0xd4, 0x30, 0xc8] };
+
+/// The namespace for X.500 distinguished names (RFC4122, Appendix C)
+pub static NAMESPACE_X500: Uuid = Uuid { bytes: [
+    0x6b, 0xa7, 0xb8, 0x14, 0x9d, 0xad, 0x11, 0xd1,
+    0x80, 0xb4, 0x00, 0xc0, 0x4f, 0xd4, 0x30, 0xc8] };
+
 /// UUID support
 impl Uuid {
     /// Returns a nil or empty UUID (containing all zeroes)
@@ -184,6 +229,10 @@ impl Uuid {
     }
 
     /// Create a new UUID of the specified version
+    ///
+    /// Only versions which need no further input can be created this way,
+    /// which is currently just the Random (V4) version. Use `new_v1`,
+    /// `new_v3` or `new_v5` for the others.
     pub fn new(v: UuidVersion) -> Option<Uuid> {
         match v {
             Version4Random => Some(Uuid::new_v4()),
@@ -205,6 +254,77 @@ impl Uuid {
         uuid
     }
 
+    /// Creates a new time-based UUID for the given node ID
+    ///
+    /// The UUID contains the current time as a count of 100ns intervals
+    /// since the start of the Gregorian calendar, a 14-bit clock sequence
+    /// and the node ID, which should be a 6-byte IEEE 802 MAC address or
+    /// a random value with its multicast bit set (see RFC4122 section
+    /// 4.5). Returns `None` if `node` is not 6 bytes long.
+    ///
+    /// The clock sequence starts at a random value and is incremented
+    /// when the system clock goes backwards or the node ID changes. UUIDs
+    /// generated by one process within a single tick of a coarse clock get
+    /// successive timestamps instead, so they are distinct too.
+    pub fn new_v1(node: &[u8]) -> Option<Uuid> {
+        if node.len() != 6 {
+            return None
+        }
+
+        let now = time::get_time();
+        let reading = (now.sec as u64) * 10_000_000 + (now.nsec as u64) / 100 +
+                      GregorianEpochOffset;
+        let (ts, clock_seq) = next_v1_clock(reading, node);
+        Some(Uuid::from_v1_parts(ts, clock_seq, node))
+    }
+
+    /// Creates a version 1 UUID from an explicit timestamp, clock sequence
+    /// and 6-byte node ID
+    fn from_v1_parts(ts: u64, clock_seq: u16, node: &[u8]) -> Uuid {
+        let mut d4 = [0u8, ..8];
+        d4[0] = (clock_seq >> 8) as u8;
+        d4[1] = clock_seq as u8;
+        slice::bytes::copy_memory(d4.mut_slice_from(2), node);
+
+        let mut uuid = Uuid::from_fields(ts as u32, (ts >> 32) as u16,
+                                         (ts >> 48) as u16, d4.as_slice());
+        uuid.set_variant(VariantRFC4122);
+        uuid.set_version(Version1Mac);
+        uuid
+    }
+
+    /// Creates a name-based UUID using the MD5 hash of a namespace and a name
+    ///
+    /// The same namespace and name always produce the same UUID. The
+    /// namespace is usually one of the `NAMESPACE_*` constants. Prefer
+    /// `new_v5` unless compatibility with existing V3 UUIDs is needed.
+    pub fn new_v3(namespace: &Uuid, name: &[u8]) -> Uuid {
+        let mut input = Vec::from_slice(namespace.as_bytes());
+        input.push_all(name);
+        let hash = md5::digest(input.as_slice());
+        Uuid::from_hash(hash.as_slice(), Version3Md5)
+    }
+
+    /// Creates a name-based UUID using the SHA-1 hash of a namespace and a
+    /// name
+    ///
+    /// The same namespace and name always produce the same UUID. The
+    /// namespace is usually one of the `NAMESPACE_*` constants.
+    pub fn new_v5(namespace: &Uuid, name: &[u8]) -> Uuid {
+        let mut input = Vec::from_slice(namespace.as_bytes());
+        input.push_all(name);
+        let hash = sha1::digest(input.as_slice());
+        Uuid::from_hash(hash.as_slice(), Version5Sha1)
+    }
+
+    /// Builds a name-based UUID from the first 16 bytes of a hash
+    fn from_hash(hash: &[u8], v: UuidVersion) -> Uuid {
+        let mut uuid = Uuid::from_bytes(hash.slice_to(16)).unwrap();
+        uuid.set_variant(VariantRFC4122);
+        uuid.set_version(v);
+        uuid
+    }
+
     /// Creates a UUID using the supplied field values
     ///
     /// # Arguments
@@ -289,9 +409,9 @@ impl Uuid {
     ///
     /// This represents the algorithm used to generate the contents.
     ///
-    /// Currently only the Random (V4) algorithm is supported by this
-    /// module.  There are security and privacy implications for using
-    /// older versions - see [Wikipedia: Universally Unique Identifier](
+    /// The MAC address (V1), MD5 (V3), Random (V4) and SHA-1 (V5)
+    /// algorithms are supported by this module.  There are security and
+    /// privacy implications for using some of the versions - see [Wikipedia: Universally Unique Identifier](
     /// http://en.wikipedia.org/wiki/Universally_unique_identifier) for
     /// details.
     ///
@@ -447,6 +567,59 @@ impl Uuid {
     }
 }
 
+/// The state used to pick the timestamp and clock sequence of version 1
+/// UUIDs (see RFC4122 section 4.2.1)
+struct V1Clock {
+    /// The previous reading of the system clock, or 0 if there is none
+    last_reading: u64,
+    /// The previous timestamp handed out, which is ahead of the clock when
+    /// it is too coarse to tell UUIDs apart
+    last_ts: u64,
+    /// The 14-bit clock sequence
+    seq: u16,
+    /// The previous node ID
+    node: [u8, ..6],
+}
+
+impl V1Clock {
+    /// Returns the timestamp and clock sequence of a UUID for `node` made
+    /// when the system clock reads `reading`
+    fn next(&mut self, reading: u64, node: &[u8]) -> (u64, u16) {
+        if self.last_reading == 0 {
+            self.last_ts = reading;
+        } else if reading < self.last_reading || node != self.node.as_slice() {
+            // Timestamps which have already been handed out may come round
+            // again, so the UUIDs are told apart by the clock sequence
+            self.seq = (self.seq + 1) & 0x3fff;
+            self.last_ts = reading;
+        } else {
+            self.last_ts = cmp::max(reading, self.last_ts + 1);
+        }
+        self.last_reading = reading;
+        slice::bytes::copy_memory(self.node, node);
+        (self.last_ts, self.seq)
+    }
+}
+
+/// Returns the timestamp and clock sequence of a version 1 UUID, using
+/// state shared by the whole process
+fn next_v1_clock(reading: u64, node: &[u8]) -> (u64, u16) {
+    static mut LOCK: StaticNativeMutex = NATIVE_MUTEX_INIT;
+    static mut CLOCK: V1Clock = V1Clock {
+        last_reading: 0,
+        last_ts: 0,
+        seq: 0,
+        node: [0, ..6],
+    };
+    unsafe {
+        let _guard = LOCK.lock();
+        if CLOCK.last_reading == 0 {
+            CLOCK.seq = rand::task_rng().gen::<u16>() & 0x3fff;
+        }
+        CLOCK.next(reading, node)
+    }
+}
+
 impl Default for Uuid {
     /// Returns the nil UUID, which is all zeroes
     fn default() -> Uuid {
@@ -524,9 +697,10 @@ impl rand::Rand for Uuid {
 
 #[cfg(test)]
 mod uuidtest {
-    use super::{Uuid, VariantMicrosoft, VariantNCS, VariantRFC4122,
+    use super::{Uuid, V1Clock, VariantMicrosoft, VariantNCS, VariantRFC4122,
                 Version1Mac, Version2Dce, Version3Md5, Version4Random,
-                Version5Sha1};
+                Version5Sha1, NAMESPACE_DNS, NAMESPACE_URL, NAMESPACE_OID,
+                NAMESPACE_X500};
     use std::rand;
 
     #[test]
@@ -562,6 +736,84 @@ mod uuidtest {
         assert!(uuid1.get_variant().unwrap() == VariantRFC4122);
     }
 
+    #[test]
+    fn test_new_v1() {
+        let node = [0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f];
+        let uuid1 = Uuid::new_v1(&node).unwrap();
+        let uuid2 = Uuid::new_v1(&node).unwrap();
+
+        assert!(uuid1.get_version().unwrap() == Version1Mac);
+        assert!(uuid1.get_variant().unwrap() == VariantRFC4122);
+        assert!(uuid1.as_bytes().slice_from(10) == node.as_slice());
+        assert!(uuid1 != uuid2);
+
+        assert!(Uuid::new_v1(&[0x0a, 0x0b, 0x0c]).is_none());
+    }
+
+    #[test]
+    fn test_v1_clock() {
+        let (a, b) = ([1u8, 2, 3, 4, 5, 6], [6u8, 5, 4, 3, 2, 1]);
+        let mut clock = V1Clock { last_reading: 0, last_ts: 0, seq: 5, node: [0, ..6] };
+        assert_eq!(clock.next(100, &a), (100, 5));
+        // A coarse clock
+        assert_eq!(clock.next(100, &a), (101, 5));
+        assert_eq!(clock.next(101, &a), (102, 5));
+        assert_eq!(clock.next(200, &a), (200, 5));
+        // The clock going backwards
+        assert_eq!(clock.next(150, &a), (150, 6));
+        // A new node
+        assert_eq!(clock.next(150, &b), (150, 7));
+        assert_eq!(clock.next(160, &b), (160, 7));
+    }
+
+    #[test]
+    fn test_v1_from_parts() {
+        let node = [0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f];
+        let uuid1 = Uuid::from_v1_parts(0x1d8b8a0e4c2f3a5, 0x1234, &node);
+
+        assert_eq!(uuid1.to_hyphenated_str().as_slice(),
+                   "e4c2f3a5-b8a0-11d8-9234-0a0b0c0d0e0f");
+    }
+
+    #[test]
+    fn test_new_v3() {
+        let uuid1 = Uuid::new_v3(&NAMESPACE_DNS, b"python.org");
+        let uuid2 = Uuid::new_v3(&NAMESPACE_OID, b"");
+
+        assert!(uuid1.get_version().unwrap() == Version3Md5);
+        assert!(uuid1.get_variant().unwrap() == VariantRFC4122);
+        assert_eq!(uuid1.to_hyphenated_str().as_slice(),
+                   "6fa459ea-ee8a-3ca4-894e-db77e160355e");
+        assert_eq!(uuid2.to_hyphenated_str().as_slice(),
+                   "596b79dc-00dd-3991-a72f-d3696c38c64f");
+    }
+
+    #[test]
+    fn test_new_v5() {
+        let uuid1 = Uuid::new_v5(&NAMESPACE_DNS, b"python.org");
+        let uuid2 = Uuid::new_v5(&NAMESPACE_URL, b"http://www.rust-lang.org/");
+
+        assert!(uuid1.get_version().unwrap() == Version5Sha1);
+        assert!(uuid1.get_variant().unwrap() == VariantRFC4122);
+        assert_eq!(uuid1.to_hyphenated_str().as_slice(),
+                   "886313e1-3b8a-5372-9b90-0c9aee199e5d");
+        assert_eq!(uuid2.to_hyphenated_str().as_slice(),
+                   "9ecb1d89-d169-5cec-8e41-21218da583d5");
+        assert!(uuid1 == Uuid::new_v5(&NAMESPACE_DNS, b"python.org"));
+    }
+
+    #[test]
+    fn test_namespaces() {
+        assert_eq!(NAMESPACE_DNS.to_hyphenated_str().as_slice(),
+                   "6ba7b810-9dad-11d1-80b4-00c04fd430c8");
+        assert_eq!(NAMESPACE_URL.to_hyphenated_str().as_slice(),
+                   "6ba7b811-9dad-11d1-80b4-00c04fd430c8");
+        assert_eq!(NAMESPACE_OID.to_hyphenated_str().as_slice(),
+                   "6ba7b812-9dad-11d1-80b4-00c04fd430c8");
+        assert_eq!(NAMESPACE_X500.to_hyphenated_str().as_slice(),
+                   "6ba7b814-9dad-11d1-80b4-00c04fd430c8");
+    }
+
     #[test]
     fn test_get_version() {
         let uuid1 = Uuid::new_v4();
@@ -0,0 +1,112 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A minimal MD5 implementation (RFC 1321), used for version 3 UUIDs.
+//!
+//! MD5 is not a secure hash; it is only here because RFC 4122 requires it
+//! for name-based UUIDs.
+
+// Per-round shift amounts
+static SHIFTS: [uint, ..64] = [
+    7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22,
+    5,  9, 14, 20, 5,  9, 14, 20, 5,  9, 14, 20, 5,  9, 14, 20,
+    4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23,
+    6, 10, 15, 21, 6, 10, 15, 21, 6, 10, 15, 21, 6, 10, 15, 21,
+];
+
+// The integer parts of abs(sin(i + 1)) * 2^32
+static CONSTANTS: [u32, ..64] = [
+    0xd76aa478, 0xe8c7b756, 0x242070db, 0xc1bdceee,
+    0xf57c0faf, 0x4787c62a, 0xa8304613, 0xfd469501,
+    0x698098d8, 0x8b44f7af, 0xffff5bb1, 0x895cd7be,
+    0x6b901122, 0xfd987193, 0xa679438e, 0x49b40821,
+    0xf61e2562, 0xc040b340, 0x265e5a51, 0xe9b6c7aa,
+    0xd62f105d, 0x02441453, 0xd8a1e681, 0xe7d3fbc8,
+    0x21e1cde6, 0xc33707d6, 0xf4d50d87, 0x455a14ed,
+    0xa9e3e905, 0xfcefa3f8, 0x676f02d9, 0x8d2a4c8a,
+    0xfffa3942, 0x8771f681, 0x6d9d6122, 0xfde5380c,
+    0xa4beea44, 0x4bdecfa9, 0xf6bb4b60, 0xbebfbc70,
+    0x289b7ec6, 0xeaa127fa, 0xd4ef3085, 0x04881d05,
+    0xd9d4d039, 0xe6db99e5, 0x1fa27cf8, 0xc4ac5665,
+    0xf4292244, 0x432aff97, 0xab9423a7, 0xfc93a039,
+    0x655b59c3, 0x8f0ccc92, 0xffeff47d, 0x85845dd1,
+    0x6fa87e4f, 0xfe2ce6e0, 0xa3014314, 0x4e0811a1,
+    0xf7537e82, 0xbd3af235, 0x2ad7d2bb, 0xeb86d391,
+];
+
+/// Computes the MD5 digest of `data`
+pub fn digest(data: &[u8]) -> [u8, ..16] {
+    let mut state: [u32, ..4] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476];
+
+    // Pad with a single 1 bit, zeroes up to 56 mod 64 bytes, and finally
+    // the message length in bits as a little-endian 64-bit integer.
+    let mut msg = Vec::from_slice(data);
+    msg.push(0x80);
+    while msg.len() % 64 != 56 {
+        msg.push(0);
+    }
+    let bits = (data.len() as u64) * 8;
+    for i in range(0u, 8) {
+        msg.push((bits >> (i * 8)) as u8);
+    }
+
+    for block in msg.as_slice().chunks(64) {
+        let mut m = [0u32, ..16];
+        for i in range(0u, 16) {
+            m[i] = (block[i * 4] as u32) |
+                   ((block[i * 4 + 1] as u32) << 8) |
+                   ((block[i * 4 + 2] as u32) << 16) |
+                   ((block[i * 4 + 3] as u32) << 24);
+        }
+
+        let (mut a, mut b, mut c, mut d) = (state[0], state[1], state[2], state[3]);
+        for i in range(0u, 64) {
+            let (f, g) = match i / 16 {
+                0 => ((b & c) | (!b & d), i),
+                1 => ((d & b) | (!d & c), (5 * i + 1) % 16),
+                2 => (b ^ c ^ d, (3 * i + 5) % 16),
+                _ => (c ^ (b | !d), (7 * i) % 16),
+            };
+            let rotated = (a + f + CONSTANTS[i] + m[g]).rotate_left(SHIFTS[i]);
+            a = d;
+            d = c;
+            c = b;
+            b = b + rotated;
+        }
+
+        state[0] = state[0] + a;
+        state[1] = state[1] + b;
+        state[2] = state[2] + c;
+        state[3] = state[3] + d;
+    }
+
+    let mut out = [0u8, ..16];
+    for i in range(0u, 16) {
+        out[i] = (state[i / 4] >> ((i % 4) * 8)) as u8;
+    }
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::digest;
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", *b)).collect::<Vec<String>>().concat()
+    }
+
+    #[test]
+    fn test_md5() {
+        assert_eq!(hex(&digest(b"")).as_slice(), "d41d8cd98f00b204e9800998ecf8427e");
+        assert_eq!(hex(&digest(b"abc")).as_slice(), "900150983cd24fb0d6963f7d28e17f72");
+        assert_eq!(hex(&digest(b"The quick brown fox jumps over the lazy dog")).as_slice(),
+                   "9e107d9d372bb6826bd81d3542a419d6");
+    }
+}
@@ -0,0 +1,89 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A minimal SHA-1 implementation (FIPS 180-4), used for version 5 UUIDs.
+
+/// Computes the SHA-1 digest of `data`
+pub fn digest(data: &[u8]) -> [u8, ..20] {
+    let mut state: [u32, ..5] = [0x67452301, 0xefcdab89, 0x98badcfe,
+                                 0x10325476, 0xc3d2e1f0];
+
+    // Same padding as MD5, except the length is stored big-endian
+    let mut msg = Vec::from_slice(data);
+    msg.push(0x80);
+    while msg.len() % 64 != 56 {
+        msg.push(0);
+    }
+    let bits = (data.len() as u64) * 8;
+    for i in range(0u, 8).rev() {
+        msg.push((bits >> (i * 8)) as u8);
+    }
+
+    for block in msg.as_slice().chunks(64) {
+        let mut w = [0u32, ..80];
+        for i in range(0u, 16) {
+            w[i] = ((block[i * 4] as u32) << 24) |
+                   ((block[i * 4 + 1] as u32) << 16) |
+                   ((block[i * 4 + 2] as u32) << 8) |
+                   (block[i * 4 + 3] as u32);
+        }
+        for i in range(16u, 80) {
+            w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+        }
+
+        let (mut a, mut b, mut c, mut d, mut e) =
+            (state[0], state[1], state[2], state[3], state[4]);
+        for i in range(0u, 80) {
+            let (f, k) = match i / 20 {
+                0 => ((b & c) | (!b & d), 0x5a827999u32),
+                1 => (b ^ c ^ d, 0x6ed9eba1),
+                2 => ((b & c) | (b & d) | (c & d), 0x8f1bbcdc),
+                _ => (b ^ c ^ d, 0xca62c1d6),
+            };
+            let temp = a.rotate_left(5) + f + e + k + w[i];
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = temp;
+        }
+
+        state[0] = state[0] + a;
+        state[1] = state[1] + b;
+        state[2] = state[2] + c;
+        state[3] = state[3] + d;
+        state[4] = state[4] + e;
+    }
+
+    let mut out = [0u8, ..20];
+    for i in range(0u, 20) {
+        out[i] = (state[i / 4] >> ((3 - i % 4) * 8)) as u8;
+    }
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::digest;
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", *b)).collect::<Vec<String>>().concat()
+    }
+
+    #[test]
+    fn test_sha1() {
+        assert_eq!(hex(&digest(b"")).as_slice(),
+                   "da39a3ee5e6b4b0d3255bfef95601890afd80709");
+        assert_eq!(hex(&digest(b"abc")).as_slice(),
+                   "a9993e364706816aba3e25717850c26c9cd0d89d");
+        assert_eq!(hex(&digest(b"The quick brown fox jumps over the lazy dog")).as_slice(),
+                   "2fd4e1c67a2d28fced849ee1bb76e7391b93eb12");
+    }
+}
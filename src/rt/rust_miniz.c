@@ -0,0 +1,60 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Glue for driving miniz's incremental tdefl/tinfl API from libflate.
+// The compressor and decompressor state structs are large and their
+// layout is private to miniz, so they are allocated and initialized here
+// and handed to Rust as opaque pointers.
+
+#include <stdlib.h>
+
+#define MINIZ_HEADER_FILE_ONLY
+#include "miniz.c"
+
+tdefl_compressor *
+rust_miniz_tdefl_new(int flags) {
+    tdefl_compressor *d = (tdefl_compressor *)malloc(sizeof(tdefl_compressor));
+    if (d == NULL) {
+        return NULL;
+    }
+    if (tdefl_init(d, NULL, NULL, flags) != TDEFL_STATUS_OKAY) {
+        free(d);
+        return NULL;
+    }
+    return d;
+}
+
+void
+rust_miniz_tdefl_free(tdefl_compressor *d) {
+    free(d);
+}
+
+tinfl_decompressor *
+rust_miniz_tinfl_new() {
+    tinfl_decompressor *r = (tinfl_decompressor *)malloc(sizeof(tinfl_decompressor));
+    if (r == NULL) {
+        return NULL;
+    }
+    tinfl_init(r);
+    return r;
+}
+
+void
+rust_miniz_tinfl_free(tinfl_decompressor *r) {
+    free(r);
+}
+
+// Once tinfl_decompress() has returned TINFL_STATUS_DONE, the number of
+// whole input bytes it pulled into its bit buffer but never used. These
+// belong to whatever follows the deflate stream (e.g. a gzip trailer).
+size_t
+rust_miniz_tinfl_unused_bytes(const tinfl_decompressor *r) {
+    return r->m_num_bits >> 3;
+}
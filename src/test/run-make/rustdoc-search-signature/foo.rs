@@ -0,0 +1,25 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![crate_name = "foo"]
+
+/// Encodes some bytes.
+///
+/// Every byte turns into two hexadecimal digits.
+pub fn encode(bytes: &Vec<u8>) -> String {
+    bytes.iter().map(|b| format!("{:02x}", *b)).collect::<Vec<String>>().concat()
+}
+
+/// Decodes hexadecimal digits into bytes.
+pub fn decode(hex: &str) -> Vec<u8> {
+    range(0, hex.len() / 2).map(|i| {
+        ::std::num::from_str_radix(hex.slice(2 * i, 2 * i + 2), 16).unwrap()
+    }).collect()
+}
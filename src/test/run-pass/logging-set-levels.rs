@@ -0,0 +1,44 @@
+// Copyright 2014 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// exec-env:RUST_LOG=error
+
+extern crate log;
+
+use log::{mod_enabled, log_level, set_logging_spec, set_target_level};
+
+pub fn main() {
+    assert!(!mod_enabled(2, "other"));
+
+    set_logging_spec("warn,app::db=info");
+    assert_eq!(log_level(), 3);
+    assert!(mod_enabled(2, "other"));
+    assert!(!mod_enabled(3, "other"));
+    assert!(mod_enabled(3, "app::db::pool"));
+
+    set_target_level(Some("app::db"), 1);
+    assert!(!mod_enabled(2, "app::db::pool"));
+    assert_eq!(log_level(), 2);
+
+    set_target_level(Some("app::db::pool"), 4);
+    assert!(mod_enabled(4, "app::db::pool"));
+    assert!(!mod_enabled(2, "app::db"));
+    assert_eq!(log_level(), 4);
+
+    set_target_level(None, 3);
+    assert!(mod_enabled(3, "other"));
+
+    // Other tasks see the new levels too.
+    let (tx, rx) = channel();
+    spawn(proc() {
+        tx.send(mod_enabled(3, "other") && !mod_enabled(2, "app::db"));
+    });
+    assert!(rx.recv());
+}